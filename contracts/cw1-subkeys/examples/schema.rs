@@ -3,9 +3,11 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, export_schema_with_title, remove_schemas, schema_for};
 
-use cw1_subkeys::msg::{AllAllowancesResponse, ExecuteMsg, QueryMsg};
-use cw1_subkeys::state::Allowance;
-use cw1_whitelist::msg::{AdminListResponse, InstantiateMsg};
+use cw1_subkeys::msg::{
+    AllAllowancesResponse, AllowanceResponse, Cw20TokensResponse, ExecuteMsg, MigrateMsg,
+    PeriodicAllowanceResponse, QueryMsg, SpendHistoryResponse,
+};
+use cw1_whitelist::msg::{AdminListResponse, InstantiateMsg, PendingFreezeResponse};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -16,7 +18,12 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema_with_title(&schema_for!(ExecuteMsg), &out_dir, "ExecuteMsg");
     export_schema_with_title(&schema_for!(QueryMsg), &out_dir, "QueryMsg");
-    export_schema(&schema_for!(Allowance), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(AllowanceResponse), &out_dir);
     export_schema(&schema_for!(AdminListResponse), &out_dir);
+    export_schema(&schema_for!(PendingFreezeResponse), &out_dir);
     export_schema(&schema_for!(AllAllowancesResponse), &out_dir);
+    export_schema(&schema_for!(PeriodicAllowanceResponse), &out_dir);
+    export_schema(&schema_for!(Cw20TokensResponse), &out_dir);
+    export_schema(&schema_for!(SpendHistoryResponse), &out_dir);
 }
@@ -121,6 +121,10 @@ const useOptions = (options: Options): Network => {
 
 type Expiration = { at_height: { height: number } } | { at_time: { time: number } } | { never: {}}
 
+type Duration = { height: number } | { time: number }
+
+type QueryOrder = "ascending" | "descending"
+
 interface CanExecuteResponse {
   readonly canExecute: boolean;
 }
@@ -135,26 +139,81 @@ interface Permissions {
 interface PermissionsInfo {
   readonly spender: string;
   readonly permissions: Permissions;
+  readonly expires: Expiration;
 }
 
 interface AllPermissionsResponse {
   readonly permissions: readonly PermissionsInfo[];
 }
 
+interface DenomAllowanceInfo {
+  readonly denom: string,
+  readonly amount: string,
+  readonly expires: Expiration,
+}
+
+interface AllowanceResponse {
+  readonly balance: readonly Coin[],
+  readonly denoms: readonly DenomAllowanceInfo[],
+}
+
 interface AllowanceInfo {
+  readonly spender: string,
   readonly balance: readonly Coin[],
-  readonly expires: Expiration,
+  readonly denoms: readonly DenomAllowanceInfo[],
 }
 
 interface AllAllowancesResponse {
   readonly allowances: readonly AllowanceInfo[];
 }
 
+interface Cw20AllowanceResponse {
+  readonly allowance: string,
+  readonly expires: Expiration,
+}
+
+interface Cw20TokensResponse {
+  readonly tokens: readonly string[],
+}
+
+interface PeriodicAllowance {
+  readonly per_period: readonly Coin[],
+  readonly remaining: readonly Coin[],
+  readonly period: Duration,
+  readonly period_end: Expiration,
+}
+
+interface PeriodicAllowanceResponse {
+  readonly allowance?: PeriodicAllowance,
+  readonly spendable: readonly Coin[],
+}
+
+interface Cw20Coin {
+  readonly address: string,
+  readonly amount: string,
+}
+
+interface SpendRecord {
+  readonly height: number,
+  readonly time: string,
+  readonly native: readonly Coin[],
+  readonly cw20: readonly Cw20Coin[],
+}
+
+interface SpendHistoryResponse {
+  readonly retention: number,
+  readonly records: readonly SpendRecord[],
+}
+
 interface AdminListResponse {
   readonly admins: readonly string[],
   readonly mutable: boolean,
 }
 
+interface PendingFreezeResponse {
+  readonly freeze_at?: Expiration,
+}
+
 type CosmosMsg = SendMsg | DelegateMsg | UndelegateMsg | RedelegateMsg | WithdrawMsg
 
 interface SendMsg {
@@ -209,20 +268,33 @@ interface CW1Instance {
 
   // queries
   admins: () => Promise<AdminListResponse>
-  allowance: (address?: string) => Promise<AllowanceInfo>
-  allAllowances: (startAfter?: string, limit?: number) => Promise<AllAllowancesResponse>
+  pendingFreeze: () => Promise<PendingFreezeResponse>
+  allowance: (address?: string) => Promise<AllowanceResponse>
+  periodicAllowance: (address?: string) => Promise<PeriodicAllowanceResponse>
+  cw20Allowance: (token: string, address?: string) => Promise<Cw20AllowanceResponse>
+  cw20Tokens: () => Promise<Cw20TokensResponse>
+  allAllowances: (startAfter?: string, limit?: number, includeExpired?: boolean, order?: QueryOrder) => Promise<AllAllowancesResponse>
 
   permissions: (address?: string) => Promise<PermissionsInfo>
-  allPermissions: (startAfter?: string, limit?: number) => Promise<AllPermissionsResponse>
+  allPermissions: (startAfter?: string, limit?: number, includeExpired?: boolean, order?: QueryOrder) => Promise<AllPermissionsResponse>
   canExecute: (sender: string, msg: CosmosMsg) => Promise<CanExecuteResponse>
+  spendHistory: (address?: string) => Promise<SpendHistoryResponse>
 
   // actions
   execute: (msgs: readonly CosmosMsg[]) => Promise<string>
   freeze: () => Promise<string>
+  cancelFreeze: () => Promise<string>
+  unfreeze: () => Promise<string>
   updateAdmins: (admins: readonly string[]) => Promise<string>
+  addAdmins: (addrs: readonly string[]) => Promise<string>
+  removeAdmins: (addrs: readonly string[]) => Promise<string>
   increaseAllowance: (recipient: string, amount: Coin, expires?: Expiration) => Promise<string>
   decreaseAllowance: (recipient: string, amount: Coin, expires?: Expiration) => Promise<string>
-  setPermissions: (recipient: string, permissions: Permissions) => Promise<string>
+  setPeriodicAllowance: (recipient: string, balance: readonly Coin[], period: Duration) => Promise<string>
+  updateCw20Tokens: (add: readonly string[], remove: readonly string[]) => Promise<string>
+  setCw20Allowance: (recipient: string, token: string, amount: string, expires?: Expiration) => Promise<string>
+  setPermissions: (recipient: string, permissions: Permissions, expires?: Expiration) => Promise<string>
+  setSpendHistoryRetention: (retention: number) => Promise<string>
 }
 
 interface CW1Contract {
@@ -241,13 +313,27 @@ interface CW1Contract {
 
 const CW1 = (client: SigningCosmWasmClient): CW1Contract => {
   const use = (contractAddress: string): CW1Instance => {
-    const allowance = async (address?: string): Promise<AllowanceInfo> => {
+    const allowance = async (address?: string): Promise<AllowanceResponse> => {
       const spender = address || client.senderAddress;
       return await client.queryContractSmart(contractAddress, {allowance: {spender}});
     };
 
-    const allAllowances = async (startAfter?: string, limit?: number): Promise<AllAllowancesResponse> => {
-      return client.queryContractSmart(contractAddress, {all_allowances: { start_after: startAfter, limit: limit }});
+    const periodicAllowance = async (address?: string): Promise<PeriodicAllowanceResponse> => {
+      const spender = address || client.senderAddress;
+      return await client.queryContractSmart(contractAddress, {periodic_allowance: {spender}});
+    };
+
+    const cw20Allowance = async (token: string, address?: string): Promise<Cw20AllowanceResponse> => {
+      const spender = address || client.senderAddress;
+      return await client.queryContractSmart(contractAddress, {cw20_allowance: {spender, token}});
+    };
+
+    const cw20Tokens = async (): Promise<Cw20TokensResponse> => {
+      return client.queryContractSmart(contractAddress, {cw20_tokens: {}});
+    };
+
+    const allAllowances = async (startAfter?: string, limit?: number, includeExpired?: boolean, order?: QueryOrder): Promise<AllAllowancesResponse> => {
+      return client.queryContractSmart(contractAddress, {all_allowances: { start_after: startAfter, limit: limit, include_expired: includeExpired, order: order }});
     };
 
     const permissions = async (address?: string): Promise<PermissionsInfo> => {
@@ -255,30 +341,61 @@ const CW1 = (client: SigningCosmWasmClient): CW1Contract => {
       return await client.queryContractSmart(contractAddress, {permissions: {spender}});
     };
 
-    const allPermissions = async (startAfter?: string, limit?: number): Promise<AllPermissionsResponse> => {
-      return client.queryContractSmart(contractAddress, {all_permissions: { start_after: startAfter, limit: limit }});
+    const allPermissions = async (startAfter?: string, limit?: number, includeExpired?: boolean, order?: QueryOrder): Promise<AllPermissionsResponse> => {
+      return client.queryContractSmart(contractAddress, {all_permissions: { start_after: startAfter, limit: limit, include_expired: includeExpired, order: order }});
     };
 
     const canExecute = async (sender: string, msg: CosmosMsg): Promise<CanExecuteResponse> => {
       return client.queryContractSmart(contractAddress, {can_execute: { sender: sender, msg: msg }});
     };
 
+    const spendHistory = async (address?: string): Promise<SpendHistoryResponse> => {
+      const spender = address || client.senderAddress;
+      return client.queryContractSmart(contractAddress, {spend_history: {spender}});
+    };
+
     const admins = async (): Promise<AdminListResponse> => {
       return client.queryContractSmart(contractAddress, {admin_list: { }});
     };
 
+    const pendingFreeze = async (): Promise<PendingFreezeResponse> => {
+      return client.queryContractSmart(contractAddress, {pending_freeze: { }});
+    };
+
     // called by an admin to make admin set immutable
     const freeze = async (): Promise<string> => {
       const result = await client.execute(contractAddress, {freeze: {}});
       return result.transactionHash;
     }
 
+    // called by an admin to cancel a scheduled freeze
+    const cancelFreeze = async (): Promise<string> => {
+      const result = await client.execute(contractAddress, {cancel_freeze: {}});
+      return result.transactionHash;
+    }
+
+    // called by an admin to make a frozen (unfreezable) contract mutable again
+    const unfreeze = async (): Promise<string> => {
+      const result = await client.execute(contractAddress, {unfreeze: {}});
+      return result.transactionHash;
+    }
+
     // burns tokens, returns transactionHash
     const updateAdmins = async (admins: readonly string[]): Promise<string> => {
       const result = await client.execute(contractAddress, {update_admins: {admins}});
       return result.transactionHash;
     }
 
+    const addAdmins = async (addrs: readonly string[]): Promise<string> => {
+      const result = await client.execute(contractAddress, {add_admins: {addrs}});
+      return result.transactionHash;
+    }
+
+    const removeAdmins = async (addrs: readonly string[]): Promise<string> => {
+      const result = await client.execute(contractAddress, {remove_admins: {addrs}});
+      return result.transactionHash;
+    }
+
     // transfers tokens, returns transactionHash
     const execute = async (msgs: readonly CosmosMsg[]): Promise<string> => {
       const result = await client.execute(contractAddress, {execute: {msgs}});
@@ -295,25 +412,58 @@ const CW1 = (client: SigningCosmWasmClient): CW1Contract => {
       return result.transactionHash;
     }
 
-    const setPermissions = async (spender: string, permissions: Permissions): Promise<string> => {
-      const result = await client.execute(contractAddress, {set_permissions: {spender, permissions}});
+    const setPeriodicAllowance = async (spender: string, balance: readonly Coin[], period: Duration): Promise<string> => {
+      const result = await client.execute(contractAddress, {set_periodic_allowance: {spender, balance, period}});
+      return result.transactionHash;
+    }
+
+    const updateCw20Tokens = async (add: readonly string[], remove: readonly string[]): Promise<string> => {
+      const result = await client.execute(contractAddress, {update_cw20_tokens: {add, remove}});
+      return result.transactionHash;
+    }
+
+    const setCw20Allowance = async (spender: string, token: string, amount: string, expires?: Expiration): Promise<string> => {
+      const result = await client.execute(contractAddress, {set_cw20_allowance: {spender, token, amount, expires}});
+      return result.transactionHash;
+    }
+
+    const setPermissions = async (spender: string, permissions: Permissions, expires?: Expiration): Promise<string> => {
+      const result = await client.execute(contractAddress, {set_permissions: {spender, permissions, expires}});
+      return result.transactionHash;
+    }
+
+    const setSpendHistoryRetention = async (retention: number): Promise<string> => {
+      const result = await client.execute(contractAddress, {set_spend_history_retention: {retention}});
       return result.transactionHash;
     }
 
     return {
       contractAddress,
       admins,
+      pendingFreeze,
       allowance,
+      periodicAllowance,
+      cw20Allowance,
+      cw20Tokens,
       allAllowances,
       permissions,
       allPermissions,
       canExecute,
+      spendHistory,
       execute,
       freeze,
+      cancelFreeze,
+      unfreeze,
       updateAdmins,
+      addAdmins,
+      removeAdmins,
       increaseAllowance,
       decreaseAllowance,
-      setPermissions
+      setPeriodicAllowance,
+      updateCw20Tokens,
+      setCw20Allowance,
+      setPermissions,
+      setSpendHistoryRetention
     };
   }
 
@@ -351,7 +501,7 @@ const CW1 = (client: SigningCosmWasmClient): CW1Contract => {
 //
 // const codeId = await factory.upload();
 // codeId -> 12
-// const contract = await factory.instantiate(12, { admins: [address], mutable: true}, "My Proxy")
+// const contract = await factory.instantiate(12, { admins: [address], mutable: true, freeze_delay: { height: 100 }}, "My Proxy")
 // contract.contractAddress -> 'coral1267wq2zk22kt5juypdczw3k4wxhc4z47mug9fd'
 // 
 // OR
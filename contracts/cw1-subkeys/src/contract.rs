@@ -1,32 +1,41 @@
 use schemars::JsonSchema;
 use std::fmt;
-use std::ops::{AddAssign, Sub};
+use std::ops::Sub;
 
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, DistributionMsg, Empty, Env,
-    MessageInfo, Order, Response, StakingMsg, StdError, StdResult, SubMsg,
+    attr, from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, CosmosMsg, Deps, DepsMut,
+    DistributionMsg, Empty, Env, MessageInfo, Order, Response, StakingMsg, StdError, StdResult,
+    Storage, SubMsg, Uint128, WasmMsg,
 };
-use cw0::Expiration;
+use cw0::{paginate_filtered, Duration, Expiration, NativeBalance};
 use cw1::CanExecuteResponse;
 use cw1_whitelist::{
     contract::{
-        execute_freeze, execute_update_admins, instantiate as whitelist_instantiate,
-        query_admin_list,
+        execute_add_admins, execute_cancel_freeze, execute_freeze, execute_remove_admins,
+        execute_unfreeze, execute_update_admins, instantiate as whitelist_instantiate,
+        query_admin_list, query_pending_freeze,
     },
     msg::InstantiateMsg,
     state::ADMIN_LIST,
 };
-use cw2::set_contract_version;
-use cw_storage_plus::Bound;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{AllowanceResponse as Cw20AllowanceResponse, Cw20ExecuteMsg};
+use cw_storage_plus::{Bound, PrimaryKey};
 
 use crate::error::ContractError;
 use crate::msg::{
-    AllAllowancesResponse, AllPermissionsResponse, AllowanceInfo, ExecuteMsg, PermissionsInfo,
-    QueryMsg,
+    AllAllowancesResponse, AllPermissionsResponse, AllowanceInfo, AllowanceResponse,
+    Cw20TokensResponse, DenomAllowanceInfo, ExecuteMsg, MigrateMsg, PeriodicAllowanceResponse,
+    PermissionsInfo, QueryMsg, SpendHistoryResponse,
+};
+use crate::state::{
+    load_spend_history_retention, push_spend_record, DenomAllowance, PeriodicAllowance,
+    Permissions, PermissionsGrant, SpendRecord, ALLOWANCES, CW20_ALLOWANCES, CW20_TOKENS,
+    LEGACY_ALLOWANCES, LEGACY_PERMISSIONS, MAX_SPEND_HISTORY_RETENTION, PERIODIC_ALLOWANCES,
+    PERMISSIONS, SPEND_HISTORY_RETENTION, SPEND_RECORDS,
 };
-use crate::state::{Allowance, Permissions, ALLOWANCES, PERMISSIONS};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw1-subkeys";
@@ -56,7 +65,11 @@ pub fn execute(
     match msg {
         ExecuteMsg::Execute { msgs } => execute_execute(deps, env, info, msgs),
         ExecuteMsg::Freeze {} => Ok(execute_freeze(deps, env, info)?),
+        ExecuteMsg::CancelFreeze {} => Ok(execute_cancel_freeze(deps, env, info)?),
+        ExecuteMsg::Unfreeze {} => Ok(execute_unfreeze(deps, env, info)?),
         ExecuteMsg::UpdateAdmins { admins } => Ok(execute_update_admins(deps, env, info, admins)?),
+        ExecuteMsg::AddAdmins { addrs } => Ok(execute_add_admins(deps, env, info, addrs)?),
+        ExecuteMsg::RemoveAdmins { addrs } => Ok(execute_remove_admins(deps, env, info, addrs)?),
         ExecuteMsg::IncreaseAllowance {
             spender,
             amount,
@@ -67,16 +80,34 @@ pub fn execute(
             amount,
             expires,
         } => execute_decrease_allowance(deps, env, info, spender, amount, expires),
+        ExecuteMsg::SetPeriodicAllowance {
+            spender,
+            balance,
+            period,
+        } => execute_set_periodic_allowance(deps, env, info, spender, balance, period),
+        ExecuteMsg::UpdateCw20Tokens { add, remove } => {
+            execute_update_cw20_tokens(deps, env, info, add, remove)
+        }
+        ExecuteMsg::SetCw20Allowance {
+            spender,
+            token,
+            amount,
+            expires,
+        } => execute_set_cw20_allowance(deps, env, info, spender, token, amount, expires),
         ExecuteMsg::SetPermissions {
             spender,
             permissions,
-        } => execute_set_permissions(deps, env, info, spender, permissions),
+            expires,
+        } => execute_set_permissions(deps, env, info, spender, permissions, expires),
+        ExecuteMsg::SetSpendHistoryRetention { retention } => {
+            execute_set_spend_history_retention(deps, env, info, retention)
+        }
     }
 }
 
 pub fn execute_execute<T>(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msgs: Vec<CosmosMsg<T>>,
 ) -> Result<Response<T>, ContractError>
@@ -95,44 +126,218 @@ where
         };
         Ok(res)
     } else {
+        // every message is checked against the allowances left by the ones before it
+        let mut record = SpendRecord::new(&env.block);
         for msg in &msgs {
-            match &msg.msg {
-                CosmosMsg::Staking(staking_msg) => {
-                    let perm = PERMISSIONS.may_load(deps.storage, &info.sender)?;
-                    let perm = perm.ok_or(ContractError::NotAllowed {})?;
-                    check_staking_permissions(staking_msg, perm)?;
-                }
-                CosmosMsg::Distribution(distribution_msg) => {
-                    let perm = PERMISSIONS.may_load(deps.storage, &info.sender)?;
-                    let perm = perm.ok_or(ContractError::NotAllowed {})?;
-                    check_distribution_permissions(distribution_msg, perm)?;
-                }
-                CosmosMsg::Bank(BankMsg::Send {
-                    to_address: _,
-                    amount,
-                }) => {
-                    ALLOWANCES.update::<_, ContractError>(deps.storage, &info.sender, |allow| {
-                        let mut allowance = allow.ok_or(ContractError::NoAllowance {})?;
-                        // Decrease allowance
-                        allowance.balance = allowance.balance.sub(amount.clone())?;
-                        Ok(allowance)
-                    })?;
-                }
-                _ => {
-                    return Err(ContractError::MessageTypeRejected {});
-                }
+            let spend = check_spend(deps.as_ref(), &env.block, &info.sender, &msg.msg)?;
+            spend.add_to(&mut record);
+            spend.save(deps.storage, &info.sender)?;
+        }
+
+        let mut attributes = vec![attr("action", "execute"), attr("owner", &info.sender)];
+        if !record.is_empty() {
+            let retention = load_spend_history_retention(deps.storage)?;
+            push_spend_record(deps.storage, &info.sender, &record, retention)?;
+            // one attribute per denom or token, with the total deducted by all messages
+            for coin in record.native.0 {
+                attributes.push(attr("spent", coin));
+            }
+            for coin in record.cw20 {
+                attributes.push(attr(
+                    "spent_cw20",
+                    format!("{}{}", coin.amount, coin.address),
+                ));
             }
         }
         // Relay messages
         let res = Response {
             messages: msgs,
-            attributes: vec![attr("action", "execute"), attr("owner", info.sender)],
+            attributes,
             ..Response::default()
         };
         Ok(res)
     }
 }
 
+/// Spend is how executing one message changes the allowances of a (non-admin) sender.
+/// It is computed by check_spend without writing anything, so CanExecute can use the
+/// very same checks as Execute.
+enum Spend {
+    /// nothing is spent, eg. for messages covered by permissions
+    Nothing,
+    /// the updated allowance, and the amount spent from it
+    Periodic(PeriodicAllowance, Vec<Coin>),
+    Allowances(Vec<(String, DenomAllowance)>, Vec<Coin>),
+    Cw20(Addr, DenomAllowance, Uint128),
+}
+
+impl Spend {
+    fn add_to(&self, record: &mut SpendRecord) {
+        match self {
+            Spend::Nothing => {}
+            Spend::Periodic(_, amount) | Spend::Allowances(_, amount) => {
+                record.native += NativeBalance(amount.clone())
+            }
+            Spend::Cw20(token, _, amount) => record.add_cw20(token, *amount),
+        }
+    }
+
+    fn save(self, storage: &mut dyn Storage, spender: &Addr) -> StdResult<()> {
+        match self {
+            Spend::Nothing => Ok(()),
+            Spend::Periodic(allowance, _) => PERIODIC_ALLOWANCES.save(storage, spender, &allowance),
+            Spend::Allowances(allowances, _) => {
+                for (denom, allow) in allowances {
+                    ALLOWANCES.save(storage, (spender, &denom), &allow)?;
+                }
+                Ok(())
+            }
+            Spend::Cw20(token, allow, _) => {
+                CW20_ALLOWANCES.save(storage, (spender, &token), &allow)
+            }
+        }
+    }
+}
+
+// checks whether a non-admin sender may execute msg, and returns what it spends
+fn check_spend<T>(
+    deps: Deps,
+    block: &BlockInfo,
+    sender: &Addr,
+    msg: &CosmosMsg<T>,
+) -> Result<Spend, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    match msg {
+        CosmosMsg::Staking(staking_msg) => {
+            let perm = load_permissions(deps.storage, sender, block)?;
+            let perm = perm.ok_or(ContractError::NotAllowed {})?;
+            check_staking_permissions(staking_msg, perm)?;
+            Ok(Spend::Nothing)
+        }
+        CosmosMsg::Distribution(distribution_msg) => {
+            let perm = load_permissions(deps.storage, sender, block)?;
+            let perm = perm.ok_or(ContractError::NotAllowed {})?;
+            check_distribution_permissions(distribution_msg, perm)?;
+            Ok(Spend::Nothing)
+        }
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: _,
+            amount,
+        }) => match periodic_spend(deps.storage, sender, block, amount)? {
+            Some(allowance) => Ok(Spend::Periodic(allowance, amount.clone())),
+            None => Ok(Spend::Allowances(
+                allowance_spend(deps.storage, sender, block, amount)?,
+                amount.clone(),
+            )),
+        },
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds,
+        }) => {
+            let (token, amount) = parse_cw20_spend(deps, contract_addr, msg, funds)?;
+            let mut allow = CW20_ALLOWANCES
+                .may_load(deps.storage, (sender, &token))?
+                .ok_or(ContractError::NoAllowance {})?;
+            if allow.expires.is_expired(block) {
+                return Err(ContractError::Expired {});
+            }
+            allow.amount = allow.amount.checked_sub(amount).map_err(StdError::from)?;
+            Ok(Spend::Cw20(token, allow, amount))
+        }
+        _ => Err(ContractError::MessageTypeRejected {}),
+    }
+}
+
+// returns the allowances of the denoms of amount after spending it. They must all be there,
+// not expired, and cover their part
+fn allowance_spend(
+    storage: &dyn Storage,
+    spender: &Addr,
+    block: &BlockInfo,
+    amount: &[Coin],
+) -> Result<Vec<(String, DenomAllowance)>, ContractError> {
+    let mut amount = NativeBalance(amount.to_vec());
+    amount.normalize();
+    let mut updated = vec![];
+    for coin in amount.into_vec() {
+        let mut allow = ALLOWANCES
+            .may_load(storage, (spender, &coin.denom))?
+            .ok_or(ContractError::NoAllowance {})?;
+        if allow.expires.is_expired(block) {
+            return Err(ContractError::Expired {});
+        }
+        allow.amount = allow
+            .amount
+            .checked_sub(coin.amount)
+            .map_err(StdError::from)?;
+        updated.push((coin.denom, allow));
+    }
+    Ok(updated)
+}
+
+// returns the token contract and amount of a cw20 Transfer or Send to an allowed token.
+// Native funds cannot be sent along, and any other message is rejected
+fn parse_cw20_spend(
+    deps: Deps,
+    contract_addr: &str,
+    msg: &Binary,
+    funds: &[Coin],
+) -> Result<(Addr, Uint128), ContractError> {
+    if !funds.is_empty() {
+        return Err(ContractError::MessageTypeRejected {});
+    }
+    let amount = match from_binary(msg) {
+        Ok(Cw20ExecuteMsg::Transfer { amount, .. }) | Ok(Cw20ExecuteMsg::Send { amount, .. }) => {
+            amount
+        }
+        _ => return Err(ContractError::MessageTypeRejected {}),
+    };
+    let token = deps.api.addr_validate(contract_addr)?;
+    if !CW20_TOKENS.has(deps.storage, &token) {
+        return Err(ContractError::Cw20NotAllowed {
+            token: token.into(),
+        });
+    }
+    Ok((token, amount))
+}
+
+// returns the periodic allowance after spending amount from it, or None
+// if there is none or it does not cover the whole amount
+fn periodic_spend(
+    storage: &dyn Storage,
+    spender: &Addr,
+    block: &BlockInfo,
+    amount: &[Coin],
+) -> StdResult<Option<PeriodicAllowance>> {
+    let mut allowance = match PERIODIC_ALLOWANCES.may_load(storage, spender)? {
+        Some(allowance) => allowance,
+        None => return Ok(None),
+    };
+    allowance.refill(block)?;
+    match allowance.remaining.clone().sub(amount.to_vec()) {
+        Ok(remaining) => {
+            allowance.remaining = remaining;
+            Ok(Some(allowance))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+// expired permissions are treated as if they were never set
+fn load_permissions(
+    storage: &dyn Storage,
+    spender: &Addr,
+    block: &BlockInfo,
+) -> StdResult<Option<Permissions>> {
+    let grant = PERMISSIONS.may_load(storage, spender)?;
+    Ok(grant
+        .filter(|grant| !grant.expires.is_expired(block))
+        .map(|grant| grant.permissions))
+}
+
 pub fn check_staking_permissions(
     staking_msg: &StakingMsg,
     permissions: Permissions,
@@ -153,7 +358,7 @@ pub fn check_staking_permissions(
                 return Err(ContractError::ReDelegatePerm {});
             }
         }
-        s => panic!("Unsupported staking message: {:?}", s),
+        _ => return Err(ContractError::MessageTypeRejected {}),
     }
     Ok(true)
 }
@@ -173,7 +378,7 @@ pub fn check_distribution_permissions(
                 return Err(ContractError::WithdrawPerm {});
             }
         }
-        s => panic!("Unsupported distribution message: {:?}", s),
+        _ => return Err(ContractError::MessageTypeRejected {}),
     }
     Ok(true)
 }
@@ -199,12 +404,13 @@ where
         return Err(ContractError::CannotSetOwnAccount {});
     }
 
-    ALLOWANCES.update::<_, StdError>(deps.storage, &spender_addr, |allow| {
+    let key = (&spender_addr, amount.denom.as_str());
+    let allowance = ALLOWANCES.update::<_, StdError>(deps.storage, key, |allow| {
         let mut allowance = allow.unwrap_or_default();
         if let Some(exp) = expires {
             allowance.expires = exp;
         }
-        allowance.balance.add_assign(amount.clone());
+        allowance.amount = allowance.amount.checked_add(amount.amount)?;
         Ok(allowance)
     })?;
 
@@ -215,6 +421,8 @@ where
             attr("spender", spender),
             attr("denomination", amount.denom),
             attr("amount", amount.amount),
+            attr("new_total", allowance.amount),
+            attr("expires", allowance.expires),
         ],
         ..Response::default()
     };
@@ -242,18 +450,19 @@ where
         return Err(ContractError::CannotSetOwnAccount {});
     }
 
-    let allowance =
-        ALLOWANCES.update::<_, ContractError>(deps.storage, &spender_addr, |allow| {
-            // Fail fast
-            let mut allowance = allow.ok_or(ContractError::NoAllowance {})?;
-            if let Some(exp) = expires {
-                allowance.expires = exp;
-            }
-            allowance.balance = allowance.balance.sub_saturating(amount.clone())?; // Tolerates underflows (amount bigger than balance), but fails if there are no tokens at all for the denom (report potential errors)
-            Ok(allowance)
-        })?;
-    if allowance.balance.is_empty() {
-        ALLOWANCES.remove(deps.storage, &spender_addr);
+    let key = (&spender_addr, amount.denom.as_str());
+    let allowance = ALLOWANCES.update::<_, ContractError>(deps.storage, key, |allow| {
+        // Fail fast, if there is no allowance for the denom at all (report potential errors)
+        let mut allowance = allow.ok_or(ContractError::NoAllowance {})?;
+        if let Some(exp) = expires {
+            allowance.expires = exp;
+        }
+        // Tolerates underflows (amount bigger than allowance)
+        allowance.amount = allowance.amount.saturating_sub(amount.amount);
+        Ok(allowance)
+    })?;
+    if allowance.amount.is_zero() {
+        ALLOWANCES.remove(deps.storage, key);
     }
 
     let res = Response {
@@ -263,6 +472,144 @@ where
             attr("spender", spender),
             attr("denomination", amount.denom),
             attr("amount", amount.amount),
+            attr("new_total", allowance.amount),
+            attr("expires", allowance.expires),
+        ],
+        ..Response::default()
+    };
+    Ok(res)
+}
+
+pub fn execute_set_periodic_allowance<T>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    mut balance: NativeBalance,
+    period: Duration,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    if !cfg.is_admin(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if info.sender == spender_addr {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    if period == Duration::Height(0) || period == Duration::Time(0) {
+        return Err(ContractError::ZeroPeriod {});
+    }
+
+    balance.normalize();
+    if balance.is_empty() {
+        PERIODIC_ALLOWANCES.remove(deps.storage, &spender_addr);
+    } else {
+        let allowance = PeriodicAllowance {
+            per_period: balance.clone(),
+            remaining: balance,
+            period,
+            period_end: period.after(&env.block),
+        };
+        PERIODIC_ALLOWANCES.save(deps.storage, &spender_addr, &allowance)?;
+    }
+
+    let res = Response {
+        attributes: vec![
+            attr("action", "set_periodic_allowance"),
+            attr("owner", info.sender),
+            attr("spender", spender),
+            attr("period", period),
+        ],
+        ..Response::default()
+    };
+    Ok(res)
+}
+
+pub fn execute_update_cw20_tokens<T>(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    if !cfg.is_admin(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for token in add.iter() {
+        let token = deps.api.addr_validate(token)?;
+        CW20_TOKENS.save(deps.storage, &token, &Empty {})?;
+    }
+    for token in remove.iter() {
+        let token = deps.api.addr_validate(token)?;
+        CW20_TOKENS.remove(deps.storage, &token);
+    }
+
+    let res = Response {
+        attributes: vec![
+            attr("action", "update_cw20_tokens"),
+            attr("owner", info.sender),
+            attr("added", add.len().to_string()),
+            attr("removed", remove.len().to_string()),
+        ],
+        ..Response::default()
+    };
+    Ok(res)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_set_cw20_allowance<T>(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    spender: String,
+    token: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    if !cfg.is_admin(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if info.sender == spender_addr {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    let token_addr = deps.api.addr_validate(&token)?;
+    if !CW20_TOKENS.has(deps.storage, &token_addr) {
+        return Err(ContractError::Cw20NotAllowed { token });
+    }
+
+    let key = (&spender_addr, &token_addr);
+    if amount.is_zero() {
+        CW20_ALLOWANCES.remove(deps.storage, key);
+    } else {
+        let allowance = DenomAllowance {
+            amount,
+            expires: expires.unwrap_or_default(),
+        };
+        CW20_ALLOWANCES.save(deps.storage, key, &allowance)?;
+    }
+
+    let res = Response {
+        attributes: vec![
+            attr("action", "set_cw20_allowance"),
+            attr("owner", info.sender),
+            attr("spender", spender),
+            attr("token", token),
+            attr("amount", amount),
         ],
         ..Response::default()
     };
@@ -275,6 +622,7 @@ pub fn execute_set_permissions<T>(
     info: MessageInfo,
     spender: String,
     perm: Permissions,
+    expires: Option<Expiration>,
 ) -> Result<Response<T>, ContractError>
 where
     T: Clone + fmt::Debug + PartialEq + JsonSchema,
@@ -288,7 +636,12 @@ where
     if info.sender == spender_addr {
         return Err(ContractError::CannotSetOwnAccount {});
     }
-    PERMISSIONS.save(deps.storage, &spender_addr, &perm)?;
+    let expires = expires.unwrap_or_default();
+    let grant = PermissionsGrant {
+        permissions: perm,
+        expires,
+    };
+    PERMISSIONS.save(deps.storage, &spender_addr, &grant)?;
 
     let res = Response {
         attributes: vec![
@@ -296,6 +649,93 @@ where
             attr("owner", info.sender),
             attr("spender", spender),
             attr("permissions", perm),
+            attr("expires", expires),
+        ],
+        ..Response::default()
+    };
+    Ok(res)
+}
+
+pub fn execute_set_spend_history_retention<T>(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    retention: u32,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    if !cfg.is_admin(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if retention > MAX_SPEND_HISTORY_RETENTION {
+        return Err(ContractError::RetentionTooLarge {
+            max: MAX_SPEND_HISTORY_RETENTION,
+        });
+    }
+    SPEND_HISTORY_RETENTION.save(deps.storage, &retention)?;
+
+    let res = Response {
+        attributes: vec![
+            attr("action", "set_spend_history_retention"),
+            attr("owner", info.sender),
+            attr("retention", retention.to_string()),
+        ],
+        ..Response::default()
+    };
+    Ok(res)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let version = get_contract_version(deps.storage)?;
+    if version.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: version.contract,
+        });
+    }
+
+    // allowances used to share one expiration for all denoms, now every denom gets a copy of it
+    let legacy = LEGACY_ALLOWANCES
+        .range_de(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (spender, allowance) in legacy.iter() {
+        for coin in allowance.balance.0.iter().filter(|c| !c.amount.is_zero()) {
+            let denom_allowance = DenomAllowance {
+                amount: coin.amount,
+                expires: allowance.expires,
+            };
+            ALLOWANCES.save(deps.storage, (spender, &coin.denom), &denom_allowance)?;
+        }
+        LEGACY_ALLOWANCES.remove(deps.storage, spender);
+    }
+
+    // permissions used to be stored without an expiration, they never expire.
+    // Both formats share a namespace, entries that are already grants do not parse as legacy
+    let spenders = PERMISSIONS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| String::from_utf8(key).map(Addr::unchecked))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(StdError::from)?;
+    let mut migrated_permissions = 0;
+    for spender in spenders.iter() {
+        if let Ok(permissions) = LEGACY_PERMISSIONS.load(deps.storage, spender) {
+            let grant = PermissionsGrant {
+                permissions,
+                expires: Expiration::Never {},
+            };
+            PERMISSIONS.save(deps.storage, spender, &grant)?;
+            migrated_permissions += 1;
+        }
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let res = Response {
+        attributes: vec![
+            attr("action", "migrate"),
+            attr("migrated_allowances", legacy.len().to_string()),
+            attr("migrated_permissions", migrated_permissions.to_string()),
         ],
         ..Response::default()
     };
@@ -303,78 +743,178 @@ where
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::AdminList {} => to_binary(&query_admin_list(deps)?),
-        QueryMsg::Allowance { spender } => to_binary(&query_allowance(deps, spender)?),
-        QueryMsg::Permissions { spender } => to_binary(&query_permissions(deps, spender)?),
-        QueryMsg::CanExecute { sender, msg } => to_binary(&query_can_execute(deps, sender, msg)?),
-        QueryMsg::AllAllowances { start_after, limit } => {
-            to_binary(&query_all_allowances(deps, start_after, limit)?)
+        QueryMsg::AdminList {} => to_binary(&query_admin_list(deps, env)?),
+        QueryMsg::PendingFreeze {} => to_binary(&query_pending_freeze(deps, env)?),
+        QueryMsg::Allowance { spender } => to_binary(&query_allowance(deps, env, spender)?),
+        QueryMsg::Cw20Allowance { spender, token } => {
+            to_binary(&query_cw20_allowance(deps, spender, token)?)
+        }
+        QueryMsg::Cw20Tokens {} => to_binary(&query_cw20_tokens(deps)?),
+        QueryMsg::PeriodicAllowance { spender } => {
+            to_binary(&query_periodic_allowance(deps, env, spender)?)
         }
-        QueryMsg::AllPermissions { start_after, limit } => {
-            to_binary(&query_all_permissions(deps, start_after, limit)?)
+        QueryMsg::Permissions { spender } => to_binary(&query_permissions(deps, env, spender)?),
+        QueryMsg::CanExecute { sender, msg } => {
+            to_binary(&query_can_execute(deps, env, sender, msg)?)
         }
+        QueryMsg::AllAllowances {
+            start_after,
+            limit,
+            include_expired,
+            order,
+        } => to_binary(&query_all_allowances(
+            deps,
+            env,
+            start_after,
+            limit,
+            include_expired.unwrap_or(true),
+            order.unwrap_or_default().into(),
+        )?),
+        QueryMsg::AllPermissions {
+            start_after,
+            limit,
+            include_expired,
+            order,
+        } => to_binary(&query_all_permissions(
+            deps,
+            env,
+            start_after,
+            limit,
+            include_expired.unwrap_or(false),
+            order.unwrap_or_default().into(),
+        )?),
+        QueryMsg::SpendHistory { spender } => to_binary(&query_spend_history(deps, spender)?),
     }
 }
 
 // if the subkey has no allowance, return an empty struct (not an error)
-pub fn query_allowance(deps: Deps, spender: String) -> StdResult<Allowance> {
-    // we can use unchecked here as it is a query - bad value means a miss, we never write it
+pub fn query_allowance(deps: Deps, env: Env, spender: String) -> StdResult<AllowanceResponse> {
     let spender = deps.api.addr_validate(&spender)?;
-    let allow = ALLOWANCES
-        .may_load(deps.storage, &spender)?
-        .unwrap_or_default();
-    Ok(allow)
+    let denoms = ALLOWANCES
+        .prefix(&spender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.and_then(|(denom, allow)| denom_allowance_info(denom, allow)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AllowanceResponse {
+        balance: spendable_balance(&denoms, &env.block),
+        denoms,
+    })
+}
+
+fn denom_allowance_info(denom: Vec<u8>, allow: DenomAllowance) -> StdResult<DenomAllowanceInfo> {
+    Ok(DenomAllowanceInfo {
+        denom: String::from_utf8(denom)?,
+        amount: allow.amount,
+        expires: allow.expires,
+    })
+}
+
+// sums up the allowances that did not expire yet
+fn spendable_balance(denoms: &[DenomAllowanceInfo], block: &BlockInfo) -> NativeBalance {
+    let coins = denoms
+        .iter()
+        .filter(|allow| !allow.expires.is_expired(block))
+        .map(|allow| Coin {
+            denom: allow.denom.clone(),
+            amount: allow.amount,
+        })
+        .collect();
+    let mut balance = NativeBalance(coins);
+    balance.normalize();
+    balance
 }
 
-// if the subkey has no permissions, return an empty struct (not an error)
-pub fn query_permissions(deps: Deps, spender: String) -> StdResult<Permissions> {
+// if the subkey has no allowance for the token, return a zero allowance (not an error)
+pub fn query_cw20_allowance(
+    deps: Deps,
+    spender: String,
+    token: String,
+) -> StdResult<Cw20AllowanceResponse> {
     let spender = deps.api.addr_validate(&spender)?;
-    let permissions = PERMISSIONS
-        .may_load(deps.storage, &spender)?
+    let token = deps.api.addr_validate(&token)?;
+    let allow = CW20_ALLOWANCES
+        .may_load(deps.storage, (&spender, &token))?
         .unwrap_or_default();
+    Ok(Cw20AllowanceResponse {
+        allowance: allow.amount,
+        expires: allow.expires,
+    })
+}
+
+pub fn query_cw20_tokens(deps: Deps) -> StdResult<Cw20TokensResponse> {
+    let tokens = CW20_TOKENS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(String::from_utf8)
+        .collect::<Result<_, _>>()?;
+    Ok(Cw20TokensResponse { tokens })
+}
+
+pub fn query_periodic_allowance(
+    deps: Deps,
+    env: Env,
+    spender: String,
+) -> StdResult<PeriodicAllowanceResponse> {
+    let spender = deps.api.addr_validate(&spender)?;
+    let allowance = PERIODIC_ALLOWANCES.may_load(deps.storage, &spender)?;
+    let spendable = match &allowance {
+        Some(allow) => allow.spendable(&env.block)?,
+        None => NativeBalance::default(),
+    };
+    Ok(PeriodicAllowanceResponse {
+        allowance,
+        spendable,
+    })
+}
+
+// if the subkey has no (or only expired) permissions, return an empty struct (not an error)
+pub fn query_permissions(deps: Deps, env: Env, spender: String) -> StdResult<Permissions> {
+    let spender = deps.api.addr_validate(&spender)?;
+    let permissions = load_permissions(deps.storage, &spender, &env.block)?.unwrap_or_default();
     Ok(permissions)
 }
 
-fn query_can_execute(deps: Deps, sender: String, msg: CosmosMsg) -> StdResult<CanExecuteResponse> {
+fn query_can_execute(
+    deps: Deps,
+    env: Env,
+    sender: String,
+    msg: CosmosMsg,
+) -> StdResult<CanExecuteResponse> {
     Ok(CanExecuteResponse {
-        can_execute: can_execute(deps, sender, msg)?,
+        can_execute: can_execute(deps, env, sender, msg)?,
     })
 }
 
 // this can just return booleans and the query_can_execute wrapper creates the struct once, not on every path
-fn can_execute(deps: Deps, sender: String, msg: CosmosMsg) -> StdResult<bool> {
+fn can_execute(deps: Deps, env: Env, sender: String, msg: CosmosMsg) -> StdResult<bool> {
     let cfg = ADMIN_LIST.load(deps.storage)?;
     if cfg.is_admin(&sender) {
         return Ok(true);
     }
 
+    // the same checks as execute_execute, without saving what it would spend
     let sender = deps.api.addr_validate(&sender)?;
-    match msg {
-        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => {
-            // now we check if there is enough allowance for this message
-            let allowance = ALLOWANCES.may_load(deps.storage, &sender)?;
-            match allowance {
-                // if there is an allowance, we subtract the requested amount to ensure it is covered (error on underflow)
-                Some(allow) => Ok(allow.balance.sub(amount).is_ok()),
-                None => Ok(false),
-            }
-        }
-        CosmosMsg::Staking(staking_msg) => {
-            let perm_opt = PERMISSIONS.may_load(deps.storage, &sender)?;
-            match perm_opt {
-                Some(permission) => Ok(check_staking_permissions(&staking_msg, permission).is_ok()),
-                None => Ok(false),
-            }
-        }
-        _ => Ok(false),
-    }
+    Ok(check_spend(deps, &env.block, &sender, &msg).is_ok())
 }
 
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
 
+// records beyond the retention are only dropped on the next spend, so they are skipped here
+pub fn query_spend_history(deps: Deps, spender: String) -> StdResult<SpendHistoryResponse> {
+    let spender = deps.api.addr_validate(&spender)?;
+    let retention = load_spend_history_retention(deps.storage)?;
+    let mut records = SPEND_RECORDS
+        .prefix(&spender)
+        .range(deps.storage, None, None, Order::Descending)
+        .take(retention as usize)
+        .map(|item| item.map(|(_, record)| record))
+        .collect::<StdResult<Vec<_>>>()?;
+    records.reverse();
+    Ok(SpendHistoryResponse { retention, records })
+}
+
 fn calc_limit(request: Option<u32>) -> usize {
     request.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
 }
@@ -382,66 +922,127 @@ fn calc_limit(request: Option<u32>) -> usize {
 // return a list of all allowances here
 pub fn query_all_allowances(
     deps: Deps,
+    env: Env,
     start_after: Option<String>,
     limit: Option<u32>,
+    include_expired: bool,
+    order: Order,
 ) -> StdResult<AllAllowancesResponse> {
     let limit = calc_limit(limit);
-    // we use raw addresses here....
-    let start = start_after.map(Bound::exclusive);
+    // all denoms of start_after sort after its (spender, "") prefix, and before the prefix
+    // followed by 0xff, as denoms are utf-8 so they never contain it
+    let (min, max) = match (start_after, order) {
+        (None, _) => (None, None),
+        (Some(spender), Order::Ascending) => {
+            let mut key = (&Addr::unchecked(spender), "").joined_key();
+            key.push(0xff);
+            (Some(Bound::Exclusive(key)), None)
+        }
+        (Some(spender), Order::Descending) => {
+            let key = (&Addr::unchecked(spender), "").joined_key();
+            (None, Some(Bound::Exclusive(key)))
+        }
+    };
 
-    let res: StdResult<Vec<AllowanceInfo>> = ALLOWANCES
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
-        .map(|item| {
-            item.and_then(|(k, allow)| {
-                Ok(AllowanceInfo {
-                    spender: String::from_utf8(k)?,
-                    balance: allow.balance,
-                    expires: allow.expires,
-                })
-            })
-        })
-        .collect();
-    Ok(AllAllowancesResponse { allowances: res? })
+    let mut allowances: Vec<AllowanceInfo> = vec![];
+    for item in ALLOWANCES.range_de(deps.storage, min, max, order) {
+        let ((spender, denom), allow) = item?;
+        if !include_expired && allow.expires.is_expired(&env.block) {
+            continue;
+        }
+        let info = DenomAllowanceInfo {
+            denom,
+            amount: allow.amount,
+            expires: allow.expires,
+        };
+        // the denoms of a spender are next to each other
+        if let Some(last) = allowances
+            .last_mut()
+            .filter(|last| last.spender == spender.as_str())
+        {
+            last.denoms.push(info);
+        } else if allowances.len() == limit {
+            break;
+        } else {
+            allowances.push(AllowanceInfo {
+                spender: spender.into(),
+                balance: NativeBalance::default(),
+                denoms: vec![info],
+            });
+        }
+    }
+    for allowance in allowances.iter_mut() {
+        // the denoms are always listed ascending
+        if let Order::Descending = order {
+            allowance.denoms.reverse();
+        }
+        allowance.balance = spendable_balance(&allowance.denoms, &env.block);
+    }
+    Ok(AllAllowancesResponse { allowances })
 }
 
 // return a list of all permissions here
 pub fn query_all_permissions(
     deps: Deps,
+    env: Env,
     start_after: Option<String>,
     limit: Option<u32>,
+    include_expired: bool,
+    order: Order,
 ) -> StdResult<AllPermissionsResponse> {
     let limit = calc_limit(limit);
-    let start = start_after.map(Bound::exclusive);
+    let bound = start_after.map(Bound::exclusive);
+    let (min, max) = match order {
+        Order::Ascending => (bound, None),
+        Order::Descending => (None, bound),
+    };
 
-    let res: StdResult<Vec<PermissionsInfo>> = PERMISSIONS
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
+    let permissions = PERMISSIONS
+        .range(deps.storage, min, max, order)
         .map(|item| {
-            item.and_then(|(k, perm)| {
+            item.and_then(|(k, grant)| {
                 Ok(PermissionsInfo {
                     spender: String::from_utf8(k)?,
-                    permissions: perm,
+                    permissions: grant.permissions,
+                    expires: grant.expires,
                 })
             })
-        })
-        .collect();
-    Ok(AllPermissionsResponse { permissions: res? })
+        });
+    let permissions = paginate_filtered(
+        permissions,
+        limit,
+        &env.block,
+        include_expired,
+        |perm, block| perm.expires.is_expired(block),
+    )?;
+    Ok(AllPermissionsResponse { permissions })
 }
 
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coin, coins, Addr, StakingMsg, Timestamp};
+    use cosmwasm_std::{coin, coins, from_binary, Addr, StakingMsg, Timestamp};
 
-    use cw0::NativeBalance;
+    use cw0::{NativeBalance, QueryOrder};
     use cw1_whitelist::msg::AdminListResponse;
     use cw2::{get_contract_version, ContractVersion};
 
-    use crate::state::Permissions;
+    use crate::state::{Allowance, Permissions, DEFAULT_SPEND_HISTORY_RETENTION};
 
     use super::*;
 
+    // the allowances per denom, as returned by the queries
+    fn denoms(allowances: &[(Coin, Expiration)]) -> Vec<DenomAllowanceInfo> {
+        allowances
+            .iter()
+            .map(|(coin, expires)| DenomAllowanceInfo {
+                denom: coin.denom.clone(),
+                amount: coin.amount,
+                expires: *expires,
+            })
+            .collect()
+    }
+
     // this will set up instantiation for other tests
     fn setup_test_case(
         mut deps: DepsMut,
@@ -455,6 +1056,8 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins: admins.iter().map(|x| x.to_string()).collect(),
             mutable: true,
+            freeze_delay: None,
+            unfreezable: false,
         };
         instantiate(deps.branch(), mock_env(), info.clone(), instantiate_msg).unwrap();
 
@@ -544,26 +1147,16 @@ mod tests {
         );
 
         // Check allowances work for accounts with balances
-        let allowance = query_allowance(deps.as_ref(), spender1.to_string()).unwrap();
-        assert_eq!(
-            allowance,
-            Allowance {
-                balance: NativeBalance(vec![allow1.clone()]),
-                expires: expires_never,
-            }
-        );
-        let allowance = query_allowance(deps.as_ref(), spender2.to_string()).unwrap();
-        assert_eq!(
-            allowance,
-            Allowance {
-                balance: NativeBalance(vec![allow1]),
-                expires: expires_never,
-            }
-        );
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender1.to_string()).unwrap();
+        assert_eq!(allowance.denoms, denoms(&[(allow1.clone(), expires_never)]));
+        assert_eq!(allowance.balance, NativeBalance(vec![allow1.clone()]));
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender2.to_string()).unwrap();
+        assert_eq!(allowance.denoms, denoms(&[(allow1.clone(), expires_never)]));
+        assert_eq!(allowance.balance, NativeBalance(vec![allow1]));
 
         // Check allowances work for accounts with no balance
-        let allowance = query_allowance(deps.as_ref(), spender3.to_string()).unwrap();
-        assert_eq!(allowance, Allowance::default(),);
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender3.to_string()).unwrap();
+        assert_eq!(allowance, AllowanceResponse::default());
     }
 
     #[test]
@@ -594,16 +1187,23 @@ mod tests {
         );
 
         // let's try pagination
-        let allowances = query_all_allowances(deps.as_ref(), None, Some(2))
-            .unwrap()
-            .allowances;
+        let allowances = query_all_allowances(
+            deps.as_ref(),
+            mock_env(),
+            None,
+            Some(2),
+            true,
+            Order::Ascending,
+        )
+        .unwrap()
+        .allowances;
         assert_eq!(2, allowances.len());
         assert_eq!(
             allowances[0],
             AllowanceInfo {
                 spender: spender1.into(),
                 balance: NativeBalance(initial_allowances.clone()),
-                expires: Expiration::Never {},
+                denoms: denoms(&[(initial_allowances[0].clone(), Expiration::Never {})]),
             }
         );
         assert_eq!(
@@ -611,25 +1211,320 @@ mod tests {
             AllowanceInfo {
                 spender: spender2.to_string(),
                 balance: NativeBalance(initial_allowances.clone()),
-                expires: Expiration::Never {},
+                denoms: denoms(&[(initial_allowances[0].clone(), Expiration::Never {})]),
             }
         );
 
         // now continue from after the last one
-        let allowances = query_all_allowances(deps.as_ref(), Some(spender2.into()), Some(2))
-            .unwrap()
-            .allowances;
+        let allowances = query_all_allowances(
+            deps.as_ref(),
+            mock_env(),
+            Some(spender2.into()),
+            Some(2),
+            true,
+            Order::Ascending,
+        )
+        .unwrap()
+        .allowances;
         assert_eq!(1, allowances.len());
+        // it is listed, but has already expired so nothing can be spent
         assert_eq!(
             allowances[0],
             AllowanceInfo {
                 spender: spender3.into(),
-                balance: NativeBalance(initial_allowances),
-                expires: expires_later,
+                balance: NativeBalance::default(),
+                denoms: denoms(&[(initial_allowances[0].clone(), expires_later)]),
             }
         );
     }
 
+    #[test]
+    fn query_all_allowances_filters_expired() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let spender1 = "spender0001";
+        let spender2 = "spender0002";
+        let spender3 = "spender0003";
+        let spenders = vec![spender1, spender2, spender3];
+        // spender1 expires at the current height, spender3 at the next block
+        let env = mock_env();
+        let expirations = vec![
+            Expiration::AtHeight(env.block.height),
+            Expiration::Never {},
+            Expiration::AtHeight(env.block.height + 1),
+        ];
+        let info = mock_info(owner, &[]);
+        setup_test_case(
+            deps.as_mut(),
+            &info,
+            &[owner],
+            &spenders,
+            &coins(1234, "mytoken"),
+            &expirations,
+        );
+
+        let spenders_of = |res: AllAllowancesResponse| -> Vec<String> {
+            res.allowances.into_iter().map(|a| a.spender).collect()
+        };
+
+        // expired ones are only left out if include_expired is false
+        let res = query_all_allowances(
+            deps.as_ref(),
+            env.clone(),
+            None,
+            None,
+            false,
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(spenders_of(res), vec![spender2, spender3]);
+        let res = query_all_allowances(
+            deps.as_ref(),
+            env.clone(),
+            None,
+            None,
+            true,
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(spenders_of(res), vec![spender1, spender2, spender3]);
+
+        // they don't count towards the limit
+        let res = query_all_allowances(
+            deps.as_ref(),
+            env.clone(),
+            None,
+            Some(1),
+            false,
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(spenders_of(res), vec![spender2]);
+
+        // once spender3 expires as well, only the unlimited one is left
+        let mut later = env;
+        later.block.height += 1;
+        let res = query_all_allowances(deps.as_ref(), later, None, None, false, Order::Ascending)
+            .unwrap();
+        assert_eq!(spenders_of(res), vec![spender2]);
+
+        // the query message includes them by default
+        let msg = |include_expired| QueryMsg::AllAllowances {
+            start_after: None,
+            limit: None,
+            include_expired,
+            order: None,
+        };
+        let res: AllAllowancesResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg(None)).unwrap()).unwrap();
+        assert_eq!(spenders_of(res), vec![spender1, spender2, spender3]);
+        let res: AllAllowancesResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg(Some(false))).unwrap()).unwrap();
+        assert_eq!(spenders_of(res), vec![spender2, spender3]);
+    }
+
+    #[test]
+    fn query_all_allowances_pages_in_both_orders() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let spenders = [
+            "spender0001",
+            "spender0002",
+            "spender0003",
+            "spender0004",
+            "spender0005",
+        ];
+        // spender3 is expired at the current height
+        let env = mock_env();
+        let mut expirations = vec![Expiration::Never {}; 5];
+        expirations[2] = Expiration::AtHeight(env.block.height);
+        let info = mock_info(owner, &[]);
+        setup_test_case(
+            deps.as_mut(),
+            &info,
+            &[owner],
+            &spenders,
+            &[coin(1234, "atom"), coin(5678, "btc")],
+            &expirations,
+        );
+
+        let page = |start_after: Option<&str>, include_expired, order| -> Vec<String> {
+            query_all_allowances(
+                deps.as_ref(),
+                env.clone(),
+                start_after.map(String::from),
+                Some(2),
+                include_expired,
+                order,
+            )
+            .unwrap()
+            .allowances
+            .into_iter()
+            .map(|a| a.spender)
+            .collect()
+        };
+
+        // the second page starts right after, or right at, the expired entry
+        assert_eq!(
+            page(None, false, Order::Ascending),
+            vec![spenders[0], spenders[1]]
+        );
+        assert_eq!(
+            page(Some(spenders[1]), false, Order::Ascending),
+            vec![spenders[3], spenders[4]]
+        );
+        assert_eq!(
+            page(Some(spenders[2]), false, Order::Ascending),
+            vec![spenders[3], spenders[4]]
+        );
+        assert_eq!(
+            page(Some(spenders[1]), true, Order::Ascending),
+            vec![spenders[2], spenders[3]]
+        );
+
+        assert_eq!(
+            page(None, false, Order::Descending),
+            vec![spenders[4], spenders[3]]
+        );
+        assert_eq!(
+            page(Some(spenders[3]), false, Order::Descending),
+            vec![spenders[1], spenders[0]]
+        );
+        assert_eq!(
+            page(Some(spenders[2]), false, Order::Descending),
+            vec![spenders[1], spenders[0]]
+        );
+        assert_eq!(
+            page(Some(spenders[3]), true, Order::Descending),
+            vec![spenders[2], spenders[1]]
+        );
+
+        // all denoms of a spender are on the same page, listed ascending in both orders
+        let msg = QueryMsg::AllAllowances {
+            start_after: Some(spenders[4].into()),
+            limit: Some(1),
+            include_expired: None,
+            order: Some(QueryOrder::Descending),
+        };
+        let res: AllAllowancesResponse =
+            from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(
+            res.allowances,
+            vec![AllowanceInfo {
+                spender: spenders[3].into(),
+                balance: NativeBalance(vec![coin(1234, "atom"), coin(5678, "btc")]),
+                denoms: denoms(&[
+                    (coin(1234, "atom"), Expiration::Never {}),
+                    (coin(5678, "btc"), Expiration::Never {}),
+                ]),
+            }]
+        );
+    }
+
+    #[test]
+    fn query_all_permissions_pages_in_both_orders() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let info = mock_info(owner, &[]);
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![owner.to_string()],
+            mutable: true,
+            freeze_delay: None,
+            unfreezable: false,
+        };
+        instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+        // spender3 is expired at the current height
+        let env = mock_env();
+        let spenders = [
+            "spender0001",
+            "spender0002",
+            "spender0003",
+            "spender0004",
+            "spender0005",
+        ];
+        for (i, spender) in spenders.iter().enumerate() {
+            let expires = if i == 2 {
+                Some(Expiration::AtHeight(env.block.height))
+            } else {
+                None
+            };
+            let msg = ExecuteMsg::SetPermissions {
+                spender: spender.to_string(),
+                permissions: Permissions {
+                    delegate: true,
+                    redelegate: false,
+                    undelegate: false,
+                    withdraw: true,
+                },
+                expires,
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let page = |start_after: Option<&str>, include_expired, order| -> Vec<String> {
+            query_all_permissions(
+                deps.as_ref(),
+                env.clone(),
+                start_after.map(String::from),
+                Some(2),
+                include_expired,
+                order,
+            )
+            .unwrap()
+            .permissions
+            .into_iter()
+            .map(|p| p.spender)
+            .collect()
+        };
+
+        // the second page starts right after, or right at, the expired entry
+        assert_eq!(
+            page(Some(spenders[1]), false, Order::Ascending),
+            vec![spenders[3], spenders[4]]
+        );
+        assert_eq!(
+            page(Some(spenders[2]), false, Order::Ascending),
+            vec![spenders[3], spenders[4]]
+        );
+        assert_eq!(
+            page(Some(spenders[1]), true, Order::Ascending),
+            vec![spenders[2], spenders[3]]
+        );
+
+        assert_eq!(
+            page(None, false, Order::Descending),
+            vec![spenders[4], spenders[3]]
+        );
+        assert_eq!(
+            page(Some(spenders[3]), false, Order::Descending),
+            vec![spenders[1], spenders[0]]
+        );
+        assert_eq!(
+            page(Some(spenders[2]), false, Order::Descending),
+            vec![spenders[1], spenders[0]]
+        );
+        assert_eq!(
+            page(Some(spenders[3]), true, Order::Descending),
+            vec![spenders[2], spenders[1]]
+        );
+
+        // the order is optional in the query message
+        let msg = QueryMsg::AllPermissions {
+            start_after: Some(spenders[3].into()),
+            limit: None,
+            include_expired: Some(true),
+            order: Some(QueryOrder::Descending),
+        };
+        let res: AllPermissionsResponse =
+            from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(res.permissions.len(), 3);
+        assert_eq!(res.permissions[0].spender, spenders[2]);
+    }
+
     #[test]
     fn query_permissions_works() {
         let mut deps = mock_dependencies(&[]);
@@ -656,12 +1551,15 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins,
             mutable: true,
+            freeze_delay: None,
+            unfreezable: false,
         };
         instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
 
         let setup_perm_msg1 = ExecuteMsg::SetPermissions {
             spender: spender1.to_string(),
             permissions: god_mode,
+            expires: None,
         };
         execute(deps.as_mut(), mock_env(), info.clone(), setup_perm_msg1).unwrap();
 
@@ -669,13 +1567,16 @@ mod tests {
             spender: spender2.to_string(),
             // default is no permission
             permissions: Default::default(),
+            expires: None,
         };
         execute(deps.as_mut(), mock_env(), info, setup_perm_msg2).unwrap();
 
-        let permissions = query_permissions(deps.as_ref(), spender1.to_string()).unwrap();
+        let permissions =
+            query_permissions(deps.as_ref(), mock_env(), spender1.to_string()).unwrap();
         assert_eq!(permissions, god_mode);
 
-        let permissions = query_permissions(deps.as_ref(), spender2.to_string()).unwrap();
+        let permissions =
+            query_permissions(deps.as_ref(), mock_env(), spender2.to_string()).unwrap();
         assert_eq!(
             permissions,
             Permissions {
@@ -687,7 +1588,8 @@ mod tests {
         );
 
         // no permission is set. should return false
-        let permissions = query_permissions(deps.as_ref(), spender3.to_string()).unwrap();
+        let permissions =
+            query_permissions(deps.as_ref(), mock_env(), spender3.to_string()).unwrap();
         assert_eq!(
             permissions,
             Permissions {
@@ -732,37 +1634,50 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins,
             mutable: true,
+            freeze_delay: None,
+            unfreezable: false,
         };
         instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
 
         let setup_perm_msg1 = ExecuteMsg::SetPermissions {
             spender: spender1.to_string(),
             permissions: god_mode,
+            expires: None,
         };
         execute(deps.as_mut(), mock_env(), info.clone(), setup_perm_msg1).unwrap();
 
         let setup_perm_msg2 = ExecuteMsg::SetPermissions {
             spender: spender2.to_string(),
             permissions: noob_mode,
+            expires: None,
         };
         execute(deps.as_mut(), mock_env(), info.clone(), setup_perm_msg2).unwrap();
 
         let setup_perm_msg3 = ExecuteMsg::SetPermissions {
             spender: spender3.to_string(),
             permissions: noob_mode,
+            expires: None,
         };
         execute(deps.as_mut(), mock_env(), info, setup_perm_msg3).unwrap();
 
         // let's try pagination
-        let permissions = query_all_permissions(deps.as_ref(), None, Some(2))
-            .unwrap()
-            .permissions;
+        let permissions = query_all_permissions(
+            deps.as_ref(),
+            mock_env(),
+            None,
+            Some(2),
+            false,
+            Order::Ascending,
+        )
+        .unwrap()
+        .permissions;
         assert_eq!(2, permissions.len());
         assert_eq!(
             permissions[0],
             PermissionsInfo {
                 spender: spender1.into(),
                 permissions: god_mode,
+                expires: Expiration::Never {},
             }
         );
         assert_eq!(
@@ -770,19 +1685,28 @@ mod tests {
             PermissionsInfo {
                 spender: spender2.to_string(),
                 permissions: noob_mode,
+                expires: Expiration::Never {},
             }
         );
 
         // now continue from after the last one
-        let permissions = query_all_permissions(deps.as_ref(), Some(spender2.into()), Some(2))
-            .unwrap()
-            .permissions;
+        let permissions = query_all_permissions(
+            deps.as_ref(),
+            mock_env(),
+            Some(spender2.into()),
+            Some(2),
+            false,
+            Order::Ascending,
+        )
+        .unwrap()
+        .permissions;
         assert_eq!(1, permissions.len());
         assert_eq!(
             permissions[0],
             PermissionsInfo {
                 spender: spender3.into(),
                 permissions: noob_mode,
+                expires: Expiration::Never {},
             }
         );
     }
@@ -800,7 +1724,7 @@ mod tests {
         setup_test_case(deps.as_mut(), &info, &initial_admins, &[], &[], &[]);
 
         // Verify
-        let config = query_admin_list(deps.as_ref()).unwrap();
+        let config = query_admin_list(deps.as_ref(), mock_env()).unwrap();
         assert_eq!(
             config,
             AdminListResponse {
@@ -817,7 +1741,7 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         // Verify
-        let config = query_admin_list(deps.as_ref()).unwrap();
+        let config = query_admin_list(deps.as_ref(), mock_env()).unwrap();
         println!("config: {:#?}", config);
         assert_eq!(
             config,
@@ -834,7 +1758,7 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         // Verify admin3 is now the sole admin
-        let config = query_admin_list(deps.as_ref()).unwrap();
+        let config = query_admin_list(deps.as_ref(), mock_env()).unwrap();
         println!("config: {:#?}", config);
         assert_eq!(
             config,
@@ -862,7 +1786,7 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
         // Verify
-        let config = query_admin_list(deps.as_ref()).unwrap();
+        let config = query_admin_list(deps.as_ref(), mock_env()).unwrap();
         println!("config: {:#?}", config);
         assert_eq!(
             config,
@@ -871,6 +1795,26 @@ mod tests {
                 mutable: true,
             }
         );
+
+        // admins can also be added and removed one by one
+        let info = mock_info(owner, &[]);
+        let msg = ExecuteMsg::AddAdmins {
+            addrs: vec![admin2.to_string()],
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::RemoveAdmins {
+            addrs: vec![admin3.to_string(), owner.to_string()],
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let config = query_admin_list(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(config.admins, vec![admin2.to_string()]);
+
+        // but the last one cannot be removed
+        let msg = ExecuteMsg::RemoveAdmins {
+            addrs: vec![admin2.to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(admin2, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::LastAdmin {});
     }
 
     #[test]
@@ -924,13 +1868,13 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         // Verify
-        let allowance = query_allowance(deps.as_ref(), spender1.to_string()).unwrap();
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender1.to_string()).unwrap();
         assert_eq!(
-            allowance,
-            Allowance {
-                balance: NativeBalance(vec![coin(amount1 * 2, &allow1.denom), allow2.clone()]),
-                expires: expires_height,
-            }
+            allowance.denoms,
+            denoms(&[
+                (coin(amount1 * 2, denom1), expires_height),
+                (allow2.clone(), expires_height)
+            ])
         );
 
         // Add to spender2 account (expires = Some)
@@ -942,13 +1886,14 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         // Verify
-        let allowance = query_allowance(deps.as_ref(), spender2.to_string()).unwrap();
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender2.to_string()).unwrap();
         assert_eq!(
-            allowance,
-            Allowance {
-                balance: NativeBalance(vec![allow1.clone(), allow2.clone(), allow3]),
-                expires: expires_height,
-            }
+            allowance.denoms,
+            denoms(&[
+                (allow1.clone(), expires_never),
+                (allow2.clone(), expires_never),
+                (allow3, expires_height)
+            ])
         );
 
         // Add to spender3 (new account) (expires = None) => default Expiration::Never
@@ -960,14 +1905,8 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         // Verify
-        let allowance = query_allowance(deps.as_ref(), spender3.to_string()).unwrap();
-        assert_eq!(
-            allowance,
-            Allowance {
-                balance: NativeBalance(vec![allow1]),
-                expires: expires_never,
-            }
-        );
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender3.to_string()).unwrap();
+        assert_eq!(allowance.denoms, denoms(&[(allow1, expires_never)]));
 
         // Add to spender4 (new account) (expires = Some)
         let msg = ExecuteMsg::IncreaseAllowance {
@@ -978,14 +1917,8 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
         // Verify
-        let allowance = query_allowance(deps.as_ref(), spender4.into()).unwrap();
-        assert_eq!(
-            allowance,
-            Allowance {
-                balance: NativeBalance(vec![allow2]),
-                expires: expires_time,
-            }
-        );
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender4.into()).unwrap();
+        assert_eq!(allowance.denoms, denoms(&[(allow2, expires_time)]));
     }
 
     #[test]
@@ -1037,15 +1970,15 @@ mod tests {
         let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
 
         // Verify
-        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), ContractError::NoAllowance {});
         // Verify everything stays the same for that spender
-        let allowance = query_allowance(deps.as_ref(), spender1.to_string()).unwrap();
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender1.to_string()).unwrap();
         assert_eq!(
-            allowance,
-            Allowance {
-                balance: NativeBalance(vec![allow1.clone(), allow2.clone()]),
-                expires: expires_height,
-            }
+            allowance.denoms,
+            denoms(&[
+                (allow1.clone(), expires_height),
+                (allow2.clone(), expires_height)
+            ])
         );
 
         // Subtract from spender2 (existing) account (brings denom to 0, other denoms left)
@@ -1057,14 +1990,8 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         // Verify
-        let allowance = query_allowance(deps.as_ref(), spender2.to_string()).unwrap();
-        assert_eq!(
-            allowance,
-            Allowance {
-                balance: NativeBalance(vec![allow1.clone()]),
-                expires: expires_never,
-            }
-        );
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender2.to_string()).unwrap();
+        assert_eq!(allowance.denoms, denoms(&[(allow1.clone(), expires_never)]));
 
         // Subtract from spender1 (existing) account (brings denom to > 0)
         let msg = ExecuteMsg::DecreaseAllowance {
@@ -1075,16 +2002,13 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         // Verify
-        let allowance = query_allowance(deps.as_ref(), spender1.to_string()).unwrap();
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender1.to_string()).unwrap();
         assert_eq!(
-            allowance,
-            Allowance {
-                balance: NativeBalance(vec![
-                    coin(amount1 / 2 + (amount1 & 1), denom1),
-                    allow2.clone()
-                ]),
-                expires: expires_height,
-            }
+            allowance.denoms,
+            denoms(&[
+                (coin(amount1 / 2 + (amount1 & 1), denom1), expires_height),
+                (allow2.clone(), expires_height)
+            ])
         );
 
         // Subtract from spender2 (existing) account (brings denom to 0, no other denoms left => should delete Allowance)
@@ -1096,8 +2020,8 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         // Verify
-        let allowance = query_allowance(deps.as_ref(), spender2.to_string()).unwrap();
-        assert_eq!(allowance, Allowance::default());
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender2.to_string()).unwrap();
+        assert_eq!(allowance, AllowanceResponse::default());
 
         // Subtract from spender2 (empty) account (should error)
         let msg = ExecuteMsg::DecreaseAllowance {
@@ -1119,82 +2043,578 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
         // Verify
-        let allowance = query_allowance(deps.as_ref(), spender1.to_string()).unwrap();
-        assert_eq!(
-            allowance,
-            Allowance {
-                balance: NativeBalance(vec![allow2]),
-                expires: expires_height,
-            }
-        );
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender1.to_string()).unwrap();
+        assert_eq!(allowance.denoms, denoms(&[(allow2, expires_height)]));
     }
 
     #[test]
-    fn execute_checks() {
+    fn can_execute_checks_every_denom() {
         let mut deps = mock_dependencies(&[]);
 
         let owner = "admin0001";
-        let admins = vec![owner, "admin0002"];
-
-        let spender1 = "spender0001";
-        let spender2 = "spender0002";
-        let initial_spenders = vec![spender1];
-
-        let denom1 = "token1";
-        let amount1 = 1111;
-        let allow1 = coin(amount1, denom1);
-        let initial_allowances = vec![allow1];
-
-        let expires_never = Expiration::Never {};
-        let initial_expirations = vec![expires_never];
-
+        let spender = "spender0001";
         let info = mock_info(owner, &[]);
         setup_test_case(
             deps.as_mut(),
             &info,
-            &admins,
-            &initial_spenders,
-            &initial_allowances,
-            &initial_expirations,
+            &[owner],
+            &[spender],
+            &[coin(100, "uatom"), coin(50, "ujuno")],
+            &[Expiration::Never {}],
         );
 
-        // Create Send message
-        let msgs = vec![BankMsg::Send {
-            to_address: spender2.to_string(),
-            amount: coins(1000, "token1"),
-        }
-        .into()];
+        let send = |amount: Vec<Coin>| -> CosmosMsg {
+            BankMsg::Send {
+                to_address: "anyone".to_string(),
+                amount,
+            }
+            .into()
+        };
+        let can_execute = |deps: Deps, msg: &CosmosMsg| -> bool {
+            query_can_execute(deps, mock_env(), spender.to_string(), msg.clone())
+                .unwrap()
+                .can_execute
+        };
+        let execute_msg = |msg: &CosmosMsg| ExecuteMsg::Execute {
+            msgs: vec![msg.clone()],
+        };
 
-        let execute_msg = ExecuteMsg::Execute { msgs: msgs.clone() };
+        // uatom is covered, ujuno is not, so the message as a whole is not
+        let too_much_juno = send(vec![coin(80, "uatom"), coin(60, "ujuno")]);
+        assert!(!can_execute(deps.as_ref(), &too_much_juno));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender, &[]),
+            execute_msg(&too_much_juno),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+        // a denom without any allowance is not covered either
+        let other_denom = send(vec![coin(80, "uatom"), coin(1, "uosmo")]);
+        assert!(!can_execute(deps.as_ref(), &other_denom));
+
+        // both covered works, and nothing was spent by the failed attempt
+        let both = send(vec![coin(80, "uatom"), coin(50, "ujuno")]);
+        assert!(can_execute(deps.as_ref(), &both));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender, &[]),
+            execute_msg(&both),
+        )
+        .unwrap();
 
-        // spender2 cannot spend funds (no initial allowance)
-        let info = mock_info(&spender2, &[]);
-        let err = execute(deps.as_mut(), mock_env(), info, execute_msg.clone()).unwrap_err();
-        assert_eq!(err, ContractError::NoAllowance {});
+        // now the remaining allowance is checked
+        assert!(can_execute(deps.as_ref(), &send(coins(20, "uatom"))));
+        assert!(!can_execute(deps.as_ref(), &send(coins(21, "uatom"))));
+        assert!(!can_execute(deps.as_ref(), &send(coins(1, "ujuno"))));
+        // the same denom twice in one message adds up
+        let twice = send(vec![coin(15, "uatom"), coin(15, "uatom")]);
+        assert!(!can_execute(deps.as_ref(), &twice));
 
-        // But spender1 can (he has enough funds)
-        let info = mock_info(&spender1, &[]);
-        let res = execute(deps.as_mut(), mock_env(), info.clone(), execute_msg.clone()).unwrap();
-        let msgs: Vec<_> = msgs.into_iter().map(SubMsg::new).collect();
-        assert_eq!(res.messages, msgs);
-        assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "execute"),
-                attr("owner", spender1.to_string())
-            ]
-        );
+        // distribution messages need the withdraw permission, like in execute
+        let withdraw: CosmosMsg = DistributionMsg::WithdrawDelegatorReward {
+            validator: "validator1".into(),
+        }
+        .into();
+        assert!(!can_execute(deps.as_ref(), &withdraw));
+        let msg = ExecuteMsg::SetPermissions {
+            spender: spender.to_string(),
+            permissions: Permissions {
+                delegate: false,
+                redelegate: false,
+                undelegate: false,
+                withdraw: true,
+            },
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(can_execute(deps.as_ref(), &withdraw));
+    }
 
-        // And then cannot (not enough funds anymore)
-        let err = execute(deps.as_mut(), mock_env(), info, execute_msg.clone()).unwrap_err();
-        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+    #[test]
+    fn spend_across_denoms_with_one_expired() {
+        let mut deps = mock_dependencies(&[]);
 
-        // Owner / admins can do anything (at the contract level)
+        let owner = "admin0001";
+        let spender = "spender0001";
         let info = mock_info(owner, &[]);
-        let res = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
-        assert_eq!(res.messages, msgs);
-        assert_eq!(
-            res.attributes,
+        let start = mock_env().block.height;
+        // uatom never expires, ujuno only for 5 blocks
+        let expires_soon = Expiration::AtHeight(start + 5);
+        setup_test_case(
+            deps.as_mut(),
+            &info,
+            &[owner],
+            &[spender],
+            &coins(100, "uatom"),
+            &[Expiration::Never {}],
+        );
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.to_string(),
+            amount: coin(10, "ujuno"),
+            expires: Some(expires_soon),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let send = |amount: Vec<Coin>| -> CosmosMsg {
+            BankMsg::Send {
+                to_address: "anyone".to_string(),
+                amount,
+            }
+            .into()
+        };
+        let both = send(vec![coin(50, "uatom"), coin(5, "ujuno")]);
+        let execute_msg = |msg: &CosmosMsg| ExecuteMsg::Execute {
+            msgs: vec![msg.clone()],
+        };
+
+        // until the expiration, both can be spent in one message
+        let env = mock_env_at_height(start + 4);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(spender, &[]),
+            execute_msg(&both),
+        )
+        .unwrap();
+        let allowance = query_allowance(deps.as_ref(), env, spender.to_string()).unwrap();
+        assert_eq!(
+            allowance.balance,
+            NativeBalance(vec![coin(50, "uatom"), coin(5, "ujuno")])
+        );
+
+        // at the expiration height, a message spending both is rejected as a whole
+        let env = mock_env_at_height(start + 5);
+        let res = query_can_execute(
+            deps.as_ref(),
+            env.clone(),
+            spender.to_string(),
+            both.clone(),
+        )
+        .unwrap();
+        assert!(!res.can_execute);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(spender, &[]),
+            execute_msg(&both),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Expired {});
+        let allowance = query_allowance(deps.as_ref(), env.clone(), spender.to_string()).unwrap();
+        assert_eq!(allowance.balance, NativeBalance(vec![coin(50, "uatom")]));
+        assert_eq!(
+            allowance.denoms,
+            denoms(&[
+                (coin(50, "uatom"), Expiration::Never {}),
+                (coin(5, "ujuno"), expires_soon)
+            ])
+        );
+
+        // but the other denom can still be spent on its own
+        let atom_only = send(coins(30, "uatom"));
+        let res = query_can_execute(
+            deps.as_ref(),
+            env.clone(),
+            spender.to_string(),
+            atom_only.clone(),
+        )
+        .unwrap();
+        assert!(res.can_execute);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(spender, &[]),
+            execute_msg(&atom_only),
+        )
+        .unwrap();
+
+        // expired denoms are only listed if requested
+        let res = query_all_allowances(
+            deps.as_ref(),
+            env.clone(),
+            None,
+            None,
+            false,
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(
+            res.allowances,
+            vec![AllowanceInfo {
+                spender: spender.to_string(),
+                balance: NativeBalance(coins(20, "uatom")),
+                denoms: denoms(&[(coin(20, "uatom"), Expiration::Never {})]),
+            }]
+        );
+        let res =
+            query_all_allowances(deps.as_ref(), env, None, None, true, Order::Ascending).unwrap();
+        assert_eq!(res.allowances[0].balance, NativeBalance(coins(20, "uatom")));
+        assert_eq!(res.allowances[0].denoms.len(), 2);
+    }
+
+    #[test]
+    fn migrate_legacy_allowances() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let info = mock_info(owner, &[]);
+        setup_test_case(deps.as_mut(), &info, &[owner], &[], &[], &[]);
+
+        // allowances as they were stored before, with one expiration for all denoms
+        let spender1 = Addr::unchecked("spender0001");
+        let spender2 = Addr::unchecked("spender0002");
+        let expires = Expiration::AtHeight(20000);
+        let legacy = Allowance {
+            balance: NativeBalance(vec![coin(100, "uatom"), coin(10, "ujuno")]),
+            expires,
+        };
+        LEGACY_ALLOWANCES
+            .save(&mut deps.storage, &spender1, &legacy)
+            .unwrap();
+        let legacy = Allowance {
+            balance: NativeBalance(coins(5, "uatom")),
+            expires: Expiration::Never {},
+        };
+        LEGACY_ALLOWANCES
+            .save(&mut deps.storage, &spender2, &legacy)
+            .unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1], attr("migrated_allowances", "2"));
+
+        // every denom got the shared expiration, the old entries are gone
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender1.to_string()).unwrap();
+        assert_eq!(
+            allowance.denoms,
+            denoms(&[(coin(100, "uatom"), expires), (coin(10, "ujuno"), expires)])
+        );
+        let allowance = query_allowance(deps.as_ref(), mock_env(), spender2.to_string()).unwrap();
+        assert_eq!(
+            allowance.denoms,
+            denoms(&[(coin(5, "uatom"), Expiration::Never {})])
+        );
+        assert!(LEGACY_ALLOWANCES.is_empty(&deps.storage));
+
+        // it only migrates this contract
+        set_contract_version(&mut deps.storage, "crates.io:another-contract", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotMigrate {
+                previous_contract: "crates.io:another-contract".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn migrate_legacy_permissions() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let info = mock_info(owner, &[]);
+        setup_test_case(deps.as_mut(), &info, &[owner], &[], &[], &[]);
+
+        // spender1 has permissions as they were stored before, without an expiration
+        let spender1 = Addr::unchecked("spender0001");
+        let legacy = Permissions {
+            delegate: true,
+            redelegate: false,
+            undelegate: true,
+            withdraw: false,
+        };
+        LEGACY_PERMISSIONS
+            .save(&mut deps.storage, &spender1, &legacy)
+            .unwrap();
+        // the old format cannot be read as a grant
+        PERMISSIONS.load(&deps.storage, &spender1).unwrap_err();
+        // spender2 already has a grant, which is left as it is
+        let spender2 = Addr::unchecked("spender0002");
+        let grant = PermissionsGrant {
+            permissions: Permissions {
+                withdraw: true,
+                ..Permissions::default()
+            },
+            expires: Expiration::AtHeight(20000),
+        };
+        PERMISSIONS
+            .save(&mut deps.storage, &spender2, &grant)
+            .unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[2], attr("migrated_permissions", "1"));
+
+        assert_eq!(
+            PERMISSIONS.load(&deps.storage, &spender1).unwrap(),
+            PermissionsGrant {
+                permissions: legacy,
+                expires: Expiration::Never {},
+            }
+        );
+        assert_eq!(PERMISSIONS.load(&deps.storage, &spender2).unwrap(), grant);
+        let permissions =
+            query_permissions(deps.as_ref(), mock_env(), spender1.to_string()).unwrap();
+        assert_eq!(permissions, legacy);
+        let delegate = CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: "validator".to_string(),
+            amount: coin(1, "uatom"),
+        });
+        assert!(can_execute(deps.as_ref(), mock_env(), spender1.to_string(), delegate).unwrap());
+
+        // migrating again changes nothing
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[2], attr("migrated_permissions", "0"));
+        assert_eq!(
+            query_permissions(deps.as_ref(), mock_env(), spender1.to_string()).unwrap(),
+            legacy
+        );
+    }
+
+    #[test]
+    fn cw20_allowances() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let spender = "spender0001";
+        let token = "cw20token";
+        let other_token = "othertoken";
+        let info = mock_info(owner, &[]);
+        setup_test_case(deps.as_mut(), &info, &[owner], &[], &[], &[]);
+
+        // only admins can allow tokens
+        let msg = ExecuteMsg::UpdateCw20Tokens {
+            add: vec![token.to_string()],
+            remove: vec![],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let res = query_cw20_tokens(deps.as_ref()).unwrap();
+        assert_eq!(res.tokens, vec![token.to_string()]);
+
+        // allowances can only be set for allowed tokens
+        let set_allowance = |token: &str| ExecuteMsg::SetCw20Allowance {
+            spender: spender.to_string(),
+            token: token.to_string(),
+            amount: Uint128::new(1000),
+            expires: Some(Expiration::AtHeight(mock_env().block.height + 10)),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set_allowance(other_token),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Cw20NotAllowed {
+                token: other_token.to_string()
+            }
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set_allowance(token),
+        )
+        .unwrap();
+
+        let cw20_msg = |token: &str, msg: &Cw20ExecuteMsg| -> CosmosMsg {
+            WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_binary(msg).unwrap(),
+                funds: vec![],
+            }
+            .into()
+        };
+        let transfer = |amount: u128| Cw20ExecuteMsg::Transfer {
+            recipient: "anyone".to_string(),
+            amount: Uint128::new(amount),
+        };
+        let run = |deps: DepsMut, env: Env, msg: CosmosMsg| {
+            let msg = ExecuteMsg::Execute { msgs: vec![msg] };
+            execute(deps, env, mock_info(spender, &[]), msg)
+        };
+        let allowance =
+            |deps: Deps| query_cw20_allowance(deps, spender.to_string(), token.to_string());
+
+        // transfers are deducted
+        run(deps.as_mut(), mock_env(), cw20_msg(token, &transfer(300))).unwrap();
+        assert_eq!(
+            allowance(deps.as_ref()).unwrap().allowance,
+            Uint128::new(700)
+        );
+
+        // so are sends, only with their own amount, the embedded message is not interpreted
+        let send = Cw20ExecuteMsg::Send {
+            contract: "somecontract".to_string(),
+            amount: Uint128::new(200),
+            msg: to_binary(&transfer(500)).unwrap(),
+        };
+        run(deps.as_mut(), mock_env(), cw20_msg(token, &send)).unwrap();
+        let res = allowance(deps.as_ref()).unwrap();
+        assert_eq!(
+            res,
+            Cw20AllowanceResponse {
+                allowance: Uint128::new(500),
+                expires: Expiration::AtHeight(mock_env().block.height + 10),
+            }
+        );
+
+        // more than the allowance is rejected
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            spender.to_string(),
+            cw20_msg(token, &transfer(500)),
+        )
+        .unwrap();
+        assert!(res.can_execute);
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            spender.to_string(),
+            cw20_msg(token, &transfer(501)),
+        )
+        .unwrap();
+        assert!(!res.can_execute);
+        let err = run(deps.as_mut(), mock_env(), cw20_msg(token, &transfer(501))).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        // tokens not on the allow-list are rejected
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            spender.to_string(),
+            cw20_msg(other_token, &transfer(1)),
+        )
+        .unwrap();
+        assert!(!res.can_execute);
+        let err = run(
+            deps.as_mut(),
+            mock_env(),
+            cw20_msg(other_token, &transfer(1)),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Cw20NotAllowed {
+                token: other_token.to_string()
+            }
+        );
+
+        // as are other cw20 messages, and sending native funds along
+        let burn = Cw20ExecuteMsg::Burn {
+            amount: Uint128::new(1),
+        };
+        let err = run(deps.as_mut(), mock_env(), cw20_msg(token, &burn)).unwrap_err();
+        assert_eq!(err, ContractError::MessageTypeRejected {});
+        let with_funds = WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&transfer(1)).unwrap(),
+            funds: coins(1, "ujuno"),
+        };
+        let err = run(deps.as_mut(), mock_env(), with_funds.into()).unwrap_err();
+        assert_eq!(err, ContractError::MessageTypeRejected {});
+
+        // the allowance expires
+        let mut env = mock_env();
+        env.block.height += 10;
+        let err = run(deps.as_mut(), env, cw20_msg(token, &transfer(1))).unwrap_err();
+        assert_eq!(err, ContractError::Expired {});
+
+        // and once the token is removed from the allow-list, it can no longer be spent
+        let msg = ExecuteMsg::UpdateCw20Tokens {
+            add: vec![],
+            remove: vec![token.to_string()],
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(query_cw20_tokens(deps.as_ref()).unwrap().tokens.len(), 0);
+        let err = run(deps.as_mut(), mock_env(), cw20_msg(token, &transfer(1))).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Cw20NotAllowed {
+                token: token.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn execute_checks() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let admins = vec![owner, "admin0002"];
+
+        let spender1 = "spender0001";
+        let spender2 = "spender0002";
+        let initial_spenders = vec![spender1];
+
+        let denom1 = "token1";
+        let amount1 = 1111;
+        let allow1 = coin(amount1, denom1);
+        let initial_allowances = vec![allow1];
+
+        let expires_never = Expiration::Never {};
+        let initial_expirations = vec![expires_never];
+
+        let info = mock_info(owner, &[]);
+        setup_test_case(
+            deps.as_mut(),
+            &info,
+            &admins,
+            &initial_spenders,
+            &initial_allowances,
+            &initial_expirations,
+        );
+
+        // Create Send message
+        let msgs = vec![BankMsg::Send {
+            to_address: spender2.to_string(),
+            amount: coins(1000, "token1"),
+        }
+        .into()];
+
+        let execute_msg = ExecuteMsg::Execute { msgs: msgs.clone() };
+
+        // spender2 cannot spend funds (no initial allowance)
+        let info = mock_info(&spender2, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, execute_msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NoAllowance {});
+
+        // But spender1 can (he has enough funds)
+        let info = mock_info(&spender1, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), execute_msg.clone()).unwrap();
+        let msgs: Vec<_> = msgs.into_iter().map(SubMsg::new).collect();
+        assert_eq!(res.messages, msgs);
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "execute"),
+                attr("owner", spender1.to_string()),
+                attr("spent", "1000token1"),
+            ]
+        );
+
+        // And then cannot (not enough funds anymore)
+        let err = execute(deps.as_mut(), mock_env(), info, execute_msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        // Owner / admins can do anything (at the contract level)
+        let info = mock_info(owner, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap();
+        assert_eq!(res.messages, msgs);
+        assert_eq!(
+            res.attributes,
             vec![attr("action", "execute"), attr("owner", owner)]
         );
 
@@ -1248,12 +2668,15 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins,
             mutable: true,
+            freeze_delay: None,
+            unfreezable: false,
         };
         instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
 
         let setup_perm_msg1 = ExecuteMsg::SetPermissions {
             spender: spender1.to_string(),
             permissions: god_mode,
+            expires: None,
         };
         execute(deps.as_mut(), mock_env(), info.clone(), setup_perm_msg1).unwrap();
 
@@ -1261,6 +2684,7 @@ mod tests {
             spender: spender2.to_string(),
             // default is no permission
             permissions: Default::default(),
+            expires: None,
         };
         // default is no permission
         execute(deps.as_mut(), mock_env(), info.clone(), setup_perm_msg2).unwrap();
@@ -1327,6 +2751,7 @@ mod tests {
                 undelegate: true,
                 withdraw: false,
             },
+            expires: None,
         };
         execute(deps.as_mut(), mock_env(), info, setup_perm_msg3).unwrap();
         let info = mock_info(&spender3, &[]);
@@ -1365,27 +2790,410 @@ mod tests {
         assert!(res.is_err())
     }
 
-    // tests permissions and allowances are independent features and does not affect each other
     #[test]
-    fn permissions_allowances_independent() {
+    fn permissions_expire() {
         let mut deps = mock_dependencies(&[]);
 
         let owner = "admin0001";
-        let admins = vec![owner.to_string()];
-
-        // spender1 has every permission to stake
         let spender1 = "spender0001";
         let spender2 = "spender0002";
-        let denom = "token1";
-        let amount = 10000;
-        let coin = coin(amount, denom);
 
-        let allow = Allowance {
-            balance: NativeBalance(vec![coin.clone()]),
-            expires: Expiration::Never {},
+        let info = mock_info(owner, &[]);
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![owner.to_string()],
+            mutable: true,
+            freeze_delay: None,
+            unfreezable: false,
         };
-        let perm = Permissions {
-            delegate: true,
+        instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+        let god_mode = Permissions {
+            delegate: true,
+            redelegate: true,
+            undelegate: true,
+            withdraw: true,
+        };
+        // spender1 loses its permissions from the next block on, spender2 keeps them
+        let expires = Expiration::AtHeight(mock_env().block.height + 1);
+        let setup_perm_msg1 = ExecuteMsg::SetPermissions {
+            spender: spender1.to_string(),
+            permissions: god_mode,
+            expires: Some(expires),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), setup_perm_msg1).unwrap();
+        assert_eq!(res.attributes[4], attr("expires", "height:12346"));
+        let setup_perm_msg2 = ExecuteMsg::SetPermissions {
+            spender: spender2.to_string(),
+            permissions: god_mode,
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, setup_perm_msg2).unwrap();
+
+        let delegate: CosmosMsg = StakingMsg::Delegate {
+            validator: "validator1".into(),
+            amount: coin(1000, "token1"),
+        }
+        .into();
+        let withdraw: CosmosMsg = DistributionMsg::WithdrawDelegatorReward {
+            validator: "validator1".into(),
+        }
+        .into();
+        let execute_msg = ExecuteMsg::Execute {
+            msgs: vec![delegate.clone(), withdraw],
+        };
+
+        // one block before the expiration everything works
+        let env = mock_env();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(spender1, &[]),
+            execute_msg.clone(),
+        )
+        .unwrap();
+        let res = query_can_execute(
+            deps.as_ref(),
+            env.clone(),
+            spender1.to_string(),
+            delegate.clone(),
+        )
+        .unwrap();
+        assert!(res.can_execute);
+        let permissions = query_permissions(deps.as_ref(), env, spender1.to_string()).unwrap();
+        assert_eq!(permissions, god_mode);
+
+        // at the expiration height, the permissions are treated as absent
+        let mut env = mock_env();
+        env.block.height += 1;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(spender1, &[]),
+            execute_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotAllowed {});
+        let res = query_can_execute(
+            deps.as_ref(),
+            env.clone(),
+            spender1.to_string(),
+            delegate.clone(),
+        )
+        .unwrap();
+        assert!(!res.can_execute);
+        let permissions =
+            query_permissions(deps.as_ref(), env.clone(), spender1.to_string()).unwrap();
+        assert_eq!(permissions, Permissions::default());
+
+        // spender2 is not affected
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(spender2, &[]),
+            execute_msg,
+        )
+        .unwrap();
+        let res =
+            query_can_execute(deps.as_ref(), env.clone(), spender2.to_string(), delegate).unwrap();
+        assert!(res.can_execute);
+
+        // expired permissions are only listed if requested, with their expiration
+        let msg = QueryMsg::AllPermissions {
+            start_after: None,
+            limit: None,
+            include_expired: None,
+            order: None,
+        };
+        let res: AllPermissionsResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(
+            res.permissions,
+            vec![PermissionsInfo {
+                spender: spender2.to_string(),
+                permissions: god_mode,
+                expires: Expiration::Never {},
+            }]
+        );
+        let res =
+            query_all_permissions(deps.as_ref(), env, None, None, true, Order::Ascending).unwrap();
+        assert_eq!(res.permissions.len(), 2);
+        assert_eq!(
+            res.permissions[0],
+            PermissionsInfo {
+                spender: spender1.to_string(),
+                permissions: god_mode,
+                expires,
+            }
+        );
+    }
+
+    fn send_msg(amount: u128, denom: &str) -> CosmosMsg {
+        BankMsg::Send {
+            to_address: "anyone".to_string(),
+            amount: coins(amount, denom),
+        }
+        .into()
+    }
+
+    fn mock_env_at_height(height: u64) -> Env {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    }
+
+    #[test]
+    fn periodic_allowance_refills() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let spender = "spender0001";
+        let info = mock_info(owner, &[]);
+        setup_test_case(deps.as_mut(), &info, &[owner], &[], &[], &[]);
+
+        // only admins can set it, and only with a non-zero period
+        let msg = ExecuteMsg::SetPeriodicAllowance {
+            spender: spender.to_string(),
+            balance: NativeBalance(coins(500, "ujuno")),
+            period: Duration::Height(0),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::ZeroPeriod {});
+        let msg = ExecuteMsg::SetPeriodicAllowance {
+            spender: spender.to_string(),
+            balance: NativeBalance(coins(500, "ujuno")),
+            period: Duration::Height(10),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // the first period ends 10 blocks from now
+        let start = mock_env().block.height;
+        let period_end = Expiration::AtHeight(start + 10);
+        let spend = |amount| ExecuteMsg::Execute {
+            msgs: vec![send_msg(amount, "ujuno")],
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender, &[]),
+            spend(300),
+        )
+        .unwrap();
+        // there is nothing else to spend from
+        let err = execute(
+            deps.as_mut(),
+            mock_env_at_height(start + 9),
+            mock_info(spender, &[]),
+            spend(300),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoAllowance {});
+
+        let res = query_periodic_allowance(deps.as_ref(), mock_env(), spender.to_string()).unwrap();
+        assert_eq!(
+            res,
+            PeriodicAllowanceResponse {
+                allowance: Some(PeriodicAllowance {
+                    per_period: NativeBalance(coins(500, "ujuno")),
+                    remaining: NativeBalance(coins(200, "ujuno")),
+                    period: Duration::Height(10),
+                    period_end,
+                }),
+                spendable: NativeBalance(coins(200, "ujuno")),
+            }
+        );
+
+        // at the end of the period it can be spent again, even before anything is stored
+        let res = query_periodic_allowance(
+            deps.as_ref(),
+            mock_env_at_height(start + 10),
+            spender.to_string(),
+        )
+        .unwrap();
+        assert_eq!(res.spendable, NativeBalance(coins(500, "ujuno")));
+        let raw = res.allowance.unwrap();
+        assert_eq!(raw.remaining, NativeBalance(coins(200, "ujuno")));
+        assert_eq!(raw.period_end, period_end);
+
+        // after missing 3 periods, the end moves to the one we are in, nothing is accumulated
+        let env = mock_env_at_height(start + 35);
+        let res = query_can_execute(
+            deps.as_ref(),
+            env.clone(),
+            spender.to_string(),
+            send_msg(501, "ujuno"),
+        )
+        .unwrap();
+        assert!(!res.can_execute);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(spender, &[]),
+            spend(100),
+        )
+        .unwrap();
+        let res = query_periodic_allowance(deps.as_ref(), env, spender.to_string()).unwrap();
+        let raw = res.allowance.unwrap();
+        assert_eq!(raw.remaining, NativeBalance(coins(400, "ujuno")));
+        assert_eq!(raw.period_end, Expiration::AtHeight(start + 40));
+        assert_eq!(res.spendable, raw.remaining);
+
+        // time based periods work the same way
+        let msg = ExecuteMsg::SetPeriodicAllowance {
+            spender: spender.to_string(),
+            balance: NativeBalance(coins(500, "ujuno")),
+            period: Duration::Time(3600),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender, &[]),
+            spend(500),
+        )
+        .unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(5 * 3600 + 1);
+        let res = query_periodic_allowance(deps.as_ref(), env, spender.to_string()).unwrap();
+        assert_eq!(res.spendable, NativeBalance(coins(500, "ujuno")));
+
+        // an empty balance removes it
+        let msg = ExecuteMsg::SetPeriodicAllowance {
+            spender: spender.to_string(),
+            balance: NativeBalance(vec![]),
+            period: Duration::Time(3600),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query_periodic_allowance(deps.as_ref(), mock_env(), spender.to_string()).unwrap();
+        assert_eq!(res.allowance, None);
+        assert_eq!(res.spendable, NativeBalance::default());
+    }
+
+    #[test]
+    fn periodic_and_one_shot_allowances() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let spender = "spender0001";
+        let info = mock_info(owner, &[]);
+        // a one-shot allowance of 1000 and 500 per 10 blocks
+        setup_test_case(
+            deps.as_mut(),
+            &info,
+            &[owner],
+            &[spender],
+            &coins(1000, "ujuno"),
+            &[Expiration::Never {}],
+        );
+        let msg = ExecuteMsg::SetPeriodicAllowance {
+            spender: spender.to_string(),
+            balance: NativeBalance(coins(500, "ujuno")),
+            period: Duration::Height(10),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let start = mock_env().block.height;
+        let spend = |amount| ExecuteMsg::Execute {
+            msgs: vec![send_msg(amount, "ujuno")],
+        };
+        let one_shot = |deps: Deps| {
+            query_allowance(deps, mock_env(), spender.to_string())
+                .unwrap()
+                .balance
+        };
+        let periodic = |deps: Deps, env: Env| {
+            query_periodic_allowance(deps, env, spender.to_string())
+                .unwrap()
+                .spendable
+        };
+
+        // the periodic allowance is used first
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender, &[]),
+            spend(400),
+        )
+        .unwrap();
+        assert_eq!(one_shot(deps.as_ref()), NativeBalance(coins(1000, "ujuno")));
+        assert_eq!(
+            periodic(deps.as_ref(), mock_env()),
+            NativeBalance(coins(100, "ujuno"))
+        );
+
+        // it does not cover this one, so it all comes from the one-shot allowance
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender, &[]),
+            spend(400),
+        )
+        .unwrap();
+        assert_eq!(one_shot(deps.as_ref()), NativeBalance(coins(600, "ujuno")));
+        assert_eq!(
+            periodic(deps.as_ref(), mock_env()),
+            NativeBalance(coins(100, "ujuno"))
+        );
+
+        // amounts are not combined from both
+        let can_send = |deps: Deps, env: Env, amount| {
+            query_can_execute(deps, env, spender.to_string(), send_msg(amount, "ujuno"))
+                .unwrap()
+                .can_execute
+        };
+        assert!(can_send(deps.as_ref(), mock_env(), 600));
+        assert!(!can_send(deps.as_ref(), mock_env(), 700));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender, &[]),
+            spend(700),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        // the next period refills only the periodic allowance
+        let next = mock_env_at_height(start + 10);
+        assert!(can_send(deps.as_ref(), next.clone(), 600));
+        execute(
+            deps.as_mut(),
+            next.clone(),
+            mock_info(spender, &[]),
+            spend(500),
+        )
+        .unwrap();
+        assert_eq!(one_shot(deps.as_ref()), NativeBalance(coins(600, "ujuno")));
+        assert_eq!(periodic(deps.as_ref(), next), NativeBalance::default());
+    }
+
+    // tests permissions and allowances are independent features and does not affect each other
+    #[test]
+    fn permissions_allowances_independent() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let admins = vec![owner.to_string()];
+
+        // spender1 has every permission to stake
+        let spender1 = "spender0001";
+        let spender2 = "spender0002";
+        let denom = "token1";
+        let amount = 10000;
+        let coin = coin(amount, denom);
+
+        let allow = AllowanceResponse {
+            balance: NativeBalance(vec![coin.clone()]),
+            denoms: denoms(&[(coin.clone(), Expiration::Never {})]),
+        };
+        let perm = Permissions {
+            delegate: true,
             redelegate: false,
             undelegate: false,
             withdraw: true,
@@ -1396,6 +3204,8 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins,
             mutable: true,
+            freeze_delay: None,
+            unfreezable: false,
         };
         instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
 
@@ -1403,6 +3213,7 @@ mod tests {
         let setup_perm_msg = ExecuteMsg::SetPermissions {
             spender: spender1.to_string(),
             permissions: perm,
+            expires: None,
         };
         execute(deps.as_mut(), mock_env(), info.clone(), setup_perm_msg).unwrap();
 
@@ -1413,9 +3224,9 @@ mod tests {
         };
         execute(deps.as_mut(), mock_env(), info.clone(), setup_allowance_msg).unwrap();
 
-        let res_perm = query_permissions(deps.as_ref(), spender1.to_string()).unwrap();
+        let res_perm = query_permissions(deps.as_ref(), mock_env(), spender1.to_string()).unwrap();
         assert_eq!(perm, res_perm);
-        let res_allow = query_allowance(deps.as_ref(), spender1.to_string()).unwrap();
+        let res_allow = query_allowance(deps.as_ref(), mock_env(), spender1.to_string()).unwrap();
         assert_eq!(allow, res_allow);
 
         // setup allowance and then permission and check if changed
@@ -1429,12 +3240,13 @@ mod tests {
         let setup_perm_msg = ExecuteMsg::SetPermissions {
             spender: spender2.to_string(),
             permissions: perm,
+            expires: None,
         };
         execute(deps.as_mut(), mock_env(), info, setup_perm_msg).unwrap();
 
-        let res_perm = query_permissions(deps.as_ref(), spender2.to_string()).unwrap();
+        let res_perm = query_permissions(deps.as_ref(), mock_env(), spender2.to_string()).unwrap();
         assert_eq!(perm, res_perm);
-        let res_allow = query_allowance(deps.as_ref(), spender2.to_string()).unwrap();
+        let res_allow = query_allowance(deps.as_ref(), mock_env(), spender2.to_string()).unwrap();
         assert_eq!(allow, res_allow);
     }
 
@@ -1465,7 +3277,11 @@ mod tests {
         };
 
         let spender_addr = Addr::unchecked(spender);
-        let _ = PERMISSIONS.save(&mut deps.storage, &spender_addr, &perm);
+        let grant = PermissionsGrant {
+            permissions: perm,
+            expires: Expiration::Never {},
+        };
+        let _ = PERMISSIONS.save(&mut deps.storage, &spender_addr, &grant);
 
         // let us make some queries... different msg types by owner and by other
         let send_msg = CosmosMsg::Bank(BankMsg::Send {
@@ -1486,14 +3302,26 @@ mod tests {
             });
 
         // owner can send big or small
-        let res = query_can_execute(deps.as_ref(), owner.to_string(), send_msg.clone()).unwrap();
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            owner.to_string(),
+            send_msg.clone(),
+        )
+        .unwrap();
         assert_eq!(res.can_execute, true);
-        let res =
-            query_can_execute(deps.as_ref(), owner.to_string(), send_msg_large.clone()).unwrap();
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            owner.to_string(),
+            send_msg_large.clone(),
+        )
+        .unwrap();
         assert_eq!(res.can_execute, true);
         // owner can stake
         let res = query_can_execute(
             deps.as_ref(),
+            mock_env(),
             owner.to_string(),
             staking_delegate_msg.clone(),
         )
@@ -1501,15 +3329,27 @@ mod tests {
         assert_eq!(res.can_execute, true);
 
         // spender can send small
-        let res = query_can_execute(deps.as_ref(), spender.to_string(), send_msg.clone()).unwrap();
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            spender.to_string(),
+            send_msg.clone(),
+        )
+        .unwrap();
         assert_eq!(res.can_execute, true);
         // not too big
-        let res =
-            query_can_execute(deps.as_ref(), spender.to_string(), send_msg_large.clone()).unwrap();
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            spender.to_string(),
+            send_msg_large.clone(),
+        )
+        .unwrap();
         assert_eq!(res.can_execute, false);
         // spender can send staking msgs if permissioned
         let res = query_can_execute(
             deps.as_ref(),
+            mock_env(),
             spender.to_string(),
             staking_delegate_msg.clone(),
         )
@@ -1517,6 +3357,7 @@ mod tests {
         assert_eq!(res.can_execute, true);
         let res = query_can_execute(
             deps.as_ref(),
+            mock_env(),
             spender.to_string(),
             staking_withdraw_msg.clone(),
         )
@@ -1524,15 +3365,303 @@ mod tests {
         assert_eq!(res.can_execute, false);
 
         // random person cannot do anything
-        let res = query_can_execute(deps.as_ref(), anyone.to_string(), send_msg).unwrap();
+        let res =
+            query_can_execute(deps.as_ref(), mock_env(), anyone.to_string(), send_msg).unwrap();
         assert_eq!(res.can_execute, false);
-        let res = query_can_execute(deps.as_ref(), anyone.to_string(), send_msg_large).unwrap();
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            anyone.to_string(),
+            send_msg_large,
+        )
+        .unwrap();
         assert_eq!(res.can_execute, false);
-        let res =
-            query_can_execute(deps.as_ref(), anyone.to_string(), staking_delegate_msg).unwrap();
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            anyone.to_string(),
+            staking_delegate_msg,
+        )
+        .unwrap();
         assert_eq!(res.can_execute, false);
-        let res =
-            query_can_execute(deps.as_ref(), anyone.to_string(), staking_withdraw_msg).unwrap();
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            anyone.to_string(),
+            staking_withdraw_msg,
+        )
+        .unwrap();
         assert_eq!(res.can_execute, false);
     }
+
+    #[test]
+    fn allowance_changes_and_spends_have_exact_attributes() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let spender = "spender0001";
+        let token = "cw20token";
+        let info = mock_info(owner, &[]);
+        setup_test_case(deps.as_mut(), &info, &[owner], &[], &[], &[]);
+        let expires = Expiration::AtHeight(20000);
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.to_string(),
+            amount: coin(100, "uatom"),
+            expires: Some(expires),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "increase_allowance"),
+                attr("owner", owner),
+                attr("spender", spender),
+                attr("denomination", "uatom"),
+                attr("amount", "100"),
+                attr("new_total", "100"),
+                attr("expires", "height:20000"),
+            ]
+        );
+
+        // the total adds up, and keeps the expiration if none is given
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.to_string(),
+            amount: coin(250, "uatom"),
+            expires: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(res.attributes[4], attr("amount", "250"));
+        assert_eq!(res.attributes[5], attr("new_total", "350"));
+        assert_eq!(res.attributes[6], attr("expires", "height:20000"));
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.to_string(),
+            amount: coin(40, "ujuno"),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::DecreaseAllowance {
+            spender: spender.to_string(),
+            amount: coin(30, "uatom"),
+            expires: Some(Expiration::Never {}),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "decrease_allowance"),
+                attr("owner", owner),
+                attr("spender", spender),
+                attr("denomination", "uatom"),
+                attr("amount", "30"),
+                attr("new_total", "320"),
+                attr("expires", "never"),
+            ]
+        );
+
+        let perm = Permissions {
+            delegate: true,
+            redelegate: false,
+            undelegate: false,
+            withdraw: true,
+        };
+        let msg = ExecuteMsg::SetPermissions {
+            spender: spender.to_string(),
+            permissions: perm,
+            expires: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_permissions"),
+                attr("owner", owner),
+                attr("spender", spender),
+                attr(
+                    "permissions",
+                    "staking: { delegate: true, redelegate: false, undelegate: false, withdraw: true }"
+                ),
+                attr("expires", "never"),
+            ]
+        );
+
+        let msg = ExecuteMsg::UpdateCw20Tokens {
+            add: vec![token.to_string()],
+            remove: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetCw20Allowance {
+            spender: spender.to_string(),
+            token: token.to_string(),
+            amount: Uint128::new(1000),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // execute reports the total deducted per denom over all messages,
+        // messages covered by permissions deduct nothing
+        let transfer = WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "anyone".to_string(),
+                amount: Uint128::new(75),
+            })
+            .unwrap(),
+            funds: vec![],
+        };
+        let msgs = vec![
+            send_msg(100, "uatom"),
+            BankMsg::Send {
+                to_address: "anyone".to_string(),
+                amount: vec![coin(10, "ujuno"), coin(20, "uatom")],
+            }
+            .into(),
+            transfer.into(),
+            StakingMsg::Delegate {
+                validator: "validator".to_string(),
+                amount: coin(1000, "uatom"),
+            }
+            .into(),
+        ];
+        let msg = ExecuteMsg::Execute { msgs };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(spender, &[]), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "execute"),
+                attr("owner", spender),
+                attr("spent", "120uatom"),
+                attr("spent", "10ujuno"),
+                attr("spent_cw20", "75cw20token"),
+            ]
+        );
+
+        // nothing spent, so nothing reported
+        let msg = ExecuteMsg::Execute {
+            msgs: vec![StakingMsg::Delegate {
+                validator: "validator".to_string(),
+                amount: coin(1000, "uatom"),
+            }
+            .into()],
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(spender, &[]), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "execute"), attr("owner", spender)]
+        );
+    }
+
+    #[test]
+    fn spend_history_keeps_most_recent() {
+        let mut deps = mock_dependencies(&[]);
+
+        let owner = "admin0001";
+        let spender = "spender0001";
+        let info = mock_info(owner, &[]);
+        setup_test_case(
+            deps.as_mut(),
+            &info,
+            &[owner],
+            &[spender],
+            &[coin(1000, "uatom")],
+            &[Expiration::Never {}],
+        );
+
+        let spend = |deps: DepsMut, height: u64, amount: u128| {
+            let msg = ExecuteMsg::Execute {
+                msgs: vec![send_msg(amount, "uatom")],
+            };
+            execute(
+                deps,
+                mock_env_at_height(height),
+                mock_info(spender, &[]),
+                msg,
+            )
+            .unwrap();
+        };
+        let record = |height: u64, amount: u128| SpendRecord {
+            height,
+            time: mock_env().block.time,
+            native: NativeBalance(coins(amount, "uatom")),
+            cw20: vec![],
+        };
+        let history = |deps: Deps| query_spend_history(deps, spender.to_string()).unwrap();
+
+        // nothing spent yet
+        assert_eq!(
+            history(deps.as_ref()),
+            SpendHistoryResponse {
+                retention: DEFAULT_SPEND_HISTORY_RETENTION,
+                records: vec![],
+            }
+        );
+
+        // only admins can set the retention, up to the max
+        let msg = ExecuteMsg::SetSpendHistoryRetention { retention: 3 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_spend_history_retention"),
+                attr("owner", owner),
+                attr("retention", "3"),
+            ]
+        );
+        let msg = ExecuteMsg::SetSpendHistoryRetention {
+            retention: MAX_SPEND_HISTORY_RETENTION + 1,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::RetentionTooLarge {
+                max: MAX_SPEND_HISTORY_RETENTION
+            }
+        );
+
+        // the oldest records are dropped beyond the retention
+        for i in 1..=5 {
+            spend(deps.as_mut(), 100 + i, i as u128 * 10);
+        }
+        let res = history(deps.as_ref());
+        assert_eq!(res.retention, 3);
+        assert_eq!(
+            res.records,
+            vec![record(103, 30), record(104, 40), record(105, 50)]
+        );
+        assert_eq!(
+            SPEND_RECORDS
+                .prefix(&Addr::unchecked(spender))
+                .range(&deps.storage, None, None, Order::Ascending)
+                .count(),
+            3
+        );
+
+        // lowering the retention hides the older records right away
+        let msg = ExecuteMsg::SetSpendHistoryRetention { retention: 1 };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(history(deps.as_ref()).records, vec![record(105, 50)]);
+        spend(deps.as_mut(), 106, 60);
+        assert_eq!(history(deps.as_ref()).records, vec![record(106, 60)]);
+        assert_eq!(
+            SPEND_RECORDS
+                .prefix(&Addr::unchecked(spender))
+                .range(&deps.storage, None, None, Order::Ascending)
+                .count(),
+            1
+        );
+
+        // other subkeys have their own history
+        let res = query_spend_history(deps.as_ref(), "spender0002".to_string()).unwrap();
+        assert_eq!(res.records, vec![]);
+    }
 }
@@ -9,6 +9,21 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Cannot remove the last admin")]
+    LastAdmin {},
+
+    #[error("A freeze is already scheduled")]
+    FreezePending {},
+
+    #[error("No freeze is scheduled")]
+    NoPendingFreeze {},
+
+    #[error("Contract cannot be unfrozen")]
+    NotUnfreezable {},
+
+    #[error("Contract is not frozen")]
+    NotFrozen {},
+
     #[error("Cannot set to own account")]
     CannotSetOwnAccount {},
 
@@ -18,6 +33,15 @@ pub enum ContractError {
     #[error("No allowance for this account")]
     NoAllowance {},
 
+    #[error("Allowance is expired")]
+    Expired {},
+
+    #[error("Cw20 token {token} is not allowed")]
+    Cw20NotAllowed { token: String },
+
+    #[error("Allowance period cannot be zero")]
+    ZeroPeriod {},
+
     #[error("Message type rejected")]
     MessageTypeRejected {},
 
@@ -35,6 +59,12 @@ pub enum ContractError {
 
     #[error("Set withdraw address is not allowed")]
     WithdrawAddrPerm {},
+
+    #[error("Spend history retention cannot be more than {max}")]
+    RetentionTooLarge { max: u32 },
+
+    #[error("Cannot migrate from different contract type: {previous_contract}")]
+    CannotMigrate { previous_contract: String },
 }
 
 impl From<cw1_whitelist::ContractError> for ContractError {
@@ -42,6 +72,11 @@ impl From<cw1_whitelist::ContractError> for ContractError {
         match err {
             cw1_whitelist::ContractError::Std(error) => ContractError::Std(error),
             cw1_whitelist::ContractError::Unauthorized {} => ContractError::Unauthorized {},
+            cw1_whitelist::ContractError::LastAdmin {} => ContractError::LastAdmin {},
+            cw1_whitelist::ContractError::FreezePending {} => ContractError::FreezePending {},
+            cw1_whitelist::ContractError::NoPendingFreeze {} => ContractError::NoPendingFreeze {},
+            cw1_whitelist::ContractError::NotUnfreezable {} => ContractError::NotUnfreezable {},
+            cw1_whitelist::ContractError::NotFrozen {} => ContractError::NotFrozen {},
         }
     }
 }
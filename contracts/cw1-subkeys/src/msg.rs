@@ -2,10 +2,13 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-use cosmwasm_std::{Coin, CosmosMsg, Empty};
-use cw0::{Expiration, NativeBalance};
+use cosmwasm_std::{Coin, CosmosMsg, Empty, Uint128};
+use cw0::{Duration, Expiration, NativeBalance, QueryOrder};
 
-use crate::state::Permissions;
+use crate::state::{PeriodicAllowance, Permissions, SpendRecord};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -17,13 +20,27 @@ where
     /// contract's address as sender. Every implementation has it's own logic to
     /// determine in
     Execute { msgs: Vec<CosmosMsg<T>> },
-    /// Freeze will make a mutable contract immutable, must be called by an admin
+    /// Freeze will make a mutable contract immutable, must be called by an admin.
+    /// If the contract has a freeze_delay, this only happens once the delay passed
     Freeze {},
+    /// CancelFreeze cancels a scheduled freeze before it takes effect, must be called by an admin
+    CancelFreeze {},
+    /// Unfreeze makes a frozen contract mutable again, must be called by an admin
+    /// and only works if the contract was instantiated as unfreezable
+    Unfreeze {},
     /// UpdateAdmins will change the admin set of the contract, must be called by an existing admin,
     /// and only works if the contract is mutable
     UpdateAdmins { admins: Vec<String> },
+    /// AddAdmins adds the given addresses to the admin set, skipping the ones that already are
+    /// admins. Must be called by an existing admin, and only works if the contract is mutable
+    AddAdmins { addrs: Vec<String> },
+    /// RemoveAdmins removes the given addresses from the admin set, skipping the ones that are
+    /// not admins. Must be called by an existing admin, and only works if the contract is mutable.
+    /// Fails if it would remove all admins
+    RemoveAdmins { addrs: Vec<String> },
 
-    /// Add an allowance to a given subkey (subkey must not be admin)
+    /// Add an allowance to a given subkey (subkey must not be admin).
+    /// Every denom has its own allowance, expires only applies to the one of amount
     IncreaseAllowance {
         spender: String,
         amount: Coin,
@@ -36,11 +53,44 @@ where
         expires: Option<Expiration>,
     },
 
-    // Setups up permissions for a given subkey.
+    /// Gives a subkey (which must not be admin) an allowance of balance per period, which is
+    /// refilled at the end of every period. This replaces any previous periodic allowance and
+    /// starts the first period now, an empty balance removes it.
+    /// The periodic allowance is spent first, the other one is only used
+    /// if the periodic allowance does not cover a message.
+    SetPeriodicAllowance {
+        spender: String,
+        balance: NativeBalance,
+        period: Duration,
+    },
+
+    /// Updates the cw20 token contracts that subkeys can get allowances for, must be called
+    /// by an admin. Transfer and Send messages to these contracts are allowed if covered by
+    /// the cw20 allowance of the subkey.
+    UpdateCw20Tokens {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Sets the allowance of a subkey (subkey must not be admin) for an allowed cw20 token,
+    /// replacing the previous one. A zero amount removes it.
+    SetCw20Allowance {
+        spender: String,
+        token: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+
+    // Setups up permissions for a given subkey, replacing any previous ones.
+    // They never expire unless expires is set.
     SetPermissions {
         spender: String,
         permissions: Permissions,
+        expires: Option<Expiration>,
     },
+
+    /// Sets how many spend records are kept per subkey (10 by default), must be called
+    /// by an admin. Lowering it drops the older records of a subkey on its next spend.
+    SetSpendHistoryRetention { retention: u32 },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -52,28 +102,49 @@ where
     /// Shows all admins and whether or not it is mutable
     /// Returns cw1-whitelist::AdminListResponse
     AdminList {},
+    /// Shows when a scheduled freeze takes effect, if there is one.
+    /// Returns cw1_whitelist::msg::PendingFreezeResponse
+    PendingFreeze {},
     /// Get the current allowance for the given subkey (how much it can spend)
-    /// Returns crate::state::Allowance
+    /// Returns AllowanceResponse
     Allowance { spender: String },
-    /// Get the current permissions for the given subkey (how much it can spend)
-    /// Returns PermissionsInfo
+    /// Get the periodic allowance for the given subkey, and how much of it can be spent now
+    /// Returns PeriodicAllowanceResponse
+    PeriodicAllowance { spender: String },
+    /// Get the current allowance of the given subkey for the given cw20 token
+    /// Returns cw20::AllowanceResponse
+    Cw20Allowance { spender: String, token: String },
+    /// Lists the cw20 token contracts that subkeys can get allowances for
+    /// Returns Cw20TokensResponse
+    Cw20Tokens {},
+    /// Get the current permissions for the given subkey, none if they expired
+    /// Returns crate::state::Permissions
     Permissions { spender: String },
     /// Checks permissions of the caller on this proxy.
     /// If CanExecute returns true then a call to `Execute` with the same message,
     /// before any further state changes, should also succeed.
     CanExecute { sender: String, msg: CosmosMsg<T> },
-    /// Gets all Allowances for this contract
+    /// Gets all Allowances for this contract, by spender in the given order (ascending by
+    /// default). The allowances of expired denoms are included, unless include_expired is false.
     /// Returns AllAllowancesResponse
     AllAllowances {
         start_after: Option<String>,
         limit: Option<u32>,
+        include_expired: Option<bool>,
+        order: Option<QueryOrder>,
     },
-    /// Gets all Permissions for this contract
+    /// Gets all Permissions for this contract, by spender in the given order (ascending by
+    /// default). Expired ones are only returned if include_expired is set.
     /// Returns AllPermissionsResponse
     AllPermissions {
         start_after: Option<String>,
         limit: Option<u32>,
+        include_expired: Option<bool>,
+        order: Option<QueryOrder>,
     },
+    /// Get what the given subkey spent in its most recent executions, oldest first
+    /// Returns SpendHistoryResponse
+    SpendHistory { spender: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -81,20 +152,58 @@ pub struct AllAllowancesResponse {
     pub allowances: Vec<AllowanceInfo>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomAllowanceInfo {
+    pub denom: String,
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct AllowanceResponse {
+    /// What can be spent now, that is the allowances of all denoms that did not expire
+    pub balance: NativeBalance,
+    /// The allowance of every denom, also of the expired ones
+    pub denoms: Vec<DenomAllowanceInfo>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct AllowanceInfo {
     pub spender: String,
+    /// What can be spent now, that is the allowances of all denoms that did not expire
     pub balance: NativeBalance,
-    pub expires: Expiration,
+    pub denoms: Vec<DenomAllowanceInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PeriodicAllowanceResponse {
+    /// The allowance as stored, None if there is none
+    pub allowance: Option<PeriodicAllowance>,
+    /// What can be spent at the current block, taking the refills of ended periods into account
+    pub spendable: NativeBalance,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20TokensResponse {
+    pub tokens: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PermissionsInfo {
     pub spender: String,
     pub permissions: Permissions,
+    pub expires: Expiration,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct AllPermissionsResponse {
     pub permissions: Vec<PermissionsInfo>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SpendHistoryResponse {
+    /// How many records are kept per subkey
+    pub retention: u32,
+    /// Oldest first, at most retention of them
+    pub records: Vec<SpendRecord>,
+}
@@ -2,9 +2,10 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-use cosmwasm_std::Addr;
-use cw0::{Expiration, NativeBalance};
-use cw_storage_plus::Map;
+use cosmwasm_std::{Addr, BlockInfo, Empty, StdError, StdResult, Storage, Timestamp, Uint128};
+use cw0::{Duration, Expiration, NativeBalance};
+use cw20::Cw20CoinVerified;
+use cw_storage_plus::{Item, Map, U64Key};
 
 // Permissions struct defines users message execution permissions.
 // Could have implemented permissions for each cosmos module(StakingPermissions, GovPermissions etc...)
@@ -28,11 +29,153 @@ impl fmt::Display for Permissions {
     }
 }
 
+/// The allowance of a subkey for one denom, which can no longer be spent once it expired
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct DenomAllowance {
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+/// How allowances were stored before every denom had its own expiration.
+/// This is only read by migrate, to convert them into DenomAllowances
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
 pub struct Allowance {
     pub balance: NativeBalance,
     pub expires: Expiration,
 }
 
-pub const PERMISSIONS: Map<&Addr, Permissions> = Map::new("permissions");
-pub const ALLOWANCES: Map<&Addr, Allowance> = Map::new("allowances");
+/// An allowance that is refilled to `per_period` whenever a period ends.
+/// Whatever was not spent in a period does not carry over to the next one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PeriodicAllowance {
+    pub per_period: NativeBalance,
+    /// What is left in the period ending at period_end. This is only updated when spending,
+    /// so it is out of date once the period ended (see `spendable`)
+    pub remaining: NativeBalance,
+    pub period: Duration,
+    pub period_end: Expiration,
+}
+
+impl PeriodicAllowance {
+    /// Resets remaining if the period ended, and moves period_end forward by as many
+    /// periods as needed to be in the future again (as some periods may have been missed)
+    pub fn refill(&mut self, block: &BlockInfo) -> StdResult<()> {
+        if !self.period_end.is_expired(block) {
+            return Ok(());
+        }
+        let elapsed = match (self.period_end, self.period) {
+            (Expiration::AtHeight(end), Duration::Height(period)) if period > 0 => {
+                (block.height - end) / period
+            }
+            (Expiration::AtTime(end), Duration::Time(period)) if period > 0 => {
+                (block.time.seconds() - end.seconds()) / period
+            }
+            _ => return Err(StdError::generic_err("Invalid allowance period")),
+        };
+        // the period ending at period_end is over as well
+        self.period_end = (self.period_end + self.period.checked_mul(elapsed + 1)?)?;
+        self.remaining = self.per_period.clone();
+        Ok(())
+    }
+
+    /// Returns what can be spent at the given block, after refilling if the period ended
+    pub fn spendable(&self, block: &BlockInfo) -> StdResult<NativeBalance> {
+        let mut current = self.clone();
+        current.refill(block)?;
+        Ok(current.remaining)
+    }
+}
+
+/// The permissions granted to a subkey, which are treated as absent once they expire
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PermissionsGrant {
+    pub permissions: Permissions,
+    pub expires: Expiration,
+}
+
+pub const PERMISSIONS: Map<&Addr, PermissionsGrant> = Map::new("permissions");
+/// How permissions were stored before they could expire, in the same namespace as PERMISSIONS.
+/// This is only read by migrate, to convert them into PermissionsGrants
+pub const LEGACY_PERMISSIONS: Map<&Addr, Permissions> = Map::new("permissions");
+pub const LEGACY_ALLOWANCES: Map<&Addr, Allowance> = Map::new("allowances");
+/// The allowances by spender and denom
+pub const ALLOWANCES: Map<(&Addr, &str), DenomAllowance> = Map::new("denom_allowances");
+/// The cw20 token contracts that subkeys can spend allowances of
+pub const CW20_TOKENS: Map<&Addr, Empty> = Map::new("cw20_tokens");
+/// The cw20 allowances by spender and token contract
+pub const CW20_ALLOWANCES: Map<(&Addr, &Addr), DenomAllowance> = Map::new("cw20_allowances");
+pub const PERIODIC_ALLOWANCES: Map<&Addr, PeriodicAllowance> = Map::new("periodic_allowances");
+
+/// What a subkey spent in one Execute, over all of its messages
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SpendRecord {
+    pub height: u64,
+    pub time: Timestamp,
+    pub native: NativeBalance,
+    /// by token contract, in the order they were first spent
+    pub cw20: Vec<Cw20CoinVerified>,
+}
+
+impl SpendRecord {
+    pub fn new(block: &BlockInfo) -> Self {
+        SpendRecord {
+            height: block.height,
+            time: block.time,
+            native: NativeBalance::default(),
+            cw20: vec![],
+        }
+    }
+
+    pub fn add_cw20(&mut self, address: &Addr, amount: Uint128) {
+        match self.cw20.iter_mut().find(|c| &c.address == address) {
+            Some(coin) => coin.amount += amount,
+            None => self.cw20.push(Cw20CoinVerified {
+                address: address.clone(),
+                amount,
+            }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.native.is_empty() && self.cw20.is_empty()
+    }
+}
+
+/// The records of spender are kept under the indexes first..next, oldest first
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct SpendLog {
+    pub first: u64,
+    pub next: u64,
+}
+
+pub const DEFAULT_SPEND_HISTORY_RETENTION: u32 = 10;
+pub const MAX_SPEND_HISTORY_RETENTION: u32 = 100;
+
+/// How many spend records are kept per subkey, DEFAULT_SPEND_HISTORY_RETENTION if not set
+pub const SPEND_HISTORY_RETENTION: Item<u32> = Item::new("spend_history_retention");
+pub const SPEND_LOGS: Map<&Addr, SpendLog> = Map::new("spend_logs");
+pub const SPEND_RECORDS: Map<(&Addr, U64Key), SpendRecord> = Map::new("spend_records");
+
+pub fn load_spend_history_retention(storage: &dyn Storage) -> StdResult<u32> {
+    Ok(SPEND_HISTORY_RETENTION
+        .may_load(storage)?
+        .unwrap_or(DEFAULT_SPEND_HISTORY_RETENTION))
+}
+
+/// Appends record to the spend history of spender, and drops the oldest records
+/// so at most retention are kept
+pub fn push_spend_record(
+    storage: &mut dyn Storage,
+    spender: &Addr,
+    record: &SpendRecord,
+    retention: u32,
+) -> StdResult<()> {
+    let mut log = SPEND_LOGS.may_load(storage, spender)?.unwrap_or_default();
+    SPEND_RECORDS.save(storage, (spender, log.next.into()), record)?;
+    log.next += 1;
+    while log.next - log.first > retention as u64 {
+        SPEND_RECORDS.remove(storage, (spender, log.first.into()));
+        log.first += 1;
+    }
+    SPEND_LOGS.save(storage, spender, &log)
+}
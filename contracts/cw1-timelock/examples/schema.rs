@@ -0,0 +1,26 @@
+use std::env::current_dir;
+use std::fs::create_dir_all;
+
+use cosmwasm_schema::{export_schema, export_schema_with_title, remove_schemas, schema_for};
+
+use cw1::CanExecuteResponse;
+use cw1_timelock::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, OperationListResponse, OperationResponse, QueryMsg,
+};
+use cw1_whitelist::msg::AdminListResponse;
+
+fn main() {
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    create_dir_all(&out_dir).unwrap();
+    remove_schemas(&out_dir).unwrap();
+
+    export_schema(&schema_for!(InstantiateMsg), &out_dir);
+    export_schema_with_title(&schema_for!(ExecuteMsg), &out_dir, "ExecuteMsg");
+    export_schema_with_title(&schema_for!(QueryMsg), &out_dir, "QueryMsg");
+    export_schema(&schema_for!(AdminListResponse), &out_dir);
+    export_schema(&schema_for!(CanExecuteResponse), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(OperationResponse), &out_dir);
+    export_schema(&schema_for!(OperationListResponse), &out_dir);
+}
@@ -0,0 +1,313 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    attr, to_binary, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response,
+    StdResult, SubMsg,
+};
+
+use cw0::Duration;
+use cw1::CanExecuteResponse;
+use cw1_whitelist::contract::{instantiate as whitelist_instantiate, query_admin_list};
+use cw1_whitelist::state::ADMIN_LIST;
+use cw2::set_contract_version;
+use cw_storage_plus::{Bound, U64Key};
+
+use crate::error::ContractError;
+use crate::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, OperationListResponse, OperationResponse, QueryMsg,
+};
+use crate::state::{next_id, Config, Operation, CONFIG, OPERATIONS};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:cw1-timelock";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    // nobody could react to operations without a delay
+    if let Duration::Height(0) | Duration::Time(0) = msg.min_delay {
+        return Err(ContractError::ZeroMinDelay {});
+    }
+    // the admins cannot be changed, as that would bypass the delay
+    let whitelist = cw1_whitelist::msg::InstantiateMsg {
+        admins: msg.admins,
+        mutable: false,
+        freeze_delay: None,
+        unfreezable: false,
+    };
+    let res = whitelist_instantiate(deps.branch(), env, info, whitelist)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let cfg = Config {
+        min_delay: msg.min_delay,
+    };
+    CONFIG.save(deps.storage, &cfg)?;
+    Ok(res)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response<Empty>, ContractError> {
+    match msg {
+        ExecuteMsg::Schedule { msgs, delay } => execute_schedule(deps, env, info, msgs, delay),
+        ExecuteMsg::Execute { id } => execute_execute(deps, env, info, id),
+        ExecuteMsg::Cancel { id } => execute_cancel(deps, env, info, id),
+    }
+}
+
+fn assert_admin(deps: Deps, sender: &str) -> Result<(), ContractError> {
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    if cfg.is_admin(sender) {
+        Ok(())
+    } else {
+        Err(ContractError::Unauthorized {})
+    }
+}
+
+/// Checks the delay is at least min_delay. A delay in blocks cannot be compared to one
+/// in seconds, so they must be of the same kind.
+fn validate_delay(delay: &Duration, min_delay: &Duration) -> Result<(), ContractError> {
+    let long_enough = match (delay, min_delay) {
+        (Duration::Height(delay), Duration::Height(min)) => delay >= min,
+        (Duration::Time(delay), Duration::Time(min)) => delay >= min,
+        _ => false,
+    };
+    if long_enough {
+        Ok(())
+    } else {
+        Err(ContractError::DelayTooShort {
+            min_delay: min_delay.to_string(),
+        })
+    }
+}
+
+pub fn execute_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msgs: Vec<CosmosMsg>,
+    delay: Option<Duration>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), info.sender.as_ref())?;
+    if msgs.is_empty() {
+        return Err(ContractError::NoMessages {});
+    }
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let delay = delay.unwrap_or(cfg.min_delay);
+    validate_delay(&delay, &cfg.min_delay)?;
+
+    let op = Operation {
+        msgs,
+        executable_at: delay.after(&env.block),
+        scheduled_by: info.sender,
+    };
+    let id = next_id(deps.storage)?;
+    OPERATIONS.save(deps.storage, id.into(), &op)?;
+
+    Ok(Response {
+        attributes: vec![
+            attr("action", "schedule"),
+            attr("sender", op.scheduled_by),
+            attr("operation_id", id),
+            attr("executable_at", op.executable_at),
+        ],
+        ..Response::default()
+    })
+}
+
+pub fn execute_execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), info.sender.as_ref())?;
+
+    let op = OPERATIONS.load(deps.storage, id.into())?;
+    if !op.is_executable(&env.block) {
+        return Err(ContractError::NotExecutable {
+            executable_at: op.executable_at,
+        });
+    }
+    // removed first, so it can never run twice
+    OPERATIONS.remove(deps.storage, id.into());
+
+    Ok(Response {
+        messages: op.msgs.into_iter().map(SubMsg::new).collect(),
+        attributes: vec![
+            attr("action", "execute"),
+            attr("sender", info.sender),
+            attr("operation_id", id),
+        ],
+        ..Response::default()
+    })
+}
+
+pub fn execute_cancel(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), info.sender.as_ref())?;
+
+    // fails if it does not exist, or was already executed
+    OPERATIONS.load(deps.storage, id.into())?;
+    OPERATIONS.remove(deps.storage, id.into());
+
+    Ok(Response {
+        attributes: vec![
+            attr("action", "cancel"),
+            attr("sender", info.sender),
+            attr("operation_id", id),
+        ],
+        ..Response::default()
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::AdminList {} => to_binary(&query_admin_list(deps, env)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::CanExecute { sender, msg } => to_binary(&query_can_execute(deps, sender, msg)?),
+        QueryMsg::Operation { id } => to_binary(&query_operation(deps, env, id)?),
+        QueryMsg::ListOperations { start_after, limit } => {
+            to_binary(&list_operations(deps, env, start_after, limit)?)
+        }
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        min_delay: cfg.min_delay,
+    })
+}
+
+pub fn query_can_execute(
+    deps: Deps,
+    sender: String,
+    _msg: CosmosMsg,
+) -> StdResult<CanExecuteResponse> {
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    Ok(CanExecuteResponse {
+        can_execute: cfg.is_admin(&sender),
+    })
+}
+
+fn map_operation(env: &Env, id: u64, op: Operation) -> OperationResponse {
+    OperationResponse {
+        id,
+        executable: op.is_executable(&env.block),
+        msgs: op.msgs,
+        executable_at: op.executable_at,
+        scheduled_by: op.scheduled_by.into(),
+    }
+}
+
+pub fn query_operation(deps: Deps, env: Env, id: u64) -> StdResult<OperationResponse> {
+    let op = OPERATIONS.load(deps.storage, id.into())?;
+    Ok(map_operation(&env, id, op))
+}
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn list_operations(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<OperationListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(U64Key::from(id)));
+    let operations = OPERATIONS
+        .range_de(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, op)| map_operation(&env, id, op)))
+        .collect::<StdResult<_>>()?;
+    Ok(OperationListResponse { operations })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, BankMsg};
+
+    #[test]
+    fn delay_must_be_long_enough() {
+        let min = Duration::Height(10);
+        validate_delay(&Duration::Height(10), &min).unwrap();
+        validate_delay(&Duration::Height(11), &min).unwrap();
+        let err = validate_delay(&Duration::Height(9), &min).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DelayTooShort {
+                min_delay: "height:10".to_string()
+            }
+        );
+
+        // blocks and seconds cannot be compared
+        let err = validate_delay(&Duration::Time(1_000_000), &min).unwrap_err();
+        assert!(matches!(err, ContractError::DelayTooShort { .. }));
+        let min = Duration::Time(3600);
+        validate_delay(&Duration::Time(3600), &min).unwrap();
+        validate_delay(&Duration::Height(1_000_000), &min).unwrap_err();
+    }
+
+    #[test]
+    fn min_delay_cannot_be_zero() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("alice", &[]);
+        for min_delay in [Duration::Height(0), Duration::Time(0)].iter().copied() {
+            let msg = InstantiateMsg {
+                admins: vec!["alice".to_string()],
+                min_delay,
+            };
+            let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+            assert_eq!(err, ContractError::ZeroMinDelay {});
+        }
+
+        let msg = InstantiateMsg {
+            admins: vec!["alice".to_string()],
+            min_delay: Duration::Height(1),
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn can_execute_for_admins() {
+        let mut deps = mock_dependencies(&[]);
+        let msg = InstantiateMsg {
+            admins: vec!["alice".to_string(), "bob".to_string()],
+            min_delay: Duration::Time(3600),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+        let send: CosmosMsg = BankMsg::Send {
+            to_address: "carl".to_string(),
+            amount: coins(1000, "BTC"),
+        }
+        .into();
+        let can_execute = |sender: &str| {
+            query_can_execute(deps.as_ref(), sender.to_string(), send.clone())
+                .unwrap()
+                .can_execute
+        };
+        assert!(can_execute("alice"));
+        assert!(can_execute("bob"));
+        assert!(!can_execute("carl"));
+    }
+}
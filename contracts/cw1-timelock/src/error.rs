@@ -0,0 +1,25 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+use cw0::Expiration;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("The minimum delay cannot be zero")]
+    ZeroMinDelay {},
+
+    #[error("Delay must be at least the minimum delay of {min_delay}")]
+    DelayTooShort { min_delay: String },
+
+    #[error("Cannot schedule an empty operation")]
+    NoMessages {},
+
+    #[error("Operation cannot be executed before {executable_at}")]
+    NotExecutable { executable_at: Expiration },
+}
@@ -0,0 +1,236 @@
+#![cfg(test)]
+
+use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
+use cosmwasm_std::{coins, Addr, BankMsg, CosmosMsg, Empty};
+use cw0::{Duration, Expiration};
+use cw_multi_test::{App, Contract, ContractWrapper, SimpleBank};
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, OperationListResponse, OperationResponse, QueryMsg};
+
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const CARL: &str = "carl";
+
+// one hour
+const DELAY: u64 = 3600;
+
+fn mock_app() -> App {
+    let env = mock_env();
+    let api = Box::new(MockApi::default());
+    let bank = SimpleBank {};
+
+    App::new(api, env.block, bank, || Box::new(MockStorage::new()))
+}
+
+pub fn contract_timelock() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    );
+    Box::new(contract)
+}
+
+// instantiates the timelock with alice and bob as admins, holding 1000 BTC
+fn setup_timelock(app: &mut App) -> Addr {
+    let code_id = app.store_code(contract_timelock());
+    let msg = InstantiateMsg {
+        admins: vec![ALICE.to_string(), BOB.to_string()],
+        min_delay: Duration::Time(DELAY),
+    };
+    let addr = app
+        .instantiate_contract(code_id, Addr::unchecked(ALICE), &msg, &[], "Timelock")
+        .unwrap();
+    app.set_bank_balance(&addr, coins(1000, "BTC")).unwrap();
+    addr
+}
+
+fn pay_carl(amount: u128) -> CosmosMsg {
+    BankMsg::Send {
+        to_address: CARL.to_string(),
+        amount: coins(amount, "BTC"),
+    }
+    .into()
+}
+
+fn schedule(
+    app: &mut App,
+    timelock: &Addr,
+    sender: &str,
+    msgs: Vec<CosmosMsg>,
+    delay: Option<Duration>,
+) -> Result<(), String> {
+    let msg = ExecuteMsg::Schedule { msgs, delay };
+    app.execute_contract(Addr::unchecked(sender), timelock.clone(), &msg, &[])
+        .map(|_| ())
+}
+
+fn execute(app: &mut App, timelock: &Addr, sender: &str, id: u64) -> Result<(), String> {
+    let msg = ExecuteMsg::Execute { id };
+    app.execute_contract(Addr::unchecked(sender), timelock.clone(), &msg, &[])
+        .map(|_| ())
+}
+
+fn pass_seconds(app: &mut App, seconds: u64) {
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(seconds);
+        block.height += 1;
+    });
+}
+
+fn carl_balance(app: &App) -> u128 {
+    app.wrap().query_balance(CARL, "BTC").unwrap().amount.u128()
+}
+
+#[test]
+fn execute_after_delay() {
+    let mut app = mock_app();
+    let timelock = setup_timelock(&mut app);
+
+    // only admins can schedule
+    let err = schedule(&mut app, &timelock, CARL, vec![pay_carl(100)], None).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}.to_string(), err);
+    schedule(&mut app, &timelock, ALICE, vec![pay_carl(100)], None).unwrap();
+
+    let executable_at = Expiration::AtTime(app.block_info().time.plus_seconds(DELAY));
+    let op: OperationResponse = app
+        .wrap()
+        .query_wasm_smart(&timelock, &QueryMsg::Operation { id: 1 })
+        .unwrap();
+    assert_eq!(
+        op,
+        OperationResponse {
+            id: 1,
+            msgs: vec![pay_carl(100)],
+            executable_at,
+            scheduled_by: ALICE.to_string(),
+            executable: false,
+        }
+    );
+
+    // not even an admin can execute it right away, or one second before the delay passed
+    let err = execute(&mut app, &timelock, BOB, 1).unwrap_err();
+    assert_eq!(
+        ContractError::NotExecutable { executable_at }.to_string(),
+        err
+    );
+    pass_seconds(&mut app, DELAY - 1);
+    execute(&mut app, &timelock, BOB, 1).unwrap_err();
+    assert_eq!(carl_balance(&app), 0);
+
+    // once it passed, it can be executed once, but not by anyone
+    pass_seconds(&mut app, 1);
+    let err = execute(&mut app, &timelock, CARL, 1).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}.to_string(), err);
+    execute(&mut app, &timelock, BOB, 1).unwrap();
+    assert_eq!(carl_balance(&app), 100);
+    execute(&mut app, &timelock, BOB, 1).unwrap_err();
+    assert_eq!(carl_balance(&app), 100);
+
+    // a failing message reverts the execution, so the operation stays scheduled
+    schedule(&mut app, &timelock, ALICE, vec![pay_carl(5000)], None).unwrap();
+    pass_seconds(&mut app, DELAY);
+    execute(&mut app, &timelock, ALICE, 2).unwrap_err();
+    let op: OperationResponse = app
+        .wrap()
+        .query_wasm_smart(&timelock, &QueryMsg::Operation { id: 2 })
+        .unwrap();
+    assert!(op.executable);
+}
+
+#[test]
+fn delay_override() {
+    let mut app = mock_app();
+    let timelock = setup_timelock(&mut app);
+
+    // shorter delays, or delays in blocks, are rejected
+    let err = schedule(
+        &mut app,
+        &timelock,
+        ALICE,
+        vec![pay_carl(100)],
+        Some(Duration::Time(DELAY - 1)),
+    )
+    .unwrap_err();
+    let too_short = ContractError::DelayTooShort {
+        min_delay: "time:3600".to_string(),
+    };
+    assert_eq!(too_short.to_string(), err);
+    let err = schedule(
+        &mut app,
+        &timelock,
+        ALICE,
+        vec![pay_carl(100)],
+        Some(Duration::Height(1000)),
+    )
+    .unwrap_err();
+    assert_eq!(too_short.to_string(), err);
+    let err = schedule(&mut app, &timelock, ALICE, vec![], None).unwrap_err();
+    assert_eq!(ContractError::NoMessages {}.to_string(), err);
+
+    // a longer one is fine, and only executable once it passed
+    schedule(
+        &mut app,
+        &timelock,
+        ALICE,
+        vec![pay_carl(100), pay_carl(50)],
+        Some(Duration::Time(2 * DELAY)),
+    )
+    .unwrap();
+    pass_seconds(&mut app, DELAY);
+    execute(&mut app, &timelock, ALICE, 1).unwrap_err();
+    pass_seconds(&mut app, DELAY);
+    execute(&mut app, &timelock, ALICE, 1).unwrap();
+    assert_eq!(carl_balance(&app), 150);
+}
+
+#[test]
+fn cancel_and_list() {
+    let mut app = mock_app();
+    let timelock = setup_timelock(&mut app);
+
+    for amount in 1..=3 {
+        schedule(&mut app, &timelock, ALICE, vec![pay_carl(amount)], None).unwrap();
+        pass_seconds(&mut app, 60);
+    }
+
+    // only admins can cancel, and only existing operations
+    let cancel = |id| ExecuteMsg::Cancel { id };
+    let err = app
+        .execute_contract(Addr::unchecked(CARL), timelock.clone(), &cancel(2), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}.to_string(), err);
+    app.execute_contract(Addr::unchecked(BOB), timelock.clone(), &cancel(2), &[])
+        .unwrap();
+    app.execute_contract(Addr::unchecked(BOB), timelock.clone(), &cancel(2), &[])
+        .unwrap_err();
+    app.execute_contract(Addr::unchecked(BOB), timelock.clone(), &cancel(7), &[])
+        .unwrap_err();
+
+    // a cancelled operation can never be executed
+    pass_seconds(&mut app, DELAY);
+    execute(&mut app, &timelock, ALICE, 2).unwrap_err();
+
+    let list = |app: &App, start_after, limit| -> Vec<(u64, bool)> {
+        let msg = QueryMsg::ListOperations { start_after, limit };
+        let res: OperationListResponse = app.wrap().query_wasm_smart(&timelock, &msg).unwrap();
+        res.operations
+            .into_iter()
+            .map(|op| (op.id, op.executable))
+            .collect()
+    };
+    assert_eq!(list(&app, None, None), vec![(1, true), (3, true)]);
+    assert_eq!(list(&app, None, Some(1)), vec![(1, true)]);
+    assert_eq!(list(&app, Some(1), Some(1)), vec![(3, true)]);
+    assert_eq!(list(&app, Some(3), None), vec![]);
+
+    // executed operations are no longer listed
+    execute(&mut app, &timelock, ALICE, 3).unwrap();
+    assert_eq!(list(&app, None, None), vec![(1, true)]);
+    assert_eq!(carl_balance(&app), 3);
+
+    // new ones get a new id, they are not reused
+    schedule(&mut app, &timelock, BOB, vec![pay_carl(4)], None).unwrap();
+    assert_eq!(list(&app, Some(1), None), vec![(4, false)]);
+}
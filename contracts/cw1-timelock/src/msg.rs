@@ -0,0 +1,75 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CosmosMsg, Empty};
+use cw0::{Duration, Expiration};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct InstantiateMsg {
+    pub admins: Vec<String>,
+    /// Scheduled operations can be executed no earlier than this after they were scheduled.
+    /// Cannot be zero
+    pub min_delay: Duration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Schedule stores the messages under a new operation id, to be executed once the delay
+    /// passed. The delay defaults to min_delay, and cannot be shorter.
+    /// Must be called by an admin
+    Schedule {
+        msgs: Vec<CosmosMsg<Empty>>,
+        delay: Option<Duration>,
+    },
+    /// Execute dispatches the messages of a scheduled operation with the contract's address
+    /// as sender, once its delay passed, and removes it. Must be called by an admin
+    Execute { id: u64 },
+    /// Cancel removes a scheduled operation that was not executed yet.
+    /// Must be called by an admin
+    Cancel { id: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Shows all admins, returns cw1_whitelist::msg::AdminListResponse
+    AdminList {},
+    /// Returns ConfigResponse
+    Config {},
+    /// The cw1 query: true if sender is an admin, so may schedule msg. Unlike with other
+    /// cw1 proxies, it is not executed right away but only after the delay.
+    /// Returns cw1::CanExecuteResponse
+    CanExecute {
+        sender: String,
+        msg: CosmosMsg<Empty>,
+    },
+    /// Returns OperationResponse
+    Operation { id: u64 },
+    /// Lists the scheduled operations by ascending id.
+    /// Returns OperationListResponse
+    ListOperations {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ConfigResponse {
+    pub min_delay: Duration,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OperationResponse {
+    pub id: u64,
+    pub msgs: Vec<CosmosMsg<Empty>>,
+    pub executable_at: Expiration,
+    pub scheduled_by: String,
+    /// true once the delay passed
+    pub executable: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OperationListResponse {
+    pub operations: Vec<OperationResponse>,
+}
@@ -0,0 +1,38 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, BlockInfo, CosmosMsg, Empty, StdResult, Storage};
+use cw0::{Duration, Expiration};
+use cw_storage_plus::{Item, Map, U64Key};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Config {
+    /// Scheduled operations can be executed no earlier than this after they were scheduled
+    pub min_delay: Duration,
+}
+
+/// Operation is a batch of messages waiting for its delay to pass
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Operation {
+    pub msgs: Vec<CosmosMsg<Empty>>,
+    pub executable_at: Expiration,
+    /// the admin who scheduled it
+    pub scheduled_by: Addr,
+}
+
+impl Operation {
+    /// returns true once the delay passed
+    pub fn is_executable(&self, block: &BlockInfo) -> bool {
+        self.executable_at.is_expired(block)
+    }
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const OPERATION_COUNT: Item<u64> = Item::new("operation_count");
+pub const OPERATIONS: Map<U64Key, Operation> = Map::new("operations");
+
+pub fn next_id(store: &mut dyn Storage) -> StdResult<u64> {
+    let id: u64 = OPERATION_COUNT.may_load(store)?.unwrap_or_default() + 1;
+    OPERATION_COUNT.save(store, &id)?;
+    Ok(id)
+}
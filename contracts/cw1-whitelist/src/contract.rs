@@ -4,16 +4,19 @@ use std::fmt;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Addr, Api, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo,
-    Response, StdResult, SubMsg,
+    attr, to_binary, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdResult, SubMsg,
 };
 
+use cw0::{validate_addresses, EventBuilder};
 use cw1::CanExecuteResponse;
 use cw2::set_contract_version;
 
 use crate::error::ContractError;
-use crate::msg::{AdminListResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{AdminList, ADMIN_LIST};
+use crate::msg::{AdminListResponse, ExecuteMsg, InstantiateMsg, PendingFreezeResponse, QueryMsg};
+use crate::state::{
+    load_admin_list, load_freeze_config, AdminList, FreezeConfig, ADMIN_LIST, FREEZE_CONFIG,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw1-whitelist";
@@ -28,17 +31,19 @@ pub fn instantiate(
 ) -> StdResult<Response> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     let cfg = AdminList {
-        admins: map_validate(deps.api, &msg.admins)?,
+        admins: validate_addresses(deps.api, &msg.admins)?,
         mutable: msg.mutable,
     };
     ADMIN_LIST.save(deps.storage, &cfg)?;
+    let freeze = FreezeConfig {
+        delay: msg.freeze_delay,
+        unfreezable: msg.unfreezable,
+        pending: None,
+    };
+    FREEZE_CONFIG.save(deps.storage, &freeze)?;
     Ok(Response::default())
 }
 
-pub fn map_validate(api: &dyn Api, admins: &[String]) -> StdResult<Vec<Addr>> {
-    admins.iter().map(|addr| api.addr_validate(&addr)).collect()
-}
-
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -51,7 +56,11 @@ pub fn execute(
     match msg {
         ExecuteMsg::Execute { msgs } => execute_execute(deps, env, info, msgs),
         ExecuteMsg::Freeze {} => execute_freeze(deps, env, info),
+        ExecuteMsg::CancelFreeze {} => execute_cancel_freeze(deps, env, info),
+        ExecuteMsg::Unfreeze {} => execute_unfreeze(deps, env, info),
         ExecuteMsg::UpdateAdmins { admins } => execute_update_admins(deps, env, info, admins),
+        ExecuteMsg::AddAdmins { addrs } => execute_add_admins(deps, env, info, addrs),
+        ExecuteMsg::RemoveAdmins { addrs } => execute_remove_admins(deps, env, info, addrs),
     }
 }
 
@@ -80,35 +89,102 @@ where
 
 pub fn execute_freeze(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    let mut cfg = ADMIN_LIST.load(deps.storage)?;
+    let mut cfg = load_admin_list(deps.storage, &env.block)?;
     if !cfg.can_modify(info.sender.as_ref()) {
-        Err(ContractError::Unauthorized {})
-    } else {
-        cfg.mutable = false;
-        ADMIN_LIST.save(deps.storage, &cfg)?;
+        return Err(ContractError::Unauthorized {});
+    }
 
-        let res = Response {
-            attributes: vec![attr("action", "freeze")],
-            ..Response::default()
-        };
-        Ok(res)
+    let mut freeze = load_freeze_config(deps.storage)?;
+    let mut attributes = vec![attr("action", "freeze")];
+    match freeze.delay {
+        None => {
+            cfg.mutable = false;
+            ADMIN_LIST.save(deps.storage, &cfg)?;
+        }
+        Some(delay) => {
+            // the contract is still mutable, so this one did not trigger yet
+            if freeze.pending.is_some() {
+                return Err(ContractError::FreezePending {});
+            }
+            let freeze_at = delay.after(&env.block);
+            freeze.pending = Some(freeze_at);
+            FREEZE_CONFIG.save(deps.storage, &freeze)?;
+            attributes.push(attr("freeze_at", freeze_at));
+        }
+    }
+
+    Ok(Response {
+        attributes,
+        ..Response::default()
+    })
+}
+
+pub fn execute_cancel_freeze(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let cfg = load_admin_list(deps.storage, &env.block)?;
+    if !cfg.is_admin(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
     }
+
+    let mut freeze = load_freeze_config(deps.storage)?;
+    // once it triggered, the contract is frozen and there is nothing to cancel
+    if freeze.pending.is_none() || freeze.is_triggered(&env.block) {
+        return Err(ContractError::NoPendingFreeze {});
+    }
+    freeze.pending = None;
+    FREEZE_CONFIG.save(deps.storage, &freeze)?;
+
+    Ok(Response {
+        attributes: vec![attr("action", "cancel_freeze")],
+        ..Response::default()
+    })
+}
+
+pub fn execute_unfreeze(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = load_admin_list(deps.storage, &env.block)?;
+    if !cfg.is_admin(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut freeze = load_freeze_config(deps.storage)?;
+    if !freeze.unfreezable {
+        return Err(ContractError::NotUnfreezable {});
+    }
+    if cfg.mutable {
+        return Err(ContractError::NotFrozen {});
+    }
+    cfg.mutable = true;
+    ADMIN_LIST.save(deps.storage, &cfg)?;
+    freeze.pending = None;
+    FREEZE_CONFIG.save(deps.storage, &freeze)?;
+
+    Ok(Response {
+        attributes: vec![attr("action", "unfreeze")],
+        ..Response::default()
+    })
 }
 
 pub fn execute_update_admins(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     admins: Vec<String>,
 ) -> Result<Response, ContractError> {
-    let mut cfg = ADMIN_LIST.load(deps.storage)?;
+    let mut cfg = load_admin_list(deps.storage, &env.block)?;
     if !cfg.can_modify(info.sender.as_ref()) {
         Err(ContractError::Unauthorized {})
     } else {
-        cfg.admins = map_validate(deps.api, &admins)?;
+        cfg.admins = validate_addresses(deps.api, &admins)?;
         ADMIN_LIST.save(deps.storage, &cfg)?;
 
         let res = Response {
@@ -119,6 +195,63 @@ pub fn execute_update_admins(
     }
 }
 
+pub fn execute_add_admins(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addrs: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut cfg = load_admin_list(deps.storage, &env.block)?;
+    if !cfg.can_modify(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut event = EventBuilder::new("add_admins");
+    for addr in validate_addresses(deps.api, &addrs)? {
+        // also skips duplicates in addrs, as they were added before
+        if !cfg.is_admin(addr.as_ref()) {
+            event = event.attr("added", &addr);
+            cfg.admins.push(addr);
+        }
+    }
+    ADMIN_LIST.save(deps.storage, &cfg)?;
+
+    Ok(Response {
+        attributes: event.attributes(),
+        ..Response::default()
+    })
+}
+
+pub fn execute_remove_admins(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addrs: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut cfg = load_admin_list(deps.storage, &env.block)?;
+    if !cfg.can_modify(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut event = EventBuilder::new("remove_admins");
+    for addr in validate_addresses(deps.api, &addrs)? {
+        if let Some(pos) = cfg.admins.iter().position(|a| *a == addr) {
+            event = event.attr("removed", &addr);
+            cfg.admins.remove(pos);
+        }
+    }
+    // a mutable contract without admins could never be changed again
+    if cfg.admins.is_empty() {
+        return Err(ContractError::LastAdmin {});
+    }
+    ADMIN_LIST.save(deps.storage, &cfg)?;
+
+    Ok(Response {
+        attributes: event.attributes(),
+        ..Response::default()
+    })
+}
+
 fn can_execute(deps: Deps, sender: &str) -> StdResult<bool> {
     let cfg = ADMIN_LIST.load(deps.storage)?;
     let can = cfg.is_admin(sender.as_ref());
@@ -126,21 +259,32 @@ fn can_execute(deps: Deps, sender: &str) -> StdResult<bool> {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::AdminList {} => to_binary(&query_admin_list(deps)?),
+        QueryMsg::AdminList {} => to_binary(&query_admin_list(deps, env)?),
+        QueryMsg::PendingFreeze {} => to_binary(&query_pending_freeze(deps, env)?),
         QueryMsg::CanExecute { sender, msg } => to_binary(&query_can_execute(deps, sender, msg)?),
     }
 }
 
-pub fn query_admin_list(deps: Deps) -> StdResult<AdminListResponse> {
-    let cfg = ADMIN_LIST.load(deps.storage)?;
+pub fn query_admin_list(deps: Deps, env: Env) -> StdResult<AdminListResponse> {
+    let cfg = load_admin_list(deps.storage, &env.block)?;
     Ok(AdminListResponse {
         admins: cfg.admins.into_iter().map(|a| a.into()).collect(),
         mutable: cfg.mutable,
     })
 }
 
+pub fn query_pending_freeze(deps: Deps, env: Env) -> StdResult<PendingFreezeResponse> {
+    let freeze = load_freeze_config(deps.storage)?;
+    let freeze_at = if freeze.is_triggered(&env.block) {
+        None
+    } else {
+        freeze.pending
+    };
+    Ok(PendingFreezeResponse { freeze_at })
+}
+
 pub fn query_can_execute(
     deps: Deps,
     sender: String,
@@ -156,6 +300,7 @@ mod tests {
     use super::*;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use cosmwasm_std::{coin, coins, BankMsg, StakingMsg, WasmMsg};
+    use cw0::{Duration, Expiration};
 
     #[test]
     fn instantiate_and_modify_config() {
@@ -171,6 +316,8 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins: vec![alice.to_string(), bob.to_string(), carl.to_string()],
             mutable: true,
+            freeze_delay: None,
+            unfreezable: false,
         };
         let info = mock_info(&anyone, &[]);
         instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
@@ -180,7 +327,10 @@ mod tests {
             admins: vec![alice.to_string(), bob.to_string(), carl.to_string()],
             mutable: true,
         };
-        assert_eq!(query_admin_list(deps.as_ref()).unwrap(), expected);
+        assert_eq!(
+            query_admin_list(deps.as_ref(), mock_env()).unwrap(),
+            expected
+        );
 
         // anyone cannot modify the contract
         let msg = ExecuteMsg::UpdateAdmins {
@@ -202,7 +352,10 @@ mod tests {
             admins: vec![alice.to_string(), bob.to_string()],
             mutable: true,
         };
-        assert_eq!(query_admin_list(deps.as_ref()).unwrap(), expected);
+        assert_eq!(
+            query_admin_list(deps.as_ref(), mock_env()).unwrap(),
+            expected
+        );
 
         // carl cannot freeze it
         let info = mock_info(&carl, &[]);
@@ -216,7 +369,10 @@ mod tests {
             admins: vec![alice.to_string(), bob.to_string()],
             mutable: false,
         };
-        assert_eq!(query_admin_list(deps.as_ref()).unwrap(), expected);
+        assert_eq!(
+            query_admin_list(deps.as_ref(), mock_env()).unwrap(),
+            expected
+        );
 
         // and now alice cannot change it again
         let msg = ExecuteMsg::UpdateAdmins {
@@ -227,6 +383,300 @@ mod tests {
         assert_eq!(err, ContractError::Unauthorized {});
     }
 
+    #[test]
+    fn add_and_remove_admins() {
+        let mut deps = mock_dependencies(&[]);
+
+        let alice = "alice";
+        let bob = "bob";
+        let carl = "carl";
+        let anyone = "anyone";
+
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![alice.to_string()],
+            mutable: true,
+            freeze_delay: None,
+            unfreezable: false,
+        };
+        let info = mock_info(anyone, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+        let admins = |deps: Deps| query_admin_list(deps, mock_env()).unwrap().admins;
+
+        // anyone cannot add or remove admins
+        let msg = ExecuteMsg::AddAdmins {
+            addrs: vec![anyone.to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(anyone, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let msg = ExecuteMsg::RemoveAdmins {
+            addrs: vec![alice.to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(anyone, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // duplicates and existing admins are only added once
+        let msg = ExecuteMsg::AddAdmins {
+            addrs: vec![
+                bob.to_string(),
+                alice.to_string(),
+                bob.to_string(),
+                carl.to_string(),
+            ],
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "add_admins"),
+                attr("added", bob),
+                attr("added", carl)
+            ]
+        );
+        assert_eq!(admins(deps.as_ref()), vec![alice, bob, carl]);
+
+        // the new admins can remove others, non-admins and duplicates are skipped
+        let msg = ExecuteMsg::RemoveAdmins {
+            addrs: vec![anyone.to_string(), alice.to_string(), alice.to_string()],
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(bob, &[]), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "remove_admins"), attr("removed", alice)]
+        );
+        assert_eq!(admins(deps.as_ref()), vec![bob, carl]);
+
+        // removing all remaining admins is rejected, and changes nothing
+        let msg = ExecuteMsg::RemoveAdmins {
+            addrs: vec![carl.to_string(), bob.to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(carl, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::LastAdmin {});
+        assert_eq!(admins(deps.as_ref()), vec![bob, carl]);
+
+        // once frozen, no one can add or remove
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(carl, &[]),
+            ExecuteMsg::Freeze {},
+        )
+        .unwrap();
+        let msg = ExecuteMsg::AddAdmins {
+            addrs: vec![alice.to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(carl, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    fn env_at_height(height: u64) -> Env {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    }
+
+    #[test]
+    fn timelocked_freeze() {
+        let mut deps = mock_dependencies(&[]);
+
+        let alice = "alice";
+        let bob = "bob";
+        let anyone = "anyone";
+
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![alice.to_string(), bob.to_string()],
+            mutable: true,
+            freeze_delay: Some(Duration::Height(10)),
+            unfreezable: false,
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(anyone, &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+        let height = mock_env().block.height;
+        let freeze_at = Expiration::AtHeight(height + 10);
+
+        // freezing only schedules it
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice, &[]),
+            ExecuteMsg::Freeze {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "freeze"), attr("freeze_at", freeze_at)]
+        );
+        let pending = query_pending_freeze(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(pending.freeze_at, Some(freeze_at));
+        assert!(query_admin_list(deps.as_ref(), mock_env()).unwrap().mutable);
+
+        // it cannot be scheduled twice, or cancelled by anyone
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(bob, &[]),
+            ExecuteMsg::Freeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::FreezePending {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(anyone, &[]),
+            ExecuteMsg::CancelFreeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // one block before it triggers, any admin can still cancel it
+        let before = env_at_height(height + 9);
+        execute(
+            deps.as_mut(),
+            before.clone(),
+            mock_info(bob, &[]),
+            ExecuteMsg::CancelFreeze {},
+        )
+        .unwrap();
+        let pending = query_pending_freeze(deps.as_ref(), before.clone()).unwrap();
+        assert_eq!(pending.freeze_at, None);
+        let err = execute(
+            deps.as_mut(),
+            before.clone(),
+            mock_info(bob, &[]),
+            ExecuteMsg::CancelFreeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingFreeze {});
+        // and it never triggers
+        assert!(
+            query_admin_list(deps.as_ref(), env_at_height(height + 100))
+                .unwrap()
+                .mutable
+        );
+
+        // schedule it again
+        execute(
+            deps.as_mut(),
+            before.clone(),
+            mock_info(alice, &[]),
+            ExecuteMsg::Freeze {},
+        )
+        .unwrap();
+        let freeze_at = Expiration::AtHeight(height + 19);
+
+        // at the trigger height it is frozen, without any transaction writing it
+        let at = env_at_height(height + 19);
+        let err = execute(
+            deps.as_mut(),
+            at.clone(),
+            mock_info(bob, &[]),
+            ExecuteMsg::CancelFreeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingFreeze {});
+        let msg = ExecuteMsg::AddAdmins {
+            addrs: vec![anyone.to_string()],
+        };
+        let err = execute(deps.as_mut(), at.clone(), mock_info(alice, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        assert!(!query_admin_list(deps.as_ref(), at.clone()).unwrap().mutable);
+        let pending = query_pending_freeze(deps.as_ref(), at.clone()).unwrap();
+        assert_eq!(pending.freeze_at, None);
+
+        // which admins can still see coming the block before
+        let pending = query_pending_freeze(deps.as_ref(), env_at_height(height + 18)).unwrap();
+        assert_eq!(pending.freeze_at, Some(freeze_at));
+
+        // this one cannot be unfrozen
+        let err = execute(
+            deps.as_mut(),
+            at,
+            mock_info(alice, &[]),
+            ExecuteMsg::Unfreeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotUnfreezable {});
+    }
+
+    #[test]
+    fn unfreeze() {
+        let mut deps = mock_dependencies(&[]);
+
+        let alice = "alice";
+        let anyone = "anyone";
+
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![alice.to_string()],
+            mutable: true,
+            freeze_delay: None,
+            unfreezable: true,
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(anyone, &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+
+        // cannot unfreeze a mutable contract
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice, &[]),
+            ExecuteMsg::Unfreeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotFrozen {});
+
+        // without a delay, freezing is immediate
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice, &[]),
+            ExecuteMsg::Freeze {},
+        )
+        .unwrap();
+        assert_eq!(res.attributes, vec![attr("action", "freeze")]);
+        assert!(!query_admin_list(deps.as_ref(), mock_env()).unwrap().mutable);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice, &[]),
+            ExecuteMsg::CancelFreeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingFreeze {});
+
+        // only admins can unfreeze it
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(anyone, &[]),
+            ExecuteMsg::Unfreeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice, &[]),
+            ExecuteMsg::Unfreeze {},
+        )
+        .unwrap();
+        assert!(query_admin_list(deps.as_ref(), mock_env()).unwrap().mutable);
+
+        // and modify it again
+        let msg = ExecuteMsg::AddAdmins {
+            addrs: vec![anyone.to_string()],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap();
+    }
+
     #[test]
     fn execute_messages_has_proper_permissions() {
         let mut deps = mock_dependencies(&[]);
@@ -239,6 +689,8 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins: vec![alice.to_string(), carl.to_string()],
             mutable: false,
+            freeze_delay: None,
+            unfreezable: false,
         };
         let info = mock_info(&bob, &[]);
         instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
@@ -289,6 +741,8 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins: vec![alice.to_string(), bob.to_string()],
             mutable: false,
+            freeze_delay: None,
+            unfreezable: false,
         };
         let info = mock_info(&anyone, &[]);
         instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
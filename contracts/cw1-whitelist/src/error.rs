@@ -8,4 +8,19 @@ pub enum ContractError {
 
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("Cannot remove the last admin")]
+    LastAdmin {},
+
+    #[error("A freeze is already scheduled")]
+    FreezePending {},
+
+    #[error("No freeze is scheduled")]
+    NoPendingFreeze {},
+
+    #[error("Contract cannot be unfrozen")]
+    NotUnfreezable {},
+
+    #[error("Contract is not frozen")]
+    NotFrozen {},
 }
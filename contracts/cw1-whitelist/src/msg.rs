@@ -3,11 +3,18 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 use cosmwasm_std::{CosmosMsg, Empty};
+use cw0::{Duration, Expiration};
 
 #[derive(Serialize, Deserialize, JsonSchema)]
 pub struct InstantiateMsg {
     pub admins: Vec<String>,
     pub mutable: bool,
+    /// If set, Freeze only takes effect this long after it was called, and can be cancelled
+    /// until then. Otherwise it takes effect immediately
+    pub freeze_delay: Option<Duration>,
+    /// If true, admins can Unfreeze the contract after it was frozen
+    #[serde(default)]
+    pub unfreezable: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -20,11 +27,24 @@ where
     /// contract's address as sender. Every implementation has it's own logic to
     /// determine in
     Execute { msgs: Vec<CosmosMsg<T>> },
-    /// Freeze will make a mutable contract immutable, must be called by an admin
+    /// Freeze will make a mutable contract immutable, must be called by an admin.
+    /// If the contract has a freeze_delay, this only happens once the delay passed
     Freeze {},
+    /// CancelFreeze cancels a scheduled freeze before it takes effect, must be called by an admin
+    CancelFreeze {},
+    /// Unfreeze makes a frozen contract mutable again, must be called by an admin
+    /// and only works if the contract was instantiated as unfreezable
+    Unfreeze {},
     /// UpdateAdmins will change the admin set of the contract, must be called by an existing admin,
     /// and only works if the contract is mutable
     UpdateAdmins { admins: Vec<String> },
+    /// AddAdmins adds the given addresses to the admin set, skipping the ones that already are
+    /// admins. Must be called by an existing admin, and only works if the contract is mutable
+    AddAdmins { addrs: Vec<String> },
+    /// RemoveAdmins removes the given addresses from the admin set, skipping the ones that are
+    /// not admins. Must be called by an existing admin, and only works if the contract is mutable.
+    /// Fails if it would remove all admins
+    RemoveAdmins { addrs: Vec<String> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -35,6 +55,9 @@ where
 {
     /// Shows all admins and whether or not it is mutable
     AdminList {},
+    /// Shows when a scheduled freeze takes effect, if there is one.
+    /// Returns PendingFreezeResponse
+    PendingFreeze {},
     /// Checks permissions of the caller on this proxy.
     /// If CanExecute returns true then a call to `Execute` with the same message,
     /// before any further state changes, should also succeed.
@@ -46,3 +69,9 @@ pub struct AdminListResponse {
     pub admins: Vec<String>,
     pub mutable: bool,
 }
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingFreezeResponse {
+    /// None if no freeze is scheduled, or if it already took effect
+    pub freeze_at: Option<Expiration>,
+}
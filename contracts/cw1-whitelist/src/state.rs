@@ -1,7 +1,8 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, BlockInfo, StdResult, Storage};
+use cw0::{Duration, Expiration};
 use cw_storage_plus::Item;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
@@ -22,7 +23,41 @@ impl AdminList {
     }
 }
 
+/// FreezeConfig controls how Freeze works. Contracts instantiated before it existed have none
+/// stored, which is the default: freezing is instant and permanent.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct FreezeConfig {
+    /// If set, Freeze only takes effect this long after it was called
+    pub delay: Option<Duration>,
+    /// If true, admins can Unfreeze a frozen contract
+    pub unfreezable: bool,
+    /// When the scheduled freeze takes effect, if there is one
+    pub pending: Option<Expiration>,
+}
+
+impl FreezeConfig {
+    /// returns true if a scheduled freeze took effect at the given block
+    pub fn is_triggered(&self, block: &BlockInfo) -> bool {
+        matches!(self.pending, Some(at) if at.is_expired(block))
+    }
+}
+
 pub const ADMIN_LIST: Item<AdminList> = Item::new("admin_list");
+pub const FREEZE_CONFIG: Item<FreezeConfig> = Item::new("freeze_config");
+
+/// load_admin_list returns the admin list as it is at the given block, so a scheduled freeze
+/// applies from the block it triggers on, even though it was not written to the admin list yet.
+pub fn load_admin_list(storage: &dyn Storage, block: &BlockInfo) -> StdResult<AdminList> {
+    let mut cfg = ADMIN_LIST.load(storage)?;
+    if load_freeze_config(storage)?.is_triggered(block) {
+        cfg.mutable = false;
+    }
+    Ok(cfg)
+}
+
+pub fn load_freeze_config(storage: &dyn Storage) -> StdResult<FreezeConfig> {
+    Ok(FREEZE_CONFIG.may_load(storage)?.unwrap_or_default())
+}
 
 #[cfg(test)]
 mod tests {
@@ -1,7 +1,8 @@
 use cosmwasm_std::{
-    attr, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
     Storage, SubMsg, Uint128,
 };
+use cw0::EventBuilder;
 use cw20::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
 
 use crate::error::ContractError;
@@ -34,12 +35,11 @@ pub fn execute_increase_allowance(
     )?;
 
     let res = Response {
-        attributes: vec![
-            attr("action", "increase_allowance"),
-            attr("owner", info.sender),
-            attr("spender", spender),
-            attr("amount", amount),
-        ],
+        attributes: EventBuilder::new("increase_allowance")
+            .attr("owner", info.sender)
+            .attr("spender", spender)
+            .attr_amount(amount)
+            .attributes(),
         ..Response::default()
     };
     Ok(res)
@@ -76,12 +76,11 @@ pub fn execute_decrease_allowance(
     }
 
     let res = Response {
-        attributes: vec![
-            attr("action", "decrease_allowance"),
-            attr("owner", info.sender),
-            attr("spender", spender),
-            attr("amount", amount),
-        ],
+        attributes: EventBuilder::new("decrease_allowance")
+            .attr("owner", info.sender)
+            .attr("spender", spender)
+            .attr_amount(amount)
+            .attributes(),
         ..Response::default()
     };
     Ok(res)
@@ -142,13 +141,12 @@ pub fn execute_transfer_from(
     )?;
 
     let res = Response {
-        attributes: vec![
-            attr("action", "transfer_from"),
-            attr("from", owner),
-            attr("to", recipient),
-            attr("by", info.sender),
-            attr("amount", amount),
-        ],
+        attributes: EventBuilder::new("transfer_from")
+            .attr("from", owner)
+            .attr("to", recipient)
+            .attr("by", info.sender)
+            .attr_amount(amount)
+            .attributes(),
         ..Response::default()
     };
     Ok(res)
@@ -182,12 +180,11 @@ pub fn execute_burn_from(
     })?;
 
     let res = Response {
-        attributes: vec![
-            attr("action", "burn_from"),
-            attr("from", owner),
-            attr("by", info.sender),
-            attr("amount", amount),
-        ],
+        attributes: EventBuilder::new("burn_from")
+            .attr("from", owner)
+            .attr("by", info.sender)
+            .attr_amount(amount)
+            .attributes(),
         ..Response::default()
     };
     Ok(res)
@@ -222,13 +219,12 @@ pub fn execute_send_from(
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
 
-    let attrs = vec![
-        attr("action", "send_from"),
-        attr("from", &owner),
-        attr("to", &contract),
-        attr("by", &info.sender),
-        attr("amount", amount),
-    ];
+    let attrs = EventBuilder::new("send_from")
+        .attr("from", &owner)
+        .attr("to", &contract)
+        .attr("by", &info.sender)
+        .attr_amount(amount)
+        .attributes();
 
     // create a send message
     let msg = SubMsg::new(
@@ -262,7 +258,7 @@ mod tests {
     use super::*;
 
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, CosmosMsg, Timestamp, WasmMsg};
+    use cosmwasm_std::{attr, coins, CosmosMsg, Timestamp, WasmMsg};
     use cw20::{Cw20Coin, TokenInfoResponse};
 
     use crate::contract::{execute, instantiate, query_balance, query_token_info};
@@ -530,7 +526,16 @@ mod tests {
         let info = mock_info(spender.as_ref(), &[]);
         let env = mock_env();
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(res.attributes[0], attr("action", "transfer_from"));
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "transfer_from"),
+                attr("from", &owner),
+                attr("to", &rcpt),
+                attr("by", &spender),
+                attr("amount", transfer),
+            ]
+        );
 
         // make sure money arrived
         assert_eq!(
@@ -609,7 +614,15 @@ mod tests {
         let info = mock_info(spender.as_ref(), &[]);
         let env = mock_env();
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(res.attributes[0], attr("action", "burn_from"));
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "burn_from"),
+                attr("from", &owner),
+                attr("by", &spender),
+                attr("amount", transfer),
+            ]
+        );
 
         // make sure money burnt
         assert_eq!(
@@ -689,7 +702,16 @@ mod tests {
         let info = mock_info(spender.as_ref(), &[]);
         let env = mock_env();
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(res.attributes[0], attr("action", "send_from"));
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "send_from"),
+                attr("from", &owner),
+                attr("to", &contract),
+                attr("by", &spender),
+                attr("amount", transfer),
+            ]
+        );
         assert_eq!(1, res.messages.len());
 
         // we record this as sent by the one who requested, not the one who was paying
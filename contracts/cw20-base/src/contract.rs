@@ -1,11 +1,15 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
-    SubMsg, Uint128,
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, SubMsg,
+    Uint128,
 };
 
-use cw2::set_contract_version;
+use cw0::EventBuilder;
+use cw2::{
+    assert_contract_version, migrate_version, set_contract_supported_interface,
+    set_contract_version, ContractSupportedInterface,
+};
 use cw20::{BalanceResponse, Cw20Coin, Cw20ReceiveMsg, MinterResponse, TokenInfoResponse};
 
 use crate::allowances::{
@@ -14,12 +18,32 @@ use crate::allowances::{
 };
 use crate::enumerable::{query_all_accounts, query_all_allowances};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 use crate::state::{MinterData, TokenInfo, BALANCES, TOKEN_INFO};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw20-base";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// the cw20 spec and its extensions implemented here, these are versioned along with the contract
+const INTERFACES: &[&str] = &[
+    "crates.io:cw20",
+    "crates.io:cw20-allowances",
+    MINTABLE_INTERFACE,
+    "crates.io:cw20-enumerable",
+];
+// only supported if the token has a minter
+const MINTABLE_INTERFACE: &str = "crates.io:cw20-mintable";
+
+fn supported_interfaces(mintable: bool) -> Vec<ContractSupportedInterface> {
+    INTERFACES
+        .iter()
+        .filter(|interface| mintable || **interface != MINTABLE_INTERFACE)
+        .map(|interface| ContractSupportedInterface {
+            interface: interface.to_string(),
+            version: CONTRACT_VERSION.to_string(),
+        })
+        .collect()
+}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -29,6 +53,7 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    set_contract_supported_interface(deps.storage, &supported_interfaces(msg.mint.is_some()))?;
     // check valid token info
     msg.validate()?;
     // create initial accounts
@@ -141,12 +166,7 @@ pub fn execute_transfer(
 
     let res = Response {
         messages: vec![],
-        attributes: vec![
-            attr("action", "transfer"),
-            attr("from", info.sender),
-            attr("to", recipient),
-            attr("amount", amount),
-        ],
+        attributes: EventBuilder::transfer(info.sender, recipient, amount).attributes(),
         events: vec![],
         data: None,
     };
@@ -179,11 +199,7 @@ pub fn execute_burn(
 
     let res = Response {
         messages: vec![],
-        attributes: vec![
-            attr("action", "burn"),
-            attr("from", info.sender),
-            attr("amount", amount),
-        ],
+        attributes: EventBuilder::burn(info.sender, amount).attributes(),
         events: vec![],
         data: None,
     };
@@ -224,11 +240,7 @@ pub fn execute_mint(
     )?;
 
     let res = Response {
-        attributes: vec![
-            attr("action", "mint"),
-            attr("to", recipient),
-            attr("amount", amount),
-        ],
+        attributes: EventBuilder::mint(recipient, amount).attributes(),
         ..Response::default()
     };
     Ok(res)
@@ -262,12 +274,11 @@ pub fn execute_send(
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
 
-    let attrs = vec![
-        attr("action", "send"),
-        attr("from", &info.sender),
-        attr("to", &contract),
-        attr("amount", amount),
-    ];
+    let attrs = EventBuilder::new("send")
+        .attr("from", &info.sender)
+        .attr("to", &contract)
+        .attr_amount(amount)
+        .attributes();
 
     // create a send message
     let msg = SubMsg::new(
@@ -287,6 +298,25 @@ pub fn execute_send(
     Ok(res)
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // the state did not change since cw2 was added, so any older version works, but no downgrades
+    assert_contract_version(
+        deps.storage,
+        CONTRACT_NAME,
+        &format!("<={}", CONTRACT_VERSION),
+    )?;
+    migrate_version(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        env.block.height,
+    )?;
+    let mintable = TOKEN_INFO.load(deps.storage)?.mint.is_some();
+    set_contract_supported_interface(deps.storage, &supported_interfaces(mintable))?;
+    Ok(Response::default())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -341,7 +371,10 @@ pub fn query_minter(deps: Deps) -> StdResult<Option<MinterResponse>> {
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary, CosmosMsg, StdError, WasmMsg};
+    use cosmwasm_std::{attr, coins, from_binary, CosmosMsg, StdError, WasmMsg};
+    use cw2::{
+        get_contract_version, get_migration_history, ContractVersion, MigrationRecord, VersionError,
+    };
 
     use super::*;
 
@@ -540,6 +573,14 @@ mod tests {
         let env = mock_env();
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
         assert_eq!(0, res.messages.len());
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "mint"),
+                attr("to", &winner),
+                attr("amount", prize),
+            ]
+        );
         assert_eq!(get_balance(deps.as_ref(), genesis), amount);
         assert_eq!(get_balance(deps.as_ref(), winner.clone()), prize);
 
@@ -729,6 +770,15 @@ mod tests {
         };
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
         assert_eq!(res.messages.len(), 0);
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "transfer"),
+                attr("from", &addr1),
+                attr("to", &addr2),
+                attr("amount", transfer),
+            ]
+        );
 
         let remainder = amount1.checked_sub(transfer).unwrap();
         assert_eq!(get_balance(deps.as_ref(), addr1), remainder);
@@ -779,6 +829,14 @@ mod tests {
         let msg = ExecuteMsg::Burn { amount: burn };
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
         assert_eq!(res.messages.len(), 0);
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "burn"),
+                attr("from", &addr1),
+                attr("amount", burn),
+            ]
+        );
 
         let remainder = amount1.checked_sub(burn).unwrap();
         assert_eq!(get_balance(deps.as_ref(), addr1), remainder);
@@ -832,6 +890,15 @@ mod tests {
         };
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
         assert_eq!(res.messages.len(), 1);
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "send"),
+                attr("from", &addr1),
+                attr("to", &contract),
+                attr("amount", transfer),
+            ]
+        );
 
         // ensure proper send message sent
         // this is the message we want delivered to the other side
@@ -861,4 +928,61 @@ mod tests {
             amount1
         );
     }
+
+    #[test]
+    fn migrate_checks_version() {
+        let mut deps = mock_dependencies(&[]);
+        let migrate = |deps: DepsMut| migrate(deps, mock_env(), MigrateMsg {});
+
+        // contracts from before cw2 cannot be migrated
+        let err = migrate(deps.as_mut()).unwrap_err();
+        assert_eq!(err, ContractError::Version(VersionError::NotFound {}));
+
+        // the current version can be migrated again, without being recorded
+        do_instantiate(deps.as_mut(), "addr0000", Uint128::new(1000));
+        migrate(deps.as_mut()).unwrap();
+        assert_eq!(get_migration_history(&deps.storage).unwrap(), vec![]);
+
+        // older versions are updated
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.5.0").unwrap();
+        migrate(deps.as_mut()).unwrap();
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+        assert_eq!(get_balance(deps.as_ref(), "addr0000"), Uint128::new(1000));
+
+        // the replaced version is recorded
+        let height = mock_env().block.height;
+        assert_eq!(
+            get_migration_history(&deps.storage).unwrap(),
+            vec![MigrationRecord {
+                version: ContractVersion {
+                    contract: CONTRACT_NAME.to_string(),
+                    version: "0.5.0".to_string(),
+                },
+                height,
+            }]
+        );
+
+        // no downgrades
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut()).unwrap_err();
+        assert_eq!(get_migration_history(&deps.storage).unwrap().len(), 1);
+        assert_eq!(
+            err,
+            ContractError::Version(VersionError::UnsupportedVersion {
+                version: "99.0.0".to_string(),
+                required: format!("<={}", CONTRACT_VERSION),
+            })
+        );
+
+        set_contract_version(deps.as_mut().storage, "crates.io:cw1-subkeys", "0.5.0").unwrap();
+        let err = migrate(deps.as_mut()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Version(VersionError::WrongContract {
+                expected: CONTRACT_NAME.to_string(),
+                found: "crates.io:cw1-subkeys".to_string(),
+            })
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use cosmwasm_std::StdError;
+use cw2::VersionError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,6 +7,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Version(#[from] VersionError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -0,0 +1,152 @@
+#![cfg(test)]
+
+use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
+use cosmwasm_std::{
+    Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+};
+use cw2::{
+    query_contract_info, query_supported_interfaces, ContractSupportedInterface, ContractVersion,
+};
+use cw20::{Cw20Coin, MinterResponse};
+use cw_multi_test::{App, Contract, ContractWrapper, SimpleBank};
+
+use crate::msg::InstantiateMsg;
+
+fn mock_app() -> App {
+    let env = mock_env();
+    let api = Box::new(MockApi::default());
+    let bank = SimpleBank {};
+
+    App::new(api, env.block, bank, || Box::new(MockStorage::new()))
+}
+
+fn contract_cw20() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    );
+    Box::new(contract)
+}
+
+// a contract that does not store any cw2 info
+fn contract_without_version() -> Box<dyn Contract<Empty>> {
+    fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+    fn execute(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+    fn query(_: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+        Ok(Binary::default())
+    }
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+#[test]
+fn query_contract_info_of_other_contracts() {
+    let mut router = mock_app();
+    let owner = Addr::unchecked("owner");
+
+    let cw20_id = router.store_code(contract_cw20());
+    let msg = InstantiateMsg {
+        name: "Cash Money".to_string(),
+        symbol: "CASH".to_string(),
+        decimals: 2,
+        initial_balances: vec![Cw20Coin {
+            address: owner.to_string(),
+            amount: Uint128::new(5000),
+        }],
+        mint: None,
+    };
+    let cash_addr = router
+        .instantiate_contract(cw20_id, owner.clone(), &msg, &[], "CASH")
+        .unwrap();
+
+    let info = query_contract_info(&router, cash_addr).unwrap();
+    assert_eq!(
+        info,
+        ContractVersion {
+            contract: "crates.io:cw20-base".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    );
+
+    // contracts that are not cw2 compliant are not found, rather than failing to parse
+    let other_id = router.store_code(contract_without_version());
+    let other_addr = router
+        .instantiate_contract(other_id, owner, &Empty {}, &[], "Other")
+        .unwrap();
+    let err = query_contract_info(&router, other_addr).unwrap_err();
+    assert!(matches!(err, StdError::NotFound { .. }), "{}", err);
+}
+
+#[test]
+fn query_supported_interfaces_of_other_contracts() {
+    let mut router = mock_app();
+    let owner = Addr::unchecked("owner");
+
+    let cw20_id = router.store_code(contract_cw20());
+    let msg = InstantiateMsg {
+        name: "Cash Money".to_string(),
+        symbol: "CASH".to_string(),
+        decimals: 2,
+        initial_balances: vec![],
+        mint: None,
+    };
+    let cash_addr = router
+        .instantiate_contract(cw20_id, owner.clone(), &msg, &[], "CASH")
+        .unwrap();
+
+    // without a minter, it is not mintable
+    let interfaces = query_supported_interfaces(&router, cash_addr).unwrap();
+    let names: Vec<_> = interfaces.iter().map(|i| i.interface.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "crates.io:cw20",
+            "crates.io:cw20-allowances",
+            "crates.io:cw20-enumerable",
+        ]
+    );
+    assert_eq!(
+        interfaces[1],
+        ContractSupportedInterface {
+            interface: "crates.io:cw20-allowances".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    );
+
+    let msg = InstantiateMsg {
+        name: "Minted Money".to_string(),
+        symbol: "MINT".to_string(),
+        decimals: 2,
+        initial_balances: vec![],
+        mint: Some(MinterResponse {
+            minter: owner.to_string(),
+            cap: None,
+        }),
+    };
+    let minted_addr = router
+        .instantiate_contract(cw20_id, owner.clone(), &msg, &[], "MINT")
+        .unwrap();
+    let interfaces = query_supported_interfaces(&router, minted_addr).unwrap();
+    let names: Vec<_> = interfaces.iter().map(|i| i.interface.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "crates.io:cw20",
+            "crates.io:cw20-allowances",
+            "crates.io:cw20-mintable",
+            "crates.io:cw20-enumerable",
+        ]
+    );
+
+    // contracts without the key support none
+    let other_id = router.store_code(contract_without_version());
+    let other_addr = router
+        .instantiate_contract(other_id, owner, &Empty {}, &[], "Other")
+        .unwrap();
+    let interfaces = query_supported_interfaces(&router, other_addr).unwrap();
+    assert_eq!(interfaces, vec![]);
+}
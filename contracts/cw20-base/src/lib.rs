@@ -2,6 +2,7 @@ pub mod allowances;
 pub mod contract;
 pub mod enumerable;
 mod error;
+mod integration_test;
 pub mod msg;
 pub mod state;
 
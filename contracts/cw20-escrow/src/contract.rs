@@ -5,6 +5,7 @@ use cosmwasm_std::{
     StdResult, SubMsg, WasmMsg,
 };
 
+use cw0::nonpayable;
 use cw2::set_contract_version;
 use cw20::{Balance, Cw20Coin, Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg};
 
@@ -53,6 +54,7 @@ pub fn execute_receive(
     info: MessageInfo,
     wrapper: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
     let msg: ReceiveMsg = from_binary(&wrapper.msg)?;
     let balance = Balance::Cw20(Cw20CoinVerified {
         address: info.sender,
@@ -155,6 +157,7 @@ pub fn execute_approve(
     info: MessageInfo,
     id: String,
 ) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
     // this fails is no escrow there
     let escrow = ESCROWS.load(deps.storage, &id)?;
 
@@ -189,6 +192,7 @@ pub fn execute_refund(
     info: MessageInfo,
     id: String,
 ) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
     // this fails is no escrow there
     let escrow = ESCROWS.load(deps.storage, &id)?;
 
@@ -301,6 +305,7 @@ mod tests {
     use cosmwasm_std::{coin, coins, CosmosMsg, StdError, Uint128};
 
     use crate::msg::ExecuteMsg::TopUp;
+    use cw0::PaymentError;
 
     use super::*;
 
@@ -348,9 +353,15 @@ mod tests {
             }
         );
 
+        // approving doesn't accept funds, but zero amounts are fine
+        let id = create.id.clone();
+        let info = mock_info(&create.arbiter, &coins(5, "tokens"));
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Approve { id }).unwrap_err();
+        assert_eq!(err, ContractError::Payment(PaymentError::NonPayable {}));
+
         // approve it
         let id = create.id.clone();
-        let info = mock_info(&create.arbiter, &[]);
+        let info = mock_info(&create.arbiter, &coins(0, "tokens"));
         let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Approve { id }).unwrap();
         assert_eq!(1, res.messages.len());
         assert_eq!(attr("action", "approve"), res.attributes[0]);
@@ -1,7 +1,7 @@
 #![cfg(test)]
 
 use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
-use cosmwasm_std::{coins, to_binary, Addr, Empty, Uint128};
+use cosmwasm_std::{coins, to_binary, Addr, Empty, QuerierWrapper, Uint128};
 use cw20::{Cw20Coin, Cw20Contract, Cw20ExecuteMsg};
 use cw_multi_test::{App, Contract, ContractWrapper, SimpleBank};
 
@@ -77,6 +77,22 @@ fn escrow_happy_path_cw20_tokens() {
     let escrow_balance = cash.balance(&router, escrow_addr.clone()).unwrap();
     assert_eq!(escrow_balance, Uint128::zero());
 
+    // the same balances can be read straight from the cw20 storage
+    let querier = QuerierWrapper::new(&router);
+    let raw_balance = cw20_base::state::BALANCES
+        .query(&querier, cash_addr.clone(), &owner)
+        .unwrap();
+    assert_eq!(raw_balance, Some(Uint128::new(5000)));
+    let raw_balance = cw20_base::state::BALANCES
+        .query(&querier, cash_addr.clone(), &escrow_addr)
+        .unwrap();
+    assert_eq!(raw_balance, None);
+    let token_info = cw20_base::state::TOKEN_INFO
+        .query(&querier, cash_addr.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(token_info.total_supply, Uint128::new(5000));
+
     // send some tokens to create an escrow
     let arb = Addr::unchecked("arbiter");
     let ben = String::from("beneficiary");
@@ -6,6 +6,7 @@ use cosmwasm_std::{
     WasmMsg,
 };
 
+use cw0::Rate;
 use cw2::set_contract_version;
 use cw20_base::allowances::{
     execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_send_from,
@@ -35,6 +36,9 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    // the tax is taken from the unbonded amount, so it cannot be more than all of it
+    Rate::from(msg.exit_tax).validate_fee()?;
+
     // ensure the validator is registered
     let vals = deps.querier.query_all_validators()?;
     if !vals.iter().any(|v| v.address == msg.validator) {
@@ -235,7 +239,7 @@ pub fn unbond(
         });
     }
     // calculate tax and remainer to unbond
-    let tax = amount * invest.exit_tax;
+    let tax = Rate::from(invest.exit_tax).checked_mul_uint(amount)?;
 
     // burn from the original caller
     execute_burn(deps.branch(), env.clone(), info.clone(), amount)?;
@@ -556,6 +560,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn instantiation_with_invalid_exit_tax() {
+        let mut deps = mock_dependencies(&[]);
+        deps.querier
+            .update_staking("ustake", &[sample_validator(DEFAULT_VALIDATOR)], &[]);
+        let info = mock_info("creator", &[]);
+
+        // more than everything cannot be taxed
+        let msg = default_instantiate(101, 50);
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Std(StdError::generic_err("Fee rate cannot be more than 100%"))
+        );
+
+        // but everything can
+        let msg = default_instantiate(100, 50);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
     #[test]
     fn proper_instantiation() {
         let mut deps = mock_dependencies(&[]);
@@ -1,4 +1,5 @@
 use cosmwasm_std::{StdError, Uint128};
+use cw2::VersionError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,6 +7,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Version(#[from] VersionError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -50,6 +54,7 @@ impl From<cw20_base::ContractError> for ContractError {
     fn from(err: cw20_base::ContractError) -> Self {
         match err {
             cw20_base::ContractError::Std(error) => ContractError::Std(error),
+            cw20_base::ContractError::Version(error) => ContractError::Version(error),
             cw20_base::ContractError::Unauthorized {} => ContractError::Unauthorized {},
             cw20_base::ContractError::CannotSetOwnAccount {} => {
                 ContractError::CannotSetOwnAccount {}
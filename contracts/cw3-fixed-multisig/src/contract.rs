@@ -1,25 +1,23 @@
-use std::cmp::Ordering;
-
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order,
-    Response, StdResult, SubMsg,
+    attr, to_binary, Addr, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, Event,
+    MessageInfo, Order, Response, StdError, StdResult, SubMsg,
 };
 
-use cw0::Expiration;
-use cw2::set_contract_version;
+use cw0::{Duration, Expiration};
+use cw2::{assert_contract_version, migrate_version, set_contract_version};
 use cw3::{
-    ProposalListResponse, ProposalResponse, Status, ThresholdResponse, Vote, VoteInfo,
-    VoteListResponse, VoteResponse, VoterDetail, VoterListResponse, VoterResponse,
+    index_proposals, proposal_expiration, range_proposals, range_votes, validate_min_voting_period,
+    Ballot, ProposalKind, ProposalListResponse, ProposalResponse, Status, Threshold,
+    ThresholdResponse, Vote, VoteInfo, VoteListResponse, VoteResponse, VoterDetail,
+    VoterListResponse, VoterResponse,
 };
-use cw_storage_plus::Bound;
+use cw_storage_plus::{Bound, U8Key};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{
-    next_id, parse_id, Ballot, Config, Proposal, BALLOTS, CONFIG, PROPOSALS, VOTERS,
-};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, Voter};
+use crate::state::{next_id, parse_id, proposals, Config, Proposal, BALLOTS, CONFIG, VOTERS};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw3-fixed-multisig";
@@ -32,16 +30,16 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    if msg.required_weight == 0 {
-        return Err(ContractError::ZeroWeight {});
-    }
     if msg.voters.is_empty() {
         return Err(ContractError::NoVoters {});
     }
     let total_weight = msg.voters.iter().map(|v| v.weight).sum();
-
-    if total_weight < msg.required_weight {
-        return Err(ContractError::UnreachableWeight {});
+    Threshold::AbsoluteCount {
+        weight: msg.required_weight,
+    }
+    .validate(total_weight)?;
+    if let Some(min) = msg.min_voting_period {
+        validate_min_voting_period(min, msg.max_voting_period)?;
     }
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -50,6 +48,7 @@ pub fn instantiate(
         required_weight: msg.required_weight,
         total_weight,
         max_voting_period: msg.max_voting_period,
+        min_voting_period: msg.min_voting_period,
     };
     CONFIG.save(deps.storage, &cfg)?;
 
@@ -74,13 +73,27 @@ pub fn execute(
             description,
             msgs,
             latest,
-        } => execute_propose(deps, env, info, title, description, msgs, latest),
+            voting_period,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            msgs,
+            latest,
+            voting_period,
+        ),
         ExecuteMsg::Vote { proposal_id, vote } => execute_vote(deps, env, info, proposal_id, vote),
         ExecuteMsg::Execute { proposal_id } => execute_execute(deps, env, info, proposal_id),
         ExecuteMsg::Close { proposal_id } => execute_close(deps, env, info, proposal_id),
+        ExecuteMsg::UpdateVoters { add, remove } => {
+            execute_update_voters(deps, env, info, add, remove)
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_propose(
     deps: DepsMut,
     env: Env,
@@ -90,6 +103,7 @@ pub fn execute_propose(
     msgs: Vec<CosmosMsg>,
     // we ignore earliest
     latest: Option<Expiration>,
+    voting_period: Option<Duration>,
 ) -> Result<Response<Empty>, ContractError> {
     // only members of the multisig can create a proposal
     let vote_power = VOTERS
@@ -97,16 +111,13 @@ pub fn execute_propose(
         .ok_or(ContractError::Unauthorized {})?;
 
     let cfg = CONFIG.load(deps.storage)?;
-
-    // max expires also used as default
-    let max_expires = cfg.max_voting_period.after(&env.block);
-    let mut expires = latest.unwrap_or(max_expires);
-    let comp = expires.partial_cmp(&max_expires);
-    if let Some(Ordering::Greater) = comp {
-        expires = max_expires;
-    } else if comp.is_none() {
-        return Err(ContractError::WrongExpiration {});
-    }
+    let expires = proposal_expiration(
+        &env.block,
+        cfg.max_voting_period,
+        cfg.min_voting_period,
+        voting_period,
+        latest,
+    )?;
 
     let status = if vote_power < cfg.required_weight {
         Status::Open
@@ -119,13 +130,14 @@ pub fn execute_propose(
         title,
         description,
         expires,
+        kind: ProposalKind::from_msgs(&msgs),
         msgs,
         status,
         yes_weight: vote_power,
         required_weight: cfg.required_weight,
     };
     let id = next_id(deps.storage)?;
-    PROPOSALS.save(deps.storage, id.into(), &prop)?;
+    proposals().save(deps.storage, id.into(), &prop)?;
 
     // add the first yes vote from voter
     let ballot = Ballot {
@@ -160,7 +172,7 @@ pub fn execute_vote(
         .ok_or(ContractError::Unauthorized {})?;
 
     // ensure proposal exists and can be voted on
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id.into())?;
+    let mut prop = proposals().load(deps.storage, proposal_id.into())?;
     if prop.status != Status::Open {
         return Err(ContractError::NotOpen {});
     }
@@ -188,7 +200,7 @@ pub fn execute_vote(
         if prop.yes_weight >= prop.required_weight {
             prop.status = Status::Passed;
         }
-        PROPOSALS.save(deps.storage, proposal_id.into(), &prop)?;
+        proposals().save(deps.storage, proposal_id.into(), &prop)?;
     }
 
     Ok(Response {
@@ -212,16 +224,19 @@ pub fn execute_execute(
 ) -> Result<Response, ContractError> {
     // anyone can trigger this if the vote passed
 
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id.into())?;
+    let mut prop = proposals().load(deps.storage, proposal_id.into())?;
     // we allow execution even after the proposal "expiration" as long as all vote come in before
     // that point. If it was approved on time, it can be executed any time.
+    if prop.kind == ProposalKind::Signaling {
+        return Err(ContractError::SignalingProposal {});
+    }
     if prop.status != Status::Passed {
         return Err(ContractError::WrongExecuteStatus {});
     }
 
     // set it to executed
     prop.status = Status::Executed;
-    PROPOSALS.save(deps.storage, proposal_id.into(), &prop)?;
+    proposals().save(deps.storage, proposal_id.into(), &prop)?;
 
     // dispatch all proposed messages
     Ok(Response {
@@ -244,8 +259,17 @@ pub fn execute_close(
 ) -> Result<Response<Empty>, ContractError> {
     // anyone can trigger this if the vote passed
 
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id.into())?;
-    if [Status::Executed, Status::Rejected, Status::Passed]
+    let mut prop = proposals().load(deps.storage, proposal_id.into())?;
+    // signaling proposals have nothing to execute, so they are closed as done once they passed
+    let passed_signaling =
+        prop.kind == ProposalKind::Signaling && prop.current_status(&env.block) == Status::Passed;
+    if !passed_signaling
+        && [
+            Status::Executed,
+            Status::Done,
+            Status::Rejected,
+            Status::Passed,
+        ]
         .iter()
         .any(|x| *x == prop.status)
     {
@@ -255,9 +279,13 @@ pub fn execute_close(
         return Err(ContractError::NotExpired {});
     }
 
-    // set it to failed
-    prop.status = Status::Rejected;
-    PROPOSALS.save(deps.storage, proposal_id.into(), &prop)?;
+    // set it to failed, or done
+    prop.status = if passed_signaling {
+        Status::Done
+    } else {
+        Status::Rejected
+    };
+    proposals().save(deps.storage, proposal_id.into(), &prop)?;
 
     Ok(Response {
         messages: vec![],
@@ -271,24 +299,153 @@ pub fn execute_close(
     })
 }
 
+pub fn execute_update_voters(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    add: Vec<Voter>,
+    remove: Vec<String>,
+) -> Result<Response<Empty>, ContractError> {
+    // only a passed proposal can change the voters
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let mut events = vec![];
+    for voter in add.into_iter() {
+        let addr = deps.api.addr_validate(&voter.addr)?;
+        let event = match VOTERS.may_load(deps.storage, &addr)? {
+            Some(old) => {
+                cfg.total_weight -= old;
+                Event::new("update_voter").attr("old_weight", old.to_string())
+            }
+            None => Event::new("add_voter"),
+        };
+        cfg.total_weight += voter.weight;
+        VOTERS.save(deps.storage, &addr, &voter.weight)?;
+        events.push(
+            event
+                .attr("addr", addr)
+                .attr("weight", voter.weight.to_string()),
+        );
+    }
+    for remove in remove.into_iter() {
+        let addr = deps.api.addr_validate(&remove)?;
+        // Only process this if they were actually in the list before
+        if let Some(old) = VOTERS.may_load(deps.storage, &addr)? {
+            cfg.total_weight -= old;
+            VOTERS.remove(deps.storage, &addr);
+            events.push(
+                Event::new("remove_voter")
+                    .attr("old_weight", old.to_string())
+                    .attr("addr", addr),
+            );
+        }
+    }
+
+    // new proposals, as well as the open ones, must still be able to pass
+    Threshold::AbsoluteCount {
+        weight: cfg.required_weight,
+    }
+    .validate(cfg.total_weight)?;
+    check_open_proposals(deps.as_ref(), &env.block, cfg.total_weight)?;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response {
+        messages: vec![],
+        attributes: vec![attr("action", "update_voters"), attr("sender", info.sender)],
+        events,
+        data: None,
+    })
+}
+
+/// check_open_proposals returns an error if an open proposal could not pass anymore,
+/// even if all voters that did not vote yet vote yes with their (new) weight.
+/// This is not bounded: it loads every open proposal and every ballot on it, so the gas used
+/// by UpdateVoters grows with the number of open proposals (times the number of voters).
+/// It cannot be capped as it must check them all, but expired proposals are skipped.
+fn check_open_proposals(
+    deps: Deps,
+    block: &BlockInfo,
+    total_weight: u64,
+) -> Result<(), ContractError> {
+    let open = proposals()
+        .idx
+        .status
+        .prefix(U8Key::new(Status::Open as u8))
+        .range(deps.storage, None, None, Order::Ascending);
+    for item in open {
+        let (key, prop) = item?;
+        if prop.current_status(block) != Status::Open {
+            continue;
+        }
+        let proposal_id = parse_id(&key)?;
+        let mut voted_weight = 0;
+        for voter in
+            BALLOTS
+                .prefix(proposal_id.into())
+                .keys(deps.storage, None, None, Order::Ascending)
+        {
+            let voter = Addr::unchecked(String::from_utf8(voter).map_err(StdError::from)?);
+            voted_weight += VOTERS.may_load(deps.storage, &voter)?.unwrap_or_default();
+        }
+        if prop.yes_weight + total_weight - voted_weight < prop.required_weight {
+            return Err(ContractError::UnreachableProposal { proposal_id });
+        }
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // no downgrades
+    assert_contract_version(
+        deps.storage,
+        CONTRACT_NAME,
+        &format!("<={}", CONTRACT_VERSION),
+    )?;
+    // proposals from before the status index are not in it, add them.
+    // This goes through all proposals in one transaction
+    let count = index_proposals(deps.storage, &proposals())?;
+    migrate_version(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        env.block.height,
+    )?;
+    Ok(Response {
+        attributes: vec![attr("action", "migrate"), attr("indexed_proposals", count)],
+        ..Response::default()
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Threshold {} => to_binary(&query_threshold(deps)?),
         QueryMsg::Proposal { proposal_id } => to_binary(&query_proposal(deps, env, proposal_id)?),
         QueryMsg::Vote { proposal_id, voter } => to_binary(&query_vote(deps, proposal_id, voter)?),
-        QueryMsg::ListProposals { start_after, limit } => {
-            to_binary(&list_proposals(deps, env, start_after, limit)?)
-        }
+        QueryMsg::ListProposals {
+            start_after,
+            limit,
+            status,
+        } => to_binary(&list_proposals(deps, env, start_after, limit, status)?),
         QueryMsg::ReverseProposals {
             start_before,
             limit,
-        } => to_binary(&reverse_proposals(deps, env, start_before, limit)?),
+            status,
+        } => to_binary(&reverse_proposals(deps, env, start_before, limit, status)?),
         QueryMsg::ListVotes {
             proposal_id,
             start_after,
             limit,
         } => to_binary(&list_votes(deps, proposal_id, start_after, limit)?),
+        QueryMsg::ReverseVotes {
+            proposal_id,
+            start_before,
+            limit,
+        } => to_binary(&reverse_votes(deps, proposal_id, start_before, limit)?),
         QueryMsg::Voter { address } => to_binary(&query_voter(deps, address)?),
         QueryMsg::ListVoters { start_after, limit } => {
             to_binary(&list_voters(deps, start_after, limit)?)
@@ -298,26 +455,21 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
 
 fn query_threshold(deps: Deps) -> StdResult<ThresholdResponse> {
     let cfg = CONFIG.load(deps.storage)?;
-    Ok(ThresholdResponse::AbsoluteCount {
-        weight: cfg.required_weight,
-        total_weight: cfg.total_weight,
-    })
+    Ok(cfg.threshold())
 }
 
 fn query_proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse> {
-    let prop = PROPOSALS.load(deps.storage, id.into())?;
+    let prop = proposals().load(deps.storage, id.into())?;
     let status = prop.current_status(&env.block);
 
     let cfg = CONFIG.load(deps.storage)?;
-    let threshold = ThresholdResponse::AbsoluteCount {
-        weight: cfg.required_weight,
-        total_weight: cfg.total_weight,
-    };
+    let threshold = cfg.threshold();
     Ok(ProposalResponse {
         id,
         title: prop.title,
         description: prop.description,
         msgs: prop.msgs,
+        kind: prop.kind,
         status,
         expires: prop.expires,
         threshold,
@@ -333,22 +485,34 @@ fn list_proposals(
     env: Env,
     start_after: Option<u64>,
     limit: Option<u32>,
+    status: Option<Status>,
 ) -> StdResult<ProposalListResponse> {
     let cfg = CONFIG.load(deps.storage)?;
-    let threshold = ThresholdResponse::AbsoluteCount {
-        weight: cfg.required_weight,
-        total_weight: cfg.total_weight,
-    };
+    let threshold = cfg.threshold();
 
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive_int);
-    let props: StdResult<Vec<_>> = PROPOSALS
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
+    let proposals = proposals();
+    let (props, next) = range_proposals(
+        deps.storage,
+        &proposals,
+        &proposals.idx.status,
+        |p| p.current_status(&env.block),
+        start,
+        None,
+        Order::Ascending,
+        limit,
+        status,
+    )?;
+    let props: StdResult<Vec<_>> = props
+        .into_iter()
         .map(|p| map_proposal(&env.block, &threshold, p))
         .collect();
 
-    Ok(ProposalListResponse { proposals: props? })
+    Ok(ProposalListResponse {
+        proposals: props?,
+        next,
+    })
 }
 
 fn reverse_proposals(
@@ -356,36 +520,49 @@ fn reverse_proposals(
     env: Env,
     start_before: Option<u64>,
     limit: Option<u32>,
+    status: Option<Status>,
 ) -> StdResult<ProposalListResponse> {
     let cfg = CONFIG.load(deps.storage)?;
-    let threshold = ThresholdResponse::AbsoluteCount {
-        weight: cfg.required_weight,
-        total_weight: cfg.total_weight,
-    };
+    let threshold = cfg.threshold();
 
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let end = start_before.map(Bound::exclusive_int);
-    let props: StdResult<Vec<_>> = PROPOSALS
-        .range(deps.storage, None, end, Order::Descending)
-        .take(limit)
+    let proposals = proposals();
+    let (props, next) = range_proposals(
+        deps.storage,
+        &proposals,
+        &proposals.idx.status,
+        |p| p.current_status(&env.block),
+        None,
+        end,
+        Order::Descending,
+        limit,
+        status,
+    )?;
+    let props: StdResult<Vec<_>> = props
+        .into_iter()
         .map(|p| map_proposal(&env.block, &threshold, p))
         .collect();
 
-    Ok(ProposalListResponse { proposals: props? })
+    Ok(ProposalListResponse {
+        proposals: props?,
+        next,
+    })
 }
 
 fn map_proposal(
     block: &BlockInfo,
     threshold: &ThresholdResponse,
-    item: StdResult<(Vec<u8>, Proposal)>,
+    item: (Vec<u8>, Proposal),
 ) -> StdResult<ProposalResponse> {
-    let (key, prop) = item?;
+    let (key, prop) = item;
     let status = prop.current_status(block);
     Ok(ProposalResponse {
         id: parse_id(&key)?,
         title: prop.title,
         description: prop.description,
         msgs: prop.msgs,
+        kind: prop.kind,
         status,
         expires: prop.expires,
         threshold: threshold.clone(),
@@ -412,21 +589,37 @@ fn list_votes(
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive);
 
-    let votes: StdResult<Vec<_>> = BALLOTS
-        .prefix(proposal_id.into())
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
-        .map(|item| {
-            let (key, ballot) = item?;
-            Ok(VoteInfo {
-                voter: String::from_utf8(key)?,
-                vote: ballot.vote,
-                weight: ballot.weight,
-            })
-        })
-        .collect();
+    let votes = range_votes(
+        deps.storage,
+        &BALLOTS,
+        proposal_id,
+        start,
+        None,
+        Order::Ascending,
+        limit,
+    )?;
+    Ok(VoteListResponse { votes })
+}
+
+fn reverse_votes(
+    deps: Deps,
+    proposal_id: u64,
+    start_before: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<VoteListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_before.map(Bound::exclusive);
 
-    Ok(VoteListResponse { votes: votes? })
+    let votes = range_votes(
+        deps.storage,
+        &BALLOTS,
+        proposal_id,
+        None,
+        end,
+        Order::Descending,
+        limit,
+    )?;
+    Ok(VoteListResponse { votes })
 }
 
 fn query_voter(deps: Deps, voter: String) -> StdResult<VoterResponse> {
@@ -463,10 +656,11 @@ mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use cosmwasm_std::{coin, from_binary, BankMsg};
 
+    use cosmwasm_std::Storage;
     use cw0::Duration;
-    use cw2::{get_contract_version, ContractVersion};
-
-    use crate::msg::Voter;
+    use cw2::{get_contract_version, ContractVersion, VersionError};
+    use cw3::{ThresholdError, VotingPeriodError};
+    use cw_storage_plus::{Map, U64Key};
 
     use super::*;
 
@@ -518,6 +712,7 @@ mod tests {
             voters,
             required_weight,
             max_voting_period,
+            min_voting_period: None,
         };
         instantiate(deps, mock_env(), info, instantiate_msg)
     }
@@ -552,6 +747,7 @@ mod tests {
             voters: vec![],
             required_weight: 1,
             max_voting_period,
+            min_voting_period: None,
         };
         let err =
             instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap_err();
@@ -562,10 +758,11 @@ mod tests {
             voters: vec![voter(OWNER, 1)],
             required_weight: 0,
             max_voting_period,
+            min_voting_period: None,
         };
         let err =
             instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap_err();
-        assert_eq!(err, ContractError::ZeroWeight {});
+        assert_eq!(err, ContractError::Threshold(ThresholdError::ZeroWeight {}));
 
         // Total weight less than required weight not allowed
         let required_weight = 100;
@@ -576,7 +773,10 @@ mod tests {
             max_voting_period,
         )
         .unwrap_err();
-        assert_eq!(err, ContractError::UnreachableWeight {});
+        assert_eq!(
+            err,
+            ContractError::Threshold(ThresholdError::UnreachableWeight {})
+        );
 
         // All valid
         let required_weight = 1;
@@ -617,6 +817,7 @@ mod tests {
             description: "Do we reward her?".to_string(),
             msgs: msgs.clone(),
             latest: None,
+            voting_period: None,
         };
         let err = execute(deps.as_mut(), mock_env(), info, proposal.clone()).unwrap_err();
         assert_eq!(err, ContractError::Unauthorized {});
@@ -628,9 +829,13 @@ mod tests {
             description: "Do we reward her?".to_string(),
             msgs,
             latest: Some(Expiration::AtHeight(123456)),
+            voting_period: None,
         };
         let err = execute(deps.as_mut(), mock_env(), info, proposal_wrong_exp).unwrap_err();
-        assert_eq!(err, ContractError::WrongExpiration {});
+        assert_eq!(
+            err,
+            ContractError::VotingPeriod(VotingPeriodError::WrongExpiration {})
+        );
 
         // Proposal from voter works
         let info = mock_info(VOTER3, &[]);
@@ -690,6 +895,7 @@ mod tests {
             description: "Do I pay her?".to_string(),
             msgs,
             latest: None,
+            voting_period: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
 
@@ -811,6 +1017,7 @@ mod tests {
             description: "Do I pay her?".to_string(),
             msgs: msgs.clone(),
             latest: None,
+            voting_period: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
 
@@ -895,6 +1102,7 @@ mod tests {
             description: "Do I pay her?".to_string(),
             msgs: msgs.clone(),
             latest: None,
+            voting_period: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
 
@@ -918,6 +1126,7 @@ mod tests {
             description: "Pay somebody after time?".to_string(),
             msgs,
             latest: Some(Expiration::AtHeight(123456)),
+            voting_period: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
 
@@ -953,4 +1162,568 @@ mod tests {
         let err = execute(deps.as_mut(), mock_env(), info, closing).unwrap_err();
         assert_eq!(err, ContractError::WrongCloseStatus {});
     }
+
+    #[test]
+    fn list_proposals_by_status() {
+        let mut deps = mock_dependencies(&[]);
+
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info, 3, Duration::Height(2000000)).unwrap();
+
+        let propose = |deps: DepsMut, proposer: &str, latest: Option<Expiration>| {
+            let proposal = ExecuteMsg::Propose {
+                title: "Pay somebody".to_string(),
+                description: "Do I pay her?".to_string(),
+                msgs: vec![BankMsg::Send {
+                    to_address: SOMEBODY.into(),
+                    amount: vec![coin(1, "BTC")],
+                }
+                .into()],
+                latest,
+                voting_period: None,
+            };
+            execute(deps, mock_env(), mock_info(proposer, &[]), proposal).unwrap();
+        };
+        let soon = Some(Expiration::AtHeight(mock_env().block.height + 10));
+        // 1, 3 and 5 are open, 2, 4 and 6 pass right away, 7 and 8 expire soon
+        for proposer in &[VOTER1, VOTER3, VOTER1, VOTER3, VOTER1, VOTER3] {
+            propose(deps.as_mut(), proposer, None);
+        }
+        propose(deps.as_mut(), VOTER1, soon);
+        propose(deps.as_mut(), VOTER1, soon);
+        // 5 passes with another vote, 6 is executed
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 5,
+            vote: Vote::Yes,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER2, &[]), vote).unwrap();
+        let execution = ExecuteMsg::Execute { proposal_id: 6 };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER3, &[]), execution).unwrap();
+
+        // returns the ids, and checks they all have the status
+        let ids = |deps: Deps, env: Env, msg: QueryMsg, status: Status| -> Vec<u64> {
+            let res: ProposalListResponse = from_binary(&query(deps, env, msg).unwrap()).unwrap();
+            assert!(res.proposals.iter().all(|p| p.status == status));
+            res.proposals.into_iter().map(|p| p.id).collect()
+        };
+        let list = |start_after, limit, status| QueryMsg::ListProposals {
+            start_after,
+            limit,
+            status: Some(status),
+        };
+        let reverse = |start_before, limit, status| QueryMsg::ReverseProposals {
+            start_before,
+            limit,
+            status: Some(status),
+        };
+
+        let open = |start_after, limit| list(start_after, limit, Status::Open);
+        let env = mock_env();
+        assert_eq!(
+            ids(
+                deps.as_ref(),
+                env.clone(),
+                open(None, Some(3)),
+                Status::Open
+            ),
+            vec![1, 3, 7]
+        );
+        assert_eq!(
+            ids(
+                deps.as_ref(),
+                env.clone(),
+                open(Some(7), Some(3)),
+                Status::Open
+            ),
+            vec![8]
+        );
+
+        let passed = |start_after, limit| list(start_after, limit, Status::Passed);
+        assert_eq!(
+            ids(
+                deps.as_ref(),
+                env.clone(),
+                passed(None, Some(2)),
+                Status::Passed
+            ),
+            vec![2, 4]
+        );
+        assert_eq!(
+            ids(
+                deps.as_ref(),
+                env.clone(),
+                passed(Some(4), Some(2)),
+                Status::Passed
+            ),
+            vec![5]
+        );
+        let passed = |start_before, limit| reverse(start_before, limit, Status::Passed);
+        assert_eq!(
+            ids(
+                deps.as_ref(),
+                env.clone(),
+                passed(None, Some(2)),
+                Status::Passed
+            ),
+            vec![5, 4]
+        );
+        assert_eq!(
+            ids(
+                deps.as_ref(),
+                env.clone(),
+                passed(Some(4), Some(2)),
+                Status::Passed
+            ),
+            vec![2]
+        );
+
+        let executed = list(None, None, Status::Executed);
+        assert_eq!(
+            ids(deps.as_ref(), env.clone(), executed, Status::Executed),
+            vec![6]
+        );
+        let rejected = list(None, None, Status::Rejected);
+        assert_eq!(
+            ids(deps.as_ref(), env, rejected, Status::Rejected),
+            Vec::<u64>::new()
+        );
+
+        // once 7 and 8 expired they are rejected, whether they are closed or not
+        let env = mock_env_height(20);
+        let closing = ExecuteMsg::Close { proposal_id: 7 };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(SOMEBODY, &[]),
+            closing,
+        )
+        .unwrap();
+        assert_eq!(
+            ids(deps.as_ref(), env.clone(), open(None, None), Status::Open),
+            vec![1, 3]
+        );
+        let rejected = |start_after, limit| list(start_after, limit, Status::Rejected);
+        assert_eq!(
+            ids(
+                deps.as_ref(),
+                env.clone(),
+                rejected(None, Some(1)),
+                Status::Rejected
+            ),
+            vec![7]
+        );
+        assert_eq!(
+            ids(
+                deps.as_ref(),
+                env.clone(),
+                rejected(Some(7), Some(1)),
+                Status::Rejected
+            ),
+            vec![8]
+        );
+        let rejected = reverse(None, None, Status::Rejected);
+        assert_eq!(
+            ids(deps.as_ref(), env, rejected, Status::Rejected),
+            vec![8, 7]
+        );
+    }
+
+    #[test]
+    fn signaling_proposals_cannot_be_executed() {
+        let mut deps = mock_dependencies(&[]);
+
+        let info = mock_info(OWNER, &[]);
+        let voting_period = Duration::Time(2000000);
+        setup_test_case(deps.as_mut(), info, 3, voting_period).unwrap();
+
+        // a proposal without msgs is signaling, this one passes right away
+        let proposal = ExecuteMsg::Propose {
+            title: "Adopt the roadmap".to_string(),
+            description: "Do we agree?".to_string(),
+            msgs: vec![],
+            latest: None,
+            voting_period: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER3, &[]), proposal).unwrap();
+        let query_prop = |deps: Deps, env: Env| -> ProposalResponse {
+            let msg = QueryMsg::Proposal { proposal_id: 1 };
+            from_binary(&query(deps, env, msg).unwrap()).unwrap()
+        };
+        let prop = query_prop(deps.as_ref(), mock_env());
+        assert_eq!(prop.kind, ProposalKind::Signaling);
+        assert_eq!(prop.status, Status::Passed);
+
+        let execution = ExecuteMsg::Execute { proposal_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SOMEBODY, &[]),
+            execution,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::SignalingProposal {});
+
+        // it can be closed as done once it expired
+        let closing = ExecuteMsg::Close { proposal_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SOMEBODY, &[]),
+            closing.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotExpired {});
+        let env = mock_env_time(2000001);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(SOMEBODY, &[]),
+            closing.clone(),
+        )
+        .unwrap();
+        assert_eq!(query_prop(deps.as_ref(), env.clone()).status, Status::Done);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(SOMEBODY, &[]),
+            closing,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::WrongCloseStatus {});
+        // done proposals are no longer listed as passed
+        let passed = QueryMsg::ListProposals {
+            start_after: None,
+            limit: None,
+            status: Some(Status::Passed),
+        };
+        let res: ProposalListResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), passed).unwrap()).unwrap();
+        assert!(res.proposals.is_empty());
+        let done = QueryMsg::ListProposals {
+            start_after: None,
+            limit: None,
+            status: Some(Status::Done),
+        };
+        let res: ProposalListResponse =
+            from_binary(&query(deps.as_ref(), env, done).unwrap()).unwrap();
+        assert_eq!(res.proposals.len(), 1);
+
+        // proposals with msgs are executable
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: vec![BankMsg::Send {
+                to_address: SOMEBODY.into(),
+                amount: vec![coin(1, "BTC")],
+            }
+            .into()],
+            latest: None,
+            voting_period: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER3, &[]), proposal).unwrap();
+        let msg = QueryMsg::Proposal { proposal_id: 2 };
+        let prop: ProposalResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(prop.kind, ProposalKind::Executable);
+    }
+
+    #[test]
+    fn per_proposal_voting_period() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info(OWNER, &[]);
+        let instantiate_msg = |max_voting_period, min_voting_period| InstantiateMsg {
+            voters: vec![voter(VOTER1, 1), voter(VOTER2, 2)],
+            required_weight: 3,
+            max_voting_period,
+            min_voting_period,
+        };
+
+        // the min has to be in the same unit as the max, and not longer
+        for (max, min) in &[
+            (Duration::Time(1000), Duration::Height(10)),
+            (Duration::Time(1000), Duration::Time(1001)),
+            (Duration::Height(1000), Duration::Time(100)),
+        ] {
+            let err = instantiate(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                instantiate_msg(*max, Some(*min)),
+            )
+            .unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::VotingPeriod(VotingPeriodError::WrongMinVotingPeriod {})
+            );
+        }
+
+        let env = mock_env();
+        let (height, time) = (env.block.height, env.block.time);
+        let min = Duration::Height(10);
+        // the min may be the same as the max
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            instantiate_msg(min, Some(min)),
+        )
+        .unwrap();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            instantiate_msg(Duration::Height(1000), Some(min)),
+        )
+        .unwrap();
+
+        let mut proposal_id = 0;
+        let mut propose = |voting_period, latest| {
+            let msg = ExecuteMsg::Propose {
+                title: "Routine".to_string(),
+                description: "Needs a short window".to_string(),
+                msgs: vec![],
+                latest,
+                voting_period,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(VOTER1, &[]), msg)?;
+            proposal_id += 1;
+            let res = query_proposal(deps.as_ref(), env.clone(), proposal_id).unwrap();
+            Ok(res.expires)
+        };
+
+        // defaults to the max, longer periods are clamped to it
+        assert_eq!(propose(None, None), Ok(Expiration::AtHeight(height + 1000)));
+        assert_eq!(
+            propose(Some(Duration::Height(5000)), None),
+            Ok(Expiration::AtHeight(height + 1000))
+        );
+        assert_eq!(
+            propose(Some(Duration::Height(50)), None),
+            Ok(Expiration::AtHeight(height + 50))
+        );
+        // exactly the min is fine, anything shorter is not
+        assert_eq!(
+            propose(Some(min), None),
+            Ok(Expiration::AtHeight(height + 10))
+        );
+        assert_eq!(
+            propose(Some(Duration::Height(9)), None),
+            Err(ContractError::VotingPeriod(
+                VotingPeriodError::VotingPeriodTooShort { min }
+            ))
+        );
+        // the period has to be in the unit of the max
+        assert_eq!(
+            propose(Some(Duration::Time(500)), None),
+            Err(ContractError::VotingPeriod(
+                VotingPeriodError::WrongExpiration {}
+            ))
+        );
+
+        // latest and the voting period both limit the expiration, the earlier one applies
+        assert_eq!(
+            propose(
+                Some(Duration::Height(50)),
+                Some(Expiration::AtHeight(height + 20))
+            ),
+            Ok(Expiration::AtHeight(height + 20))
+        );
+        assert_eq!(
+            propose(
+                Some(Duration::Height(20)),
+                Some(Expiration::AtHeight(height + 50))
+            ),
+            Ok(Expiration::AtHeight(height + 20))
+        );
+        assert_eq!(
+            propose(Some(Duration::Height(50)), Some(Expiration::Never {})),
+            Ok(Expiration::AtHeight(height + 50))
+        );
+        // the min also applies to latest
+        assert_eq!(
+            propose(None, Some(Expiration::AtHeight(height + 5))),
+            Err(ContractError::VotingPeriod(
+                VotingPeriodError::VotingPeriodTooShort { min }
+            ))
+        );
+        assert_eq!(
+            propose(
+                Some(Duration::Height(50)),
+                Some(Expiration::AtTime(time.plus_seconds(100)))
+            ),
+            Err(ContractError::VotingPeriod(
+                VotingPeriodError::WrongExpiration {}
+            ))
+        );
+    }
+
+    #[test]
+    fn per_proposal_voting_period_in_time() {
+        let mut deps = mock_dependencies(&[]);
+        let min = Duration::Time(3600);
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![voter(VOTER1, 1), voter(VOTER2, 2)],
+            required_weight: 3,
+            max_voting_period: Duration::Time(7 * 86400),
+            min_voting_period: Some(min),
+        };
+        let env = mock_env();
+        let time = env.block.time;
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(OWNER, &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+
+        let mut proposal_id = 0;
+        let mut propose = |voting_period, latest| {
+            let msg = ExecuteMsg::Propose {
+                title: "Constitutional".to_string(),
+                description: "Needs a long window".to_string(),
+                msgs: vec![],
+                latest,
+                voting_period,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(VOTER2, &[]), msg)?;
+            proposal_id += 1;
+            let res = query_proposal(deps.as_ref(), env.clone(), proposal_id).unwrap();
+            Ok(res.expires)
+        };
+
+        assert_eq!(
+            propose(None, None),
+            Ok(Expiration::AtTime(time.plus_seconds(7 * 86400)))
+        );
+        assert_eq!(
+            propose(Some(Duration::Time(30 * 86400)), None),
+            Ok(Expiration::AtTime(time.plus_seconds(7 * 86400)))
+        );
+        assert_eq!(
+            propose(Some(Duration::Time(86400)), None),
+            Ok(Expiration::AtTime(time.plus_seconds(86400)))
+        );
+        assert_eq!(
+            propose(Some(Duration::Time(3599)), None),
+            Err(ContractError::VotingPeriod(
+                VotingPeriodError::VotingPeriodTooShort { min }
+            ))
+        );
+        assert_eq!(
+            propose(Some(Duration::Height(1000)), None),
+            Err(ContractError::VotingPeriod(
+                VotingPeriodError::WrongExpiration {}
+            ))
+        );
+        assert_eq!(
+            propose(
+                Some(Duration::Time(86400)),
+                Some(Expiration::AtTime(time.plus_seconds(7200)))
+            ),
+            Ok(Expiration::AtTime(time.plus_seconds(7200)))
+        );
+        assert_eq!(
+            propose(None, Some(Expiration::AtTime(time.plus_seconds(1800)))),
+            Err(ContractError::VotingPeriod(
+                VotingPeriodError::VotingPeriodTooShort { min }
+            ))
+        );
+    }
+
+    #[test]
+    fn update_voters_checks_many_open_proposals() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info, 10, Duration::Height(100)).unwrap();
+        let env = mock_env();
+
+        let propose = |deps: DepsMut| {
+            let msg = ExecuteMsg::Propose {
+                title: "Pay somebody".to_string(),
+                description: "Do I pay her?".to_string(),
+                msgs: vec![],
+                latest: None,
+                voting_period: None,
+            };
+            execute(deps, mock_env(), mock_info(VOTER1, &[]), msg).unwrap();
+        };
+        // 100 open proposals, with only the yes vote of the proposer
+        for _ in 0..100 {
+            propose(deps.as_mut());
+        }
+        // and the last one can only pass if all the others vote yes
+        propose(deps.as_mut());
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 101,
+            vote: Vote::No,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER5, &[]), vote).unwrap();
+
+        // adding a voter leaves all of them reachable
+        let contract = mock_info(env.contract.address.as_str(), &[]);
+        let msg = ExecuteMsg::UpdateVoters {
+            add: vec![voter(SOMEBODY, 1)],
+            remove: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), contract.clone(), msg).unwrap();
+
+        // removing VOTER3 leaves 1 + 13 - 6 for the last one, all the others are still fine
+        let msg = ExecuteMsg::UpdateVoters {
+            add: vec![],
+            remove: vec![VOTER3.to_string()],
+        };
+        let err = execute(deps.as_mut(), env, contract, msg).unwrap_err();
+        assert_eq!(ContractError::UnreachableProposal { proposal_id: 101 }, err);
+    }
+
+    #[test]
+    fn migrate_indexes_old_proposals() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info, 3, Duration::Height(2000000)).unwrap();
+
+        // a passed proposal stored before the status index (and kind) were added
+        let old = br#"{"title":"Pay somebody","description":"Do I pay her?",
+            "expires":{"at_height":2012345},"msgs":[{"bank":{"send":{"to_address":"somebody",
+            "amount":[{"denom":"BTC","amount":"1"}]}}}],"status":"passed","yes_weight":3,
+            "required_weight":3}"#;
+        let key = Map::<U64Key, Proposal>::new("proposals").key(1.into());
+        deps.storage.set(&key, old);
+
+        let passed = QueryMsg::ListProposals {
+            start_after: None,
+            limit: None,
+            status: Some(Status::Passed),
+        };
+        let ids = |deps: Deps| -> Vec<u64> {
+            let res: ProposalListResponse =
+                from_binary(&query(deps, mock_env(), passed.clone()).unwrap()).unwrap();
+            res.proposals.into_iter().map(|p| p.id).collect()
+        };
+        assert_eq!(ids(deps.as_ref()), Vec::<u64>::new());
+
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.6.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "migrate"), attr("indexed_proposals", 1)]
+        );
+        assert_eq!(ids(deps.as_ref()), vec![1]);
+        let query_prop = QueryMsg::Proposal { proposal_id: 1 };
+        let prop: ProposalResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), query_prop).unwrap()).unwrap();
+        assert_eq!(prop.kind, ProposalKind::Executable);
+
+        // no downgrades
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Version(VersionError::UnsupportedVersion {
+                version: "99.0.0".to_string(),
+                required: format!("<={}", CONTRACT_VERSION),
+            })
+        );
+    }
 }
@@ -1,4 +1,6 @@
 use cosmwasm_std::StdError;
+use cw2::VersionError;
+use cw3::{ThresholdError, VotingPeriodError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,11 +8,14 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("Required weight cannot be zero")]
-    ZeroWeight {},
+    #[error("{0}")]
+    Threshold(#[from] ThresholdError),
+
+    #[error("{0}")]
+    VotingPeriod(#[from] VotingPeriodError),
 
-    #[error("Not possible to reach required (passing) weight")]
-    UnreachableWeight {},
+    #[error("{0}")]
+    Version(#[from] VersionError),
 
     #[error("No voters")]
     NoVoters {},
@@ -27,15 +32,18 @@ pub enum ContractError {
     #[error("Proposal must expire before you can close it")]
     NotExpired {},
 
-    #[error("Wrong expiration option")]
-    WrongExpiration {},
-
     #[error("Already voted on this proposal")]
     AlreadyVoted {},
 
     #[error("Proposal must have passed and not yet been executed")]
     WrongExecuteStatus {},
 
+    #[error("Signaling proposals have no messages and cannot be executed")]
+    SignalingProposal {},
+
     #[error("Cannot close completed or passed proposals")]
     WrongCloseStatus {},
+
+    #[error("Open proposal {proposal_id} could not pass anymore with the new voters")]
+    UnreachableProposal { proposal_id: u64 },
 }
@@ -1,13 +1,14 @@
 #![cfg(test)]
 
 use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, Voter};
 use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
-use cosmwasm_std::{from_binary, to_binary, Addr, Empty, Uint128, WasmMsg, WasmQuery};
+use cosmwasm_std::{from_binary, to_binary, Addr, Empty, Event, Uint128, WasmMsg, WasmQuery};
 use cw0::Duration;
 use cw20::{BalanceResponse, MinterResponse};
 use cw20_base::msg::QueryMsg;
-use cw3::Vote;
+use cw3::{ThresholdResponse, Vote, VoterResponse};
 use cw_multi_test::{App, Contract, ContractWrapper, SimpleBank};
 
 fn mock_app() -> App {
@@ -60,6 +61,7 @@ fn cw3_controls_cw20() {
         ],
         required_weight: 2,
         max_voting_period: Duration::Height(3),
+        min_voting_period: None,
     };
 
     let multisig_addr = router
@@ -113,6 +115,7 @@ fn cw3_controls_cw20() {
         description: "Need to mint tokens".to_string(),
         msgs: vec![execute_mint_msg.into()],
         latest: None,
+        voting_period: None,
     };
     // propose mint
     router
@@ -149,3 +152,163 @@ fn cw3_controls_cw20() {
     // compare minted amount
     assert_eq!(balance.balance, mint_amount);
 }
+
+#[test]
+// cw3 multisig can rotate its own voters through a proposal
+fn cw3_updates_own_voters() {
+    let mut router = mock_app();
+    let cw3_id = router.store_code(contract_cw3_fixed_multisig());
+
+    let voter = |addr: &str, weight: u64| Voter {
+        addr: addr.to_string(),
+        weight,
+    };
+    let cw3_instantiate_msg = InstantiateMsg {
+        voters: vec![voter("addr1", 1), voter("addr2", 1), voter("addr3", 1)],
+        required_weight: 2,
+        max_voting_period: Duration::Height(10),
+        min_voting_period: None,
+    };
+    let multisig_addr = router
+        .instantiate_contract(
+            cw3_id,
+            Addr::unchecked("addr1"),
+            &cw3_instantiate_msg,
+            &[],
+            "Consortium",
+        )
+        .unwrap();
+
+    let update_voters = |add, remove| {
+        let msg = ExecuteMsg::UpdateVoters { add, remove };
+        WasmMsg::Execute {
+            contract_addr: multisig_addr.to_string(),
+            msg: to_binary(&msg).unwrap(),
+            funds: vec![],
+        }
+    };
+    let propose = |router: &mut App, proposer: &str, msg: WasmMsg| {
+        let propose_msg = ExecuteMsg::Propose {
+            title: "Rotate keys".to_string(),
+            description: "addr3 was compromised".to_string(),
+            msgs: vec![msg.into()],
+            latest: None,
+            voting_period: None,
+        };
+        router.execute_contract(
+            Addr::unchecked(proposer),
+            multisig_addr.clone(),
+            &propose_msg,
+            &[],
+        )
+    };
+    let vote = |router: &mut App, voter: &str, proposal_id: u64, vote: Vote| {
+        let vote_msg = ExecuteMsg::Vote { proposal_id, vote };
+        router
+            .execute_contract(
+                Addr::unchecked(voter),
+                multisig_addr.clone(),
+                &vote_msg,
+                &[],
+            )
+            .unwrap();
+    };
+    let execute_proposal = |router: &mut App, proposal_id: u64| {
+        let execute_msg = ExecuteMsg::Execute { proposal_id };
+        router.execute_contract(
+            Addr::unchecked("addr1"),
+            multisig_addr.clone(),
+            &execute_msg,
+            &[],
+        )
+    };
+    let weight = |router: &App, address: &str| -> Option<u64> {
+        let query = crate::msg::QueryMsg::Voter {
+            address: address.to_string(),
+        };
+        let res: VoterResponse = router
+            .wrap()
+            .query_wasm_smart(&multisig_addr, &query)
+            .unwrap();
+        res.weight
+    };
+
+    // voters cannot be changed directly, not even by a voter
+    let rotation = ExecuteMsg::UpdateVoters {
+        add: vec![voter("addr4", 1), voter("addr2", 2)],
+        remove: vec!["addr3".to_string()],
+    };
+    let err = router
+        .execute_contract(
+            Addr::unchecked("addr1"),
+            multisig_addr.clone(),
+            &rotation,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {}.to_string());
+
+    // replace addr3 by addr4, and give addr2 more weight
+    let msg = update_voters(
+        vec![voter("addr4", 1), voter("addr2", 2)],
+        vec!["addr3".to_string()],
+    );
+    propose(&mut router, "addr1", msg).unwrap();
+    vote(&mut router, "addr2", 1, Vote::Yes);
+    let res = execute_proposal(&mut router, 1).unwrap();
+    let expected = vec![
+        Event::new("add_voter")
+            .attr("addr", "addr4")
+            .attr("weight", "1"),
+        Event::new("update_voter")
+            .attr("old_weight", "1")
+            .attr("addr", "addr2")
+            .attr("weight", "2"),
+        Event::new("remove_voter")
+            .attr("old_weight", "1")
+            .attr("addr", "addr3"),
+    ];
+    assert_eq!(res.events, expected);
+
+    assert_eq!(weight(&router, "addr2"), Some(2));
+    assert_eq!(weight(&router, "addr3"), None);
+    assert_eq!(weight(&router, "addr4"), Some(1));
+    let threshold: ThresholdResponse = router
+        .wrap()
+        .query_wasm_smart(&multisig_addr, &crate::msg::QueryMsg::Threshold {})
+        .unwrap();
+    assert_eq!(
+        threshold,
+        ThresholdResponse::AbsoluteCount {
+            weight: 2,
+            total_weight: 4
+        }
+    );
+    // the old key cannot propose anymore, the new one can
+    let msg = update_voters(vec![], vec!["addr1".to_string()]);
+    let err = propose(&mut router, "addr3", msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {}.to_string());
+
+    // proposal 2 is open, with 1 yes and 1 no vote
+    propose(&mut router, "addr4", msg).unwrap();
+    vote(&mut router, "addr1", 2, Vote::No);
+
+    // without addr2, proposal 2 could not pass anymore
+    let msg = update_voters(vec![], vec!["addr2".to_string()]);
+    propose(&mut router, "addr2", msg).unwrap();
+    let err = execute_proposal(&mut router, 3).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnreachableProposal { proposal_id: 2 }.to_string()
+    );
+    // nor could new proposals
+    let msg = update_voters(vec![voter("addr4", 0)], vec!["addr2".to_string()]);
+    propose(&mut router, "addr2", msg).unwrap();
+    let err = execute_proposal(&mut router, 4).unwrap_err();
+    assert!(
+        err.contains("Not possible to reach required (passing) weight"),
+        "{}",
+        err
+    );
+    assert_eq!(weight(&router, "addr2"), Some(2));
+}
@@ -3,13 +3,15 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{CosmosMsg, Empty};
 use cw0::{Duration, Expiration};
-use cw3::Vote;
+use cw3::{Status, Vote};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct InstantiateMsg {
     pub voters: Vec<Voter>,
     pub required_weight: u64,
     pub max_voting_period: Duration,
+    /// proposals cannot pick a shorter voting period than this. None means there is no minimum
+    pub min_voting_period: Option<Duration>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -18,6 +20,9 @@ pub struct Voter {
     pub weight: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 // TODO: add some T variants? Maybe good enough as fixed Empty for now
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -28,6 +33,9 @@ pub enum ExecuteMsg {
         msgs: Vec<CosmosMsg<Empty>>,
         // note: we ignore API-spec'd earliest if passed, always opens immediately
         latest: Option<Expiration>,
+        /// voting period of this proposal, defaults to (and cannot be longer than)
+        /// max_voting_period. It must be at least min_voting_period
+        voting_period: Option<Duration>,
     },
     Vote {
         proposal_id: u64,
@@ -39,6 +47,12 @@ pub enum ExecuteMsg {
     Close {
         proposal_id: u64,
     },
+    /// Adds voters or updates their weight, and removes voters. Only accepted from the
+    /// multisig itself, so it has to be executed as part of a passed proposal
+    UpdateVoters {
+        add: Vec<Voter>,
+        remove: Vec<String>,
+    },
 }
 
 // We can also add this as a cw3 extension
@@ -53,11 +67,15 @@ pub enum QueryMsg {
     ListProposals {
         start_after: Option<u64>,
         limit: Option<u32>,
+        /// only list proposals with this (current) status
+        status: Option<Status>,
     },
     /// Returns ProposalListResponse
     ReverseProposals {
         start_before: Option<u64>,
         limit: Option<u32>,
+        /// only list proposals with this (current) status
+        status: Option<Status>,
     },
     /// Returns VoteResponse
     Vote { proposal_id: u64, voter: String },
@@ -67,6 +85,12 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Returns VoteListResponse
+    ReverseVotes {
+        proposal_id: u64,
+        start_before: Option<String>,
+        limit: Option<u32>,
+    },
     /// Returns VoterInfo
     Voter { address: String },
     /// Returns VoterListResponse
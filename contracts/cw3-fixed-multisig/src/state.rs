@@ -5,14 +5,25 @@ use std::convert::TryInto;
 use cosmwasm_std::{Addr, BlockInfo, CosmosMsg, Empty, StdError, StdResult, Storage};
 
 use cw0::{Duration, Expiration};
-use cw3::{Status, Vote};
-use cw_storage_plus::{Item, Map, U64Key};
+use cw3::{Ballot, ProposalKind, Status, Threshold, ThresholdResponse};
+use cw_storage_plus::{IndexList, IndexedMap, Item, Map, MultiIndex, U64Key, U8Key};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct Config {
     pub required_weight: u64,
     pub total_weight: u64,
     pub max_voting_period: Duration,
+    pub min_voting_period: Option<Duration>,
+}
+
+impl Config {
+    /// A fixed multisig always needs an absolute count of yes votes
+    pub fn threshold(&self) -> ThresholdResponse {
+        Threshold::AbsoluteCount {
+            weight: self.required_weight,
+        }
+        .to_response(self.total_weight)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -21,6 +32,9 @@ pub struct Proposal {
     pub description: String,
     pub expires: Expiration,
     pub msgs: Vec<CosmosMsg<Empty>>,
+    // Signaling if there are no msgs, see ProposalKind (proposals from before it are Executable)
+    #[serde(default)]
+    pub kind: ProposalKind,
     pub status: Status,
     /// how many votes have already said yes
     pub yes_weight: u64,
@@ -44,23 +58,32 @@ impl Proposal {
     }
 }
 
-// we cast a ballot with our chosen vote and a given weight
-// stored under the key that voted
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct Ballot {
-    pub weight: u64,
-    pub vote: Vote,
-}
-
 // unique items
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
 
 // multiple-item maps
 pub const VOTERS: Map<&Addr, u64> = Map::new("voters");
-pub const PROPOSALS: Map<U64Key, Proposal> = Map::new("proposals");
 pub const BALLOTS: Map<(U64Key, &Addr), Ballot> = Map::new("ballots");
 
+#[derive(IndexList)]
+#[index_list(Proposal)]
+pub struct ProposalIndexes<'a> {
+    /// the stored status, see cw3::stored_statuses
+    pub status: MultiIndex<'a, U8Key, Proposal>,
+}
+
+pub fn proposals<'a>() -> IndexedMap<'a, U64Key, Proposal, ProposalIndexes<'a>> {
+    let indexes = ProposalIndexes {
+        status: MultiIndex::new(
+            |p| U8Key::new(p.status as u8),
+            "proposals",
+            "proposals__status",
+        ),
+    };
+    IndexedMap::new("proposals", indexes)
+}
+
 pub fn next_id(store: &mut dyn Storage) -> StdResult<u64> {
     let id: u64 = PROPOSAL_COUNT.may_load(store)?.unwrap_or_default() + 1;
     PROPOSAL_COUNT.save(store, &id)?;
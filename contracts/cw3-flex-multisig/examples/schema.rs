@@ -3,7 +3,10 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, export_schema_with_title, remove_schemas, schema_for};
 
-use cw3_flex_multisig::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use cw3_flex_multisig::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, ProposalListResponse, ProposalResponse,
+    QueryMsg, ReceiveMsg, TallyResponse,
+};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -14,4 +17,10 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema_with_title(&schema_for!(ExecuteMsg), &out_dir, "ExecuteMsg");
     export_schema_with_title(&schema_for!(QueryMsg), &out_dir, "QueryMsg");
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(ReceiveMsg), &out_dir);
+    export_schema(&schema_for!(ProposalResponse), &out_dir);
+    export_schema(&schema_for!(ProposalListResponse), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(TallyResponse), &out_dir);
 }
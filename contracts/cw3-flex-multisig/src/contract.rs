@@ -1,25 +1,29 @@
-use std::cmp::Ordering;
-
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order,
-    Response, StdResult, SubMsg,
+    attr, coins, from_slice, to_binary, Addr, Api, BankMsg, Binary, BlockInfo, CosmosMsg, Decimal,
+    Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdResult, SubMsg, Uint128, WasmMsg,
 };
 
-use cw0::{maybe_addr, Expiration};
-use cw2::set_contract_version;
+use cw0::{maybe_addr, must_pay, Duration, Expiration, Scheduled};
+use cw2::{assert_contract_version, migrate_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, Denom};
 use cw3::{
-    ProposalListResponse, ProposalResponse, Status, ThresholdResponse, Vote, VoteInfo,
-    VoteListResponse, VoteResponse, VoterDetail, VoterListResponse, VoterResponse,
+    index_proposals, proposal_expiration, range_proposals, range_votes, validate_min_voting_period,
+    Ballot, ProposalKind, Status, ThresholdResponse, Vote, VoteInfo, VoteListResponse,
+    VoteResponse, VoterDetail, VoterListResponse, VoterResponse,
 };
 use cw4::{Cw4Contract, MemberChangedHookMsg, MemberDiff};
 use cw_storage_plus::Bound;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::msg::{
+    ConfigResponse, DepositInfo, ExecuteMsg, InstantiateMsg, MigrateMsg, ProposalListResponse,
+    ProposalResponse, QueryMsg, ReceiveMsg, TallyResponse, VotePercentages,
+};
 use crate::state::{
-    next_id, parse_id, Ballot, Config, Proposal, Votes, BALLOTS, CONFIG, PROPOSALS,
+    next_id, parse_id, proposals, Config, Deposit, DepositConfig, DepositStatus, Executor,
+    Proposal, Votes, BALLOTS, CONFIG,
 };
 
 // version info for migration info
@@ -40,19 +44,54 @@ pub fn instantiate(
     })?);
     let total_weight = group_addr.total_weight(&deps.querier)?;
     msg.threshold.validate(total_weight)?;
+    if let Some(min) = msg.min_voting_period {
+        validate_min_voting_period(min, msg.max_voting_period)?;
+    }
+    let deposit = msg
+        .deposit_info
+        .map(|info| validate_deposit(deps.api, info))
+        .transpose()?;
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     let cfg = Config {
         threshold: msg.threshold,
         max_voting_period: msg.max_voting_period,
+        min_voting_period: msg.min_voting_period,
         group_addr,
+        deposit,
+        early_execution: msg.early_execution,
+        executor: validate_executor(deps.api, msg.executor)?,
     };
     CONFIG.save(deps.storage, &cfg)?;
 
     Ok(Response::default())
 }
 
+fn validate_executor(api: &dyn Api, executor: Option<crate::msg::Executor>) -> StdResult<Executor> {
+    match executor {
+        None | Some(crate::msg::Executor::Anybody) => Ok(Executor::Anybody),
+        Some(crate::msg::Executor::OnlyMembers) => Ok(Executor::OnlyMembers),
+        Some(crate::msg::Executor::Addr(addr)) => Ok(Executor::Addr(api.addr_validate(&addr)?)),
+    }
+}
+
+fn validate_deposit(api: &dyn Api, info: DepositInfo) -> Result<DepositConfig, ContractError> {
+    if info.amount.is_zero() {
+        return Err(ContractError::ZeroDeposit {});
+    }
+    let denom = match info.denom {
+        Denom::Native(denom) => Denom::Native(denom),
+        Denom::Cw20(addr) => Denom::Cw20(api.addr_validate(addr.as_str())?),
+    };
+    Ok(DepositConfig {
+        denom,
+        amount: info.amount,
+        refund_failed: info.refund_failed,
+        forfeit_addr: maybe_addr(api, info.forfeit_addr)?,
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -66,16 +105,30 @@ pub fn execute(
             description,
             msgs,
             latest,
-        } => execute_propose(deps, env, info, title, description, msgs, latest),
+            voting_period,
+            start,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            msgs,
+            latest,
+            voting_period,
+            start,
+        ),
         ExecuteMsg::Vote { proposal_id, vote } => execute_vote(deps, env, info, proposal_id, vote),
         ExecuteMsg::Execute { proposal_id } => execute_execute(deps, env, info, proposal_id),
         ExecuteMsg::Close { proposal_id } => execute_close(deps, env, info, proposal_id),
         ExecuteMsg::MemberChangedHook(MemberChangedHookMsg { diffs }) => {
             execute_membership_hook(deps, env, info, diffs)
         }
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_propose(
     deps: DepsMut,
     env: Env,
@@ -85,53 +138,169 @@ pub fn execute_propose(
     msgs: Vec<CosmosMsg>,
     // we ignore earliest
     latest: Option<Expiration>,
+    voting_period: Option<Duration>,
+    start: Option<Scheduled>,
+) -> Result<Response<Empty>, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // native deposits are paid along with the proposal, cw20 ones only through Receive
+    if let Some(deposit) = &cfg.deposit {
+        let paid = match &deposit.denom {
+            Denom::Native(denom) => must_pay(&info, denom)?,
+            Denom::Cw20(_) => Uint128::zero(),
+        };
+        if paid != deposit.amount {
+            return Err(wrong_deposit(deposit));
+        }
+    }
+    propose(
+        deps,
+        env,
+        cfg,
+        info.sender,
+        title,
+        description,
+        msgs,
+        latest,
+        voting_period,
+        start,
+    )
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
 ) -> Result<Response<Empty>, ContractError> {
-    // only members of the multisig can create a proposal
+    // info.sender is the address of the cw20 contract (that re-sent this message).
+    // wrapper.sender is the address of the user that requested the cw20 contract to send this,
+    // and pays the deposit
+    let msg: ReceiveMsg = from_slice(&wrapper.msg)?;
     let cfg = CONFIG.load(deps.storage)?;
 
+    // ensure the sent token is the deposit
+    let deposit = cfg.deposit.as_ref().ok_or(ContractError::NoDeposit {})?;
+    match &deposit.denom {
+        Denom::Cw20(token) if *token == info.sender && wrapper.amount == deposit.amount => {}
+        _ => return Err(wrong_deposit(deposit)),
+    }
+    let proposer = deps.api.addr_validate(&wrapper.sender)?;
+    match msg {
+        ReceiveMsg::Propose {
+            title,
+            description,
+            msgs,
+            latest,
+            voting_period,
+            start,
+        } => propose(
+            deps,
+            env,
+            cfg,
+            proposer,
+            title,
+            description,
+            msgs,
+            latest,
+            voting_period,
+            start,
+        ),
+    }
+}
+
+fn wrong_deposit(deposit: &DepositConfig) -> ContractError {
+    let denom = match &deposit.denom {
+        Denom::Native(denom) => denom.clone(),
+        Denom::Cw20(token) => token.to_string(),
+    };
+    ContractError::WrongDeposit {
+        amount: deposit.amount,
+        denom,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn propose(
+    deps: DepsMut,
+    env: Env,
+    cfg: Config,
+    proposer: Addr,
+    title: String,
+    description: String,
+    msgs: Vec<CosmosMsg>,
+    // we ignore earliest
+    latest: Option<Expiration>,
+    voting_period: Option<Duration>,
+    start: Option<Scheduled>,
+) -> Result<Response<Empty>, ContractError> {
+    // only members of the multisig can create a proposal.
+    // All weights are taken from the snapshot at the start height, so membership changes
+    // while the proposal is open (or earlier in this block) do not affect it
+    let start_height = env.block.height;
     let vote_power = cfg
         .group_addr
-        .is_member(&deps.querier, &info.sender)?
+        .member_at_height(&deps.querier, proposer.clone(), start_height)?
         .ok_or(ContractError::Unauthorized {})?;
 
-    // max expires also used as default
-    let max_expires = cfg.max_voting_period.after(&env.block);
-    let mut expires = latest.unwrap_or(max_expires);
-    let comp = expires.partial_cmp(&max_expires);
-    if let Some(Ordering::Greater) = comp {
-        expires = max_expires;
-    } else if comp.is_none() {
-        return Err(ContractError::WrongExpiration {});
+    let expires = proposal_expiration(
+        &env.block,
+        cfg.max_voting_period,
+        cfg.min_voting_period,
+        voting_period,
+        latest,
+    )?;
+    // a proposal starting at or after its expiration could never be voted on
+    let starts_too_late = match (start, expires) {
+        (Some(Scheduled::AtHeight(start)), Expiration::AtHeight(end)) => start >= end,
+        (Some(Scheduled::AtTime(start)), Expiration::AtTime(end)) => start >= end,
+        _ => false,
+    };
+    if starts_too_late {
+        return Err(ContractError::WrongStart {});
     }
 
     // create a proposal
     let mut prop = Proposal {
         title,
         description,
-        start_height: env.block.height,
+        start_height,
+        start,
         expires,
+        kind: ProposalKind::from_msgs(&msgs),
         msgs,
         status: Status::Open,
         votes: Votes::new(vote_power),
         threshold: cfg.threshold,
-        total_weight: cfg.group_addr.total_weight(&deps.querier)?,
+        early_execution: cfg.early_execution,
+        total_weight: cfg
+            .group_addr
+            .total_weight_at_height(&deps.querier, start_height)?,
+        deposit: cfg.deposit.map(|deposit| Deposit {
+            depositor: proposer.clone(),
+            denom: deposit.denom,
+            amount: deposit.amount,
+            refund_failed: deposit.refund_failed,
+            forfeit_addr: deposit.forfeit_addr,
+            status: DepositStatus::Held,
+        }),
     };
     prop.update_status(&env.block);
     let id = next_id(deps.storage)?;
-    PROPOSALS.save(deps.storage, id.into(), &prop)?;
+    proposals().save(deps.storage, id.into(), &prop)?;
 
     // add the first yes vote from voter
     let ballot = Ballot {
         weight: vote_power,
         vote: Vote::Yes,
     };
-    BALLOTS.save(deps.storage, (id.into(), &info.sender), &ballot)?;
+    BALLOTS.save(deps.storage, (id.into(), &proposer), &ballot)?;
 
     Ok(Response {
         messages: vec![],
         attributes: vec![
             attr("action", "propose"),
-            attr("sender", info.sender),
+            attr("sender", proposer),
             attr("proposal_id", id),
             attr("status", format!("{:?}", prop.status)),
         ],
@@ -151,13 +320,20 @@ pub fn execute_vote(
     let cfg = CONFIG.load(deps.storage)?;
 
     // ensure proposal exists and can be voted on
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id.into())?;
+    let mut prop = proposals().load(deps.storage, proposal_id.into())?;
     if prop.status != Status::Open {
         return Err(ContractError::NotOpen {});
     }
+    if !prop.is_started(&env.block) {
+        return Err(ContractError::NotStarted {});
+    }
     if prop.expires.is_expired(&env.block) {
         return Err(ContractError::Expired {});
     }
+    // with early execution, a rejection may be decided before it expires
+    if prop.current_status(&env.block) != Status::Open {
+        return Err(ContractError::NotOpen {});
+    }
 
     // use a snapshot of "start of proposal"
     let vote_power = cfg
@@ -165,32 +341,42 @@ pub fn execute_vote(
         .member_at_height(&deps.querier, info.sender.clone(), prop.start_height)?
         .ok_or(ContractError::Unauthorized {})?;
 
-    // cast vote if no vote previously cast
-    BALLOTS.update(
+    // cast the vote, or change a previous one while the proposal is open
+    let key = (proposal_id.into(), &info.sender);
+    let previous = BALLOTS.may_load(deps.storage, key.clone())?;
+    if let Some(ballot) = &previous {
+        if ballot.vote == vote {
+            return Err(ContractError::AlreadyVoted {});
+        }
+        prop.votes.remove_vote(ballot.vote, ballot.weight);
+    }
+    BALLOTS.save(
         deps.storage,
-        (proposal_id.into(), &info.sender),
-        |bal| match bal {
-            Some(_) => Err(ContractError::AlreadyVoted {}),
-            None => Ok(Ballot {
-                weight: vote_power,
-                vote,
-            }),
+        key,
+        &Ballot {
+            weight: vote_power,
+            vote,
         },
     )?;
 
     // update vote tally
     prop.votes.add_vote(vote, vote_power);
     prop.update_status(&env.block);
-    PROPOSALS.save(deps.storage, proposal_id.into(), &prop)?;
-
+    proposals().save(deps.storage, proposal_id.into(), &prop)?;
+
+    let mut attributes = vec![
+        attr("action", "vote"),
+        attr("sender", info.sender),
+        attr("proposal_id", proposal_id),
+        attr("status", format!("{:?}", prop.status)),
+    ];
+    if let Some(ballot) = previous {
+        attributes.push(attr("previous_vote", format!("{:?}", ballot.vote)));
+        attributes.push(attr("vote", format!("{:?}", vote)));
+    }
     Ok(Response {
         messages: vec![],
-        attributes: vec![
-            attr("action", "vote"),
-            attr("sender", info.sender),
-            attr("proposal_id", proposal_id),
-            attr("status", format!("{:?}", prop.status)),
-        ],
+        attributes,
         events: vec![],
         data: None,
     })
@@ -198,36 +384,69 @@ pub fn execute_vote(
 
 pub fn execute_execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     proposal_id: u64,
 ) -> Result<Response, ContractError> {
-    // anyone can trigger this if the vote passed
+    // only the configured executor can trigger this if the vote passed
+    let cfg = CONFIG.load(deps.storage)?;
+    check_executor(deps.as_ref(), &cfg, &info.sender)?;
 
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id.into())?;
+    let mut prop = proposals().load(deps.storage, proposal_id.into())?;
     // we allow execution even after the proposal "expiration" as long as all vote come in before
     // that point. If it was approved on time, it can be executed any time.
+    if prop.kind == ProposalKind::Signaling {
+        return Err(ContractError::SignalingProposal {});
+    }
+    prop.update_status(&env.block);
     if prop.status != Status::Passed {
         return Err(ContractError::WrongExecuteStatus {});
     }
 
     // set it to executed
     prop.status = Status::Executed;
-    PROPOSALS.save(deps.storage, proposal_id.into(), &prop)?;
+    let mut attributes = vec![
+        attr("action", "execute"),
+        attr("sender", info.sender),
+        attr("proposal_id", proposal_id),
+    ];
+    // the deposit is always refunded once executed, unless that happened when it passed
+    let refund = match prop.deposit.as_mut() {
+        Some(deposit) if deposit.status == DepositStatus::Held => {
+            attributes.push(attr("deposit", "refunded"));
+            Some(release_deposit(deposit, true)?)
+        }
+        _ => None,
+    };
+    proposals().save(deps.storage, proposal_id.into(), &prop)?;
 
     // dispatch all proposed messages
     Ok(Response {
-        messages: prop.msgs.into_iter().map(SubMsg::new).collect(),
-        attributes: vec![
-            attr("action", "execute"),
-            attr("sender", info.sender),
-            attr("proposal_id", proposal_id),
-        ],
+        messages: prop
+            .msgs
+            .into_iter()
+            .map(SubMsg::new)
+            .chain(refund)
+            .collect(),
+        attributes,
         events: vec![],
         data: None,
     })
 }
 
+fn check_executor(deps: Deps, cfg: &Config, sender: &Addr) -> Result<(), ContractError> {
+    let allowed = match &cfg.executor {
+        Executor::Anybody => true,
+        Executor::OnlyMembers => cfg.group_addr.is_member(&deps.querier, sender)?.is_some(),
+        Executor::Addr(addr) => addr == sender,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(ContractError::Unauthorized {})
+    }
+}
+
 pub fn execute_close(
     deps: DepsMut,
     env: Env,
@@ -236,40 +455,171 @@ pub fn execute_close(
 ) -> Result<Response<Empty>, ContractError> {
     // anyone can trigger this if the vote passed
 
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id.into())?;
-    if [Status::Executed, Status::Rejected, Status::Passed]
-        .iter()
-        .any(|x| *x == prop.status)
+    let mut prop = proposals().load(deps.storage, proposal_id.into())?;
+    if prop.kind == ProposalKind::Signaling && prop.current_status(&env.block) == Status::Passed {
+        return close_signaling(deps, env, info, proposal_id, prop);
+    }
+    if prop.current_status(&env.block) == Status::Passed {
+        return refund_passed(deps, env, info, proposal_id, prop);
+    }
+    if [
+        Status::Executed,
+        Status::Done,
+        Status::Rejected,
+        Status::Passed,
+    ]
+    .iter()
+    .any(|x| *x == prop.status)
     {
         return Err(ContractError::WrongCloseStatus {});
     }
-    if !prop.expires.is_expired(&env.block) {
-        return Err(ContractError::NotExpired {});
+    // it can be closed once expired, or earlier once it cannot pass anymore (with early execution)
+    match prop.current_status(&env.block) {
+        Status::Rejected => {}
+        Status::Passed => return Err(ContractError::WrongCloseStatus {}),
+        _ => return Err(ContractError::NotExpired {}),
     }
 
     // set it to failed
     prop.status = Status::Rejected;
-    PROPOSALS.save(deps.storage, proposal_id.into(), &prop)?;
+    let mut messages = vec![];
+    let mut attributes = vec![
+        attr("action", "close"),
+        attr("sender", info.sender),
+        attr("proposal_id", proposal_id),
+    ];
+    if let Some(deposit) = prop.deposit.as_mut() {
+        let refund = deposit.refund_failed;
+        messages.push(release_deposit(deposit, refund)?);
+        let released = if refund { "refunded" } else { "forfeited" };
+        attributes.push(attr("deposit", released));
+    }
+    proposals().save(deps.storage, proposal_id.into(), &prop)?;
 
     Ok(Response {
-        messages: vec![],
+        messages,
+        attributes,
+        events: vec![],
+        data: None,
+    })
+}
+
+/// close_signaling finishes a passed signaling proposal once it expired, as there is
+/// nothing to execute. It is marked Done, and its deposit is refunded like it would be
+/// on execution.
+fn close_signaling(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    mut prop: Proposal,
+) -> Result<Response<Empty>, ContractError> {
+    if !prop.expires.is_expired(&env.block) {
+        return Err(ContractError::NotExpired {});
+    }
+    prop.status = Status::Done;
+    let mut messages = vec![];
+    let mut attributes = vec![
+        attr("action", "close"),
+        attr("sender", info.sender),
+        attr("proposal_id", proposal_id),
+    ];
+    if let Some(deposit) = prop.deposit.as_mut() {
+        if deposit.status == DepositStatus::Held {
+            messages.push(release_deposit(deposit, true)?);
+            attributes.push(attr("deposit", "refunded"));
+        }
+    }
+    proposals().save(deps.storage, proposal_id.into(), &prop)?;
+
+    Ok(Response {
+        messages,
+        attributes,
+        events: vec![],
+        data: None,
+    })
+}
+
+/// refund_passed refunds the deposit of a passed proposal, without executing it, so the
+/// deposit is not locked if its messages fail or nobody executes it. The proposal stays
+/// Passed, and can still be executed.
+fn refund_passed(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    mut prop: Proposal,
+) -> Result<Response<Empty>, ContractError> {
+    let deposit = match prop.deposit.as_mut() {
+        Some(deposit) if deposit.status == DepositStatus::Held => deposit,
+        _ => return Err(ContractError::WrongCloseStatus {}),
+    };
+    let refund = release_deposit(deposit, true)?;
+    prop.update_status(&env.block);
+    proposals().save(deps.storage, proposal_id.into(), &prop)?;
+
+    Ok(Response {
+        messages: vec![refund],
         attributes: vec![
             attr("action", "close"),
             attr("sender", info.sender),
             attr("proposal_id", proposal_id),
+            attr("deposit", "refunded"),
         ],
         events: vec![],
         data: None,
     })
 }
 
+/// release_deposit sends the deposit back to the depositor if refund is true,
+/// otherwise to the forfeit address, or burns it if there is none.
+/// It updates the status of the deposit, which must be saved along with the proposal.
+fn release_deposit(deposit: &mut Deposit, refund: bool) -> StdResult<SubMsg> {
+    let recipient = if refund {
+        deposit.status = DepositStatus::Refunded;
+        Some(deposit.depositor.clone())
+    } else {
+        deposit.status = DepositStatus::Forfeited;
+        deposit.forfeit_addr.clone()
+    };
+    let msg: CosmosMsg = match (&deposit.denom, recipient) {
+        (Denom::Native(denom), Some(recipient)) => BankMsg::Send {
+            to_address: recipient.into(),
+            amount: coins(deposit.amount.u128(), denom),
+        }
+        .into(),
+        (Denom::Native(denom), None) => BankMsg::Burn {
+            amount: coins(deposit.amount.u128(), denom),
+        }
+        .into(),
+        (Denom::Cw20(token), recipient) => {
+            let msg = match recipient {
+                Some(recipient) => Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.into(),
+                    amount: deposit.amount,
+                },
+                None => Cw20ExecuteMsg::Burn {
+                    amount: deposit.amount,
+                },
+            };
+            WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_binary(&msg)?,
+                funds: vec![],
+            }
+            .into()
+        }
+    };
+    Ok(SubMsg::new(msg))
+}
+
 pub fn execute_membership_hook(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     _diffs: Vec<MemberDiff>,
 ) -> Result<Response<Empty>, ContractError> {
-    // This is now a no-op
+    // This is now a no-op, as proposals use the weights from the snapshot at their start height.
     // But we leave the authorization check as a demo
     let cfg = CONFIG.load(deps.storage)?;
     if info.sender != cfg.group_addr.0 {
@@ -279,24 +629,57 @@ pub fn execute_membership_hook(
     Ok(Response::default())
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // no downgrades
+    assert_contract_version(
+        deps.storage,
+        CONTRACT_NAME,
+        &format!("<={}", CONTRACT_VERSION),
+    )?;
+    // proposals from before the status index are not in it, add them.
+    // This goes through all proposals in one transaction
+    let count = index_proposals(deps.storage, &proposals())?;
+    migrate_version(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        env.block.height,
+    )?;
+    Ok(Response {
+        attributes: vec![attr("action", "migrate"), attr("indexed_proposals", count)],
+        ..Response::default()
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Threshold {} => to_binary(&query_threshold(deps)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::Proposal { proposal_id } => to_binary(&query_proposal(deps, env, proposal_id)?),
+        QueryMsg::Tally { proposal_id } => to_binary(&query_tally(deps, env, proposal_id)?),
         QueryMsg::Vote { proposal_id, voter } => to_binary(&query_vote(deps, proposal_id, voter)?),
-        QueryMsg::ListProposals { start_after, limit } => {
-            to_binary(&list_proposals(deps, env, start_after, limit)?)
-        }
+        QueryMsg::ListProposals {
+            start_after,
+            limit,
+            status,
+        } => to_binary(&list_proposals(deps, env, start_after, limit, status)?),
         QueryMsg::ReverseProposals {
             start_before,
             limit,
-        } => to_binary(&reverse_proposals(deps, env, start_before, limit)?),
+            status,
+        } => to_binary(&reverse_proposals(deps, env, start_before, limit, status)?),
         QueryMsg::ListVotes {
             proposal_id,
             start_after,
             limit,
         } => to_binary(&list_votes(deps, proposal_id, start_after, limit)?),
+        QueryMsg::ReverseVotes {
+            proposal_id,
+            start_before,
+            limit,
+        } => to_binary(&reverse_votes(deps, proposal_id, start_before, limit)?),
         QueryMsg::Voter { address } => to_binary(&query_voter(deps, address)?),
         QueryMsg::ListVoters { start_after, limit } => {
             to_binary(&list_voters(deps, start_after, limit)?)
@@ -310,8 +693,21 @@ fn query_threshold(deps: Deps) -> StdResult<ThresholdResponse> {
     Ok(cfg.threshold.to_response(total_weight))
 }
 
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        threshold: cfg.threshold,
+        max_voting_period: cfg.max_voting_period,
+        min_voting_period: cfg.min_voting_period,
+        group_addr: cfg.group_addr,
+        deposit: cfg.deposit,
+        early_execution: cfg.early_execution,
+        executor: cfg.executor,
+    })
+}
+
 fn query_proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse> {
-    let prop = PROPOSALS.load(deps.storage, id.into())?;
+    let prop = proposals().load(deps.storage, id.into())?;
     let status = prop.current_status(&env.block);
     let threshold = prop.threshold.to_response(prop.total_weight);
     Ok(ProposalResponse {
@@ -319,9 +715,33 @@ fn query_proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse>
         title: prop.title,
         description: prop.description,
         msgs: prop.msgs,
+        kind: prop.kind,
         status,
         expires: prop.expires,
         threshold,
+        deposit: prop.deposit,
+    })
+}
+
+fn query_tally(deps: Deps, env: Env, id: u64) -> StdResult<TallyResponse> {
+    let prop = proposals().load(deps.storage, id.into())?;
+    let share = |weight: u64| match prop.total_weight {
+        0 => Decimal::zero(),
+        total => Decimal::from_ratio(weight, total),
+    };
+    let percentages = VotePercentages {
+        yes: share(prop.votes.yes),
+        no: share(prop.votes.no),
+        abstain: share(prop.votes.abstain),
+        veto: share(prop.votes.veto),
+    };
+    Ok(TallyResponse {
+        percentages,
+        total_weight: prop.total_weight,
+        quorum_reached: prop.quorum_reached(),
+        threshold_reached: prop.threshold_reached(),
+        status_if_expired: prop.status_if_expired(&env.block),
+        votes: prop.votes,
     })
 }
 
@@ -334,16 +754,31 @@ fn list_proposals(
     env: Env,
     start_after: Option<u64>,
     limit: Option<u32>,
+    status: Option<Status>,
 ) -> StdResult<ProposalListResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive_int);
-    let props: StdResult<Vec<_>> = PROPOSALS
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
+    let proposals = proposals();
+    let (props, next) = range_proposals(
+        deps.storage,
+        &proposals,
+        &proposals.idx.status,
+        |p| p.current_status(&env.block),
+        start,
+        None,
+        Order::Ascending,
+        limit,
+        status,
+    )?;
+    let props: StdResult<Vec<_>> = props
+        .into_iter()
         .map(|p| map_proposal(&env.block, p))
         .collect();
 
-    Ok(ProposalListResponse { proposals: props? })
+    Ok(ProposalListResponse {
+        proposals: props?,
+        next,
+    })
 }
 
 fn reverse_proposals(
@@ -351,23 +786,35 @@ fn reverse_proposals(
     env: Env,
     start_before: Option<u64>,
     limit: Option<u32>,
+    status: Option<Status>,
 ) -> StdResult<ProposalListResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let end = start_before.map(Bound::exclusive_int);
-    let props: StdResult<Vec<_>> = PROPOSALS
-        .range(deps.storage, None, end, Order::Descending)
-        .take(limit)
+    let proposals = proposals();
+    let (props, next) = range_proposals(
+        deps.storage,
+        &proposals,
+        &proposals.idx.status,
+        |p| p.current_status(&env.block),
+        None,
+        end,
+        Order::Descending,
+        limit,
+        status,
+    )?;
+    let props: StdResult<Vec<_>> = props
+        .into_iter()
         .map(|p| map_proposal(&env.block, p))
         .collect();
 
-    Ok(ProposalListResponse { proposals: props? })
+    Ok(ProposalListResponse {
+        proposals: props?,
+        next,
+    })
 }
 
-fn map_proposal(
-    block: &BlockInfo,
-    item: StdResult<(Vec<u8>, Proposal)>,
-) -> StdResult<ProposalResponse> {
-    let (key, prop) = item?;
+fn map_proposal(block: &BlockInfo, item: (Vec<u8>, Proposal)) -> StdResult<ProposalResponse> {
+    let (key, prop) = item;
     let status = prop.current_status(block);
     let threshold = prop.threshold.to_response(prop.total_weight);
     Ok(ProposalResponse {
@@ -375,9 +822,11 @@ fn map_proposal(
         title: prop.title,
         description: prop.description,
         msgs: prop.msgs,
+        kind: prop.kind,
         status,
         expires: prop.expires,
         threshold,
+        deposit: prop.deposit,
     })
 }
 
@@ -402,21 +851,38 @@ fn list_votes(
     let addr = maybe_addr(deps.api, start_after)?;
     let start = addr.map(|addr| Bound::exclusive(addr.as_ref()));
 
-    let votes: StdResult<Vec<_>> = BALLOTS
-        .prefix(proposal_id.into())
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
-        .map(|item| {
-            let (voter, ballot) = item?;
-            Ok(VoteInfo {
-                voter: String::from_utf8(voter)?,
-                vote: ballot.vote,
-                weight: ballot.weight,
-            })
-        })
-        .collect();
+    let votes = range_votes(
+        deps.storage,
+        &BALLOTS,
+        proposal_id,
+        start,
+        None,
+        Order::Ascending,
+        limit,
+    )?;
+    Ok(VoteListResponse { votes })
+}
+
+fn reverse_votes(
+    deps: Deps,
+    proposal_id: u64,
+    start_before: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<VoteListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_before)?;
+    let end = addr.map(|addr| Bound::exclusive(addr.as_ref()));
 
-    Ok(VoteListResponse { votes: votes? })
+    let votes = range_votes(
+        deps.storage,
+        &BALLOTS,
+        proposal_id,
+        None,
+        end,
+        Order::Descending,
+        limit,
+    )?;
+    Ok(VoteListResponse { votes })
 }
 
 fn query_voter(deps: Deps, voter: String) -> StdResult<VoterResponse> {
@@ -447,17 +913,21 @@ fn list_voters(
 
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
-    use cosmwasm_std::{coin, coins, Addr, BankMsg, Coin, Decimal, Timestamp};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockStorage};
+    use cosmwasm_std::{
+        coin, coins, from_binary, Addr, BankMsg, Coin, Decimal, Storage, Timestamp,
+    };
 
-    use cw0::Duration;
-    use cw2::{query_contract_info, ContractVersion};
+    use cw0::{Duration, PaymentError};
+    use cw2::{query_contract_info, ContractVersion, VersionError};
+    use cw20::{BalanceResponse, Cw20Coin, Cw20QueryMsg, TokenInfoResponse};
     use cw4::{Cw4ExecuteMsg, Member};
     use cw4_group::helpers::Cw4GroupContract;
     use cw_multi_test::{next_block, App, Contract, ContractWrapper, SimpleBank};
+    use cw_storage_plus::{Map, U64Key};
 
     use super::*;
-    use crate::msg::Threshold;
+    use cw3::{Threshold, ThresholdError, VotingPeriodError};
 
     const OWNER: &str = "admin0001";
     const VOTER1: &str = "voter0001";
@@ -488,6 +958,16 @@ mod tests {
             cw4_group::contract::execute,
             cw4_group::contract::instantiate,
             cw4_group::contract::query,
+        )
+        .with_reply(cw4_group::contract::reply);
+        Box::new(contract)
+    }
+
+    pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            cw20_base::contract::execute,
+            cw20_base::contract::instantiate,
+            cw20_base::contract::query,
         );
         Box::new(contract)
     }
@@ -516,12 +996,18 @@ mod tests {
         group: Addr,
         threshold: Threshold,
         max_voting_period: Duration,
+        deposit_info: Option<DepositInfo>,
+        early_execution: bool,
     ) -> Addr {
         let flex_id = app.store_code(contract_flex());
         let msg = crate::msg::InstantiateMsg {
             group_addr: group.to_string(),
             threshold,
             max_voting_period,
+            min_voting_period: None,
+            deposit_info,
+            early_execution,
+            executor: None,
         };
         app.instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex")
             .unwrap()
@@ -568,7 +1054,14 @@ mod tests {
         app.update_block(next_block);
 
         // 2. Set up Multisig backed by this group
-        let flex_addr = instantiate_flex(app, group_addr.clone(), threshold, max_voting_period);
+        let flex_addr = instantiate_flex(
+            app,
+            group_addr.clone(),
+            threshold,
+            max_voting_period,
+            None,
+            false,
+        );
         app.update_block(next_block);
 
         // 3. (Optional) Set the multisig as the group owner
@@ -611,6 +1104,8 @@ mod tests {
             description,
             msgs,
             latest: None,
+            voting_period: None,
+            start: None,
         }
     }
 
@@ -629,6 +1124,10 @@ mod tests {
             group_addr: group_addr.to_string(),
             threshold: Threshold::AbsoluteCount { weight: 0 },
             max_voting_period,
+            min_voting_period: None,
+            deposit_info: None,
+            early_execution: false,
+            executor: None,
         };
         let err = app
             .instantiate_contract(
@@ -639,13 +1138,20 @@ mod tests {
                 "zero required weight",
             )
             .unwrap_err();
-        assert_eq!(err, ContractError::ZeroThreshold {}.to_string());
+        assert_eq!(
+            err,
+            ContractError::Threshold(ThresholdError::ZeroWeight {}).to_string()
+        );
 
         // Total weight less than required weight not allowed
         let instantiate_msg = InstantiateMsg {
             group_addr: group_addr.to_string(),
             threshold: Threshold::AbsoluteCount { weight: 100 },
             max_voting_period,
+            min_voting_period: None,
+            deposit_info: None,
+            early_execution: false,
+            executor: None,
         };
         let err = app
             .instantiate_contract(
@@ -656,13 +1162,46 @@ mod tests {
                 "high required weight",
             )
             .unwrap_err();
-        assert_eq!(err, ContractError::UnreachableThreshold {}.to_string());
+        assert_eq!(
+            err,
+            ContractError::Threshold(ThresholdError::UnreachableWeight {}).to_string()
+        );
+
+        // Zero deposit not allowed
+        let instantiate_msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 1 },
+            max_voting_period,
+            min_voting_period: None,
+            deposit_info: Some(DepositInfo {
+                denom: Denom::Native("DEPO".to_string()),
+                amount: Uint128::zero(),
+                refund_failed: false,
+                forfeit_addr: None,
+            }),
+            early_execution: false,
+            executor: None,
+        };
+        let err = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate_msg,
+                &[],
+                "zero deposit",
+            )
+            .unwrap_err();
+        assert_eq!(err, ContractError::ZeroDeposit {}.to_string());
 
         // All valid
         let instantiate_msg = InstantiateMsg {
             group_addr: group_addr.to_string(),
             threshold: Threshold::AbsoluteCount { weight: 1 },
             max_voting_period,
+            min_voting_period: None,
+            deposit_info: None,
+            early_execution: false,
+            executor: None,
         };
         let flex_addr = app
             .instantiate_contract(
@@ -735,6 +1274,8 @@ mod tests {
             description: "Do we reward her?".to_string(),
             msgs,
             latest: Some(Expiration::AtHeight(123456)),
+            voting_period: None,
+            start: None,
         };
         let err = app
             .execute_contract(
@@ -744,7 +1285,7 @@ mod tests {
                 &[],
             )
             .unwrap_err();
-        assert_eq!(err, ContractError::WrongExpiration {}.to_string());
+        assert_eq!(err, VotingPeriodError::WrongExpiration {}.to_string());
 
         // Proposal from voter works
         let res = app
@@ -858,6 +1399,7 @@ mod tests {
         let list_query = QueryMsg::ListProposals {
             start_after: None,
             limit: None,
+            status: None,
         };
         let res: ProposalListResponse = app
             .wrap()
@@ -886,6 +1428,7 @@ mod tests {
         let list_query = QueryMsg::ReverseProposals {
             start_before: None,
             limit: Some(1),
+            status: None,
         };
         let res: ProposalListResponse = app
             .wrap()
@@ -899,12 +1442,14 @@ mod tests {
             title,
             description,
             msgs,
+            kind: ProposalKind::Executable,
             expires: voting_period.after(&proposed_at),
             status: Status::Open,
             threshold: ThresholdResponse::AbsoluteCount {
                 weight: 3,
                 total_weight: 15,
             },
+            deposit: None,
         };
         assert_eq!(&expected, &res.proposals[0]);
     }
@@ -988,8 +1533,9 @@ mod tests {
         // Tally unchanged
         assert_eq!(tally, get_tally(&app, flex_addr.as_ref(), proposal_id));
 
+        // Voting the same again is rejected
         let err = app
-            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &yes_vote, &[])
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &veto_vote, &[])
             .unwrap_err();
         assert_eq!(ContractError::AlreadyVoted {}.to_string(), err);
 
@@ -1062,34 +1608,218 @@ mod tests {
     }
 
     #[test]
-    fn test_execute_works() {
+    fn test_change_vote() {
         let mut app = mock_app();
 
-        let required_weight = 3;
-        let voting_period = Duration::Time(2000000);
-        let (flex_addr, _) = setup_test_case_fixed(
+        // 20% required for quora, which is 3 of the initial 15
+        // 50% yes required to pass early (8 of the initial 15)
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, _) = setup_test_case(
             &mut app,
-            required_weight,
+            Threshold::ThresholdQuorum {
+                threshold: Decimal::percent(50),
+                quorum: Decimal::percent(20),
+            },
             voting_period,
             coins(10, "BTC"),
-            true,
+            false,
         );
+        let vote = |proposal_id: u64, vote: Vote| ExecuteMsg::Vote { proposal_id, vote };
+        let prop_status = |app: &App, proposal_id: u64| -> Status {
+            let query_prop = QueryMsg::Proposal { proposal_id };
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &query_prop)
+                .unwrap();
+            prop.status
+        };
 
-        // ensure we have cash to cover the proposal
-        let contract_bal = app.wrap().query_balance(&flex_addr, "BTC").unwrap();
-        assert_eq!(contract_bal, coin(10, "BTC"));
-
-        // create proposal with 0 vote power
-        let proposal = pay_somebody_proposal();
+        // VOTER5 proposes (5 yes), VOTER4 is against it (4 no)
         let res = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+            .execute_contract(
+                Addr::unchecked(VOTER5),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
             .unwrap();
-
-        // Get the proposal id from the logs
         let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        let no = vote(proposal_id, Vote::No);
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &no, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app, proposal_id), Status::Open);
 
-        // Only Passed can be executed
-        let execution = ExecuteMsg::Execute { proposal_id };
+        // VOTER4 changes their mind, which moves their weight to yes: 9 of 15 passes
+        let yes = vote(proposal_id, Vote::Yes);
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes, &[])
+            .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "vote"),
+                attr("sender", VOTER4),
+                attr("proposal_id", proposal_id),
+                attr("status", "Passed"),
+                attr("previous_vote", "No"),
+                attr("vote", "Yes"),
+            ],
+        );
+        assert_eq!(get_tally(&app, flex_addr.as_ref(), proposal_id), 9);
+        let voter = VOTER4.into();
+        let ballot: VoteResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Vote { proposal_id, voter })
+            .unwrap();
+        assert_eq!(
+            ballot.vote.unwrap(),
+            VoteInfo {
+                voter: VOTER4.into(),
+                vote: Vote::Yes,
+                weight: 4
+            }
+        );
+
+        // once passed, votes cannot be changed anymore, even before it expires
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &no, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::NotOpen {}.to_string(), err);
+
+        // VOTER3 proposes (3 yes), VOTER2 is against it (2 no).
+        // This would pass once expired (3 of 5 votes), but VOTER3 changes to no
+        let res = app
+            .execute_contract(
+                Addr::unchecked(VOTER3),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+            .unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        let no = vote(proposal_id, Vote::No);
+        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &no, &[])
+            .unwrap();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &no, &[])
+            .unwrap();
+        assert_eq!(res.attributes[3], attr("status", "Open"));
+        assert_eq!(res.attributes[4], attr("previous_vote", "Yes"));
+        assert_eq!(res.attributes[5], attr("vote", "No"));
+        assert_eq!(get_tally(&app, flex_addr.as_ref(), proposal_id), 0);
+
+        app.update_block(expire(voting_period));
+        assert_eq!(prop_status(&app, proposal_id), Status::Rejected);
+        let yes = vote(proposal_id, Vote::Yes);
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &yes, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Expired {}.to_string(), err);
+    }
+
+    #[test]
+    fn test_vote_after_start() {
+        let mut app = mock_app();
+
+        let required_weight = 3;
+        let voting_period = Duration::Height(2000);
+        let (flex_addr, _) = setup_test_case_fixed(
+            &mut app,
+            required_weight,
+            voting_period,
+            coins(10, "BTC"),
+            false,
+        );
+
+        let height = app.block_info().height;
+        let (msgs, title, description) = proposal_info();
+        let delayed = |start: u64| ExecuteMsg::Propose {
+            title: title.clone(),
+            description: description.clone(),
+            msgs: msgs.clone(),
+            latest: None,
+            voting_period: None,
+            start: Some(Scheduled::AtHeight(start)),
+        };
+
+        // cannot start when (or after) it expires
+        let err = app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                flex_addr.clone(),
+                &delayed(height + 2000),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::WrongStart {}.to_string(), err);
+
+        let res = app
+            .execute_contract(
+                Addr::unchecked(VOTER1),
+                flex_addr.clone(),
+                &delayed(height + 10),
+                &[],
+            )
+            .unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        // the yes vote of the proposer is cast right away
+        assert_eq!(1, get_tally(&app, flex_addr.as_ref(), proposal_id));
+
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.update_block(|block| block.height += 9);
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::NotStarted {}.to_string(), err);
+
+        // votes are accepted exactly at the start height
+        app.update_block(|block| block.height += 1);
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER2), flex_addr, &yes_vote, &[])
+            .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "vote"),
+                attr("sender", VOTER2),
+                attr("proposal_id", proposal_id),
+                attr("status", "Passed"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_execute_works() {
+        let mut app = mock_app();
+
+        let required_weight = 3;
+        let voting_period = Duration::Time(2000000);
+        let (flex_addr, _) = setup_test_case_fixed(
+            &mut app,
+            required_weight,
+            voting_period,
+            coins(10, "BTC"),
+            true,
+        );
+
+        // ensure we have cash to cover the proposal
+        let contract_bal = app.wrap().query_balance(&flex_addr, "BTC").unwrap();
+        assert_eq!(contract_bal, coin(10, "BTC"));
+
+        // create proposal with 0 vote power
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+
+        // Only Passed can be executed
+        let execution = ExecuteMsg::Execute { proposal_id };
         let err = app
             .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &execution, &[])
             .unwrap_err();
@@ -1330,6 +2060,63 @@ mod tests {
         // TODO: check proposal threshold not changed
     }
 
+    #[test]
+    fn failing_group_hook_is_removed() {
+        let mut app = mock_app();
+
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, group_addr) =
+            setup_test_case_fixed(&mut app, 4, voting_period, vec![], false);
+        // this multisig only accepts member changes from another group
+        let other_group = instantiate_group(&mut app, vec![member(VOTER1, 1)]);
+        let other_flex = instantiate_flex(
+            &mut app,
+            other_group,
+            Threshold::AbsoluteCount { weight: 1 },
+            voting_period,
+            None,
+            false,
+        );
+
+        // register both multisigs as hooks on the group
+        for hook in [&flex_addr, &other_flex].iter() {
+            let add_hook = cw4_group::msg::ExecuteMsg::AddHook {
+                addr: hook.to_string(),
+            };
+            app.execute_contract(Addr::unchecked(OWNER), group_addr.clone(), &add_hook, &[])
+                .unwrap();
+        }
+
+        // the membership update succeeds, even if one of the hooks reverts
+        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![VOTER3.into()],
+            add: vec![],
+        };
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), group_addr.clone(), &update_msg, &[])
+            .unwrap();
+        assert!(res
+            .attributes
+            .contains(&attr("hook", other_flex.to_string())));
+        let power: VoterResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::Voter {
+                    address: VOTER3.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(power.weight, None);
+
+        // and the failing hook was removed
+        let hooks: cw4::HooksResponse = app
+            .wrap()
+            .query_wasm_smart(&group_addr, &cw4::Cw4QueryMsg::Hooks {})
+            .unwrap();
+        assert_eq!(hooks.hooks, vec![flex_addr.to_string()]);
+    }
+
     // uses the power from the beginning of the voting period
     // similar to above - simpler case, but shows that one proposals can
     // trigger the action
@@ -1356,6 +2143,8 @@ mod tests {
             description: "He's trying to steal our money".to_string(),
             msgs: vec![update_msg],
             latest: None,
+            voting_period: None,
+            start: None,
         };
         let res = app
             .execute_contract(
@@ -1657,4 +2446,1340 @@ mod tests {
             .unwrap();
         assert_eq!(prop_status(&app), Status::Passed);
     }
+
+    #[test]
+    fn tally_query() {
+        let mut app = mock_app();
+
+        // 80% quorum is 12 of the initial 15
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            Threshold::ThresholdQuorum {
+                threshold: Decimal::percent(60),
+                quorum: Decimal::percent(80),
+            },
+            voting_period,
+            coins(10, "BTC"),
+            false,
+        );
+
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        let tally = |app: &App| -> TallyResponse {
+            app.wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Tally { proposal_id })
+                .unwrap()
+        };
+        let prop_status = |app: &App| -> Status {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            prop.status
+        };
+
+        // the proposer's votes are over the threshold, but far from the quorum
+        let res = tally(&app);
+        assert_eq!(res.votes, Votes::new(5));
+        assert_eq!(res.total_weight, 15);
+        assert_eq!(res.percentages.yes, Decimal::from_ratio(1u64, 3u64));
+        assert_eq!(res.percentages.no, Decimal::zero());
+        assert!(!res.quorum_reached);
+        assert!(res.threshold_reached);
+        assert_eq!(res.status_if_expired, Status::Rejected);
+        app.update_block(|block| block.height += 3);
+
+        let vote = |app: &mut App, voter: &str, vote| {
+            let msg = ExecuteMsg::Vote { proposal_id, vote };
+            app.execute_contract(Addr::unchecked(voter), flex_addr.clone(), &msg, &[])
+                .unwrap();
+        };
+        vote(&mut app, VOTER2, Vote::Yes);
+        vote(&mut app, VOTER4, Vote::Veto);
+        vote(&mut app, VOTER1, Vote::Abstain);
+
+        // 12 voted, so the quorum is reached, and 7 of 11 opinions are yes.
+        // It could still fail if VOTER3 votes no, so it is open until it expires
+        let res = tally(&app);
+        assert_eq!(
+            res.votes,
+            Votes {
+                yes: 7,
+                no: 0,
+                abstain: 1,
+                veto: 4,
+            }
+        );
+        assert_eq!(
+            res.percentages,
+            VotePercentages {
+                yes: Decimal::from_ratio(7u64, 15u64),
+                no: Decimal::zero(),
+                abstain: Decimal::from_ratio(1u64, 15u64),
+                veto: Decimal::from_ratio(4u64, 15u64),
+            }
+        );
+        assert!(res.quorum_reached);
+        assert!(res.threshold_reached);
+        assert_eq!(res.status_if_expired, Status::Passed);
+        assert_eq!(prop_status(&app), Status::Open);
+
+        // the projection holds once it expires
+        app.update_block(expire(voting_period));
+        assert_eq!(prop_status(&app), Status::Passed);
+        assert_eq!(tally(&app).status_if_expired, Status::Passed);
+    }
+
+    // sets up a multisig with the given deposit, backed by a group of VOTER3, VOTER4 and VOTER5.
+    // Proposals need a weight of 8 to pass, and the multisig has some funds to pay somebody
+    fn setup_deposit_case(app: &mut App, deposit_info: DepositInfo) -> Addr {
+        let members = vec![member(VOTER3, 3), member(VOTER4, 4), member(VOTER5, 5)];
+        let group_addr = instantiate_group(app, members);
+        app.update_block(next_block);
+        let flex_addr = instantiate_flex(
+            app,
+            group_addr,
+            Threshold::AbsoluteCount { weight: 8 },
+            Duration::Time(2000),
+            Some(deposit_info),
+            false,
+        );
+        app.update_block(next_block);
+        app.set_bank_balance(&flex_addr, coins(10, "BTC")).unwrap();
+        flex_addr
+    }
+
+    fn query_deposit(app: &App, flex_addr: &Addr, proposal_id: u64) -> Option<Deposit> {
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        prop.deposit
+    }
+
+    #[test]
+    fn native_deposit_refunded_or_forfeited() {
+        let mut app = mock_app();
+        let flex_addr = setup_deposit_case(
+            &mut app,
+            DepositInfo {
+                denom: Denom::Native("DEPO".to_string()),
+                amount: Uint128::new(10),
+                refund_failed: false,
+                forfeit_addr: Some(SOMEBODY.into()),
+            },
+        );
+        app.set_bank_balance(&Addr::unchecked(VOTER4), coins(100, "DEPO"))
+            .unwrap();
+        let balance = |app: &App, addr: &str| -> u128 {
+            app.wrap()
+                .query_balance(addr, "DEPO")
+                .unwrap()
+                .amount
+                .u128()
+        };
+        let proposer = Addr::unchecked(VOTER4);
+
+        // the deposit must be paid in full
+        let err = app
+            .execute_contract(
+                proposer.clone(),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Payment(PaymentError::NoFunds {}).to_string()
+        );
+        let err = app
+            .execute_contract(
+                proposer.clone(),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &coins(5, "DEPO"),
+            )
+            .unwrap_err();
+        let wrong_deposit = ContractError::WrongDeposit {
+            amount: Uint128::new(10),
+            denom: "DEPO".to_string(),
+        };
+        assert_eq!(err, wrong_deposit.to_string());
+
+        // it is held until the proposal is executed
+        let res = app
+            .execute_contract(
+                proposer.clone(),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &coins(10, "DEPO"),
+            )
+            .unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        assert_eq!(balance(&app, VOTER4), 90);
+        assert_eq!(balance(&app, flex_addr.as_str()), 10);
+        let mut deposit = Deposit {
+            depositor: proposer.clone(),
+            denom: Denom::Native("DEPO".to_string()),
+            amount: Uint128::new(10),
+            refund_failed: false,
+            forfeit_addr: Some(Addr::unchecked(SOMEBODY)),
+            status: DepositStatus::Held,
+        };
+        assert_eq!(
+            query_deposit(&app, &flex_addr, proposal_id),
+            Some(deposit.clone())
+        );
+
+        let yes = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &yes, &[])
+            .unwrap();
+        let execute = ExecuteMsg::Execute { proposal_id };
+        let res = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &execute, &[])
+            .unwrap();
+        assert_eq!(res.attributes[3], attr("deposit", "refunded"));
+        assert_eq!(balance(&app, VOTER4), 100);
+        assert_eq!(balance(&app, flex_addr.as_str()), 0);
+        deposit.status = DepositStatus::Refunded;
+        assert_eq!(
+            query_deposit(&app, &flex_addr, proposal_id),
+            Some(deposit.clone())
+        );
+
+        // a rejected proposal forfeits the deposit
+        let res = app
+            .execute_contract(
+                proposer,
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &coins(10, "DEPO"),
+            )
+            .unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        app.update_block(expire(Duration::Time(2000)));
+        let close = ExecuteMsg::Close { proposal_id };
+        let res = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &close, &[])
+            .unwrap();
+        assert_eq!(res.attributes[3], attr("deposit", "forfeited"));
+        assert_eq!(balance(&app, VOTER4), 90);
+        assert_eq!(balance(&app, SOMEBODY), 10);
+        deposit.status = DepositStatus::Forfeited;
+        assert_eq!(query_deposit(&app, &flex_addr, proposal_id), Some(deposit));
+    }
+
+    #[test]
+    fn deposit_refunded_when_passed_but_not_executed() {
+        let mut app = mock_app();
+        let flex_addr = setup_deposit_case(
+            &mut app,
+            DepositInfo {
+                denom: Denom::Native("DEPO".to_string()),
+                amount: Uint128::new(10),
+                refund_failed: false,
+                forfeit_addr: None,
+            },
+        );
+        app.set_bank_balance(&Addr::unchecked(VOTER4), coins(10, "DEPO"))
+            .unwrap();
+        let balance = |app: &App, addr: &str| -> u128 {
+            app.wrap()
+                .query_balance(addr, "DEPO")
+                .unwrap()
+                .amount
+                .u128()
+        };
+        let prop_status = |app: &App, proposal_id: u64| -> Status {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            prop.status
+        };
+
+        let res = app
+            .execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &coins(10, "DEPO"),
+            )
+            .unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        let close = ExecuteMsg::Close { proposal_id };
+        // it cannot be refunded before it passed
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &close, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::NotExpired {}.to_string(), err);
+
+        let yes = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &yes, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app, proposal_id), Status::Passed);
+
+        // nobody executes it, but closing it refunds the deposit, even once expired
+        app.update_block(expire(Duration::Time(2000)));
+        let res = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &close, &[])
+            .unwrap();
+        assert_eq!(res.attributes[3], attr("deposit", "refunded"));
+        assert_eq!(balance(&app, VOTER4), 10);
+        assert_eq!(balance(&app, flex_addr.as_str()), 0);
+        let deposit = query_deposit(&app, &flex_addr, proposal_id).unwrap();
+        assert_eq!(deposit.status, DepositStatus::Refunded);
+        assert_eq!(prop_status(&app, proposal_id), Status::Passed);
+
+        // only once
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &close, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::WrongCloseStatus {}.to_string(), err);
+
+        // and it can still be executed, without refunding it again
+        let execute = ExecuteMsg::Execute { proposal_id };
+        let res = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &execute, &[])
+            .unwrap();
+        assert!(!res.attributes.iter().any(|a| a.key == "deposit"));
+        assert_eq!(balance(&app, VOTER4), 10);
+        assert_eq!(prop_status(&app, proposal_id), Status::Executed);
+    }
+
+    #[test]
+    fn cw20_deposit_refunded_or_burned() {
+        let mut app = mock_app();
+        let cw20_id = app.store_code(contract_cw20());
+        let msg = cw20_base::msg::InstantiateMsg {
+            name: "Deposit Token".to_string(),
+            symbol: "DEPO".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: VOTER4.into(),
+                amount: Uint128::new(100),
+            }],
+            mint: None,
+        };
+        let token = app
+            .instantiate_contract(cw20_id, Addr::unchecked(OWNER), &msg, &[], "DEPO")
+            .unwrap();
+        let deposit_info = |refund_failed| DepositInfo {
+            denom: Denom::Cw20(token.clone()),
+            amount: Uint128::new(10),
+            refund_failed,
+            forfeit_addr: None,
+        };
+        let refunding = setup_deposit_case(&mut app, deposit_info(true));
+        let burning = setup_deposit_case(&mut app, deposit_info(false));
+
+        let balance = |app: &App| -> u128 {
+            let query = Cw20QueryMsg::Balance {
+                address: VOTER4.into(),
+            };
+            let res: BalanceResponse = app.wrap().query_wasm_smart(&token, &query).unwrap();
+            res.balance.u128()
+        };
+        let (msgs, title, description) = proposal_info();
+        let propose = ReceiveMsg::Propose {
+            title,
+            description,
+            msgs,
+            latest: None,
+            voting_period: None,
+            start: None,
+        };
+        let send = |flex_addr: &Addr, amount: u128| Cw20ExecuteMsg::Send {
+            contract: flex_addr.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&propose).unwrap(),
+        };
+        let proposer = Addr::unchecked(VOTER4);
+
+        // cw20 deposits can only be paid by the token contract, in full
+        let wrong_deposit = ContractError::WrongDeposit {
+            amount: Uint128::new(10),
+            denom: token.to_string(),
+        }
+        .to_string();
+        let err = app
+            .execute_contract(
+                proposer.clone(),
+                refunding.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(err, wrong_deposit);
+        let fake = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: VOTER4.into(),
+            amount: Uint128::new(10),
+            msg: to_binary(&propose).unwrap(),
+        });
+        let err = app
+            .execute_contract(proposer.clone(), refunding.clone(), &fake, &[])
+            .unwrap_err();
+        assert_eq!(err, wrong_deposit);
+        let err = app
+            .execute_contract(proposer.clone(), token.clone(), &send(&refunding, 5), &[])
+            .unwrap_err();
+        assert_eq!(err, wrong_deposit);
+        assert_eq!(balance(&app), 100);
+
+        // both proposals are rejected, one deposit is refunded, the other one burned
+        app.execute_contract(proposer.clone(), token.clone(), &send(&refunding, 10), &[])
+            .unwrap();
+        app.execute_contract(proposer.clone(), token.clone(), &send(&burning, 10), &[])
+            .unwrap();
+        assert_eq!(balance(&app), 80);
+        let held = Deposit {
+            depositor: proposer,
+            denom: Denom::Cw20(token.clone()),
+            amount: Uint128::new(10),
+            refund_failed: true,
+            forfeit_addr: None,
+            status: DepositStatus::Held,
+        };
+        assert_eq!(query_deposit(&app, &refunding, 1), Some(held.clone()));
+
+        app.update_block(expire(Duration::Time(2000)));
+        let close = ExecuteMsg::Close { proposal_id: 1 };
+        let res = app
+            .execute_contract(Addr::unchecked(SOMEBODY), refunding.clone(), &close, &[])
+            .unwrap();
+        assert_eq!(res.attributes[3], attr("deposit", "refunded"));
+        assert_eq!(balance(&app), 90);
+        assert_eq!(
+            query_deposit(&app, &refunding, 1),
+            Some(Deposit {
+                status: DepositStatus::Refunded,
+                ..held.clone()
+            })
+        );
+
+        let res = app
+            .execute_contract(Addr::unchecked(SOMEBODY), burning.clone(), &close, &[])
+            .unwrap();
+        assert_eq!(res.attributes[3], attr("deposit", "forfeited"));
+        assert_eq!(balance(&app), 90);
+        let info: TokenInfoResponse = app
+            .wrap()
+            .query_wasm_smart(&token, &Cw20QueryMsg::TokenInfo {})
+            .unwrap();
+        assert_eq!(info.total_supply, Uint128::new(90));
+        assert_eq!(
+            query_deposit(&app, &burning, 1),
+            Some(Deposit {
+                refund_failed: false,
+                status: DepositStatus::Forfeited,
+                ..held
+            })
+        );
+    }
+
+    #[test]
+    fn early_execution_is_opt_in() {
+        let mut app = mock_app();
+        let members = vec![
+            member(OWNER, 0),
+            member(VOTER1, 1),
+            member(VOTER2, 2),
+            member(VOTER3, 3),
+            member(VOTER4, 4),
+            member(VOTER5, 5),
+        ];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let voting_period = Duration::Time(2000);
+        let threshold = Threshold::AbsoluteCount { weight: 8 };
+        let full = instantiate_flex(
+            &mut app,
+            group_addr.clone(),
+            threshold.clone(),
+            voting_period,
+            None,
+            false,
+        );
+        let early = instantiate_flex(&mut app, group_addr, threshold, voting_period, None, true);
+        app.update_block(next_block);
+        app.set_bank_balance(&full, coins(10, "BTC")).unwrap();
+        app.set_bank_balance(&early, coins(10, "BTC")).unwrap();
+
+        let prop_status = |app: &App, flex_addr: &Addr, proposal_id: u64| -> Status {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(flex_addr, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            prop.status
+        };
+        let propose = |app: &mut App, flex_addr: &Addr, proposer: &str| -> u64 {
+            let res = app
+                .execute_contract(
+                    Addr::unchecked(proposer),
+                    flex_addr.clone(),
+                    &pay_somebody_proposal(),
+                    &[],
+                )
+                .unwrap();
+            res.attributes[2].value.parse().unwrap()
+        };
+        let vote = |app: &mut App, flex_addr: &Addr, voter: &str, proposal_id, vote| {
+            let msg = ExecuteMsg::Vote { proposal_id, vote };
+            app.execute_contract(Addr::unchecked(voter), flex_addr.clone(), &msg, &[])
+        };
+        let execute = ExecuteMsg::Execute { proposal_id: 1 };
+        let close = ExecuteMsg::Close { proposal_id: 2 };
+
+        for flex_addr in [&full, &early].iter() {
+            // 9 yes votes, the first proposal passes
+            propose(&mut app, flex_addr, VOTER5);
+            vote(&mut app, flex_addr, VOTER4, 1, Vote::Yes).unwrap();
+            // 9 no votes, the second one cannot get more than 6 yes votes
+            propose(&mut app, flex_addr, VOTER1);
+            vote(&mut app, flex_addr, VOTER5, 2, Vote::No).unwrap();
+            vote(&mut app, flex_addr, VOTER4, 2, Vote::No).unwrap();
+        }
+
+        // either way, the first one passed and can be executed right away
+        for flex_addr in [&full, &early].iter() {
+            assert_eq!(prop_status(&app, flex_addr, 1), Status::Passed);
+            let flex_addr = (*flex_addr).clone();
+            app.execute_contract(Addr::unchecked(SOMEBODY), flex_addr, &execute, &[])
+                .unwrap();
+        }
+        assert_eq!(prop_status(&app, &full, 1), Status::Executed);
+        assert_eq!(prop_status(&app, &early, 1), Status::Executed);
+
+        // with early execution, the second one is rejected right away
+        assert_eq!(prop_status(&app, &early, 2), Status::Rejected);
+        let err = vote(&mut app, &early, VOTER2, 2, Vote::Yes).unwrap_err();
+        assert_eq!(ContractError::NotOpen {}.to_string(), err);
+        app.execute_contract(Addr::unchecked(SOMEBODY), early.clone(), &close, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app, &early, 2), Status::Rejected);
+
+        // otherwise, it stays open for the full voting period
+        assert_eq!(prop_status(&app, &full, 2), Status::Open);
+        vote(&mut app, &full, VOTER2, 2, Vote::Yes).unwrap();
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), full.clone(), &close, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::NotExpired {}.to_string(), err);
+
+        app.update_block(expire(voting_period));
+        assert_eq!(prop_status(&app, &full, 2), Status::Rejected);
+        app.execute_contract(Addr::unchecked(SOMEBODY), full.clone(), &close, &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn early_execution_with_quorum_and_abstain() {
+        let mut app = mock_app();
+
+        let members = vec![
+            member(OWNER, 0),
+            member(VOTER1, 1),
+            member(VOTER2, 2),
+            member(VOTER3, 3),
+            member(VOTER4, 4),
+            member(VOTER5, 5),
+        ];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        // 60% yes of the votes which are not abstaining, and a quorum of 20%
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(60),
+            quorum: Decimal::percent(20),
+        };
+        let voting_period = Duration::Time(2000);
+        let flex_addr =
+            instantiate_flex(&mut app, group_addr, threshold, voting_period, None, true);
+        app.update_block(next_block);
+        app.set_bank_balance(&flex_addr, coins(10, "BTC")).unwrap();
+        let prop_status = |app: &App, proposal_id: u64| -> Status {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            prop.status
+        };
+        let vote = |app: &mut App, voter: &str, proposal_id, vote| {
+            let msg = ExecuteMsg::Vote { proposal_id, vote };
+            app.execute_contract(Addr::unchecked(voter), flex_addr.clone(), &msg, &[])
+                .unwrap();
+        };
+        let propose = |app: &mut App, proposer: &str| {
+            app.execute_contract(
+                Addr::unchecked(proposer),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+            .unwrap();
+        };
+
+        // 3 yes, 4 abstain: 7 of the remaining 11 are needed
+        propose(&mut app, VOTER3);
+        vote(&mut app, VOTER4, 1, Vote::Abstain);
+        assert_eq!(prop_status(&app, 1), Status::Open);
+        // 8 yes would not be enough without the abstain (9 of 15), but passes now
+        vote(&mut app, VOTER5, 1, Vote::Yes);
+        assert_eq!(prop_status(&app, 1), Status::Passed);
+
+        // 3 yes, 4 abstain and 7 no: at most 4 of the needed 7 yes
+        propose(&mut app, VOTER3);
+        vote(&mut app, VOTER4, 2, Vote::Abstain);
+        vote(&mut app, VOTER2, 2, Vote::No);
+        // 6 yes would still be possible
+        assert_eq!(prop_status(&app, 2), Status::Open);
+        vote(&mut app, VOTER5, 2, Vote::No);
+        assert_eq!(prop_status(&app, 2), Status::Rejected);
+        app.execute_contract(
+            Addr::unchecked(SOMEBODY),
+            flex_addr.clone(),
+            &ExecuteMsg::Close { proposal_id: 2 },
+            &[],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn proposals_snapshot_weights_at_start_height() {
+        let mut app = mock_app();
+
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, group_addr) = setup_test_case(
+            &mut app,
+            Threshold::AbsolutePercentage {
+                percentage: Decimal::percent(50),
+            },
+            voting_period,
+            coins(10, "BTC"),
+            false,
+        );
+
+        // the group changes right before the proposal, in the same block:
+        // VOTER5 goes down to 1, VOTER4 is removed and newbie added with 10 (total 17)
+        let newbie: &str = "newbie";
+        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![VOTER4.into()],
+            add: vec![member(VOTER5, 1), member(newbie, 10)],
+        };
+        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
+            .unwrap();
+
+        // newbie only becomes a member at the next block
+        let err = app
+            .execute_contract(
+                Addr::unchecked(newbie),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}.to_string(), err);
+        let res = app
+            .execute_contract(
+                Addr::unchecked(VOTER5),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+            .unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        app.update_block(next_block);
+
+        // the proposal uses the weights from the beginning of the block
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(
+            prop.threshold,
+            ThresholdResponse::AbsolutePercentage {
+                percentage: Decimal::percent(50),
+                total_weight: 15,
+            }
+        );
+        let ballot: VoteResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::Vote {
+                    proposal_id,
+                    voter: VOTER5.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(ballot.vote.unwrap().weight, 5);
+        assert_eq!(get_tally(&app, flex_addr.as_ref(), proposal_id), 5);
+
+        // new proposals use the new group
+        let threshold: ThresholdResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Threshold {})
+            .unwrap();
+        assert_eq!(
+            threshold,
+            ThresholdResponse::AbsolutePercentage {
+                percentage: Decimal::percent(50),
+                total_weight: 17,
+            }
+        );
+
+        // newbie cannot vote on it, but the removed VOTER4 can, passing it with 9 of 15
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(newbie), flex_addr.clone(), &yes_vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}.to_string(), err);
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        assert_eq!(get_tally(&app, flex_addr.as_ref(), proposal_id), 9);
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.status, Status::Passed);
+    }
+
+    #[test]
+    fn vote_cursors_must_be_addresses() {
+        let mut app = mock_app();
+
+        let (flex_addr, _) = setup_test_case_fixed(
+            &mut app,
+            15,
+            Duration::Time(2000000),
+            coins(10, "BTC"),
+            false,
+        );
+        app.execute_contract(
+            Addr::unchecked(VOTER1),
+            flex_addr.clone(),
+            &pay_somebody_proposal(),
+            &[],
+        )
+        .unwrap();
+
+        for msg in &[
+            QueryMsg::ListVotes {
+                proposal_id: 1,
+                start_after: Some("".to_string()),
+                limit: None,
+            },
+            QueryMsg::ReverseVotes {
+                proposal_id: 1,
+                start_before: Some("".to_string()),
+                limit: None,
+            },
+        ] {
+            let err = app
+                .wrap()
+                .query_wasm_smart::<VoteListResponse, _, _>(&flex_addr, msg)
+                .unwrap_err();
+            assert!(err.to_string().contains("Invalid input"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn list_proposals_by_status() {
+        let mut app = mock_app();
+        let members = vec![
+            member(OWNER, 0),
+            member(VOTER1, 1),
+            member(VOTER2, 2),
+            member(VOTER3, 3),
+            member(VOTER4, 4),
+            member(VOTER5, 5),
+        ];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        // without early execution, proposals that cannot pass stay open until they expire
+        let voting_period = Duration::Time(2000);
+        let threshold = Threshold::AbsoluteCount { weight: 8 };
+        let flex_addr =
+            instantiate_flex(&mut app, group_addr, threshold, voting_period, None, false);
+        app.update_block(next_block);
+        app.set_bank_balance(&flex_addr, coins(10, "BTC")).unwrap();
+
+        let propose = |app: &mut App, proposer: &str| {
+            app.execute_contract(
+                Addr::unchecked(proposer),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+            .unwrap();
+        };
+        let vote = |app: &mut App, voter: &str, proposal_id| {
+            let msg = ExecuteMsg::Vote {
+                proposal_id,
+                vote: Vote::Yes,
+            };
+            app.execute_contract(Addr::unchecked(voter), flex_addr.clone(), &msg, &[])
+                .unwrap();
+        };
+        // returns the ids, and checks they all have the status
+        let ids = |app: &App, msg: QueryMsg, status: Status| -> Vec<u64> {
+            let res: ProposalListResponse = app.wrap().query_wasm_smart(&flex_addr, &msg).unwrap();
+            assert!(res.proposals.iter().all(|p| p.status == status));
+            res.proposals.into_iter().map(|p| p.id).collect()
+        };
+        let list = |start_after, limit, status| QueryMsg::ListProposals {
+            start_after,
+            limit,
+            status: Some(status),
+        };
+        let reverse = |start_before, limit, status| QueryMsg::ReverseProposals {
+            start_before,
+            limit,
+            status: Some(status),
+        };
+
+        // 1 and 4 get enough votes, 2 and 3 don't
+        for proposer in &[VOTER5, VOTER1, VOTER4, VOTER5] {
+            propose(&mut app, proposer);
+        }
+        vote(&mut app, VOTER4, 1);
+        vote(&mut app, VOTER3, 4);
+        let open = |start_after| list(start_after, Some(1), Status::Open);
+        assert_eq!(ids(&app, open(None), Status::Open), vec![2]);
+        assert_eq!(ids(&app, open(Some(2)), Status::Open), vec![3]);
+        assert_eq!(ids(&app, open(Some(3)), Status::Open), Vec::<u64>::new());
+        let passed = list(None, None, Status::Passed);
+        assert_eq!(ids(&app, passed, Status::Passed), vec![1, 4]);
+
+        // once expired, the others are rejected, though still stored as open
+        app.update_block(expire(voting_period));
+        propose(&mut app, VOTER1);
+        let passed = |start_after| list(start_after, Some(1), Status::Passed);
+        assert_eq!(ids(&app, passed(None), Status::Passed), vec![1]);
+        assert_eq!(ids(&app, passed(Some(1)), Status::Passed), vec![4]);
+        assert_eq!(
+            ids(&app, passed(Some(4)), Status::Passed),
+            Vec::<u64>::new()
+        );
+        let open = list(None, None, Status::Open);
+        assert_eq!(ids(&app, open, Status::Open), vec![5]);
+
+        // executing and closing moves them to another bucket
+        let execution = ExecuteMsg::Execute { proposal_id: 1 };
+        app.execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &execution, &[])
+            .unwrap();
+        let closing = ExecuteMsg::Close { proposal_id: 2 };
+        app.execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &closing, &[])
+            .unwrap();
+        let passed = reverse(None, None, Status::Passed);
+        assert_eq!(ids(&app, passed, Status::Passed), vec![4]);
+        let executed = list(None, None, Status::Executed);
+        assert_eq!(ids(&app, executed, Status::Executed), vec![1]);
+        // 2 is stored as rejected, 3 is still stored as open
+        let rejected = |start_before| reverse(start_before, Some(1), Status::Rejected);
+        assert_eq!(ids(&app, rejected(None), Status::Rejected), vec![3]);
+        assert_eq!(ids(&app, rejected(Some(3)), Status::Rejected), vec![2]);
+        assert_eq!(
+            ids(&app, rejected(Some(2)), Status::Rejected),
+            Vec::<u64>::new()
+        );
+
+        // without a status, all of them are listed
+        let all = QueryMsg::ListProposals {
+            start_after: None,
+            limit: None,
+            status: None,
+        };
+        let res: ProposalListResponse = app.wrap().query_wasm_smart(&flex_addr, &all).unwrap();
+        assert_eq!(res.proposals.len(), 5);
+    }
+
+    #[test]
+    fn signaling_proposals_cannot_be_executed() {
+        let mut app = mock_app();
+        let flex_addr = setup_deposit_case(
+            &mut app,
+            DepositInfo {
+                denom: Denom::Native("DEPO".to_string()),
+                amount: Uint128::new(10),
+                refund_failed: false,
+                forfeit_addr: Some(SOMEBODY.into()),
+            },
+        );
+        app.set_bank_balance(&Addr::unchecked(VOTER4), coins(100, "DEPO"))
+            .unwrap();
+        let query_prop = |app: &App| -> ProposalResponse {
+            app.wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
+                .unwrap()
+        };
+
+        // a proposal without msgs is signaling
+        let proposal = ExecuteMsg::Propose {
+            title: "Adopt the roadmap".to_string(),
+            description: "Do we agree?".to_string(),
+            msgs: vec![],
+            latest: None,
+            voting_period: None,
+            start: None,
+        };
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &proposal,
+            &coins(10, "DEPO"),
+        )
+        .unwrap();
+        let yes = ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &yes, &[])
+            .unwrap();
+        let prop = query_prop(&app);
+        assert_eq!(prop.kind, ProposalKind::Signaling);
+        assert_eq!(prop.status, Status::Passed);
+
+        let execute = ExecuteMsg::Execute { proposal_id: 1 };
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &execute, &[])
+            .unwrap_err();
+        assert_eq!(err, ContractError::SignalingProposal {}.to_string());
+
+        // once expired, closing refunds the deposit and marks it done
+        let close = ExecuteMsg::Close { proposal_id: 1 };
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &close, &[])
+            .unwrap_err();
+        assert_eq!(err, ContractError::NotExpired {}.to_string());
+        app.update_block(expire(Duration::Time(2000)));
+        let res = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &close, &[])
+            .unwrap();
+        assert_eq!(res.attributes[3], attr("deposit", "refunded"));
+        let balance = app.wrap().query_balance(VOTER4, "DEPO").unwrap();
+        assert_eq!(balance.amount, Uint128::new(100));
+        let prop = query_prop(&app);
+        assert_eq!(prop.status, Status::Done);
+        assert_eq!(prop.deposit.unwrap().status, DepositStatus::Refunded);
+
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &close, &[])
+            .unwrap_err();
+        assert_eq!(err, ContractError::WrongCloseStatus {}.to_string());
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &execute, &[])
+            .unwrap_err();
+        assert_eq!(err, ContractError::SignalingProposal {}.to_string());
+
+        // proposals with msgs are executable
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &pay_somebody_proposal(),
+            &coins(10, "DEPO"),
+        )
+        .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 2 })
+            .unwrap();
+        assert_eq!(prop.kind, ProposalKind::Executable);
+    }
+
+    #[test]
+    fn signaling_proposals_without_deposit_are_closed_as_done() {
+        let mut app = mock_app();
+        let voting_period = Duration::Time(2000);
+        let (flex_addr, _) = setup_test_case_fixed(&mut app, 4, voting_period, vec![], false);
+
+        // passes right away with early execution
+        let proposal = ExecuteMsg::Propose {
+            title: "Adopt the roadmap".to_string(),
+            description: "Do we agree?".to_string(),
+            msgs: vec![],
+            latest: None,
+            voting_period: None,
+            start: None,
+        };
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let prop_status = |app: &App| -> Status {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
+                .unwrap();
+            prop.status
+        };
+        let list = |app: &App, status| -> usize {
+            let msg = QueryMsg::ListProposals {
+                start_after: None,
+                limit: None,
+                status: Some(status),
+            };
+            let res: ProposalListResponse = app.wrap().query_wasm_smart(&flex_addr, &msg).unwrap();
+            res.proposals.len()
+        };
+        assert_eq!(prop_status(&app), Status::Passed);
+        assert_eq!(list(&app, Status::Passed), 1);
+
+        let close = ExecuteMsg::Close { proposal_id: 1 };
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &close, &[])
+            .unwrap_err();
+        assert_eq!(err, ContractError::NotExpired {}.to_string());
+
+        // there is no deposit to refund, it is done anyway
+        app.update_block(expire(voting_period));
+        let res = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &close, &[])
+            .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "close"),
+                attr("sender", SOMEBODY),
+                attr("proposal_id", 1),
+            ]
+        );
+        assert_eq!(prop_status(&app), Status::Done);
+        assert_eq!(list(&app, Status::Passed), 0);
+        assert_eq!(list(&app, Status::Done), 1);
+
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &close, &[])
+            .unwrap_err();
+        assert_eq!(err, ContractError::WrongCloseStatus {}.to_string());
+    }
+
+    #[test]
+    fn executor_restriction() {
+        use crate::msg::Executor as ExecutorMsg;
+
+        let mut app = mock_app();
+        let members = vec![
+            member(OWNER, 0),
+            member(VOTER1, 1),
+            member(VOTER2, 2),
+            member(VOTER3, 3),
+        ];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate = |app: &mut App, executor: Option<ExecutorMsg>| {
+            let msg = InstantiateMsg {
+                group_addr: group_addr.to_string(),
+                threshold: Threshold::AbsoluteCount { weight: 3 },
+                max_voting_period: Duration::Time(2000),
+                min_voting_period: None,
+                deposit_info: None,
+                early_execution: false,
+                executor,
+            };
+            app.instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex")
+        };
+        // VOTER3 has enough weight to pass a proposal right away
+        let propose = |app: &mut App, flex_addr: &Addr| -> u64 {
+            app.set_bank_balance(flex_addr, coins(10, "BTC")).unwrap();
+            let res = app
+                .execute_contract(
+                    Addr::unchecked(VOTER3),
+                    flex_addr.clone(),
+                    &pay_somebody_proposal(),
+                    &[],
+                )
+                .unwrap();
+            res.attributes[2].value.parse().unwrap()
+        };
+        let execute = |app: &mut App, flex_addr: &Addr, sender: &str, proposal_id| {
+            let msg = ExecuteMsg::Execute { proposal_id };
+            app.execute_contract(Addr::unchecked(sender), flex_addr.clone(), &msg, &[])
+        };
+        let executor = |app: &App, flex_addr: &Addr| -> Executor {
+            let cfg: ConfigResponse = app
+                .wrap()
+                .query_wasm_smart(flex_addr, &QueryMsg::Config {})
+                .unwrap();
+            cfg.executor
+        };
+        let unauthorized = ContractError::Unauthorized {}.to_string();
+
+        // by default anybody can execute
+        let anybody = instantiate(&mut app, None).unwrap();
+        assert_eq!(executor(&app, &anybody), Executor::Anybody);
+        let proposal_id = propose(&mut app, &anybody);
+        execute(&mut app, &anybody, SOMEBODY, proposal_id).unwrap();
+
+        // only members, with any weight
+        let members_only = instantiate(&mut app, Some(ExecutorMsg::OnlyMembers)).unwrap();
+        assert_eq!(executor(&app, &members_only), Executor::OnlyMembers);
+        let proposal_id = propose(&mut app, &members_only);
+        let err = execute(&mut app, &members_only, SOMEBODY, proposal_id).unwrap_err();
+        assert_eq!(err, unauthorized);
+        execute(&mut app, &members_only, OWNER, proposal_id).unwrap();
+
+        // membership is checked when executing, not when the proposal passed
+        let proposal_id = propose(&mut app, &members_only);
+        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![VOTER2.into()],
+            add: vec![],
+        };
+        app.execute_contract(Addr::unchecked(OWNER), group_addr.clone(), &update_msg, &[])
+            .unwrap();
+        let err = execute(&mut app, &members_only, VOTER2, proposal_id).unwrap_err();
+        assert_eq!(err, unauthorized);
+        execute(&mut app, &members_only, VOTER1, proposal_id).unwrap();
+
+        // only the given address, which need not be a member
+        let err = instantiate(&mut app, Some(ExecutorMsg::Addr("".into()))).unwrap_err();
+        assert!(err.contains("Invalid input"), "{}", err);
+        let single = instantiate(&mut app, Some(ExecutorMsg::Addr(SOMEBODY.into()))).unwrap();
+        assert_eq!(
+            executor(&app, &single),
+            Executor::Addr(Addr::unchecked(SOMEBODY))
+        );
+        let proposal_id = propose(&mut app, &single);
+        let err = execute(&mut app, &single, VOTER3, proposal_id).unwrap_err();
+        assert_eq!(err, unauthorized);
+        execute(&mut app, &single, SOMEBODY, proposal_id).unwrap();
+    }
+
+    #[test]
+    fn per_proposal_voting_period() {
+        let mut app = mock_app();
+        let group_addr = instantiate_group(&mut app, vec![member(VOTER1, 1), member(VOTER2, 2)]);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate_msg = |max_voting_period, min_voting_period| InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 3 },
+            max_voting_period,
+            min_voting_period,
+            deposit_info: None,
+            early_execution: false,
+            executor: None,
+        };
+
+        // the min has to be in the same unit as the max, and not longer
+        for (max, min) in &[
+            (Duration::Time(1000), Duration::Height(10)),
+            (Duration::Height(1000), Duration::Height(1001)),
+        ] {
+            let err = app
+                .instantiate_contract(
+                    flex_id,
+                    Addr::unchecked(OWNER),
+                    &instantiate_msg(*max, Some(*min)),
+                    &[],
+                    "flex",
+                )
+                .unwrap_err();
+            assert_eq!(err, VotingPeriodError::WrongMinVotingPeriod {}.to_string());
+        }
+
+        let height_min = Duration::Height(10);
+        let by_height = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate_msg(Duration::Height(1000), Some(height_min)),
+                &[],
+                "flex",
+            )
+            .unwrap();
+        let time_min = Duration::Time(3600);
+        let by_time = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate_msg(Duration::Time(7 * 86400), Some(time_min)),
+                &[],
+                "flex",
+            )
+            .unwrap();
+        let cfg: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(&by_height, &QueryMsg::Config {})
+            .unwrap();
+        assert_eq!(cfg.min_voting_period, Some(height_min));
+
+        let block = app.block_info();
+        let propose = |app: &mut App, flex: &Addr, voting_period, latest| {
+            let (msgs, title, description) = proposal_info();
+            let msg = ExecuteMsg::Propose {
+                title,
+                description,
+                msgs,
+                latest,
+                voting_period,
+                start: None,
+            };
+            let res = app.execute_contract(Addr::unchecked(VOTER1), flex.clone(), &msg, &[])?;
+            let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(flex, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            Ok::<_, String>(prop.expires)
+        };
+
+        // defaults to the max, longer periods are clamped to it
+        assert_eq!(
+            propose(&mut app, &by_height, None, None),
+            Ok(Expiration::AtHeight(block.height + 1000))
+        );
+        assert_eq!(
+            propose(&mut app, &by_height, Some(Duration::Height(5000)), None),
+            Ok(Expiration::AtHeight(block.height + 1000))
+        );
+        assert_eq!(
+            propose(&mut app, &by_height, Some(Duration::Height(10)), None),
+            Ok(Expiration::AtHeight(block.height + 10))
+        );
+        assert_eq!(
+            propose(&mut app, &by_height, Some(Duration::Height(9)), None),
+            Err(VotingPeriodError::VotingPeriodTooShort { min: height_min }.to_string())
+        );
+        assert_eq!(
+            propose(&mut app, &by_height, Some(Duration::Time(3600)), None),
+            Err(VotingPeriodError::WrongExpiration {}.to_string())
+        );
+        // the earlier of latest and the voting period applies, and the min holds for both
+        assert_eq!(
+            propose(
+                &mut app,
+                &by_height,
+                Some(Duration::Height(50)),
+                Some(Expiration::AtHeight(block.height + 20))
+            ),
+            Ok(Expiration::AtHeight(block.height + 20))
+        );
+        assert_eq!(
+            propose(
+                &mut app,
+                &by_height,
+                None,
+                Some(Expiration::AtHeight(block.height + 5))
+            ),
+            Err(VotingPeriodError::VotingPeriodTooShort { min: height_min }.to_string())
+        );
+
+        assert_eq!(
+            propose(&mut app, &by_time, None, None),
+            Ok(Expiration::AtTime(block.time.plus_seconds(7 * 86400)))
+        );
+        assert_eq!(
+            propose(&mut app, &by_time, Some(Duration::Time(86400)), None),
+            Ok(Expiration::AtTime(block.time.plus_seconds(86400)))
+        );
+        assert_eq!(
+            propose(&mut app, &by_time, Some(Duration::Time(3599)), None),
+            Err(VotingPeriodError::VotingPeriodTooShort { min: time_min }.to_string())
+        );
+        assert_eq!(
+            propose(
+                &mut app,
+                &by_time,
+                Some(Duration::Time(86400)),
+                Some(Expiration::AtTime(block.time.plus_seconds(7200)))
+            ),
+            Ok(Expiration::AtTime(block.time.plus_seconds(7200)))
+        );
+        assert_eq!(
+            propose(
+                &mut app,
+                &by_time,
+                None,
+                Some(Expiration::AtTime(block.time.plus_seconds(1800)))
+            ),
+            Err(VotingPeriodError::VotingPeriodTooShort { min: time_min }.to_string())
+        );
+    }
+
+    #[test]
+    fn early_execution_can_be_left_out() {
+        let mut app = mock_app();
+        let group_addr = instantiate_group(&mut app, vec![member(VOTER1, 1)]);
+        app.update_block(next_block);
+
+        // an instantiate message from before early execution was added
+        let json = format!(
+            r#"{{"group_addr":"{}","threshold":{{"absolute_count":{{"weight":1}}}},"max_voting_period":{{"height":10}},"deposit_info":null}}"#,
+            group_addr
+        );
+        let msg: InstantiateMsg = cosmwasm_std::from_slice(json.as_bytes()).unwrap();
+        assert!(!msg.early_execution);
+        let flex_id = app.store_code(contract_flex());
+        let flex_addr = app
+            .instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex")
+            .unwrap();
+        let cfg: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Config {})
+            .unwrap();
+        assert!(!cfg.early_execution);
+    }
+
+    #[test]
+    fn migrate_indexes_old_proposals() {
+        let mut deps = mock_dependencies(&[]);
+
+        // a passed proposal stored before the status index (and all later fields) were added
+        let old = br#"{"title":"Pay somebody","description":"Do I pay her?",
+            "start_height":12345,"expires":{"at_height":2012345},"msgs":[{"bank":{"send":
+            {"to_address":"somebody","amount":[{"denom":"BTC","amount":"1"}]}}}],
+            "status":"passed","threshold":{"absolute_count":{"weight":3}},"total_weight":6,
+            "votes":{"yes":3,"no":0,"abstain":0,"veto":0}}"#;
+        let key = Map::<U64Key, Proposal>::new("proposals").key(1.into());
+        deps.storage.set(&key, old);
+
+        let passed = QueryMsg::ListProposals {
+            start_after: None,
+            limit: None,
+            status: Some(Status::Passed),
+        };
+        let ids = |deps: Deps| -> Vec<u64> {
+            let res: ProposalListResponse =
+                from_binary(&query(deps, mock_env(), passed.clone()).unwrap()).unwrap();
+            res.proposals.into_iter().map(|p| p.id).collect()
+        };
+        assert_eq!(ids(deps.as_ref()), Vec::<u64>::new());
+
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.6.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "migrate"), attr("indexed_proposals", 1)]
+        );
+        assert_eq!(ids(deps.as_ref()), vec![1]);
+        let query_prop = QueryMsg::Proposal { proposal_id: 1 };
+        let prop: ProposalResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), query_prop).unwrap()).unwrap();
+        assert_eq!(prop.kind, ProposalKind::Executable);
+        assert_eq!(prop.deposit, None);
+
+        // no downgrades
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Version(VersionError::UnsupportedVersion {
+                version: "99.0.0".to_string(),
+                required: format!("<={}", CONTRACT_VERSION),
+            })
+        );
+    }
 }
@@ -1,4 +1,7 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
+use cw0::PaymentError;
+use cw2::VersionError;
+use cw3::{ThresholdError, VotingPeriodError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,11 +9,17 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("Required threshold cannot be zero")]
-    ZeroThreshold {},
+    #[error("{0}")]
+    Threshold(#[from] ThresholdError),
+
+    #[error("{0}")]
+    VotingPeriod(#[from] VotingPeriodError),
 
-    #[error("Not possible to reach required (passing) threshold")]
-    UnreachableThreshold {},
+    #[error("{0}")]
+    Version(#[from] VersionError),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
 
     #[error("Group contract invalid address '{addr}'")]
     InvalidGroup { addr: String },
@@ -24,11 +33,14 @@ pub enum ContractError {
     #[error("Proposal voting period has expired")]
     Expired {},
 
-    #[error("Proposal must expire before you can close it")]
-    NotExpired {},
+    #[error("Proposal voting period has not started yet")]
+    NotStarted {},
 
-    #[error("Wrong expiration option")]
-    WrongExpiration {},
+    #[error("Proposal must start before it expires")]
+    WrongStart {},
+
+    #[error("Proposal must expire or be rejected before you can close it")]
+    NotExpired {},
 
     #[error("Already voted on this proposal")]
     AlreadyVoted {},
@@ -36,6 +48,18 @@ pub enum ContractError {
     #[error("Proposal must have passed and not yet been executed")]
     WrongExecuteStatus {},
 
+    #[error("Signaling proposals have no messages and cannot be executed")]
+    SignalingProposal {},
+
     #[error("Cannot close completed or passed proposals")]
     WrongCloseStatus {},
+
+    #[error("Proposal deposit cannot be zero")]
+    ZeroDeposit {},
+
+    #[error("Proposal deposit must be {amount} {denom}")]
+    WrongDeposit { amount: Uint128, denom: String },
+
+    #[error("This multisig does not take proposal deposits")]
+    NoDeposit {},
 }
@@ -1,11 +1,13 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::error::ContractError;
-use cosmwasm_std::{CosmosMsg, Decimal, Empty};
-use cw0::{Duration, Expiration};
-use cw3::{ThresholdResponse, Vote};
-use cw4::MemberChangedHookMsg;
+use cosmwasm_std::{CosmosMsg, Decimal, Empty, Uint128};
+use cw0::{Duration, Expiration, Scheduled};
+use cw20::{Cw20ReceiveMsg, Denom};
+use cw3::{ProposalKind, Status, Threshold, ThresholdResponse, Vote};
+use cw4::{Cw4Contract, MemberChangedHookMsg};
+
+pub use crate::state::{Deposit, DepositConfig, DepositStatus, Votes};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct InstantiateMsg {
@@ -13,94 +15,47 @@ pub struct InstantiateMsg {
     pub group_addr: String,
     pub threshold: Threshold,
     pub max_voting_period: Duration,
+    /// proposals cannot pick a shorter voting period than this. None means there is no minimum
+    pub min_voting_period: Option<Duration>,
+    /// if set, proposers have to pay this deposit to create a proposal
+    pub deposit_info: Option<DepositInfo>,
+    /// If true, proposals are rejected (and can be closed) as soon as they cannot pass anymore.
+    /// Otherwise they are only rejected once they expire. Either way they pass as soon as
+    /// enough weight voted yes
+    #[serde(default)]
+    pub early_execution: bool,
+    /// who may execute passed proposals, Anybody if None
+    pub executor: Option<Executor>,
 }
 
-/// This defines the different ways tallies can happen.
-///
-/// The total_weight used for calculating success as well as the weights of each
-/// individual voter used in tallying should be snapshotted at the beginning of
-/// the block at which the proposal starts (this is likely the responsibility of a
-/// correct cw4 implementation).
-/// See also `ThresholdResponse` in the cw3 spec.
+/// DepositInfo is the deposit needed to create a proposal, to deter spam.
+/// It is refunded once the proposal is executed, and forfeited when it is closed
+/// as rejected, unless refund_failed is set.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-#[serde(rename_all = "snake_case")]
-pub enum Threshold {
-    /// Declares that a fixed weight of Yes votes is needed to pass.
-    /// See `ThresholdResponse.AbsoluteCount` in the cw3 spec for details.
-    AbsoluteCount { weight: u64 },
-
-    /// Declares a percentage of the total weight that must cast Yes votes in order for
-    /// a proposal to pass.
-    /// See `ThresholdResponse.AbsolutePercentage` in the cw3 spec for details.
-    AbsolutePercentage { percentage: Decimal },
-
-    /// Declares a `quorum` of the total votes that must participate in the election in order
-    /// for the vote to be considered at all.
-    /// See `ThresholdResponse.ThresholdQuorum` in the cw3 spec for details.
-    ThresholdQuorum { threshold: Decimal, quorum: Decimal },
+pub struct DepositInfo {
+    /// a native denom is paid along with Propose, a cw20 token is sent with a ReceiveMsg
+    pub denom: Denom,
+    pub amount: Uint128,
+    /// if true, the deposits of rejected proposals are refunded as well
+    pub refund_failed: bool,
+    /// forfeited deposits are sent to this address, or burned if None
+    pub forfeit_addr: Option<String>,
 }
 
-impl Threshold {
-    /// returns error if this is an unreachable value,
-    /// given a total weight of all members in the group
-    pub fn validate(&self, total_weight: u64) -> Result<(), ContractError> {
-        match self {
-            Threshold::AbsoluteCount {
-                weight: weight_needed,
-            } => {
-                if *weight_needed == 0 {
-                    Err(ContractError::ZeroThreshold {})
-                } else if *weight_needed > total_weight {
-                    Err(ContractError::UnreachableThreshold {})
-                } else {
-                    Ok(())
-                }
-            }
-            Threshold::AbsolutePercentage {
-                percentage: percentage_needed,
-            } => valid_percentage(percentage_needed),
-            Threshold::ThresholdQuorum {
-                threshold,
-                quorum: quroum,
-            } => {
-                valid_percentage(threshold)?;
-                valid_percentage(quroum)
-            }
-        }
-    }
-
-    /// Creates a response from the saved data, just missing the total_weight info
-    pub fn to_response(&self, total_weight: u64) -> ThresholdResponse {
-        match self.clone() {
-            Threshold::AbsoluteCount { weight } => ThresholdResponse::AbsoluteCount {
-                weight,
-                total_weight,
-            },
-            Threshold::AbsolutePercentage { percentage } => ThresholdResponse::AbsolutePercentage {
-                percentage,
-                total_weight,
-            },
-            Threshold::ThresholdQuorum { threshold, quorum } => {
-                ThresholdResponse::ThresholdQuorum {
-                    threshold,
-                    quorum,
-                    total_weight,
-                }
-            }
-        }
-    }
+/// Executor restricts who may execute passed proposals
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Executor {
+    /// anyone can execute passed proposals
+    Anybody,
+    /// only current members of the group (with any weight) can execute them
+    OnlyMembers,
+    /// only this address can execute them
+    Addr(String),
 }
 
-/// Asserts that the 0.0 < percent <= 1.0
-fn valid_percentage(percent: &Decimal) -> Result<(), ContractError> {
-    if percent.is_zero() {
-        Err(ContractError::ZeroThreshold {})
-    } else if *percent > Decimal::one() {
-        Err(ContractError::UnreachableThreshold {})
-    } else {
-        Ok(())
-    }
-}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
 
 // TODO: add some T variants? Maybe good enough as fixed Empty for now
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -112,6 +67,12 @@ pub enum ExecuteMsg {
         msgs: Vec<CosmosMsg<Empty>>,
         // note: we ignore API-spec'd earliest if passed, always opens immediately
         latest: Option<Expiration>,
+        /// voting period of this proposal, defaults to (and cannot be longer than)
+        /// max_voting_period. It must be at least min_voting_period
+        voting_period: Option<Duration>,
+        /// votes are only accepted once this is triggered (the yes vote of the proposer is
+        /// always cast right away). None opens the proposal immediately
+        start: Option<Scheduled>,
     },
     Vote {
         proposal_id: u64,
@@ -125,6 +86,23 @@ pub enum ExecuteMsg {
     },
     /// Handles update hook messages from the group contract
     MemberChangedHook(MemberChangedHookMsg),
+    /// This accepts a properly-encoded ReceiveMsg from a cw20 contract,
+    /// to pay the deposit in cw20 tokens
+    Receive(Cw20ReceiveMsg),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    /// Same as ExecuteMsg::Propose, the sent tokens are the deposit
+    Propose {
+        title: String,
+        description: String,
+        msgs: Vec<CosmosMsg<Empty>>,
+        latest: Option<Expiration>,
+        voting_period: Option<Duration>,
+        start: Option<Scheduled>,
+    },
 }
 
 // We can also add this as a cw3 extension
@@ -133,17 +111,25 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     /// Return ThresholdResponse
     Threshold {},
+    /// Returns ConfigResponse
+    Config {},
     /// Returns ProposalResponse
     Proposal { proposal_id: u64 },
+    /// Returns TallyResponse
+    Tally { proposal_id: u64 },
     /// Returns ProposalListResponse
     ListProposals {
         start_after: Option<u64>,
         limit: Option<u32>,
+        /// only list proposals with this (current) status
+        status: Option<Status>,
     },
     /// Returns ProposalListResponse
     ReverseProposals {
         start_before: Option<u64>,
         limit: Option<u32>,
+        /// only list proposals with this (current) status
+        status: Option<Status>,
     },
     /// Returns VoteResponse
     Vote { proposal_id: u64, voter: String },
@@ -153,6 +139,12 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Returns VoteListResponse
+    ReverseVotes {
+        proposal_id: u64,
+        start_before: Option<String>,
+        limit: Option<u32>,
+    },
     /// Returns VoterInfo
     Voter { address: String },
     /// Returns VoterListResponse
@@ -162,135 +154,63 @@ pub enum QueryMsg {
     },
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn validate_percentage() {
-        // TODO: test the error messages
-
-        // 0 is never a valid percentage
-        let err = valid_percentage(&Decimal::zero()).unwrap_err();
-        assert_eq!(err.to_string(), ContractError::ZeroThreshold {}.to_string());
-
-        // 100% is
-        valid_percentage(&Decimal::one()).unwrap();
-
-        // 101% is not
-        let err = valid_percentage(&Decimal::percent(101)).unwrap_err();
-        assert_eq!(
-            err.to_string(),
-            ContractError::UnreachableThreshold {}.to_string()
-        );
-        // not 100.1%
-        let err = valid_percentage(&Decimal::permille(1001)).unwrap_err();
-        assert_eq!(
-            err.to_string(),
-            ContractError::UnreachableThreshold {}.to_string()
-        );
-
-        // other values in between 0 and 1 are valid
-        valid_percentage(&Decimal::permille(1)).unwrap();
-        valid_percentage(&Decimal::percent(17)).unwrap();
-        valid_percentage(&Decimal::percent(99)).unwrap();
-    }
-
-    #[test]
-    fn validate_threshold() {
-        // absolute count ensures 0 < required <= total_weight
-        let err = Threshold::AbsoluteCount { weight: 0 }
-            .validate(5)
-            .unwrap_err();
-        // TODO: remove to_string() when PartialEq implemented
-        assert_eq!(err.to_string(), ContractError::ZeroThreshold {}.to_string());
-        let err = Threshold::AbsoluteCount { weight: 6 }
-            .validate(5)
-            .unwrap_err();
-        assert_eq!(
-            err.to_string(),
-            ContractError::UnreachableThreshold {}.to_string()
-        );
-
-        Threshold::AbsoluteCount { weight: 1 }.validate(5).unwrap();
-        Threshold::AbsoluteCount { weight: 5 }.validate(5).unwrap();
-
-        // AbsolutePercentage just enforces valid_percentage (tested above)
-        let err = Threshold::AbsolutePercentage {
-            percentage: Decimal::zero(),
-        }
-        .validate(5)
-        .unwrap_err();
-        assert_eq!(err.to_string(), ContractError::ZeroThreshold {}.to_string());
-        Threshold::AbsolutePercentage {
-            percentage: Decimal::percent(51),
-        }
-        .validate(5)
-        .unwrap();
-
-        // Quorum enforces both valid just enforces valid_percentage (tested above)
-        Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(40),
-        }
-        .validate(5)
-        .unwrap();
-        let err = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(101),
-            quorum: Decimal::percent(40),
-        }
-        .validate(5)
-        .unwrap_err();
-        assert_eq!(
-            err.to_string(),
-            ContractError::UnreachableThreshold {}.to_string()
-        );
-        let err = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(0),
-        }
-        .validate(5)
-        .unwrap_err();
-        assert_eq!(err.to_string(), ContractError::ZeroThreshold {}.to_string());
-    }
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ConfigResponse {
+    pub threshold: Threshold,
+    pub max_voting_period: Duration,
+    pub min_voting_period: Option<Duration>,
+    pub group_addr: Cw4Contract,
+    pub deposit: Option<DepositConfig>,
+    pub early_execution: bool,
+    pub executor: crate::state::Executor,
+}
 
-    #[test]
-    fn threshold_response() {
-        let total_weight: u64 = 100;
+/// cw3 ProposalResponse, extended with the deposit. cw3 clients can parse it as usual
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ProposalResponse {
+    pub id: u64,
+    pub title: String,
+    pub description: String,
+    pub msgs: Vec<CosmosMsg<Empty>>,
+    pub kind: ProposalKind,
+    pub status: Status,
+    pub expires: Expiration,
+    pub threshold: ThresholdResponse,
+    /// the deposit paid by the proposer and whether it was released already,
+    /// None if no deposit was required
+    pub deposit: Option<Deposit>,
+}
 
-        let res = Threshold::AbsoluteCount { weight: 42 }.to_response(total_weight);
-        assert_eq!(
-            res,
-            ThresholdResponse::AbsoluteCount {
-                weight: 42,
-                total_weight
-            }
-        );
+/// TallyResponse sums up the votes on a proposal, decided the same way as its status
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TallyResponse {
+    /// weight voted for each option
+    pub votes: Votes,
+    /// share of total_weight voted for each option
+    pub percentages: VotePercentages,
+    /// the total weight when the proposal started
+    pub total_weight: u64,
+    /// true if enough weight voted (including abstain), always true without a quorum
+    pub quorum_reached: bool,
+    /// true if the yes votes cast so far meet the threshold, see Proposal::threshold_reached
+    pub threshold_reached: bool,
+    /// the status the proposal would have if it expired right now
+    pub status_if_expired: Status,
+}
 
-        let res = Threshold::AbsolutePercentage {
-            percentage: Decimal::percent(51),
-        }
-        .to_response(total_weight);
-        assert_eq!(
-            res,
-            ThresholdResponse::AbsolutePercentage {
-                percentage: Decimal::percent(51),
-                total_weight
-            }
-        );
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VotePercentages {
+    pub yes: Decimal,
+    pub no: Decimal,
+    pub abstain: Decimal,
+    pub veto: Decimal,
+}
 
-        let res = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(66),
-            quorum: Decimal::percent(50),
-        }
-        .to_response(total_weight);
-        assert_eq!(
-            res,
-            ThresholdResponse::ThresholdQuorum {
-                threshold: Decimal::percent(66),
-                quorum: Decimal::percent(50),
-                total_weight
-            }
-        );
-    }
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ProposalListResponse {
+    pub proposals: Vec<ProposalResponse>,
+    /// The id to pass as start_after (or start_before) for the next page, None if there are
+    /// no more proposals, see cw3::ProposalListResponse
+    #[serde(default)]
+    pub next: Option<u64>,
 }
@@ -6,12 +6,11 @@ use cosmwasm_std::{
     Addr, BlockInfo, CosmosMsg, Decimal, Empty, StdError, StdResult, Storage, Uint128,
 };
 
-use cw0::{Duration, Expiration};
-use cw3::{Status, Vote};
+use cw0::{Duration, Expiration, Scheduled};
+use cw20::Denom;
+use cw3::{Ballot, ProposalKind, Status, Threshold, Vote};
 use cw4::Cw4Contract;
-use cw_storage_plus::{Item, Map, U64Key};
-
-use crate::msg::Threshold;
+use cw_storage_plus::{IndexList, IndexedMap, Item, Map, MultiIndex, U64Key, U8Key};
 
 // we multiply by this when calculating needed_votes in order to round up properly
 // Note: `10u128.pow(9)` fails as "u128::pow` is not yet stable as a const fn"
@@ -21,8 +20,49 @@ const PRECISION_FACTOR: u128 = 1_000_000_000;
 pub struct Config {
     pub threshold: Threshold,
     pub max_voting_period: Duration,
+    // proposals cannot pick a shorter voting period, None if there is no minimum
+    pub min_voting_period: Option<Duration>,
     // Total weight and voters are queried from this contract
     pub group_addr: Cw4Contract,
+    // the deposit needed to create a proposal, None if proposals are free
+    pub deposit: Option<DepositConfig>,
+    // if true, proposals are rejected as soon as they cannot pass anymore,
+    // otherwise only once they expire
+    #[serde(default)]
+    pub early_execution: bool,
+    // who may execute passed proposals, anybody for contracts from before it
+    #[serde(default)]
+    pub executor: Executor,
+}
+
+/// Executor restricts who may execute passed proposals, like msg::Executor but validated
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Executor {
+    /// anyone can execute passed proposals
+    Anybody,
+    /// only current members of the group (with any weight) can execute them
+    OnlyMembers,
+    /// only this address can execute them
+    Addr(Addr),
+}
+
+// deriving Default for enums needs a newer rust than we support
+#[allow(clippy::derivable_impls)]
+impl Default for Executor {
+    fn default() -> Self {
+        Executor::Anybody
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DepositConfig {
+    pub denom: Denom,
+    pub amount: Uint128,
+    // if true, the deposits of rejected proposals are refunded as well
+    pub refund_failed: bool,
+    // forfeited deposits are sent here, they are burned if None
+    pub forfeit_addr: Option<Addr>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -30,15 +70,47 @@ pub struct Proposal {
     pub title: String,
     pub description: String,
     pub start_height: u64,
+    // votes are only accepted once this is triggered, None if open from start_height
+    pub start: Option<Scheduled>,
     pub expires: Expiration,
     pub msgs: Vec<CosmosMsg<Empty>>,
+    // Signaling if there are no msgs, see ProposalKind (proposals from before it are Executable)
+    #[serde(default)]
+    pub kind: ProposalKind,
     pub status: Status,
     /// pass requirements
     pub threshold: Threshold,
+    // copied from the config, see Config::early_execution
+    #[serde(default)]
+    pub early_execution: bool,
     // the total weight when the proposal started (used to calculate percentages)
     pub total_weight: u64,
     // summary of existing votes
     pub votes: Votes,
+    // the deposit paid by the proposer, if one was required
+    pub deposit: Option<Deposit>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Deposit {
+    pub depositor: Addr,
+    pub denom: Denom,
+    pub amount: Uint128,
+    // copied from the config when the proposal was created
+    pub refund_failed: bool,
+    pub forfeit_addr: Option<Addr>,
+    pub status: DepositStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositStatus {
+    /// held by the contract until the proposal is executed or closed
+    Held,
+    /// sent back to the depositor
+    Refunded,
+    /// sent to the forfeit address or burned
+    Forfeited,
 }
 
 // weight of votes for each option
@@ -74,6 +146,16 @@ impl Votes {
             Vote::Veto => self.veto += weight,
         }
     }
+
+    /// removes a vote that was added before, eg. when a voter changes their vote
+    pub fn remove_vote(&mut self, vote: Vote, weight: u64) {
+        match vote {
+            Vote::Yes => self.yes -= weight,
+            Vote::Abstain => self.abstain -= weight,
+            Vote::No => self.no -= weight,
+            Vote::Veto => self.veto -= weight,
+        }
+    }
 }
 
 impl Proposal {
@@ -82,26 +164,71 @@ impl Proposal {
     pub fn current_status(&self, block: &BlockInfo) -> Status {
         let mut status = self.status;
 
-        // if open, check if voting is passed or timed out
+        // if open, check if voting is passed or timed out.
+        // With early execution, also check if it cannot pass anymore
         if status == Status::Open && self.is_passed(block) {
             status = Status::Passed;
         }
         if status == Status::Open && self.expires.is_expired(block) {
             status = Status::Rejected;
         }
+        if status == Status::Open && self.early_execution && self.is_rejected() {
+            status = Status::Rejected;
+        }
 
         status
     }
 
-    /// update_status sets the status of the proposal to current_status.
+    /// update_status sets the status of the proposal to Passed, if current_status is Passed.
+    /// Rejected proposals are only marked as such when they are closed.
     /// (designed for handler logic)
     pub fn update_status(&mut self, block: &BlockInfo) {
-        self.status = self.current_status(block);
+        if self.current_status(block) == Status::Passed {
+            self.status = Status::Passed;
+        }
+    }
+
+    /// is_started returns true once the proposal accepts votes
+    pub fn is_started(&self, block: &BlockInfo) -> bool {
+        match self.start {
+            Some(start) => start.is_triggered(block),
+            None => true,
+        }
     }
 
     // returns true iff this proposal is sure to pass (even before expiration if no future
     // sequence of possible votes can cause it to fail)
     pub fn is_passed(&self, block: &BlockInfo) -> bool {
+        // we always require the quorum
+        if !self.quorum_reached() {
+            return false;
+        }
+        match self.threshold {
+            Threshold::ThresholdQuorum { threshold, .. } if !self.expires.is_expired(block) => {
+                // If not expired, we must assume all non-votes will be cast as No.
+                // We compare threshold against the total weight (minus abstain).
+                let possible_opinions = self.total_weight - self.votes.abstain;
+                self.votes.yes >= votes_needed(possible_opinions, threshold)
+            }
+            _ => self.threshold_reached(),
+        }
+    }
+
+    /// quorum_reached returns true if enough weight voted (including abstain).
+    /// Always true for thresholds without a quorum
+    pub fn quorum_reached(&self) -> bool {
+        match self.threshold {
+            Threshold::ThresholdQuorum { quorum, .. } => {
+                self.votes.total() >= votes_needed(self.total_weight, quorum)
+            }
+            _ => true,
+        }
+    }
+
+    /// threshold_reached returns true if the yes votes cast so far meet the threshold.
+    /// For ThresholdQuorum, they are compared against the votes cast (minus abstain), which is
+    /// how the proposal is decided once it expired
+    pub fn threshold_reached(&self) -> bool {
         match self.threshold {
             Threshold::AbsoluteCount {
                 weight: weight_needed,
@@ -112,21 +239,38 @@ impl Proposal {
                 self.votes.yes
                     >= votes_needed(self.total_weight - self.votes.abstain, percentage_needed)
             }
-            Threshold::ThresholdQuorum { threshold, quorum } => {
-                // we always require the quorum
-                if self.votes.total() < votes_needed(self.total_weight, quorum) {
-                    return false;
-                }
-                if self.expires.is_expired(block) {
-                    // If expired, we compare Yes votes against the total number of votes (minus abstain).
-                    let opinions = self.votes.total() - self.votes.abstain;
-                    self.votes.yes >= votes_needed(opinions, threshold)
-                } else {
-                    // If not expired, we must assume all non-votes will be cast as No.
-                    // We compare threshold against the total weight (minus abstain).
-                    let possible_opinions = self.total_weight - self.votes.abstain;
-                    self.votes.yes >= votes_needed(possible_opinions, threshold)
-                }
+            Threshold::ThresholdQuorum { threshold, .. } => {
+                let opinions = self.votes.total() - self.votes.abstain;
+                self.votes.yes >= votes_needed(opinions, threshold)
+            }
+        }
+    }
+
+    /// status_if_expired returns the status the proposal would have if it expired right now,
+    /// with the votes cast so far. Proposals that are no longer open keep their current_status
+    pub fn status_if_expired(&self, block: &BlockInfo) -> Status {
+        match self.current_status(block) {
+            Status::Open if self.quorum_reached() && self.threshold_reached() => Status::Passed,
+            Status::Open => Status::Rejected,
+            status => status,
+        }
+    }
+
+    // returns true iff this proposal can no longer pass, even if all remaining weight
+    // was cast as Yes
+    pub fn is_rejected(&self) -> bool {
+        let max_yes = self.votes.yes + self.total_weight.saturating_sub(self.votes.total());
+        match self.threshold {
+            Threshold::AbsoluteCount {
+                weight: weight_needed,
+            } => max_yes < weight_needed,
+            // the abstained weight is not counted, and the quorum is always met
+            // once everybody voted
+            Threshold::AbsolutePercentage {
+                percentage: threshold,
+            }
+            | Threshold::ThresholdQuorum { threshold, .. } => {
+                max_yes < votes_needed(self.total_weight - self.votes.abstain, threshold)
             }
         }
     }
@@ -140,21 +284,30 @@ fn votes_needed(weight: u64, percentage: Decimal) -> u64 {
     ((applied.u128() + PRECISION_FACTOR - 1) / PRECISION_FACTOR) as u64
 }
 
-// we cast a ballot with our chosen vote and a given weight
-// stored under the key that voted
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct Ballot {
-    pub weight: u64,
-    pub vote: Vote,
-}
-
 // unique items
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
 
 // multiple-item map
 pub const BALLOTS: Map<(U64Key, &Addr), Ballot> = Map::new("votes");
-pub const PROPOSALS: Map<U64Key, Proposal> = Map::new("proposals");
+
+#[derive(IndexList)]
+#[index_list(Proposal)]
+pub struct ProposalIndexes<'a> {
+    /// the stored status, see cw3::stored_statuses
+    pub status: MultiIndex<'a, U8Key, Proposal>,
+}
+
+pub fn proposals<'a>() -> IndexedMap<'a, U64Key, Proposal, ProposalIndexes<'a>> {
+    let indexes = ProposalIndexes {
+        status: MultiIndex::new(
+            |p| U8Key::new(p.status as u8),
+            "proposals",
+            "proposals__status",
+        ),
+    };
+    IndexedMap::new("proposals", indexes)
+}
 
 pub fn next_id(store: &mut dyn Storage) -> StdResult<u64> {
     let id: u64 = PROPOSAL_COUNT.may_load(store)?.unwrap_or_default() + 1;
@@ -189,6 +342,12 @@ mod test {
         assert_eq!(votes.no, 10);
         assert_eq!(votes.veto, 20);
         assert_eq!(votes.abstain, 40);
+
+        votes.remove_vote(Vote::Veto, 20);
+        votes.remove_vote(Vote::Yes, 5);
+        assert_eq!(votes.total(), 80);
+        assert_eq!(votes.yes, 30);
+        assert_eq!(votes.veto, 0);
     }
 
     #[test]
@@ -205,29 +364,46 @@ mod test {
         assert_eq!(12, votes_needed(48, Decimal::percent(25)));
     }
 
-    fn check_is_passed(
+    fn mock_proposal(
         threshold: Threshold,
         votes: Votes,
         total_weight: u64,
         is_expired: bool,
-    ) -> bool {
+    ) -> Proposal {
         let block = mock_env().block;
         let expires = match is_expired {
             true => Expiration::AtHeight(block.height - 5),
             false => Expiration::AtHeight(block.height + 100),
         };
-        let prop = Proposal {
+        Proposal {
             title: "Demo".to_string(),
             description: "Info".to_string(),
             start_height: 100,
+            start: None,
             expires,
             msgs: vec![],
+            kind: ProposalKind::Signaling,
             status: Status::Open,
             threshold,
+            early_execution: true,
             total_weight,
             votes,
-        };
-        prop.is_passed(&block)
+            deposit: None,
+        }
+    }
+
+    fn check_is_passed(
+        threshold: Threshold,
+        votes: Votes,
+        total_weight: u64,
+        is_expired: bool,
+    ) -> bool {
+        let prop = mock_proposal(threshold, votes, total_weight, is_expired);
+        prop.is_passed(&mock_env().block)
+    }
+
+    fn check_is_rejected(threshold: Threshold, votes: Votes, total_weight: u64) -> bool {
+        mock_proposal(threshold, votes, total_weight, false).is_rejected()
     }
 
     #[test]
@@ -412,4 +588,193 @@ mod test {
         );
         assert_eq!(true, check_is_passed(quorum, passes_early, 15, true));
     }
+
+    #[test]
+    fn proposal_rejected_early() {
+        let votes = |yes, no, abstain| Votes {
+            yes,
+            no,
+            abstain,
+            veto: 0,
+        };
+
+        // 10 yes needed, 15 total
+        let fixed = Threshold::AbsoluteCount { weight: 10 };
+        assert!(!check_is_rejected(fixed.clone(), votes(3, 5, 0), 15));
+        assert!(check_is_rejected(fixed.clone(), votes(3, 6, 0), 15));
+        // abstaining is as good as voting no
+        assert!(check_is_rejected(fixed, votes(3, 5, 1), 15));
+
+        // 50% of (15 - abstain) yes needed
+        let percent = Threshold::AbsolutePercentage {
+            percentage: Decimal::percent(50),
+        };
+        assert!(!check_is_rejected(percent.clone(), votes(2, 7, 0), 15));
+        assert!(check_is_rejected(percent.clone(), votes(2, 8, 0), 15));
+        // 2 + 4 yes at most, but 7 of 13 needed
+        assert!(check_is_rejected(percent, votes(2, 7, 2), 15));
+
+        // the quorum is met once everybody voted, so only the threshold decides
+        let quorum = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(60),
+            quorum: Decimal::percent(80),
+        };
+        assert!(!check_is_rejected(quorum.clone(), votes(0, 0, 0), 20));
+        // 6 + 6 yes at most, 12 of 20 needed
+        assert!(!check_is_rejected(quorum.clone(), votes(6, 8, 0), 20));
+        assert!(check_is_rejected(quorum.clone(), votes(6, 9, 0), 20));
+        // abstain lowers the yes needed: 6 + 5 yes at most, 9 of 15 needed
+        assert!(!check_is_rejected(quorum.clone(), votes(6, 4, 5), 20));
+        // 6 + 4 yes at most, 10 of 16 needed
+        assert!(!check_is_rejected(quorum.clone(), votes(6, 6, 4), 20));
+        // 6 + 3 yes at most, 10 of 16 needed
+        assert!(check_is_rejected(quorum, votes(6, 7, 4), 20));
+    }
+
+    // returns (quorum_reached, threshold_reached, status_if_expired) of an open proposal
+    fn check_tally(threshold: Threshold, votes: Votes, total_weight: u64) -> (bool, bool, Status) {
+        let block = mock_env().block;
+        let prop = mock_proposal(threshold.clone(), votes.clone(), total_weight, false);
+        let projected = prop.status_if_expired(&block);
+        // the projection matches the status once it actually expired
+        let expired = mock_proposal(threshold, votes, total_weight, true);
+        assert_eq!(projected, expired.current_status(&block));
+        (prop.quorum_reached(), prop.threshold_reached(), projected)
+    }
+
+    #[test]
+    fn tally_quorum() {
+        let quorum = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(50),
+            quorum: Decimal::percent(40),
+        };
+        let passing = Votes {
+            yes: 7,
+            no: 3,
+            abstain: 2,
+            veto: 1,
+        };
+        // not passed while open, as the remaining votes could be no, but it would pass if
+        // it expired now: over quorum (40% of 30 = 12), over threshold (7/11 > 50%)
+        assert!(!check_is_passed(quorum.clone(), passing.clone(), 30, false));
+        assert_eq!(
+            check_tally(quorum.clone(), passing.clone(), 30),
+            (true, true, Status::Passed)
+        );
+        // under quorum (40% of 33 = 13.2 > 13), even though the threshold is reached
+        assert_eq!(
+            check_tally(quorum.clone(), passing, 33),
+            (false, true, Status::Rejected)
+        );
+        // abstain counts for the quorum, but not for the threshold: 6 / (6 + 4 + 2)
+        let passes_ignoring_abstain = Votes {
+            yes: 6,
+            no: 4,
+            abstain: 5,
+            veto: 2,
+        };
+        assert_eq!(
+            check_tally(quorum.clone(), passes_ignoring_abstain, 40),
+            (true, true, Status::Passed)
+        );
+        let failing = Votes {
+            yes: 6,
+            no: 5,
+            abstain: 2,
+            veto: 2,
+        };
+        assert_eq!(
+            check_tally(quorum, failing, 20),
+            (true, false, Status::Rejected)
+        );
+    }
+
+    #[test]
+    fn tally_quorum_edge_cases() {
+        let quorum = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(60),
+            quorum: Decimal::percent(80),
+        };
+        // 90% of the votes are yes, but too few voted
+        let missing_voters = Votes {
+            yes: 9,
+            no: 1,
+            abstain: 0,
+            veto: 0,
+        };
+        assert_eq!(
+            check_tally(quorum.clone(), missing_voters, 15),
+            (false, true, Status::Rejected)
+        );
+        // still open with early execution, but passes once expired
+        let wait_til_expired = Votes {
+            yes: 8,
+            no: 1,
+            abstain: 0,
+            veto: 3,
+        };
+        let block = mock_env().block;
+        let prop = mock_proposal(quorum.clone(), wait_til_expired.clone(), 15, false);
+        assert_eq!(prop.current_status(&block), Status::Open);
+        assert_eq!(
+            check_tally(quorum.clone(), wait_til_expired, 15),
+            (true, true, Status::Passed)
+        );
+        // already passed early, this does not change
+        let passes_early = Votes {
+            yes: 9,
+            no: 3,
+            abstain: 0,
+            veto: 0,
+        };
+        let mut prop = mock_proposal(quorum.clone(), passes_early.clone(), 15, false);
+        assert_eq!(prop.current_status(&block), Status::Passed);
+        assert_eq!(
+            check_tally(quorum.clone(), passes_early, 15),
+            (true, true, Status::Passed)
+        );
+        // nor does any other final status
+        prop.status = Status::Executed;
+        assert_eq!(prop.status_if_expired(&block), Status::Executed);
+
+        // everybody abstained: the quorum is met, and no opinions means no yes votes are needed
+        let all_abstain = Votes {
+            yes: 0,
+            no: 0,
+            abstain: 15,
+            veto: 0,
+        };
+        assert_eq!(
+            check_tally(quorum, all_abstain, 15),
+            (true, true, Status::Passed)
+        );
+    }
+
+    #[test]
+    fn tally_without_quorum() {
+        let votes = Votes {
+            yes: 7,
+            no: 4,
+            abstain: 2,
+            veto: 0,
+        };
+        // there is no quorum to reach
+        let fixed = Threshold::AbsoluteCount { weight: 8 };
+        assert_eq!(
+            check_tally(fixed, votes.clone(), 15),
+            (true, false, Status::Rejected)
+        );
+        // 7 of (15 - 2) passes, 7 of (17 - 2) does not
+        let percent = Threshold::AbsolutePercentage {
+            percentage: Decimal::percent(50),
+        };
+        assert_eq!(
+            check_tally(percent.clone(), votes.clone(), 15),
+            (true, true, Status::Passed)
+        );
+        assert_eq!(
+            check_tally(percent, votes, 17),
+            (true, false, Status::Rejected)
+        );
+    }
 }
@@ -1,8 +1,8 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
-    SubMsg,
+    attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Reply, Response,
+    StdResult, SubMsg,
 };
 use cw0::maybe_addr;
 use cw2::set_contract_version;
@@ -20,6 +20,9 @@ use crate::state::{ADMIN, HOOKS, MEMBERS, TOTAL};
 const CONTRACT_NAME: &str = "crates.io:cw4-group";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// a hook is called with reply id HOOK_REPLY_ID plus the number of hooks added after it
+pub const HOOK_REPLY_ID: u64 = 1;
+
 // Note, you can use StdResult in some functions where you do not
 // make use of the custom errors
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -42,9 +45,7 @@ pub fn create(
     members: Vec<Member>,
     height: u64,
 ) -> Result<(), ContractError> {
-    let admin_addr = admin
-        .map(|admin| deps.api.addr_validate(&admin))
-        .transpose()?;
+    let admin_addr = maybe_addr(deps.api, admin)?;
     ADMIN.set(deps.branch(), admin_addr)?;
 
     let mut total = 0u64;
@@ -53,7 +54,7 @@ pub fn create(
         let member_addr = deps.api.addr_validate(&member.addr)?;
         MEMBERS.save(deps.storage, &member_addr, &member.weight, height)?;
     }
-    TOTAL.save(deps.storage, &total)?;
+    TOTAL.save(deps.storage, &total, height)?;
 
     Ok(())
 }
@@ -68,11 +69,9 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     let api = deps.api;
     match msg {
-        ExecuteMsg::UpdateAdmin { admin } => Ok(ADMIN.execute_update_admin(
-            deps,
-            info,
-            admin.map(|admin| api.addr_validate(&admin)).transpose()?,
-        )?),
+        ExecuteMsg::UpdateAdmin { admin } => {
+            Ok(ADMIN.execute_force_update_admin(deps, info, maybe_addr(api, admin)?)?)
+        }
         ExecuteMsg::UpdateMembers { add, remove } => {
             execute_update_members(deps, env, info, add, remove)
         }
@@ -101,8 +100,8 @@ pub fn execute_update_members(
 
     // make the local update
     let diff = update_members(deps.branch(), env.block.height, info.sender, add, remove)?;
-    // call all registered hooks
-    let messages = HOOKS.prepare_hooks(deps.storage, |h| {
+    // call all registered hooks, a failing one is removed in reply
+    let messages = HOOKS.prepare_hooks_as_submsgs(deps.storage, HOOK_REPLY_ID, |h| {
         diff.clone().into_cosmos_msg(h).map(SubMsg::new)
     })?;
     Ok(Response {
@@ -148,10 +147,15 @@ pub fn update_members(
         }
     }
 
-    TOTAL.save(deps.storage, &total)?;
+    TOTAL.save(deps.storage, &total, height)?;
     Ok(MemberChangedHookMsg { diffs })
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    Ok(HOOKS.handle_hook_reply(deps.storage, HOOK_REPLY_ID, msg)?)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -162,14 +166,20 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::ListMembers { start_after, limit } => {
             to_binary(&list_members(deps, start_after, limit)?)
         }
-        QueryMsg::TotalWeight {} => to_binary(&query_total_weight(deps)?),
+        QueryMsg::TotalWeight { at_height } => to_binary(&query_total_weight(deps, at_height)?),
         QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
         QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
     }
 }
 
-fn query_total_weight(deps: Deps) -> StdResult<TotalWeightResponse> {
-    let weight = TOTAL.load(deps.storage)?;
+fn query_total_weight(deps: Deps, height: Option<u64>) -> StdResult<TotalWeightResponse> {
+    let weight = match height {
+        // there is no total before the contract was created
+        Some(h) => TOTAL
+            .may_load_at_height(deps.storage, h)?
+            .unwrap_or_default(),
+        None => TOTAL.load(deps.storage)?,
+    };
     Ok(TotalWeightResponse { weight })
 }
 
@@ -214,7 +224,7 @@ fn list_members(
 mod tests {
     use super::*;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{from_slice, Api, OwnedDeps, Querier, Storage};
+    use cosmwasm_std::{from_slice, Api, ContractResult, OwnedDeps, Querier, Storage};
     use cw4::{member_key, TOTAL_KEY};
     use cw_controllers::{AdminError, HookError};
 
@@ -250,7 +260,7 @@ mod tests {
         let res = ADMIN.query_admin(deps.as_ref()).unwrap();
         assert_eq!(Some(INIT_ADMIN.into()), res.admin);
 
-        let res = query_total_weight(deps.as_ref()).unwrap();
+        let res = query_total_weight(deps.as_ref(), None).unwrap();
         assert_eq!(17, res.weight);
     }
 
@@ -289,19 +299,21 @@ mod tests {
         let member3 = query_member(deps.as_ref(), USER3.into(), height).unwrap();
         assert_eq!(member3.weight, user3_weight);
 
+        // compute expected metrics
+        let weights = vec![user1_weight, user2_weight, user3_weight];
+        let sum: u64 = weights.iter().map(|x| x.unwrap_or_default()).sum();
+
+        // the total is snapshotted along with the members
+        let total = query_total_weight(deps.as_ref(), height).unwrap();
+        assert_eq!(sum, total.weight); // 17 - 11 + 15 = 21
+
         // this is only valid if we are not doing a historical query
         if height.is_none() {
-            // compute expected metrics
-            let weights = vec![user1_weight, user2_weight, user3_weight];
-            let sum: u64 = weights.iter().map(|x| x.unwrap_or_default()).sum();
             let count = weights.iter().filter(|x| x.is_some()).count();
 
             // TODO: more detailed compare?
             let members = list_members(deps.as_ref(), None, None).unwrap();
             assert_eq!(count, members.members.len());
-
-            let total = query_total_weight(deps.as_ref()).unwrap();
-            assert_eq!(sum, total.weight); // 17 - 11 + 15 = 21
         }
     }
 
@@ -532,11 +544,39 @@ mod tests {
             MemberDiff::new(USER2, Some(6), None),
         ];
         let hook_msg = MemberChangedHookMsg { diffs };
-        let msg1 = SubMsg::new(hook_msg.clone().into_cosmos_msg(contract1).unwrap());
-        let msg2 = SubMsg::new(hook_msg.into_cosmos_msg(contract2).unwrap());
+        // in the order they were added, each one replying on error
+        let msg1 = SubMsg::reply_on_error(
+            hook_msg.clone().into_cosmos_msg(contract1).unwrap(),
+            HOOK_REPLY_ID + 1,
+        );
+        let msg2 =
+            SubMsg::reply_on_error(hook_msg.into_cosmos_msg(contract2).unwrap(), HOOK_REPLY_ID);
         assert_eq!(res.messages, vec![msg1, msg2]);
     }
 
+    #[test]
+    fn failing_hook_is_removed() {
+        let mut deps = mock_dependencies(&[]);
+        do_instantiate(deps.as_mut());
+
+        let admin_info = mock_info(INIT_ADMIN, &[]);
+        for addr in ["hook1", "hook2"].iter() {
+            let msg = ExecuteMsg::AddHook {
+                addr: addr.to_string(),
+            };
+            execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+        }
+
+        // hook2 reverts
+        let failed = Reply {
+            id: HOOK_REPLY_ID,
+            result: ContractResult::Err("boom".to_string()),
+        };
+        reply(deps.as_mut(), mock_env(), failed).unwrap();
+        let hooks = HOOKS.query_hooks(deps.as_ref()).unwrap();
+        assert_eq!(hooks.hooks, vec!["hook1".to_string()]);
+    }
+
     #[test]
     fn raw_queries_work() {
         // add will over-write and remove have no effect
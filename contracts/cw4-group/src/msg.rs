@@ -34,8 +34,8 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     /// Return AdminResponse
     Admin {},
-    /// Return TotalWeightResponse
-    TotalWeight {},
+    /// Return TotalWeightResponse, at the beginning of block at_height if set
+    TotalWeight { at_height: Option<u64> },
     /// Returns MembersListResponse
     ListMembers {
         start_after: Option<String>,
@@ -2,12 +2,12 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     attr, coins, from_slice, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo,
-    Order, Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
+    Order, Reply, Response, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 
-use cw0::{maybe_addr, NativeBalance};
-use cw2::set_contract_version;
-use cw20::{Balance, Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg, Denom};
+use cw0::{maybe_addr, must_pay};
+use cw2::{assert_contract_version, migrate_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, Denom};
 use cw4::{
     Member, MemberChangedHookMsg, MemberDiff, MemberListResponse, MemberResponse,
     TotalWeightResponse,
@@ -15,19 +15,24 @@ use cw4::{
 use cw_storage_plus::Bound;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, StakedResponse};
-use crate::state::{Config, ADMIN, CLAIMS, CONFIG, HOOKS, MEMBERS, STAKE, TOTAL};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg, StakedResponse};
+use crate::state::{
+    Config, ADMIN, CLAIMS, CONFIG, HOOKS, MEMBERS, MEMBERS_BY_WEIGHT, STAKE, TOTAL,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw4-stake";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// a hook is called with reply id HOOK_REPLY_ID plus the number of hooks added after it
+pub const HOOK_REPLY_ID: u64 = 1;
+
 // Note, you can use StdResult in some functions where you do not
 // make use of the custom errors
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -45,7 +50,7 @@ pub fn instantiate(
         unbonding_period: msg.unbonding_period,
     };
     CONFIG.save(deps.storage, &config)?;
-    TOTAL.save(deps.storage, &0)?;
+    TOTAL.save(deps.storage, &0, env.block.height)?;
 
     Ok(Response::default())
 }
@@ -61,7 +66,7 @@ pub fn execute(
     let api = deps.api;
     match msg {
         ExecuteMsg::UpdateAdmin { admin } => {
-            Ok(ADMIN.execute_update_admin(deps, info, maybe_addr(api, admin)?)?)
+            Ok(ADMIN.execute_force_update_admin(deps, info, maybe_addr(api, admin)?)?)
         }
         ExecuteMsg::AddHook { addr } => {
             Ok(HOOKS.execute_add_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
@@ -69,38 +74,35 @@ pub fn execute(
         ExecuteMsg::RemoveHook { addr } => {
             Ok(HOOKS.execute_remove_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
         }
-        ExecuteMsg::Bond {} => execute_bond(deps, env, Balance::from(info.funds), info.sender),
+        ExecuteMsg::Bond {} => execute_bond(deps, env, info),
         ExecuteMsg::Unbond { tokens: amount } => execute_unbond(deps, env, info, amount),
         ExecuteMsg::Claim {} => execute_claim(deps, env, info),
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
     }
 }
 
-pub fn execute_bond(
-    deps: DepsMut,
-    env: Env,
-    amount: Balance,
-    sender: Addr,
-) -> Result<Response, ContractError> {
+pub fn execute_bond(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
 
     // ensure the sent denom was proper
-    // NOTE: those clones are not needed (if we move denom, we return early),
-    // but the compiler cannot see that (yet...)
-    let amount = match (&cfg.denom, &amount) {
-        (Denom::Native(want), Balance::Native(have)) => must_pay_funds(have, want),
-        (Denom::Cw20(want), Balance::Cw20(have)) => {
-            if want == &have.address {
-                Ok(have.amount)
-            } else {
-                Err(ContractError::InvalidDenom(want.into()))
-            }
+    let amount = match &cfg.denom {
+        Denom::Native(denom) => must_pay(&info, denom)?,
+        Denom::Cw20(_) => {
+            return Err(ContractError::MixedNativeAndCw20(
+                "Invalid address or denom".to_string(),
+            ))
         }
-        _ => Err(ContractError::MixedNativeAndCw20(
-            "Invalid address or denom".to_string(),
-        )),
-    }?;
+    };
+    bond(deps, env, cfg, amount, info.sender)
+}
 
+fn bond(
+    deps: DepsMut,
+    env: Env,
+    cfg: Config,
+    amount: Uint128,
+    sender: Addr,
+) -> Result<Response, ContractError> {
     // update the sender's stake
     let new_stake = STAKE.update(deps.storage, &sender, |stake| -> StdResult<_> {
         Ok(stake.unwrap_or_default() + amount)
@@ -138,15 +140,19 @@ pub fn execute_receive(
     // This cannot be fully trusted (the cw20 contract can fake it), so only use it for actions
     // in the address's favor (like paying/bonding tokens, not withdrawls)
     let msg: ReceiveMsg = from_slice(&wrapper.msg)?;
-    let balance = Balance::Cw20(Cw20CoinVerified {
-        address: info.sender,
-        amount: wrapper.amount,
-    });
-    let api = deps.api;
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // ensure the sent token was proper
+    match &cfg.denom {
+        Denom::Cw20(want) if want == &info.sender => Ok(()),
+        Denom::Cw20(want) => Err(ContractError::InvalidDenom(want.into())),
+        Denom::Native(_) => Err(ContractError::MixedNativeAndCw20(
+            "Invalid address or denom".to_string(),
+        )),
+    }?;
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
     match msg {
-        ReceiveMsg::Bond {} => {
-            execute_bond(deps, env, balance, api.addr_validate(&wrapper.sender)?)
-        }
+        ReceiveMsg::Bond {} => bond(deps, env, cfg, wrapper.amount, sender),
     }
 }
 
@@ -191,22 +197,6 @@ pub fn execute_unbond(
     })
 }
 
-pub fn must_pay_funds(balance: &NativeBalance, denom: &str) -> Result<Uint128, ContractError> {
-    match balance.0.len() {
-        0 => Err(ContractError::NoFunds {}),
-        1 => {
-            let balance = &balance.0;
-            let payment = balance[0].amount;
-            if balance[0].denom == denom {
-                Ok(payment)
-            } else {
-                Err(ContractError::MissingDenom(denom.to_string()))
-            }
-        }
-        _ => Err(ContractError::ExtraDenoms(denom.to_string())),
-    }
-}
-
 fn update_membership(
     storage: &mut dyn Storage,
     sender: Addr,
@@ -224,18 +214,24 @@ fn update_membership(
     }
     // otherwise, record change of weight
     match new.as_ref() {
-        Some(w) => MEMBERS.save(storage, &sender, w, height),
-        None => MEMBERS.remove(storage, &sender, height),
+        Some(w) => {
+            MEMBERS.save(storage, &sender, w, height)?;
+            MEMBERS_BY_WEIGHT.save(storage, &sender, w)
+        }
+        None => {
+            MEMBERS.remove(storage, &sender, height)?;
+            MEMBERS_BY_WEIGHT.remove(storage, &sender)
+        }
     }?;
 
     // update total
-    TOTAL.update(storage, |total| -> StdResult<_> {
-        Ok(total + new.unwrap_or_default() - old.unwrap_or_default())
+    TOTAL.update(storage, height, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default() + new.unwrap_or_default() - old.unwrap_or_default())
     })?;
 
-    // alert the hooks
+    // alert the hooks, a failing one is removed in reply
     let diff = MemberDiff::new(sender, old, new);
-    HOOKS.prepare_hooks(storage, |h| {
+    HOOKS.prepare_hooks_as_submsgs(storage, HOOK_REPLY_ID, |h| {
         MemberChangedHookMsg::one(diff.clone())
             .into_cosmos_msg(h)
             .map(SubMsg::new)
@@ -305,6 +301,43 @@ fn coin_to_string(amount: Uint128, denom: &str) -> String {
     format!("{} {}", amount, denom)
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    Ok(HOOKS.handle_hook_reply(deps.storage, HOOK_REPLY_ID, msg)?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // no downgrades
+    assert_contract_version(
+        deps.storage,
+        CONTRACT_NAME,
+        &format!("<={}", CONTRACT_VERSION),
+    )?;
+    // MEMBERS_BY_WEIGHT only has the members that bonded since it was added, add all others.
+    // This goes through all members in one transaction
+    let members = MEMBERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, weight) in members.iter() {
+        let addr = Addr::unchecked(String::from_utf8(key.clone()).map_err(StdError::from)?);
+        MEMBERS_BY_WEIGHT.save(deps.storage, &addr, weight)?;
+    }
+    migrate_version(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        env.block.height,
+    )?;
+    Ok(Response {
+        attributes: vec![
+            attr("action", "migrate"),
+            attr("indexed_members", members.len()),
+        ],
+        ..Response::default()
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -315,7 +348,8 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::ListMembers { start_after, limit } => {
             to_binary(&list_members(deps, start_after, limit)?)
         }
-        QueryMsg::TotalWeight {} => to_binary(&query_total_weight(deps)?),
+        QueryMsg::ListMembersByWeight { limit } => to_binary(&list_members_by_weight(deps, limit)?),
+        QueryMsg::TotalWeight { at_height } => to_binary(&query_total_weight(deps, at_height)?),
         QueryMsg::Claims { address } => {
             to_binary(&CLAIMS.query_claims(deps, &deps.api.addr_validate(&address)?)?)
         }
@@ -325,8 +359,14 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     }
 }
 
-fn query_total_weight(deps: Deps) -> StdResult<TotalWeightResponse> {
-    let weight = TOTAL.load(deps.storage)?;
+fn query_total_weight(deps: Deps, height: Option<u64>) -> StdResult<TotalWeightResponse> {
+    let weight = match height {
+        // there is no total before the contract was created
+        Some(h) => TOTAL
+            .may_load_at_height(deps.storage, h)?
+            .unwrap_or_default(),
+        None => TOTAL.load(deps.storage)?,
+    };
     Ok(TotalWeightResponse { weight })
 }
 
@@ -374,13 +414,30 @@ fn list_members(
     Ok(MemberListResponse { members: members? })
 }
 
+fn list_members_by_weight(deps: Deps, limit: Option<u32>) -> StdResult<MemberListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let members: StdResult<Vec<_>> = MEMBERS_BY_WEIGHT
+        .top(deps.storage, limit)
+        .into_iter()
+        .map(|(key, weight)| {
+            Ok(Member {
+                addr: String::from_utf8(key)?,
+                weight,
+            })
+        })
+        .collect();
+
+    Ok(MemberListResponse { members: members? })
+}
+
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use cosmwasm_std::{
         coin, from_slice, CosmosMsg, OverflowError, OverflowOperation, StdError, Storage,
     };
-    use cw0::Duration;
+    use cw0::{Duration, PaymentError};
+    use cw2::VersionError;
     use cw20::Denom;
     use cw4::{member_key, TOTAL_KEY};
     use cw_controllers::{AdminError, Claim, HookError};
@@ -491,7 +548,7 @@ mod tests {
         let res = ADMIN.query_admin(deps.as_ref()).unwrap();
         assert_eq!(Some(INIT_ADMIN.into()), res.admin);
 
-        let res = query_total_weight(deps.as_ref()).unwrap();
+        let res = query_total_weight(deps.as_ref(), None).unwrap();
         assert_eq!(0, res.weight);
     }
 
@@ -518,11 +575,22 @@ mod tests {
         let member3 = get_member(deps, USER3.into(), height);
         assert_eq!(member3, user3_weight);
 
+        // compute expected metrics
+        let weights = vec![user1_weight, user2_weight, user3_weight];
+        let sum: u64 = weights.iter().map(|x| x.unwrap_or_default()).sum();
+
+        // the total is snapshotted along with the members
+        let raw = query(
+            deps,
+            mock_env(),
+            QueryMsg::TotalWeight { at_height: height },
+        )
+        .unwrap();
+        let total: TotalWeightResponse = from_slice(&raw).unwrap();
+        assert_eq!(sum, total.weight); // 17 - 11 + 15 = 21
+
         // this is only valid if we are not doing a historical query
         if height.is_none() {
-            // compute expected metrics
-            let weights = vec![user1_weight, user2_weight, user3_weight];
-            let sum: u64 = weights.iter().map(|x| x.unwrap_or_default()).sum();
             let count = weights.iter().filter(|x| x.is_some()).count();
 
             // TODO: more detailed compare?
@@ -533,10 +601,6 @@ mod tests {
             let raw = query(deps, mock_env(), msg).unwrap();
             let members: MemberListResponse = from_slice(&raw).unwrap();
             assert_eq!(count, members.members.len());
-
-            let raw = query(deps, mock_env(), QueryMsg::TotalWeight {}).unwrap();
-            let total: TotalWeightResponse = from_slice(&raw).unwrap();
-            assert_eq!(sum, total.weight); // 17 - 11 + 15 = 21
         }
     }
 
@@ -582,6 +646,99 @@ mod tests {
         // after second stake
     }
 
+    #[test]
+    fn list_members_by_weight() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut());
+
+        let by_weight = |deps: Deps, limit: Option<u32>| -> Vec<(String, u64)> {
+            let msg = QueryMsg::ListMembersByWeight { limit };
+            let res: MemberListResponse =
+                from_slice(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.members
+                .into_iter()
+                .map(|m| (m.addr, m.weight))
+                .collect()
+        };
+        assert_eq!(by_weight(deps.as_ref(), None), vec![]);
+
+        // USER1 and USER3 tie
+        bond(deps.as_mut(), 7_000, 12_000, 7_500, 1);
+        assert_eq!(
+            by_weight(deps.as_ref(), None),
+            vec![
+                (USER2.to_string(), 12),
+                (USER1.to_string(), 7),
+                (USER3.to_string(), 7),
+            ]
+        );
+        assert_eq!(
+            by_weight(deps.as_ref(), Some(1)),
+            vec![(USER2.to_string(), 12)]
+        );
+
+        // USER3 moves to the top, USER2 drops below the minimum and is gone
+        bond(deps.as_mut(), 0, 0, 6_000, 2);
+        unbond(deps.as_mut(), 0, 8_000, 0, 3);
+        assert_eq!(
+            by_weight(deps.as_ref(), None),
+            vec![(USER3.to_string(), 13), (USER1.to_string(), 7)]
+        );
+        assert_users(deps.as_ref(), Some(7), None, Some(13), None);
+    }
+
+    #[test]
+    fn migrate_indexes_members_by_weight() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut());
+        bond(deps.as_mut(), 7_000, 12_000, 7_500, 1);
+
+        // members that bonded before MEMBERS_BY_WEIGHT was added are not in it
+        for user in &[USER1, USER2] {
+            MEMBERS_BY_WEIGHT
+                .remove(deps.as_mut().storage, &Addr::unchecked(*user))
+                .unwrap();
+        }
+        let by_weight = |deps: Deps| -> Vec<(String, u64)> {
+            let msg = QueryMsg::ListMembersByWeight { limit: None };
+            let res: MemberListResponse =
+                from_slice(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.members
+                .into_iter()
+                .map(|m| (m.addr, m.weight))
+                .collect()
+        };
+        assert_eq!(by_weight(deps.as_ref()), vec![(USER3.to_string(), 7)]);
+
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.6.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "migrate"), attr("indexed_members", 3)]
+        );
+        let all = vec![
+            (USER2.to_string(), 12),
+            (USER1.to_string(), 7),
+            (USER3.to_string(), 7),
+        ];
+        assert_eq!(by_weight(deps.as_ref()), all);
+
+        // running it again changes nothing
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(by_weight(deps.as_ref()), all);
+
+        // no downgrades
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Version(VersionError::UnsupportedVersion {
+                version: "99.0.0".to_string(),
+                required: format!("<={}", CONTRACT_VERSION),
+            })
+        );
+    }
+
     #[test]
     fn unbond_stake_update_membership() {
         let mut deps = mock_dependencies(&[]);
@@ -964,8 +1121,14 @@ mod tests {
         assert_eq!(res.messages.len(), 2);
         let diff = MemberDiff::new(USER1, None, Some(13));
         let hook_msg = MemberChangedHookMsg::one(diff);
-        let msg1 = SubMsg::new(hook_msg.clone().into_cosmos_msg(contract1.clone()).unwrap());
-        let msg2 = SubMsg::new(hook_msg.into_cosmos_msg(contract2.clone()).unwrap());
+        let msg1 = SubMsg::reply_on_error(
+            hook_msg.clone().into_cosmos_msg(contract1.clone()).unwrap(),
+            HOOK_REPLY_ID + 1,
+        );
+        let msg2 = SubMsg::reply_on_error(
+            hook_msg.into_cosmos_msg(contract2.clone()).unwrap(),
+            HOOK_REPLY_ID,
+        );
         assert_eq!(res.messages, vec![msg1, msg2]);
 
         // check firing on unbond
@@ -980,8 +1143,12 @@ mod tests {
         assert_eq!(res.messages.len(), 2);
         let diff = MemberDiff::new(USER1, Some(13), Some(6));
         let hook_msg = MemberChangedHookMsg::one(diff);
-        let msg1 = SubMsg::new(hook_msg.clone().into_cosmos_msg(contract1).unwrap());
-        let msg2 = SubMsg::new(hook_msg.into_cosmos_msg(contract2).unwrap());
+        let msg1 = SubMsg::reply_on_error(
+            hook_msg.clone().into_cosmos_msg(contract1).unwrap(),
+            HOOK_REPLY_ID + 1,
+        );
+        let msg2 =
+            SubMsg::reply_on_error(hook_msg.into_cosmos_msg(contract2).unwrap(), HOOK_REPLY_ID);
         assert_eq!(res.messages, vec![msg1, msg2]);
     }
 
@@ -993,22 +1160,34 @@ mod tests {
         // cannot bond with 0 coins
         let info = mock_info(USER1, &[]);
         let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Bond {}).unwrap_err();
-        assert_eq!(err, ContractError::NoFunds {});
+        assert_eq!(err, ContractError::Payment(PaymentError::NoFunds {}));
 
         // cannot bond with incorrect denom
         let info = mock_info(USER1, &[coin(500, "FOO")]);
         let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Bond {}).unwrap_err();
-        assert_eq!(err, ContractError::MissingDenom(DENOM.to_string()));
+        assert_eq!(
+            err,
+            ContractError::Payment(PaymentError::MissingDenom(DENOM.to_string()))
+        );
 
         // cannot bond with 2 coins (even if one is correct)
         let info = mock_info(USER1, &[coin(1234, DENOM), coin(5000, "BAR")]);
         let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Bond {}).unwrap_err();
-        assert_eq!(err, ContractError::ExtraDenoms(DENOM.to_string()));
+        assert_eq!(err, ContractError::Payment(PaymentError::MultipleDenoms {}));
+
+        // the same for 0 of the proper denom
+        let info = mock_info(USER1, &[coin(0, DENOM)]);
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Bond {}).unwrap_err();
+        assert_eq!(err, ContractError::Payment(PaymentError::NoFunds {}));
 
         // can bond with just the proper denom
         // cannot bond with incorrect denom
         let info = mock_info(USER1, &[coin(500, DENOM)]);
         execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Bond {}).unwrap();
+
+        // zero amounts of other denoms don't pay anything, so they are ignored
+        let info = mock_info(USER2, &[coin(0, "BAR"), coin(500, DENOM)]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Bond {}).unwrap();
     }
 
     #[test]
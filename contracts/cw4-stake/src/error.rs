@@ -1,6 +1,8 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
+use cw0::PaymentError;
+use cw2::VersionError;
 use cw_controllers::{AdminError, HookError};
 
 #[derive(Error, Debug, PartialEq)]
@@ -14,27 +16,24 @@ pub enum ContractError {
     #[error("{0}")]
     Hook(#[from] HookError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("{0}")]
+    Version(#[from] VersionError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
     #[error("No claims that can be released currently")]
     NothingToClaim {},
 
-    #[error("Must send '{0}' to stake")]
-    MissingDenom(String),
-
-    #[error("Sent unsupported denoms, must send '{0}' to stake")]
-    ExtraDenoms(String),
-
     #[error("Must send valid address to stake")]
     InvalidDenom(String),
 
     #[error("Missed address or denom")]
     MixedNativeAndCw20(String),
 
-    #[error("No funds sent")]
-    NoFunds {},
-
     #[error("No data in ReceiveMsg")]
     NoData {},
 }
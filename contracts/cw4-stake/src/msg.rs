@@ -18,6 +18,9 @@ pub struct InstantiateMsg {
     pub admin: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
@@ -63,13 +66,20 @@ pub enum QueryMsg {
 
     /// Return AdminResponse
     Admin {},
-    /// Return TotalWeightResponse
-    TotalWeight {},
+    /// Return TotalWeightResponse, at the beginning of block at_height if set
+    TotalWeight {
+        at_height: Option<u64>,
+    },
     /// Returns MembersListResponse
     ListMembers {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Returns MembersListResponse, with the members of the highest weight first.
+    /// Members of the same weight are listed in descending order of address.
+    ListMembersByWeight {
+        limit: Option<u32>,
+    },
     /// Returns MemberResponse
     Member {
         addr: String,
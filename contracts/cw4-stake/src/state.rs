@@ -4,9 +4,9 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_std::{Addr, Uint128};
 use cw0::Duration;
 use cw20::Denom;
-use cw4::TOTAL_KEY;
+use cw4::{TOTAL_KEY, TOTAL_KEY_CHANGELOG, TOTAL_KEY_CHECKPOINTS};
 use cw_controllers::{Admin, Claims, Hooks};
-use cw_storage_plus::{Item, Map, SnapshotMap, Strategy};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, SortedMap, Strategy};
 
 pub const CLAIMS: Claims = Claims::new("claims");
 
@@ -22,7 +22,12 @@ pub struct Config {
 pub const ADMIN: Admin = Admin::new("admin");
 pub const HOOKS: Hooks = Hooks::new("cw4-hooks");
 pub const CONFIG: Item<Config> = Item::new("config");
-pub const TOTAL: Item<u64> = Item::new(TOTAL_KEY);
+pub const TOTAL: SnapshotItem<u64> = SnapshotItem::new(
+    TOTAL_KEY,
+    TOTAL_KEY_CHECKPOINTS,
+    TOTAL_KEY_CHANGELOG,
+    Strategy::EveryBlock,
+);
 
 pub const MEMBERS: SnapshotMap<&Addr, u64> = SnapshotMap::new(
     cw4::MEMBERS_KEY,
@@ -31,4 +36,8 @@ pub const MEMBERS: SnapshotMap<&Addr, u64> = SnapshotMap::new(
     Strategy::EveryBlock,
 );
 
+/// The current weights of MEMBERS again, ordered by weight for ListMembersByWeight
+pub const MEMBERS_BY_WEIGHT: SortedMap<&Addr, u64> =
+    SortedMap::new("members_by_weight", "members_by_weight__order");
+
 pub const STAKE: Map<&Addr, Uint128> = Map::new("stake");
@@ -5,8 +5,8 @@ use cosmwasm_std::{
     StdError, StdResult, SubMsg,
 };
 
-use cw0::maybe_addr;
-use cw2::set_contract_version;
+use cw0::{maybe_addr, paginate_filtered, EventBuilder};
+use cw2::{set_contract_supported_interface, set_contract_version, ContractSupportedInterface};
 use cw721::{
     AllNftInfoResponse, ApprovedForAllResponse, ContractInfoResponse, Cw721ReceiveMsg, Expiration,
     NftInfoResponse, NumTokensResponse, OwnerOfResponse, TokensResponse,
@@ -22,6 +22,12 @@ use cw_storage_plus::Bound;
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw721-base";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// the cw721 spec and its extensions implemented here, these are versioned along with the contract
+const INTERFACES: &[&str] = &[
+    "crates.io:cw721",
+    "crates.io:cw721-metadata",
+    "crates.io:cw721-enumerable",
+];
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -31,6 +37,14 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let interfaces: Vec<_> = INTERFACES
+        .iter()
+        .map(|interface| ContractSupportedInterface {
+            interface: interface.to_string(),
+            version: CONTRACT_VERSION.to_string(),
+        })
+        .collect();
+    set_contract_supported_interface(deps.storage, &interfaces)?;
 
     let info = ContractInfoResponse {
         name: msg.name,
@@ -196,12 +210,7 @@ pub fn execute_approve(
 
     Ok(Response {
         messages: vec![],
-        attributes: vec![
-            attr("action", "approve"),
-            attr("sender", info.sender),
-            attr("spender", spender),
-            attr("token_id", token_id),
-        ],
+        attributes: EventBuilder::approve(info.sender, spender, token_id).attributes(),
         events: vec![],
         data: None,
     })
@@ -483,14 +492,18 @@ fn query_all_approvals(
     let start = start_addr.map(|addr| Bound::exclusive(addr.as_ref()));
 
     let owner_addr = deps.api.addr_validate(&owner)?;
-    let res: StdResult<Vec<_>> = OPERATORS
+    let operators = OPERATORS
         .prefix(&owner_addr)
         .range(deps.storage, start, None, Order::Ascending)
-        .filter(|r| include_expired || r.is_err() || !r.as_ref().unwrap().1.is_expired(&env.block))
-        .take(limit)
-        .map(parse_approval)
-        .collect();
-    Ok(ApprovedForAllResponse { operators: res? })
+        .map(parse_approval);
+    let operators = paginate_filtered(
+        operators,
+        limit,
+        &env.block,
+        include_expired,
+        |approval, block| approval.expires.is_expired(block),
+    )?;
+    Ok(ApprovedForAllResponse { operators })
 }
 
 fn parse_approval(item: StdResult<Pair<Expiration>>) -> StdResult<cw721::Approval> {
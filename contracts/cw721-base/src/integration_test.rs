@@ -0,0 +1,60 @@
+#![cfg(test)]
+
+use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
+use cosmwasm_std::{Addr, Empty};
+use cw2::{query_contract_info, query_supported_interfaces, ContractSupportedInterface};
+use cw_multi_test::{App, Contract, ContractWrapper, SimpleBank};
+
+use crate::msg::InstantiateMsg;
+
+fn mock_app() -> App {
+    let env = mock_env();
+    let api = Box::new(MockApi::default());
+    let bank = SimpleBank {};
+
+    App::new(api, env.block, bank, || Box::new(MockStorage::new()))
+}
+
+fn contract_cw721() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    );
+    Box::new(contract)
+}
+
+#[test]
+fn publishes_supported_interfaces() {
+    let mut router = mock_app();
+    let owner = Addr::unchecked("owner");
+
+    let cw721_id = router.store_code(contract_cw721());
+    let msg = InstantiateMsg {
+        name: "Magic Power".to_string(),
+        symbol: "MGK".to_string(),
+        minter: owner.to_string(),
+    };
+    let nft_addr = router
+        .instantiate_contract(cw721_id, owner, &msg, &[], "MGK")
+        .unwrap();
+
+    let version = env!("CARGO_PKG_VERSION").to_string();
+    let info = query_contract_info(&router, &nft_addr).unwrap();
+    assert_eq!(info.contract, "crates.io:cw721-base");
+    assert_eq!(info.version, version);
+
+    let interfaces = query_supported_interfaces(&router, &nft_addr).unwrap();
+    let expected: Vec<_> = [
+        "crates.io:cw721",
+        "crates.io:cw721-metadata",
+        "crates.io:cw721-enumerable",
+    ]
+    .iter()
+    .map(|interface| ContractSupportedInterface {
+        interface: interface.to_string(),
+        version: version.clone(),
+    })
+    .collect();
+    assert_eq!(interfaces, expected);
+}
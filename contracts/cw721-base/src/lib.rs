@@ -1,5 +1,6 @@
 pub mod contract;
 mod error;
+mod integration_test;
 pub mod msg;
 pub mod state;
 
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Addr, BlockInfo, StdResult, Storage};
 use cw721::{ContractInfoResponse, Expiration};
-use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_storage_plus::{IndexList, IndexedMap, Item, Map, MultiIndex};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TokenInfo {
@@ -51,25 +51,15 @@ pub fn increment_tokens(storage: &mut dyn Storage) -> StdResult<u64> {
     Ok(val)
 }
 
+#[derive(IndexList)]
+#[index_list(TokenInfo)]
 pub struct TokenIndexes<'a> {
-    // pk goes to second tuple element
-    pub owner: MultiIndex<'a, (Vec<u8>, Vec<u8>), TokenInfo>,
-}
-
-impl<'a> IndexList<TokenInfo> for TokenIndexes<'a> {
-    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<TokenInfo>> + '_> {
-        let v: Vec<&dyn Index<TokenInfo>> = vec![&self.owner];
-        Box::new(v.into_iter())
-    }
+    pub owner: MultiIndex<'a, Vec<u8>, TokenInfo>,
 }
 
 pub fn tokens<'a>() -> IndexedMap<'a, &'a str, TokenInfo, TokenIndexes<'a>> {
     let indexes = TokenIndexes {
-        owner: MultiIndex::new(
-            |d, k| (Vec::from(d.owner.as_ref()), k),
-            "tokens",
-            "tokens__owner",
-        ),
+        owner: MultiIndex::new(|d| Vec::from(d.owner.as_ref()), "tokens", "tokens__owner"),
     };
     IndexedMap::new("tokens", indexes)
 }
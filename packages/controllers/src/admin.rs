@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use cosmwasm_std::{attr, Addr, Deps, DepsMut, MessageInfo, Response, StdError, StdResult};
-use cw_storage_plus::Item;
+use cw_storage_plus::{Item, Map};
 
 // TODO: should the return values end up in cw0, so eg. cw4 can import them as well as this module?
 /// Returned from Admin.query_admin()
@@ -12,6 +12,12 @@ pub struct AdminResponse {
     pub admin: Option<String>,
 }
 
+/// Returned from Admin.query_pending_admin()
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingAdminResponse {
+    pub pending_admin: Option<String>,
+}
+
 /// Errors returned from Admin
 #[derive(Error, Debug, PartialEq)]
 pub enum AdminError {
@@ -20,29 +26,50 @@ pub enum AdminError {
 
     #[error("Caller is not admin")]
     NotAdmin {},
+
+    #[error("Caller is not the pending admin")]
+    NotPendingAdmin {},
+
+    #[error("No admin transfer is pending")]
+    NoPendingAdmin {},
 }
 
+// the pending admin is stored under this key inside the admin namespace.
+// it is length-prefixed, so it can never collide with the admin itself.
+const PENDING_KEY: &str = "pending";
+
 // state/logic
-pub struct Admin<'a>(Item<'a, Option<Addr>>);
+pub struct Admin<'a> {
+    admin: Item<'a, Option<Addr>>,
+    pending: Map<'a, &'a str, Addr>,
+}
 
 // this is the core business logic we expose
 impl<'a> Admin<'a> {
     pub const fn new(namespace: &'a str) -> Self {
-        Admin(Item::new(namespace))
+        Admin {
+            admin: Item::new(namespace),
+            pending: Map::new(namespace),
+        }
     }
 
     pub fn set(&self, deps: DepsMut, admin: Option<Addr>) -> StdResult<()> {
-        self.0.save(deps.storage, &admin)
+        self.admin.save(deps.storage, &admin)
     }
 
     pub fn get(&self, deps: Deps) -> StdResult<Option<Addr>> {
-        self.0.load(deps.storage)
+        self.admin.load(deps.storage)
+    }
+
+    /// Returns the address proposed by execute_propose_admin, if it didn't accept yet
+    pub fn get_pending(&self, deps: Deps) -> StdResult<Option<Addr>> {
+        self.pending.may_load(deps.storage, PENDING_KEY)
     }
 
     /// Returns Ok(true) if this is an admin, Ok(false) if not and an Error if
     /// we hit an error with Api or Storage usage
     pub fn is_admin(&self, deps: Deps, caller: &Addr) -> StdResult<bool> {
-        match self.0.load(deps.storage)? {
+        match self.admin.load(deps.storage)? {
             Some(owner) => Ok(caller == &owner),
             None => Ok(false),
         }
@@ -58,7 +85,10 @@ impl<'a> Admin<'a> {
         }
     }
 
-    pub fn execute_update_admin(
+    /// Hands control to new_admin right away (or removes the admin if None).
+    /// Prefer execute_propose_admin, which only does so once the new admin accepted,
+    /// unless you really want the single-step transfer. Any pending proposal is cleared.
+    pub fn execute_force_update_admin(
         &self,
         deps: DepsMut,
         info: MessageInfo,
@@ -76,6 +106,7 @@ impl<'a> Admin<'a> {
             attr("sender", info.sender),
         ];
 
+        self.pending.remove(deps.storage, PENDING_KEY);
         self.set(deps, new_admin)?;
 
         Ok(Response {
@@ -86,10 +117,109 @@ impl<'a> Admin<'a> {
         })
     }
 
+    /// Single-step transfer, as it was called before the two-step transfer was added
+    #[deprecated(note = "use execute_force_update_admin, or the two-step execute_propose_admin")]
+    pub fn execute_update_admin(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        new_admin: Option<Addr>,
+    ) -> Result<Response, AdminError> {
+        self.execute_force_update_admin(deps, info, new_admin)
+    }
+
+    /// First step of the admin transfer: the admin proposes new_admin, who has to call
+    /// execute_accept_admin to take over. Overwrites any previous proposal.
+    pub fn execute_propose_admin(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        new_admin: Addr,
+    ) -> Result<Response, AdminError> {
+        self.assert_admin(deps.as_ref(), &info.sender)?;
+
+        let attributes = vec![
+            attr("action", "propose_admin"),
+            attr("pending_admin", &new_admin),
+            attr("sender", info.sender),
+        ];
+
+        self.pending.save(deps.storage, PENDING_KEY, &new_admin)?;
+
+        Ok(Response {
+            messages: vec![],
+            attributes,
+            events: vec![],
+            data: None,
+        })
+    }
+
+    /// Second step of the admin transfer, called by the pending admin
+    pub fn execute_accept_admin(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response, AdminError> {
+        let pending = self
+            .get_pending(deps.as_ref())?
+            .ok_or(AdminError::NoPendingAdmin {})?;
+        if info.sender != pending {
+            return Err(AdminError::NotPendingAdmin {});
+        }
+
+        let attributes = vec![
+            attr("action", "accept_admin"),
+            attr("admin", &pending),
+            attr("sender", info.sender),
+        ];
+
+        self.pending.remove(deps.storage, PENDING_KEY);
+        self.set(deps, Some(pending))?;
+
+        Ok(Response {
+            messages: vec![],
+            attributes,
+            events: vec![],
+            data: None,
+        })
+    }
+
+    /// Cancels the pending admin transfer, only the current admin can do so
+    pub fn execute_revoke_pending(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response, AdminError> {
+        self.assert_admin(deps.as_ref(), &info.sender)?;
+        let pending = self
+            .get_pending(deps.as_ref())?
+            .ok_or(AdminError::NoPendingAdmin {})?;
+
+        let attributes = vec![
+            attr("action", "revoke_pending_admin"),
+            attr("pending_admin", pending),
+            attr("sender", info.sender),
+        ];
+
+        self.pending.remove(deps.storage, PENDING_KEY);
+
+        Ok(Response {
+            messages: vec![],
+            attributes,
+            events: vec![],
+            data: None,
+        })
+    }
+
     pub fn query_admin(&self, deps: Deps) -> StdResult<AdminResponse> {
         let admin = self.get(deps)?.map(String::from);
         Ok(AdminResponse { admin })
     }
+
+    pub fn query_pending_admin(&self, deps: Deps) -> StdResult<PendingAdminResponse> {
+        let pending_admin = self.get_pending(deps)?.map(String::from);
+        Ok(PendingAdminResponse { pending_admin })
+    }
 }
 
 #[cfg(test)]
@@ -160,19 +290,138 @@ mod tests {
         let info = mock_info(imposter.as_ref(), &[]);
         let new_admin = Some(friend.clone());
         let err = control
-            .execute_update_admin(deps.as_mut(), info, new_admin.clone())
+            .execute_force_update_admin(deps.as_mut(), info, new_admin.clone())
             .unwrap_err();
         assert_eq!(AdminError::NotAdmin {}, err);
 
         // owner can update
         let info = mock_info(owner.as_ref(), &[]);
         let res = control
-            .execute_update_admin(deps.as_mut(), info, new_admin)
+            .execute_force_update_admin(deps.as_mut(), info, new_admin)
             .unwrap();
         assert_eq!(0, res.messages.len());
 
         // query shows results
         let res = control.query_admin(deps.as_ref()).unwrap();
         assert_eq!(Some(friend.to_string()), res.admin);
+
+        // the deprecated name still works the same way
+        let info = mock_info(friend.as_ref(), &[]);
+        #[allow(deprecated)]
+        control
+            .execute_update_admin(deps.as_mut(), info, Some(owner.clone()))
+            .unwrap();
+        let res = control.query_admin(deps.as_ref()).unwrap();
+        assert_eq!(Some(owner.to_string()), res.admin);
+    }
+
+    #[test]
+    fn two_step_transfer() {
+        let mut deps = mock_dependencies(&[]);
+
+        let control = Admin::new("foo");
+        let owner = Addr::unchecked("big boss");
+        let friend = Addr::unchecked("buddy");
+        let other = Addr::unchecked("other buddy");
+        control.set(deps.as_mut(), Some(owner.clone())).unwrap();
+
+        // nothing to accept or revoke yet
+        let info = mock_info(friend.as_ref(), &[]);
+        let err = control
+            .execute_accept_admin(deps.as_mut(), info)
+            .unwrap_err();
+        assert_eq!(AdminError::NoPendingAdmin {}, err);
+        let info = mock_info(owner.as_ref(), &[]);
+        let err = control
+            .execute_revoke_pending(deps.as_mut(), info)
+            .unwrap_err();
+        assert_eq!(AdminError::NoPendingAdmin {}, err);
+
+        // only the admin can propose
+        let info = mock_info(friend.as_ref(), &[]);
+        let err = control
+            .execute_propose_admin(deps.as_mut(), info, friend.clone())
+            .unwrap_err();
+        assert_eq!(AdminError::NotAdmin {}, err);
+
+        // a proposal doesn't change the admin yet
+        let info = mock_info(owner.as_ref(), &[]);
+        let res = control
+            .execute_propose_admin(deps.as_mut(), info, friend.clone())
+            .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "propose_admin"));
+        let res = control.query_pending_admin(deps.as_ref()).unwrap();
+        assert_eq!(Some(friend.to_string()), res.pending_admin);
+        let res = control.query_admin(deps.as_ref()).unwrap();
+        assert_eq!(Some(owner.to_string()), res.admin);
+
+        // a new proposal overwrites the previous one
+        let info = mock_info(owner.as_ref(), &[]);
+        control
+            .execute_propose_admin(deps.as_mut(), info, other.clone())
+            .unwrap();
+        let res = control.query_pending_admin(deps.as_ref()).unwrap();
+        assert_eq!(Some(other.to_string()), res.pending_admin);
+
+        // so only the last one proposed can accept, not the admin either
+        for addr in [&friend, &owner].iter() {
+            let info = mock_info(addr.as_ref(), &[]);
+            let err = control
+                .execute_accept_admin(deps.as_mut(), info)
+                .unwrap_err();
+            assert_eq!(AdminError::NotPendingAdmin {}, err);
+        }
+        let info = mock_info(other.as_ref(), &[]);
+        let res = control.execute_accept_admin(deps.as_mut(), info).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "accept_admin"));
+        assert!(control.is_admin(deps.as_ref(), &other).unwrap());
+        assert!(!control.is_admin(deps.as_ref(), &owner).unwrap());
+        let res = control.query_pending_admin(deps.as_ref()).unwrap();
+        assert_eq!(None, res.pending_admin);
+    }
+
+    #[test]
+    fn revoke_pending_transfer() {
+        let mut deps = mock_dependencies(&[]);
+
+        let control = Admin::new("foo");
+        let owner = Addr::unchecked("big boss");
+        let friend = Addr::unchecked("buddy");
+        control.set(deps.as_mut(), Some(owner.clone())).unwrap();
+
+        let info = mock_info(owner.as_ref(), &[]);
+        control
+            .execute_propose_admin(deps.as_mut(), info, friend.clone())
+            .unwrap();
+
+        // the pending admin cannot revoke
+        let info = mock_info(friend.as_ref(), &[]);
+        let err = control
+            .execute_revoke_pending(deps.as_mut(), info)
+            .unwrap_err();
+        assert_eq!(AdminError::NotAdmin {}, err);
+
+        // the admin can, and then there is nothing left to accept
+        let info = mock_info(owner.as_ref(), &[]);
+        let res = control.execute_revoke_pending(deps.as_mut(), info).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "revoke_pending_admin"));
+        let info = mock_info(friend.as_ref(), &[]);
+        let err = control
+            .execute_accept_admin(deps.as_mut(), info)
+            .unwrap_err();
+        assert_eq!(AdminError::NoPendingAdmin {}, err);
+
+        // a forced update also clears a pending proposal
+        let info = mock_info(owner.as_ref(), &[]);
+        control
+            .execute_propose_admin(deps.as_mut(), info.clone(), friend.clone())
+            .unwrap();
+        control
+            .execute_force_update_admin(deps.as_mut(), info, None)
+            .unwrap();
+        let res = control.query_pending_admin(deps.as_ref()).unwrap();
+        assert_eq!(None, res.pending_admin);
+        let res = control.query_admin(deps.as_ref()).unwrap();
+        assert_eq!(None, res.admin);
     }
 }
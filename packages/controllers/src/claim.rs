@@ -1,9 +1,11 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, BlockInfo, Deps, StdResult, Storage, Uint128};
+use cosmwasm_std::{
+    Addr, BlockInfo, Deps, Order, StdError, StdResult, Storage, Timestamp, Uint128,
+};
 use cw0::Expiration;
-use cw_storage_plus::Map;
+use cw_storage_plus::{Bound, Map, U64Key, U8Key};
 
 // TODO: pull into cw0?
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -28,11 +30,29 @@ impl Claim {
 }
 
 // TODO: revisit design (split each claim on own key?)
-pub struct Claims<'a>(Map<'a, &'a Addr, Vec<Claim>>);
+pub struct Claims<'a> {
+    claims: Map<'a, &'a Addr, Vec<Claim>>,
+    // the total amount of claims per (release kind, release value, address)
+    // only set if created with_index
+    index: Option<Map<'a, (U8Key, U64Key, &'a Addr), Uint128>>,
+}
 
 impl<'a> Claims<'a> {
     pub const fn new(storage_key: &'a str) -> Self {
-        Claims(Map::new(storage_key))
+        Claims {
+            claims: Map::new(storage_key),
+            index: None,
+        }
+    }
+
+    /// with_index works like new, but also indexes the claims by their release point
+    /// under index_key, so claim_matured can release the matured claims of all addresses
+    /// without scanning every address. Never expiring claims are not indexed.
+    pub const fn with_index(storage_key: &'a str, index_key: &'a str) -> Self {
+        Claims {
+            claims: Map::new(storage_key),
+            index: Some(Map::new(index_key)),
+        }
     }
 
     /// This creates a claim, such that the given address can claim an amount of tokens after
@@ -45,11 +65,17 @@ impl<'a> Claims<'a> {
         release_at: Expiration,
     ) -> StdResult<()> {
         // add a claim to this user to get their tokens after the unbonding period
-        self.0.update(storage, addr, |old| -> StdResult<_> {
+        self.claims.update(storage, addr, |old| -> StdResult<_> {
             let mut claims = old.unwrap_or_default();
             claims.push(Claim { amount, release_at });
             Ok(claims)
         })?;
+        if let (Some(index), Some((kind, value))) = (&self.index, release_key(&release_at)) {
+            let key = (U8Key::new(kind), U64Key::new(value), addr);
+            index.update(storage, key, |old| -> StdResult<_> {
+                Ok(old.unwrap_or_default().checked_add(amount)?)
+            })?;
+        }
         Ok(())
     }
 
@@ -63,10 +89,11 @@ impl<'a> Claims<'a> {
         cap: Option<Uint128>,
     ) -> StdResult<Uint128> {
         let mut to_send = Uint128::zero();
-        self.0.update(storage, addr, |claim| -> StdResult<_> {
-            let (_send, waiting): (Vec<_>, _) =
+        let mut released = vec![];
+        self.claims.update(storage, addr, |claim| -> StdResult<_> {
+            let (send, waiting): (Vec<_>, _) =
                 claim.unwrap_or_default().iter().cloned().partition(|c| {
-                    // if mature and we can pay fully, then include in _send
+                    // if mature and we can pay fully, then include in send
                     if c.release_at.is_expired(block) {
                         if let Some(limit) = cap {
                             if to_send + c.amount > limit {
@@ -81,15 +108,531 @@ impl<'a> Claims<'a> {
                         false
                     }
                 });
+            released = send;
             Ok(waiting)
         })?;
+        for claim in released {
+            self.unindex(storage, addr, &claim)?;
+        }
+        Ok(to_send)
+    }
+
+    /// This releases exactly amount tokens from the mature claims of the address, oldest claim
+    /// first. If only part of a claim is needed, the remainder stays in its place with its
+    /// original release point. Returns an error if less than amount is mature.
+    pub fn claim_amount(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+        block: &BlockInfo,
+        amount: Uint128,
+    ) -> StdResult<Uint128> {
+        let mut claims = self.claims.may_load(storage, addr)?.unwrap_or_default();
+        let mature = claims
+            .iter()
+            .filter(|c| c.release_at.is_expired(block))
+            .try_fold(Uint128::zero(), |total, c| total.checked_add(c.amount))?;
+        if mature < amount {
+            return Err(StdError::generic_err(format!(
+                "Cannot claim {}, only {} is mature",
+                amount, mature
+            )));
+        }
+
+        let mut left = amount;
+        let mut released = vec![];
+        for claim in claims.iter_mut() {
+            if left.is_zero() {
+                break;
+            }
+            if !claim.release_at.is_expired(block) {
+                continue;
+            }
+            let take = claim.amount.min(left);
+            claim.amount -= take;
+            left -= take;
+            released.push(Claim {
+                amount: take,
+                release_at: claim.release_at,
+            });
+        }
+        claims.retain(|c| !c.amount.is_zero());
+
+        if claims.is_empty() {
+            self.claims.remove(storage, addr);
+        } else {
+            self.claims.save(storage, addr, &claims)?;
+        }
+        for claim in released {
+            self.unindex(storage, addr, &claim)?;
+        }
+        Ok(amount)
+    }
+
+    /// This releases the matured claims of all addresses, in order of their release point
+    /// (heights first, then times), and returns the total amount released per address.
+    /// At most limit release points (of one address each) are handled, so call it again until
+    /// it returns nothing to release everything. Only works if created with_index.
+    pub fn claim_matured(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        limit: usize,
+    ) -> StdResult<Vec<(Addr, Uint128)>> {
+        let index = self
+            .index
+            .as_ref()
+            .ok_or_else(|| StdError::generic_err("Claims was not created with_index"))?;
+        let mut matured = vec![];
+        for (kind, now) in [(HEIGHT, block.height), (TIME, block.time.nanos())].iter() {
+            // the first key after every release value <= now, if there is any
+            let max = now
+                .checked_add(1)
+                .map(|end| Bound::exclusive((U64Key::new(end), "")));
+            let entries = index
+                .sub_prefix(U8Key::new(*kind))
+                .range_de::<(U64Key, Addr)>(storage, None, max, Order::Ascending)
+                .take(limit - matured.len())
+                .collect::<StdResult<Vec<_>>>()?;
+            matured.extend(
+                entries
+                    .into_iter()
+                    .map(|((value, addr), amount)| (*kind, value, addr, amount)),
+            );
+        }
+
+        let mut to_send: Vec<(Addr, Uint128)> = vec![];
+        for (kind, value, addr, amount) in matured {
+            index.remove(storage, (U8Key::new(kind), U64Key::new(value), &addr));
+            let release_at = release_expiration(kind, value);
+            let waiting: Vec<_> = self
+                .claims
+                .may_load(storage, &addr)?
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|c| c.release_at != release_at)
+                .collect();
+            if waiting.is_empty() {
+                self.claims.remove(storage, &addr);
+            } else {
+                self.claims.save(storage, &addr, &waiting)?;
+            }
+            match to_send.iter_mut().find(|(a, _)| *a == addr) {
+                Some((_, total)) => *total += amount,
+                None => to_send.push((addr, amount)),
+            }
+        }
         Ok(to_send)
     }
 
     pub fn query_claims(&self, deps: Deps, address: &Addr) -> StdResult<ClaimsResponse> {
-        let claims = self.0.may_load(deps.storage, address)?.unwrap_or_default();
+        let claims = self
+            .claims
+            .may_load(deps.storage, address)?
+            .unwrap_or_default();
         Ok(ClaimsResponse { claims })
     }
+
+    /// query_claims_paged returns at most limit claims of the address, in creation order.
+    /// start_after is the position (in the full list) of the last claim of the previous page.
+    /// Note that releasing claims shifts the positions of the remaining ones.
+    pub fn query_claims_paged(
+        &self,
+        deps: Deps,
+        address: &Addr,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<ClaimsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map_or(0, |pos| pos as usize + 1);
+        let claims = self
+            .claims
+            .may_load(deps.storage, address)?
+            .unwrap_or_default()
+            .into_iter()
+            .skip(start)
+            .take(limit)
+            .collect();
+        Ok(ClaimsResponse { claims })
+    }
+
+    /// query_mature_claims returns the claims of the address that can be released at the
+    /// given block, that is, the ones claim_tokens would release without a cap
+    pub fn query_mature_claims(
+        &self,
+        deps: Deps,
+        address: &Addr,
+        block: &BlockInfo,
+    ) -> StdResult<ClaimsResponse> {
+        let claims = self
+            .claims
+            .may_load(deps.storage, address)?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|c| c.release_at.is_expired(block))
+            .collect();
+        Ok(ClaimsResponse { claims })
+    }
+
+    /// total_claims returns the sum of all claims of the address, matured or not
+    pub fn total_claims(&self, deps: Deps, address: &Addr) -> StdResult<Uint128> {
+        self.claims
+            .may_load(deps.storage, address)?
+            .unwrap_or_default()
+            .iter()
+            .try_fold(Uint128::zero(), |total, c| Ok(total.checked_add(c.amount)?))
+    }
+
+    // removes a released claim from the index (if any). Claims created before the index was
+    // used (with Claims::new) are not in it, so a missing or too small entry is not an error
+    fn unindex(&self, storage: &mut dyn Storage, addr: &Addr, claim: &Claim) -> StdResult<()> {
+        if let (Some(index), Some((kind, value))) = (&self.index, release_key(&claim.release_at)) {
+            let key = (U8Key::new(kind), U64Key::new(value), addr);
+            if let Some(total) = index.may_load(storage, key.clone())? {
+                let remaining = total.saturating_sub(claim.amount);
+                if remaining.is_zero() {
+                    index.remove(storage, key);
+                } else {
+                    index.save(storage, key, &remaining)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+// the kinds of release points in the index, heights sort before times
+const HEIGHT: u8 = 0;
+const TIME: u8 = 1;
+
+// the (kind, value) of the release point in the index, None if it never expires
+fn release_key(release_at: &Expiration) -> Option<(u8, u64)> {
+    match release_at {
+        Expiration::AtHeight(height) => Some((HEIGHT, *height)),
+        Expiration::AtTime(time) => Some((TIME, time.nanos())),
+        Expiration::Never {} => None,
+    }
+}
+
+fn release_expiration(kind: u8, value: u64) -> Expiration {
+    match kind {
+        HEIGHT => Expiration::AtHeight(value),
+        _ => Expiration::AtTime(Timestamp::from_nanos(value)),
+    }
 }
 
-// TODO: add test coverage
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    const CLAIMS: Claims = Claims::with_index("claims", "claims__release");
+
+    #[test]
+    fn claim_matured_across_addresses() {
+        let mut deps = mock_dependencies(&[]);
+        let block = mock_env().block;
+        let (alice, bob) = (Addr::unchecked("alice"), Addr::unchecked("bob"));
+        let now = Expiration::AtHeight(block.height);
+        let later = Expiration::AtHeight(block.height + 1);
+        let past = Expiration::AtTime(block.time.minus_seconds(10));
+
+        let store = deps.as_mut().storage;
+        CLAIMS
+            .create_claim(store, &alice, 100u128.into(), now)
+            .unwrap();
+        CLAIMS
+            .create_claim(store, &alice, 200u128.into(), later)
+            .unwrap();
+        CLAIMS
+            .create_claim(store, &alice, 50u128.into(), now)
+            .unwrap();
+        CLAIMS
+            .create_claim(store, &bob, 7u128.into(), past)
+            .unwrap();
+        CLAIMS
+            .create_claim(store, &bob, 1000u128.into(), Expiration::Never {})
+            .unwrap();
+
+        // the limit counts release points: alice at now, then bob in the past
+        let released = CLAIMS.claim_matured(store, &block, 1).unwrap();
+        assert_eq!(released, vec![(alice.clone(), Uint128::new(150))]);
+        let released = CLAIMS.claim_matured(store, &block, 10).unwrap();
+        assert_eq!(released, vec![(bob.clone(), Uint128::new(7))]);
+        assert_eq!(CLAIMS.claim_matured(store, &block, 10).unwrap(), vec![]);
+
+        // only the matured claims were removed
+        let claims = CLAIMS.query_claims(deps.as_ref(), &alice).unwrap().claims;
+        assert_eq!(claims, vec![Claim::new(200, later)]);
+        let claims = CLAIMS.query_claims(deps.as_ref(), &bob).unwrap().claims;
+        assert_eq!(claims, vec![Claim::new(1000, Expiration::Never {})]);
+
+        // the next block releases the rest (but never the never expiring claim)
+        let mut next = block;
+        next.height += 1;
+        let store = deps.as_mut().storage;
+        let released = CLAIMS.claim_matured(store, &next, 10).unwrap();
+        assert_eq!(released, vec![(alice.clone(), Uint128::new(200))]);
+        let claims = CLAIMS.query_claims(deps.as_ref(), &alice).unwrap().claims;
+        assert_eq!(claims, vec![]);
+    }
+
+    #[test]
+    fn claim_matured_sums_per_address() {
+        let mut deps = mock_dependencies(&[]);
+        let block = mock_env().block;
+        let alice = Addr::unchecked("alice");
+        let store = deps.as_mut().storage;
+        for offset in 0..5 {
+            let release_at = Expiration::AtHeight(block.height - offset);
+            CLAIMS
+                .create_claim(store, &alice, 10u128.into(), release_at)
+                .unwrap();
+        }
+
+        let released = CLAIMS.claim_matured(store, &block, 3).unwrap();
+        assert_eq!(released, vec![(alice.clone(), Uint128::new(30))]);
+        // the oldest ones go first, the others keep their creation order
+        let claims = CLAIMS.query_claims(deps.as_ref(), &alice).unwrap().claims;
+        assert_eq!(
+            claims,
+            vec![
+                Claim::new(10, Expiration::AtHeight(block.height)),
+                Claim::new(10, Expiration::AtHeight(block.height - 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn claim_tokens_updates_index() {
+        let mut deps = mock_dependencies(&[]);
+        let block = mock_env().block;
+        let alice = Addr::unchecked("alice");
+        let now = Expiration::AtHeight(block.height);
+
+        let store = deps.as_mut().storage;
+        CLAIMS
+            .create_claim(store, &alice, 100u128.into(), now)
+            .unwrap();
+        CLAIMS
+            .create_claim(store, &alice, 50u128.into(), now)
+            .unwrap();
+
+        // the cap only leaves room for the first claim
+        let sent = CLAIMS
+            .claim_tokens(store, &alice, &block, Some(120u128.into()))
+            .unwrap();
+        assert_eq!(Uint128::new(100), sent);
+
+        // so only the other one is left to release
+        let released = CLAIMS.claim_matured(store, &block, 10).unwrap();
+        assert_eq!(released, vec![(alice.clone(), Uint128::new(50))]);
+        let sent = CLAIMS.claim_tokens(store, &alice, &block, None).unwrap();
+        assert_eq!(Uint128::zero(), sent);
+    }
+
+    #[test]
+    fn release_claims_created_without_index() {
+        let mut deps = mock_dependencies(&[]);
+        let block = mock_env().block;
+        let alice = Addr::unchecked("alice");
+        let now = Expiration::AtHeight(block.height);
+
+        // claims created before the contract used with_index
+        const OLD_CLAIMS: Claims = Claims::new("claims");
+        let store = deps.as_mut().storage;
+        OLD_CLAIMS
+            .create_claim(store, &alice, 100u128.into(), now)
+            .unwrap();
+        OLD_CLAIMS
+            .create_claim(store, &alice, 50u128.into(), now)
+            .unwrap();
+        // and one after, so the index only holds part of the amount at this height
+        CLAIMS
+            .create_claim(store, &alice, 30u128.into(), now)
+            .unwrap();
+
+        let sent = CLAIMS
+            .claim_amount(store, &alice, &block, 120u128.into())
+            .unwrap();
+        assert_eq!(Uint128::new(120), sent);
+        let sent = CLAIMS.claim_tokens(store, &alice, &block, None).unwrap();
+        assert_eq!(Uint128::new(60), sent);
+        // nothing is left in the index
+        assert_eq!(CLAIMS.claim_matured(store, &block, 10).unwrap(), vec![]);
+        let claims = CLAIMS.query_claims(deps.as_ref(), &alice).unwrap().claims;
+        assert_eq!(claims, vec![]);
+    }
+
+    #[test]
+    fn claim_amount_splits_claims() {
+        let mut deps = mock_dependencies(&[]);
+        let block = mock_env().block;
+        let alice = Addr::unchecked("alice");
+        let past = Expiration::AtHeight(block.height - 5);
+        let now = Expiration::AtHeight(block.height);
+        let later = Expiration::AtHeight(block.height + 1);
+
+        let store = deps.as_mut().storage;
+        CLAIMS
+            .create_claim(store, &alice, 100u128.into(), now)
+            .unwrap();
+        CLAIMS
+            .create_claim(store, &alice, 500u128.into(), later)
+            .unwrap();
+        CLAIMS
+            .create_claim(store, &alice, 50u128.into(), past)
+            .unwrap();
+        CLAIMS
+            .create_claim(store, &alice, 30u128.into(), now)
+            .unwrap();
+
+        // more than is mature is an error, and changes nothing
+        let err = CLAIMS
+            .claim_amount(store, &alice, &block, 181u128.into())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Cannot claim 181, only 180 is mature")
+        );
+
+        // takes the whole first claim, and splits the next mature one
+        let sent = CLAIMS
+            .claim_amount(store, &alice, &block, 120u128.into())
+            .unwrap();
+        assert_eq!(Uint128::new(120), sent);
+        let claims = CLAIMS.query_claims(deps.as_ref(), &alice).unwrap().claims;
+        assert_eq!(
+            claims,
+            vec![
+                Claim::new(500, later),
+                Claim::new(30, past),
+                Claim::new(30, now),
+            ]
+        );
+
+        // exactly the rest of the mature claims
+        let store = deps.as_mut().storage;
+        let sent = CLAIMS
+            .claim_amount(store, &alice, &block, 60u128.into())
+            .unwrap();
+        assert_eq!(Uint128::new(60), sent);
+        let claims = CLAIMS.query_claims(deps.as_ref(), &alice).unwrap().claims;
+        assert_eq!(claims, vec![Claim::new(500, later)]);
+
+        // nothing mature left, the index was updated along
+        let store = deps.as_mut().storage;
+        CLAIMS
+            .claim_amount(store, &alice, &block, 1u128.into())
+            .unwrap_err();
+        assert_eq!(CLAIMS.claim_matured(store, &block, 10).unwrap(), vec![]);
+
+        // a split claim is still released at its release point
+        let mut next = block;
+        next.height += 1;
+        let sent = CLAIMS
+            .claim_amount(store, &alice, &next, 200u128.into())
+            .unwrap();
+        assert_eq!(Uint128::new(200), sent);
+        let released = CLAIMS.claim_matured(store, &next, 10).unwrap();
+        assert_eq!(released, vec![(alice.clone(), Uint128::new(300))]);
+        let claims = CLAIMS.query_claims(deps.as_ref(), &alice).unwrap().claims;
+        assert_eq!(claims, vec![]);
+    }
+
+    #[test]
+    fn query_claims_in_pages() {
+        let mut deps = mock_dependencies(&[]);
+        let block = mock_env().block;
+        let alice = Addr::unchecked("alice");
+        let store = deps.as_mut().storage;
+        // more than the max page size, every other one matured
+        for i in 0..45u64 {
+            let release_at = Expiration::AtHeight(block.height - 1 + (i % 2) * 10);
+            CLAIMS
+                .create_claim(store, &alice, (i as u128).into(), release_at)
+                .unwrap();
+        }
+
+        let page = CLAIMS
+            .query_claims_paged(deps.as_ref(), &alice, None, None)
+            .unwrap()
+            .claims;
+        assert_eq!(10, page.len());
+        assert_eq!(Uint128::new(0), page[0].amount);
+
+        // the limit is capped
+        let page = CLAIMS
+            .query_claims_paged(deps.as_ref(), &alice, Some(9), Some(100))
+            .unwrap()
+            .claims;
+        assert_eq!(30, page.len());
+        assert_eq!(Uint128::new(10), page[0].amount);
+        assert_eq!(Uint128::new(39), page[29].amount);
+        let page = CLAIMS
+            .query_claims_paged(deps.as_ref(), &alice, Some(39), Some(100))
+            .unwrap()
+            .claims;
+        assert_eq!(5, page.len());
+        assert_eq!(Uint128::new(44), page[4].amount);
+        let page = CLAIMS
+            .query_claims_paged(deps.as_ref(), &alice, Some(44), None)
+            .unwrap()
+            .claims;
+        assert_eq!(0, page.len());
+
+        // the matured ones are the even ones
+        let mature = CLAIMS
+            .query_mature_claims(deps.as_ref(), &alice, &block)
+            .unwrap()
+            .claims;
+        assert_eq!(23, mature.len());
+        assert!(mature.iter().all(|c| c.amount.u128() % 2 == 0));
+
+        // 0 + 1 + ... + 44
+        let total = CLAIMS.total_claims(deps.as_ref(), &alice).unwrap();
+        assert_eq!(Uint128::new(990), total);
+        let bob = Addr::unchecked("bob");
+        let total = CLAIMS.total_claims(deps.as_ref(), &bob).unwrap();
+        assert_eq!(Uint128::zero(), total);
+
+        // releasing them leaves the others
+        let store = deps.as_mut().storage;
+        let sent = CLAIMS.claim_tokens(store, &alice, &block, None).unwrap();
+        assert_eq!(Uint128::new(506), sent);
+        let total = CLAIMS.total_claims(deps.as_ref(), &alice).unwrap();
+        assert_eq!(Uint128::new(990 - 506), total);
+        let mature = CLAIMS
+            .query_mature_claims(deps.as_ref(), &alice, &block)
+            .unwrap()
+            .claims;
+        assert_eq!(0, mature.len());
+    }
+
+    #[test]
+    fn claim_matured_needs_index() {
+        let mut deps = mock_dependencies(&[]);
+        let block = mock_env().block;
+        let claims = Claims::new("claims");
+        let alice = Addr::unchecked("alice");
+
+        let store = deps.as_mut().storage;
+        let release_at = Expiration::AtHeight(block.height);
+        claims
+            .create_claim(store, &alice, 100u128.into(), release_at)
+            .unwrap();
+        let err = claims.claim_matured(store, &block, 10).unwrap_err();
+        assert_eq!(
+            StdError::generic_err("Claims was not created with_index"),
+            err
+        );
+
+        // the per-address claims work as before
+        let sent = claims.claim_tokens(store, &alice, &block, None).unwrap();
+        assert_eq!(Uint128::new(100), sent);
+    }
+}
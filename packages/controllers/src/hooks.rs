@@ -3,7 +3,8 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use cosmwasm_std::{
-    attr, Addr, Deps, DepsMut, MessageInfo, Response, StdError, StdResult, Storage, SubMsg,
+    attr, Addr, ContractResult, Deps, DepsMut, MessageInfo, Reply, ReplyOn, Response, StdError,
+    StdResult, Storage, SubMsg,
 };
 use cw_storage_plus::Item;
 
@@ -29,34 +30,62 @@ pub enum HookError {
 
     #[error("Given address not registered as a hook")]
     HookNotRegistered {},
+
+    #[error("Cannot register more than {max} hooks")]
+    TooManyHooks { max: u32 },
+
+    #[error("Unknown hook reply id {id}")]
+    UnknownReplyId { id: u64 },
 }
 
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
 // store all hook addresses in one item. We cannot have many of them before the contract becomes unusable anyway.
-pub struct Hooks<'a>(Item<'a, Vec<Addr>>);
+pub struct Hooks<'a> {
+    hooks: Item<'a, Vec<Addr>>,
+    max_hooks: Option<u32>,
+}
 
 impl<'a> Hooks<'a> {
     pub const fn new(storage_key: &'a str) -> Self {
-        Hooks(Item::new(storage_key))
+        Hooks {
+            hooks: Item::new(storage_key),
+            max_hooks: None,
+        }
+    }
+
+    /// with_max_hooks works like new, but add_hook fails once max_hooks are registered
+    pub const fn with_max_hooks(storage_key: &'a str, max_hooks: u32) -> Self {
+        Hooks {
+            hooks: Item::new(storage_key),
+            max_hooks: Some(max_hooks),
+        }
     }
 
     pub fn add_hook(&self, storage: &mut dyn Storage, addr: Addr) -> Result<(), HookError> {
-        let mut hooks = self.0.may_load(storage)?.unwrap_or_default();
-        if !hooks.iter().any(|h| h == &addr) {
-            hooks.push(addr);
-        } else {
+        let mut hooks = self.hooks.may_load(storage)?.unwrap_or_default();
+        if hooks.iter().any(|h| h == &addr) {
             return Err(HookError::HookAlreadyRegistered {});
         }
-        Ok(self.0.save(storage, &hooks)?)
+        if let Some(max) = self.max_hooks {
+            if hooks.len() >= max as usize {
+                return Err(HookError::TooManyHooks { max });
+            }
+        }
+        hooks.push(addr);
+        Ok(self.hooks.save(storage, &hooks)?)
     }
 
     pub fn remove_hook(&self, storage: &mut dyn Storage, addr: Addr) -> Result<(), HookError> {
-        let mut hooks = self.0.load(storage)?;
+        let mut hooks = self.hooks.load(storage)?;
         if let Some(p) = hooks.iter().position(|x| x == &addr) {
             hooks.remove(p);
         } else {
             return Err(HookError::HookNotRegistered {});
         }
-        Ok(self.0.save(storage, &hooks)?)
+        Ok(self.hooks.save(storage, &hooks)?)
     }
 
     pub fn prepare_hooks<F: Fn(Addr) -> StdResult<SubMsg>>(
@@ -64,7 +93,7 @@ impl<'a> Hooks<'a> {
         storage: &dyn Storage,
         prep: F,
     ) -> StdResult<Vec<SubMsg>> {
-        self.0
+        self.hooks
             .may_load(storage)?
             .unwrap_or_default()
             .into_iter()
@@ -72,6 +101,67 @@ impl<'a> Hooks<'a> {
             .collect()
     }
 
+    /// prepare_hooks_as_submsgs works like prepare_hooks, but sends every message with
+    /// ReplyOn::Error, so a failing hook doesn't fail the whole call. The contract must pass
+    /// the replies to handle_hook_reply, which deregisters the failing hook.
+    ///
+    /// The messages are sent in the order the hooks were added. The reply id of a hook is
+    /// reply_id_base plus the number of hooks after it, so removing a failing hook never
+    /// changes the id of the hooks that still have to reply.
+    pub fn prepare_hooks_as_submsgs<F: Fn(Addr) -> StdResult<SubMsg>>(
+        &self,
+        storage: &dyn Storage,
+        reply_id_base: u64,
+        prep: F,
+    ) -> StdResult<Vec<SubMsg>> {
+        let hooks = self.hooks.may_load(storage)?.unwrap_or_default();
+        let count = hooks.len();
+        hooks
+            .into_iter()
+            .enumerate()
+            .map(|(i, h)| {
+                let mut msg = prep(h)?;
+                msg.id = reply_id_base + (count - 1 - i) as u64;
+                msg.reply_on = ReplyOn::Error;
+                Ok(msg)
+            })
+            .collect()
+    }
+
+    /// handle_hook_reply deregisters the hook that failed, given the reply to a message of
+    /// prepare_hooks_as_submsgs with the same reply_id_base. Successful replies are ignored.
+    pub fn handle_hook_reply(
+        &self,
+        storage: &mut dyn Storage,
+        reply_id_base: u64,
+        msg: Reply,
+    ) -> Result<Response, HookError> {
+        let err = match msg.result {
+            ContractResult::Ok(_) => return Ok(Response::default()),
+            ContractResult::Err(err) => err,
+        };
+        let mut hooks = self.hooks.may_load(storage)?.unwrap_or_default();
+        let after = msg
+            .id
+            .checked_sub(reply_id_base)
+            .filter(|after| *after < hooks.len() as u64)
+            .ok_or(HookError::UnknownReplyId { id: msg.id })?;
+        let hook = hooks.remove(hooks.len() - 1 - after as usize);
+        self.hooks.save(storage, &hooks)?;
+
+        let attributes = vec![
+            attr("action", "remove_failing_hook"),
+            attr("hook", hook),
+            attr("error", err),
+        ];
+        Ok(Response {
+            messages: vec![],
+            attributes,
+            events: vec![],
+            data: None,
+        })
+    }
+
     pub fn execute_add_hook(
         &self,
         admin: &Admin,
@@ -119,10 +209,171 @@ impl<'a> Hooks<'a> {
     }
 
     pub fn query_hooks(&self, deps: Deps) -> StdResult<HooksResponse> {
-        let hooks = self.0.may_load(deps.storage)?.unwrap_or_default();
+        let hooks = self.hooks.may_load(deps.storage)?.unwrap_or_default();
         let hooks = hooks.into_iter().map(String::from).collect();
         Ok(HooksResponse { hooks })
     }
+
+    /// query_hooks_paged returns at most limit hooks, sorted by address,
+    /// starting after the given address (which doesn't need to be registered)
+    pub fn query_hooks_paged(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<HooksResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let mut hooks: Vec<String> = self
+            .hooks
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(String::from)
+            .filter(|h| match &start_after {
+                Some(start) => h > start,
+                None => true,
+            })
+            .collect();
+        hooks.sort();
+        hooks.truncate(limit);
+        Ok(HooksResponse { hooks })
+    }
 }
 
-// TODO: add test coverage
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::{to_binary, WasmMsg};
+
+    fn hook_msg(addr: Addr) -> StdResult<SubMsg> {
+        Ok(SubMsg::new(WasmMsg::Execute {
+            contract_addr: addr.to_string(),
+            msg: to_binary(&"hook")?,
+            funds: vec![],
+        }))
+    }
+
+    #[test]
+    fn add_hooks_up_to_max() {
+        let mut deps = mock_dependencies(&[]);
+        let hooks = Hooks::with_max_hooks("hooks", 2);
+
+        let storage = deps.as_mut().storage;
+        hooks.add_hook(storage, Addr::unchecked("one")).unwrap();
+        // duplicates are rejected, and don't count
+        let err = hooks.add_hook(storage, Addr::unchecked("one")).unwrap_err();
+        assert_eq!(HookError::HookAlreadyRegistered {}, err);
+        hooks.add_hook(storage, Addr::unchecked("two")).unwrap();
+
+        let err = hooks
+            .add_hook(storage, Addr::unchecked("three"))
+            .unwrap_err();
+        assert_eq!(HookError::TooManyHooks { max: 2 }, err);
+        // a duplicate is still reported as such when full
+        let err = hooks.add_hook(storage, Addr::unchecked("two")).unwrap_err();
+        assert_eq!(HookError::HookAlreadyRegistered {}, err);
+
+        // removing one makes room again
+        hooks.remove_hook(storage, Addr::unchecked("one")).unwrap();
+        hooks.add_hook(storage, Addr::unchecked("three")).unwrap();
+        let res = hooks.query_hooks(deps.as_ref()).unwrap();
+        assert_eq!(res.hooks, vec!["two".to_string(), "three".to_string()]);
+
+        // without a max, there is no limit
+        let unlimited = Hooks::new("unlimited");
+        let storage = deps.as_mut().storage;
+        for i in 0..50 {
+            unlimited
+                .add_hook(storage, Addr::unchecked(format!("hook{:02}", i)))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn remove_failing_hooks() {
+        let mut deps = mock_dependencies(&[]);
+        let hooks = Hooks::new("hooks");
+
+        let storage = deps.as_mut().storage;
+        for name in ["one", "two", "three", "four"].iter() {
+            hooks.add_hook(storage, Addr::unchecked(*name)).unwrap();
+        }
+        let msgs = hooks
+            .prepare_hooks_as_submsgs(storage, 100, hook_msg)
+            .unwrap();
+        let ids: Vec<_> = msgs.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![103, 102, 101, 100]);
+        assert!(msgs.iter().all(|m| m.reply_on == ReplyOn::Error));
+        assert_eq!(msgs[0].msg, hook_msg(Addr::unchecked("one")).unwrap().msg);
+
+        // the replies arrive in message order, one and three fail
+        let failed = |id| Reply {
+            id,
+            result: ContractResult::Err("boom".to_string()),
+        };
+        let res = hooks.handle_hook_reply(storage, 100, failed(103)).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "remove_failing_hook"),
+                attr("hook", "one"),
+                attr("error", "boom"),
+            ]
+        );
+        let res = hooks.handle_hook_reply(storage, 100, failed(101)).unwrap();
+        assert_eq!(res.attributes[1], attr("hook", "three"));
+        let res = hooks.query_hooks(deps.as_ref()).unwrap();
+        assert_eq!(res.hooks, vec!["two".to_string(), "four".to_string()]);
+
+        // unknown ids are an error
+        let storage = deps.as_mut().storage;
+        let err = hooks
+            .handle_hook_reply(storage, 100, failed(99))
+            .unwrap_err();
+        assert_eq!(HookError::UnknownReplyId { id: 99 }, err);
+        let err = hooks
+            .handle_hook_reply(storage, 100, failed(102))
+            .unwrap_err();
+        assert_eq!(HookError::UnknownReplyId { id: 102 }, err);
+    }
+
+    #[test]
+    fn query_hooks_in_pages() {
+        let mut deps = mock_dependencies(&[]);
+        let hooks = Hooks::new("hooks");
+
+        // registered in reverse order
+        let storage = deps.as_mut().storage;
+        for i in (0..35).rev() {
+            hooks
+                .add_hook(storage, Addr::unchecked(format!("hook{:02}", i)))
+                .unwrap();
+        }
+
+        let page = hooks.query_hooks_paged(deps.as_ref(), None, None).unwrap();
+        assert_eq!(10, page.hooks.len());
+        assert_eq!("hook00", page.hooks[0]);
+        assert_eq!("hook09", page.hooks[9]);
+
+        // the limit is capped
+        let start = page.hooks.last().cloned();
+        let page = hooks
+            .query_hooks_paged(deps.as_ref(), start, Some(100))
+            .unwrap();
+        assert_eq!(25, page.hooks.len());
+        assert_eq!("hook10", page.hooks[0]);
+        assert_eq!("hook34", page.hooks[24]);
+
+        // start_after doesn't need to be registered
+        let start = Some("hook305".to_string());
+        let page = hooks
+            .query_hooks_paged(deps.as_ref(), start, Some(2))
+            .unwrap();
+        assert_eq!(page.hooks, vec!["hook31".to_string(), "hook32".to_string()]);
+
+        let start = Some("hook34".to_string());
+        let page = hooks.query_hooks_paged(deps.as_ref(), start, None).unwrap();
+        assert_eq!(0, page.hooks.len());
+    }
+}
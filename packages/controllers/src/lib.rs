@@ -2,6 +2,6 @@ mod admin;
 mod claim;
 mod hooks;
 
-pub use admin::{Admin, AdminError, AdminResponse};
+pub use admin::{Admin, AdminError, AdminResponse, PendingAdminResponse};
 pub use claim::{Claim, Claims, ClaimsResponse};
 pub use hooks::{HookError, Hooks};
@@ -4,6 +4,34 @@ use std::ops;
 
 use cosmwasm_std::{Coin, OverflowError, OverflowOperation, StdError, StdResult, Uint128};
 
+/// normalize_coins sorts the coins by denom, adds up the ones with the same denom
+/// and drops all zero amounts
+pub fn normalize_coins(mut coins: Vec<Coin>) -> Vec<Coin> {
+    coins.retain(|c| !c.amount.is_zero());
+    coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+    let mut normalized: Vec<Coin> = Vec::with_capacity(coins.len());
+    for coin in coins {
+        match normalized.last_mut() {
+            Some(last) if last.denom == coin.denom => last.amount += coin.amount,
+            _ => normalized.push(coin),
+        }
+    }
+    normalized
+}
+
+/// merge_coins adds two lists of coins, eg. funds sent to a stored balance.
+/// The result is normalized, even if the inputs were not.
+pub fn merge_coins(mut coins: Vec<Coin>, other: Vec<Coin>) -> Vec<Coin> {
+    coins.extend(other);
+    normalize_coins(coins)
+}
+
+/// find_coin returns the (first) coin of the given denom
+pub fn find_coin<'a>(coins: &'a [Coin], denom: &str) -> Option<&'a Coin> {
+    coins.iter().find(|c| c.denom == denom)
+}
+
 // Balance wraps Vec<Coin> and provides some nice helpers. It mutates the Vec and can be
 // unwrapped when done.
 #[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
@@ -16,93 +44,100 @@ impl NativeBalance {
 
     /// returns true if the list of coins has at least the required amount
     pub fn has(&self, required: &Coin) -> bool {
-        self.0
-            .iter()
-            .find(|c| c.denom == required.denom)
+        find_coin(&self.0, &required.denom)
             .map(|m| m.amount >= required.amount)
             .unwrap_or(false)
     }
 
+    /// returns true if the list of coins has at least the required amount.
+    /// Unlike has, a zero coin is contained in every balance.
+    pub fn contains(&self, required: &Coin) -> bool {
+        required.amount.is_zero() || self.has(required)
+    }
+
+    /// returns true if the list of coins has at least the amount of every required coin.
+    /// Coins of the same denom are added up before comparing.
+    pub fn is_superset_of(&self, required: &[Coin]) -> bool {
+        let mut required = NativeBalance(required.to_vec());
+        required.normalize();
+        required.0.iter().all(|c| self.contains(c))
+    }
+
     /// normalize Wallet (sorted by denom, no 0 elements, no duplicate denoms)
     pub fn normalize(&mut self) {
-        // drop 0's
-        self.0.retain(|c| c.amount.u128() != 0);
-        // sort
-        self.0.sort_unstable_by(|a, b| a.denom.cmp(&b.denom));
-
-        // find all i where (self[i-1].denom == self[i].denom).
-        let mut dups: Vec<usize> = self
-            .0
-            .iter()
-            .enumerate()
-            .filter_map(|(i, c)| {
-                if i != 0 && c.denom == self.0[i - 1].denom {
-                    Some(i)
-                } else {
-                    None
-                }
-            })
-            .collect();
-        dups.reverse();
-
-        // we go through the dups in reverse order (to avoid shifting indexes of other ones)
-        for dup in dups {
-            let add = self.0[dup].amount;
-            self.0[dup - 1].amount += add;
-            self.0.remove(dup);
-        }
+        self.0 = normalize_coins(std::mem::take(&mut self.0));
     }
 
     fn find(&self, denom: &str) -> Option<(usize, &Coin)> {
         self.0.iter().enumerate().find(|(_i, c)| c.denom == denom)
     }
 
-    /// insert_pos should only be called when denom is not in the Wallet.
-    /// it returns the position where denom should be inserted at (via splice).
-    /// It returns None if this should be appended
-    fn insert_pos(&self, denom: &str) -> Option<usize> {
-        self.0.iter().position(|c| c.denom.as_str() >= denom)
-    }
-
     pub fn is_empty(&self) -> bool {
         !self.0.iter().any(|x| x.amount != Uint128::zero())
     }
 
-    /// similar to `Balance.sub`, but doesn't fail when minuend less than subtrahend
-    pub fn sub_saturating(mut self, other: Coin) -> StdResult<Self> {
+    /// similar to `Balance.sub`, but doesn't fail when minuend less than subtrahend.
+    /// It still fails if there is none of the denom, see saturating_sub
+    #[deprecated(
+        note = "use saturating_sub, which returns the balance unchanged for an unknown denom"
+    )]
+    pub fn sub_saturating(self, other: Coin) -> StdResult<Self> {
+        // error if no tokens
+        if self.find(&other.denom).is_none() {
+            return Err(StdError::overflow(OverflowError::new(
+                OverflowOperation::Sub,
+                0,
+                other.amount.u128(),
+            )));
+        }
+        Ok(self.saturating_sub(&other))
+    }
+
+    /// checked_sub returns the balance minus the given coin, removing its denom if nothing is left.
+    /// Returns an error if there is not enough of the denom (or none at all).
+    pub fn checked_sub(&self, other: &Coin) -> Result<Self, OverflowError> {
+        let mut res = self.clone();
         match self.find(&other.denom) {
             Some((i, c)) => {
-                if c.amount <= other.amount {
-                    self.0.remove(i);
+                let remainder = c.amount.checked_sub(other.amount)?;
+                if remainder.is_zero() {
+                    res.0.remove(i);
                 } else {
-                    self.0[i].amount = self.0[i].amount.checked_sub(other.amount)?;
+                    res.0[i].amount = remainder;
                 }
             }
             // error if no tokens
             None => {
-                return Err(StdError::overflow(OverflowError::new(
+                return Err(OverflowError::new(
                     OverflowOperation::Sub,
                     0,
                     other.amount.u128(),
-                )))
+                ))
             }
         };
-        Ok(self)
+        Ok(res)
+    }
+
+    /// saturating_sub returns the balance minus the given coin, removing its denom
+    /// if there was not more than that. An unknown denom is not an error, the balance is just
+    /// returned unchanged.
+    pub fn saturating_sub(&self, other: &Coin) -> Self {
+        let mut res = self.clone();
+        if let Some((i, c)) = self.find(&other.denom) {
+            if c.amount <= other.amount {
+                res.0.remove(i);
+            } else {
+                res.0[i].amount = c.amount - other.amount;
+            }
+        }
+        res
     }
 }
 
+/// Adding normalizes the balance, so it stays sorted by denom without zero or duplicate entries
 impl ops::AddAssign<Coin> for NativeBalance {
     fn add_assign(&mut self, other: Coin) {
-        match self.find(&other.denom) {
-            Some((i, c)) => {
-                self.0[i].amount = c.amount + other.amount;
-            }
-            // place this in proper sorted order
-            None => match self.insert_pos(&other.denom) {
-                Some(idx) => self.0.insert(idx, other),
-                None => self.0.push(other),
-            },
-        };
+        self.0 = merge_coins(std::mem::take(&mut self.0), vec![other]);
     }
 }
 
@@ -117,9 +152,7 @@ impl ops::Add<Coin> for NativeBalance {
 
 impl ops::AddAssign<NativeBalance> for NativeBalance {
     fn add_assign(&mut self, other: NativeBalance) {
-        for coin in other.0.into_iter() {
-            self.add_assign(coin);
-        }
+        self.0 = merge_coins(std::mem::take(&mut self.0), other.0);
     }
 }
 
@@ -135,26 +168,8 @@ impl ops::Add<NativeBalance> for NativeBalance {
 impl ops::Sub<Coin> for NativeBalance {
     type Output = StdResult<Self>;
 
-    fn sub(mut self, other: Coin) -> StdResult<Self> {
-        match self.find(&other.denom) {
-            Some((i, c)) => {
-                let remainder = c.amount.checked_sub(other.amount)?;
-                if remainder.u128() == 0 {
-                    self.0.remove(i);
-                } else {
-                    self.0[i].amount = remainder;
-                }
-            }
-            // error if no tokens
-            None => {
-                return Err(StdError::overflow(OverflowError::new(
-                    OverflowOperation::Sub,
-                    0,
-                    other.amount.u128(),
-                )))
-            }
-        };
-        Ok(self)
+    fn sub(self, other: Coin) -> StdResult<Self> {
+        Ok(self.checked_sub(&other)?)
     }
 }
 
@@ -260,6 +275,7 @@ mod test {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn balance_subtract_saturating_works() {
         let balance = NativeBalance(vec![coin(555, "BTC"), coin(12345, "ETH")]);
 
@@ -284,6 +300,78 @@ mod test {
         assert!(missing.is_err());
     }
 
+    #[test]
+    fn balance_checked_sub_works() {
+        let balance = NativeBalance(vec![coin(555, "BTC"), coin(12345, "ETH")]);
+
+        let less_eth = balance.checked_sub(&coin(2345, "ETH")).unwrap();
+        assert_eq!(
+            less_eth,
+            NativeBalance(vec![coin(555, "BTC"), coin(10000, "ETH")])
+        );
+
+        // subtract to exactly zero removes the entry
+        let no_btc = balance.checked_sub(&coin(555, "BTC")).unwrap();
+        assert_eq!(no_btc, NativeBalance(vec![coin(12345, "ETH")]));
+        let empty = no_btc.checked_sub(&coin(12345, "ETH")).unwrap();
+        assert_eq!(empty, NativeBalance(vec![]));
+        assert!(empty.is_empty());
+
+        // more than we have, or an unknown denom
+        let err = balance.checked_sub(&coin(556, "BTC")).unwrap_err();
+        assert_eq!(err.operation, OverflowOperation::Sub);
+        let err = balance.checked_sub(&coin(1, "ATOM")).unwrap_err();
+        assert_eq!(err, OverflowError::new(OverflowOperation::Sub, 0, 1));
+
+        // the original is untouched
+        assert_eq!(
+            balance,
+            NativeBalance(vec![coin(555, "BTC"), coin(12345, "ETH")])
+        );
+    }
+
+    #[test]
+    fn balance_saturating_sub_works() {
+        let balance = NativeBalance(vec![coin(555, "BTC"), coin(12345, "ETH")]);
+
+        let less_eth = balance.saturating_sub(&coin(2345, "ETH"));
+        assert_eq!(
+            less_eth,
+            NativeBalance(vec![coin(555, "BTC"), coin(10000, "ETH")])
+        );
+
+        // exactly zero, and more than we have, both remove the entry
+        let expected = NativeBalance(vec![coin(12345, "ETH")]);
+        assert_eq!(expected, balance.saturating_sub(&coin(555, "BTC")));
+        assert_eq!(expected, balance.saturating_sub(&coin(556, "BTC")));
+
+        // unknown denom is a no-op
+        assert_eq!(balance, balance.saturating_sub(&coin(1, "ATOM")));
+    }
+
+    #[test]
+    fn balance_superset_works() {
+        let balance = NativeBalance(vec![coin(555, "BTC"), coin(12345, "ETH")]);
+
+        assert!(balance.contains(&coin(555, "BTC")));
+        assert!(!balance.contains(&coin(556, "BTC")));
+        assert!(!balance.contains(&coin(1, "ATOM")));
+        // zero of anything is contained
+        assert!(balance.contains(&coin(0, "ATOM")));
+
+        assert!(balance.is_superset_of(&[]));
+        assert!(balance.is_superset_of(&[coin(555, "BTC")]));
+        assert!(balance.is_superset_of(&[coin(12345, "ETH"), coin(100, "BTC")]));
+        assert!(balance.is_superset_of(&[coin(1, "BTC"), coin(0, "ATOM")]));
+        assert!(!balance.is_superset_of(&[coin(100, "BTC"), coin(1, "ATOM")]));
+        assert!(!balance.is_superset_of(&[coin(12346, "ETH"), coin(1, "BTC")]));
+        // duplicate denoms are added up
+        assert!(balance.is_superset_of(&[coin(300, "BTC"), coin(255, "BTC")]));
+        assert!(!balance.is_superset_of(&[coin(300, "BTC"), coin(256, "BTC")]));
+        // and a balance is a superset of itself
+        assert!(balance.is_superset_of(&balance.0));
+    }
+
     #[test]
     fn normalize_balance() {
         // remove 0 value items and sort
@@ -307,4 +395,58 @@ mod test {
             NativeBalance(vec![coin(800, "BTC"), coin(444, "ETH")])
         );
     }
+
+    #[test]
+    fn normalize_and_merge_coins() {
+        // duplicates in the same input are added up, zeros dropped
+        assert_eq!(
+            normalize_coins(vec![
+                coin(5, "ETH"),
+                coin(0, "ATOM"),
+                coin(7, "BTC"),
+                coin(3, "ETH"),
+                coin(0, "ETH"),
+            ]),
+            vec![coin(7, "BTC"), coin(8, "ETH")]
+        );
+        assert_eq!(normalize_coins(vec![coin(0, "ETH")]), vec![]);
+
+        let stored = vec![coin(100, "ATOM"), coin(5, "ETH")];
+        let funds = vec![
+            coin(1, "ETH"),
+            coin(2, "BTC"),
+            coin(3, "ETH"),
+            coin(0, "XRP"),
+        ];
+        assert_eq!(
+            merge_coins(stored, funds),
+            vec![coin(100, "ATOM"), coin(2, "BTC"), coin(9, "ETH")]
+        );
+        // also if the stored value was not normalized
+        assert_eq!(
+            merge_coins(vec![coin(1, "ETH"), coin(1, "ETH")], vec![]),
+            vec![coin(2, "ETH")]
+        );
+        assert_eq!(merge_coins(vec![], vec![]), vec![]);
+
+        let coins = vec![coin(100, "ATOM"), coin(5, "ETH")];
+        assert_eq!(find_coin(&coins, "ETH"), Some(&coin(5, "ETH")));
+        assert_eq!(find_coin(&coins, "BTC"), None);
+    }
+
+    #[test]
+    fn add_unnormalized_balance() {
+        // duplicate denoms in the balance, and in what is added
+        let balance = NativeBalance(vec![coin(5, "ETH"), coin(3, "ETH"), coin(0, "BTC")]);
+        let sum = balance.clone() + coin(1, "ETH");
+        assert_eq!(sum, NativeBalance(vec![coin(9, "ETH")]));
+
+        let sum = balance + NativeBalance(vec![coin(10, "ATOM"), coin(2, "ETH"), coin(20, "ATOM")]);
+        assert_eq!(sum, NativeBalance(vec![coin(30, "ATOM"), coin(10, "ETH")]));
+        assert!(sum.has(&coin(10, "ETH")));
+
+        // adding nothing does not leave a zero entry
+        let sum = NativeBalance(vec![coin(1, "BTC")]) + coin(0, "ETH");
+        assert_eq!(sum, NativeBalance(vec![coin(1, "BTC")]));
+    }
 }
@@ -1,7 +1,154 @@
-use cosmwasm_std::Response;
+use cosmwasm_std::{attr, Attribute, Response};
 
 /// This defines a set of attributes which should be added to `Response`.
 pub trait Event {
     /// Append attributes to response
     fn add_attributes(&self, response: &mut Response);
 }
+
+/// EventBuilder collects the attributes describing one action, so all contracts emit them
+/// the same way. The `action` attribute always comes first, followed by the others in
+/// the order they were added.
+///
+/// Use the constructors (transfer, mint, burn, approve) for the common token actions,
+/// so indexers can rely on the same keys and ordering everywhere.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventBuilder {
+    action: String,
+    attributes: Vec<Attribute>,
+}
+
+impl EventBuilder {
+    pub fn new(action: &str) -> Self {
+        EventBuilder {
+            action: action.to_string(),
+            attributes: vec![],
+        }
+    }
+
+    /// transfer of fungible tokens, as emitted by cw20
+    pub fn transfer<F: ToString, T: ToString, A: ToString>(from: F, to: T, amount: A) -> Self {
+        EventBuilder::new("transfer")
+            .attr("from", from)
+            .attr("to", to)
+            .attr_amount(amount)
+    }
+
+    /// minting of fungible tokens, as emitted by cw20
+    pub fn mint<T: ToString, A: ToString>(to: T, amount: A) -> Self {
+        EventBuilder::new("mint").attr("to", to).attr_amount(amount)
+    }
+
+    /// burning of fungible tokens, as emitted by cw20
+    pub fn burn<F: ToString, A: ToString>(from: F, amount: A) -> Self {
+        EventBuilder::new("burn")
+            .attr("from", from)
+            .attr_amount(amount)
+    }
+
+    /// approval of a spender for one token, as emitted by cw721
+    pub fn approve<S: ToString, P: ToString, T: ToString>(
+        sender: S,
+        spender: P,
+        token_id: T,
+    ) -> Self {
+        EventBuilder::new("approve")
+            .attr("sender", sender)
+            .attr("spender", spender)
+            .attr("token_id", token_id)
+    }
+
+    pub fn attr<K: ToString, V: ToString>(mut self, key: K, value: V) -> Self {
+        self.attributes.push(attr(key, value));
+        self
+    }
+
+    /// Adds the `amount` attribute. Works for cw20 amounts (Uint128) as well as
+    /// native Coins, which are written like "100uatom".
+    pub fn attr_amount<A: ToString>(self, amount: A) -> Self {
+        self.attr("amount", amount)
+    }
+
+    /// Returns all attributes, starting with `action`, to be used as `Response.attributes`
+    pub fn attributes(self) -> Vec<Attribute> {
+        let mut attributes = Vec::with_capacity(self.attributes.len() + 1);
+        attributes.push(attr("action", self.action));
+        attributes.extend(self.attributes);
+        attributes
+    }
+
+    /// Returns an Event of type action, with all other attributes
+    pub fn event(self) -> cosmwasm_std::Event {
+        cosmwasm_std::Event {
+            ty: self.action,
+            attributes: self.attributes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::{coin, Uint128};
+
+    #[test]
+    fn attributes_start_with_action() {
+        let attrs = EventBuilder::new("payout")
+            .attr("to", "bob")
+            .attr_amount(coin(100, "uatom"))
+            .attr("memo", "thanks")
+            .attributes();
+        assert_eq!(
+            attrs,
+            vec![
+                attr("action", "payout"),
+                attr("to", "bob"),
+                attr("amount", "100uatom"),
+                attr("memo", "thanks"),
+            ]
+        );
+
+        let event = EventBuilder::new("payout").attr("to", "bob").event();
+        assert_eq!(event.ty, "payout");
+        assert_eq!(event.attributes, vec![attr("to", "bob")]);
+    }
+
+    #[test]
+    fn canonical_events() {
+        let amount = Uint128::new(1234);
+        assert_eq!(
+            EventBuilder::transfer("alice", "bob", amount).attributes(),
+            vec![
+                attr("action", "transfer"),
+                attr("from", "alice"),
+                attr("to", "bob"),
+                attr("amount", "1234"),
+            ]
+        );
+        assert_eq!(
+            EventBuilder::mint("bob", amount).attributes(),
+            vec![
+                attr("action", "mint"),
+                attr("to", "bob"),
+                attr("amount", "1234"),
+            ]
+        );
+        assert_eq!(
+            EventBuilder::burn("alice", amount).attributes(),
+            vec![
+                attr("action", "burn"),
+                attr("from", "alice"),
+                attr("amount", "1234"),
+            ]
+        );
+        assert_eq!(
+            EventBuilder::approve("alice", "bob", "nft1").attributes(),
+            vec![
+                attr("action", "approve"),
+                attr("sender", "alice"),
+                attr("spender", "bob"),
+                attr("token_id", "nft1"),
+            ]
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{BlockInfo, StdError, StdResult, Timestamp};
+use crate::Scheduled;
+use cosmwasm_std::{BlockInfo, OverflowError, OverflowOperation, StdError, StdResult, Timestamp};
 use std::cmp::Ordering;
 use std::fmt;
 use std::ops::{Add, Mul};
+use std::str::FromStr;
 
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
@@ -23,13 +25,59 @@ pub enum Expiration {
 impl fmt::Display for Expiration {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Expiration::AtHeight(height) => write!(f, "expiration height: {}", height),
-            Expiration::AtTime(time) => write!(f, "expiration time: {}", time),
-            Expiration::Never {} => write!(f, "expiration: never"),
+            Expiration::AtHeight(height) => write!(f, "height:{}", height),
+            Expiration::AtTime(time) => write!(f, "time:{}", time),
+            Expiration::Never {} => write!(f, "never"),
         }
     }
 }
 
+/// Parses the format written by Display: "height:12345", "time:1629123456.000000000" or "never".
+/// The fractional part of the time is optional.
+impl FromStr for Expiration {
+    type Err = StdError;
+
+    fn from_str(s: &str) -> StdResult<Self> {
+        let err = |msg: &str| StdError::parse_err("Expiration", msg);
+        if s == "never" {
+            return Ok(Expiration::Never {});
+        }
+        match split_at(s, ':') {
+            Some(("height", height)) => height
+                .parse()
+                .map(Expiration::AtHeight)
+                .map_err(|_| err("invalid height")),
+            Some(("time", time)) => parse_timestamp(time)
+                .map(Expiration::AtTime)
+                .ok_or_else(|| err("invalid time")),
+            _ => Err(err(
+                "expected height:<height>, time:<seconds>.<nanos> or never",
+            )),
+        }
+    }
+}
+
+// the same as str::split_once, which needs a newer rust than we support
+fn split_at(s: &str, delimiter: char) -> Option<(&str, &str)> {
+    s.find(delimiter)
+        .map(|i| (&s[..i], &s[i + delimiter.len_utf8()..]))
+}
+
+/// Parses "<seconds>[.<nanos>]", with at most 9 digits after the dot
+fn parse_timestamp(s: &str) -> Option<Timestamp> {
+    let (seconds, fraction) = split_at(s, '.').unwrap_or((s, ""));
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let seconds: u64 = seconds.parse().ok()?;
+    let nanos = match fraction {
+        "" => 0,
+        digits => format!("{:0<9}", digits).parse().ok()?,
+    };
+    let nanos = seconds.checked_mul(1_000_000_000)?.checked_add(nanos)?;
+    Some(Timestamp::from_nanos(nanos))
+}
+
 /// The default (empty value) is to never expire
 impl Default for Expiration {
     fn default() -> Self {
@@ -45,6 +93,32 @@ impl Expiration {
             Expiration::Never {} => false,
         }
     }
+
+    /// min returns the earlier of the two expirations, eg. to cap the expiration of an
+    /// allowance by the one of its owner. Never is later than anything else.
+    /// Returns an error if one is height-based and the other time-based.
+    pub fn min(self, other: Expiration) -> StdResult<Expiration> {
+        match self.partial_cmp(&other) {
+            Some(Ordering::Greater) => Ok(other),
+            Some(_) => Ok(self),
+            None => Err(StdError::generic_err(
+                "Cannot compare height and time expirations",
+            )),
+        }
+    }
+
+    /// max returns the later of the two expirations, eg. to extend an existing expiration.
+    /// Never is later than anything else.
+    /// Returns an error if one is height-based and the other time-based.
+    pub fn max(self, other: Expiration) -> StdResult<Expiration> {
+        match self.partial_cmp(&other) {
+            Some(Ordering::Less) => Ok(other),
+            Some(_) => Ok(self),
+            None => Err(StdError::generic_err(
+                "Cannot compare height and time expirations",
+            )),
+        }
+    }
 }
 
 impl Add<Duration> for Expiration {
@@ -55,9 +129,12 @@ impl Add<Duration> for Expiration {
             (Expiration::AtTime(t), Duration::Time(delta)) => {
                 Ok(Expiration::AtTime(t.plus_seconds(delta)))
             }
-            (Expiration::AtHeight(h), Duration::Height(delta)) => {
-                Ok(Expiration::AtHeight(h + delta))
-            }
+            (Expiration::AtHeight(h), Duration::Height(delta)) => h
+                .checked_add(delta)
+                .map(Expiration::AtHeight)
+                .ok_or_else(|| {
+                    StdError::overflow(OverflowError::new(OverflowOperation::Add, h, delta))
+                }),
             (Expiration::Never {}, _) => Ok(Expiration::Never {}),
             _ => Err(StdError::generic_err("Cannot add height and time")),
         }
@@ -99,8 +176,28 @@ pub enum Duration {
 impl fmt::Display for Duration {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Duration::Height(height) => write!(f, "height: {}", height),
-            Duration::Time(time) => write!(f, "time: {}", time),
+            Duration::Height(height) => write!(f, "height:{}", height),
+            Duration::Time(time) => write!(f, "time:{}", time),
+        }
+    }
+}
+
+/// Parses the format written by Display: "height:100" or "time:3600" (in seconds)
+impl FromStr for Duration {
+    type Err = StdError;
+
+    fn from_str(s: &str) -> StdResult<Self> {
+        let err = |msg: &str| StdError::parse_err("Duration", msg);
+        match split_at(s, ':') {
+            Some(("height", height)) => height
+                .parse()
+                .map(Duration::Height)
+                .map_err(|_| err("invalid height")),
+            Some(("time", time)) => time
+                .parse()
+                .map(Duration::Time)
+                .map_err(|_| err("invalid time")),
+            _ => Err(err("expected height:<height> or time:<seconds>")),
         }
     }
 }
@@ -114,6 +211,14 @@ impl Duration {
         }
     }
 
+    /// Create a start time (Scheduled) for Duration after current block
+    pub fn scheduled_after(&self, block: &BlockInfo) -> Scheduled {
+        match self {
+            Duration::Height(h) => Scheduled::AtHeight(block.height + h),
+            Duration::Time(t) => Scheduled::AtTime(block.time.plus_seconds(*t)),
+        }
+    }
+
     // creates a number just a little bigger, so we can use it to pass expiration point
     pub fn plus_one(&self) -> Duration {
         match self {
@@ -121,16 +226,68 @@ impl Duration {
             Duration::Time(t) => Duration::Time(t + 1),
         }
     }
+
+    /// A time-based Duration of the given number of days (saturating at u64::MAX seconds)
+    pub const fn days(days: u64) -> Duration {
+        Duration::Time(days.saturating_mul(24 * 60 * 60))
+    }
+
+    /// A time-based Duration of the given number of weeks (saturating at u64::MAX seconds)
+    pub const fn weeks(weeks: u64) -> Duration {
+        Duration::Time(weeks.saturating_mul(7 * 24 * 60 * 60))
+    }
+
+    /// Adds two Durations of the same kind.
+    /// Returns an error on overflow, or if a height is added to a time.
+    pub fn checked_add(self, rhs: Duration) -> StdResult<Duration> {
+        let overflow =
+            |a: u64, b: u64| StdError::overflow(OverflowError::new(OverflowOperation::Add, a, b));
+        match (self, rhs) {
+            (Duration::Time(t), Duration::Time(t2)) => t
+                .checked_add(t2)
+                .map(Duration::Time)
+                .ok_or_else(|| overflow(t, t2)),
+            (Duration::Height(h), Duration::Height(h2)) => h
+                .checked_add(h2)
+                .map(Duration::Height)
+                .ok_or_else(|| overflow(h, h2)),
+            _ => Err(StdError::generic_err("Cannot add height and time")),
+        }
+    }
+
+    /// Multiplies the Duration, returning an error on overflow
+    pub fn checked_mul(self, rhs: u64) -> StdResult<Duration> {
+        let overflow =
+            |a: u64| StdError::overflow(OverflowError::new(OverflowOperation::Mul, a, rhs));
+        match self {
+            Duration::Time(t) => t
+                .checked_mul(rhs)
+                .map(Duration::Time)
+                .ok_or_else(|| overflow(t)),
+            Duration::Height(h) => h
+                .checked_mul(rhs)
+                .map(Duration::Height)
+                .ok_or_else(|| overflow(h)),
+        }
+    }
 }
 
 impl Add<Duration> for Duration {
     type Output = StdResult<Duration>;
 
     fn add(self, rhs: Duration) -> StdResult<Duration> {
-        match (self, rhs) {
-            (Duration::Time(t), Duration::Time(t2)) => Ok(Duration::Time(t + t2)),
-            (Duration::Height(h), Duration::Height(h2)) => Ok(Duration::Height(h + h2)),
-            _ => Err(StdError::generic_err("Cannot add height and time")),
+        self.checked_add(rhs)
+    }
+}
+
+impl PartialOrd for Duration {
+    fn partial_cmp(&self, other: &Duration) -> Option<Ordering> {
+        match (self, other) {
+            // compare if both height or both time
+            (Duration::Height(h1), Duration::Height(h2)) => Some(h1.cmp(h2)),
+            (Duration::Time(t1), Duration::Time(t2)) => Some(t1.cmp(t2)),
+            // if they are mis-matched, no compare possible
+            _ => None,
         }
     }
 }
@@ -149,6 +306,7 @@ impl Mul<u64> for Duration {
 #[cfg(test)]
 mod test {
     use super::*;
+    use cosmwasm_std::to_vec;
 
     #[test]
     fn compare_expiration() {
@@ -208,11 +366,48 @@ mod test {
         let end = Expiration::AtHeight(12345) + Duration::Time(1500);
         end.unwrap_err();
 
+        // overflow is an error, not a panic
+        let end = Expiration::AtHeight(u64::MAX - 1) + Duration::Height(2);
+        assert!(matches!(end.unwrap_err(), StdError::Overflow { .. }));
+
+        // never absorbs any duration, even a mismatched one
+        let end = Expiration::Never {} + Duration::Height(1);
+        assert_eq!(end.unwrap(), Expiration::Never {});
+
         // // not possible other way
         // let end = Duration::Time(1000) + Expiration::AtTime(50000);
         // assert_eq!(end.unwrap(), Expiration::AtTime(51000));
     }
 
+    #[test]
+    fn expiration_min_max() {
+        let low = Expiration::AtHeight(100);
+        let high = Expiration::AtHeight(200);
+        assert_eq!(low, low.min(high).unwrap());
+        assert_eq!(low, high.min(low).unwrap());
+        assert_eq!(high, low.max(high).unwrap());
+        assert_eq!(high, high.max(low).unwrap());
+        assert_eq!(low, low.min(low).unwrap());
+
+        let early = Expiration::AtTime(Timestamp::from_seconds(1000));
+        let late = Expiration::AtTime(Timestamp::from_seconds(2000));
+        assert_eq!(early, late.min(early).unwrap());
+        assert_eq!(late, early.max(late).unwrap());
+
+        // never is later than anything, of any variant
+        let never = Expiration::Never {};
+        assert_eq!(low, never.min(low).unwrap());
+        assert_eq!(early, early.min(never).unwrap());
+        assert_eq!(never, never.max(late).unwrap());
+        assert_eq!(never, high.max(never).unwrap());
+        assert_eq!(never, never.min(never).unwrap());
+        assert_eq!(never, never.max(never).unwrap());
+
+        // mismatched finite ends cannot be compared
+        low.min(early).unwrap_err();
+        late.max(high).unwrap_err();
+    }
+
     #[test]
     fn block_plus_duration() {
         let block = BlockInfo {
@@ -236,4 +431,131 @@ mod test {
         let days = DAY * 3;
         assert_eq!(Duration::Time(3 * 24 * 60 * 60), days);
     }
+
+    #[test]
+    fn duration_constructors() {
+        assert_eq!(Duration::days(1), DAY);
+        assert_eq!(Duration::days(7), WEEK);
+        assert_eq!(Duration::weeks(2), Duration::Time(1209600));
+        assert_eq!(Duration::days(u64::MAX), Duration::Time(u64::MAX));
+    }
+
+    #[test]
+    fn duration_checked_math() {
+        assert_eq!(
+            Duration::days(1).checked_add(HOUR).unwrap(),
+            Duration::Time(25 * 60 * 60)
+        );
+        assert_eq!(
+            Duration::Height(5)
+                .checked_add(Duration::Height(7))
+                .unwrap(),
+            Duration::Height(12)
+        );
+        assert_eq!(
+            Duration::Height(5).checked_mul(3).unwrap(),
+            Duration::Height(15)
+        );
+        assert_eq!(DAY.checked_mul(7).unwrap(), WEEK);
+
+        // mixing height and time
+        Duration::Height(5).checked_add(HOUR).unwrap_err();
+        (HOUR + Duration::Height(5)).unwrap_err();
+
+        // overflow is an error, not a panic
+        let err = Duration::Height(u64::MAX)
+            .checked_add(Duration::Height(1))
+            .unwrap_err();
+        assert!(matches!(err, StdError::Overflow { .. }));
+        let err = (Duration::Time(u64::MAX) + Duration::Time(1)).unwrap_err();
+        assert!(matches!(err, StdError::Overflow { .. }));
+        let err = Duration::weeks(u64::MAX / (7 * 24 * 60 * 60))
+            .checked_mul(2)
+            .unwrap_err();
+        assert!(matches!(err, StdError::Overflow { .. }));
+    }
+
+    #[test]
+    fn compare_durations() {
+        assert!(Duration::Height(5) < Duration::Height(10));
+        assert!(Duration::days(2) > Duration::days(1));
+        assert!(HOUR <= Duration::Time(3600));
+
+        // height and time are not comparable
+        assert_eq!(None, Duration::Height(5).partial_cmp(&HOUR));
+        assert_eq!(None, HOUR.partial_cmp(&Duration::Height(5)));
+        assert_eq!(None, Duration::Height(3600).partial_cmp(&HOUR));
+    }
+
+    #[test]
+    fn expiration_strings() {
+        let cases = [
+            (Expiration::AtHeight(12345), "height:12345"),
+            (
+                Expiration::AtTime(Timestamp::from_seconds(1629123456)),
+                "time:1629123456.000000000",
+            ),
+            (
+                Expiration::AtTime(Timestamp::from_seconds(1629123456).plus_nanos(42)),
+                "time:1629123456.000000042",
+            ),
+            (Expiration::Never {}, "never"),
+        ];
+        for (expiration, string) in cases.iter() {
+            assert_eq!(&expiration.to_string(), string);
+            assert_eq!(&string.parse::<Expiration>().unwrap(), expiration);
+        }
+
+        // the fractional part is optional, and may be shorter than 9 digits
+        assert_eq!(
+            "time:100".parse::<Expiration>().unwrap(),
+            Expiration::AtTime(Timestamp::from_seconds(100))
+        );
+        assert_eq!(
+            "time:100.5".parse::<Expiration>().unwrap(),
+            Expiration::AtTime(Timestamp::from_seconds(100).plus_nanos(500_000_000))
+        );
+
+        let invalid = [
+            "",
+            "height",
+            "height:",
+            "height:-1",
+            "height:12.5",
+            "time:1.0000000001",
+            "time:1.-5",
+            "time:18446744073709551615",
+            "at_height:12345",
+            "Never",
+        ];
+        for string in invalid.iter() {
+            let err = string.parse::<Expiration>().unwrap_err();
+            assert!(matches!(err, StdError::ParseErr { .. }), "{}", string);
+        }
+    }
+
+    #[test]
+    fn duration_strings() {
+        let cases = [(Duration::Height(100), "height:100"), (HOUR, "time:3600")];
+        for (duration, string) in cases.iter() {
+            assert_eq!(&duration.to_string(), string);
+            assert_eq!(&string.parse::<Duration>().unwrap(), duration);
+        }
+
+        let invalid = ["", "never", "time:1.5", "height:", "day:1"];
+        for string in invalid.iter() {
+            let err = string.parse::<Duration>().unwrap_err();
+            assert!(matches!(err, StdError::ParseErr { .. }), "{}", string);
+        }
+    }
+
+    #[test]
+    fn default_json_unchanged() {
+        let json = to_vec(&Expiration::AtHeight(12345)).unwrap();
+        assert_eq!(br#"{"at_height":12345}"#.to_vec(), json);
+        let json = to_vec(&Expiration::Never {}).unwrap();
+        assert_eq!(br#"{"never":{}}"#.to_vec(), json);
+        let json = to_vec(&Duration::Time(60)).unwrap();
+        assert_eq!(br#"{"time":60}"#.to_vec(), json);
+    }
 }
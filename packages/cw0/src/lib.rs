@@ -2,13 +2,25 @@ mod balance;
 mod event;
 mod expiration;
 mod pagination;
+mod parse_reply;
 mod payment;
+mod rate;
+mod scheduled;
+pub mod serde_str;
 
 pub use pagination::{
     calc_range_end, calc_range_start, calc_range_start_string, maybe_addr, maybe_canonical,
+    paginate_filtered, start_after_addr, validate_addresses, QueryOrder,
+};
+pub use parse_reply::{
+    parse_execute_response_data, parse_instantiate_response_data, parse_reply_execute_data,
+    parse_reply_instantiate_data, MsgExecuteContractResponse, MsgInstantiateContractResponse,
+    ParseReplyError,
 };
 pub use payment::{may_pay, must_pay, nonpayable, one_coin, PaymentError};
 
-pub use crate::balance::NativeBalance;
-pub use crate::event::Event;
+pub use crate::balance::{find_coin, merge_coins, normalize_coins, NativeBalance};
+pub use crate::event::{Event, EventBuilder};
 pub use crate::expiration::{Duration, Expiration, DAY, HOUR, WEEK};
+pub use crate::rate::Rate;
+pub use crate::scheduled::Scheduled;
@@ -1,4 +1,33 @@
-use cosmwasm_std::{Addr, Api, CanonicalAddr, StdResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Api, BlockInfo, CanonicalAddr, Order, StdError, StdResult};
+
+/// QueryOrder is the order of a paginated query, as set in a query message
+/// (cosmwasm_std::Order cannot be serialized). Queries default to ascending.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryOrder {
+    Ascending,
+    Descending,
+}
+
+// deriving Default for enums needs a newer rust than we support
+#[allow(clippy::derivable_impls)]
+impl Default for QueryOrder {
+    fn default() -> Self {
+        QueryOrder::Ascending
+    }
+}
+
+impl From<QueryOrder> for Order {
+    fn from(order: QueryOrder) -> Self {
+        match order {
+            QueryOrder::Ascending => Order::Ascending,
+            QueryOrder::Descending => Order::Descending,
+        }
+    }
+}
 
 // this is used for pagination. Maybe we move it into the std lib one day?
 pub fn maybe_canonical(api: &dyn Api, human: Option<Addr>) -> StdResult<Option<CanonicalAddr>> {
@@ -10,6 +39,20 @@ pub fn maybe_addr(api: &dyn Api, human: Option<String>) -> StdResult<Option<Addr
     human.map(|x| api.addr_validate(&x)).transpose()
 }
 
+/// validate_addresses validates all addresses (eg. from an instantiate message), keeping their order.
+/// If one is invalid, the error names the index of the first invalid entry.
+pub fn validate_addresses(api: &dyn Api, humans: &[String]) -> StdResult<Vec<Addr>> {
+    humans
+        .iter()
+        .enumerate()
+        .map(|(i, human)| {
+            api.addr_validate(human).map_err(|err| {
+                StdError::generic_err(format!("Invalid address at index {}: {}", i, err))
+            })
+        })
+        .collect()
+}
+
 // this will set the first key after the provided key, by appending a 0 byte
 pub fn calc_range_start(start_after: Option<Addr>) -> Option<Vec<u8>> {
     start_after.map(|addr| {
@@ -33,10 +76,45 @@ pub fn calc_range_start_string(start_after: Option<String>) -> Option<Vec<u8>> {
     })
 }
 
+/// start_after_addr validates the (optional) address from a query message, and returns the
+/// first key after it, by appending a 0 byte. Use it as an inclusive min bound to get the entries
+/// after the address, like an exclusive bound on the address itself.
+/// Returns an error if the address is not valid.
+pub fn start_after_addr(api: &dyn Api, start_after: Option<String>) -> StdResult<Option<Vec<u8>>> {
+    let addr = maybe_addr(api, start_after)?;
+    Ok(calc_range_start(addr))
+}
+
+/// paginate_filtered collects up to `limit` items from a range (which already starts after
+/// the `start_after` bound), skipping the ones `is_expired` at the given block, unless
+/// include_expired is set. Skipped items don't count towards the limit.
+/// Errors are never skipped, so they are returned to the caller.
+pub fn paginate_filtered<T, I, F>(
+    items: I,
+    limit: usize,
+    block: &BlockInfo,
+    include_expired: bool,
+    is_expired: F,
+) -> StdResult<Vec<T>>
+where
+    I: IntoIterator<Item = StdResult<T>>,
+    F: Fn(&T, &BlockInfo) -> bool,
+{
+    items
+        .into_iter()
+        .filter(|item| match item {
+            Ok(item) => include_expired || !is_expired(item, block),
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use cosmwasm_std::{testing::mock_dependencies, Order, StdError};
+    use crate::Expiration;
+    use cosmwasm_std::{from_slice, testing::mock_dependencies, testing::mock_env, to_vec};
     use cw_storage_plus::{Bound, Map};
 
     pub const HOLDERS: Map<&Addr, usize> = Map::new("some_data");
@@ -52,6 +130,18 @@ mod test {
         (key, v)
     }
 
+    #[test]
+    fn query_order_json() {
+        assert_eq!(to_vec(&QueryOrder::Descending).unwrap(), br#""descending""#);
+        let order: QueryOrder = from_slice(br#""ascending""#).unwrap();
+        assert_eq!(order, QueryOrder::default());
+        assert!(matches!(Order::from(order), Order::Ascending));
+        assert!(matches!(
+            Order::from(QueryOrder::Descending),
+            Order::Descending
+        ));
+    }
+
     #[test]
     fn calc_range_start_works_as_expected() {
         let total_elements_count = 100;
@@ -114,8 +204,173 @@ mod test {
         }
     }
 
+    #[test]
+    fn start_after_addr_is_exclusive() {
+        let mut deps = mock_dependencies(&[]);
+        for i in 0..10 {
+            HOLDERS
+                .save(&mut deps.storage, &addr_from_i(i), &i)
+                .unwrap();
+        }
+        // a key that starts with another one sorts after it, and must be included
+        let longer = Addr::unchecked(format!("{}0", addr_from_i(4)));
+        HOLDERS.save(&mut deps.storage, &longer, &100).unwrap();
+
+        let list = |min: Option<Bound>| -> Vec<usize> {
+            HOLDERS
+                .range(&deps.storage, min, None, Order::Ascending)
+                .map(|r| r.unwrap().1)
+                .collect()
+        };
+        for i in 0..10 {
+            let start_after = addr_from_i(i);
+            let start = start_after_addr(&deps.api, Some(start_after.to_string())).unwrap();
+            let appended = list(start.map(Bound::Inclusive));
+            let exclusive = list(Some(Bound::exclusive(&start_after)));
+            assert_eq!(appended, exclusive);
+            assert!(!appended.contains(&i));
+        }
+        let start = start_after_addr(&deps.api, Some(addr_from_i(4).to_string())).unwrap();
+        assert_eq!(list(start.map(Bound::Inclusive)), vec![100, 5, 6, 7, 8, 9]);
+
+        // None starts from the beginning
+        assert_eq!(None, start_after_addr(&deps.api, None).unwrap());
+
+        // invalid addresses are rejected
+        start_after_addr(&deps.api, Some("a".to_string())).unwrap_err();
+    }
+
+    #[test]
+    fn calc_range_start_string_is_exclusive() {
+        const TOKENS: Map<&str, u32> = Map::new("tokens");
+        let mut deps = mock_dependencies(&[]);
+        for (i, id) in ["apple", "banana", "banana2", "cherry"].iter().enumerate() {
+            TOKENS.save(&mut deps.storage, id, &(i as u32)).unwrap();
+        }
+
+        let start = calc_range_start_string(Some("banana".to_string()));
+        assert_eq!(Some(b"banana\x00".to_vec()), start);
+        let after: Vec<_> = TOKENS
+            .range(
+                &deps.storage,
+                start.map(Bound::Inclusive),
+                None,
+                Order::Ascending,
+            )
+            .map(|r| r.unwrap().1)
+            .collect();
+        let exclusive: Vec<_> = TOKENS
+            .range(
+                &deps.storage,
+                Some(Bound::exclusive("banana")),
+                None,
+                Order::Ascending,
+            )
+            .map(|r| r.unwrap().1)
+            .collect();
+        assert_eq!(vec![2, 3], after);
+        assert_eq!(after, exclusive);
+        assert_eq!(None, calc_range_start_string(None));
+    }
+
     // TODO: add unit tests
     #[ignore]
     #[test]
     fn add_more_tests() {}
+
+    #[test]
+    fn maybe_addr_validates() {
+        let deps = mock_dependencies(&[]);
+        assert_eq!(None, maybe_addr(&deps.api, None).unwrap());
+        assert_eq!(
+            Some(Addr::unchecked("addr0001")),
+            maybe_addr(&deps.api, Some("addr0001".to_string())).unwrap()
+        );
+        maybe_addr(&deps.api, Some("".to_string())).unwrap_err();
+    }
+
+    #[test]
+    fn validate_addresses_reports_index() {
+        let deps = mock_dependencies(&[]);
+        assert_eq!(
+            Vec::<Addr>::new(),
+            validate_addresses(&deps.api, &[]).unwrap()
+        );
+
+        // mixed case inputs are kept as given, it is up to the Api to accept them
+        let humans = vec!["addr0001".to_string(), "AdDr0002".to_string()];
+        let addrs = validate_addresses(&deps.api, &humans).unwrap();
+        assert_eq!(
+            addrs,
+            vec![Addr::unchecked("addr0001"), Addr::unchecked("AdDr0002")]
+        );
+
+        // the first invalid entry is reported
+        let humans = vec![
+            "addr0001".to_string(),
+            "addr0002".to_string(),
+            "".to_string(),
+            "x".to_string(),
+        ];
+        let err = validate_addresses(&deps.api, &humans).unwrap_err();
+        match err {
+            StdError::GenericErr { msg, .. } => {
+                assert!(msg.starts_with("Invalid address at index 2: "), "{}", msg)
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn paginate_filtered_skips_expired() {
+        const EXPIRES: Map<&str, Expiration> = Map::new("expires");
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let height = env.block.height;
+        let entries = [
+            ("a", Expiration::Never {}),
+            ("b", Expiration::AtHeight(height)),
+            ("c", Expiration::AtHeight(height + 1)),
+            ("d", Expiration::AtHeight(height - 1)),
+            ("e", Expiration::Never {}),
+        ];
+        for (key, expires) in entries.iter() {
+            EXPIRES.save(&mut deps.storage, key, expires).unwrap();
+        }
+
+        let page = |start_after: Option<&str>, limit: usize, include_expired: bool| {
+            let start = start_after.map(Bound::exclusive);
+            let items = EXPIRES
+                .range(&deps.storage, start, None, Order::Ascending)
+                .map(|r| r.map(|(k, v)| (String::from_utf8(k).unwrap(), v)));
+            let res = paginate_filtered(
+                items,
+                limit,
+                &env.block,
+                include_expired,
+                |(_, expires), block| expires.is_expired(block),
+            )
+            .unwrap();
+            res.into_iter().map(|(k, _)| k).collect::<Vec<_>>()
+        };
+
+        // expired entries are excluded only when include_expired is false
+        assert_eq!(page(None, 10, false), vec!["a", "c", "e"]);
+        assert_eq!(page(None, 10, true), vec!["a", "b", "c", "d", "e"]);
+
+        // and don't count towards the limit
+        assert_eq!(page(None, 2, false), vec!["a", "c"]);
+        assert_eq!(page(None, 2, true), vec!["a", "b"]);
+        assert_eq!(page(Some("c"), 2, false), vec!["e"]);
+        assert_eq!(page(Some("a"), 2, true), vec!["b", "c"]);
+
+        // errors are passed through
+        let items = vec![
+            Ok(Expiration::AtHeight(1)),
+            Err(StdError::generic_err("broken")),
+        ];
+        let err = paginate_filtered(items, 10, &env.block, false, |e, block| e.is_expired(block))
+            .unwrap_err();
+        assert_eq!(err, StdError::generic_err("broken"));
+    }
 }
@@ -0,0 +1,285 @@
+use thiserror::Error;
+
+use cosmwasm_std::{Binary, Reply};
+
+// Only length-delimited fields (strings and bytes) are used in the responses below
+const WIRE_TYPE_LENGTH_DELIMITED: u8 = 2;
+// A u64 varint takes at most 10 bytes
+const VARINT_MAX_BYTES: usize = 10;
+
+/// The response of MsgInstantiateContract, as returned in the data field of the reply
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgInstantiateContractResponse {
+    pub contract_address: String,
+    pub data: Option<Binary>,
+}
+
+/// The response of MsgExecuteContract, as returned in the data field of the reply
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgExecuteContractResponse {
+    pub data: Option<Binary>,
+}
+
+/// Errors returned from the reply parsers
+#[derive(Error, Debug, PartialEq)]
+pub enum ParseReplyError {
+    #[error("Failure response from sub-message: {0}")]
+    SubMsgFailure(String),
+
+    #[error("Invalid reply from sub-message: {0}")]
+    ParseFailure(String),
+
+    #[error("Error occurred while converting from UTF-8")]
+    BrokenUtf8(#[from] std::string::FromUtf8Error),
+}
+
+/// Parses the reply of a submessage sending a MsgInstantiateContract.
+/// Returns the address of the new contract and the data it set, if any.
+pub fn parse_reply_instantiate_data(
+    msg: Reply,
+) -> Result<MsgInstantiateContractResponse, ParseReplyError> {
+    let data = reply_data(msg)?;
+    parse_instantiate_response_data(data.as_slice())
+}
+
+/// Parses the reply of a submessage sending a MsgExecuteContract.
+/// Returns the data set by the executed contract, if any.
+pub fn parse_reply_execute_data(msg: Reply) -> Result<MsgExecuteContractResponse, ParseReplyError> {
+    let data = reply_data(msg)?;
+    parse_execute_response_data(data.as_slice())
+}
+
+/// Decodes the protobuf encoded MsgInstantiateContractResponse
+pub fn parse_instantiate_response_data(
+    data: &[u8],
+) -> Result<MsgInstantiateContractResponse, ParseReplyError> {
+    let mut data = data;
+    let contract_address = String::from_utf8(parse_protobuf_bytes(&mut data, 1)?)?;
+    if contract_address.is_empty() {
+        return Err(ParseReplyError::ParseFailure(
+            "Missing contract address".to_string(),
+        ));
+    }
+    let response_data = maybe_binary(parse_protobuf_bytes(&mut data, 2)?);
+    Ok(MsgInstantiateContractResponse {
+        contract_address,
+        data: response_data,
+    })
+}
+
+/// Decodes the protobuf encoded MsgExecuteContractResponse
+pub fn parse_execute_response_data(
+    data: &[u8],
+) -> Result<MsgExecuteContractResponse, ParseReplyError> {
+    let mut data = data;
+    let response_data = maybe_binary(parse_protobuf_bytes(&mut data, 1)?);
+    Ok(MsgExecuteContractResponse {
+        data: response_data,
+    })
+}
+
+fn reply_data(msg: Reply) -> Result<Binary, ParseReplyError> {
+    msg.result
+        .into_result()
+        .map_err(ParseReplyError::SubMsgFailure)?
+        .data
+        .ok_or_else(|| ParseReplyError::ParseFailure("Missing reply data".to_string()))
+}
+
+fn maybe_binary(bytes: Vec<u8>) -> Option<Binary> {
+    if bytes.is_empty() {
+        None
+    } else {
+        Some(Binary(bytes))
+    }
+}
+
+/// Reads a varint from the front of data, advancing it past the varint
+fn parse_protobuf_varint(data: &mut &[u8], field_number: u8) -> Result<u64, ParseReplyError> {
+    let mut value: u64 = 0;
+    for i in 0..VARINT_MAX_BYTES {
+        let byte = *data.get(i).ok_or_else(|| {
+            ParseReplyError::ParseFailure(format!(
+                "failed to decode Protobuf message: field #{}: varint data too short",
+                field_number
+            ))
+        })?;
+        value |= ((byte & 0x7f) as u64) << (i * 7);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Ok(value);
+        }
+    }
+    Err(ParseReplyError::ParseFailure(format!(
+        "failed to decode Protobuf message: field #{}: varint data too long",
+        field_number
+    )))
+}
+
+/// Reads the length-delimited field with the given number from the front of data,
+/// advancing it past the field. As protobuf omits empty fields, this returns an
+/// empty value if data is empty.
+fn parse_protobuf_bytes(data: &mut &[u8], field_number: u8) -> Result<Vec<u8>, ParseReplyError> {
+    if data.is_empty() {
+        return Ok(vec![]);
+    }
+    let key = parse_protobuf_varint(data, field_number)?;
+    if key >> 3 != field_number as u64 {
+        return Err(ParseReplyError::ParseFailure(format!(
+            "failed to decode Protobuf message: invalid field #{} for field #{}",
+            key >> 3,
+            field_number
+        )));
+    }
+    if key & 0x07 != WIRE_TYPE_LENGTH_DELIMITED as u64 {
+        return Err(ParseReplyError::ParseFailure(format!(
+            "failed to decode Protobuf message: invalid wire type for field #{}: expected {}, got {}",
+            field_number,
+            WIRE_TYPE_LENGTH_DELIMITED,
+            key & 0x07
+        )));
+    }
+    let len = parse_protobuf_varint(data, field_number)?;
+    if (data.len() as u64) < len {
+        return Err(ParseReplyError::ParseFailure(format!(
+            "failed to decode Protobuf message: field #{}: message too short",
+            field_number
+        )));
+    }
+    let (value, rest) = data.split_at(len as usize);
+    *data = rest;
+    Ok(value.to_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::{ContractResult, SubMsgExecutionResponse};
+
+    // a MsgInstantiateContractResponse as wasmd returns it, without data
+    const INSTANTIATE_REPLY: &str =
+        "Cj93YXNtMTRoajJ0YXZxOGZwZXNkd3h4Y3U0NHJ0eTNoaDkwdmh1anJ2Y21zdGw0enIzdHhtZnZ3OXM0aG1hbHI=";
+    // the same, where the instantiated contract set some data
+    const INSTANTIATE_REPLY_WITH_DATA: &str = "Cj93YXNtMTRoajJ0YXZxOGZwZXNkd3h4Y3U0NHJ0eTNoaDkwdmh1anJ2Y21zdGw0enIzdHhtZnZ3OXM0aG1hbHISN3sib3duZXIiOiJ3YXNtMWN5eXpweHBseGR6a2VlYTdrd3N5ZGFkZzg3MzU3cW5haGFrYWtzIn0=";
+    // a MsgExecuteContractResponse as wasmd returns it, where the contract set some data
+    const EXECUTE_REPLY: &str =
+        "Cjd7Im93bmVyIjoid2FzbTFjeXl6cHhwbHhkemtlZWE3a3dzeWRhZGc4NzM1N3FuYWhha2FrcyJ9";
+
+    const CONTRACT: &str = "wasm14hj2tavq8fpesdwxxcu44rty3hh90vhujrvcmstl4zr3txmfvw9s4hmalr";
+    const DATA: &str = r#"{"owner":"wasm1cyyzpxplxdzkeea7kwsydadg87357qnahakaks"}"#;
+
+    fn reply(data: Option<&str>) -> Reply {
+        Reply {
+            id: 1,
+            result: ContractResult::Ok(SubMsgExecutionResponse {
+                events: vec![],
+                data: data.map(|d| Binary::from_base64(d).unwrap()),
+            }),
+        }
+    }
+
+    #[test]
+    fn parse_instantiate_replies() {
+        let res = parse_reply_instantiate_data(reply(Some(INSTANTIATE_REPLY))).unwrap();
+        assert_eq!(
+            res,
+            MsgInstantiateContractResponse {
+                contract_address: CONTRACT.to_string(),
+                data: None,
+            }
+        );
+
+        let res = parse_reply_instantiate_data(reply(Some(INSTANTIATE_REPLY_WITH_DATA))).unwrap();
+        assert_eq!(
+            res,
+            MsgInstantiateContractResponse {
+                contract_address: CONTRACT.to_string(),
+                data: Some(Binary::from(DATA.as_bytes())),
+            }
+        );
+
+        // no address, no instantiation
+        let err = parse_reply_instantiate_data(reply(Some(""))).unwrap_err();
+        assert_eq!(
+            err,
+            ParseReplyError::ParseFailure("Missing contract address".to_string())
+        );
+        // field #1 is the address, so it must be a string
+        let err = parse_instantiate_response_data(&[0x0a, 0x02, 0xc3, 0x28]).unwrap_err();
+        assert!(matches!(err, ParseReplyError::BrokenUtf8(..)));
+    }
+
+    #[test]
+    fn parse_execute_replies() {
+        let res = parse_reply_execute_data(reply(Some(EXECUTE_REPLY))).unwrap();
+        assert_eq!(res.data, Some(Binary::from(DATA.as_bytes())));
+
+        // protobuf encodes an empty response as no bytes at all
+        let res = parse_reply_execute_data(reply(Some(""))).unwrap();
+        assert_eq!(res.data, None);
+
+        // the length is longer than the data
+        let err = parse_execute_response_data(&[0x0a, 0x05, 0x01]).unwrap_err();
+        assert_eq!(
+            err,
+            ParseReplyError::ParseFailure(
+                "failed to decode Protobuf message: field #1: message too short".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_broken_replies() {
+        let failed = Reply {
+            id: 1,
+            result: ContractResult::Err("out of gas".to_string()),
+        };
+        let err = parse_reply_execute_data(failed).unwrap_err();
+        assert_eq!(
+            err,
+            ParseReplyError::SubMsgFailure("out of gas".to_string())
+        );
+
+        let err = parse_reply_instantiate_data(reply(None)).unwrap_err();
+        assert_eq!(
+            err,
+            ParseReplyError::ParseFailure("Missing reply data".to_string())
+        );
+
+        // field #3 is not part of the response
+        let err = parse_execute_response_data(&[0x1a, 0x01, 0x00]).unwrap_err();
+        assert_eq!(
+            err,
+            ParseReplyError::ParseFailure(
+                "failed to decode Protobuf message: invalid field #3 for field #1".to_string()
+            )
+        );
+        // a varint instead of bytes
+        let err = parse_execute_response_data(&[0x08, 0x01]).unwrap_err();
+        assert_eq!(
+            err,
+            ParseReplyError::ParseFailure(
+                "failed to decode Protobuf message: invalid wire type for field #1: expected 2, got 0"
+                    .to_string()
+            )
+        );
+        // a length that never ends
+        let err = parse_execute_response_data(&[0x0a, 0x80, 0x80]).unwrap_err();
+        assert_eq!(
+            err,
+            ParseReplyError::ParseFailure(
+                "failed to decode Protobuf message: field #1: varint data too short".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_long_data() {
+        // longer than 127 bytes, so the length takes two bytes
+        let long = vec![b'x'; 300];
+        let mut encoded = vec![0x0a, 0xac, 0x02];
+        encoded.extend_from_slice(&long);
+        let res = parse_execute_response_data(&encoded).unwrap();
+        assert_eq!(res.data, Some(Binary(long)));
+    }
+}
@@ -1,9 +1,14 @@
 use cosmwasm_std::{Coin, MessageInfo, Uint128};
 use thiserror::Error;
 
+// Coins with a zero amount don't pay anything, so all helpers below ignore them
+fn paid(info: &MessageInfo) -> Vec<&Coin> {
+    info.funds.iter().filter(|c| !c.amount.is_zero()).collect()
+}
+
 /// returns an error if any coins were sent
 pub fn nonpayable(info: &MessageInfo) -> Result<(), PaymentError> {
-    if info.funds.is_empty() {
+    if paid(info).is_empty() {
         Ok(())
     } else {
         Err(PaymentError::NonPayable {})
@@ -13,16 +18,9 @@ pub fn nonpayable(info: &MessageInfo) -> Result<(), PaymentError> {
 /// If exactly one coin was sent, returns it regardless of denom.
 /// Returns error if 0 or 2+ coins were sent
 pub fn one_coin(info: &MessageInfo) -> Result<Coin, PaymentError> {
-    match info.funds.len() {
-        0 => Err(PaymentError::NoFunds {}),
-        1 => {
-            let coin = &info.funds[0];
-            if coin.amount.is_zero() {
-                Err(PaymentError::NoFunds {})
-            } else {
-                Ok(coin.clone())
-            }
-        }
+    match paid(info).as_slice() {
+        [] => Err(PaymentError::NoFunds {}),
+        [coin] => Ok((*coin).clone()),
         _ => Err(PaymentError::MultipleDenoms {}),
     }
 }
@@ -41,14 +39,11 @@ pub fn must_pay(info: &MessageInfo, denom: &str) -> Result<Uint128, PaymentError
 /// Similar to must_pay, but it any payment is optional. Returns an error if a different
 /// denom was sent. Otherwise, returns the amount of `denom` sent, or 0 if nothing sent.
 pub fn may_pay(info: &MessageInfo, denom: &str) -> Result<Uint128, PaymentError> {
-    if info.funds.is_empty() {
-        Ok(Uint128::zero())
-    } else if info.funds.len() == 1 && info.funds[0].denom == denom {
-        Ok(info.funds[0].amount)
-    } else {
+    let paid = paid(info);
+    match paid.iter().find(|c| c.denom != denom) {
         // find first mis-match
-        let wrong = info.funds.iter().find(|c| c.denom != denom).unwrap();
-        Err(PaymentError::ExtraDenom(wrong.denom.to_string()))
+        Some(wrong) => Err(PaymentError::ExtraDenom(wrong.denom.to_string())),
+        None => Ok(paid.iter().map(|c| c.amount).sum()),
     }
 }
 
@@ -133,4 +128,46 @@ mod test {
         let err = must_pay(&mixed_payment, atom).unwrap_err();
         assert_eq!(err, PaymentError::MultipleDenoms {});
     }
+
+    #[test]
+    fn zero_amounts_are_ignored() {
+        let atom: &str = "uatom";
+        let zero_atom = mock_info(SENDER, &coins(0, atom));
+        let zero_eth = mock_info(SENDER, &[coin(0, "wei"), coin(0, atom)]);
+        let atom_and_zero_eth = mock_info(SENDER, &[coin(0, "wei"), coin(100, atom)]);
+        let eth_and_zero_atom = mock_info(SENDER, &[coin(100, "wei"), coin(0, atom)]);
+
+        // only zero amounts pay nothing at all
+        nonpayable(&zero_atom).unwrap();
+        nonpayable(&zero_eth).unwrap();
+        assert_eq!(one_coin(&zero_eth).unwrap_err(), PaymentError::NoFunds {});
+        assert_eq!(may_pay(&zero_eth, atom).unwrap(), Uint128::zero());
+        assert_eq!(
+            must_pay(&zero_eth, atom).unwrap_err(),
+            PaymentError::NoFunds {}
+        );
+
+        // a zero amount next to a payment doesn't count as another denom
+        assert_eq!(one_coin(&atom_and_zero_eth).unwrap(), coin(100, atom));
+        assert_eq!(
+            must_pay(&atom_and_zero_eth, atom).unwrap(),
+            Uint128::new(100)
+        );
+        assert_eq!(
+            may_pay(&atom_and_zero_eth, atom).unwrap(),
+            Uint128::new(100)
+        );
+        let err = nonpayable(&atom_and_zero_eth).unwrap_err();
+        assert_eq!(err, PaymentError::NonPayable {});
+
+        // and a zero amount of the requested denom doesn't pay for it
+        assert_eq!(
+            must_pay(&eth_and_zero_atom, atom).unwrap_err(),
+            PaymentError::MissingDenom(atom.to_string())
+        );
+        assert_eq!(
+            may_pay(&eth_and_zero_atom, atom).unwrap_err(),
+            PaymentError::ExtraDenom("wei".to_string())
+        );
+    }
 }
@@ -0,0 +1,220 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Decimal, Fraction, StdError, StdResult, Uint128};
+
+/// Rate is a ratio applied to token amounts, eg. a fee, tax or commission.
+/// It serializes just like the Decimal it wraps (eg. "0.015"), and makes the rounding
+/// explicit: `checked_mul_uint` rounds down, `checked_mul_uint_ceil` rounds up.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema,
+)]
+#[serde(transparent)]
+pub struct Rate(Decimal);
+
+impl Rate {
+    pub fn zero() -> Self {
+        Rate(Decimal::zero())
+    }
+
+    pub fn one() -> Self {
+        Rate(Decimal::one())
+    }
+
+    /// 1% is `from_percent(1)`
+    pub fn from_percent(percent: u64) -> Self {
+        Rate(Decimal::percent(percent))
+    }
+
+    /// 1% is `from_basis_points(100)`
+    pub fn from_basis_points(bps: u64) -> Self {
+        Rate(Decimal::from_ratio(bps, 10_000u64))
+    }
+
+    pub fn decimal(&self) -> Decimal {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// A fee can take at most everything, so returns an error if the rate is above 100%
+    pub fn validate_fee(&self) -> StdResult<()> {
+        if self.0 > Decimal::one() {
+            Err(StdError::generic_err("Fee rate cannot be more than 100%"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns `amount * rate`, rounded down. Returns an error on overflow
+    pub fn checked_mul_uint(&self, amount: Uint128) -> StdResult<Uint128> {
+        let (whole, rest) = self.split_mul(amount)?;
+        Ok(whole.checked_add(Uint128::from(rest / self.0.denominator()))?)
+    }
+
+    /// Returns `amount * rate`, rounded up. Returns an error on overflow
+    pub fn checked_mul_uint_ceil(&self, amount: Uint128) -> StdResult<Uint128> {
+        let (whole, rest) = self.split_mul(amount)?;
+        let denominator = self.0.denominator();
+        let mut part = rest / denominator;
+        if rest % denominator != 0 {
+            part += 1;
+        }
+        Ok(whole.checked_add(Uint128::from(part))?)
+    }
+
+    /// Splits `amount * numerator / denominator` into `q * numerator` (which needs no
+    /// rounding) and `r * numerator`, which is left to divide, where `amount = q * denominator + r`.
+    /// This way it only overflows if the result would not fit.
+    fn split_mul(&self, amount: Uint128) -> StdResult<(Uint128, u128)> {
+        let numerator = Uint128::from(self.0.numerator());
+        let denominator = Uint128::from(self.0.denominator());
+        let q = amount.checked_div(denominator)?;
+        let r = amount.checked_rem(denominator)?;
+        let whole = q.checked_mul(numerator)?;
+        let rest = r.checked_mul(numerator)?;
+        Ok((whole, rest.u128()))
+    }
+}
+
+impl From<Decimal> for Rate {
+    fn from(decimal: Decimal) -> Self {
+        Rate(decimal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::{from_slice, to_vec};
+
+    #[test]
+    fn constructors() {
+        assert_eq!(Rate::from_percent(1), Rate::from_basis_points(100));
+        assert_eq!(Rate::from_basis_points(10_000), Rate::one());
+        assert_eq!(Rate::from_basis_points(0), Rate::zero());
+        assert_eq!(
+            Rate::from_basis_points(15).decimal(),
+            Decimal::permille(15) / Uint128::new(10)
+        );
+        assert!(Rate::zero().is_zero());
+
+        // same json as a Decimal
+        let json = to_vec(&Rate::from_basis_points(150)).unwrap();
+        assert_eq!(br#""0.015""#.to_vec(), json);
+        assert_eq!(Rate::from_basis_points(150), from_slice(&json).unwrap());
+    }
+
+    #[test]
+    fn validate_fee() {
+        Rate::zero().validate_fee().unwrap();
+        Rate::from_percent(2).validate_fee().unwrap();
+        // exactly 100% is fine
+        Rate::one().validate_fee().unwrap();
+        Rate::from_basis_points(10_001).validate_fee().unwrap_err();
+        Rate::from_percent(200).validate_fee().unwrap_err();
+    }
+
+    #[test]
+    fn mul_rounding() {
+        let rate = Rate::from_percent(3);
+        assert_eq!(
+            rate.checked_mul_uint(Uint128::new(100)).unwrap(),
+            Uint128::new(3)
+        );
+        assert_eq!(
+            rate.checked_mul_uint_ceil(Uint128::new(100)).unwrap(),
+            Uint128::new(3)
+        );
+        // 3% of 50 is 1.5
+        assert_eq!(
+            rate.checked_mul_uint(Uint128::new(50)).unwrap(),
+            Uint128::new(1)
+        );
+        assert_eq!(
+            rate.checked_mul_uint_ceil(Uint128::new(50)).unwrap(),
+            Uint128::new(2)
+        );
+        // a tiny fee on a tiny amount is free when rounding down, never when rounding up
+        let tiny = Rate::from_basis_points(1);
+        assert_eq!(
+            tiny.checked_mul_uint(Uint128::new(1)).unwrap(),
+            Uint128::zero()
+        );
+        assert_eq!(
+            tiny.checked_mul_uint_ceil(Uint128::new(1)).unwrap(),
+            Uint128::new(1)
+        );
+        assert_eq!(
+            Rate::zero().checked_mul_uint_ceil(Uint128::new(1)).unwrap(),
+            Uint128::zero()
+        );
+
+        // matches Decimal * Uint128, which rounds down
+        let amount = Uint128::new(123_456_789);
+        let decimal = Decimal::permille(7);
+        assert_eq!(
+            Rate::from(decimal).checked_mul_uint(amount).unwrap(),
+            amount * decimal
+        );
+    }
+
+    #[test]
+    fn mul_large_amounts() {
+        // the full range works for rates up to 100%
+        let max = Uint128::new(u128::MAX);
+        assert_eq!(Rate::one().checked_mul_uint(max).unwrap(), max);
+        assert_eq!(Rate::one().checked_mul_uint_ceil(max).unwrap(), max);
+        assert_eq!(
+            Rate::from_percent(50).checked_mul_uint(max).unwrap(),
+            Uint128::new(u128::MAX / 2)
+        );
+        assert_eq!(
+            Rate::from_percent(50).checked_mul_uint_ceil(max).unwrap(),
+            Uint128::new(u128::MAX / 2 + 1)
+        );
+
+        // only overflows if the result does not fit
+        let err = Rate::from_percent(200).checked_mul_uint(max).unwrap_err();
+        assert!(matches!(err, StdError::Overflow { .. }));
+        assert_eq!(
+            Rate::from_percent(200)
+                .checked_mul_uint(Uint128::new(u128::MAX / 2))
+                .unwrap(),
+            Uint128::new(u128::MAX - 1)
+        );
+    }
+
+    #[test]
+    fn floor_exact_ceil() {
+        // simple deterministic pseudo-random sequence, to cover many combinations
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+
+        for _ in 0..1000 {
+            let rate = Rate::from_basis_points(next() % 10_001);
+            let amount = Uint128::from(next() as u128);
+            let floor = rate.checked_mul_uint(amount).unwrap().u128();
+            let ceil = rate.checked_mul_uint_ceil(amount).unwrap().u128();
+
+            // compare with the exact value, scaled up by the denominator
+            let exact = amount.u128() * rate.decimal().numerator();
+            let denominator = rate.decimal().denominator();
+            assert!(floor * denominator <= exact);
+            assert!(exact <= ceil * denominator);
+            // they only differ if the exact value is not a whole number
+            if exact % denominator == 0 {
+                assert_eq!(floor, ceil);
+            } else {
+                assert_eq!(floor + 1, ceil);
+            }
+        }
+    }
+}
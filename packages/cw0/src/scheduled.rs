@@ -0,0 +1,159 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::Duration;
+use cosmwasm_std::{BlockInfo, OverflowError, OverflowOperation, StdError, StdResult, Timestamp};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Add;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+/// Scheduled represents a point in time when some event starts, the opposite of an Expiration.
+/// It can compare with a BlockInfo and will return is_triggered() == true
+/// once the condition is hit (and for every block in the future).
+/// It serializes just like the matching Expiration variants.
+pub enum Scheduled {
+    /// AtHeight will schedule when `env.block.height` >= height
+    AtHeight(u64),
+    /// AtTime will schedule when `env.block.time` >= time
+    AtTime(Timestamp),
+}
+
+impl fmt::Display for Scheduled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scheduled::AtHeight(height) => write!(f, "scheduled height: {}", height),
+            Scheduled::AtTime(time) => write!(f, "scheduled time: {}", time),
+        }
+    }
+}
+
+impl Scheduled {
+    pub fn is_triggered(&self, block: &BlockInfo) -> bool {
+        match self {
+            Scheduled::AtHeight(height) => block.height >= *height,
+            Scheduled::AtTime(time) => block.time >= *time,
+        }
+    }
+}
+
+impl Add<Duration> for Scheduled {
+    type Output = StdResult<Scheduled>;
+
+    fn add(self, duration: Duration) -> StdResult<Scheduled> {
+        match (self, duration) {
+            (Scheduled::AtTime(t), Duration::Time(delta)) => {
+                Ok(Scheduled::AtTime(t.plus_seconds(delta)))
+            }
+            (Scheduled::AtHeight(h), Duration::Height(delta)) => h
+                .checked_add(delta)
+                .map(Scheduled::AtHeight)
+                .ok_or_else(|| {
+                    StdError::overflow(OverflowError::new(OverflowOperation::Add, h, delta))
+                }),
+            _ => Err(StdError::generic_err("Cannot add height and time")),
+        }
+    }
+}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Scheduled) -> Option<Ordering> {
+        match (self, other) {
+            // compare if both height or both time
+            (Scheduled::AtHeight(h1), Scheduled::AtHeight(h2)) => Some(h1.cmp(h2)),
+            (Scheduled::AtTime(t1), Scheduled::AtTime(t2)) => Some(t1.cmp(t2)),
+            // if they are mis-matched, no compare possible
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Expiration;
+    use cosmwasm_std::{from_slice, to_vec};
+
+    fn block(height: u64, seconds: u64) -> BlockInfo {
+        BlockInfo {
+            height,
+            time: Timestamp::from_seconds(seconds),
+            chain_id: "foo".to_string(),
+        }
+    }
+
+    #[test]
+    fn triggers_at_boundary() {
+        let start = Scheduled::AtHeight(1000);
+        assert!(!start.is_triggered(&block(999, 5000)));
+        assert!(start.is_triggered(&block(1000, 5000)));
+        assert!(start.is_triggered(&block(1001, 5000)));
+
+        let start = Scheduled::AtTime(Timestamp::from_seconds(5000));
+        assert!(!start.is_triggered(&block(1000, 4999)));
+        assert!(start.is_triggered(&block(1000, 5000)));
+        assert!(start.is_triggered(&block(1000, 5001)));
+
+        // triggers exactly when the matching expiration expires
+        let b = block(1000, 5000);
+        assert_eq!(
+            Expiration::AtHeight(1000).is_expired(&b),
+            Scheduled::AtHeight(1000).is_triggered(&b)
+        );
+    }
+
+    #[test]
+    fn serializes_like_expiration() {
+        let height = Scheduled::AtHeight(12345);
+        let json = to_vec(&height).unwrap();
+        assert_eq!(br#"{"at_height":12345}"#.to_vec(), json);
+        assert_eq!(to_vec(&Expiration::AtHeight(12345)).unwrap(), json);
+        assert_eq!(height, from_slice::<Scheduled>(&json).unwrap());
+
+        let time = Scheduled::AtTime(Timestamp::from_seconds(777));
+        let json = to_vec(&time).unwrap();
+        assert_eq!(
+            to_vec(&Expiration::AtTime(Timestamp::from_seconds(777))).unwrap(),
+            json
+        );
+        assert_eq!(time, from_slice::<Scheduled>(&json).unwrap());
+
+        // there is no never
+        from_slice::<Scheduled>(br#"{"never":{}}"#).unwrap_err();
+    }
+
+    #[test]
+    fn scheduled_math() {
+        let b = block(1000, 7777);
+        assert_eq!(
+            Scheduled::AtHeight(1456),
+            Duration::Height(456).scheduled_after(&b)
+        );
+        assert_eq!(
+            Scheduled::AtTime(Timestamp::from_seconds(8989)),
+            Duration::Time(1212).scheduled_after(&b)
+        );
+
+        let later = Scheduled::AtHeight(100) + Duration::Height(50);
+        assert_eq!(Scheduled::AtHeight(150), later.unwrap());
+        let later = Scheduled::AtTime(Timestamp::from_seconds(100)) + Duration::Time(50);
+        assert_eq!(
+            Scheduled::AtTime(Timestamp::from_seconds(150)),
+            later.unwrap()
+        );
+        (Scheduled::AtHeight(100) + Duration::Time(50)).unwrap_err();
+        let overflow = Scheduled::AtHeight(u64::MAX) + Duration::Height(1);
+        assert!(matches!(overflow.unwrap_err(), StdError::Overflow { .. }));
+
+        assert!(Scheduled::AtHeight(5) < Scheduled::AtHeight(10));
+        assert!(
+            Scheduled::AtTime(Timestamp::from_seconds(10))
+                > Scheduled::AtTime(Timestamp::from_seconds(5))
+        );
+        assert_eq!(
+            None,
+            Scheduled::AtHeight(5).partial_cmp(&Scheduled::AtTime(Timestamp::from_seconds(10)))
+        );
+    }
+}
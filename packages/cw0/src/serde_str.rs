@@ -0,0 +1,68 @@
+//! Serializes a field as its Display string and parses it back with FromStr, eg. to write
+//! an `Expiration` as `"height:12345"` rather than `{"at_height":12345}`.
+//! Opt in per field with `#[serde(with = "cw0::serde_str")]` (and `#[schemars(with = "String")]`
+//! so the schema matches), the default representation of the types is not affected.
+
+use serde::de::{Deserialize, Deserializer, Error};
+use serde::Serializer;
+use std::fmt::Display;
+use std::str::FromStr;
+
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    serializer.serialize_str(&value.to_string())
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Duration, Expiration};
+    use cosmwasm_std::{from_slice, to_vec, Timestamp};
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq)]
+    struct Lock {
+        #[serde(with = "crate::serde_str")]
+        #[schemars(with = "String")]
+        expires: Expiration,
+        #[serde(with = "crate::serde_str")]
+        #[schemars(with = "String")]
+        period: Duration,
+    }
+
+    #[test]
+    fn round_trip_as_string() {
+        let lock = Lock {
+            expires: Expiration::AtTime(Timestamp::from_seconds(1629123456)),
+            period: Duration::Height(100),
+        };
+        let json = to_vec(&lock).unwrap();
+        assert_eq!(
+            br#"{"expires":"time:1629123456.000000000","period":"height:100"}"#.to_vec(),
+            json
+        );
+        assert_eq!(lock, from_slice::<Lock>(&json).unwrap());
+
+        let never = br#"{"expires":"never","period":"time:60"}"#;
+        let lock = from_slice::<Lock>(never).unwrap();
+        assert_eq!(lock.expires, Expiration::Never {});
+        assert_eq!(lock.period, Duration::Time(60));
+
+        // the default representation is not accepted in these fields
+        from_slice::<Lock>(br#"{"expires":{"never":{}},"period":"time:60"}"#).unwrap_err();
+        from_slice::<Lock>(br#"{"expires":"later","period":"time:60"}"#).unwrap_err();
+    }
+}
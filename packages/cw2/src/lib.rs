@@ -1,9 +1,19 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Querier, QuerierWrapper, QueryRequest, StdResult, Storage, WasmQuery};
+use cosmwasm_std::{
+    from_slice, to_vec, Binary, ContractResult, Empty, Querier, QueryRequest, StdError, StdResult,
+    Storage, SystemResult, WasmQuery,
+};
 use cw_storage_plus::Item;
 
+mod migrate;
+
+pub use migrate::{
+    assert_contract_version, get_migration_history, migrate_version, push_contract_migration,
+    MigrationRecord, VersionError, MAX_MIGRATION_HISTORY, MIGRATION_HISTORY,
+};
+
 pub const CONTRACT: Item<ContractVersion> = Item::new("contract_info");
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -18,11 +28,50 @@ pub struct ContractVersion {
     pub version: String,
 }
 
+/// The interfaces a contract implements, eg. `crates.io:cw20` for the base cw20 spec
+/// and `crates.io:cw20-allowances` for its allowances extension
+pub const SUPPORTED_INTERFACES: Item<Vec<ContractSupportedInterface>> =
+    Item::new("supported_interfaces");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ContractSupportedInterface {
+    /// interface is the name of the spec (or extension of it), prefixed like the contract name,
+    /// eg. `crates.io:cw721-enumerable`
+    pub interface: String,
+    /// version of the spec, in the same format as the contract version
+    pub version: String,
+}
+
 /// get_contract_version can be use in migrate to read the previous version of this contract
 pub fn get_contract_version(store: &dyn Storage) -> StdResult<ContractVersion> {
     CONTRACT.load(store)
 }
 
+/// get_contract_supported_interfaces returns the interfaces this contract claims to support,
+/// an empty list if none were set
+pub fn get_contract_supported_interfaces(
+    store: &dyn Storage,
+) -> StdResult<Vec<ContractSupportedInterface>> {
+    Ok(SUPPORTED_INTERFACES.may_load(store)?.unwrap_or_default())
+}
+
+/// set_contract_supported_interface should be used in instantiate (and migrate) along with
+/// set_contract_version. Interfaces that were already set are updated to the given version,
+/// the others are added, so contracts building on another one can add their own.
+pub fn set_contract_supported_interface(
+    store: &mut dyn Storage,
+    supported_interfaces: &[ContractSupportedInterface],
+) -> StdResult<()> {
+    let mut stored = get_contract_supported_interfaces(store)?;
+    for new in supported_interfaces {
+        match stored.iter_mut().find(|i| i.interface == new.interface) {
+            Some(existing) => existing.version = new.version.clone(),
+            None => stored.push(new.clone()),
+        }
+    }
+    SUPPORTED_INTERFACES.save(store, &stored)
+}
+
 /// set_contract_version should be used in instantiate to store the original version, and after a successful
 /// migrate to update it
 pub fn set_contract_version<T: Into<String>, U: Into<String>>(
@@ -42,15 +91,59 @@ pub fn set_contract_version<T: Into<String>, U: Into<String>>(
 /// if the other contract exists and claims to be a cw20-base contract for example.
 /// (Note: you usually want to require *interfaces* not *implementations* of the
 /// contracts you compose with, so be careful of overuse)
+/// Returns StdError::NotFound if the contract did not store a ContractVersion,
+/// eg. because it is not CW2-compliant.
 pub fn query_contract_info<Q: Querier, T: Into<String>>(
     querier: &Q,
     contract_addr: T,
 ) -> StdResult<ContractVersion> {
-    let req = QueryRequest::Wasm(WasmQuery::Raw {
-        contract_addr: contract_addr.into(),
-        key: CONTRACT.as_slice().into(),
+    let value = raw_query(querier, contract_addr.into(), CONTRACT.as_slice())?;
+    match value {
+        Some(value) => from_slice(&value),
+        None => Err(StdError::not_found("cw2::ContractVersion")),
+    }
+}
+
+/// Like query_contract_info, this makes a raw query to another contract, to read the
+/// interfaces it claims to support. Returns an empty list if it did not store any.
+pub fn query_supported_interfaces<Q: Querier, T: Into<String>>(
+    querier: &Q,
+    contract_addr: T,
+) -> StdResult<Vec<ContractSupportedInterface>> {
+    let value = raw_query(
+        querier,
+        contract_addr.into(),
+        SUPPORTED_INTERFACES.as_slice(),
+    )?;
+    match value {
+        Some(value) => from_slice(&value),
+        None => Ok(vec![]),
+    }
+}
+
+// raw queries return an empty value for missing keys, which QuerierWrapper
+// would report as a parse error, so these are None
+fn raw_query<Q: Querier>(
+    querier: &Q,
+    contract_addr: String,
+    key: &[u8],
+) -> StdResult<Option<Binary>> {
+    let req: QueryRequest<Empty> = QueryRequest::Wasm(WasmQuery::Raw {
+        contract_addr,
+        key: key.into(),
     });
-    QuerierWrapper::new(querier).query(&req)
+    match querier.raw_query(&to_vec(&req)?) {
+        SystemResult::Err(system_err) => Err(StdError::generic_err(format!(
+            "Querier system error: {}",
+            system_err
+        ))),
+        SystemResult::Ok(ContractResult::Err(contract_err)) => Err(StdError::generic_err(format!(
+            "Querier contract error: {}",
+            contract_err
+        ))),
+        SystemResult::Ok(ContractResult::Ok(value)) if value.is_empty() => Ok(None),
+        SystemResult::Ok(ContractResult::Ok(value)) => Ok(Some(value)),
+    }
 }
 
 #[cfg(test)]
@@ -77,4 +170,40 @@ mod tests {
         };
         assert_eq!(expected, loaded);
     }
+
+    #[test]
+    fn supported_interfaces_are_merged() {
+        let mut store = MockStorage::new();
+        assert_eq!(get_contract_supported_interfaces(&store).unwrap(), vec![]);
+
+        let interface = |name: &str, version: &str| ContractSupportedInterface {
+            interface: name.to_string(),
+            version: version.to_string(),
+        };
+        set_contract_supported_interface(
+            &mut store,
+            &[
+                interface("crates.io:cw20", "0.6.0"),
+                interface("crates.io:cw20-allowances", "0.6.0"),
+            ],
+        )
+        .unwrap();
+        // eg. a contract building on cw20-base adds one, and updates another
+        set_contract_supported_interface(
+            &mut store,
+            &[
+                interface("crates.io:cw20-bonding", "0.1.0"),
+                interface("crates.io:cw20", "0.7.0"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            get_contract_supported_interfaces(&store).unwrap(),
+            vec![
+                interface("crates.io:cw20", "0.7.0"),
+                interface("crates.io:cw20-allowances", "0.6.0"),
+                interface("crates.io:cw20-bonding", "0.1.0"),
+            ]
+        );
+    }
 }
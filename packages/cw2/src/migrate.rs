@@ -0,0 +1,386 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::str::FromStr;
+use thiserror::Error;
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cw_storage_plus::Item;
+
+use crate::{ContractVersion, CONTRACT};
+
+/// How many migrations are kept in the history, older ones are dropped
+pub const MAX_MIGRATION_HISTORY: usize = 20;
+
+/// The versions a contract was migrated from, oldest first
+pub const MIGRATION_HISTORY: Item<Vec<MigrationRecord>> = Item::new("migration_history");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MigrationRecord {
+    /// the version that was replaced
+    pub version: ContractVersion,
+    /// the block height of the migration
+    pub height: u64,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum VersionError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("No contract version stored, the contract predates cw2")]
+    NotFound {},
+
+    #[error("Cannot migrate from contract {found}, expected {expected}")]
+    WrongContract { expected: String, found: String },
+
+    #[error("Cannot migrate from version {version}, requires {required}")]
+    UnsupportedVersion { version: String, required: String },
+
+    #[error("Invalid version requirement: {req}")]
+    InvalidVersionReq { req: String },
+}
+
+/// assert_contract_version should be used at the start of migrate, to check that the previous
+/// contract is the expected one, with a version matching expected_version_req, which is a
+/// comma separated list of comparisons that all have to hold (eg. `">=0.6.0, <0.9.0"`),
+/// or `"*"` for any version. Versions are `major.minor.patch`, optionally prefixed with `v`,
+/// anything else never matches.
+/// Returns the stored version, eg. to pick a migration path.
+pub fn assert_contract_version(
+    storage: &dyn Storage,
+    expected_name: &str,
+    expected_version_req: &str,
+) -> Result<ContractVersion, VersionError> {
+    let req: VersionReq = expected_version_req.parse()?;
+    let stored = CONTRACT
+        .may_load(storage)?
+        .ok_or(VersionError::NotFound {})?;
+    if stored.contract != expected_name {
+        return Err(VersionError::WrongContract {
+            expected: expected_name.to_string(),
+            found: stored.contract,
+        });
+    }
+    match Version::parse(&stored.version) {
+        Some(version) if req.matches(&version) => Ok(stored),
+        _ => Err(VersionError::UnsupportedVersion {
+            version: stored.version,
+            required: expected_version_req.to_string(),
+        }),
+    }
+}
+
+/// push_contract_migration adds version to the migration history, as replaced at height.
+/// Only the last MAX_MIGRATION_HISTORY migrations are kept.
+/// Usually called through migrate_version.
+pub fn push_contract_migration(
+    storage: &mut dyn Storage,
+    version: ContractVersion,
+    height: u64,
+) -> StdResult<()> {
+    let mut history = get_migration_history(storage)?;
+    history.push(MigrationRecord { version, height });
+    if history.len() > MAX_MIGRATION_HISTORY {
+        history.drain(..history.len() - MAX_MIGRATION_HISTORY);
+    }
+    MIGRATION_HISTORY.save(storage, &history)
+}
+
+/// migrate_version should be used at the end of a successful migrate instead of
+/// set_contract_version. It also adds the previous version to the migration history,
+/// unless there is none (as the contract predates cw2). Migrating to the stored contract and
+/// version (eg. to re-run a backfill) writes nothing.
+pub fn migrate_version<T: Into<String>, U: Into<String>>(
+    storage: &mut dyn Storage,
+    name: T,
+    new_version: U,
+    height: u64,
+) -> StdResult<()> {
+    let new = ContractVersion {
+        contract: name.into(),
+        version: new_version.into(),
+    };
+    match CONTRACT.may_load(storage)? {
+        Some(previous) if previous == new => return Ok(()),
+        Some(previous) => push_contract_migration(storage, previous, height)?,
+        None => {}
+    }
+    CONTRACT.save(storage, &new)
+}
+
+/// get_migration_history returns the versions this contract was migrated from, oldest first
+pub fn get_migration_history(storage: &dyn Storage) -> StdResult<Vec<MigrationRecord>> {
+    Ok(MIGRATION_HISTORY.may_load(storage)?.unwrap_or_default())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+}
+
+impl Version {
+    fn parse(version: &str) -> Option<Self> {
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let mut parts = version.split('.').map(|p| {
+            // u64::from_str accepts a leading +
+            if p.is_empty() || !p.bytes().all(|b| b.is_ascii_digit()) {
+                None
+            } else {
+                p.parse::<u64>().ok()
+            }
+        });
+        let v = Version {
+            major: parts.next()??,
+            minor: parts.next()??,
+            patch: parts.next()??,
+        };
+        match parts.next() {
+            None => Some(v),
+            Some(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+/// All comparisons have to hold, an empty list matches any version
+#[derive(Clone, Debug, PartialEq)]
+struct VersionReq(Vec<(Op, Version)>);
+
+impl VersionReq {
+    fn matches(&self, version: &Version) -> bool {
+        self.0.iter().all(|(op, bound)| {
+            let ord = version.cmp(bound);
+            match op {
+                Op::Exact => ord == Ordering::Equal,
+                Op::Greater => ord == Ordering::Greater,
+                Op::GreaterEq => ord != Ordering::Less,
+                Op::Less => ord == Ordering::Less,
+                Op::LessEq => ord != Ordering::Greater,
+            }
+        })
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = VersionError;
+
+    fn from_str(req: &str) -> Result<Self, Self::Err> {
+        let invalid = || VersionError::InvalidVersionReq {
+            req: req.to_string(),
+        };
+        if req.trim() == "*" {
+            return Ok(VersionReq(vec![]));
+        }
+        req.split(',')
+            .map(|part| {
+                let part = part.trim();
+                // the two character operators have to be checked first
+                let (op, rest) = [
+                    (">=", Op::GreaterEq),
+                    ("<=", Op::LessEq),
+                    (">", Op::Greater),
+                    ("<", Op::Less),
+                    ("=", Op::Exact),
+                ]
+                .iter()
+                .find_map(|(prefix, op)| part.strip_prefix(prefix).map(|rest| (*op, rest)))
+                .unwrap_or((Op::Exact, part));
+                let version = Version::parse(rest.trim()).ok_or_else(invalid)?;
+                Ok((op, version))
+            })
+            .collect::<Result<_, _>>()
+            .map(VersionReq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::set_contract_version;
+    use cosmwasm_std::testing::MockStorage;
+
+    fn version(major: u64, minor: u64, patch: u64) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!(Version::parse("0.7.0"), Some(version(0, 7, 0)));
+        assert_eq!(Version::parse("v1.12.3"), Some(version(1, 12, 3)));
+        // ordered by number, not as strings
+        assert!(version(0, 10, 0) > version(0, 9, 1));
+
+        for invalid in &[
+            "",
+            "1",
+            "1.2",
+            "1.2.3.4",
+            "1.2.x",
+            "1.+2.3",
+            "1.2.3-beta",
+            "V1.2.3",
+        ] {
+            assert_eq!(Version::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parse_and_match_requirements() {
+        let req: VersionReq = ">=0.6.0, <0.9.0".parse().unwrap();
+        assert!(!req.matches(&version(0, 5, 9)));
+        assert!(req.matches(&version(0, 6, 0)));
+        assert!(req.matches(&version(0, 8, 99)));
+        assert!(!req.matches(&version(0, 9, 0)));
+
+        let req: VersionReq = "<=0.7.0".parse().unwrap();
+        assert!(req.matches(&version(0, 7, 0)));
+        assert!(!req.matches(&version(0, 7, 1)));
+
+        // without an operator the version has to be exact
+        let req: VersionReq = "v0.7.0".parse().unwrap();
+        assert_eq!(req, "=0.7.0".parse().unwrap());
+        assert!(req.matches(&version(0, 7, 0)));
+        assert!(!req.matches(&version(0, 7, 1)));
+
+        let req: VersionReq = ">0.7.0".parse().unwrap();
+        assert!(!req.matches(&version(0, 7, 0)));
+        assert!(req.matches(&version(1, 0, 0)));
+
+        let req: VersionReq = " * ".parse().unwrap();
+        assert!(req.matches(&version(0, 0, 0)));
+
+        for invalid in &[
+            "",
+            ">=0.6",
+            ">=0.6.0,",
+            "~0.6.0",
+            "=>0.6.0",
+            "0.6.0 - 0.7.0",
+        ] {
+            let err = invalid.parse::<VersionReq>().unwrap_err();
+            assert_eq!(
+                err,
+                VersionError::InvalidVersionReq {
+                    req: invalid.to_string()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn assert_version() {
+        let mut store = MockStorage::new();
+        let name = "crates.io:cw20-base";
+
+        // contracts without version info cannot be checked
+        let err = assert_contract_version(&store, name, "*").unwrap_err();
+        assert_eq!(err, VersionError::NotFound {});
+
+        set_contract_version(&mut store, name, "0.7.0").unwrap();
+        let stored = assert_contract_version(&store, name, ">=0.6.0, <0.9.0").unwrap();
+        assert_eq!(stored.version, "0.7.0");
+        assert_contract_version(&store, name, "*").unwrap();
+
+        let err = assert_contract_version(&store, "crates.io:cw1-subkeys", "*").unwrap_err();
+        assert_eq!(
+            err,
+            VersionError::WrongContract {
+                expected: "crates.io:cw1-subkeys".to_string(),
+                found: name.to_string(),
+            }
+        );
+
+        // eg. a downgrade
+        let err = assert_contract_version(&store, name, "<=0.6.5").unwrap_err();
+        assert_eq!(
+            err,
+            VersionError::UnsupportedVersion {
+                version: "0.7.0".to_string(),
+                required: "<=0.6.5".to_string(),
+            }
+        );
+
+        // only matches any version if it can be parsed
+        set_contract_version(&mut store, name, "my-fork-2").unwrap();
+        let err = assert_contract_version(&store, name, "*").unwrap_err();
+        assert!(matches!(err, VersionError::UnsupportedVersion { .. }));
+
+        // the requirement is checked first
+        let err = assert_contract_version(&store, "other", "0.7").unwrap_err();
+        assert_eq!(
+            err,
+            VersionError::InvalidVersionReq {
+                req: "0.7".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn migration_history() {
+        let mut store = MockStorage::new();
+        let name = "crates.io:cw20-base";
+        let version = |version: &str| ContractVersion {
+            contract: name.to_string(),
+            version: version.to_string(),
+        };
+        assert_eq!(get_migration_history(&store).unwrap(), vec![]);
+
+        // nothing to record for contracts from before cw2
+        migrate_version(&mut store, name, "0.5.0", 100).unwrap();
+        assert_eq!(get_migration_history(&store).unwrap(), vec![]);
+
+        migrate_version(&mut store, name, "0.6.0", 200).unwrap();
+        migrate_version(&mut store, name, "0.7.0", 300).unwrap();
+        assert_eq!(
+            get_migration_history(&store).unwrap(),
+            vec![
+                MigrationRecord {
+                    version: version("0.5.0"),
+                    height: 200,
+                },
+                MigrationRecord {
+                    version: version("0.6.0"),
+                    height: 300,
+                },
+            ]
+        );
+        assert_eq!(CONTRACT.load(&store).unwrap(), version("0.7.0"));
+
+        // migrating to the same version is not recorded
+        migrate_version(&mut store, name, "0.7.0", 350).unwrap();
+        assert_eq!(get_migration_history(&store).unwrap().len(), 2);
+        assert_eq!(CONTRACT.load(&store).unwrap(), version("0.7.0"));
+
+        // only the most recent ones are kept
+        for i in 0..MAX_MIGRATION_HISTORY as u64 {
+            push_contract_migration(&mut store, version(&format!("1.0.{}", i)), 400 + i).unwrap();
+        }
+        let history = get_migration_history(&store).unwrap();
+        assert_eq!(history.len(), MAX_MIGRATION_HISTORY);
+        assert_eq!(history[0].version, version("1.0.0"));
+        assert_eq!(history[0].height, 400);
+        let last = MAX_MIGRATION_HISTORY as u64 - 1;
+        assert_eq!(
+            history[MAX_MIGRATION_HISTORY - 1],
+            MigrationRecord {
+                version: version(&format!("1.0.{}", last)),
+                height: 400 + last,
+            }
+        );
+    }
+}
@@ -1,13 +1,23 @@
 // mod helpers;
 mod helpers;
 mod msg;
+mod proposals;
 mod query;
+mod threshold;
+mod votes;
+mod voting_period;
 
 pub use crate::helpers::Cw3Contract;
 pub use crate::msg::{Cw3ExecuteMsg, Vote};
+pub use crate::proposals::{index_proposals, range_proposals, stored_statuses, MAX_SCANNED};
 pub use crate::query::{
-    Cw3QueryMsg, ProposalListResponse, ProposalResponse, Status, ThresholdResponse, VoteInfo,
-    VoteListResponse, VoteResponse, VoterDetail, VoterListResponse, VoterResponse,
+    Cw3QueryMsg, ProposalKind, ProposalListResponse, ProposalResponse, Status, ThresholdResponse,
+    VoteInfo, VoteListResponse, VoteResponse, VoterDetail, VoterListResponse, VoterResponse,
+};
+pub use crate::threshold::{Threshold, ThresholdError};
+pub use crate::votes::{range_votes, Ballot};
+pub use crate::voting_period::{
+    proposal_expiration, validate_min_voting_period, VotingPeriodError,
 };
 
 #[cfg(test)]
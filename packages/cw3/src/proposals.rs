@@ -0,0 +1,340 @@
+use std::cmp::Ordering;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{Order, Pair, StdError, StdResult, Storage};
+use cw_storage_plus::{Bound, IndexList, IndexedMap, MultiIndex, U64Key, U8Key};
+
+use crate::query::Status;
+
+/// The number of stored proposals a status filter looks at (per stored status, see
+/// stored_statuses) in one query, so that stale Open proposals cannot make it run out of gas
+pub const MAX_SCANNED: usize = 100;
+
+/// stored_statuses returns the statuses a proposal with the given current status can be
+/// stored with. Open proposals are only updated when they are voted on, executed or closed,
+/// so they may have passed or been rejected in the meantime.
+pub fn stored_statuses(status: Status) -> &'static [Status] {
+    match status {
+        Status::Passed => &[Status::Open, Status::Passed],
+        Status::Rejected => &[Status::Open, Status::Rejected],
+        Status::Open => &[Status::Open],
+        Status::Pending => &[Status::Pending],
+        Status::Executed => &[Status::Executed],
+        Status::Done => &[Status::Done],
+    }
+}
+
+/// range_proposals returns (at most) limit proposals between min and max, in the given order,
+/// along with the id to continue after for the next page, or None if there are no more.
+/// The proposals are keyed by their id, and status_index indexes them by their stored status
+/// (as u8).
+///
+/// With a status, only the proposals whose current_status is this status are returned.
+/// They are found through the status index, checking every bucket they can be stored in,
+/// but at most MAX_SCANNED proposals of each. So a page may have less than limit proposals,
+/// or none, while there are more to come: use the returned id rather than the last one listed.
+#[allow(clippy::too_many_arguments)]
+pub fn range_proposals<'a, T, I>(
+    store: &dyn Storage,
+    proposals: &IndexedMap<'a, U64Key, T, I>,
+    status_index: &MultiIndex<'a, U8Key, T>,
+    current_status: impl Fn(&T) -> Status,
+    min: Option<Bound>,
+    max: Option<Bound>,
+    order: Order,
+    limit: usize,
+    status: Option<Status>,
+) -> StdResult<(Vec<Pair<T>>, Option<u64>)>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    I: IndexList<T>,
+{
+    // an empty page would look like the last one
+    let limit = limit.max(1);
+    let status = match status {
+        Some(status) => status,
+        None => {
+            let props = proposals
+                .range(store, min, max, order)
+                .take(limit + 1)
+                .collect::<StdResult<Vec<_>>>()?;
+            return page(props, limit, None);
+        }
+    };
+
+    // the proposals of every bucket, and the last id scanned in a bucket that has more left.
+    // Any proposal up to this one (in the given order) is on this page or the next ones
+    let mut props = vec![];
+    let mut scanned_up_to: Option<Vec<u8>> = None;
+    for stored in stored_statuses(status) {
+        let bucket = status_index.prefix(U8Key::new(*stored as u8)).range(
+            store,
+            min.clone(),
+            max.clone(),
+            order,
+        );
+        let mut found = 0;
+        let mut last: Option<Vec<u8>> = None;
+        for (scanned, item) in bucket.enumerate() {
+            let (key, prop) = item?;
+            if scanned == MAX_SCANNED {
+                scanned_up_to = match (scanned_up_to, last) {
+                    (Some(up_to), Some(last))
+                        if cmp_keys(order, &up_to, &last) == Ordering::Less =>
+                    {
+                        Some(up_to)
+                    }
+                    (up_to, last) => last.or(up_to),
+                };
+                break;
+            }
+            last = Some(key.clone());
+            if current_status(&prop) == status {
+                props.push((key, prop));
+                found += 1;
+                // one more than the page, to know if there is a next one
+                if found > limit {
+                    break;
+                }
+            }
+        }
+    }
+    props.sort_by(|(a, _), (b, _)| cmp_keys(order, a, b));
+    if let Some(up_to) = scanned_up_to.as_ref() {
+        props.retain(|(key, _)| cmp_keys(order, key, up_to) != Ordering::Greater);
+    }
+    page(props, limit, scanned_up_to)
+}
+
+/// index_proposals re-saves all proposals, so that the status index has the ones that were
+/// stored before it was used. Meant for migrate, it goes through all of them at once.
+/// Returns how many proposals there are.
+pub fn index_proposals<'a, T, I>(
+    store: &mut dyn Storage,
+    proposals: &IndexedMap<'a, U64Key, T, I>,
+) -> StdResult<usize>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    I: IndexList<T>,
+{
+    let props = proposals
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, prop) in props.iter() {
+        // removing the (missing) old index entry is a no-op, so this only adds the new one
+        proposals.save(store, parse_id(key)?.into(), prop)?;
+    }
+    Ok(props.len())
+}
+
+// compares the keys in the order of the range
+fn cmp_keys(order: Order, a: &[u8], b: &[u8]) -> Ordering {
+    match order {
+        Order::Ascending => a.cmp(b),
+        Order::Descending => b.cmp(a),
+    }
+}
+
+// cuts props to one page, returning the id to continue after: the last one on the page if
+// there are more props, or scanned_up_to if given
+fn page<T>(
+    mut props: Vec<Pair<T>>,
+    limit: usize,
+    scanned_up_to: Option<Vec<u8>>,
+) -> StdResult<(Vec<Pair<T>>, Option<u64>)> {
+    let next = if props.len() > limit {
+        props.truncate(limit);
+        props.last().map(|(key, _)| key.clone())
+    } else {
+        scanned_up_to
+    };
+    Ok((props, next.map(|key| parse_id(&key)).transpose()?))
+}
+
+fn parse_id(key: &[u8]) -> StdResult<u64> {
+    let mut bytes = [0u8; 8];
+    if key.len() != 8 {
+        return Err(StdError::invalid_data_size(8, key.len()));
+    }
+    bytes.copy_from_slice(key);
+    Ok(u64::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cw_storage_plus::{Index, Map};
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+    struct Prop {
+        stored: Status,
+        current: Status,
+    }
+
+    struct PropIndexes<'a> {
+        status: MultiIndex<'a, U8Key, Prop>,
+    }
+
+    impl<'a> IndexList<Prop> for PropIndexes<'a> {
+        fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Prop>> + '_> {
+            let v: Vec<&dyn Index<Prop>> = vec![&self.status];
+            Box::new(v.into_iter())
+        }
+    }
+
+    fn props<'a>() -> IndexedMap<'a, U64Key, Prop, PropIndexes<'a>> {
+        let indexes = PropIndexes {
+            status: MultiIndex::new(|p| U8Key::new(p.stored as u8), "props", "props__status"),
+        };
+        IndexedMap::new("props", indexes)
+    }
+
+    fn save(store: &mut dyn Storage, id: u64, stored: Status, current: Status) {
+        props()
+            .save(store, id.into(), &Prop { stored, current })
+            .unwrap();
+    }
+
+    // returns the ids of the page and the next cursor
+    fn list(
+        store: &dyn Storage,
+        start_after: Option<u64>,
+        order: Order,
+        limit: usize,
+        status: Option<Status>,
+    ) -> (Vec<u64>, Option<u64>) {
+        let (min, max) = match order {
+            Order::Ascending => (start_after.map(Bound::exclusive_int), None),
+            Order::Descending => (None, start_after.map(Bound::exclusive_int)),
+        };
+        let props = props();
+        let (page, next) = range_proposals(
+            store,
+            &props,
+            &props.idx.status,
+            |p| p.current,
+            min,
+            max,
+            order,
+            limit,
+            status,
+        )
+        .unwrap();
+        let ids = page
+            .into_iter()
+            .map(|(key, _)| parse_id(&key).unwrap())
+            .collect();
+        (ids, next)
+    }
+
+    #[test]
+    fn pages_with_and_without_status() {
+        let mut store = MockStorage::new();
+        save(&mut store, 1, Status::Open, Status::Open);
+        save(&mut store, 2, Status::Open, Status::Passed);
+        save(&mut store, 3, Status::Passed, Status::Passed);
+        save(&mut store, 4, Status::Open, Status::Rejected);
+        save(&mut store, 5, Status::Passed, Status::Passed);
+
+        // all of them
+        let asc = Order::Ascending;
+        assert_eq!(list(&store, None, asc, 3, None), (vec![1, 2, 3], Some(3)));
+        assert_eq!(list(&store, Some(3), asc, 3, None), (vec![4, 5], None));
+        // a limit of 0 still makes progress
+        assert_eq!(list(&store, None, asc, 0, None), (vec![1], Some(1)));
+
+        // the passed ones are merged from both buckets
+        let passed = Some(Status::Passed);
+        assert_eq!(list(&store, None, asc, 2, passed), (vec![2, 3], Some(3)));
+        assert_eq!(list(&store, Some(3), asc, 2, passed), (vec![5], None));
+        let desc = Order::Descending;
+        assert_eq!(list(&store, None, desc, 2, passed), (vec![5, 3], Some(3)));
+        assert_eq!(list(&store, Some(3), desc, 2, passed), (vec![2], None));
+
+        let open = Some(Status::Open);
+        assert_eq!(list(&store, None, asc, 10, open), (vec![1], None));
+        let rejected = Some(Status::Rejected);
+        assert_eq!(list(&store, None, desc, 10, rejected), (vec![4], None));
+    }
+
+    #[test]
+    fn status_filter_scans_a_bounded_number_of_proposals() {
+        let mut store = MockStorage::new();
+        // 5 and 251 were stored as passed, 240 passed while stored as open
+        for id in 1..=250 {
+            match id {
+                5 => save(&mut store, id, Status::Passed, Status::Passed),
+                240 => save(&mut store, id, Status::Open, Status::Passed),
+                _ => save(&mut store, id, Status::Open, Status::Open),
+            }
+        }
+        save(&mut store, 251, Status::Passed, Status::Passed);
+        let passed = Some(Status::Passed);
+
+        // the first page looks at 100 open ones (up to 101), so only contains 5
+        let asc = Order::Ascending;
+        assert_eq!(list(&store, None, asc, 10, passed), (vec![5], Some(101)));
+        // the next one has none, but goes on
+        assert_eq!(
+            list(&store, Some(101), asc, 10, passed),
+            (vec![], Some(201))
+        );
+        assert_eq!(
+            list(&store, Some(201), asc, 10, passed),
+            (vec![240, 251], None)
+        );
+
+        // the same backwards
+        let desc = Order::Descending;
+        assert_eq!(
+            list(&store, None, desc, 10, passed),
+            (vec![251, 240], Some(151))
+        );
+        assert_eq!(
+            list(&store, Some(151), desc, 10, passed),
+            (vec![], Some(51))
+        );
+        assert_eq!(list(&store, Some(51), desc, 10, passed), (vec![5], None));
+
+        // a full page ends at its last proposal, even if the scan went further
+        assert_eq!(
+            list(&store, Some(201), asc, 1, passed),
+            (vec![240], Some(240))
+        );
+        assert_eq!(list(&store, Some(240), asc, 1, passed), (vec![251], None));
+    }
+
+    #[test]
+    fn index_proposals_backfills_the_status_index() {
+        let mut store = MockStorage::new();
+        // stored without the index, as before it was added
+        let old: Map<U64Key, Prop> = Map::new("props");
+        for id in 1..=3 {
+            let prop = Prop {
+                stored: Status::Passed,
+                current: Status::Passed,
+            };
+            old.save(&mut store, id.into(), &prop).unwrap();
+        }
+        save(&mut store, 4, Status::Passed, Status::Passed);
+        let passed = Some(Status::Passed);
+        let asc = Order::Ascending;
+        assert_eq!(list(&store, None, asc, 10, passed), (vec![4], None));
+
+        assert_eq!(index_proposals(&mut store, &props()).unwrap(), 4);
+        assert_eq!(
+            list(&store, None, asc, 10, passed),
+            (vec![1, 2, 3, 4], None)
+        );
+        // and nothing is listed twice when running it again
+        assert_eq!(index_proposals(&mut store, &props()).unwrap(), 4);
+        assert_eq!(
+            list(&store, None, asc, 10, passed),
+            (vec![1, 2, 3, 4], None)
+        );
+    }
+}
@@ -21,12 +21,18 @@ pub enum Cw3QueryMsg {
     ListProposals {
         start_after: Option<u64>,
         limit: Option<u32>,
+        /// If set, only proposals with this status (as returned in ProposalResponse) are listed.
+        /// Pagination works the same way, with the last id of a page as the next cursor.
+        status: Option<Status>,
     },
     /// Iterate reverse over details of all proposals, this is useful to easily query
     /// only the most recent proposals (to get updates). Returns ProposalListResponse
     ReverseProposals {
         start_before: Option<u64>,
         limit: Option<u32>,
+        /// If set, only proposals with this status (as returned in ProposalResponse) are listed.
+        /// Pagination works the same way, with the last id of a page as the next cursor.
+        status: Option<Status>,
     },
     /// Query the vote made by the given voter on `proposal_id`. This should
     /// return an error if there is no such proposal. It will return a None value
@@ -40,6 +46,13 @@ pub enum Cw3QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Iterate reverse over all votes for this proposal, in the opposite order of ListVotes.
+    /// Returns VoteListResponse
+    ReverseVotes {
+        proposal_id: u64,
+        start_before: Option<String>,
+        limit: Option<u32>,
+    },
     /// Voter extension: Returns VoterResponse
     Voter { address: String },
     /// ListVoters extension: Returns VoterListResponse
@@ -130,6 +143,9 @@ where
     pub title: String,
     pub description: String,
     pub msgs: Vec<CosmosMsg<T>>,
+    /// Signaling if there are no msgs. Defaults to Executable for contracts that don't set it
+    #[serde(default)]
+    pub kind: ProposalKind,
     pub status: Status,
     pub expires: Expiration,
     /// This is the threshold that is applied to this proposal. Both the rules of the voting contract,
@@ -138,6 +154,38 @@ where
     pub threshold: ThresholdResponse,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalKind {
+    /// the msgs are dispatched when the proposal is executed
+    Executable,
+    /// there are no msgs, the proposal only records the outcome of the vote.
+    /// It cannot be executed, once it passed and expired it is closed as Done
+    Signaling,
+}
+
+// deriving Default for enums needs a newer rust than we support
+#[allow(clippy::derivable_impls)]
+impl Default for ProposalKind {
+    fn default() -> Self {
+        ProposalKind::Executable
+    }
+}
+
+impl ProposalKind {
+    /// Proposals without msgs are Signaling, all others Executable
+    pub fn from_msgs<T>(msgs: &[CosmosMsg<T>]) -> Self
+    where
+        T: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        if msgs.is_empty() {
+            ProposalKind::Signaling
+        } else {
+            ProposalKind::Executable
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "lowercase")]
 #[repr(u8)]
@@ -152,11 +200,19 @@ pub enum Status {
     Passed = 4,
     /// voting is over it passed, and the proposal was executed
     Executed = 5,
+    /// voting is over it passed, and the signaling proposal was closed, as there is
+    /// nothing to execute
+    Done = 6,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct ProposalListResponse {
     pub proposals: Vec<ProposalResponse>,
+    /// The id to pass as start_after (or start_before) for the next page, None if there are
+    /// no more proposals. With a status filter, a page may hold less than limit proposals (or
+    /// none) while there are more to come, so use this rather than the last id listed.
+    #[serde(default)]
+    pub next: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -0,0 +1,109 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
+use cw_storage_plus::{Bound, Map, U64Key};
+
+use crate::msg::Vote;
+use crate::query::VoteInfo;
+
+/// Ballot is a vote cast on a proposal, with the weight of the voter.
+/// Stored under the proposal id and the voter's address
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Ballot {
+    pub weight: u64,
+    pub vote: Vote,
+}
+
+/// range_votes returns (at most) limit votes on the given proposal, between the voters
+/// min and max, in the given order.
+pub fn range_votes<'a>(
+    store: &dyn Storage,
+    ballots: &Map<'a, (U64Key, &'a Addr), Ballot>,
+    proposal_id: u64,
+    min: Option<Bound>,
+    max: Option<Bound>,
+    order: Order,
+    limit: usize,
+) -> StdResult<Vec<VoteInfo>> {
+    ballots
+        .prefix(proposal_id.into())
+        .range(store, min, max, order)
+        .take(limit)
+        .map(map_vote)
+        .collect()
+}
+
+fn map_vote(item: StdResult<(Vec<u8>, Ballot)>) -> StdResult<VoteInfo> {
+    let (voter, ballot) = item?;
+    Ok(VoteInfo {
+        voter: String::from_utf8(voter)?,
+        vote: ballot.vote,
+        weight: ballot.weight,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const BALLOTS: Map<(U64Key, &Addr), Ballot> = Map::new("ballots");
+
+    #[test]
+    fn pagination_cursors_are_exclusive() {
+        let mut store = MockStorage::new();
+        for (weight, voter) in ["voter1", "voter2", "voter3", "voter4"].iter().enumerate() {
+            let ballot = Ballot {
+                weight: weight as u64 + 1,
+                vote: Vote::Yes,
+            };
+            let key = (1.into(), &Addr::unchecked(*voter));
+            BALLOTS.save(&mut store, key, &ballot).unwrap();
+        }
+        // another proposal, that is not listed
+        let key = (2.into(), &Addr::unchecked("voter0"));
+        let ballot = Ballot {
+            weight: 5,
+            vote: Vote::No,
+        };
+        BALLOTS.save(&mut store, key, &ballot).unwrap();
+
+        let voters = |min: Option<&str>, max: Option<&str>, order, limit| -> Vec<String> {
+            let min = min.map(|v| Bound::exclusive(v.as_bytes()));
+            let max = max.map(|v| Bound::exclusive(v.as_bytes()));
+            range_votes(&store, &BALLOTS, 1, min, max, order, limit)
+                .unwrap()
+                .into_iter()
+                .map(|v| v.voter)
+                .collect()
+        };
+        let asc = Order::Ascending;
+        assert_eq!(voters(None, None, asc, 2), vec!["voter1", "voter2"]);
+        // the next page starts right after the last voter
+        assert_eq!(
+            voters(Some("voter2"), None, asc, 2),
+            vec!["voter3", "voter4"]
+        );
+        assert_eq!(voters(Some("voter4"), None, asc, 2), Vec::<String>::new());
+
+        let desc = Order::Descending;
+        assert_eq!(voters(None, None, desc, 2), vec!["voter4", "voter3"]);
+        // the next page starts right before the last voter
+        assert_eq!(
+            voters(None, Some("voter3"), desc, 2),
+            vec!["voter2", "voter1"]
+        );
+        assert_eq!(voters(None, Some("voter1"), desc, 2), Vec::<String>::new());
+
+        let votes = range_votes(&store, &BALLOTS, 2, None, None, desc, 10).unwrap();
+        assert_eq!(
+            votes,
+            vec![VoteInfo {
+                voter: "voter0".to_string(),
+                vote: Vote::No,
+                weight: 5,
+            }]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::cmp::Ordering;
+
+use thiserror::Error;
+
+use cosmwasm_std::BlockInfo;
+use cw0::{Duration, Expiration};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum VotingPeriodError {
+    #[error("Wrong expiration option")]
+    WrongExpiration {},
+
+    #[error(
+        "Min voting period must use the same unit as the max voting period, and not exceed it"
+    )]
+    WrongMinVotingPeriod {},
+
+    #[error("Voting period must be at least {min}")]
+    VotingPeriodTooShort { min: Duration },
+}
+
+/// validate_min_voting_period checks that a min_voting_period can be combined with the
+/// max_voting_period: it is in the same unit (height or time), and not longer.
+pub fn validate_min_voting_period(min: Duration, max: Duration) -> Result<(), VotingPeriodError> {
+    match (min, max) {
+        (Duration::Height(min), Duration::Height(max)) if min <= max => Ok(()),
+        (Duration::Time(min), Duration::Time(max)) if min <= max => Ok(()),
+        _ => Err(VotingPeriodError::WrongMinVotingPeriod {}),
+    }
+}
+
+/// proposal_expiration returns when a proposal created in this block expires: after its
+/// voting_period (max_voting_period if not set) or at latest, whichever comes first, and
+/// never later than max_voting_period allows.
+/// Returns an error if this leaves less than min_voting_period, or if the voting_period or
+/// latest are in another unit than max_voting_period.
+pub fn proposal_expiration(
+    block: &BlockInfo,
+    max_voting_period: Duration,
+    min_voting_period: Option<Duration>,
+    voting_period: Option<Duration>,
+    latest: Option<Expiration>,
+) -> Result<Expiration, VotingPeriodError> {
+    // max expires also used as default
+    let mut expires = max_voting_period.after(block);
+    let limits = [voting_period.map(|period| period.after(block)), latest];
+    for limit in limits.iter().flatten() {
+        match limit.partial_cmp(&expires) {
+            Some(Ordering::Less) => expires = *limit,
+            Some(_) => {}
+            None => return Err(VotingPeriodError::WrongExpiration {}),
+        }
+    }
+    if let Some(min) = min_voting_period {
+        match expires.partial_cmp(&min.after(block)) {
+            Some(Ordering::Less) => return Err(VotingPeriodError::VotingPeriodTooShort { min }),
+            Some(_) => {}
+            None => return Err(VotingPeriodError::WrongExpiration {}),
+        }
+    }
+    Ok(expires)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_env;
+
+    #[test]
+    fn min_voting_period_matches_max() {
+        let max = Duration::Height(1000);
+        validate_min_voting_period(Duration::Height(10), max).unwrap();
+        // the min may be the same as the max
+        validate_min_voting_period(max, max).unwrap();
+
+        // but not longer, or in another unit
+        for (max, min) in &[
+            (Duration::Height(1000), Duration::Height(1001)),
+            (Duration::Height(1000), Duration::Time(100)),
+            (Duration::Time(1000), Duration::Height(10)),
+            (Duration::Time(1000), Duration::Time(1001)),
+        ] {
+            let err = validate_min_voting_period(*min, *max).unwrap_err();
+            assert_eq!(err, VotingPeriodError::WrongMinVotingPeriod {});
+        }
+    }
+
+    #[test]
+    fn expiration_is_limited_by_period_and_latest() {
+        let block = mock_env().block;
+        let height = block.height;
+        let max = Duration::Height(1000);
+        let min = Some(Duration::Height(10));
+        let expires =
+            |voting_period, latest| proposal_expiration(&block, max, min, voting_period, latest);
+
+        // defaults to the max, longer periods are clamped to it
+        assert_eq!(expires(None, None), Ok(Expiration::AtHeight(height + 1000)));
+        assert_eq!(
+            expires(Some(Duration::Height(5000)), None),
+            Ok(Expiration::AtHeight(height + 1000))
+        );
+        // the earlier of the period and latest applies
+        let at = |h| Some(Expiration::AtHeight(height + h));
+        let period = Some(Duration::Height(50));
+        assert_eq!(
+            expires(period, at(20)),
+            Ok(Expiration::AtHeight(height + 20))
+        );
+        assert_eq!(
+            expires(period, at(80)),
+            Ok(Expiration::AtHeight(height + 50))
+        );
+        assert_eq!(
+            expires(period, Some(Expiration::Never {})),
+            Ok(Expiration::AtHeight(height + 50))
+        );
+
+        // exactly the min is fine, anything shorter is not, also when set by latest
+        assert_eq!(expires(min, None), Ok(Expiration::AtHeight(height + 10)));
+        let too_short = Err(VotingPeriodError::VotingPeriodTooShort {
+            min: Duration::Height(10),
+        });
+        assert_eq!(expires(Some(Duration::Height(9)), None), too_short);
+        assert_eq!(expires(None, at(5)), too_short);
+
+        // blocks and seconds cannot be compared
+        let wrong = Err(VotingPeriodError::WrongExpiration {});
+        assert_eq!(expires(Some(Duration::Time(500)), None), wrong);
+        let at_time = Some(Expiration::AtTime(block.time.plus_seconds(100)));
+        assert_eq!(expires(None, at_time), wrong);
+    }
+}
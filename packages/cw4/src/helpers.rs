@@ -9,7 +9,8 @@ use cosmwasm_std::{
 use crate::msg::Cw4ExecuteMsg;
 use crate::query::HooksResponse;
 use crate::{
-    member_key, AdminResponse, Cw4QueryMsg, Member, MemberListResponse, MemberResponse, TOTAL_KEY,
+    member_key, AdminResponse, Cw4QueryMsg, Member, MemberListResponse, MemberResponse,
+    TotalWeightResponse, TOTAL_KEY,
 };
 
 /// Cw4Contract is a wrapper around Addr that provides a lot of helpers
@@ -83,6 +84,15 @@ impl Cw4Contract {
         querier.query(&query)
     }
 
+    /// Return the total weight at the given snapshot - requires a smart query
+    pub fn total_weight_at_height(&self, querier: &QuerierWrapper, height: u64) -> StdResult<u64> {
+        let query = self.encode_smart_query(Cw4QueryMsg::TotalWeight {
+            at_height: Some(height),
+        })?;
+        let res: TotalWeightResponse = querier.query(&query)?;
+        Ok(res.weight)
+    }
+
     /// Check if this address is a member, and if so, with which weight
     pub fn is_member(&self, querier: &QuerierWrapper, addr: &Addr) -> StdResult<Option<u64>> {
         let path = member_key(addr.as_ref());
@@ -9,7 +9,7 @@ pub use crate::msg::Cw4ExecuteMsg;
 pub use crate::query::{
     member_key, AdminResponse, Cw4QueryMsg, HooksResponse, Member, MemberListResponse,
     MemberResponse, TotalWeightResponse, MEMBERS_CHANGELOG, MEMBERS_CHECKPOINTS, MEMBERS_KEY,
-    TOTAL_KEY,
+    TOTAL_KEY, TOTAL_KEY_CHANGELOG, TOTAL_KEY_CHECKPOINTS,
 };
 
 #[cfg(test)]
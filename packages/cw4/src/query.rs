@@ -6,8 +6,9 @@ use serde::{Deserialize, Serialize};
 pub enum Cw4QueryMsg {
     /// Return AdminResponse
     Admin {},
-    /// Return TotalWeightResponse
-    TotalWeight {},
+    /// Return TotalWeightResponse. If at_height is set, this is the total weight at the
+    /// beginning of that block, like the member weights at that height
+    TotalWeight { at_height: Option<u64> },
     /// Returns MembersListResponse
     ListMembers {
         start_after: Option<String>,
@@ -58,6 +59,8 @@ pub struct HooksResponse {
 
 /// TOTAL_KEY is meant for raw queries
 pub const TOTAL_KEY: &str = "total";
+pub const TOTAL_KEY_CHECKPOINTS: &str = "total__checkpoints";
+pub const TOTAL_KEY_CHANGELOG: &str = "total__changelog";
 pub const MEMBERS_KEY: &str = "members";
 pub const MEMBERS_CHECKPOINTS: &str = "members__checkpoints";
 pub const MEMBERS_CHANGELOG: &str = "members__changelog";
@@ -5,7 +5,8 @@ use cosmwasm_std::testing::{mock_env, MockApi};
 use cosmwasm_std::{
     from_slice, to_binary, to_vec, Addr, Api, Attribute, BankMsg, Binary, BlockInfo, Coin,
     ContractResult, CosmosMsg, Empty, Event, MessageInfo, Querier, QuerierResult, QuerierWrapper,
-    QueryRequest, ReplyOn, Response, SubMsg, SystemError, SystemResult, WasmMsg,
+    QueryRequest, Reply, ReplyOn, Response, SubMsg, SubMsgExecutionResponse, SystemError,
+    SystemResult, WasmMsg,
 };
 
 use crate::bank::{Bank, BankCache, BankOps, BankRouter};
@@ -276,30 +277,58 @@ where
     /// For normal use cases, you can use Router::execute() or Router::execute_multi().
     /// This is designed to be handled internally as part of larger process flows.
     fn execute(&mut self, sender: Addr, msg: SubMsg<C>) -> Result<AppResponse, String> {
-        // TODO: actually handle reply semantics
-        if msg.reply_on != ReplyOn::Never {
-            unimplemented!();
+        let SubMsg {
+            id, msg, reply_on, ..
+        } = msg;
+        if reply_on == ReplyOn::Never {
+            return self.execute_msg(sender, msg);
         }
-        match msg.msg {
-            CosmosMsg::Wasm(msg) => {
-                let (resender, res) = self.execute_wasm(sender, msg)?;
+
+        // a failing submessage is rolled back, but doesn't fail the sender if it handles the reply
+        let checkpoint = self.checkpoint();
+        match self.execute_msg(sender.clone(), msg) {
+            Ok(res) if reply_on == ReplyOn::Success || reply_on == ReplyOn::Always => {
+                let reply = Reply {
+                    id,
+                    result: ContractResult::Ok(SubMsgExecutionResponse {
+                        events: res.events.clone(),
+                        data: res.data.clone(),
+                    }),
+                };
+                let reply_res = self.reply(sender, reply)?;
                 let mut attributes = res.attributes;
+                attributes.extend(reply_res.attributes);
                 let mut events = res.events;
-                // recurse in all messages
-                for resend in res.messages {
-                    let subres = self.execute(resender.clone(), resend)?;
-                    // ignore the data now, just like in wasmd
-                    // append the events
-                    // TODO: is this correct for attributes??? Or do we turn them into sub-events?
-                    attributes.extend_from_slice(&subres.attributes);
-                    events.extend_from_slice(&subres.events);
-                }
+                events.extend(reply_res.events);
                 Ok(AppResponse {
                     attributes,
                     events,
-                    data: res.data,
+                    // the reply only overrides the data if it sets some
+                    data: reply_res.data.or(res.data),
                 })
             }
+            Ok(res) => Ok(res),
+            Err(err) => {
+                self.rollback(checkpoint);
+                if reply_on == ReplyOn::Error || reply_on == ReplyOn::Always {
+                    let reply = Reply {
+                        id,
+                        result: ContractResult::Err(err),
+                    };
+                    self.reply(sender, reply)
+                } else {
+                    Err(err)
+                }
+            }
+        }
+    }
+
+    fn execute_msg(&mut self, sender: Addr, msg: CosmosMsg<C>) -> Result<AppResponse, String> {
+        match msg {
+            CosmosMsg::Wasm(msg) => {
+                let (resender, res) = self.execute_wasm(sender, msg)?;
+                self.process_response(resender, res)
+            }
             CosmosMsg::Bank(msg) => {
                 self.bank.execute(sender, msg)?;
                 Ok(AppResponse::default())
@@ -310,6 +339,20 @@ where
 
     fn sudo(&mut self, contract_addr: Addr, msg: Vec<u8>) -> Result<AppResponse, String> {
         let res = self.wasm.sudo(contract_addr.clone(), self.router, msg)?;
+        self.process_response(contract_addr, res)
+    }
+
+    fn reply(&mut self, contract_addr: Addr, reply: Reply) -> Result<AppResponse, String> {
+        let res = self.wasm.reply(contract_addr.clone(), self.router, reply)?;
+        self.process_response(contract_addr, res)
+    }
+
+    // this executes all messages sent by the contract, collecting their attributes and events
+    fn process_response(
+        &mut self,
+        contract_addr: Addr,
+        res: Response<C>,
+    ) -> Result<AppResponse, String> {
         let mut attributes = res.attributes;
         let mut events = res.events;
         // recurse in all messages
@@ -328,6 +371,16 @@ where
         })
     }
 
+    // the point to roll back to, if a submessage fails
+    fn checkpoint(&self) -> (usize, usize) {
+        (self.wasm.checkpoint(), self.bank.checkpoint())
+    }
+
+    fn rollback(&mut self, (wasm, bank): (usize, usize)) {
+        self.wasm.rollback(wasm);
+        self.bank.rollback(bank);
+    }
+
     // this returns the contract address as well, so we can properly resend the data
     fn execute_wasm(&mut self, sender: Addr, msg: WasmMsg) -> Result<(Addr, Response<C>), String> {
         match msg {
@@ -658,6 +711,147 @@ mod test {
         assert_eq!(2, qres.count);
     }
 
+    #[test]
+    fn reply_without_data_keeps_the_submessage_data() {
+        let mut router = custom_router();
+
+        let owner = Addr::unchecked("owner");
+        let reflect_id = router.store_code(contract_reflect());
+        let reflect_addr = router
+            .instantiate_contract(reflect_id, owner.clone(), &EmptyMsg {}, &[], "Reflect")
+            .unwrap();
+        let inner_addr = router
+            .instantiate_contract(reflect_id, owner, &EmptyMsg {}, &[], "Inner")
+            .unwrap();
+
+        // the reply of the reflect contract sets no data, so the count of inner is returned
+        let msg = SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: inner_addr.into(),
+                msg: to_binary(&ReflectMessage { messages: vec![] }).unwrap(),
+                funds: vec![],
+            },
+            7,
+        );
+        let res = router.execute(reflect_addr, msg).unwrap();
+        assert_eq!(res.attributes[1], attr("id", "7"));
+        assert_eq!(res.data, Some(to_binary(&2u32).unwrap()));
+    }
+
+    #[test]
+    fn reflect_submessage_reply() {
+        let mut router = custom_router();
+
+        let owner = Addr::unchecked("owner");
+        router.set_bank_balance(&owner, coins(100, "eth")).unwrap();
+        let reflect_id = router.store_code(contract_reflect());
+        let reflect_addr = router
+            .instantiate_contract(
+                reflect_id,
+                owner.clone(),
+                &EmptyMsg {},
+                &coins(40, "eth"),
+                "Reflect",
+            )
+            .unwrap();
+        let inner_addr = router
+            .instantiate_contract(reflect_id, owner, &EmptyMsg {}, &[], "Inner")
+            .unwrap();
+        let random = Addr::unchecked("random");
+
+        // the inner contract gets 5 eth and updates its count, but cannot pay out btc
+        let inner_msgs = ReflectMessage {
+            messages: vec![SubMsg::new(BankMsg::Send {
+                to_address: random.clone().into(),
+                amount: coins(3, "btc"),
+            })],
+        };
+        let msg = SubMsg::reply_on_error(
+            WasmMsg::Execute {
+                contract_addr: inner_addr.clone().into(),
+                msg: to_binary(&inner_msgs).unwrap(),
+                funds: coins(5, "eth"),
+            },
+            123,
+        );
+        let msgs = ReflectMessage {
+            messages: vec![msg],
+        };
+        let res = router
+            .execute_contract(random.clone(), reflect_addr.clone(), &msgs, &[])
+            .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "reply"),
+                attr("id", "123"),
+                attr("result", "error: Overflow: Cannot Sub with 0 and 3"),
+            ]
+        );
+
+        // the outer contract call succeeded
+        let qres: ReflectResponse = router
+            .wrap()
+            .query_wasm_smart(&reflect_addr, &EmptyMsg {})
+            .unwrap();
+        assert_eq!(2, qres.count);
+        // but all changes of the failed submessage were rolled back
+        let qres: ReflectResponse = router
+            .wrap()
+            .query_wasm_smart(&inner_addr, &EmptyMsg {})
+            .unwrap();
+        assert_eq!(1, qres.count);
+        assert_eq!(get_balance(&router, &reflect_addr), coins(40, "eth"));
+        assert_eq!(get_balance(&router, &inner_addr), vec![]);
+
+        // successful submessages only reply if asked to
+        let msg = SubMsg::reply_on_error(
+            BankMsg::Send {
+                to_address: random.clone().into(),
+                amount: coins(7, "eth"),
+            },
+            1,
+        );
+        let msg2 = SubMsg::reply_on_success(
+            BankMsg::Send {
+                to_address: random.clone().into(),
+                amount: coins(3, "eth"),
+            },
+            2,
+        );
+        let msgs = ReflectMessage {
+            messages: vec![msg, msg2],
+        };
+        let res = router
+            .execute_contract(random.clone(), reflect_addr.clone(), &msgs, &[])
+            .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "reply"),
+                attr("id", "2"),
+                attr("result", "ok, 0 events"),
+            ]
+        );
+        assert_eq!(get_balance(&router, &random), coins(10, "eth"));
+
+        // without a reply, a failing submessage still fails the whole call
+        let msg = SubMsg::reply_on_success(
+            BankMsg::Send {
+                to_address: random.clone().into(),
+                amount: coins(3, "btc"),
+            },
+            3,
+        );
+        let msgs = ReflectMessage {
+            messages: vec![msg],
+        };
+        router
+            .execute_contract(random.clone(), reflect_addr.clone(), &msgs, &[])
+            .unwrap_err();
+        assert_eq!(get_balance(&router, &random), coins(10, "eth"));
+    }
+
     #[test]
     fn sudo_works() {
         let mut router = custom_router();
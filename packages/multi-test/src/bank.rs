@@ -3,9 +3,11 @@ use cosmwasm_std::{
     Storage,
 };
 
-use crate::transactions::{RepLog, StorageTransaction};
 use cw0::NativeBalance;
 use cw_storage_plus::Map;
+use cw_storage_plus::{RepLog, StorageTransaction};
+
+use crate::transactions::{undo, JournaledStorage, Undo};
 
 const BALANCES: Map<&Addr, NativeBalance> = Map::new("balances");
 
@@ -59,6 +61,8 @@ pub struct BankCache<'a> {
     // and this into one with reference
     router: &'a BankRouter,
     state: StorageTransaction<'a>,
+    // the previous values of all writes, to roll back to a checkpoint
+    journal: Vec<Undo>,
 }
 
 pub struct BankOps(RepLog);
@@ -74,6 +78,7 @@ impl<'a> BankCache<'a> {
         BankCache {
             router,
             state: StorageTransaction::new(router.storage.as_ref()),
+            journal: vec![],
         }
     }
 
@@ -85,7 +90,21 @@ impl<'a> BankCache<'a> {
     }
 
     pub fn execute(&mut self, sender: Addr, msg: BankMsg) -> Result<(), String> {
-        self.router.bank.execute(&mut self.state, sender, msg)
+        let mut storage = JournaledStorage::new(&mut self.state, &mut self.journal);
+        self.router.bank.execute(&mut storage, sender, msg)
+    }
+
+    /// checkpoint returns a point to roll back to, undoing all later writes
+    pub fn checkpoint(&self) -> usize {
+        self.journal.len()
+    }
+
+    /// rollback undoes all writes made after the given checkpoint
+    pub fn rollback(&mut self, checkpoint: usize) {
+        while self.journal.len() > checkpoint {
+            let entry = self.journal.pop().unwrap();
+            undo(&mut self.state, entry);
+        }
     }
 }
 
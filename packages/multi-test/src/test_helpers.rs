@@ -4,8 +4,8 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 use cosmwasm_std::{
-    attr, to_binary, BankMsg, Binary, Coin, Deps, DepsMut, Empty, Env, MessageInfo, Response,
-    StdError, SubMsg,
+    attr, to_binary, BankMsg, Binary, Coin, ContractResult, Deps, DepsMut, Empty, Env, MessageInfo,
+    Reply, Response, StdError, SubMsg,
 };
 use cw_storage_plus::Item;
 
@@ -149,13 +149,14 @@ fn execute_reflect(
     _info: MessageInfo,
     msg: ReflectMessage,
 ) -> Result<Response<CustomMsg>, StdError> {
-    REFLECT.update::<_, StdError>(deps.storage, |old| Ok(old + 1))?;
+    let count = REFLECT.update::<_, StdError>(deps.storage, |old| Ok(old + 1))?;
 
+    // returns the new count as data
     let res = Response {
         messages: msg.messages,
         attributes: vec![],
         events: vec![],
-        data: None,
+        data: Some(to_binary(&count)?),
     };
     Ok(res)
 }
@@ -169,6 +170,21 @@ fn sudo_reflect(
     Ok(Response::default())
 }
 
+// reports the result of the submessage as attributes
+fn reply_reflect(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response<CustomMsg>, StdError> {
+    let result = match msg.result {
+        ContractResult::Ok(res) => attr("result", format!("ok, {} events", res.events.len())),
+        ContractResult::Err(err) => attr("result", format!("error: {}", err)),
+    };
+    let res = Response {
+        messages: vec![],
+        attributes: vec![attr("action", "reply"), attr("id", msg.id), result],
+        events: vec![],
+        data: None,
+    };
+    Ok(res)
+}
+
 fn query_reflect(deps: Deps, _env: Env, _msg: EmptyMsg) -> Result<Binary, StdError> {
     let count = REFLECT.load(deps.storage)?;
     let res = ReflectResponse { count };
@@ -181,6 +197,7 @@ pub fn contract_reflect() -> Box<dyn Contract<CustomMsg>> {
         instantiate_reflect,
         query_reflect,
         sudo_reflect,
-    );
+    )
+    .with_reply(reply_reflect);
     Box::new(contract)
 }
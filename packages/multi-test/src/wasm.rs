@@ -6,10 +6,12 @@ use std::ops::Deref;
 
 use cosmwasm_std::{
     from_slice, Addr, Api, Binary, BlockInfo, ContractInfo, CosmosMsg, Deps, DepsMut, Empty, Env,
-    MessageInfo, Querier, QuerierWrapper, Response, Storage, SubMsg, WasmQuery,
+    MessageInfo, Querier, QuerierWrapper, Reply, Response, Storage, SubMsg, WasmQuery,
 };
 
-use crate::transactions::{RepLog, StorageTransaction};
+use cw_storage_plus::{RepLog, StorageTransaction};
+
+use crate::transactions::{undo, JournaledStorage, Undo};
 
 /// Interface to call into a Contract
 pub trait Contract<T>
@@ -35,20 +37,28 @@ where
     fn sudo(&self, deps: DepsMut, env: Env, msg: Vec<u8>) -> Result<Response<T>, String>;
 
     fn query(&self, deps: Deps, env: Env, msg: Vec<u8>) -> Result<Binary, String>;
+
+    /// reply is called with the result of a submessage (sent with `ReplyOn` other than `Never`)
+    fn reply(&self, _deps: DepsMut, _env: Env, _msg: Reply) -> Result<Response<T>, String> {
+        Err("reply not implemented for contract".to_string())
+    }
 }
 
 type ContractFn<T, C, E> =
     fn(deps: DepsMut, env: Env, info: MessageInfo, msg: T) -> Result<Response<C>, E>;
 type SudoFn<T, C, E> = fn(deps: DepsMut, env: Env, msg: T) -> Result<Response<C>, E>;
 type QueryFn<T, E> = fn(deps: Deps, env: Env, msg: T) -> Result<Binary, E>;
+type ReplyFn<C, E> = fn(deps: DepsMut, env: Env, msg: Reply) -> Result<Response<C>, E>;
 
 type ContractClosure<T, C, E> = Box<dyn Fn(DepsMut, Env, MessageInfo, T) -> Result<Response<C>, E>>;
 type SudoClosure<T, C, E> = Box<dyn Fn(DepsMut, Env, T) -> Result<Response<C>, E>>;
 type QueryClosure<T, E> = Box<dyn Fn(Deps, Env, T) -> Result<Binary, E>>;
+type ReplyClosure<C, E> = Box<dyn Fn(DepsMut, Env, Reply) -> Result<Response<C>, E>>;
 
 /// Wraps the exported functions from a contract and provides the normalized format
-/// Place T4 and E4 at the end, as we just want default placeholders for most contracts that don't have sudo
-pub struct ContractWrapper<T1, T2, T3, E1, E2, E3, C = Empty, T4 = Empty, E4 = String>
+/// Place T4, E4 and E5 at the end, as we just want default placeholders for most contracts
+/// that don't have sudo or reply
+pub struct ContractWrapper<T1, T2, T3, E1, E2, E3, C = Empty, T4 = Empty, E4 = String, E5 = String>
 where
     T1: DeserializeOwned,
     T2: DeserializeOwned,
@@ -58,12 +68,14 @@ where
     E2: ToString,
     E3: ToString,
     E4: ToString,
+    E5: ToString,
     C: Clone + fmt::Debug + PartialEq + JsonSchema,
 {
     execute_fn: ContractClosure<T1, C, E1>,
     instantiate_fn: ContractClosure<T2, C, E2>,
     query_fn: QueryClosure<T3, E3>,
     sudo_fn: Option<SudoClosure<T4, C, E4>>,
+    reply_fn: Option<ReplyClosure<C, E5>>,
 }
 
 impl<T1, T2, T3, E1, E2, E3, C> ContractWrapper<T1, T2, T3, E1, E2, E3, C>
@@ -86,6 +98,7 @@ where
             instantiate_fn: Box::new(instantiate_fn),
             query_fn: Box::new(query_fn),
             sudo_fn: None,
+            reply_fn: None,
         }
     }
 
@@ -101,6 +114,7 @@ where
             instantiate_fn: customize_fn(instantiate_fn),
             query_fn: Box::new(query_fn),
             sudo_fn: None,
+            reply_fn: None,
         }
     }
 }
@@ -128,6 +142,25 @@ where
             instantiate_fn: Box::new(instantiate_fn),
             query_fn: Box::new(query_fn),
             sudo_fn: Some(Box::new(sudo_fn)),
+            reply_fn: None,
+        }
+    }
+
+    /// with_reply adds the reply entry point of the contract, which is called with the
+    /// results of its submessages
+    pub fn with_reply<E5>(
+        self,
+        reply_fn: ReplyFn<C, E5>,
+    ) -> ContractWrapper<T1, T2, T3, E1, E2, E3, C, T4, E4, E5>
+    where
+        E5: ToString + 'static,
+    {
+        ContractWrapper {
+            execute_fn: self.execute_fn,
+            instantiate_fn: self.instantiate_fn,
+            query_fn: self.query_fn,
+            sudo_fn: self.sudo_fn,
+            reply_fn: Some(Box::new(reply_fn)),
         }
     }
 }
@@ -179,8 +212,8 @@ where
     }
 }
 
-impl<T1, T2, T3, E1, E2, E3, C, T4, E4> Contract<C>
-    for ContractWrapper<T1, T2, T3, E1, E2, E3, C, T4, E4>
+impl<T1, T2, T3, E1, E2, E3, C, T4, E4, E5> Contract<C>
+    for ContractWrapper<T1, T2, T3, E1, E2, E3, C, T4, E4, E5>
 where
     T1: DeserializeOwned,
     T2: DeserializeOwned,
@@ -190,6 +223,7 @@ where
     E2: ToString,
     E3: ToString,
     E4: ToString,
+    E5: ToString,
     C: Clone + fmt::Debug + PartialEq + JsonSchema,
 {
     fn execute(
@@ -231,6 +265,15 @@ where
         let res = (self.query_fn)(deps, env, msg);
         res.map_err(|e| e.to_string())
     }
+
+    // this returns an error if the contract doesn't implement reply
+    fn reply(&self, deps: DepsMut, env: Env, msg: Reply) -> Result<Response<C>, String> {
+        let res = match &self.reply_fn {
+            Some(reply) => reply(deps, env, msg),
+            None => return Err("reply not implemented for contract".to_string()),
+        };
+        res.map_err(|e| e.to_string())
+    }
 }
 
 struct ContractData {
@@ -400,6 +443,13 @@ where
 pub struct WasmCacheState<'a> {
     contracts: HashMap<Addr, ContractData>,
     contract_diffs: HashMap<Addr, StorageTransaction<'a>>,
+    // all changes in order, to roll back to a checkpoint
+    journal: Vec<JournalEntry>,
+}
+
+enum JournalEntry {
+    Created(Addr),
+    Write(Addr, Undo),
 }
 
 /// This is a set of data from the WasmCache with no external reference,
@@ -434,6 +484,7 @@ where
             state: WasmCacheState {
                 contracts: HashMap::new(),
                 contract_diffs: HashMap::new(),
+                journal: vec![],
             },
         }
     }
@@ -455,9 +506,34 @@ where
         let addr = self.next_address();
         let info = ContractData::new(code_id, (self.router.storage_factory)());
         self.state.contracts.insert(addr.clone(), info);
+        self.state.journal.push(JournalEntry::Created(addr.clone()));
         Ok(addr)
     }
 
+    /// checkpoint returns a point to roll back to, undoing all later changes
+    pub fn checkpoint(&self) -> usize {
+        self.state.journal.len()
+    }
+
+    /// rollback undoes all changes made after the given checkpoint, the writes to contract
+    /// storage as well as the contracts created since then
+    pub fn rollback(&mut self, checkpoint: usize) {
+        while self.state.journal.len() > checkpoint {
+            match self.state.journal.pop().unwrap() {
+                JournalEntry::Created(addr) => {
+                    self.state.contracts.remove(&addr);
+                }
+                JournalEntry::Write(addr, entry) => {
+                    if let Some((_, storage)) =
+                        self.state.get_contract(&self.router.contracts, &addr)
+                    {
+                        undo(storage, entry);
+                    }
+                }
+            }
+        }
+    }
+
     // TODO: better addr generation
     fn next_address(&self) -> Addr {
         let count = self.router.contracts.len() + self.state.contracts.len();
@@ -544,6 +620,32 @@ where
             },
         )
     }
+
+    pub fn reply(
+        &mut self,
+        address: Addr,
+        querier: &dyn Querier,
+        reply: Reply,
+    ) -> Result<Response<C>, String> {
+        let parent = &self.router.codes;
+        let contracts = &self.router.contracts;
+        let env = self.router.get_env(address.clone());
+        let api = self.router.api.as_ref();
+
+        self.state.with_storage(
+            querier,
+            contracts,
+            address,
+            env,
+            api,
+            |code_id, deps, env| {
+                let handler = parent
+                    .get(&code_id)
+                    .ok_or_else(|| "Unregistered code id".to_string())?;
+                handler.reply(deps, env, reply)
+            },
+        )
+    }
 }
 
 impl<'a> WasmCacheState<'a> {
@@ -596,15 +698,23 @@ impl<'a> WasmCacheState<'a> {
     where
         F: FnOnce(usize, DepsMut, Env) -> Result<T, String>,
     {
+        let mut writes = vec![];
         let (code_id, storage) = self
             .get_contract(parent, &address)
             .ok_or_else(|| "Unregistered contract address".to_string())?;
+        let mut storage = JournaledStorage::new(storage, &mut writes);
         let deps = DepsMut {
-            storage,
+            storage: &mut storage,
             api,
             querier: QuerierWrapper::new(querier),
         };
-        action(code_id, deps, env)
+        let res = action(code_id, deps, env);
+        self.journal.extend(
+            writes
+                .into_iter()
+                .map(|entry| JournalEntry::Write(address.clone(), entry)),
+        );
+        res
     }
 }
 
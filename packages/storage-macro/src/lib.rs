@@ -0,0 +1,126 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Type};
+
+/// Implements `cw_storage_plus::IndexList<T>` for a struct of indexes.
+///
+/// The stored type `T` is given with the `index_list` attribute. All the fields of the struct
+/// are returned by `get_indexes()`, in declaration order.
+///
+/// ```
+/// use cw_storage_plus::{IndexList, IndexedMap, MultiIndex, UniqueIndex, U32Key};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, Clone)]
+/// struct Data {
+///     pub name: String,
+///     pub age: u32,
+/// }
+///
+/// #[derive(cw_storage_macro::IndexList)]
+/// #[index_list(Data)]
+/// struct DataIndexes<'a> {
+///     pub name: MultiIndex<'a, Vec<u8>, Data>,
+///     pub age: UniqueIndex<'a, U32Key, Data>,
+/// }
+///
+/// let indexes = DataIndexes {
+///     name: MultiIndex::new(|d| d.name.as_bytes().to_vec(), "data", "data__name"),
+///     age: UniqueIndex::new(|d| U32Key::new(d.age), "data__age"),
+/// };
+/// assert_eq!(2, indexes.get_indexes().count());
+/// let map: IndexedMap<&[u8], Data, DataIndexes> = IndexedMap::new("data", indexes);
+/// ```
+///
+/// Fields that are not an `Index<T>` are rejected at compile time:
+///
+/// ```compile_fail
+/// use cw_storage_plus::{MultiIndex};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, Clone)]
+/// struct Data {
+///     pub name: String,
+/// }
+///
+/// #[derive(cw_storage_macro::IndexList)]
+/// #[index_list(Data)]
+/// struct DataIndexes<'a> {
+///     pub name: MultiIndex<'a, Vec<u8>, Data>,
+///     pub count: u32,
+/// }
+/// ```
+#[proc_macro_derive(IndexList, attributes(index_list))]
+pub fn derive_index_list(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_index_list(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_index_list(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ty = stored_type(&input)?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "IndexList can only be derived for structs",
+            ))
+        }
+    };
+    let indexes = match fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|f| {
+                let name = &f.ident;
+                quote_spanned! {f.ty.span()=> &self.#name}
+            })
+            .collect::<Vec<_>>(),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let index = syn::Index::from(i);
+                quote_spanned! {f.ty.span()=> &self.#index}
+            })
+            .collect::<Vec<_>>(),
+        Fields::Unit => vec![],
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::cw_storage_plus::IndexList<#ty> for #name #ty_generics #where_clause {
+            fn get_indexes(
+                &'_ self,
+            ) -> Box<dyn Iterator<Item = &'_ dyn ::cw_storage_plus::Index<#ty>> + '_> {
+                let v: Vec<&dyn ::cw_storage_plus::Index<#ty>> = vec![#(#indexes),*];
+                Box::new(v.into_iter())
+            }
+        }
+    })
+}
+
+/// Reads the type given in `#[index_list(T)]`
+fn stored_type(input: &DeriveInput) -> syn::Result<Type> {
+    let mut attrs = input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("index_list"));
+    let attr = attrs.next().ok_or_else(|| {
+        Error::new(
+            input.ident.span(),
+            "missing #[index_list(T)] attribute, with the type stored in the IndexedMap",
+        )
+    })?;
+    if let Some(dup) = attrs.next() {
+        return Err(Error::new(dup.span(), "duplicate #[index_list] attribute"));
+    }
+    attr.parse_args()
+}
@@ -0,0 +1,79 @@
+use cosmwasm_std::testing::MockStorage;
+use cosmwasm_std::Order;
+use cw_storage_macro::IndexList;
+use cw_storage_plus::{IndexList as _, IndexedMap, MultiIndex, U32Key, UniqueIndex};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+struct Data {
+    pub name: String,
+    pub age: u32,
+}
+
+#[derive(IndexList)]
+#[index_list(Data)]
+struct DataIndexes<'a> {
+    pub name: MultiIndex<'a, Vec<u8>, Data>,
+    pub age: UniqueIndex<'a, U32Key, Data>,
+}
+
+#[derive(IndexList)]
+#[index_list(Data)]
+struct TupleIndexes<'a>(MultiIndex<'a, Vec<u8>, Data>);
+
+fn build_map<'a>() -> IndexedMap<'a, &'a [u8], Data, DataIndexes<'a>> {
+    let indexes = DataIndexes {
+        name: MultiIndex::new(|d| d.name.as_bytes().to_vec(), "data", "data__name"),
+        age: UniqueIndex::new(|d| U32Key::new(d.age), "data__age"),
+    };
+    IndexedMap::new("data", indexes)
+}
+
+#[test]
+fn derive_returns_all_fields() {
+    let map = build_map();
+    assert_eq!(2, map.idx.get_indexes().count());
+
+    let indexes = TupleIndexes(MultiIndex::new(
+        |d| d.name.as_bytes().to_vec(),
+        "data",
+        "data__name",
+    ));
+    assert_eq!(1, indexes.get_indexes().count());
+}
+
+#[test]
+fn derived_indexes_are_updated() {
+    let mut store = MockStorage::new();
+    let map = build_map();
+
+    let maria = Data {
+        name: "Maria".to_string(),
+        age: 42,
+    };
+    map.save(&mut store, b"1", &maria).unwrap();
+    let john = Data {
+        name: "John".to_string(),
+        age: 32,
+    };
+    map.save(&mut store, b"2", &john).unwrap();
+
+    let marias: Vec<_> = map
+        .idx
+        .name
+        .prefix(b"Maria".to_vec())
+        .range(&store, None, None, Order::Ascending)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(marias, vec![(b"1".to_vec(), maria)]);
+
+    // the unique index is enforced as well
+    let other = Data {
+        name: "Other".to_string(),
+        age: 32,
+    };
+    map.save(&mut store, b"3", &other).unwrap_err();
+
+    map.remove(&mut store, b"2").unwrap();
+    assert_eq!(None, map.idx.age.item(&store, U32Key::new(32)).unwrap());
+}
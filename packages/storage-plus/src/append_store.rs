@@ -0,0 +1,259 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+
+#[cfg(feature = "iterator")]
+use cosmwasm_std::Order;
+use cosmwasm_std::{to_vec, StdError, StdResult, Storage};
+
+use crate::helpers::{may_deserialize, namespaces_with_key};
+
+// the number of elements is stored under this key inside the namespace.
+// elements use 8 byte keys, so they can never collide with it.
+const LEN_KEY: &[u8] = b"len";
+
+/// AppendStore is an append-only log of typed elements under the given namespace,
+/// eg. for events or transaction history. Every element gets the next index (starting at 0),
+/// and is stored under the big-endian u64 of it, so the elements are listed in push order.
+/// Use add_suffix to keep one log per address (or any other runtime value).
+pub struct AppendStore<'a, T> {
+    namespace: &'a [u8],
+    suffix: Option<Vec<u8>>,
+    // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
+    item_type: PhantomData<T>,
+}
+
+impl<'a, T> AppendStore<'a, T> {
+    pub const fn new(namespace: &'a str) -> Self {
+        AppendStore {
+            namespace: namespace.as_bytes(),
+            suffix: None,
+            item_type: PhantomData,
+        }
+    }
+
+    /// add_suffix returns a log scoped by the given runtime suffix, eg. the address of a user.
+    /// The suffix is stored as a length-prefixed segment after the namespace, so logs with
+    /// different suffixes (or without one) never see each other's elements.
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        AppendStore {
+            namespace: self.namespace,
+            suffix: Some(suffix.to_vec()),
+            item_type: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> AppendStore<'a, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// push adds an element to the end of the log and returns its index
+    pub fn push(&self, store: &mut dyn Storage, value: &T) -> StdResult<u64> {
+        let index = self.len(store)?;
+        if index == u64::MAX {
+            return Err(StdError::generic_err("AppendStore is full"));
+        }
+        store.set(&self.element_key(index), &to_vec(value)?);
+        store.set(&self.key(LEN_KEY), &to_vec(&(index + 1))?);
+        Ok(index)
+    }
+
+    /// get returns the element at the given index, None if out of bounds
+    pub fn get(&self, store: &dyn Storage, index: u64) -> StdResult<Option<T>> {
+        let value = store.get(&self.element_key(index));
+        may_deserialize(&value)
+    }
+
+    /// last returns the most recently pushed element, None if empty
+    pub fn last(&self, store: &dyn Storage) -> StdResult<Option<T>> {
+        match self.len(store)? {
+            0 => Ok(None),
+            len => self.get(store, len - 1),
+        }
+    }
+
+    /// len returns the number of elements in the log
+    pub fn len(&self, store: &dyn Storage) -> StdResult<u64> {
+        let value = store.get(&self.key(LEN_KEY));
+        Ok(may_deserialize(&value)?.unwrap_or_default())
+    }
+
+    pub fn is_empty(&self, store: &dyn Storage) -> StdResult<bool> {
+        Ok(self.len(store)? == 0)
+    }
+
+    /// range returns at most limit elements (with their index) after start_after, which is
+    /// never included. With Order::Ascending these are the elements pushed after start_after,
+    /// with Order::Descending the ones pushed before it, most recent first.
+    /// A None start_after starts from the first (or last) element.
+    #[cfg(feature = "iterator")]
+    pub fn range(
+        &self,
+        store: &dyn Storage,
+        start_after: Option<u64>,
+        limit: usize,
+        order: Order,
+    ) -> StdResult<Vec<(u64, T)>> {
+        let len = self.len(store)?;
+        let indexes: Box<dyn Iterator<Item = u64>> = match order {
+            Order::Ascending => {
+                let start = start_after.map_or(0, |i| i.saturating_add(1));
+                Box::new(start..len)
+            }
+            Order::Descending => {
+                let end = start_after.map_or(len, |i| i.min(len));
+                Box::new((0..end).rev())
+            }
+        };
+        indexes
+            .take(limit)
+            .map(|index| {
+                let value = self
+                    .get(store, index)?
+                    .ok_or_else(|| StdError::not_found("append store element"))?;
+                Ok((index, value))
+            })
+            .collect()
+    }
+
+    fn key(&self, key: &[u8]) -> Vec<u8> {
+        match &self.suffix {
+            Some(suffix) => namespaces_with_key(&[self.namespace, suffix], key),
+            None => namespaces_with_key(&[self.namespace], key),
+        }
+    }
+
+    fn element_key(&self, index: u64) -> Vec<u8> {
+        self.key(&index.to_be_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Event {
+        pub action: String,
+        pub amount: u64,
+    }
+
+    const HISTORY: AppendStore<u64> = AppendStore::new("history");
+
+    #[test]
+    fn push_and_get() {
+        let mut store = MockStorage::new();
+        const EVENTS: AppendStore<Event> = AppendStore::new("events");
+        assert!(EVENTS.is_empty(&store).unwrap());
+        assert_eq!(None, EVENTS.last(&store).unwrap());
+
+        let mint = Event {
+            action: "mint".to_string(),
+            amount: 100,
+        };
+        let burn = Event {
+            action: "burn".to_string(),
+            amount: 5,
+        };
+        assert_eq!(0, EVENTS.push(&mut store, &mint).unwrap());
+        assert_eq!(1, EVENTS.push(&mut store, &burn).unwrap());
+
+        assert_eq!(2, EVENTS.len(&store).unwrap());
+        assert!(!EVENTS.is_empty(&store).unwrap());
+        assert_eq!(Some(mint), EVENTS.get(&store, 0).unwrap());
+        assert_eq!(Some(burn.clone()), EVENTS.get(&store, 1).unwrap());
+        assert_eq!(None, EVENTS.get(&store, 2).unwrap());
+        assert_eq!(Some(burn), EVENTS.last(&store).unwrap());
+    }
+
+    #[test]
+    fn push_past_one_byte() {
+        let mut store = MockStorage::new();
+        for i in 0..300u64 {
+            assert_eq!(i, HISTORY.push(&mut store, &(i * 10)).unwrap());
+        }
+        assert_eq!(300, HISTORY.len(&store).unwrap());
+        assert_eq!(Some(2550), HISTORY.get(&store, 255).unwrap());
+        assert_eq!(Some(2560), HISTORY.get(&store, 256).unwrap());
+        assert_eq!(Some(2990), HISTORY.last(&store).unwrap());
+
+        // stored under the big-endian index
+        let key = namespaces_with_key(&[b"history"], &256u64.to_be_bytes());
+        assert_eq!(Some(b"2560".to_vec()), store.get(&key));
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_in_both_directions() {
+        let mut store = MockStorage::new();
+        for i in 0..300u64 {
+            HISTORY.push(&mut store, &i).unwrap();
+        }
+
+        // the raw storage order matches the push order, even past 255
+        let raw: Vec<u64> = crate::Prefix::<u64>::new(b"history", &[])
+            .range(&store, None, None, Order::Ascending)
+            .filter_map(|r| r.ok())
+            .filter(|(k, _)| k.len() == 8)
+            .map(|(_, v)| v)
+            .collect();
+        assert_eq!(raw, (0..300).collect::<Vec<_>>());
+
+        let page = HISTORY
+            .range(&store, Some(254), 3, Order::Ascending)
+            .unwrap();
+        assert_eq!(page, vec![(255, 255), (256, 256), (257, 257)]);
+
+        let page = HISTORY
+            .range(&store, Some(257), 3, Order::Descending)
+            .unwrap();
+        assert_eq!(page, vec![(256, 256), (255, 255), (254, 254)]);
+
+        // most recent first
+        let page = HISTORY.range(&store, None, 2, Order::Descending).unwrap();
+        assert_eq!(page, vec![(299, 299), (298, 298)]);
+
+        // the ends
+        let page = HISTORY
+            .range(&store, Some(298), 10, Order::Ascending)
+            .unwrap();
+        assert_eq!(page, vec![(299, 299)]);
+        assert_eq!(
+            0,
+            HISTORY
+                .range(&store, Some(0), 10, Order::Descending)
+                .unwrap()
+                .len()
+        );
+        assert_eq!(
+            0,
+            HISTORY
+                .range(&store, Some(u64::MAX), 10, Order::Ascending)
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn per_address_logs() {
+        let mut store = MockStorage::new();
+        let john = HISTORY.add_suffix(b"john");
+        let jim = HISTORY.add_suffix(b"jim");
+
+        assert_eq!(0, john.push(&mut store, &1).unwrap());
+        assert_eq!(1, john.push(&mut store, &2).unwrap());
+        assert_eq!(0, jim.push(&mut store, &3).unwrap());
+        assert_eq!(0, HISTORY.push(&mut store, &4).unwrap());
+
+        assert_eq!(2, john.len(&store).unwrap());
+        assert_eq!(1, jim.len(&store).unwrap());
+        assert_eq!(1, HISTORY.len(&store).unwrap());
+        assert_eq!(Some(2), john.get(&store, 1).unwrap());
+        assert_eq!(Some(3), jim.get(&store, 0).unwrap());
+        assert_eq!(None, jim.get(&store, 1).unwrap());
+        assert_eq!(Some(4), HISTORY.get(&store, 0).unwrap());
+    }
+}
@@ -0,0 +1,194 @@
+use cosmwasm_std::{Addr, StdError, StdResult, Timestamp};
+
+use crate::keys::{IntKey, TimestampKey};
+use crate::Endian;
+
+/// KeyDeserialize is the inverse of PrimaryKey::joined_key.
+/// It turns the raw (relative) key bytes returned from iteration back into
+/// an owned value of the key type.
+pub trait KeyDeserialize {
+    type Output: Sized;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output>;
+
+    fn from_slice(value: &[u8]) -> StdResult<Self::Output> {
+        Self::from_vec(value.to_vec())
+    }
+}
+
+impl KeyDeserialize for () {
+    type Output = ();
+
+    fn from_vec(_value: Vec<u8>) -> StdResult<Self::Output> {
+        Ok(())
+    }
+}
+
+impl KeyDeserialize for Vec<u8> {
+    type Output = Vec<u8>;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        Ok(value)
+    }
+}
+
+impl KeyDeserialize for &[u8] {
+    type Output = Vec<u8>;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        Ok(value)
+    }
+}
+
+impl KeyDeserialize for String {
+    type Output = String;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        String::from_utf8(value).map_err(StdError::invalid_utf8)
+    }
+}
+
+impl KeyDeserialize for &str {
+    type Output = String;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        String::from_vec(value)
+    }
+}
+
+impl KeyDeserialize for &Addr {
+    type Output = Addr;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        // the address was validated before it was stored, so we don't validate again
+        Ok(Addr::unchecked(String::from_vec(value)?))
+    }
+}
+
+impl KeyDeserialize for Addr {
+    type Output = Addr;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        <&Addr>::from_vec(value)
+    }
+}
+
+impl<T: Endian> KeyDeserialize for IntKey<T> {
+    type Output = T;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        let mut buf = T::Buf::default();
+        let expected = buf.as_ref().len();
+        if value.len() != expected {
+            return Err(StdError::invalid_data_size(expected, value.len()));
+        }
+        buf.as_mut().copy_from_slice(&value);
+        Ok(T::from_key_bytes(buf))
+    }
+}
+
+impl KeyDeserialize for TimestampKey {
+    type Output = Timestamp;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        IntKey::<u64>::from_vec(value).map(Timestamp::from_nanos)
+    }
+}
+
+/// Splits off one length-prefixed element from the front of a composite key.
+/// Returns the element and the remainder of the key.
+fn split_first_key(value: &[u8]) -> StdResult<(&[u8], &[u8])> {
+    if value.len() < 2 {
+        return Err(StdError::invalid_data_size(2, value.len()));
+    }
+    let len = u16::from_be_bytes([value[0], value[1]]) as usize;
+    let rest = &value[2..];
+    if rest.len() < len {
+        return Err(StdError::invalid_data_size(len, rest.len()));
+    }
+    Ok(rest.split_at(len))
+}
+
+// The non-final elements of a composite key are length-prefixed, the last one is raw.
+// This is the exact inverse of what PrimaryKey::joined_key produces.
+impl<T: KeyDeserialize, U: KeyDeserialize> KeyDeserialize for (T, U) {
+    type Output = (T::Output, U::Output);
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        let (t, u) = split_first_key(&value)?;
+        Ok((T::from_slice(t)?, U::from_slice(u)?))
+    }
+}
+
+impl<T: KeyDeserialize, U: KeyDeserialize, V: KeyDeserialize> KeyDeserialize for (T, U, V) {
+    type Output = (T::Output, U::Output, V::Output);
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        let (t, rest) = split_first_key(&value)?;
+        let (u, v) = split_first_key(rest)?;
+        Ok((T::from_slice(t)?, U::from_slice(u)?, V::from_slice(v)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrimaryKey, U32Key, U64Key, U8Key};
+
+    #[test]
+    fn deserialize_simple_keys() {
+        assert_eq!(<&[u8]>::from_slice(b"foo").unwrap(), b"foo".to_vec());
+        assert_eq!(<Vec<u8>>::from_slice(b"foo").unwrap(), b"foo".to_vec());
+        assert_eq!(<&str>::from_slice(b"foo").unwrap(), "foo".to_string());
+        assert_eq!(String::from_slice(b"foo").unwrap(), "foo".to_string());
+        assert_eq!(<&Addr>::from_slice(b"foo").unwrap(), Addr::unchecked("foo"));
+        assert_eq!(Addr::from_slice(b"foo").unwrap(), Addr::unchecked("foo"));
+    }
+
+    #[test]
+    fn deserialize_int_keys() {
+        let k = U64Key::new(1234567);
+        assert_eq!(U64Key::from_vec(k.joined_key()).unwrap(), 1234567u64);
+        let k = U32Key::new(4242);
+        assert_eq!(U32Key::from_vec(k.joined_key()).unwrap(), 4242u32);
+
+        // wrong length is an error, not a panic
+        let err = U64Key::from_slice(b"\x01\x02").unwrap_err();
+        assert!(matches!(
+            err,
+            StdError::InvalidDataSize {
+                expected: 8,
+                actual: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn deserialize_invalid_utf8() {
+        let err = String::from_slice(b"\xff\xfe").unwrap_err();
+        assert!(matches!(err, StdError::InvalidUtf8 { .. }));
+    }
+
+    #[test]
+    fn deserialize_composite_keys() {
+        let k: (&[u8], U64Key) = (b"owner", 987654.into());
+        let parsed = <(&[u8], U64Key)>::from_vec(k.joined_key()).unwrap();
+        assert_eq!(parsed, (b"owner".to_vec(), 987654u64));
+
+        let k: (&str, U8Key, &str) = ("john", 8.into(), "pedro");
+        let parsed = <(&str, U8Key, &str)>::from_vec(k.joined_key()).unwrap();
+        assert_eq!(parsed, ("john".to_string(), 8u8, "pedro".to_string()));
+    }
+
+    #[test]
+    fn deserialize_malformed_composite_keys() {
+        // too short for the length prefix
+        let err = <(&[u8], &[u8])>::from_slice(b"\x00").unwrap_err();
+        assert!(matches!(err, StdError::InvalidDataSize { .. }));
+
+        // length prefix longer than the remaining data
+        let err = <(&[u8], &[u8])>::from_slice(b"\x00\x09foo").unwrap_err();
+        assert!(matches!(err, StdError::InvalidDataSize { .. }));
+    }
+}
@@ -0,0 +1,327 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+
+use cosmwasm_std::{to_vec, StdError, StdResult, Storage};
+
+use crate::helpers::{may_deserialize, namespaces_with_key};
+
+// the head and tail counters are stored under these keys inside the namespace.
+// elements use 8 byte keys, so they can never collide with these.
+const HEAD_KEY: &[u8] = b"h";
+const TAIL_KEY: &[u8] = b"t";
+
+/// Deque stores a double-ended queue of typed elements under the given namespace.
+/// Elements are stored under sequential u64 positions, the queue holds
+/// all positions from head (inclusive) to tail (exclusive).
+/// Positions wrap around, so pushing to the front of a fresh queue works as well.
+pub struct Deque<'a, T> {
+    namespace: &'a [u8],
+    // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
+    item_type: PhantomData<T>,
+}
+
+impl<'a, T> Deque<'a, T> {
+    pub const fn new(namespace: &'a str) -> Self {
+        Deque {
+            namespace: namespace.as_bytes(),
+            item_type: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Deque<'a, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// push_back adds an element to the end of the queue
+    pub fn push_back(&self, store: &mut dyn Storage, value: &T) -> StdResult<()> {
+        self.assert_not_full(store)?;
+        let pos = self.tail(store)?;
+        self.set_unchecked(store, pos, value)?;
+        self.set_counter(store, TAIL_KEY, pos.wrapping_add(1))
+    }
+
+    /// push_front adds an element to the start of the queue
+    pub fn push_front(&self, store: &mut dyn Storage, value: &T) -> StdResult<()> {
+        self.assert_not_full(store)?;
+        let pos = self.head(store)?.wrapping_sub(1);
+        self.set_unchecked(store, pos, value)?;
+        self.set_counter(store, HEAD_KEY, pos)
+    }
+
+    /// pop_back removes the last element of the queue and returns it.
+    /// Returns StdError::NotFound if the queue is empty
+    pub fn pop_back(&self, store: &mut dyn Storage) -> StdResult<T> {
+        let (head, tail) = (self.head(store)?, self.tail(store)?);
+        if head == tail {
+            return Err(StdError::not_found("deque element"));
+        }
+        let pos = tail.wrapping_sub(1);
+        let value = self.load_unchecked(store, pos)?;
+        self.remove_unchecked(store, pos);
+        self.set_counter(store, TAIL_KEY, pos)?;
+        Ok(value)
+    }
+
+    /// pop_front removes the first element of the queue and returns it.
+    /// Returns StdError::NotFound if the queue is empty
+    pub fn pop_front(&self, store: &mut dyn Storage) -> StdResult<T> {
+        let (head, tail) = (self.head(store)?, self.tail(store)?);
+        if head == tail {
+            return Err(StdError::not_found("deque element"));
+        }
+        let value = self.load_unchecked(store, head)?;
+        self.remove_unchecked(store, head);
+        self.set_counter(store, HEAD_KEY, head.wrapping_add(1))?;
+        Ok(value)
+    }
+
+    /// front returns the first element of the queue without removing it, None if empty
+    pub fn front(&self, store: &dyn Storage) -> StdResult<Option<T>> {
+        self.get(store, 0)
+    }
+
+    /// back returns the last element of the queue without removing it, None if empty
+    pub fn back(&self, store: &dyn Storage) -> StdResult<Option<T>> {
+        match self.len(store)? {
+            0 => Ok(None),
+            len => self.get(store, len - 1),
+        }
+    }
+
+    /// get returns the element at the given index (counted from the front), None if out of bounds
+    pub fn get(&self, store: &dyn Storage, index: u64) -> StdResult<Option<T>> {
+        if index >= self.len(store)? {
+            return Ok(None);
+        }
+        let pos = self.head(store)?.wrapping_add(index);
+        let value = store.get(&self.element_key(pos));
+        may_deserialize(&value)
+    }
+
+    /// len returns the number of elements in the queue
+    pub fn len(&self, store: &dyn Storage) -> StdResult<u64> {
+        Ok(self.tail(store)?.wrapping_sub(self.head(store)?))
+    }
+
+    pub fn is_empty(&self, store: &dyn Storage) -> StdResult<bool> {
+        Ok(self.len(store)? == 0)
+    }
+
+    /// iter returns an iterator over all elements, from front to back.
+    /// Use `.rev()` to go from back to front.
+    pub fn iter<'c>(&'c self, store: &'c dyn Storage) -> StdResult<DequeIter<'a, 'c, T>> {
+        Ok(DequeIter {
+            deque: self,
+            store,
+            start: self.head(store)?,
+            end: self.tail(store)?,
+        })
+    }
+
+    fn assert_not_full(&self, store: &dyn Storage) -> StdResult<()> {
+        if self.len(store)? == u64::MAX {
+            return Err(StdError::generic_err("Deque is full"));
+        }
+        Ok(())
+    }
+
+    fn head(&self, store: &dyn Storage) -> StdResult<u64> {
+        self.counter(store, HEAD_KEY)
+    }
+
+    fn tail(&self, store: &dyn Storage) -> StdResult<u64> {
+        self.counter(store, TAIL_KEY)
+    }
+
+    fn counter(&self, store: &dyn Storage, key: &[u8]) -> StdResult<u64> {
+        let value = store.get(&namespaces_with_key(&[self.namespace], key));
+        Ok(may_deserialize(&value)?.unwrap_or_default())
+    }
+
+    fn set_counter(&self, store: &mut dyn Storage, key: &[u8], value: u64) -> StdResult<()> {
+        store.set(
+            &namespaces_with_key(&[self.namespace], key),
+            &to_vec(&value)?,
+        );
+        Ok(())
+    }
+
+    fn element_key(&self, pos: u64) -> Vec<u8> {
+        namespaces_with_key(&[self.namespace], &pos.to_be_bytes())
+    }
+
+    fn load_unchecked(&self, store: &dyn Storage, pos: u64) -> StdResult<T> {
+        let value = store.get(&self.element_key(pos));
+        may_deserialize(&value)?.ok_or_else(|| StdError::not_found("deque element"))
+    }
+
+    fn set_unchecked(&self, store: &mut dyn Storage, pos: u64, value: &T) -> StdResult<()> {
+        store.set(&self.element_key(pos), &to_vec(value)?);
+        Ok(())
+    }
+
+    fn remove_unchecked(&self, store: &mut dyn Storage, pos: u64) {
+        store.remove(&self.element_key(pos));
+    }
+}
+
+/// DequeIter iterates over the elements of a Deque, see Deque::iter
+pub struct DequeIter<'a, 'c, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    deque: &'c Deque<'a, T>,
+    store: &'c dyn Storage,
+    start: u64,
+    end: u64,
+}
+
+impl<'a, 'c, T> Iterator for DequeIter<'a, 'c, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    type Item = StdResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+        let item = self.deque.load_unchecked(self.store, self.start);
+        self.start = self.start.wrapping_add(1);
+        Some(item)
+    }
+}
+
+impl<'a, 'c, T> DoubleEndedIterator for DequeIter<'a, 'c, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+        self.end = self.end.wrapping_sub(1);
+        Some(self.deque.load_unchecked(self.store, self.end))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const QUEUE: Deque<u32> = Deque::new("queue");
+
+    #[test]
+    fn push_and_pop() {
+        let mut store = MockStorage::new();
+
+        assert!(QUEUE.is_empty(&store).unwrap());
+        QUEUE.push_back(&mut store, &1).unwrap();
+        QUEUE.push_back(&mut store, &2).unwrap();
+        QUEUE.push_front(&mut store, &0).unwrap();
+        assert_eq!(QUEUE.len(&store).unwrap(), 3);
+
+        assert_eq!(QUEUE.front(&store).unwrap(), Some(0));
+        assert_eq!(QUEUE.back(&store).unwrap(), Some(2));
+        assert_eq!(QUEUE.get(&store, 1).unwrap(), Some(1));
+        assert_eq!(QUEUE.get(&store, 3).unwrap(), None);
+
+        assert_eq!(QUEUE.pop_front(&mut store).unwrap(), 0);
+        assert_eq!(QUEUE.pop_back(&mut store).unwrap(), 2);
+        assert_eq!(QUEUE.pop_back(&mut store).unwrap(), 1);
+        assert!(QUEUE.is_empty(&store).unwrap());
+        assert_eq!(QUEUE.front(&store).unwrap(), None);
+        assert_eq!(QUEUE.back(&store).unwrap(), None);
+    }
+
+    #[test]
+    fn pop_from_empty_errors() {
+        let mut store = MockStorage::new();
+
+        let err = QUEUE.pop_front(&mut store).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+        let err = QUEUE.pop_back(&mut store).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+
+        // still usable after that
+        QUEUE.push_back(&mut store, &7).unwrap();
+        assert_eq!(QUEUE.pop_front(&mut store).unwrap(), 7);
+        let err = QUEUE.pop_front(&mut store).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+    }
+
+    #[test]
+    fn iterate_both_ways() {
+        let mut store = MockStorage::new();
+
+        for i in 1..=3 {
+            QUEUE.push_back(&mut store, &i).unwrap();
+        }
+        QUEUE.push_front(&mut store, &0).unwrap();
+
+        let all: StdResult<Vec<_>> = QUEUE.iter(&store).unwrap().collect();
+        assert_eq!(all.unwrap(), vec![0, 1, 2, 3]);
+        let all: StdResult<Vec<_>> = QUEUE.iter(&store).unwrap().rev().collect();
+        assert_eq!(all.unwrap(), vec![3, 2, 1, 0]);
+
+        // mixing both ends
+        let mut iter = QUEUE.iter(&store).unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), 0);
+        assert_eq!(iter.next_back().unwrap().unwrap(), 3);
+        assert_eq!(iter.next().unwrap().unwrap(), 1);
+        assert_eq!(iter.next_back().unwrap().unwrap(), 2);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+    }
+
+    #[test]
+    fn wrap_around() {
+        let mut store = MockStorage::new();
+
+        // start just before the end of the u64 range
+        QUEUE
+            .set_counter(&mut store, HEAD_KEY, u64::MAX - 1)
+            .unwrap();
+        QUEUE
+            .set_counter(&mut store, TAIL_KEY, u64::MAX - 1)
+            .unwrap();
+
+        for i in 0..4 {
+            QUEUE.push_back(&mut store, &i).unwrap();
+        }
+        assert_eq!(QUEUE.len(&store).unwrap(), 4);
+        assert_eq!(QUEUE.tail(&store).unwrap(), 2);
+
+        let all: StdResult<Vec<_>> = QUEUE.iter(&store).unwrap().collect();
+        assert_eq!(all.unwrap(), vec![0, 1, 2, 3]);
+        assert_eq!(QUEUE.back(&store).unwrap(), Some(3));
+
+        for i in 0..4 {
+            assert_eq!(QUEUE.pop_front(&mut store).unwrap(), i);
+        }
+        assert!(QUEUE.is_empty(&store).unwrap());
+
+        // push_front on a fresh queue wraps the other way
+        const OTHER: Deque<u32> = Deque::new("other");
+        OTHER.push_front(&mut store, &1).unwrap();
+        OTHER.push_front(&mut store, &0).unwrap();
+        assert_eq!(OTHER.head(&store).unwrap(), u64::MAX - 1);
+        let all: StdResult<Vec<_>> = OTHER.iter(&store).unwrap().collect();
+        assert_eq!(all.unwrap(), vec![0, 1]);
+    }
+
+    #[test]
+    fn separate_namespaces() {
+        let mut store = MockStorage::new();
+        const OTHER: Deque<u32> = Deque::new("queue2");
+
+        QUEUE.push_back(&mut store, &1).unwrap();
+        OTHER.push_back(&mut store, &2).unwrap();
+        assert_eq!(QUEUE.len(&store).unwrap(), 1);
+        assert_eq!(OTHER.pop_front(&mut store).unwrap(), 2);
+        assert_eq!(QUEUE.pop_front(&mut store).unwrap(), 1);
+    }
+}
@@ -0,0 +1,17 @@
+/// StorageDescription tells where a storage type stores its data and which types it holds,
+/// eg. for off-chain tools decoding a dump of contract storage. Contracts can list the
+/// descriptions of all their Items and Maps (eg. behind a "schema" feature) rather than
+/// repeating the namespaces in the tooling.
+///
+/// An Item is stored at exactly its namespace, while the keys of a Map start with its
+/// length-prefixed namespace, followed by the (joined) key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageDescription<'a> {
+    /// "Item" or "Map"
+    pub kind: &'static str,
+    pub namespace: &'a [u8],
+    /// the type name of the key, None for an Item
+    pub key_type: Option<&'static str>,
+    /// the type name of the value
+    pub value_type: &'static str,
+}
@@ -17,6 +17,17 @@ pub trait Endian: Sized + Copy {
 
     fn from_le_bytes(bytes: Self::Buf) -> Self;
     fn from_be_bytes(bytes: Self::Buf) -> Self;
+
+    /// Encoding used for storage keys, whose byte order matches the numeric order.
+    /// This is big endian for unsigned types, and big endian with the sign bit flipped
+    /// for signed types, so that negative values sort before positive ones.
+    fn to_key_bytes(self) -> Self::Buf {
+        self.to_be_bytes()
+    }
+
+    fn from_key_bytes(bytes: Self::Buf) -> Self {
+        Self::from_be_bytes(bytes)
+    }
 }
 
 macro_rules! delegate {
@@ -32,20 +43,43 @@ macro_rules! delegate {
 }
 
 macro_rules! impl_primitives {
-    ($($ty:ty),* $(,)?) => {
+    (@inner $ty:ty) => {
+        type Buf = [u8; mem::size_of::<$ty>()];
+
+        delegate!($ty, [
+            to_le_bytes,
+            to_be_bytes,
+        ], (self: Self) -> Self::Buf);
+
+        delegate!($ty, [
+            from_le_bytes,
+            from_be_bytes,
+        ], (bytes: Self::Buf) -> Self);
+    };
+    (signed: $($ty:ty),* $(,)?) => {
         $(
             impl Endian for $ty {
-                type Buf = [u8; mem::size_of::<$ty>()];
+                impl_primitives!(@inner $ty);
 
-                delegate!($ty, [
-                    to_le_bytes,
-                    to_be_bytes,
-                ], (self: Self) -> Self::Buf);
+                #[inline]
+                fn to_key_bytes(self) -> Self::Buf {
+                    let mut bytes = self.to_be_bytes();
+                    bytes[0] ^= 0x80;
+                    bytes
+                }
 
-                delegate!($ty, [
-                    from_le_bytes,
-                    from_be_bytes,
-                ], (bytes: Self::Buf) -> Self);
+                #[inline]
+                fn from_key_bytes(mut bytes: Self::Buf) -> Self {
+                    bytes[0] ^= 0x80;
+                    Self::from_be_bytes(bytes)
+                }
+            }
+        )*
+    };
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Endian for $ty {
+                impl_primitives!(@inner $ty);
             }
         )*
     };
@@ -53,6 +87,10 @@ macro_rules! impl_primitives {
 
 #[rustfmt::skip]
 impl_primitives![
-    i8, i16, i32, i64, i128,
     u8, u16, u32, u64, u128,
 ];
+
+#[rustfmt::skip]
+impl_primitives![signed:
+    i8, i16, i32, i64, i128,
+];
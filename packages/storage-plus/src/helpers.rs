@@ -7,7 +7,9 @@
 use serde::de::DeserializeOwned;
 use std::any::type_name;
 
-use cosmwasm_std::{from_slice, StdError, StdResult};
+use cosmwasm_std::{StdError, StdResult};
+
+use crate::serialization::{Json, Serde};
 
 /// may_deserialize parses json bytes from storage (Option), returning Ok(None) if no data present
 ///
@@ -15,21 +17,41 @@ use cosmwasm_std::{from_slice, StdError, StdResult};
 /// and value.map(|s| s.as_slice()) seems trickier than &value
 pub(crate) fn may_deserialize<T: DeserializeOwned>(
     value: &Option<Vec<u8>>,
+) -> StdResult<Option<T>> {
+    may_deserialize_with::<Json, T>(value)
+}
+
+/// may_deserialize_with works like may_deserialize, parsing with the given Serde strategy
+pub(crate) fn may_deserialize_with<S: Serde, T: DeserializeOwned>(
+    value: &Option<Vec<u8>>,
 ) -> StdResult<Option<T>> {
     match value {
-        Some(vec) => Ok(Some(from_slice(&vec)?)),
+        Some(vec) => Ok(Some(S::from_slice(vec)?)),
         None => Ok(None),
     }
 }
 
 /// must_deserialize parses json bytes from storage (Option), returning NotFound error if no data present
+#[cfg(test)]
 pub(crate) fn must_deserialize<T: DeserializeOwned>(value: &Option<Vec<u8>>) -> StdResult<T> {
+    must_deserialize_with::<Json, T>(value)
+}
+
+/// must_deserialize_with works like must_deserialize, parsing with the given Serde strategy
+pub(crate) fn must_deserialize_with<S: Serde, T: DeserializeOwned>(
+    value: &Option<Vec<u8>>,
+) -> StdResult<T> {
     match value {
-        Some(vec) => from_slice(&vec),
+        Some(vec) => S::from_slice(vec),
         None => Err(StdError::not_found(type_name::<T>())),
     }
 }
 
+/// already_exists is the error returned when saving to a key that must not be set yet
+pub(crate) fn already_exists<T>() -> StdError {
+    StdError::generic_err(format!("{} already exists", type_name::<T>()))
+}
+
 /// This is equivalent concat(to_length_prefixed_nested(namespaces), key)
 /// But more efficient when the intermediate namespaces often must be recalculated
 pub(crate) fn namespaces_with_key(namespaces: &[&[u8]], key: &[u8]) -> Vec<u8> {
@@ -75,9 +97,25 @@ pub(crate) fn nested_namespaces_with_key(
     out
 }
 
+/// Maximum length of a namespace, or of any key segment that is length-prefixed
+pub(crate) const MAX_NAMESPACE_LENGTH: usize = 0xFFFF;
+
+/// check_namespaces returns an error if any of the namespaces is too long to be length-prefixed.
+/// Use it before building keys from user-controlled data, as encode_length panics on those.
+pub(crate) fn check_namespaces(namespaces: &[&[u8]]) -> StdResult<()> {
+    match namespaces.iter().find(|n| n.len() > MAX_NAMESPACE_LENGTH) {
+        Some(n) => Err(StdError::generic_err(format!(
+            "Key segment of {} bytes exceeds the maximum length of {} bytes",
+            n.len(),
+            MAX_NAMESPACE_LENGTH
+        ))),
+        None => Ok(()),
+    }
+}
+
 /// Encodes the length of a given namespace as a 2 byte big endian encoded integer
 pub(crate) fn encode_length(namespace: &[u8]) -> [u8; 2] {
-    if namespace.len() > 0xFFFF {
+    if namespace.len() > MAX_NAMESPACE_LENGTH {
         panic!("only supports namespaces up to length 0xFFFF")
     }
     let length_bytes = (namespace.len() as u32).to_be_bytes();
@@ -114,6 +152,19 @@ mod test {
         encode_length(&vec![1; 65536]);
     }
 
+    #[test]
+    fn check_namespaces_works() {
+        check_namespaces(&[]).unwrap();
+        check_namespaces(&[b"", &vec![1; 0xFFFF]]).unwrap();
+        match check_namespaces(&[b"foo", &vec![1; 0x10000]]).unwrap_err() {
+            StdError::GenericErr { msg, .. } => assert_eq!(
+                msg,
+                "Key segment of 65536 bytes exceeds the maximum length of 65535 bytes"
+            ),
+            e => panic!("Unexpected error {}", e),
+        }
+    }
+
     #[test]
     fn may_deserialize_handles_some() {
         let person = Person {
@@ -5,6 +5,7 @@ use cosmwasm_std::{StdError, StdResult, Storage};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use crate::helpers::already_exists;
 use crate::indexes::Index;
 use crate::keys::{EmptyPrefix, Prefixer, PrimaryKey};
 use crate::map::Map;
@@ -69,6 +70,25 @@ where
         self.replace(store, key, None, old_data.as_ref())
     }
 
+    /// save_if_absent works like save, but returns an "already exists" error
+    /// (and doesn't touch the data nor the indexes) if there is already data at the key
+    pub fn save_if_absent(&self, store: &mut dyn Storage, key: K, data: &T) -> StdResult<()> {
+        if self.primary.has(store, key.clone()) {
+            return Err(already_exists::<T>());
+        }
+        self.replace(store, key, Some(data), None)
+    }
+
+    /// take removes the data at the key (and its indexes), returning the previous value if any.
+    /// This costs a single read, like remove, eg. to refund the funds of a deleted escrow.
+    pub fn take(&self, store: &mut dyn Storage, key: K) -> StdResult<Option<T>> {
+        let old_data = self.may_load(store, key.clone())?;
+        if old_data.is_some() {
+            self.replace(store, key, None, old_data.as_ref())?;
+        }
+        Ok(old_data)
+    }
+
     /// replace writes data to key. old_data must be the current stored value (from a previous load)
     /// and is used to properly update the index. This is used by save, replace, and update
     /// and can be called directly if you want to optimize
@@ -101,6 +121,8 @@ where
     /// in the database. This is shorthand for some common sequences, which may be useful.
     ///
     /// If the data exists, `action(Some(value))` is called. Otherwise `action(None)` is called.
+    /// The indexes are moved from the old value to the new one with replace.
+    /// If action returns an error, nothing is written, neither to the map nor to the indexes.
     pub fn update<A, E>(&self, store: &mut dyn Storage, key: K, action: A) -> Result<T, E>
     where
         A: FnOnce(Option<T>) -> Result<T, E>,
@@ -127,6 +149,12 @@ where
         self.primary.may_load(store, key)
     }
 
+    /// has returns true or false if any data is at this key, without parsing or interpreting the
+    /// contents.
+    pub fn has(&self, store: &dyn Storage, key: K) -> bool {
+        self.primary.has(store, key)
+    }
+
     // use prefix to scan -> range
     pub fn prefix(&self, p: K::Prefix) -> Prefix<T> {
         Prefix::new(self.pk_namespace, &p.prefix())
@@ -167,10 +195,11 @@ where
 mod test {
     use super::*;
 
-    use crate::indexes::{index_string_tuple, index_triple, MultiIndex, UniqueIndex};
+    use crate::helpers::namespaces_with_key;
+    use crate::indexes::{index_string_tuple, index_tuple, MultiIndex, SumIndex, UniqueIndex};
     use crate::U32Key;
     use cosmwasm_std::testing::MockStorage;
-    use cosmwasm_std::{MemoryStorage, Order};
+    use cosmwasm_std::{MemoryStorage, Order, Uint128};
     use serde::{Deserialize, Serialize};
 
     #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -181,8 +210,7 @@ mod test {
     }
 
     struct DataIndexes<'a> {
-        // Second arg is for storing pk
-        pub name: MultiIndex<'a, (Vec<u8>, Vec<u8>), Data>,
+        pub name: MultiIndex<'a, Vec<u8>, Data>,
         pub age: UniqueIndex<'a, U32Key, Data>,
         pub name_lastname: UniqueIndex<'a, (Vec<u8>, Vec<u8>), Data>,
     }
@@ -197,8 +225,7 @@ mod test {
 
     // For composite multi index tests
     struct DataCompositeMultiIndex<'a> {
-        // Third arg needed for storing pk
-        pub name_age: MultiIndex<'a, (Vec<u8>, U32Key, Vec<u8>), Data>,
+        pub name_age: MultiIndex<'a, (Vec<u8>, U32Key), Data>,
     }
 
     // Future Note: this can likely be macro-derived
@@ -212,7 +239,7 @@ mod test {
     // Can we make it easier to define this? (less wordy generic)
     fn build_map<'a>() -> IndexedMap<'a, &'a [u8], Data, DataIndexes<'a>> {
         let indexes = DataIndexes {
-            name: MultiIndex::new(|d, k| (d.name.as_bytes().to_vec(), k), "data", "data__name"),
+            name: MultiIndex::new(|d| d.name.as_bytes().to_vec(), "data", "data__name"),
             age: UniqueIndex::new(|d| U32Key::new(d.age), "data__age"),
             name_lastname: UniqueIndex::new(
                 |d| index_string_tuple(&d.name, &d.last_name),
@@ -351,9 +378,8 @@ mod test {
         // index_key() over MultiIndex works (empty pk)
         // In a MultiIndex, an index key is composed by the index and the primary key.
         // Primary key may be empty (so that to iterate over all elements that match just the index)
-        let key = (b"Maria".to_vec(), b"".to_vec());
         // Use the index_key() helper to build the (raw) index key
-        let key = map.idx.name.index_key(key);
+        let key = map.idx.name.index_key(b"Maria".to_vec(), b"");
         // Iterate using a bound over the raw key
         let count = map
             .idx
@@ -365,9 +391,8 @@ mod test {
 
         // index_key() over MultiIndex works (non-empty pk)
         // Build key including a non-empty pk
-        let key = (b"Maria".to_vec(), b"1".to_vec());
         // Use the index_key() helper to build the (raw) index key
-        let key = map.idx.name.index_key(key);
+        let key = map.idx.name.index_key(b"Maria".to_vec(), b"1");
         // Iterate using a (exclusive) bound over the raw key.
         // (Useful for pagination / continuation contexts).
         let count = map
@@ -469,11 +494,7 @@ mod test {
         let mut store = MockStorage::new();
 
         let indexes = DataCompositeMultiIndex {
-            name_age: MultiIndex::new(
-                |d, k| index_triple(&d.name, d.age, k),
-                "data",
-                "data__name_age",
-            ),
+            name_age: MultiIndex::new(|d| index_tuple(&d.name, d.age), "data", "data__name_age"),
         };
         let map = IndexedMap::new("data", indexes);
 
@@ -529,6 +550,289 @@ mod test {
         assert_eq!(data3, marias[1].1);
     }
 
+    #[test]
+    fn multi_index_count_and_keys() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+
+        save_data(&mut store, &map);
+        // one more pk for the same name
+        let data = Data {
+            name: "Maria".to_string(),
+            last_name: "Smith".to_string(),
+            age: 65,
+        };
+        map.save(&mut store, b"6", &data).unwrap();
+
+        assert_eq!(3, map.idx.name.count(&store, b"Maria".to_vec()));
+        assert_eq!(1, map.idx.name.count(&store, b"John".to_vec()));
+        assert_eq!(0, map.idx.name.count(&store, b"Mar".to_vec()));
+
+        let keys: Vec<_> = map
+            .idx
+            .name
+            .keys(&store, b"Maria".to_vec(), None, None, Order::Ascending)
+            .collect();
+        assert_eq!(keys, vec![b"1".to_vec(), b"2".to_vec(), b"6".to_vec()]);
+
+        // bounds apply to the pks
+        let keys: Vec<_> = map
+            .idx
+            .name
+            .keys(
+                &store,
+                b"Maria".to_vec(),
+                Some(Bound::exclusive(b"1".as_ref())),
+                None,
+                Order::Ascending,
+            )
+            .collect();
+        assert_eq!(keys, vec![b"2".to_vec(), b"6".to_vec()]);
+        let count = map
+            .idx
+            .name
+            .keys(
+                &store,
+                b"Maria".to_vec(),
+                None,
+                Some(Bound::exclusive(b"6".as_ref())),
+                Order::Descending,
+            )
+            .count();
+        assert_eq!(2, count);
+        let keys: Vec<_> = map
+            .idx
+            .name
+            .keys(
+                &store,
+                b"Maria".to_vec(),
+                None,
+                Some(Bound::inclusive(b"2".as_ref())),
+                Order::Descending,
+            )
+            .collect();
+        assert_eq!(keys, vec![b"2".to_vec(), b"1".to_vec()]);
+
+        // the values are never loaded
+        store.remove(&namespaces_with_key(&[b"data"], b"2"));
+        assert_eq!(3, map.idx.name.count(&store, b"Maria".to_vec()));
+        assert_eq!(
+            3,
+            map.idx
+                .name
+                .keys(&store, b"Maria".to_vec(), None, None, Order::Ascending)
+                .count()
+        );
+
+        // removing through the map updates the counts
+        map.remove(&mut store, b"1").unwrap();
+        assert_eq!(2, map.idx.name.count(&store, b"Maria".to_vec()));
+    }
+
+    #[test]
+    fn multi_index_pks_match_load() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+        let (pks, datas) = save_data(&mut store, &map);
+
+        let marias: Vec<_> = map
+            .idx
+            .name
+            .pks(&store, b"Maria".to_vec(), None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(marias, vec![pks[0].to_vec(), pks[1].to_vec()]);
+        for (pk, data) in marias.iter().zip(&datas) {
+            assert_eq!(*data, map.load(&store, pk).unwrap());
+        }
+
+        // paginate the pks
+        let after: Vec<_> = map
+            .idx
+            .name
+            .pks(
+                &store,
+                b"Maria".to_vec(),
+                Some(Bound::exclusive(pks[0])),
+                None,
+                Order::Ascending,
+            )
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(after, vec![pks[1].to_vec()]);
+        let desc: Vec<_> = map
+            .idx
+            .name
+            .pks(&store, b"Maria".to_vec(), None, None, Order::Descending)
+            .take(1)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(desc, vec![pks[1].to_vec()]);
+
+        // composite pks are returned joined, as stored in the primary map
+        let indexes = DataCompositeMultiIndex {
+            name_age: MultiIndex::new(|d| index_tuple(&d.name, d.age), "comp", "comp__name_age"),
+        };
+        let map: IndexedMap<(&[u8], &[u8]), Data, _> = IndexedMap::new("comp", indexes);
+        map.save(&mut store, (b"owner", b"1"), &datas[0]).unwrap();
+        map.save(&mut store, (b"own", b"er1"), &datas[0]).unwrap();
+        let found: Vec<_> = map
+            .idx
+            .name_age
+            .pks(
+                &store,
+                index_tuple("Maria", 42),
+                None,
+                None,
+                Order::Ascending,
+            )
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(
+            found,
+            vec![
+                (b"own".as_ref(), b"er1".as_ref()).joined_key(),
+                (b"owner".as_ref(), b"1".as_ref()).joined_key(),
+            ]
+        );
+        assert_eq!(
+            map.key((b"own", b"er1")).to_vec(),
+            namespaces_with_key(&[b"comp"], &found[0])
+        );
+    }
+
+    #[test]
+    fn save_if_absent_and_take() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+
+        let (pks, datas) = save_data(&mut store, &map);
+
+        // existing key is rejected, the data and its indexes are untouched
+        let other = Data {
+            name: "Other".to_string(),
+            last_name: "Name".to_string(),
+            age: 99,
+        };
+        let err = map.save_if_absent(&mut store, pks[0], &other).unwrap_err();
+        match err {
+            StdError::GenericErr { msg, .. } => assert!(msg.ends_with("already exists")),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        assert_eq!(datas[0], map.load(&store, pks[0]).unwrap());
+        assert_eq!(None, map.idx.age.item(&store, U32Key::new(99)).unwrap());
+        assert_eq!(0, map.idx.name.count(&store, b"Other".to_vec()));
+
+        // a new key is indexed
+        map.save_if_absent(&mut store, b"new", &other).unwrap();
+        assert_eq!(
+            b"new".to_vec(),
+            map.idx
+                .age
+                .item(&store, U32Key::new(99))
+                .unwrap()
+                .unwrap()
+                .0
+        );
+        assert_eq!(1, map.idx.name.count(&store, b"Other".to_vec()));
+
+        // take returns the data and removes it from the indexes
+        let taken = map.take(&mut store, pks[0]).unwrap();
+        assert_eq!(Some(datas[0].clone()), taken);
+        assert_eq!(None, map.may_load(&store, pks[0]).unwrap());
+        assert_eq!(
+            None,
+            map.idx.age.item(&store, U32Key::new(datas[0].age)).unwrap()
+        );
+        assert_eq!(
+            vec![pks[1].to_vec()],
+            map.idx.name.all_pks(&store, b"Maria".to_vec())
+        );
+        assert_eq!(None, map.take(&mut store, pks[0]).unwrap());
+
+        // the unique index allows re-using the freed values now
+        map.save_if_absent(&mut store, pks[0], &datas[0]).unwrap();
+        assert_eq!(2, map.idx.name.count(&store, b"Maria".to_vec()));
+    }
+
+    #[test]
+    fn take_cleans_indexes() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+        let (pks, datas) = save_data(&mut store, &map);
+
+        let marias = |store: &MockStorage| -> Vec<_> {
+            map.idx
+                .name
+                .prefix(b"Maria".to_vec())
+                .range(store, None, None, Order::Ascending)
+                .collect::<StdResult<_>>()
+                .unwrap()
+        };
+        assert_eq!(2, marias(&store).len());
+
+        let taken = map.take(&mut store, pks[1]).unwrap().unwrap();
+        assert_eq!(datas[1], taken);
+        // the pk is not listed under its old index value anymore
+        let remaining = marias(&store);
+        assert_eq!(vec![(pks[0].to_vec(), datas[0].clone())], remaining);
+        assert!(!remaining.iter().any(|(pk, _)| pk == pks[1]));
+
+        // the unique index slots are freed, and can be used by another pk
+        assert_eq!(
+            None,
+            map.idx.age.item(&store, U32Key::new(taken.age)).unwrap()
+        );
+        map.save(&mut store, b"other", &taken).unwrap();
+        let (pk, _) = map
+            .idx
+            .age
+            .item(&store, U32Key::new(taken.age))
+            .unwrap()
+            .unwrap();
+        assert_eq!(b"other".to_vec(), pk);
+        assert_eq!(2, marias(&store).len());
+    }
+
+    #[test]
+    fn save_if_absent_composite_key() {
+        let mut store = MockStorage::new();
+
+        let indexes = DataCompositeMultiIndex {
+            name_age: MultiIndex::new(|d| index_tuple(&d.name, d.age), "data", "data__name_age"),
+        };
+        let map: IndexedMap<(&[u8], &[u8]), Data, DataCompositeMultiIndex> =
+            IndexedMap::new("data", indexes);
+
+        let data = Data {
+            name: "Maria".to_string(),
+            last_name: "Doe".to_string(),
+            age: 42,
+        };
+        map.save_if_absent(&mut store, (b"1", b"2"), &data).unwrap();
+        map.save_if_absent(&mut store, (b"1", b"2"), &data)
+            .unwrap_err();
+        map.save_if_absent(&mut store, (b"1", b"3"), &data).unwrap();
+        assert_eq!(
+            2,
+            map.idx
+                .name_age
+                .sub_prefix(b"Maria".to_vec())
+                .range(&store, None, None, Order::Ascending)
+                .count()
+        );
+
+        assert_eq!(Some(data), map.take(&mut store, (b"1", b"2")).unwrap());
+        assert_eq!(
+            1,
+            map.idx
+                .name_age
+                .sub_prefix(b"Maria".to_vec())
+                .range(&store, None, None, Order::Ascending)
+                .count()
+        );
+    }
+
     #[test]
     fn unique_index_enforced() {
         let mut store = MockStorage::new();
@@ -573,6 +877,92 @@ mod test {
         assert_eq!(v.age, data5.age);
     }
 
+    #[test]
+    fn unique_index_error_shows_conflicting_pk() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+        let (pks, datas) = save_data(&mut store, &map);
+
+        // same age as the first entry
+        let data = Data {
+            name: "Marcel".to_string(),
+            last_name: "Laurens".to_string(),
+            age: datas[0].age,
+        };
+        // the pre-check knows who has it
+        let (pk, existing) = map
+            .idx
+            .age
+            .may_item(&store, U32Key::new(data.age))
+            .unwrap()
+            .unwrap();
+        assert_eq!(pks[0].to_vec(), pk);
+        assert_eq!(datas[0], existing);
+        assert_eq!(None, map.idx.age.may_item(&store, U32Key::new(99)).unwrap());
+
+        let err = map.save(&mut store, b"new", &data).unwrap_err();
+        assert_eq!(
+            StdError::generic_err(
+                "Violates unique constraint on index data__age: already used by pk 1"
+            ),
+            err
+        );
+
+        // non printable pks are shown as hex
+        let first = datas[0].clone();
+        const BY_HEIGHT: &str = "by_height";
+        let numbered: IndexedMap<U32Key, Data, _> = IndexedMap::new(
+            BY_HEIGHT,
+            DataIndexes {
+                name: MultiIndex::new(|d| d.name.as_bytes().to_vec(), BY_HEIGHT, "h__name"),
+                age: UniqueIndex::new(|d| U32Key::new(d.age), "h__age"),
+                name_lastname: UniqueIndex::new(
+                    |d| index_string_tuple(&d.name, &d.last_name),
+                    "h__name_lastname",
+                ),
+            },
+        );
+        numbered.save(&mut store, 258.into(), &first).unwrap();
+        let err = numbered.save(&mut store, 7.into(), &first).unwrap_err();
+        assert_eq!(
+            StdError::generic_err(
+                "Violates unique constraint on index h__age: already used by pk 0x00000102"
+            ),
+            err
+        );
+    }
+
+    #[test]
+    fn unique_index_returns_pk_for_mutation() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+
+        // save data
+        let (pks, datas) = save_data(&mut store, &map);
+
+        // fetch John by his unique age, and use the returned pk to modify him
+        let (pk, john) = map.idx.age.item(&store, U32Key::new(32)).unwrap().unwrap();
+        assert_eq!(pk.as_slice(), pks[2]);
+        assert_eq!(john, datas[2]);
+        map.update(&mut store, &pk, |d| -> StdResult<_> {
+            let mut x = d.unwrap();
+            x.age = 33;
+            Ok(x)
+        })
+        .unwrap();
+
+        // index follows the change
+        assert_eq!(map.idx.age.item(&store, U32Key::new(32)).unwrap(), None);
+        let (pk, john) = map.idx.age.item(&store, U32Key::new(33)).unwrap().unwrap();
+        assert_eq!(pk.as_slice(), pks[2]);
+        assert_eq!(john.age, 33);
+
+        // and remove him via the pk of the unique index
+        map.remove(&mut store, &pk).unwrap();
+        assert_eq!(map.idx.age.item(&store, U32Key::new(33)).unwrap(), None);
+        assert_eq!(map.may_load(&store, pks[2]).unwrap(), None);
+    }
+
     #[test]
     fn unique_index_enforced_composite_key() {
         let mut store = MockStorage::new();
@@ -641,6 +1031,98 @@ mod test {
         assert_eq!(name_count(&map, &store, "Mary"), 1);
     }
 
+    #[test]
+    fn update_moves_indexes() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+        let (pks, _) = save_data(&mut store, &map);
+
+        // rename john and change his age, inside the closure
+        let updated = map
+            .update(&mut store, pks[2], |d| -> StdResult<_> {
+                let mut x = d.unwrap();
+                x.name = "Johnny".to_string();
+                x.age = 33;
+                Ok(x)
+            })
+            .unwrap();
+        assert_eq!(updated, map.load(&store, pks[2]).unwrap());
+
+        let count = |store: &MockStorage, name: &str| {
+            map.idx
+                .name
+                .prefix(name.as_bytes().to_vec())
+                .keys(store, None, None, Order::Ascending)
+                .count()
+        };
+        assert_eq!(0, count(&store, "John"));
+        assert_eq!(1, count(&store, "Johnny"));
+        assert_eq!(None, map.idx.age.item(&store, U32Key::new(32)).unwrap());
+        let (pk, by_age) = map.idx.age.item(&store, U32Key::new(33)).unwrap().unwrap();
+        assert_eq!(pks[2].to_vec(), pk);
+        assert_eq!(updated, by_age);
+
+        // update on a missing key creates it, with its indexes
+        let created = map
+            .update(&mut store, b"6", |d| -> StdResult<_> {
+                assert_eq!(None, d);
+                Ok(Data {
+                    name: "Johnny".to_string(),
+                    last_name: "Cash".to_string(),
+                    age: 71,
+                })
+            })
+            .unwrap();
+        assert_eq!(2, count(&store, "Johnny"));
+        assert_eq!(
+            Some((b"6".to_vec(), created)),
+            map.idx.age.item(&store, U32Key::new(71)).unwrap()
+        );
+    }
+
+    #[test]
+    fn update_error_leaves_map_and_indexes_untouched() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+        let (pks, datas) = save_data(&mut store, &map);
+        let before: Vec<_> = store.range(None, None, Order::Ascending).collect();
+
+        // the closure changes indexed fields, then fails
+        let err = map
+            .update(&mut store, pks[0], |d| {
+                let mut x = d.unwrap();
+                x.name = "Mary".to_string();
+                x.age = 1;
+                if x.age < 18 {
+                    return Err(StdError::generic_err("too young"));
+                }
+                Ok(x)
+            })
+            .unwrap_err();
+        assert_eq!(StdError::generic_err("too young"), err);
+
+        let after: Vec<_> = store.range(None, None, Order::Ascending).collect();
+        assert_eq!(before, after);
+        assert_eq!(datas[0], map.load(&store, pks[0]).unwrap());
+        assert_eq!(None, map.idx.age.item(&store, U32Key::new(1)).unwrap());
+        assert!(map.idx.age.item(&store, U32Key::new(42)).unwrap().is_some());
+    }
+
+    #[test]
+    fn has_does_not_parse_values() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+        let (pks, _) = save_data(&mut store, &map);
+
+        assert!(map.has(&store, pks[0]));
+        assert!(!map.has(&store, b"unknown"));
+
+        // the value can't be parsed anymore, but is still there
+        store.set(&map.key(pks[0]), b"{garbage");
+        assert!(map.has(&store, pks[0]));
+        map.may_load(&store, pks[0]).unwrap_err();
+    }
+
     #[test]
     fn unique_index_simple_key_range() {
         let mut store = MockStorage::new();
@@ -702,4 +1184,350 @@ mod test {
         assert_eq!(datas[0], marias[0].1);
         assert_eq!(datas[1], marias[1].1);
     }
+
+    #[test]
+    fn range_by_index_value() {
+        use crate::U64Key;
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+        struct LimitOrder {
+            pub id: u64,
+            pub price: u64,
+        }
+
+        struct OrderIndexes<'a> {
+            pub price: MultiIndex<'a, U64Key, LimitOrder>,
+            pub id: UniqueIndex<'a, U64Key, LimitOrder>,
+        }
+
+        impl<'a> IndexList<LimitOrder> for OrderIndexes<'a> {
+            fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<LimitOrder>> + '_> {
+                let v: Vec<&dyn Index<LimitOrder>> = vec![&self.price, &self.id];
+                Box::new(v.into_iter())
+            }
+        }
+
+        let mut store = MockStorage::new();
+        let indexes = OrderIndexes {
+            price: MultiIndex::new(|o| U64Key::new(o.price), "orders", "orders__price"),
+            id: UniqueIndex::new(|o| U64Key::new(o.id), "orders__id"),
+        };
+        let map: IndexedMap<&[u8], LimitOrder, OrderIndexes> = IndexedMap::new("orders", indexes);
+
+        // several orders share a price
+        let orders: [(&[u8], u64, u64); 6] = [
+            (b"a", 1, 300),
+            (b"b", 2, 100),
+            (b"c", 3, 200),
+            (b"d", 4, 300),
+            (b"e", 5, 200),
+            (b"f", 6, 400),
+        ];
+        for (pk, id, price) in orders.iter() {
+            let order = LimitOrder {
+                id: *id,
+                price: *price,
+            };
+            map.save(&mut store, *pk, &order).unwrap();
+        }
+
+        let by_price = |min, max, order| -> Vec<Vec<u8>> {
+            map.idx
+                .price
+                .range_by_value(&store, min, max, order)
+                .map(|r| r.unwrap().0)
+                .collect()
+        };
+        let pks = |pks: &[&[u8]]| -> Vec<Vec<u8>> { pks.iter().map(|p| p.to_vec()).collect() };
+        let price = U64Key::new;
+
+        // all orders, in price then pk order
+        assert_eq!(
+            by_price(Unbounded, Unbounded, Order::Ascending),
+            pks(&[b"b", b"c", b"e", b"a", b"d", b"f"])
+        );
+        // inclusive bounds include all the pks with that price
+        assert_eq!(
+            by_price(Included(price(200)), Included(price(300)), Order::Ascending),
+            pks(&[b"c", b"e", b"a", b"d"])
+        );
+        // exclusive bounds exclude all of them
+        assert_eq!(
+            by_price(Excluded(price(200)), Excluded(price(400)), Order::Ascending),
+            pks(&[b"a", b"d"])
+        );
+        assert_eq!(
+            by_price(Excluded(price(100)), Included(price(200)), Order::Ascending),
+            pks(&[b"c", b"e"])
+        );
+        // bounds between the existing prices
+        assert_eq!(
+            by_price(Included(price(150)), Excluded(price(301)), Order::Ascending),
+            pks(&[b"c", b"e", b"a", b"d"])
+        );
+        // descending
+        assert_eq!(
+            by_price(
+                Included(price(200)),
+                Included(price(300)),
+                Order::Descending
+            ),
+            pks(&[b"d", b"a", b"e", b"c"])
+        );
+        assert_eq!(
+            by_price(Unbounded, Excluded(price(300)), Order::Descending),
+            pks(&[b"e", b"c", b"b"])
+        );
+        let values: Vec<_> = map
+            .idx
+            .price
+            .range_by_value(&store, Excluded(price(300)), Unbounded, Order::Ascending)
+            .map(|r| r.unwrap().1)
+            .collect();
+        assert_eq!(values, vec![LimitOrder { id: 6, price: 400 }]);
+
+        // same on the unique index
+        let by_id: Vec<_> = map
+            .idx
+            .id
+            .range_by_value(
+                &store,
+                Excluded(U64Key::new(2)),
+                Included(U64Key::new(4)),
+                Order::Descending,
+            )
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(by_id, pks(&[b"d", b"c"]));
+        let by_id: Vec<_> = map
+            .idx
+            .id
+            .range_by_value(
+                &store,
+                Included(U64Key::new(5)),
+                Unbounded,
+                Order::Ascending,
+            )
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(by_id, pks(&[b"e", b"f"]));
+    }
+
+    #[test]
+    fn multi_index_typed_composite_key() {
+        use crate::U64Key;
+        use cosmwasm_std::Addr;
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+        struct Grant {
+            pub owner: Addr,
+            pub expires: u64,
+        }
+
+        struct GrantIndexes<'a> {
+            pub owner_expires: MultiIndex<'a, (Vec<u8>, U64Key), Grant>,
+        }
+
+        impl<'a> IndexList<Grant> for GrantIndexes<'a> {
+            fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Grant>> + '_> {
+                let v: Vec<&dyn Index<Grant>> = vec![&self.owner_expires];
+                Box::new(v.into_iter())
+            }
+        }
+
+        let mut store = MockStorage::new();
+        let indexes = GrantIndexes {
+            owner_expires: MultiIndex::new(
+                |g| (g.owner.as_bytes().to_vec(), U64Key::new(g.expires)),
+                "grants",
+                "grants__owner_expires",
+            ),
+        };
+        let map: IndexedMap<&[u8], Grant, GrantIndexes> = IndexedMap::new("grants", indexes);
+
+        let alice = Addr::unchecked("alice");
+        // a prefix of "alice", to ensure owners are not mixed up
+        let ali = Addr::unchecked("ali");
+        let grants: [(&[u8], Addr, u64); 5] = [
+            (b"1", alice.clone(), 300),
+            (b"2", alice.clone(), 100),
+            (b"3", ali.clone(), 200),
+            (b"4", alice.clone(), 300),
+            (b"5", alice.clone(), 200),
+        ];
+        for (pk, owner, expires) in grants.iter() {
+            let grant = Grant {
+                owner: owner.clone(),
+                expires: *expires,
+            };
+            map.save(&mut store, *pk, &grant).unwrap();
+        }
+
+        // all alice grants, sorted by expiration then pk
+        let pks: Vec<_> = map
+            .idx
+            .owner_expires
+            .sub_prefix(alice.as_bytes().to_vec())
+            .range(&store, None, None, Order::Ascending)
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(
+            pks,
+            vec![b"2".to_vec(), b"5".to_vec(), b"1".to_vec(), b"4".to_vec()]
+        );
+
+        // exact (owner, expiration) match
+        let pks = map
+            .idx
+            .owner_expires
+            .all_pks(&store, (alice.as_bytes().to_vec(), U64Key::new(300)));
+        assert_eq!(pks, vec![b"1".to_vec(), b"4".to_vec()]);
+        let count = map
+            .idx
+            .owner_expires
+            .count(&store, (ali.as_bytes().to_vec(), U64Key::new(300)));
+        assert_eq!(count, 0);
+
+        // everything expiring after 100 for alice (and any owner sorted after alice)
+        let start = map
+            .idx
+            .owner_expires
+            .index_key((alice.as_bytes().to_vec(), U64Key::new(101)), b"");
+        let items: Vec<_> = map
+            .idx
+            .owner_expires
+            .range(
+                &store,
+                Some(Bound::inclusive(start)),
+                None,
+                Order::Descending,
+            )
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(3, items.len());
+        assert_eq!(b"4", items[0].0.as_slice());
+        assert_eq!(300, items[0].1.expires);
+        assert_eq!(b"5", items[2].0.as_slice());
+
+        // range over the whole index, starting after the first alice grant
+        let start = map
+            .idx
+            .owner_expires
+            .index_key((alice.as_bytes().to_vec(), U64Key::new(100)), b"2");
+        let count = map
+            .idx
+            .owner_expires
+            .range(
+                &store,
+                Some(Bound::exclusive(start)),
+                None,
+                Order::Ascending,
+            )
+            .count();
+        assert_eq!(3, count);
+
+        // index is updated on removal
+        map.remove(&mut store, b"1").unwrap();
+        let pks = map
+            .idx
+            .owner_expires
+            .all_pks(&store, (alice.as_bytes().to_vec(), U64Key::new(300)));
+        assert_eq!(pks, vec![b"4".to_vec()]);
+    }
+
+    #[test]
+    fn sum_index_tracks_totals_per_group() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+        struct Delegation {
+            pub validator: String,
+            pub amount: Uint128,
+        }
+
+        struct DelegationIndexes<'a> {
+            pub validator: SumIndex<'a, Vec<u8>, Delegation>,
+        }
+
+        impl<'a> IndexList<Delegation> for DelegationIndexes<'a> {
+            fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Delegation>> + '_> {
+                let v: Vec<&dyn Index<Delegation>> = vec![&self.validator];
+                Box::new(v.into_iter())
+            }
+        }
+
+        let mut store = MockStorage::new();
+        let indexes = DelegationIndexes {
+            validator: SumIndex::new(
+                |d| d.validator.as_bytes().to_vec(),
+                |d| d.amount,
+                "delegations__validator",
+            ),
+        };
+        let map: IndexedMap<&[u8], Delegation, _> = IndexedMap::new("delegations", indexes);
+        let total = |store: &MockStorage, validator: &str| {
+            map.idx
+                .validator
+                .total(store, validator.as_bytes().to_vec())
+                .unwrap()
+                .u128()
+        };
+        let delegate = |store: &mut MockStorage, pk: &[u8], validator: &str, amount: u128| {
+            let d = Delegation {
+                validator: validator.to_string(),
+                amount: Uint128::new(amount),
+            };
+            map.save(store, pk, &d).unwrap();
+        };
+
+        delegate(&mut store, b"1", "val1", 100);
+        delegate(&mut store, b"2", "val1", 50);
+        delegate(&mut store, b"3", "val2", 70);
+        assert_eq!(150, total(&store, "val1"));
+        assert_eq!(70, total(&store, "val2"));
+        assert_eq!(0, total(&store, "val3"));
+
+        // saving over an existing entry replaces its amount
+        delegate(&mut store, b"1", "val1", 120);
+        assert_eq!(170, total(&store, "val1"));
+
+        // moving an entry to another group subtracts from the old one, and adds to the new one
+        map.update(&mut store, b"2", |d| -> StdResult<_> {
+            let mut d = d.unwrap();
+            d.validator = "val2".to_string();
+            d.amount += Uint128::new(5);
+            Ok(d)
+        })
+        .unwrap();
+        assert_eq!(120, total(&store, "val1"));
+        assert_eq!(125, total(&store, "val2"));
+
+        // moving to a new group creates it
+        delegate(&mut store, b"3", "val3", 70);
+        assert_eq!(55, total(&store, "val2"));
+        assert_eq!(70, total(&store, "val3"));
+
+        // removing the last entry of a group removes its total
+        map.remove(&mut store, b"1").unwrap();
+        assert_eq!(0, total(&store, "val1"));
+        let totals: Vec<_> = map
+            .idx
+            .validator
+            .range(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(
+            totals,
+            vec![
+                (b"val2".to_vec(), Uint128::new(55)),
+                (b"val3".to_vec(), Uint128::new(70))
+            ]
+        );
+
+        // the totals always match the sum of the entries
+        let sum: u128 = map
+            .range(&store, None, None, Order::Ascending)
+            .map(|r| r.unwrap().1.amount.u128())
+            .sum();
+        assert_eq!(sum, total(&store, "val2") + total(&store, "val3"));
+    }
 }
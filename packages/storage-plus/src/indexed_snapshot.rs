@@ -10,7 +10,8 @@ use crate::prefix::{Bound, Prefix};
 use crate::snapshot::SnapshotMap;
 use crate::{IndexList, Path, Strategy};
 
-/// IndexedSnapshotMap works like a SnapshotMap but has a secondary index
+/// IndexedSnapshotMap works like a SnapshotMap but has a secondary index.
+/// Only the primary data is snapshotted, the indexes always reflect the current state.
 pub struct IndexedSnapshotMap<'a, K, T, I>
 where
     K: PrimaryKey<'a>,
@@ -51,6 +52,14 @@ where
     pub fn remove_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
         self.primary.remove_checkpoint(store, height)
     }
+
+    /// list_checkpoints returns the heights with an open checkpoint, from lowest to highest
+    pub fn list_checkpoints<'c>(
+        &self,
+        store: &'c dyn Storage,
+    ) -> Box<dyn Iterator<Item = StdResult<u64>> + 'c> {
+        self.primary.list_checkpoints(store)
+    }
 }
 
 impl<'a, K, T, I> IndexedSnapshotMap<'a, K, T, I>
@@ -161,6 +170,12 @@ where
         self.primary.may_load(store, key)
     }
 
+    /// has returns true or false if any data is at this key, without parsing or interpreting the
+    /// contents.
+    pub fn has(&self, store: &dyn Storage, key: K) -> bool {
+        self.primary.has(store, key)
+    }
+
     // use prefix to scan -> range
     pub fn prefix(&self, p: K::Prefix) -> Prefix<T> {
         Prefix::new(self.pk_namespace, &p.prefix())
@@ -201,7 +216,7 @@ where
 mod test {
     use super::*;
 
-    use crate::indexes::{index_string_tuple, index_triple, MultiIndex, UniqueIndex};
+    use crate::indexes::{index_string_tuple, index_tuple, MultiIndex, UniqueIndex};
     use crate::{Index, U32Key};
     use cosmwasm_std::testing::MockStorage;
     use cosmwasm_std::{MemoryStorage, Order};
@@ -215,8 +230,7 @@ mod test {
     }
 
     struct DataIndexes<'a> {
-        // Second arg is for storing pk
-        pub name: MultiIndex<'a, (Vec<u8>, Vec<u8>), Data>,
+        pub name: MultiIndex<'a, Vec<u8>, Data>,
         pub age: UniqueIndex<'a, U32Key, Data>,
         pub name_lastname: UniqueIndex<'a, (Vec<u8>, Vec<u8>), Data>,
     }
@@ -231,8 +245,7 @@ mod test {
 
     // For composite multi index tests
     struct DataCompositeMultiIndex<'a> {
-        // Third arg needed for storing pk
-        pub name_age: MultiIndex<'a, (Vec<u8>, U32Key, Vec<u8>), Data>,
+        pub name_age: MultiIndex<'a, (Vec<u8>, U32Key), Data>,
     }
 
     // Future Note: this can likely be macro-derived
@@ -246,7 +259,7 @@ mod test {
     // Can we make it easier to define this? (less wordy generic)
     fn build_snapshot_map<'a>() -> IndexedSnapshotMap<'a, &'a [u8], Data, DataIndexes<'a>> {
         let indexes = DataIndexes {
-            name: MultiIndex::new(|d, k| (d.name.as_bytes().to_vec(), k), "data", "data__name"),
+            name: MultiIndex::new(|d| d.name.as_bytes().to_vec(), "data", "data__name"),
             age: UniqueIndex::new(|d| U32Key::new(d.age), "data__age"),
             name_lastname: UniqueIndex::new(
                 |d| index_string_tuple(&d.name, &d.last_name),
@@ -460,11 +473,7 @@ mod test {
         let mut height = 2;
 
         let indexes = DataCompositeMultiIndex {
-            name_age: MultiIndex::new(
-                |d, k| index_triple(&d.name, d.age, k),
-                "data",
-                "data__name_age",
-            ),
+            name_age: MultiIndex::new(|d| index_tuple(&d.name, d.age), "data", "data__name_age"),
         };
         let map =
             IndexedSnapshotMap::new("data", "checks", "changes", Strategy::EveryBlock, indexes);
@@ -642,6 +651,67 @@ mod test {
         assert_eq!(name_count(&map, &store, "Mary"), 1);
     }
 
+    #[test]
+    fn height_queries_ignore_index_state() {
+        let mut store = MockStorage::new();
+        let map = build_snapshot_map();
+
+        // save data (heights 0 to 3)
+        let (pks, datas) = save_data(&mut store, &map);
+
+        // remove maria 2 at 5, and change john to mary at 6
+        map.remove(&mut store, pks[1], 5).unwrap();
+        map.update(&mut store, pks[2], 6, |d| -> StdResult<_> {
+            let mut x = d.unwrap();
+            x.name = "Mary".to_string();
+            Ok(x)
+        })
+        .unwrap();
+
+        // indexes only reflect the current state
+        let marias = map
+            .idx
+            .name
+            .pks(&store, b"Maria".to_vec(), None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(marias, vec![pks[0].to_vec()]);
+        let johns = map
+            .idx
+            .name
+            .pks(&store, b"John".to_vec(), None, None, Order::Ascending)
+            .count();
+        assert_eq!(johns, 0);
+        assert_eq!(map.idx.age.item(&store, U32Key::new(23)).unwrap(), None);
+
+        // while the history is still available
+        assert_eq!(
+            map.may_load_at_height(&store, pks[1], 5).unwrap(),
+            Some(datas[1].clone())
+        );
+        assert_eq!(map.may_load_at_height(&store, pks[1], 6).unwrap(), None);
+        assert_eq!(
+            map.may_load_at_height(&store, pks[2], 6).unwrap(),
+            Some(datas[2].clone())
+        );
+        let mary = map.may_load_at_height(&store, pks[2], 7).unwrap().unwrap();
+        assert_eq!(mary.name, "Mary");
+
+        // the freed unique index value can be taken by another key, history doesn't block it
+        let data = Data {
+            name: "Pedro".to_string(),
+            last_name: "Perez".to_string(),
+            age: 23,
+        };
+        map.save(&mut store, b"6", &data, 7).unwrap();
+        let (pk, _) = map.idx.age.item(&store, U32Key::new(23)).unwrap().unwrap();
+        assert_eq!(pk, b"6".to_vec());
+        assert_eq!(
+            map.may_load_at_height(&store, pks[1], 5).unwrap(),
+            Some(datas[1].clone())
+        );
+    }
+
     #[test]
     fn unique_index_simple_key_range() {
         let mut store = MockStorage::new();
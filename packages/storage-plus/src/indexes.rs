@@ -3,12 +3,14 @@
 
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::ops::Bound::{Excluded, Included, Unbounded};
 
-use cosmwasm_std::{from_slice, Binary, Order, Pair, StdError, StdResult, Storage};
+use cosmwasm_std::{from_slice, Binary, Order, Pair, StdError, StdResult, Storage, Uint128};
 
 use crate::helpers::namespaces_with_key;
 use crate::keys::EmptyPrefix;
 use crate::map::Map;
+use crate::prefix::{namespace_upper_bound, range_with_prefix};
 use crate::{Bound, Prefix, Prefixer, PrimaryKey, U32Key};
 
 pub fn index_string(data: &str) -> Vec<u8> {
@@ -45,26 +47,23 @@ where
 /// The stored pk_len is used to recover the pk from the index namespace, and perform
 /// the secondary load of the associated value from the main map.
 ///
-/// The MultiIndex definition must include a field for the pk. That is, the MultiIndex K value
-/// is always a n-tuple (n >= 2) and its last element must be the pk.
-/// The index function must therefore put the pk as last element, when generating the index.
-pub struct MultiIndex<'a, K, T> {
-    index: fn(&T, Vec<u8>) -> K,
+/// The index function only returns the typed index value `IK` (which may be a tuple,
+/// e.g. `(Vec<u8>, U64Key)`). The pk is appended to it when the index is stored, so
+/// there is no need to include it in `IK`.
+pub struct MultiIndex<'a, IK, T> {
+    index: fn(&T) -> IK,
     idx_namespace: &'a [u8],
-    idx_map: Map<'a, K, u32>,
+    // the key is the joined (idx_value, pk), built by `index_key`
+    idx_map: Map<'a, Vec<u8>, u32>,
     pk_namespace: &'a [u8],
 }
 
-impl<'a, K, T> MultiIndex<'a, K, T>
+impl<'a, IK, T> MultiIndex<'a, IK, T>
 where
     T: Serialize + DeserializeOwned + Clone,
 {
     // TODO: make this a const fn
-    pub fn new(
-        idx_fn: fn(&T, Vec<u8>) -> K,
-        pk_namespace: &'a str,
-        idx_namespace: &'a str,
-    ) -> Self {
+    pub fn new(idx_fn: fn(&T) -> IK, pk_namespace: &'a str, idx_namespace: &'a str) -> Self {
         MultiIndex {
             index: idx_fn,
             idx_namespace: idx_namespace.as_bytes(),
@@ -74,19 +73,22 @@ where
     }
 }
 
+/// extract_pk recovers the pk from the last part of a raw index key, using the stored pk_len
+fn extract_pk<'k>(key: &'k [u8], pk_len: &[u8]) -> StdResult<&'k [u8]> {
+    let pk_len = from_slice::<u32>(pk_len)? as usize;
+    if pk_len > key.len() {
+        return Err(StdError::invalid_data_size(pk_len, key.len()));
+    }
+    Ok(&key[key.len() - pk_len..])
+}
+
 fn deserialize_multi_kv<T: DeserializeOwned>(
     store: &dyn Storage,
     pk_namespace: &[u8],
     kv: Pair,
 ) -> StdResult<Pair<T>> {
     let (key, pk_len) = kv;
-
-    // Deserialize pk_len
-    let pk_len = from_slice::<u32>(pk_len.as_slice())?;
-
-    // Recover pk from last part of k
-    let offset = key.len() - pk_len as usize;
-    let pk = &key[offset..];
+    let pk = extract_pk(&key, &pk_len)?;
 
     let full_key = namespaces_with_key(&[pk_namespace], pk);
 
@@ -98,29 +100,38 @@ fn deserialize_multi_kv<T: DeserializeOwned>(
     Ok((pk.into(), v))
 }
 
-impl<'a, K, T> Index<T> for MultiIndex<'a, K, T>
+impl<'a, IK, T> Index<T> for MultiIndex<'a, IK, T>
 where
     T: Serialize + DeserializeOwned + Clone,
-    K: PrimaryKey<'a>,
+    IK: PrimaryKey<'a>,
 {
     fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &T) -> StdResult<()> {
-        let idx = (self.index)(data, pk.to_vec());
+        let idx = self.index_key((self.index)(data), pk);
         self.idx_map.save(store, idx, &(pk.len() as u32))
     }
 
     fn remove(&self, store: &mut dyn Storage, pk: &[u8], old_data: &T) -> StdResult<()> {
-        let idx = (self.index)(old_data, pk.to_vec());
+        let idx = self.index_key((self.index)(old_data), pk);
         self.idx_map.remove(store, idx);
         Ok(())
     }
 }
 
-impl<'a, K, T> MultiIndex<'a, K, T>
+impl<'a, IK, T> MultiIndex<'a, IK, T>
 where
     T: Serialize + DeserializeOwned + Clone,
-    K: PrimaryKey<'a>,
+    IK: PrimaryKey<'a>,
 {
-    pub fn prefix(&self, p: K::Prefix) -> Prefix<T> {
+    /// index_key returns the raw key stored for the given index value and pk, that is,
+    /// all the elements of `k` (length-prefixed) followed by the pk.
+    /// The pk may be empty, so that it can be used as a bound to iterate from the first
+    /// element that matches the index value.
+    pub fn index_key(&self, k: IK, pk: &[u8]) -> Vec<u8> {
+        let keys = k.key();
+        namespaces_with_key(&keys, pk)
+    }
+
+    pub fn sub_prefix(&self, p: IK::Prefix) -> Prefix<T> {
         Prefix::with_deserialization_function(
             self.idx_namespace,
             &p.prefix(),
@@ -129,24 +140,82 @@ where
         )
     }
 
-    pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<T> {
+    // I would prefer not to copy code from Prefix, but no other way
+    // with lifetimes (create Prefix inside function and return ref = no no)
+    pub fn range<'c>(
+        &'c self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<Pair<T>>> + 'c>
+    where
+        T: 'c,
+    {
         Prefix::with_deserialization_function(
             self.idx_namespace,
-            &p.prefix(),
+            &[],
             self.pk_namespace,
             deserialize_multi_kv,
         )
+        .range(store, min, max, order)
     }
 
-    pub fn index_key(&self, k: K) -> Vec<u8> {
-        k.joined_key()
+    /// range_by_value iterates over the items with an index value between min and max,
+    /// in index order (and pk order for equal index values), eg. all the orders with a
+    /// price in some range. The bounds are index values, and apply to all their pks.
+    pub fn range_by_value<'c>(
+        &'c self,
+        store: &'c dyn Storage,
+        min: std::ops::Bound<IK>,
+        max: std::ops::Bound<IK>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<Pair<T>>> + 'c>
+    where
+        T: 'c,
+    {
+        // the raw index keys of a value all start with index_key(value, b"")
+        let min = match min {
+            Included(k) => Some(Bound::Inclusive(self.index_key(k, b""))),
+            Excluded(k) => Some(Bound::Inclusive(namespace_upper_bound(
+                &self.index_key(k, b""),
+            ))),
+            Unbounded => None,
+        };
+        let max = match max {
+            Included(k) => Some(Bound::Exclusive(namespace_upper_bound(
+                &self.index_key(k, b""),
+            ))),
+            Excluded(k) => Some(Bound::Exclusive(self.index_key(k, b""))),
+            Unbounded => None,
+        };
+        self.range(store, min, max, order)
     }
+}
 
-    // FIXME?: Move to Prefix<T> for ergonomics
+impl<'a, IK, T> MultiIndex<'a, IK, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    IK: PrimaryKey<'a> + Prefixer<'a>,
+{
+    /// prefix iterates over all the pks with the given (full) index value
+    pub fn prefix(&self, p: IK) -> Prefix<T> {
+        Prefix::with_deserialization_function(
+            self.idx_namespace,
+            &p.prefix(),
+            self.pk_namespace,
+            deserialize_multi_kv,
+        )
+    }
+
+    /// pks returns the primary keys with the given (full) index value, as used by
+    /// IndexedMap::load, in pk order. The bounds apply to the pks.
+    /// The pks are recovered from the index entries, the values are never loaded
+    /// from the main map, so it is cheap to count or paginate them.
     pub fn pks<'c>(
         &self,
         store: &'c dyn Storage,
-        p: K::Prefix,
+        p: IK,
         min: Option<Bound>,
         max: Option<Bound>,
         order: Order,
@@ -155,57 +224,48 @@ where
         T: 'c,
     {
         let prefix = self.prefix(p);
-        let mapped = prefix.range(store, min, max, order).map(|res| {
-            let t = res?;
-            Ok(t.0)
-        });
+        let mapped = range_with_prefix(store, &prefix, min, max, order)
+            .map(|(k, pk_len)| extract_pk(&k, &pk_len).map(|pk| pk.to_vec()));
         Box::new(mapped)
     }
 
-    #[cfg(test)]
-    pub fn count(&self, store: &dyn Storage, p: K::Prefix) -> usize {
-        self.pks(store, p, None, None, Order::Ascending).count()
+    /// keys works like pks, but returns the raw keys relative to the index value, without
+    /// parsing the stored pk length. These are the same as the pks, for a full index value.
+    pub fn keys<'c>(
+        &self,
+        store: &'c dyn Storage,
+        p: IK,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'c>
+    where
+        T: 'c,
+    {
+        self.prefix(p).keys(store, min, max, order)
+    }
+
+    /// count returns the number of pks with the given (full) index value, without loading
+    /// the values. Use `keys(..).count()` to only count the pks within some bounds.
+    pub fn count(&self, store: &dyn Storage, p: IK) -> usize {
+        self.prefix(p).count(store, None)
     }
 
     #[cfg(test)]
-    pub fn all_pks(&self, store: &dyn Storage, p: K::Prefix) -> Vec<Vec<u8>> {
+    pub fn all_pks(&self, store: &dyn Storage, p: IK) -> Vec<Vec<u8>> {
         self.pks(store, p, None, None, Order::Ascending)
             .collect::<StdResult<Vec<Vec<u8>>>>()
             .unwrap()
     }
 
     #[cfg(test)]
-    pub fn all_items(&self, store: &dyn Storage, prefix: K::Prefix) -> StdResult<Vec<Pair<T>>> {
+    pub fn all_items(&self, store: &dyn Storage, prefix: IK) -> StdResult<Vec<Pair<T>>> {
         self.prefix(prefix)
             .range(store, None, None, Order::Ascending)
             .collect()
     }
 }
 
-// short-cut for simple keys, rather than .prefix(()).range(...)
-impl<'a, K, T> MultiIndex<'a, K, T>
-where
-    T: Serialize + DeserializeOwned + Clone,
-    K: PrimaryKey<'a>,
-    K::SubPrefix: EmptyPrefix,
-{
-    // I would prefer not to copy code from Prefix, but no other way
-    // with lifetimes (create Prefix inside function and return ref = no no)
-    pub fn range<'c>(
-        &'c self,
-        store: &'c dyn Storage,
-        min: Option<Bound>,
-        max: Option<Bound>,
-        order: Order,
-    ) -> Box<dyn Iterator<Item = StdResult<Pair<T>>> + 'c>
-    where
-        T: 'c,
-    {
-        self.sub_prefix(K::SubPrefix::new())
-            .range(store, min, max, order)
-    }
-}
-
 #[derive(Deserialize, Serialize)]
 pub(crate) struct UniqueRef<T> {
     // note, we collapse the pk - combining everything under the namespace - even if it is composite
@@ -214,7 +274,13 @@ pub(crate) struct UniqueRef<T> {
 }
 
 /// UniqueIndex stores (namespace, index_name, idx_value) -> {key, value}
-/// Allows one value per index (i.e. unique) and copies pk and data
+/// Allows one value per index (i.e. unique) and copies pk and data.
+/// The pk is returned along with the data by `item()` and `range()`, so it can be used
+/// to modify the entry in the IndexedMap.
+///
+/// Note: indexes written by versions that only stored the value (without the pk) cannot be
+/// read by this layout. Such contracts must rebuild the index on migration, by removing the
+/// old index entries and re-saving every item of the IndexedMap.
 pub struct UniqueIndex<'a, K, T> {
     index: fn(&T) -> K,
     idx_map: Map<'a, K, UniqueRef<T>>,
@@ -239,11 +305,15 @@ where
 {
     fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &T) -> StdResult<()> {
         let idx = (self.index)(data);
-        // error if this is already set
+        // error if this is already set, with the pk of the entry that has it
         self.idx_map
             .update(store, idx, |existing| -> StdResult<_> {
                 match existing {
-                    Some(_) => Err(StdError::generic_err("Violates unique constraint on index")),
+                    Some(existing) => Err(StdError::generic_err(format!(
+                        "Violates unique constraint on index {}: already used by pk {}",
+                        String::from_utf8_lossy(self.idx_namespace),
+                        display_pk(&existing.pk)
+                    ))),
                     None => Ok(UniqueRef::<T> {
                         pk: pk.into(),
                         value: data.clone(),
@@ -260,6 +330,20 @@ where
     }
 }
 
+/// display_pk shows a (raw) pk in error messages, as text if it is printable, or as hex
+/// otherwise (eg. for integer or composite keys)
+fn display_pk(pk: &[u8]) -> String {
+    match std::str::from_utf8(pk) {
+        Ok(text) if !text.is_empty() && text.bytes().all(|b| b.is_ascii_graphic() || b == b' ') => {
+            text.to_string()
+        }
+        _ => format!(
+            "0x{}",
+            pk.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+        ),
+    }
+}
+
 fn deserialize_unique_kv<T: DeserializeOwned>(kv: Pair) -> StdResult<Pair<T>> {
     let (_, v) = kv;
     let t = from_slice::<UniqueRef<T>>(&v)?;
@@ -287,7 +371,14 @@ where
         })
     }
 
-    /// returns all items that match this secondary index, always by pk Ascending
+    /// may_item returns the (pk, data) pair of the item that already uses this index value,
+    /// if any. Check it before saving, to return a domain-specific error rather than
+    /// the generic unique constraint violation.
+    pub fn may_item(&self, store: &dyn Storage, idx: K) -> StdResult<Option<Pair<T>>> {
+        self.item(store, idx)
+    }
+
+    /// returns the (pk, data) pair of the item that matches this unique index, if any
     pub fn item(&self, store: &dyn Storage, idx: K) -> StdResult<Option<Pair<T>>> {
         let data = self
             .idx_map
@@ -319,4 +410,113 @@ where
         self.sub_prefix(K::SubPrefix::new())
             .range(store, min, max, order)
     }
+
+    /// range_by_value works like range, but with the bounds given as typed index values,
+    /// just like range_by_value on the MultiIndex
+    pub fn range_by_value<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: std::ops::Bound<K>,
+        max: std::ops::Bound<K>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<Pair<T>>> + 'c>
+    where
+        T: 'c,
+    {
+        let to_bound = |b: std::ops::Bound<K>| match b {
+            Included(k) => Some(Bound::inclusive(k)),
+            Excluded(k) => Some(Bound::exclusive(k)),
+            Unbounded => None,
+        };
+        self.range(store, to_bound(min), to_bound(max), order)
+    }
+}
+
+/// SumIndex maintains a running total of an amount per index value, eg. the total staked
+/// per validator for a map of delegations. It stores idx_value -> total in its own namespace,
+/// and is kept up to date by the IndexedMap on save, remove and replace, by subtracting the
+/// old amount from the old group and adding the new amount to the new group.
+/// Groups whose total drops to zero are removed.
+pub struct SumIndex<'a, K, T> {
+    index: fn(&T) -> K,
+    amount: fn(&T) -> Uint128,
+    totals: Map<'a, K, Uint128>,
+}
+
+impl<'a, K, T> SumIndex<'a, K, T> {
+    // TODO: make this a const fn
+    pub fn new(idx_fn: fn(&T) -> K, amount_fn: fn(&T) -> Uint128, idx_namespace: &'a str) -> Self {
+        SumIndex {
+            index: idx_fn,
+            amount: amount_fn,
+            totals: Map::new(idx_namespace),
+        }
+    }
+}
+
+impl<'a, K, T> Index<T> for SumIndex<'a, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a>,
+{
+    fn save(&self, store: &mut dyn Storage, _pk: &[u8], data: &T) -> StdResult<()> {
+        let amount = (self.amount)(data);
+        if amount.is_zero() {
+            return Ok(());
+        }
+        self.totals
+            .update(store, (self.index)(data), |total| -> StdResult<_> {
+                Ok(total.unwrap_or_default().checked_add(amount)?)
+            })?;
+        Ok(())
+    }
+
+    fn remove(&self, store: &mut dyn Storage, _pk: &[u8], old_data: &T) -> StdResult<()> {
+        let amount = (self.amount)(old_data);
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let idx = (self.index)(old_data);
+        let total = self
+            .totals
+            .may_load(store, idx.clone())?
+            .unwrap_or_default()
+            .checked_sub(amount)?;
+        if total.is_zero() {
+            self.totals.remove(store, idx);
+            Ok(())
+        } else {
+            self.totals.save(store, idx, &total)
+        }
+    }
+}
+
+impl<'a, K, T> SumIndex<'a, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a>,
+{
+    /// total returns the sum of the amounts of all the entries in this group, zero if none
+    pub fn total(&self, store: &dyn Storage, idx: K) -> StdResult<Uint128> {
+        Ok(self.totals.may_load(store, idx)?.unwrap_or_default())
+    }
+}
+
+// short-cut for simple keys, rather than .prefix(()).range(...)
+impl<'a, K, T> SumIndex<'a, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a>,
+    K::SubPrefix: EmptyPrefix,
+{
+    /// range returns the (non-zero) totals of all the groups, as (raw idx_value, total) pairs
+    pub fn range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<Pair<Uint128>>> + 'c> {
+        self.totals.range(store, min, max, order)
+    }
 }
@@ -1,42 +1,80 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::any::type_name;
 use std::marker::PhantomData;
 
-use cosmwasm_std::{to_vec, StdError, StdResult, Storage};
+use cosmwasm_std::{
+    Addr, OverflowError, OverflowOperation, QuerierWrapper, StdError, StdResult, Storage,
+};
 
-use crate::helpers::{may_deserialize, must_deserialize};
+use crate::describe::StorageDescription;
+use crate::helpers::{may_deserialize_with, must_deserialize_with};
+use crate::lazy::Lazy;
+use crate::serialization::{Json, Serde};
 
 /// Item stores one typed item at the given key.
 /// This is an analog of Singleton.
 /// It functions just as Path but doesn't ue a Vec and thus has a const fn constructor.
-pub struct Item<'a, T> {
+///
+/// The value is encoded with the Serde strategy `S`, which is Json by default.
+pub struct Item<'a, T, S = Json> {
     // this is full key - no need to length-prefix it, we only store one item
     storage_key: &'a [u8],
     // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
     data_type: PhantomData<T>,
+    serde: PhantomData<S>,
 }
 
 impl<'a, T> Item<'a, T> {
     pub const fn new(storage_key: &'a str) -> Self {
+        Item::with_serde(storage_key)
+    }
+}
+
+impl<'a, T, S> Item<'a, T, S> {
+    /// with_serde works like new, but encodes the value with the given Serde strategy, eg.
+    /// `const CONFIG: Item<Config, Bincode> = Item::with_serde("config");`
+    pub const fn with_serde(storage_key: &'a str) -> Self {
         Item {
             storage_key: storage_key.as_bytes(),
             data_type: PhantomData,
+            serde: PhantomData,
+        }
+    }
+
+    /// describe returns the storage key and the value type name, see StorageDescription
+    pub fn describe(&self) -> StorageDescription<'a> {
+        StorageDescription {
+            kind: "Item",
+            namespace: self.storage_key,
+            key_type: None,
+            value_type: type_name::<T>(),
         }
     }
 }
 
-impl<'a, T> Item<'a, T>
+impl<'a, T, S> Item<'a, T, S>
 where
     T: Serialize + DeserializeOwned,
+    S: Serde,
 {
     // this gets the path of the data to use elsewhere
     pub fn as_slice(&self) -> &[u8] {
         self.storage_key
     }
 
+    /// lazy returns a Lazy, which caches the value for repeated loads within one call
+    pub fn lazy(&self) -> Lazy<'a, T, S> {
+        Lazy::new(Item {
+            storage_key: self.storage_key,
+            data_type: PhantomData,
+            serde: PhantomData,
+        })
+    }
+
     /// save will serialize the model and store, returns an error on serialization issues
     pub fn save(&self, store: &mut dyn Storage, data: &T) -> StdResult<()> {
-        store.set(self.storage_key, &to_vec(data)?);
+        store.set(self.storage_key, &S::to_vec(data)?);
         Ok(())
     }
 
@@ -47,14 +85,28 @@ where
     /// load will return an error if no data is set at the given key, or on parse error
     pub fn load(&self, store: &dyn Storage) -> StdResult<T> {
         let value = store.get(self.storage_key);
-        must_deserialize(&value)
+        must_deserialize_with::<S, T>(&value)
     }
 
     /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
     /// returns an error on issues parsing
     pub fn may_load(&self, store: &dyn Storage) -> StdResult<Option<T>> {
         let value = store.get(self.storage_key);
-        may_deserialize(&value)
+        may_deserialize_with::<S, T>(&value)
+    }
+
+    /// query reads the item from the storage of another contract, with a raw query.
+    /// The other contract must store it at the same key, with the same encoding.
+    /// Returns Ok(None) if there is no data there.
+    pub fn query(&self, querier: &QuerierWrapper, remote_contract: Addr) -> StdResult<Option<T>> {
+        let value = querier.query_wasm_raw(remote_contract, self.storage_key)?;
+        may_deserialize_with::<S, T>(&value)
+    }
+
+    /// load_or works like load, but returns the given default if no data is set at the key.
+    /// It still returns an error on parse error.
+    pub fn load_or(&self, store: &dyn Storage, default: T) -> StdResult<T> {
+        Ok(self.may_load(store)?.unwrap_or(default))
     }
 
     /// Loads the data, perform the specified action, and store the result
@@ -71,16 +123,149 @@ where
         self.save(store, &output)?;
         Ok(output)
     }
+
+    /// update_or_default works like update, but calls `action` with the default value
+    /// if there is no data yet, instead of returning an error.
+    pub fn update_or_default<A, E>(&self, store: &mut dyn Storage, action: A) -> Result<T, E>
+    where
+        A: FnOnce(T) -> Result<T, E>,
+        E: From<StdError>,
+        T: Default,
+    {
+        let input = self.may_load(store)?.unwrap_or_default();
+        let output = action(input)?;
+        self.save(store, &output)?;
+        Ok(output)
+    }
+
+    /// update_old loads the data (if any), performs the specified action, and stores the result.
+    /// Unlike update, this also works when there is no data yet (`action(None)` is called),
+    /// and returns the previous value along with the new one, eg. to report both in events.
+    pub fn update_old<A, E>(&self, store: &mut dyn Storage, action: A) -> Result<(Option<T>, T), E>
+    where
+        A: FnOnce(Option<T>) -> Result<T, E>,
+        E: From<StdError>,
+        T: Clone,
+    {
+        let old = self.may_load(store)?;
+        let output = action(old.clone())?;
+        self.save(store, &output)?;
+        Ok((old, output))
+    }
+}
+
+/// ItemWithDefault works like Item, but loads `T::default()` instead of returning NotFound
+/// when no data is set. Useful for counters and config flags.
+/// Note that the default is never written, until it is saved (or updated) explicitly.
+pub struct ItemWithDefault<'a, T, S = Json> {
+    item: Item<'a, T, S>,
+}
+
+impl<'a, T> ItemWithDefault<'a, T> {
+    pub const fn new(storage_key: &'a str) -> Self {
+        ItemWithDefault {
+            item: Item::new(storage_key),
+        }
+    }
+}
+
+impl<'a, T, S> ItemWithDefault<'a, T, S> {
+    /// with_serde works like new, but encodes the value with the given Serde strategy
+    pub const fn with_serde(storage_key: &'a str) -> Self {
+        ItemWithDefault {
+            item: Item::with_serde(storage_key),
+        }
+    }
+}
+
+impl<'a, T, S> ItemWithDefault<'a, T, S>
+where
+    T: Serialize + DeserializeOwned + Default,
+    S: Serde,
+{
+    // this gets the path of the data to use elsewhere
+    pub fn as_slice(&self) -> &[u8] {
+        self.item.as_slice()
+    }
+
+    /// save will serialize the model and store, returns an error on serialization issues
+    pub fn save(&self, store: &mut dyn Storage, data: &T) -> StdResult<()> {
+        self.item.save(store, data)
+    }
+
+    /// remove deletes the data, so the default is loaded again
+    pub fn remove(&self, store: &mut dyn Storage) {
+        self.item.remove(store)
+    }
+
+    /// load returns the default if no data is set at the key, or an error on parse error
+    pub fn load(&self, store: &dyn Storage) -> StdResult<T> {
+        Ok(self.item.may_load(store)?.unwrap_or_default())
+    }
+
+    /// may_load returns Ok(None) if no data is set at the key, to tell apart an explicitly
+    /// stored default from missing data
+    pub fn may_load(&self, store: &dyn Storage) -> StdResult<Option<T>> {
+        self.item.may_load(store)
+    }
+
+    /// Loads the data (or the default), perform the specified action, and store the result.
+    pub fn update<A, E>(&self, store: &mut dyn Storage, action: A) -> Result<T, E>
+    where
+        A: FnOnce(T) -> Result<T, E>,
+        E: From<StdError>,
+    {
+        self.item.update_or_default(store, action)
+    }
+}
+
+/// Counter issues auto-incrementing u64 ids (eg. for proposals or escrows), storing the last
+/// issued id at the given key. The first id is 1, unless created with `with_start`.
+/// Keying a `Map<U64Key, T>` with these ids makes ascending ranges follow creation order.
+pub struct Counter<'a> {
+    last_id: Item<'a, u64>,
+    start: u64,
+}
+
+impl<'a> Counter<'a> {
+    pub const fn new(storage_key: &'a str) -> Self {
+        Counter::with_start(storage_key, 1)
+    }
+
+    /// with_start works like new, but the first id is start (eg. 0) instead of 1
+    pub const fn with_start(storage_key: &'a str, start: u64) -> Self {
+        Counter {
+            last_id: Item::new(storage_key),
+            start,
+        }
+    }
+
+    /// increment issues a new id, one more than the last one, and saves it.
+    /// Returns an error if the ids would overflow.
+    pub fn increment(&self, store: &mut dyn Storage) -> StdResult<u64> {
+        let id = match self.last_id.may_load(store)? {
+            Some(last) => last.checked_add(1).ok_or_else(|| {
+                StdError::overflow(OverflowError::new(OverflowOperation::Add, last, 1))
+            })?,
+            None => self.start,
+        };
+        self.last_id.save(store, &id)?;
+        Ok(id)
+    }
+
+    /// current returns the last issued id, or None if no id was issued yet
+    pub fn current(&self, store: &dyn Storage) -> StdResult<Option<u64>> {
+        self.last_id.may_load(store)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::to_vec;
     use serde::{Deserialize, Serialize};
 
-    use cosmwasm_std::{OverflowError, OverflowOperation, StdError};
-
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
     struct Config {
         pub owner: String,
@@ -106,6 +291,33 @@ mod test {
         assert_eq!(cfg, CONFIG.load(&store).unwrap());
     }
 
+    #[test]
+    fn describe_matches_key() {
+        let mut store = MockStorage::new();
+        let description = CONFIG.describe();
+        assert_eq!("Item", description.kind);
+        assert_eq!(b"config", description.namespace);
+        assert_eq!(None, description.key_type);
+        // the type name tells the value types apart
+        const OTHER_CONFIG: Item<Config> = Item::new("other");
+        assert_eq!(description.value_type, OTHER_CONFIG.describe().value_type);
+        const COUNT: Item<u64> = Item::new("count");
+        assert_ne!(description.value_type, COUNT.describe().value_type);
+        assert_eq!(CONFIG.as_slice(), description.namespace);
+
+        // stored at exactly the namespace
+        CONFIG
+            .save(
+                &mut store,
+                &Config {
+                    owner: "admin".to_string(),
+                    max_tokens: 1234,
+                },
+            )
+            .unwrap();
+        assert!(store.get(description.namespace).is_some());
+    }
+
     #[test]
     fn remove_works() {
         let mut store = MockStorage::new();
@@ -252,6 +464,105 @@ mod test {
         assert_eq!(CONFIG.load(&store).unwrap(), cfg);
     }
 
+    #[test]
+    fn update_or_default_works() {
+        const TOTAL: Item<u64> = Item::new("total");
+        let mut store = MockStorage::new();
+
+        // update fails without data, update_or_default creates it
+        TOTAL
+            .update(&mut store, |t| -> StdResult<_> { Ok(t + 5) })
+            .unwrap_err();
+        let total = TOTAL
+            .update_or_default(&mut store, |t| -> StdResult<_> { Ok(t + 5) })
+            .unwrap();
+        assert_eq!(5, total);
+
+        let total = TOTAL
+            .update_or_default(&mut store, |t| -> StdResult<_> { Ok(t + 7) })
+            .unwrap();
+        assert_eq!(12, total);
+        assert_eq!(12, TOTAL.load(&store).unwrap());
+    }
+
+    #[test]
+    fn load_or_works() {
+        let mut store = MockStorage::new();
+        const COUNTER: Item<u64> = Item::new("counter");
+
+        assert_eq!(7, COUNTER.load_or(&store, 7).unwrap());
+        COUNTER.save(&mut store, &3).unwrap();
+        assert_eq!(3, COUNTER.load_or(&store, 7).unwrap());
+
+        // parse errors are still reported
+        store.set(b"counter", b"nope");
+        COUNTER.load_or(&store, 7).unwrap_err();
+    }
+
+    #[test]
+    fn item_with_default_works() {
+        let mut store = MockStorage::new();
+        const COUNTER: ItemWithDefault<u64> = ItemWithDefault::new("counter");
+
+        // missing data loads the default, without writing it
+        assert_eq!(0, COUNTER.load(&store).unwrap());
+        assert_eq!(None, COUNTER.may_load(&store).unwrap());
+        assert_eq!(None, store.get(COUNTER.as_slice()));
+
+        // update starts from the default and persists the result
+        let count = COUNTER
+            .update(&mut store, |c| -> StdResult<_> { Ok(c + 5) })
+            .unwrap();
+        assert_eq!(5, count);
+        assert_eq!(Some(5), COUNTER.may_load(&store).unwrap());
+        let count = COUNTER
+            .update(&mut store, |c| -> StdResult<_> { Ok(c + 5) })
+            .unwrap();
+        assert_eq!(10, count);
+
+        // it is the same entry as the plain Item
+        let plain: Item<u64> = Item::new("counter");
+        assert_eq!(10, plain.load(&store).unwrap());
+
+        // errors don't write anything
+        let res = COUNTER.update(&mut store, |_| Err(StdError::generic_err("fail")));
+        res.unwrap_err();
+        assert_eq!(10, COUNTER.load(&store).unwrap());
+
+        // and remove goes back to the default
+        COUNTER.remove(&mut store);
+        assert_eq!(0, COUNTER.load(&store).unwrap());
+        COUNTER.save(&mut store, &0).unwrap();
+        assert_eq!(Some(0), COUNTER.may_load(&store).unwrap());
+    }
+
+    #[test]
+    fn update_old_returns_both_values() {
+        const COUNTER: Item<u64> = Item::new("counter");
+        let mut store = MockStorage::new();
+
+        // no prior value
+        let (old, new) = COUNTER
+            .update_old(&mut store, |c| -> StdResult<_> {
+                Ok(c.unwrap_or_default() + 1)
+            })
+            .unwrap();
+        assert_eq!(None, old);
+        assert_eq!(1, new);
+
+        let (old, new) = COUNTER
+            .update_old(&mut store, |c| -> StdResult<_> { Ok(c.unwrap() * 10) })
+            .unwrap();
+        assert_eq!(Some(1), old);
+        assert_eq!(10, new);
+        assert_eq!(10, COUNTER.load(&store).unwrap());
+
+        // nothing is written on error
+        let res = COUNTER.update_old(&mut store, |_| Err(StdError::generic_err("broken")));
+        assert_eq!(res.unwrap_err(), StdError::generic_err("broken"));
+        assert_eq!(10, COUNTER.load(&store).unwrap());
+    }
+
     #[test]
     fn readme_works() -> StdResult<()> {
         let mut store = MockStorage::new();
@@ -297,4 +608,59 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn counter_issues_increasing_ids() {
+        let mut store = MockStorage::new();
+        const IDS: Counter = Counter::new("proposal_count");
+        const FROM_ZERO: Counter = Counter::with_start("swap_count", 0);
+
+        assert_eq!(None, IDS.current(&store).unwrap());
+        assert_eq!(1, IDS.increment(&mut store).unwrap());
+        assert_eq!(2, IDS.increment(&mut store).unwrap());
+        assert_eq!(Some(2), IDS.current(&store).unwrap());
+
+        assert_eq!(None, FROM_ZERO.current(&store).unwrap());
+        assert_eq!(0, FROM_ZERO.increment(&mut store).unwrap());
+        assert_eq!(Some(0), FROM_ZERO.current(&store).unwrap());
+        assert_eq!(1, FROM_ZERO.increment(&mut store).unwrap());
+        // they don't interfere
+        assert_eq!(Some(2), IDS.current(&store).unwrap());
+
+        // the last id is stored as a plain u64, like a hand-written Item<u64>
+        const RAW: Item<u64> = Item::new("proposal_count");
+        assert_eq!(2, RAW.load(&store).unwrap());
+        RAW.save(&mut store, &u64::MAX).unwrap();
+        match IDS.increment(&mut store).unwrap_err() {
+            StdError::Overflow { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        assert_eq!(Some(u64::MAX), IDS.current(&store).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn counter_ids_range_in_creation_order() {
+        use crate::{Map, U64Key};
+        use cosmwasm_std::Order;
+
+        let mut store = MockStorage::new();
+        const IDS: Counter = Counter::new("escrow_count");
+        const ESCROWS: Map<U64Key, String> = Map::new("escrows");
+
+        // more than 255, so the ids don't sort just by their last byte
+        let names: Vec<String> = (0..300).map(|i| format!("escrow {}", i)).collect();
+        for name in names.iter() {
+            let id = IDS.increment(&mut store).unwrap();
+            ESCROWS.save(&mut store, id.into(), name).unwrap();
+        }
+        let all: Vec<_> = ESCROWS
+            .range_de(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        let ids: Vec<u64> = all.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, (1..=300).collect::<Vec<_>>());
+        let stored: Vec<String> = all.into_iter().map(|(_, name)| name).collect();
+        assert_eq!(names, stored);
+    }
 }
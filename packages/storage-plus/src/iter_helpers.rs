@@ -3,13 +3,14 @@
 use serde::de::DeserializeOwned;
 
 use cosmwasm_std::Pair;
-use cosmwasm_std::{from_slice, StdResult};
+use cosmwasm_std::StdResult;
 
 use crate::helpers::encode_length;
+use crate::serialization::Serde;
 
-pub(crate) fn deserialize_kv<T: DeserializeOwned>(kv: Pair) -> StdResult<Pair<T>> {
+pub(crate) fn deserialize_kv<S: Serde, T: DeserializeOwned>(kv: Pair) -> StdResult<Pair<T>> {
     let (k, v) = kv;
-    let t = from_slice::<T>(&v)?;
+    let t = S::from_slice::<T>(&v)?;
     Ok((k, t))
 }
 
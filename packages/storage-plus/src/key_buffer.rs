@@ -0,0 +1,178 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+
+use cosmwasm_std::{StdResult, Storage};
+
+use crate::helpers::{
+    check_namespaces, encode_length, may_deserialize_with, must_deserialize_with,
+};
+use crate::keys::PrimaryKey;
+use crate::serialization::{Json, Serde};
+
+/// KeyBuffer builds the keys of a Map in one reused buffer, created with `Map::with_prefix_buffer`.
+/// The length-prefixed namespace is only encoded once, and every key just replaces the bytes
+/// after it, so writing many entries in a loop doesn't allocate a new key each time.
+/// The keys are byte-identical to the ones of `Map::key`.
+///
+/// Borrowed keys (eg. `&Addr`) must outlive the buffer, so create it after the data
+/// the keys point to. Owned key types (eg. `U64Key` or `Addr`) have no such limit.
+pub struct KeyBuffer<'a, K, T, S = Json> {
+    // the length-prefixed namespace, followed by the last key
+    buffer: Vec<u8>,
+    prefix_len: usize,
+    // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
+    key_type: PhantomData<&'a K>,
+    data_type: PhantomData<T>,
+    serde: PhantomData<S>,
+}
+
+impl<'a, K, T, S> KeyBuffer<'a, K, T, S> {
+    pub(crate) fn new(namespace: &[u8]) -> Self {
+        let mut buffer = Vec::with_capacity(namespace.len() + 2);
+        buffer.extend_from_slice(&encode_length(namespace));
+        buffer.extend_from_slice(namespace);
+        let prefix_len = buffer.len();
+        KeyBuffer {
+            buffer,
+            prefix_len,
+            key_type: PhantomData,
+            data_type: PhantomData,
+            serde: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, T, S> KeyBuffer<'a, K, T, S>
+where
+    T: Serialize + DeserializeOwned,
+    K: PrimaryKey<'a>,
+    S: Serde,
+{
+    /// key returns the full raw key of k, valid until the next call.
+    /// Returns an error if a part of the key (other than the last one) is too long
+    /// to be length-prefixed, like Map::key_checked.
+    pub fn key(&mut self, k: K) -> StdResult<&[u8]> {
+        let keys = k.key();
+        let (last, rest) = match keys.split_last() {
+            Some((last, rest)) => (*last, rest),
+            None => (&[][..], &[][..]),
+        };
+        check_namespaces(rest)?;
+        self.buffer.truncate(self.prefix_len);
+        for segment in rest {
+            self.buffer.extend_from_slice(&encode_length(segment));
+            self.buffer.extend_from_slice(segment);
+        }
+        self.buffer.extend_from_slice(last);
+        Ok(&self.buffer)
+    }
+
+    pub fn save(&mut self, store: &mut dyn Storage, k: K, data: &T) -> StdResult<()> {
+        let value = S::to_vec(data)?;
+        store.set(self.key(k)?, &value);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, store: &mut dyn Storage, k: K) -> StdResult<()> {
+        store.remove(self.key(k)?);
+        Ok(())
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&mut self, store: &dyn Storage, k: K) -> StdResult<T> {
+        let value = store.get(self.key(k)?);
+        must_deserialize_with::<S, T>(&value)
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&mut self, store: &dyn Storage, k: K) -> StdResult<Option<T>> {
+        let value = store.get(self.key(k)?);
+        may_deserialize_with::<S, T>(&value)
+    }
+
+    /// has returns true or false if any data is at this key, without parsing or interpreting the
+    /// contents.
+    pub fn has(&mut self, store: &dyn Storage, k: K) -> StdResult<bool> {
+        Ok(store.get(self.key(k)?).is_some())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cosmwasm_std::testing::MockStorage;
+
+    use crate::{Map, U64Key, U8Key};
+
+    const BALANCES: Map<&[u8], u64> = Map::new("balances");
+    const TRIPLE: Map<(&[u8], U8Key, &str), u64> = Map::new("triple");
+
+    #[test]
+    fn keys_match_map_keys() {
+        let mut buffer = BALANCES.with_prefix_buffer();
+        for name in [
+            b"john".as_ref(),
+            b"",
+            b"a much longer name than before",
+            b"j",
+        ]
+        .iter()
+        {
+            let expected = BALANCES.key(name).to_vec();
+            assert_eq!(expected, buffer.key(name).unwrap());
+        }
+
+        let mut buffer = TRIPLE.with_prefix_buffer();
+        for (a, b, c) in [
+            (b"owner".as_ref(), 9u8, "recipient"),
+            (b"o", 10, ""),
+            (b"", 0, "recipient"),
+            (b"owner", 255, "r"),
+        ]
+        .iter()
+        {
+            let k = (*a, U8Key::new(*b), *c);
+            let expected = TRIPLE.key(k.clone()).to_vec();
+            assert_eq!(expected, buffer.key(k).unwrap());
+        }
+
+        const IDS: Map<U64Key, u64> = Map::new("ids");
+        let mut buffer = IDS.with_prefix_buffer();
+        for id in [0u64, 1, 256, u64::MAX].iter() {
+            let expected = IDS.key(U64Key::new(*id)).to_vec();
+            assert_eq!(expected, buffer.key(U64Key::new(*id)).unwrap());
+        }
+    }
+
+    #[test]
+    fn storage_is_shared_with_map() {
+        let mut store = MockStorage::new();
+        let names: Vec<String> = (0..500).map(|i| format!("user{}", i)).collect();
+        let mut buffer = BALANCES.with_prefix_buffer();
+        for (i, name) in names.iter().enumerate() {
+            buffer
+                .save(&mut store, name.as_bytes(), &(i as u64))
+                .unwrap();
+        }
+        assert_eq!(321, BALANCES.load(&store, b"user321").unwrap());
+
+        BALANCES.save(&mut store, b"other", &7).unwrap();
+        assert_eq!(7, buffer.load(&store, b"other").unwrap());
+        assert_eq!(Some(499), buffer.may_load(&store, b"user499").unwrap());
+        assert_eq!(None, buffer.may_load(&store, b"user500").unwrap());
+        buffer.load(&store, b"user500").unwrap_err();
+
+        assert!(buffer.has(&store, b"user0").unwrap());
+        buffer.remove(&mut store, b"user0").unwrap();
+        assert!(!buffer.has(&store, b"user0").unwrap());
+        assert!(!BALANCES.has(&store, b"user0"));
+
+        // too long key parts are an error, not a panic
+        let long = vec![b'x'; 0x10000];
+        let mut buffer = TRIPLE.with_prefix_buffer();
+        buffer
+            .save(&mut store, (&long, U8Key::new(1), "r"), &1)
+            .unwrap_err();
+    }
+}
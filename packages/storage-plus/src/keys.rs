@@ -1,4 +1,4 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Timestamp, Uint128};
 use std::marker::PhantomData;
 
 use crate::helpers::namespaces_with_key;
@@ -19,6 +19,20 @@ pub trait PrimaryKey<'a>: Clone {
     }
 }
 
+// the empty key is used for single-value stores built on top of keyed ones (eg. SnapshotItem)
+impl<'a> PrimaryKey<'a> for () {
+    type Prefix = ();
+    type SubPrefix = ();
+
+    fn key(&self) -> Vec<&[u8]> {
+        vec![]
+    }
+
+    fn joined_key(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
 impl<'a> PrimaryKey<'a> for &'a [u8] {
     type Prefix = ();
     type SubPrefix = ();
@@ -165,6 +179,23 @@ impl<'a> Prefixer<'a> for &'a Addr {
     }
 }
 
+/// owned version of the Addr key, with the same raw key as &Addr.
+/// eg. for keys coming straight from a deserialized message
+impl<'a> PrimaryKey<'a> for Addr {
+    type Prefix = ();
+    type SubPrefix = ();
+
+    fn key(&self) -> Vec<&[u8]> {
+        vec![self.as_ref().as_bytes()]
+    }
+}
+
+impl<'a> Prefixer<'a> for Addr {
+    fn prefix(&self) -> Vec<&[u8]> {
+        vec![self.as_ref().as_bytes()]
+    }
+}
+
 // this auto-implements PrimaryKey for all the IntKey types
 impl<'a, T: Endian + Clone> PrimaryKey<'a> for IntKey<T> {
     type Prefix = ();
@@ -200,6 +231,9 @@ pub type I128Key = IntKey<i128>;
 ///   let k = U64Key::new(12345);
 ///   let k = U32Key::from(12345);
 ///   let k: U16Key = 12345.into();
+///
+/// Signed ints are stored with the sign bit flipped (see `Endian::to_key_bytes`), so that
+/// iterating over the keys returns them in numeric order, negative values first.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct IntKey<T: Endian> {
     pub wrapped: Vec<u8>,
@@ -209,7 +243,7 @@ pub struct IntKey<T: Endian> {
 impl<T: Endian> IntKey<T> {
     pub fn new(val: T) -> Self {
         IntKey {
-            wrapped: val.to_be_bytes().into(),
+            wrapped: val.to_key_bytes().into(),
             data: PhantomData,
         }
     }
@@ -221,6 +255,12 @@ impl<T: Endian> From<T> for IntKey<T> {
     }
 }
 
+impl From<Uint128> for U128Key {
+    fn from(val: Uint128) -> Self {
+        U128Key::new(val.u128())
+    }
+}
+
 impl<T: Endian> From<Vec<u8>> for IntKey<T> {
     fn from(wrap: Vec<u8>) -> Self {
         // TODO: assert proper length
@@ -237,6 +277,44 @@ impl<T: Endian> From<IntKey<T>> for Vec<u8> {
     }
 }
 
+/// TimestampKey keys a Map by a Timestamp, stored as big endian nanoseconds.
+/// This makes ascending iteration chronological.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimestampKey(U64Key);
+
+impl TimestampKey {
+    pub fn new(ts: Timestamp) -> Self {
+        TimestampKey(ts.nanos().into())
+    }
+}
+
+impl<'a> PrimaryKey<'a> for TimestampKey {
+    type Prefix = ();
+    type SubPrefix = ();
+
+    fn key(&self) -> Vec<&[u8]> {
+        self.0.key()
+    }
+}
+
+impl<'a> Prefixer<'a> for TimestampKey {
+    fn prefix(&self) -> Vec<&[u8]> {
+        self.0.prefix()
+    }
+}
+
+impl From<Timestamp> for TimestampKey {
+    fn from(ts: Timestamp) -> Self {
+        TimestampKey::new(ts)
+    }
+}
+
+impl From<TimestampKey> for Vec<u8> {
+    fn from(k: TimestampKey) -> Vec<u8> {
+        k.0.into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -257,6 +335,74 @@ mod test {
         assert_eq!(4242u32.to_be_bytes().to_vec(), path[0].to_vec());
     }
 
+    #[test]
+    fn signed_int_keys_sort_numerically() {
+        let k: I32Key = (-1i32).into();
+        let path = k.key();
+        assert_eq!(1, path.len());
+        assert_eq!(vec![0x7f, 0xff, 0xff, 0xff], path[0].to_vec());
+
+        let k = I64Key::from(0i64);
+        assert_eq!(vec![0x80, 0, 0, 0, 0, 0, 0, 0], k.key()[0].to_vec());
+
+        let values = [i64::MIN, -1000, -5, -1, 0, 1, 5, 1000, i64::MAX];
+        let encoded: Vec<Vec<u8>> = values.iter().map(|v| I64Key::new(*v).into()).collect();
+        let mut sorted = encoded.clone();
+        sorted.sort();
+        assert_eq!(encoded, sorted);
+
+        let values = [i8::MIN, -1, 0, 1, i8::MAX];
+        let encoded: Vec<Vec<u8>> = values.iter().map(|v| I8Key::new(*v).into()).collect();
+        let mut sorted = encoded.clone();
+        sorted.sort();
+        assert_eq!(encoded, sorted);
+    }
+
+    #[test]
+    fn signed_int_keys_round_trip() {
+        use crate::KeyDeserialize;
+
+        for v in [i32::MIN, -5, 0, 5, i32::MAX].iter() {
+            let k = I32Key::new(*v);
+            assert_eq!(*v, I32Key::from_slice(&k.joined_key()).unwrap());
+        }
+        for v in [i64::MIN, -123456789, 0, 987654321, i64::MAX].iter() {
+            let k = I64Key::from(*v);
+            assert_eq!(*v, I64Key::from_vec(k.into()).unwrap());
+        }
+        let k = I16Key::new(-300);
+        assert_eq!(-300, I16Key::from_slice(&k.joined_key()).unwrap());
+
+        // unsigned keys are still plain big endian
+        let k = U32Key::new(4242);
+        assert_eq!(4242u32.to_be_bytes().to_vec(), k.joined_key());
+    }
+
+    #[test]
+    fn timestamp_key_works() {
+        let ts = Timestamp::from_seconds(1_000).plus_nanos(7);
+        let k: TimestampKey = ts.into();
+        let path = k.key();
+        assert_eq!(1, path.len());
+        assert_eq!(
+            1_000_000_000_007u64.to_be_bytes().to_vec(),
+            path[0].to_vec()
+        );
+        assert_eq!(
+            k,
+            TimestampKey::new(Timestamp::from_nanos(1_000_000_000_007))
+        );
+    }
+
+    #[test]
+    fn u128key_works() {
+        let k: U128Key = Uint128::new(1234567890).into();
+        let path = k.key();
+        assert_eq!(1, path.len());
+        assert_eq!(1234567890u128.to_be_bytes().to_vec(), path[0].to_vec());
+        assert_eq!(k, U128Key::from(1234567890u128));
+    }
+
     #[test]
     fn str_key_works() {
         type K<'a> = &'a str;
@@ -283,6 +429,54 @@ mod test {
         assert_eq!(joined, b"hello")
     }
 
+    #[test]
+    fn owned_keys_match_borrowed_keys() {
+        let addr = Addr::unchecked("owner");
+        let addr_ref: &Addr = &addr;
+        assert_eq!(addr.key(), <&Addr>::key(&addr_ref));
+        assert_eq!(addr.joined_key(), <&Addr>::joined_key(&addr_ref));
+        assert_eq!(addr.prefix(), <&Addr>::prefix(&addr_ref));
+
+        let name = "john".to_string();
+        assert_eq!(name.key(), name.as_str().key());
+        assert_eq!(name.prefix(), name.as_str().prefix());
+
+        let raw = b"raw".to_vec();
+        assert_eq!(raw.key(), raw.as_slice().key());
+        assert_eq!(raw.prefix(), raw.as_slice().prefix());
+
+        // also inside composite keys
+        let owned = (addr.clone(), U64Key::new(1234));
+        let borrowed = (&addr, U64Key::new(1234));
+        assert_eq!(owned.key(), borrowed.key());
+        assert_eq!(owned.joined_key(), borrowed.joined_key());
+        let owned = (name.clone(), addr.clone(), raw.clone());
+        let borrowed = (name.as_str(), &addr, raw.as_slice());
+        assert_eq!(owned.joined_key(), borrowed.joined_key());
+    }
+
+    #[test]
+    fn addr_composite_keys_match_byte_slices() {
+        let owner = Addr::unchecked("owner");
+        let spender = Addr::unchecked("spender");
+
+        let typed = (&owner, &spender);
+        let raw: (&[u8], &[u8]) = (b"owner", b"spender");
+        assert_eq!(typed.key(), raw.key());
+        assert_eq!(typed.joined_key(), raw.joined_key());
+        assert_eq!(b"\x00\x05ownerspender".to_vec(), typed.joined_key());
+        assert_eq!(typed.0.prefix(), raw.0.prefix());
+
+        let typed = (&owner, U64Key::new(1234));
+        let raw: (&[u8], U64Key) = (b"owner", U64Key::new(1234));
+        assert_eq!(typed.joined_key(), raw.joined_key());
+
+        let typed = (U64Key::new(1234), &owner);
+        let raw: (U64Key, &[u8]) = (U64Key::new(1234), b"owner");
+        assert_eq!(typed.joined_key(), raw.joined_key());
+        assert_eq!(typed.0.prefix(), raw.0.prefix());
+    }
+
     #[test]
     fn nested_str_key_works() {
         type K<'a> = (&'a str, &'a [u8]);
@@ -0,0 +1,256 @@
+use std::marker::PhantomData;
+
+#[cfg(feature = "iterator")]
+use cosmwasm_std::Order;
+use cosmwasm_std::{StdResult, Storage};
+
+#[cfg(feature = "iterator")]
+use crate::de::KeyDeserialize;
+use crate::keys::PrimaryKey;
+#[cfg(feature = "iterator")]
+use crate::keys::{EmptyPrefix, Prefixer};
+use crate::path::Path;
+#[cfg(feature = "iterator")]
+use crate::prefix::{Bound, Prefix};
+
+// the value stored for every key in the set, so it is not empty
+const MARKER: &[u8] = b"\x01";
+
+/// KeySet stores a set of typed keys under the given namespace, without any value.
+/// This is the same layout as a Map with the same namespace and key type, but each
+/// entry only holds a 1 byte marker. Useful to track membership, eg. of hooks or operators.
+pub struct KeySet<'a, K> {
+    namespace: &'a [u8],
+    // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
+    key_type: PhantomData<K>,
+}
+
+impl<'a, K> KeySet<'a, K> {
+    pub const fn new(namespace: &'a str) -> Self {
+        KeySet {
+            namespace: namespace.as_bytes(),
+            key_type: PhantomData,
+        }
+    }
+}
+
+impl<'a, K> KeySet<'a, K>
+where
+    K: PrimaryKey<'a>,
+{
+    /// insert adds the key to the set. It is a no-op if the key is already there.
+    /// Returns an error if a part of the key is too long to be length-prefixed.
+    pub fn insert(&self, store: &mut dyn Storage, k: K) -> StdResult<()> {
+        let path = Path::<()>::new_checked(self.namespace, &k.key())?;
+        store.set(&path, MARKER);
+        Ok(())
+    }
+
+    /// remove deletes the key from the set. It is a no-op if the key is not there,
+    /// including keys that are too long to ever be inserted.
+    pub fn remove(&self, store: &mut dyn Storage, k: K) {
+        if let Ok(path) = Path::<()>::new_checked(self.namespace, &k.key()) {
+            store.remove(&path);
+        }
+    }
+
+    /// contains returns true if the key is in the set. Keys that are too long to be
+    /// inserted are never in it.
+    pub fn contains(&self, store: &dyn Storage, k: K) -> bool {
+        match Path::<()>::new_checked(self.namespace, &k.key()) {
+            Ok(path) => path.has(store),
+            Err(_) => false,
+        }
+    }
+
+    /// is_empty returns true if there are no keys in the set
+    #[cfg(feature = "iterator")]
+    pub fn is_empty(&self, store: &dyn Storage) -> bool {
+        Prefix::<()>::new(self.namespace, &[]).is_empty(store)
+    }
+
+    /// clear removes all the keys in the set, or at most limit keys if given.
+    /// Returns the number of keys removed. If this is equal to limit, there may be more left.
+    #[cfg(feature = "iterator")]
+    pub fn clear(&self, store: &mut dyn Storage, limit: Option<usize>) -> usize {
+        Prefix::<()>::new(self.namespace, &[]).clear(store, limit)
+    }
+
+    /// prefix_keys returns the raw keys (relative to the prefix) under the given prefix,
+    /// eg. all the spenders of one owner for a `KeySet<(&Addr, &Addr)>`
+    #[cfg(feature = "iterator")]
+    pub fn prefix_keys<'c>(
+        &self,
+        store: &'c dyn Storage,
+        p: K::Prefix,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'c> {
+        Prefix::<()>::new(self.namespace, &p.prefix()).keys(store, min, max, order)
+    }
+}
+
+// short-cut for simple keys, rather than .prefix_keys(()...)
+#[cfg(feature = "iterator")]
+impl<'a, K> KeySet<'a, K>
+where
+    K: PrimaryKey<'a>,
+    K::SubPrefix: EmptyPrefix,
+{
+    /// keys returns all the raw keys in the set, in storage order
+    pub fn keys<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'c> {
+        Prefix::<()>::new(self.namespace, &[]).keys(store, min, max, order)
+    }
+
+    /// keys_de works like keys, but deserializes the keys into their owned typed form
+    pub fn keys_de<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<K::Output>> + 'c>
+    where
+        K: KeyDeserialize,
+        K::Output: 'c,
+    {
+        let keys = self.keys(store, min, max, order).map(|k| K::from_vec(k));
+        Box::new(keys)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::Addr;
+
+    #[cfg(feature = "iterator")]
+    use crate::Map;
+
+    const OPERATORS: KeySet<&Addr> = KeySet::new("operators");
+    const ALLOWED: KeySet<(&[u8], &[u8])> = KeySet::new("allowed");
+
+    #[test]
+    fn insert_contains_remove() {
+        let mut store = MockStorage::new();
+        let john = Addr::unchecked("john");
+        let jim = Addr::unchecked("jim");
+
+        assert!(!OPERATORS.contains(&store, &john));
+        OPERATORS.insert(&mut store, &john).unwrap();
+        assert!(OPERATORS.contains(&store, &john));
+        assert!(!OPERATORS.contains(&store, &jim));
+
+        // inserting twice is fine
+        OPERATORS.insert(&mut store, &john).unwrap();
+        assert!(OPERATORS.contains(&store, &john));
+
+        OPERATORS.remove(&mut store, &john);
+        assert!(!OPERATORS.contains(&store, &john));
+        // removing twice is fine
+        OPERATORS.remove(&mut store, &john);
+        assert!(!OPERATORS.contains(&store, &john));
+
+        // only a 1 byte marker is stored
+        OPERATORS.insert(&mut store, &jim).unwrap();
+        let key = Path::<()>::new(b"operators", &[b"jim"]);
+        assert_eq!(Some(MARKER.to_vec()), store.get(&key));
+
+        // composite keys
+        ALLOWED.insert(&mut store, (b"owner", b"spender")).unwrap();
+        assert!(ALLOWED.contains(&store, (b"owner", b"spender")));
+        assert!(!ALLOWED.contains(&store, (b"owner", b"other")));
+        assert!(!ALLOWED.contains(&store, (b"owners", b"pender")));
+        ALLOWED.remove(&mut store, (b"owner", b"spender"));
+        assert!(!ALLOWED.contains(&store, (b"owner", b"spender")));
+
+        // a prefix that is too long cannot be inserted, so is not there, and removing is a no-op
+        let too_long = vec![b'a'; 0x10000];
+        ALLOWED
+            .insert(&mut store, (&too_long, b"spender"))
+            .unwrap_err();
+        assert!(!ALLOWED.contains(&store, (&too_long, b"spender")));
+        ALLOWED.remove(&mut store, (&too_long, b"spender"));
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn iterate_keys() {
+        let mut store = MockStorage::new();
+        assert!(OPERATORS.is_empty(&store));
+
+        for name in &["john", "jim", "maria"] {
+            OPERATORS
+                .insert(&mut store, &Addr::unchecked(*name))
+                .unwrap();
+        }
+        assert!(!OPERATORS.is_empty(&store));
+
+        let all: Vec<_> = OPERATORS
+            .keys(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(
+            all,
+            vec![b"jim".to_vec(), b"john".to_vec(), b"maria".to_vec()]
+        );
+
+        let desc: Vec<_> = OPERATORS
+            .keys_de(
+                &store,
+                None,
+                Some(Bound::exclusive(b"maria".as_ref())),
+                Order::Descending,
+            )
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(desc, vec![Addr::unchecked("john"), Addr::unchecked("jim")]);
+
+        // removed keys are not listed anymore
+        OPERATORS.remove(&mut store, &Addr::unchecked("john"));
+        let all: Vec<_> = OPERATORS
+            .keys(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(all, vec![b"jim".to_vec(), b"maria".to_vec()]);
+
+        assert_eq!(2, OPERATORS.clear(&mut store, None));
+        assert!(OPERATORS.is_empty(&store));
+        assert!(!OPERATORS.contains(&store, &Addr::unchecked("jim")));
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn iterate_prefix() {
+        let mut store = MockStorage::new();
+        ALLOWED.insert(&mut store, (b"owner", b"spender")).unwrap();
+        ALLOWED.insert(&mut store, (b"owner", b"admin")).unwrap();
+        ALLOWED.insert(&mut store, (b"owner2", b"spender")).unwrap();
+
+        let spenders: Vec<_> = ALLOWED
+            .prefix_keys(&store, b"owner", None, None, Order::Ascending)
+            .collect();
+        assert_eq!(spenders, vec![b"admin".to_vec(), b"spender".to_vec()]);
+        let spenders: Vec<_> = ALLOWED
+            .prefix_keys(&store, b"owner2", None, None, Order::Descending)
+            .collect();
+        assert_eq!(spenders, vec![b"spender".to_vec()]);
+
+        // same keys as a Map with the same namespace
+        const ALLOWED_MAP: Map<(&[u8], &[u8]), u8> = Map::new("allowed");
+        let keys: Vec<_> = KeySet::<(&[u8], &[u8])>::new("allowed")
+            .prefix_keys(&store, b"owner", None, None, Order::Ascending)
+            .collect();
+        let map_keys: Vec<_> = ALLOWED_MAP
+            .prefix(b"owner")
+            .keys(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(keys, map_keys);
+    }
+}
@@ -0,0 +1,279 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::type_name;
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+use crate::item::Item;
+use crate::serialization::{Json, Serde};
+
+/// Lazy caches the value of an Item for the duration of one call, created with `Item::lazy`.
+/// The first load reads and parses the value from storage, and later loads return the
+/// cached value, so it can be passed (as `&mut Lazy`) to helper functions that all need it.
+///
+/// save, remove and update only change the cached value. They are written to storage
+/// by an explicit flush, and are lost if the Lazy is dropped before that.
+/// Writes made directly through the Item are not seen by a Lazy that already loaded
+/// the value: call reset to read it again.
+pub struct Lazy<'a, T, S = Json> {
+    item: Item<'a, T, S>,
+    // None if not loaded yet, Some(None) if loaded (or removed) but empty
+    cached: Option<Option<T>>,
+    // true if the cached value differs from the stored one
+    dirty: bool,
+}
+
+impl<'a, T, S> Lazy<'a, T, S> {
+    pub(crate) fn new(item: Item<'a, T, S>) -> Self {
+        Lazy {
+            item,
+            cached: None,
+            dirty: false,
+        }
+    }
+
+    /// is_dirty returns true if there are changes not written to storage yet
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// reset drops the cached value and any change not flushed yet,
+    /// so the next load reads from storage again
+    pub fn reset(&mut self) {
+        self.cached = None;
+        self.dirty = false;
+    }
+}
+
+impl<'a, T, S> Lazy<'a, T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Serde,
+{
+    /// may_load returns the cached value, reading it from storage on the first call.
+    /// Returns Ok(None) if there is no data, and an error on issues parsing
+    pub fn may_load(&mut self, store: &dyn Storage) -> StdResult<Option<&T>> {
+        if self.cached.is_none() {
+            self.cached = Some(self.item.may_load(store)?);
+        }
+        Ok(self.cached.as_ref().and_then(Option::as_ref))
+    }
+
+    /// load works like may_load, but returns a NotFound error if there is no data
+    pub fn load(&mut self, store: &dyn Storage) -> StdResult<&T> {
+        self.may_load(store)?
+            .ok_or_else(|| StdError::not_found(type_name::<T>()))
+    }
+
+    /// save replaces the cached value, without loading the stored one.
+    /// It is only written to storage by flush.
+    pub fn save(&mut self, data: T) {
+        self.cached = Some(Some(data));
+        self.dirty = true;
+    }
+
+    /// remove clears the cached value. It is only removed from storage by flush.
+    pub fn remove(&mut self) {
+        self.cached = Some(None);
+        self.dirty = true;
+    }
+
+    /// Loads the data (if not cached yet), perform the specified action, and caches the result.
+    /// Returns an error if there is no data yet, just like Item::update.
+    /// The cached value is unchanged if the action fails.
+    pub fn update<A, E>(&mut self, store: &dyn Storage, action: A) -> Result<&T, E>
+    where
+        A: FnOnce(T) -> Result<T, E>,
+        E: From<StdError>,
+        T: Clone,
+    {
+        let input = self.load(store)?.clone();
+        let output = action(input)?;
+        self.save(output);
+        Ok(self.load(store)?)
+    }
+
+    /// flush writes the cached value to storage if it was changed, and is a no-op otherwise.
+    /// The value stays cached, so the Lazy can still be used afterwards.
+    pub fn flush(&mut self, store: &mut dyn Storage) -> StdResult<()> {
+        if self.dirty {
+            match &self.cached {
+                Some(Some(data)) => self.item.save(store, data)?,
+                _ => self.item.remove(store),
+            }
+            self.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    #[cfg(feature = "iterator")]
+    use cosmwasm_std::{Order, Pair};
+    use serde::Deserialize;
+    use std::cell::Cell;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Config {
+        pub owner: String,
+        pub max_tokens: i32,
+    }
+
+    const CONFIG: Item<Config> = Item::new("config");
+
+    /// Counts the reads, to check the value is only loaded once
+    #[derive(Default)]
+    struct CountingStorage {
+        storage: MockStorage,
+        gets: Cell<usize>,
+    }
+
+    impl Storage for CountingStorage {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.gets.set(self.gets.get() + 1);
+            self.storage.get(key)
+        }
+
+        #[cfg(feature = "iterator")]
+        fn range<'a>(
+            &'a self,
+            start: Option<&[u8]>,
+            end: Option<&[u8]>,
+            order: Order,
+        ) -> Box<dyn Iterator<Item = Pair> + 'a> {
+            self.storage.range(start, end, order)
+        }
+
+        fn set(&mut self, key: &[u8], value: &[u8]) {
+            self.storage.set(key, value)
+        }
+
+        fn remove(&mut self, key: &[u8]) {
+            self.storage.remove(key)
+        }
+    }
+
+    fn owner(config: &mut Lazy<Config>, store: &dyn Storage) -> StdResult<String> {
+        Ok(config.load(store)?.owner.clone())
+    }
+
+    fn max_tokens(config: &mut Lazy<Config>, store: &dyn Storage) -> StdResult<i32> {
+        Ok(config.load(store)?.max_tokens)
+    }
+
+    #[test]
+    fn loads_only_once() {
+        let mut store = CountingStorage::default();
+        let cfg = Config {
+            owner: "admin".to_string(),
+            max_tokens: 1234,
+        };
+        CONFIG.save(&mut store, &cfg).unwrap();
+
+        let mut config = CONFIG.lazy();
+        assert_eq!(0, store.gets.get());
+        assert_eq!("admin", owner(&mut config, &store).unwrap());
+        assert_eq!(1234, max_tokens(&mut config, &store).unwrap());
+        assert_eq!(&cfg, config.load(&store).unwrap());
+        assert_eq!(1, store.gets.get());
+        assert!(!config.is_dirty());
+
+        // nothing to write
+        config.flush(&mut store).unwrap();
+        assert_eq!(1, store.gets.get());
+
+        // an empty item is cached as well
+        let store = CountingStorage::default();
+        let mut config = CONFIG.lazy();
+        assert_eq!(None, config.may_load(&store).unwrap());
+        match config.load(&store).unwrap_err() {
+            StdError::NotFound { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        assert_eq!(1, store.gets.get());
+    }
+
+    #[test]
+    fn writes_on_flush() {
+        let mut store = CountingStorage::default();
+        let cfg = Config {
+            owner: "admin".to_string(),
+            max_tokens: 1234,
+        };
+        CONFIG.save(&mut store, &cfg).unwrap();
+
+        let mut config = CONFIG.lazy();
+        let updated = config
+            .update(&store, |mut c| -> StdResult<_> {
+                c.max_tokens *= 2;
+                Ok(c)
+            })
+            .unwrap()
+            .clone();
+        assert_eq!(2468, updated.max_tokens);
+        assert!(config.is_dirty());
+        // helpers see the new value, storage not yet
+        assert_eq!(2468, max_tokens(&mut config, &store).unwrap());
+        assert_eq!(cfg, CONFIG.load(&store).unwrap());
+
+        config.flush(&mut store).unwrap();
+        assert!(!config.is_dirty());
+        assert_eq!(updated, CONFIG.load(&store).unwrap());
+        // one read by update, the others by CONFIG.load
+        assert_eq!(3, store.gets.get());
+
+        // a failed update leaves the cache unchanged
+        let err = config
+            .update(&store, |_| Err(StdError::generic_err("no")))
+            .unwrap_err();
+        assert_eq!(StdError::generic_err("no"), err);
+        assert_eq!(&updated, config.load(&store).unwrap());
+        assert!(!config.is_dirty());
+
+        // save doesn't need to load
+        let mut config = CONFIG.lazy();
+        config.save(cfg.clone());
+        config.flush(&mut store).unwrap();
+        assert_eq!(cfg, CONFIG.load(&store).unwrap());
+        assert_eq!(4, store.gets.get());
+
+        config.remove();
+        assert_eq!(None, config.may_load(&store).unwrap());
+        assert!(CONFIG.may_load(&store).unwrap().is_some());
+        config.flush(&mut store).unwrap();
+        assert_eq!(None, CONFIG.may_load(&store).unwrap());
+    }
+
+    #[test]
+    fn reset_reads_storage_again() {
+        let mut store = MockStorage::new();
+        let cfg = Config {
+            owner: "admin".to_string(),
+            max_tokens: 1234,
+        };
+        CONFIG.save(&mut store, &cfg).unwrap();
+
+        let mut config = CONFIG.lazy();
+        config.load(&store).unwrap();
+        // changed directly through the item
+        let other = Config {
+            owner: "other".to_string(),
+            max_tokens: 1,
+        };
+        CONFIG.save(&mut store, &other).unwrap();
+        assert_eq!(&cfg, config.load(&store).unwrap());
+
+        config.reset();
+        assert_eq!(&other, config.load(&store).unwrap());
+
+        // unflushed changes are dropped as well
+        config.remove();
+        config.reset();
+        assert!(!config.is_dirty());
+        config.flush(&mut store).unwrap();
+        assert_eq!(other, CONFIG.load(&store).unwrap());
+    }
+}
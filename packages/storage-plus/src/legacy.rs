@@ -0,0 +1,349 @@
+//! Readers for data written with the cosmwasm-storage `Bucket` and `Singleton` types,
+//! to migrate old contracts to storage-plus without migrating their data layout.
+//!
+//! A `Bucket::new(storage, b"balances")` stores every value at
+//! `to_length_prefixed(b"balances") + key`, which is exactly the layout of a
+//! `Map<&[u8], T>` with the same namespace, so `LegacyBucket` is just an alias of it.
+//! The nested `Bucket::multilevel(storage, &[b"a", b"b"])` is `Map::new("a").add_suffix(b"b")`.
+//!
+//! A `Singleton::new(storage, b"config")` (unlike an `Item`) length-prefixes its key,
+//! so it can only be read with a `LegacySingleton`.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(feature = "iterator")]
+use cosmwasm_std::{Order, Pair};
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+#[cfg(feature = "iterator")]
+use crate::helpers::namespaces_with_key;
+#[cfg(feature = "iterator")]
+use crate::keys::{EmptyPrefix, PrimaryKey};
+use crate::map::Map;
+use crate::serialization::{Json, Serde};
+
+/// LegacyBucket reads and writes the data of a cosmwasm-storage `Bucket` with the same namespace
+pub type LegacyBucket<'a, T, S = Json> = Map<'a, &'a [u8], T, S>;
+
+/// LegacySingleton stores one typed item at the same key as a cosmwasm-storage `Singleton`,
+/// that is, the length-prefixed namespace. Use it to read (and migrate) old singletons.
+pub struct LegacySingleton<'a, T, S = Json> {
+    // a Singleton is stored just like the empty key of a Bucket
+    bucket: LegacyBucket<'a, T, S>,
+}
+
+impl<'a, T> LegacySingleton<'a, T> {
+    pub const fn new(namespace: &'a str) -> Self {
+        LegacySingleton::with_serde(namespace)
+    }
+}
+
+impl<'a, T, S> LegacySingleton<'a, T, S> {
+    /// with_serde works like new, but encodes the value with the given Serde strategy
+    pub const fn with_serde(namespace: &'a str) -> Self {
+        LegacySingleton {
+            bucket: Map::with_serde(namespace),
+        }
+    }
+}
+
+impl<'a, T, S> LegacySingleton<'a, T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Serde,
+{
+    /// storage_key returns the full key the item is stored at
+    pub fn storage_key(&self) -> Vec<u8> {
+        self.bucket.key(b"").to_vec()
+    }
+
+    /// save will serialize the model and store, returns an error on serialization issues
+    pub fn save(&self, store: &mut dyn Storage, data: &T) -> StdResult<()> {
+        self.bucket.save(store, b"", data)
+    }
+
+    pub fn remove(&self, store: &mut dyn Storage) {
+        self.bucket.remove(store, b"")
+    }
+
+    /// load will return an error if no data is set at the key, or on parse error
+    pub fn load(&self, store: &dyn Storage) -> StdResult<T> {
+        self.bucket.load(store, b"")
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, store: &dyn Storage) -> StdResult<Option<T>> {
+        self.bucket.may_load(store, b"")
+    }
+
+    /// Loads the data, perform the specified action, and store the result
+    /// in the database. Returns an error if there is no data yet, just like Item::update.
+    pub fn update<A, E>(&self, store: &mut dyn Storage, action: A) -> Result<T, E>
+    where
+        A: FnOnce(T) -> Result<T, E>,
+        E: From<StdError>,
+    {
+        let input = self.load(store)?;
+        let output = action(input)?;
+        self.save(store, &output)?;
+        Ok(output)
+    }
+}
+
+/// migrate_map moves at most limit entries from old to new, keeping the same (raw) keys, and
+/// returns the number of entries moved. The values are re-encoded with the Serde strategy of
+/// new, so this also works to change the encoding (under another namespace).
+/// The moved entries are removed from old, so call it again (eg. in follow-up transactions)
+/// until it returns less than limit, to migrate big maps without running out of gas.
+///
+/// old and new must have distinct namespaces. Indexes (of an IndexedMap) are not migrated.
+#[cfg(feature = "iterator")]
+pub fn migrate_map<'a, 'b, K, L, T, S, R>(
+    old: &Map<'a, K, T, S>,
+    new: &Map<'b, L, T, R>,
+    store: &mut dyn Storage,
+    limit: usize,
+) -> StdResult<usize>
+where
+    K: PrimaryKey<'a>,
+    K::SubPrefix: EmptyPrefix,
+    L: PrimaryKey<'b>,
+    T: Serialize + DeserializeOwned,
+    S: Serde,
+    R: Serde,
+{
+    migrate_entries(old, new, store, limit, |k| k.to_vec())
+}
+
+/// migrate_signed_int_keys works like migrate_map, for maps keyed by one signed int key
+/// (`I8Key` to `I128Key`) that were written by older versions of storage-plus.
+/// Those stored signed ints as plain big endian bytes, so negative keys sorted after positive
+/// ones, and load with the current key encoding (with the sign bit flipped) doesn't find them.
+/// Using the old map with a raw key type (eg. `Map<&[u8], T>`) avoids decoding them.
+///
+/// The keys are re-encoded with the sign bit flipped while they are moved to new.
+/// Keys of other types (including composite keys) are not supported.
+#[cfg(feature = "iterator")]
+pub fn migrate_signed_int_keys<'a, 'b, K, L, T, S, R>(
+    old: &Map<'a, K, T, S>,
+    new: &Map<'b, L, T, R>,
+    store: &mut dyn Storage,
+    limit: usize,
+) -> StdResult<usize>
+where
+    K: PrimaryKey<'a>,
+    K::SubPrefix: EmptyPrefix,
+    L: PrimaryKey<'b>,
+    T: Serialize + DeserializeOwned,
+    S: Serde,
+    R: Serde,
+{
+    migrate_entries(old, new, store, limit, |k| {
+        let mut key = k.to_vec();
+        if let Some(first) = key.first_mut() {
+            *first ^= 0x80;
+        }
+        key
+    })
+}
+
+#[cfg(feature = "iterator")]
+fn migrate_entries<'a, 'b, K, L, T, S, R>(
+    old: &Map<'a, K, T, S>,
+    new: &Map<'b, L, T, R>,
+    store: &mut dyn Storage,
+    limit: usize,
+    new_key: impl Fn(&[u8]) -> Vec<u8>,
+) -> StdResult<usize>
+where
+    K: PrimaryKey<'a>,
+    K::SubPrefix: EmptyPrefix,
+    L: PrimaryKey<'b>,
+    T: Serialize + DeserializeOwned,
+    S: Serde,
+    R: Serde,
+{
+    if old.namespace() == new.namespace() {
+        return Err(StdError::generic_err("Cannot migrate a map onto itself"));
+    }
+    let batch = old
+        .range(store, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<Pair<T>>>>()?;
+    for (k, v) in batch.iter() {
+        store.set(
+            &namespaces_with_key(&[new.namespace()], &new_key(k)),
+            &R::to_vec(v)?,
+        );
+        store.remove(&namespaces_with_key(&[old.namespace()], k));
+    }
+    Ok(batch.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::to_vec;
+    use serde::Deserialize;
+
+    use crate::Item;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Config {
+        pub owner: String,
+        pub max_tokens: u32,
+    }
+
+    const BALANCES: LegacyBucket<u64> = Map::new("balances");
+    const CONFIG: LegacySingleton<Config> = LegacySingleton::new("config");
+
+    #[test]
+    fn reads_bucket_layout() {
+        let mut store = MockStorage::new();
+        // written by Bucket::new(storage, b"balances").save(b"john", &100)
+        store.set(b"\x00\x08balancesjohn", b"100");
+        store.set(b"\x00\x08balancesjim", b"50");
+
+        assert_eq!(100, BALANCES.load(&store, b"john").unwrap());
+        assert_eq!(50, BALANCES.load(&store, b"jim").unwrap());
+        assert_eq!(None, BALANCES.may_load(&store, b"maria").unwrap());
+
+        // and new writes are readable with a Bucket
+        BALANCES.save(&mut store, b"maria", &7).unwrap();
+        assert_eq!(Some(b"7".to_vec()), store.get(b"\x00\x08balancesmaria"));
+
+        // written by Bucket::multilevel(storage, &[b"allowance", b"owner"]).save(b"spender", &3)
+        store.set(b"\x00\x09allowance\x00\x05ownerspender", b"3");
+        const ALLOWANCES: LegacyBucket<u64> = Map::new("allowance");
+        let owner = ALLOWANCES.add_suffix(b"owner");
+        assert_eq!(3, owner.load(&store, b"spender").unwrap());
+    }
+
+    #[test]
+    fn reads_singleton_layout() {
+        let mut store = MockStorage::new();
+        let config = Config {
+            owner: "admin".to_string(),
+            max_tokens: 1234,
+        };
+        // written by Singleton::new(storage, b"config").save(&config)
+        store.set(b"\x00\x06config", &to_vec(&config).unwrap());
+
+        assert_eq!(b"\x00\x06config".to_vec(), CONFIG.storage_key());
+        assert_eq!(config, CONFIG.load(&store).unwrap());
+        // an Item doesn't length-prefix its key, so it doesn't see it
+        const NEW_CONFIG: Item<Config> = Item::new("config");
+        assert_eq!(None, NEW_CONFIG.may_load(&store).unwrap());
+
+        let updated = CONFIG
+            .update(&mut store, |mut c| -> StdResult<_> {
+                c.max_tokens += 1;
+                Ok(c)
+            })
+            .unwrap();
+        assert_eq!(1235, updated.max_tokens);
+        assert_eq!(
+            to_vec(&updated).unwrap(),
+            store.get(b"\x00\x06config").unwrap()
+        );
+
+        // migrating it to an Item
+        let old = CONFIG.load(&store).unwrap();
+        NEW_CONFIG.save(&mut store, &old).unwrap();
+        CONFIG.remove(&mut store);
+        assert_eq!(None, CONFIG.may_load(&store).unwrap());
+        assert_eq!(updated, NEW_CONFIG.load(&store).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn migrate_map_in_batches() {
+        let mut store = MockStorage::new();
+        for (i, name) in ["a", "b", "c", "d", "e"].iter().enumerate() {
+            let key = [b"\x00\x08balances".as_ref(), name.as_bytes()].concat();
+            store.set(&key, i.to_string().as_bytes());
+        }
+        const NEW_BALANCES: Map<&[u8], u64> = Map::new("balances_v2");
+
+        assert_eq!(
+            2,
+            migrate_map(&BALANCES, &NEW_BALANCES, &mut store, 2).unwrap()
+        );
+        assert_eq!(2, NEW_BALANCES.count(&store, None));
+        assert_eq!(3, BALANCES.count(&store, None));
+        assert_eq!(
+            2,
+            migrate_map(&BALANCES, &NEW_BALANCES, &mut store, 2).unwrap()
+        );
+        // the last batch is not full
+        assert_eq!(
+            1,
+            migrate_map(&BALANCES, &NEW_BALANCES, &mut store, 2).unwrap()
+        );
+        assert_eq!(
+            0,
+            migrate_map(&BALANCES, &NEW_BALANCES, &mut store, 2).unwrap()
+        );
+
+        assert!(BALANCES.is_empty(&store));
+        let all: Vec<_> = NEW_BALANCES
+            .range(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(
+            all,
+            vec![
+                (b"a".to_vec(), 0),
+                (b"b".to_vec(), 1),
+                (b"c".to_vec(), 2),
+                (b"d".to_vec(), 3),
+                (b"e".to_vec(), 4),
+            ]
+        );
+
+        let err = migrate_map(&NEW_BALANCES, &NEW_BALANCES, &mut store, 2).unwrap_err();
+        assert_eq!(
+            StdError::generic_err("Cannot migrate a map onto itself"),
+            err
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn migrate_signed_int_keys_flips_sign_bit() {
+        use crate::I32Key;
+
+        let mut store = MockStorage::new();
+        // written by older versions, with the keys as plain big endian bytes
+        for n in [-300i32, -1, 0, 7].iter() {
+            let key = [b"\x00\x06scores".as_ref(), &n.to_be_bytes()].concat();
+            store.set(&key, n.to_string().as_bytes());
+        }
+        const OLD_SCORES: Map<&[u8], i32> = Map::new("scores");
+        const SCORES: Map<I32Key, i32> = Map::new("scores_v2");
+
+        assert_eq!(
+            3,
+            migrate_signed_int_keys(&OLD_SCORES, &SCORES, &mut store, 3).unwrap()
+        );
+        assert_eq!(
+            1,
+            migrate_signed_int_keys(&OLD_SCORES, &SCORES, &mut store, 3).unwrap()
+        );
+        assert!(OLD_SCORES.is_empty(&store));
+
+        for n in [-300i32, -1, 0, 7].iter() {
+            assert_eq!(*n, SCORES.load(&store, (*n).into()).unwrap());
+        }
+        // and they are now sorted numerically
+        let all: Vec<_> = SCORES
+            .range(&store, None, None, Order::Ascending)
+            .map(|r| r.map(|(_, v)| v))
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(all, vec![-300, -1, 0, 7]);
+    }
+}
@@ -1,3 +1,7 @@
+mod append_store;
+mod de;
+mod deque;
+mod describe;
 mod endian;
 mod helpers;
 mod indexed_map;
@@ -5,12 +9,28 @@ mod indexed_snapshot;
 mod indexes;
 mod item;
 mod iter_helpers;
+mod key_buffer;
 mod keys;
+mod keyset;
+mod lazy;
+mod legacy;
 mod map;
 mod path;
 mod prefix;
+mod serialization;
 mod snapshot;
+#[cfg(feature = "iterator")]
+mod sorted_map;
+mod suffixed_map;
+#[cfg(feature = "iterator")]
+mod transactions;
 
+pub use append_store::AppendStore;
+#[cfg(feature = "macro")]
+pub use cw_storage_macro::IndexList;
+pub use de::KeyDeserialize;
+pub use deque::{Deque, DequeIter};
+pub use describe::StorageDescription;
 pub use endian::Endian;
 #[cfg(feature = "iterator")]
 pub use indexed_map::{IndexList, IndexedMap};
@@ -18,14 +38,31 @@ pub use indexed_map::{IndexList, IndexedMap};
 pub use indexed_snapshot::IndexedSnapshotMap;
 #[cfg(feature = "iterator")]
 pub use indexes::{
-    index_string, index_string_tuple, index_triple, index_tuple, Index, MultiIndex, UniqueIndex,
+    index_string, index_string_tuple, index_triple, index_tuple, Index, MultiIndex, SumIndex,
+    UniqueIndex,
 };
-pub use item::Item;
+pub use item::{Counter, Item, ItemWithDefault};
+pub use key_buffer::KeyBuffer;
 pub use keys::{I128Key, I16Key, I32Key, I64Key, I8Key};
-pub use keys::{Prefixer, PrimaryKey, U128Key, U16Key, U32Key, U64Key, U8Key};
+pub use keys::{Prefixer, PrimaryKey, TimestampKey, U128Key, U16Key, U32Key, U64Key, U8Key};
+pub use keyset::KeySet;
+pub use lazy::Lazy;
+#[cfg(feature = "iterator")]
+pub use legacy::{migrate_map, migrate_signed_int_keys};
+pub use legacy::{LegacyBucket, LegacySingleton};
 pub use map::Map;
+#[cfg(feature = "iterator")]
+pub use map::Page;
 pub use path::Path;
 #[cfg(feature = "iterator")]
-pub use prefix::{range_with_prefix, Bound, Prefix};
+pub use prefix::{range_with_prefix, Bound, Prefix, RawEntry};
+#[cfg(feature = "bincode")]
+pub use serialization::Bincode;
+pub use serialization::{Json, Serde};
+#[cfg(feature = "iterator")]
+pub use snapshot::{SnapshotItem, SnapshotMap, Strategy};
+#[cfg(feature = "iterator")]
+pub use sorted_map::SortedMap;
+pub use suffixed_map::SuffixedMap;
 #[cfg(feature = "iterator")]
-pub use snapshot::{SnapshotMap, Strategy};
+pub use transactions::{transactional, RepLog, StorageTransaction};
@@ -1,75 +1,229 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::any::type_name;
 use std::marker::PhantomData;
 
+#[cfg(feature = "iterator")]
+use crate::de::KeyDeserialize;
+use crate::describe::StorageDescription;
+use crate::helpers::{already_exists, check_namespaces, may_deserialize_with, namespaces_with_key};
+use crate::key_buffer::KeyBuffer;
 use crate::keys::PrimaryKey;
 #[cfg(feature = "iterator")]
 use crate::keys::{EmptyPrefix, Prefixer};
 use crate::path::Path;
 #[cfg(feature = "iterator")]
 use crate::prefix::{Bound, Prefix};
-use cosmwasm_std::{StdError, StdResult, Storage};
+use crate::serialization::{Json, Serde};
+use crate::suffixed_map::SuffixedMap;
+use cosmwasm_std::{Addr, QuerierWrapper, StdError, StdResult, Storage};
+
+/// A page of deserialized entries, along with the cursor for the next page
+#[cfg(feature = "iterator")]
+pub type Page<K, T> = (Vec<(K, T)>, Option<K>);
 
+/// Map stores typed values under typed keys, in the given namespace.
+/// The values are encoded with the Serde strategy `S`, which is Json by default.
 #[derive(Debug, Clone)]
-pub struct Map<'a, K, T> {
+pub struct Map<'a, K, T, S = Json> {
     namespace: &'a [u8],
     // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
     key_type: PhantomData<K>,
     data_type: PhantomData<T>,
+    serde: PhantomData<S>,
 }
 
 impl<'a, K, T> Map<'a, K, T> {
     pub const fn new(namespace: &'a str) -> Self {
+        Map::with_serde(namespace)
+    }
+}
+
+impl<'a, K, T, S> Map<'a, K, T, S> {
+    /// with_serde works like new, but encodes the values with the given Serde strategy, eg.
+    /// `const BALANCES: Map<&Addr, Balance, Bincode> = Map::with_serde("balances");`
+    pub const fn with_serde(namespace: &'a str) -> Self {
         Map {
             namespace: namespace.as_bytes(),
             data_type: PhantomData,
             key_type: PhantomData,
+            serde: PhantomData,
+        }
+    }
+
+    /// namespace returns the raw namespace, every key of the map starts with its
+    /// length-prefixed form
+    pub fn namespace(&self) -> &'a [u8] {
+        self.namespace
+    }
+
+    /// describe returns the namespace and the key and value type names, see StorageDescription
+    pub fn describe(&self) -> StorageDescription<'a> {
+        StorageDescription {
+            kind: "Map",
+            namespace: self.namespace,
+            key_type: Some(type_name::<K>()),
+            value_type: type_name::<T>(),
         }
     }
+
+    /// with_prefix_buffer returns a KeyBuffer, which builds the keys of this map in one
+    /// reused buffer, eg. to save many entries in a loop
+    pub fn with_prefix_buffer(&self) -> KeyBuffer<'a, K, T, S> {
+        KeyBuffer::new(self.namespace)
+    }
+
+    /// add_suffix returns a map scoped by a runtime suffix (eg. a pool id) under the same
+    /// namespace, so one static Map can hold many independent maps. The entries of distinct
+    /// suffixes never overlap, and load (or has) on this map doesn't find them. But range and keys
+    /// of this map do return them, with the length-prefixed suffix in front of the key, so don't
+    /// save entries both here and under a suffix if you iterate over this map.
+    pub fn add_suffix(&self, suffix: &[u8]) -> SuffixedMap<'a, K, T, S> {
+        SuffixedMap::new(self.namespace, suffix)
+    }
 }
 
-impl<'a, K, T> Map<'a, K, T>
+impl<'a, K, T, S> Map<'a, K, T, S>
 where
     T: Serialize + DeserializeOwned,
     K: PrimaryKey<'a>,
+    S: Serde,
 {
-    pub fn key(&self, k: K) -> Path<T> {
+    /// key returns the path of the data at k. It panics if a part of the key (other than the
+    /// last one) is too long to be length-prefixed, see key_checked
+    pub fn key(&self, k: K) -> Path<T, S> {
         Path::new(self.namespace, &k.key())
     }
 
+    /// key_checked works like key, but returns an error rather than panicking if a part of the
+    /// key (other than the last one) is too long to be length-prefixed. The methods returning
+    /// a StdResult use it, so user-controlled keys give an error instead of aborting the contract.
+    pub fn key_checked(&self, k: K) -> StdResult<Path<T, S>> {
+        Path::new_checked(self.namespace, &k.key())
+    }
+
     #[cfg(feature = "iterator")]
     pub fn prefix(&self, p: K::Prefix) -> Prefix<T> {
-        Prefix::new(self.namespace, &p.prefix())
+        Prefix::with_serde::<S>(self.namespace, &p.prefix())
     }
 
     #[cfg(feature = "iterator")]
     pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<T> {
-        Prefix::new(self.namespace, &p.prefix())
+        Prefix::with_serde::<S>(self.namespace, &p.prefix())
+    }
+
+    /// is_empty returns true if there are no entries in the map, for any key
+    #[cfg(feature = "iterator")]
+    pub fn is_empty(&self, store: &dyn Storage) -> bool {
+        Prefix::<T>::new(self.namespace, &[]).is_empty(store)
+    }
+
+    /// count returns the number of entries in the map, counting at most limit entries if given.
+    /// Values are never parsed.
+    #[cfg(feature = "iterator")]
+    pub fn count(&self, store: &dyn Storage, limit: Option<usize>) -> usize {
+        Prefix::<T>::new(self.namespace, &[]).count(store, limit)
+    }
+
+    /// clear removes all the entries of the map, or at most limit entries if given.
+    /// Returns the number of entries removed. If this is equal to limit, there may be more left.
+    #[cfg(feature = "iterator")]
+    pub fn clear(&self, store: &mut dyn Storage, limit: Option<usize>) -> usize {
+        Prefix::<T>::new(self.namespace, &[]).clear(store, limit)
     }
 
     pub fn save(&self, store: &mut dyn Storage, k: K, data: &T) -> StdResult<()> {
-        self.key(k).save(store, data)
+        self.key_checked(k)?.save(store, data)
     }
 
+    /// remove removes the data at the key, if any. Keys that are too long to be saved
+    /// cannot hold any data, so nothing is removed for them
     pub fn remove(&self, store: &mut dyn Storage, k: K) {
-        self.key(k).remove(store)
+        if let Ok(path) = self.key_checked(k) {
+            path.remove(store)
+        }
+    }
+
+    /// remove_many removes all the given keys. Keys with no data are skipped.
+    pub fn remove_many(&self, store: &mut dyn Storage, keys: &[K])
+    where
+        K: Clone,
+    {
+        for k in keys {
+            self.remove(store, k.clone());
+        }
+    }
+
+    /// save_if_absent works like save, but returns an "already exists" error
+    /// (and doesn't write anything) if there is already data at the key
+    pub fn save_if_absent(&self, store: &mut dyn Storage, k: K, data: &T) -> StdResult<()> {
+        let path = self.key_checked(k)?;
+        if path.has(store) {
+            return Err(already_exists::<T>());
+        }
+        path.save(store, data)
+    }
+
+    /// take removes the data at the key, returning the previous value if there was any
+    pub fn take(&self, store: &mut dyn Storage, k: K) -> StdResult<Option<T>> {
+        let path = self.key_checked(k)?;
+        let old = path.may_load(store)?;
+        if old.is_some() {
+            path.remove(store);
+        }
+        Ok(old)
     }
 
     /// load will return an error if no data is set at the given key, or on parse error
     pub fn load(&self, store: &dyn Storage, k: K) -> StdResult<T> {
-        self.key(k).load(store)
+        self.key_checked(k)?.load(store)
     }
 
     /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
     /// returns an error on issues parsing
     pub fn may_load(&self, store: &dyn Storage, k: K) -> StdResult<Option<T>> {
-        self.key(k).may_load(store)
+        self.key_checked(k)?.may_load(store)
+    }
+
+    /// query reads the value at the key from the storage of another contract, with a raw query.
+    /// The raw key is built just like for load, so the other contract must use the same
+    /// namespace and key type (eg. a cw20 contract's balances). Returns Ok(None) if there
+    /// is no data at the key.
+    pub fn query(
+        &self,
+        querier: &QuerierWrapper,
+        remote_contract: Addr,
+        k: K,
+    ) -> StdResult<Option<T>> {
+        let key = self.key_checked(k)?.to_vec();
+        let value = querier.query_wasm_raw(remote_contract, key)?;
+        may_deserialize_with::<S, T>(&value)
+    }
+
+    /// load_many works like may_load for every key, returning the results in the same order.
+    /// A missing key gives None, and does not fail the whole batch.
+    /// The namespace is only encoded once, and the buffer is reused for all keys.
+    pub fn load_many(&self, store: &dyn Storage, keys: &[K]) -> StdResult<Vec<Option<T>>> {
+        let mut storage_key = namespaces_with_key(&[self.namespace], b"");
+        let namespace_len = storage_key.len();
+        keys.iter()
+            .map(|k| {
+                let segments = k.key();
+                check_namespaces(&segments[0..segments.len().saturating_sub(1)])?;
+                storage_key.truncate(namespace_len);
+                storage_key.extend_from_slice(&k.joined_key());
+                may_deserialize_with::<S, T>(&store.get(&storage_key))
+            })
+            .collect()
     }
 
     /// has returns true or false if any data is at this key, without parsing or interpreting the
-    /// contents.
+    /// contents. Keys that are too long to be saved never have any data.
     pub fn has(&self, store: &dyn Storage, k: K) -> bool {
-        self.key(k).has(store)
+        match self.key_checked(k) {
+            Ok(path) => path.has(store),
+            Err(_) => false,
+        }
     }
 
     /// Loads the data, perform the specified action, and store the result
@@ -81,17 +235,44 @@ where
         A: FnOnce(Option<T>) -> Result<T, E>,
         E: From<StdError>,
     {
-        self.key(k).update(store, action)
+        self.key_checked(k)?.update(store, action)
+    }
+
+    /// update_or_default works like update, but calls `action` with the default value
+    /// if there is no data at the key yet. eg. to increment a counter or balance.
+    pub fn update_or_default<A, E>(&self, store: &mut dyn Storage, k: K, action: A) -> Result<T, E>
+    where
+        A: FnOnce(T) -> Result<T, E>,
+        E: From<StdError>,
+        T: Default,
+    {
+        self.key_checked(k)?.update_or_default(store, action)
+    }
+
+    /// update_old works like update, but returns the previous value (if any) along with the new one
+    pub fn update_old<A, E>(
+        &self,
+        store: &mut dyn Storage,
+        k: K,
+        action: A,
+    ) -> Result<(Option<T>, T), E>
+    where
+        A: FnOnce(Option<T>) -> Result<T, E>,
+        E: From<StdError>,
+        T: Clone,
+    {
+        self.key_checked(k)?.update_old(store, action)
     }
 }
 
 // short-cut for simple keys, rather than .prefix(()).range(...)
 #[cfg(feature = "iterator")]
-impl<'a, K, T> Map<'a, K, T>
+impl<'a, K, T, S> Map<'a, K, T, S>
 where
     T: Serialize + DeserializeOwned,
     K: PrimaryKey<'a>,
     K::SubPrefix: EmptyPrefix,
+    S: Serde,
 {
     pub fn range<'c>(
         &self,
@@ -107,6 +288,85 @@ where
             .range(store, min, max, order)
     }
 
+    /// range_de works like range, but deserializes the keys into their owned typed form.
+    /// eg. a `Map<(&[u8], U64Key), T>` yields `((Vec<u8>, u64), T)` items.
+    pub fn range_de<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K: KeyDeserialize,
+        K::Output: 'c,
+    {
+        let mapped = self.range(store, min, max, order).map(|res| {
+            let (k, v) = res?;
+            Ok((K::from_vec(k)?, v))
+        });
+        Box::new(mapped)
+    }
+
+    /// paginate returns one page of (deserialized) entries after start_after (exclusive),
+    /// along with the cursor to pass as start_after for the next page, or None if there are
+    /// no more entries. limit defaults to max_limit and is capped by it, but is at least 1, as
+    /// an empty page would look like the last one.
+    /// With Order::Descending, the page holds the keys before start_after, from highest to lowest.
+    pub fn paginate(
+        &self,
+        store: &dyn Storage,
+        start_after: Option<K>,
+        limit: Option<u32>,
+        max_limit: u32,
+        order: cosmwasm_std::Order,
+    ) -> StdResult<Page<K::Output, T>>
+    where
+        K: KeyDeserialize,
+        K::Output: Clone,
+    {
+        let limit = limit.unwrap_or(max_limit).min(max_limit).max(1) as usize;
+        let bound = start_after.map(Bound::exclusive);
+        let (min, max) = match order {
+            cosmwasm_std::Order::Ascending => (bound, None),
+            cosmwasm_std::Order::Descending => (None, bound),
+        };
+        // load one more than needed, to know if there is a next page
+        let mut items = self
+            .range_de(store, min, max, order)
+            .take(limit + 1)
+            .collect::<StdResult<Vec<_>>>()?;
+        let next = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|(k, _)| k.clone())
+        } else {
+            None
+        };
+        Ok((items, next))
+    }
+
+    /// first returns the entry with the lowest key (if any), with the key deserialized
+    pub fn first(&self, store: &dyn Storage) -> StdResult<Option<(K::Output, T)>>
+    where
+        K: KeyDeserialize,
+    {
+        self.range_de(store, None, None, cosmwasm_std::Order::Ascending)
+            .next()
+            .transpose()
+    }
+
+    /// last returns the entry with the highest key (if any), with the key deserialized
+    pub fn last(&self, store: &dyn Storage) -> StdResult<Option<(K::Output, T)>>
+    where
+        K: KeyDeserialize,
+    {
+        self.range_de(store, None, None, cosmwasm_std::Order::Descending)
+            .next()
+            .transpose()
+    }
+
+    /// keys works like range, but only returns the raw keys, without deserializing the values
     pub fn keys<'c>(
         &self,
         store: &'c dyn Storage,
@@ -130,6 +390,8 @@ mod test {
 
     #[cfg(feature = "iterator")]
     use crate::iter_helpers::to_length_prefixed;
+    #[cfg(feature = "iterator")]
+    use crate::U64Key;
     use crate::U8Key;
     use cosmwasm_std::testing::MockStorage;
     #[cfg(feature = "iterator")]
@@ -404,7 +666,7 @@ mod test {
             .collect();
         let all = all.unwrap();
         assert_eq!(3, all.len());
-        // FIXME: range() works, but remaining keys are still encoded
+        // range() works, but remaining keys are still encoded, see range_de
         assert_eq!(
             all,
             vec![
@@ -424,6 +686,1564 @@ mod test {
         );
     }
 
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_triple_key_remaining_components() {
+        let mut store = MockStorage::new();
+        for (owner, id, recipient, amount) in [
+            (b"owner".as_ref(), 9u8, "recipient", 1u64),
+            (b"owner", 9, "recipient2", 2),
+            (b"owner", 10, "recipient", 3),
+            (b"owner", 10, "recipient3", 4),
+            (b"owner2", 9, "recipient", 5),
+        ]
+        .iter()
+        {
+            TRIPLE
+                .save(&mut store, (owner, U8Key::new(*id), recipient), amount)
+                .unwrap();
+        }
+
+        // sub_prefix yields the two remaining components
+        let all: StdResult<Vec<_>> = TRIPLE
+            .sub_prefix(b"owner")
+            .range_de::<(U8Key, &str)>(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(
+            all.unwrap(),
+            vec![
+                ((9, "recipient".to_string()), 1),
+                ((9, "recipient2".to_string()), 2),
+                ((10, "recipient".to_string()), 3),
+                ((10, "recipient3".to_string()), 4),
+            ]
+        );
+
+        // bounds on the remaining components, descending
+        let all: StdResult<Vec<_>> = TRIPLE
+            .sub_prefix(b"owner")
+            .range_de::<(U8Key, &str)>(
+                &store,
+                Some(Bound::exclusive((U8Key::new(9), "recipient"))),
+                Some(Bound::exclusive((U8Key::new(10), "recipient3"))),
+                Order::Descending,
+            )
+            .collect();
+        assert_eq!(
+            all.unwrap(),
+            vec![
+                ((10, "recipient".to_string()), 3),
+                ((9, "recipient2".to_string()), 2),
+            ]
+        );
+
+        // prefix yields the innermost component, bounds apply to it
+        let all: StdResult<Vec<_>> = TRIPLE
+            .prefix((b"owner", U8Key::new(10)))
+            .range_de::<&str>(
+                &store,
+                None,
+                Some(Bound::exclusive("recipient3")),
+                Order::Descending,
+            )
+            .collect();
+        assert_eq!(all.unwrap(), vec![("recipient".to_string(), 3)]);
+        let all: StdResult<Vec<_>> = TRIPLE
+            .prefix((b"owner", U8Key::new(9)))
+            .range_de::<&str>(
+                &store,
+                Some(Bound::inclusive("recipient2")),
+                None,
+                Order::Descending,
+            )
+            .collect();
+        assert_eq!(all.unwrap(), vec![("recipient2".to_string(), 2)]);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_triple_key_by_expiration() {
+        // (owner, operator, expiration) -> approved amount
+        const APPROVALS: Map<(&[u8], &[u8], U64Key), u64> = Map::new("approvals");
+        let mut store = MockStorage::new();
+
+        for (owner, operator, expires, amount) in [
+            (b"owner".as_ref(), b"spender".as_ref(), 500u64, 1u64),
+            (b"owner", b"spender", 50, 2),
+            (b"owner", b"spender", 5000, 3),
+            (b"owner", b"spender2", 100, 4),
+            (b"owner2", b"spender", 100, 5),
+        ]
+        .iter()
+        {
+            APPROVALS
+                .save(&mut store, (owner, operator, U64Key::new(*expires)), amount)
+                .unwrap();
+        }
+
+        // prefix over the first two elements, ranges over the expiration
+        let all: StdResult<Vec<_>> = APPROVALS
+            .prefix((b"owner", b"spender"))
+            .range(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(
+            all.unwrap(),
+            vec![
+                (50u64.to_be_bytes().to_vec(), 2),
+                (500u64.to_be_bytes().to_vec(), 1),
+                (5000u64.to_be_bytes().to_vec(), 3),
+            ]
+        );
+
+        // descending, with bounds on the last element
+        let all: StdResult<Vec<_>> = APPROVALS
+            .prefix((b"owner", b"spender"))
+            .range(
+                &store,
+                Some(Bound::Exclusive(U64Key::new(50).into())),
+                Some(Bound::Inclusive(U64Key::new(5000).into())),
+                Order::Descending,
+            )
+            .collect();
+        assert_eq!(
+            all.unwrap(),
+            vec![
+                (5000u64.to_be_bytes().to_vec(), 3),
+                (500u64.to_be_bytes().to_vec(), 1),
+            ]
+        );
+
+        // sub_prefix over the owner returns all operators, in order
+        let all: Vec<_> = APPROVALS
+            .sub_prefix(b"owner")
+            .range(&store, None, None, Order::Descending)
+            .map(|r| r.unwrap().1)
+            .collect();
+        assert_eq!(all, vec![4, 3, 1, 2]);
+
+        // the key uses the canonical nested length-prefixed encoding
+        let key = APPROVALS.key((b"owner2", b"spender", U64Key::new(100)));
+        assert_eq!(
+            key.to_vec(),
+            [
+                to_length_prefixed(b"approvals"),
+                to_length_prefixed(b"owner2"),
+                to_length_prefixed(b"spender"),
+                100u64.to_be_bytes().to_vec()
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_signed_int_key() {
+        use crate::I32Key;
+
+        const DELTAS: Map<I32Key, u64> = Map::new("deltas");
+        let mut store = MockStorage::new();
+
+        for (i, delta) in [7, -100, 0, -5, 3, -1].iter().enumerate() {
+            DELTAS
+                .save(&mut store, I32Key::new(*delta), &(i as u64))
+                .unwrap();
+        }
+
+        let all: StdResult<Vec<_>> = DELTAS
+            .range_de(&store, None, None, Order::Ascending)
+            .collect();
+        let keys: Vec<i32> = all.unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![-100, -5, -1, 0, 3, 7]);
+
+        // exclusive bound on -5 excludes exactly that entry
+        let all: StdResult<Vec<_>> = DELTAS
+            .range_de(
+                &store,
+                Some(Bound::exclusive(I32Key::new(-5))),
+                None,
+                Order::Ascending,
+            )
+            .collect();
+        let keys: Vec<i32> = all.unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![-1, 0, 3, 7]);
+
+        let all: StdResult<Vec<_>> = DELTAS
+            .range_de(
+                &store,
+                None,
+                Some(Bound::exclusive_int(-5i32)),
+                Order::Descending,
+            )
+            .collect();
+        let keys: Vec<i32> = all.unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![-100]);
+
+        let all: StdResult<Vec<_>> = DELTAS
+            .range_de(
+                &store,
+                Some(Bound::inclusive_int(-1i32)),
+                Some(Bound::inclusive_int(3i32)),
+                Order::Descending,
+            )
+            .collect();
+        let all = all.unwrap();
+        assert_eq!(all, vec![(3, 4), (0, 2), (-1, 5)]);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_timestamp_key() {
+        use crate::TimestampKey;
+        use cosmwasm_std::Timestamp;
+
+        const PENDING: Map<TimestampKey, u64> = Map::new("pending");
+        let mut store = MockStorage::new();
+
+        let times = [
+            Timestamp::from_seconds(2),
+            Timestamp::from_nanos(1_999_999_999),
+            Timestamp::from_seconds(1).plus_nanos(1),
+            Timestamp::from_seconds(10),
+            Timestamp::from_nanos(5),
+        ];
+        for (i, time) in times.iter().enumerate() {
+            PENDING
+                .save(&mut store, (*time).into(), &(i as u64))
+                .unwrap();
+        }
+
+        // chronological, regardless of second or nanosecond precision
+        let all: StdResult<Vec<_>> = PENDING
+            .range_de(&store, None, None, Order::Ascending)
+            .collect();
+        let all = all.unwrap();
+        assert_eq!(
+            all,
+            vec![
+                (Timestamp::from_nanos(5), 4),
+                (Timestamp::from_nanos(1_000_000_001), 2),
+                (Timestamp::from_nanos(1_999_999_999), 1),
+                (Timestamp::from_seconds(2), 0),
+                (Timestamp::from_seconds(10), 3),
+            ]
+        );
+
+        // bounds built directly from timestamps
+        let all: StdResult<Vec<_>> = PENDING
+            .range(
+                &store,
+                Some(Bound::exclusive_timestamp(Timestamp::from_nanos(
+                    1_999_999_999,
+                ))),
+                Some(Bound::inclusive_timestamp(Timestamp::from_seconds(10))),
+                Order::Descending,
+            )
+            .map(|r| r.map(|(_, v)| v))
+            .collect();
+        assert_eq!(all.unwrap(), vec![3, 0]);
+
+        let all: StdResult<Vec<_>> = PENDING
+            .range(
+                &store,
+                None,
+                Some(Bound::exclusive_timestamp(Timestamp::from_seconds(2))),
+                Order::Ascending,
+            )
+            .map(|r| r.map(|(_, v)| v))
+            .collect();
+        assert_eq!(all.unwrap(), vec![4, 2, 1]);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_u128_key() {
+        use crate::U128Key;
+        use cosmwasm_std::Uint128;
+
+        // (price, order id) -> amount
+        const ORDERS: Map<(U128Key, &[u8]), u64> = Map::new("orders");
+        let mut store = MockStorage::new();
+
+        let big = u64::MAX as u128;
+        let prices = [big + 1, 7, big, u128::MAX, big * 1000, 0];
+        for (i, price) in prices.iter().enumerate() {
+            let key = (Uint128::new(*price).into(), b"order".as_ref());
+            ORDERS.save(&mut store, key, &(i as u64)).unwrap();
+        }
+        // a second order at the same price
+        ORDERS
+            .save(&mut store, (U128Key::new(7), b"order2"), &6)
+            .unwrap();
+
+        // ascending iteration matches numeric order across the u64 boundary
+        let all: StdResult<Vec<_>> = ORDERS
+            .range_de(&store, None, None, Order::Ascending)
+            .collect();
+        let keys: Vec<_> = all.unwrap().into_iter().map(|((p, _), v)| (p, v)).collect();
+        assert_eq!(
+            keys,
+            vec![
+                (0, 5),
+                (7, 1),
+                (7, 6),
+                (big, 2),
+                (big + 1, 0),
+                (big * 1000, 4),
+                (u128::MAX, 3)
+            ]
+        );
+
+        // and it composes as the first element of a tuple key
+        let all: StdResult<Vec<_>> = ORDERS
+            .prefix(U128Key::new(7))
+            .range(&store, None, None, Order::Descending)
+            .collect();
+        assert_eq!(
+            all.unwrap(),
+            vec![(b"order2".to_vec(), 6), (b"order".to_vec(), 1)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn addr_composite_keys_read_byte_slice_state() {
+        use cosmwasm_std::Addr;
+
+        // existing state, keyed by the address bytes
+        const OLD_ALLOWANCE: Map<(&[u8], &[u8]), u64> = Map::new("allow");
+        const TYPED_ALLOWANCE: Map<(&Addr, &Addr), u64> = Map::new("allow");
+        const BY_ID: Map<(U64Key, &Addr), u64> = Map::new("by_id");
+
+        let mut store = MockStorage::new();
+        let owner = Addr::unchecked("owner");
+        let spender = Addr::unchecked("spender");
+        let other = Addr::unchecked("other");
+        OLD_ALLOWANCE
+            .save(&mut store, (b"owner", b"spender"), &10)
+            .unwrap();
+        TYPED_ALLOWANCE
+            .save(&mut store, (&owner, &other), &20)
+            .unwrap();
+        TYPED_ALLOWANCE
+            .save(&mut store, (&other, &owner), &30)
+            .unwrap();
+
+        assert_eq!(
+            10,
+            TYPED_ALLOWANCE.load(&store, (&owner, &spender)).unwrap()
+        );
+        assert_eq!(
+            20,
+            OLD_ALLOWANCE.load(&store, (b"owner", b"other")).unwrap()
+        );
+
+        // prefix by owner
+        let spenders: Vec<_> = TYPED_ALLOWANCE
+            .prefix(&owner)
+            .range_de::<&Addr>(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(spenders, vec![(other.clone(), 20), (spender.clone(), 10)]);
+
+        // int first
+        BY_ID
+            .save(&mut store, (U64Key::new(1), &owner), &1)
+            .unwrap();
+        BY_ID
+            .save(&mut store, (U64Key::new(1), &spender), &2)
+            .unwrap();
+        BY_ID
+            .save(&mut store, (U64Key::new(2), &owner), &3)
+            .unwrap();
+        let all: Vec<_> = BY_ID
+            .prefix(U64Key::new(1))
+            .range_de::<&Addr>(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(all, vec![(owner, 1), (spender, 2)]);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn paginate_with_typed_bounds() {
+        use cosmwasm_std::Addr;
+
+        const PROPOSALS: Map<U64Key, u64> = Map::new("proposals");
+        const EXPIRATIONS: Map<(&Addr, U64Key), u64> = Map::new("expirations");
+        let mut store = MockStorage::new();
+
+        for id in 1..=7u64 {
+            PROPOSALS
+                .save(&mut store, U64Key::new(id), &(id * 10))
+                .unwrap();
+        }
+
+        // page through u64 keys, 3 at a time, using start_after
+        let mut pages = vec![];
+        let mut start_after: Option<u64> = None;
+        loop {
+            let start = start_after.map(|id| Bound::exclusive(U64Key::new(id)));
+            let page: Vec<_> = PROPOSALS
+                .range_de(&store, start, None, Order::Ascending)
+                .take(3)
+                .collect::<StdResult<_>>()
+                .unwrap();
+            if page.is_empty() {
+                break;
+            }
+            start_after = page.last().map(|(id, _)| *id);
+            pages.push(page.into_iter().map(|(id, _)| id).collect::<Vec<_>>());
+        }
+        assert_eq!(pages, vec![vec![1, 2, 3], vec![4, 5, 6], vec![7]]);
+
+        // inclusive bound with the typed key, descending
+        let ids: Vec<_> = PROPOSALS
+            .range_de(
+                &store,
+                None,
+                Some(Bound::inclusive(U64Key::new(2))),
+                Order::Descending,
+            )
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(ids, vec![2, 1]);
+
+        // composite (address, height) keys
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+        for (addr, height) in [(&alice, 10u64), (&alice, 20), (&bob, 5), (&alice, 5)].iter() {
+            EXPIRATIONS
+                .save(&mut store, (*addr, U64Key::new(*height)), height)
+                .unwrap();
+        }
+
+        // note that keys are sorted by the length-prefixed address first, so "bob" comes before "alice"
+        let start = Bound::exclusive((&bob, U64Key::new(5)));
+        let page: Vec<_> = EXPIRATIONS
+            .range_de(&store, Some(start), None, Order::Ascending)
+            .take(2)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(
+            page,
+            vec![((alice.clone(), 5), 5), ((alice.clone(), 10), 10)]
+        );
+
+        // the next page starts after the last returned key
+        let ((addr, height), _) = page.last().unwrap();
+        let start = Bound::exclusive((addr, U64Key::new(*height)));
+        let page: Vec<_> = EXPIRATIONS
+            .range_de(&store, Some(start), None, Order::Ascending)
+            .take(2)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(page, vec![((alice.clone(), 20), 20)]);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn clear_map() {
+        use crate::{Item, U32Key};
+
+        const NUMBERS: Map<U32Key, Data> = Map::new("numbers");
+        const NUMBERS2: Map<&[u8], Data> = Map::new("numbers2");
+        const COUNTER: Item<u64> = Item::new("numbers");
+        let mut store = MockStorage::new();
+
+        let data = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        for i in 0..250u32 {
+            NUMBERS.save(&mut store, U32Key::new(i), &data).unwrap();
+        }
+        // entries outside the namespace, with similar names
+        NUMBERS2.save(&mut store, b"john", &data).unwrap();
+        PEOPLE.save(&mut store, b"john", &data).unwrap();
+        COUNTER.save(&mut store, &7).unwrap();
+
+        // with a limit, only removes that many, in several batches
+        assert_eq!(120, NUMBERS.clear(&mut store, Some(120)));
+        let count = NUMBERS.range(&store, None, None, Order::Ascending).count();
+        assert_eq!(130, count);
+        // the lowest keys were removed
+        assert!(!NUMBERS.has(&store, U32Key::new(119)));
+        assert!(NUMBERS.has(&store, U32Key::new(120)));
+
+        // loop until done
+        assert_eq!(100, NUMBERS.clear(&mut store, Some(100)));
+        assert_eq!(30, NUMBERS.clear(&mut store, Some(100)));
+        assert_eq!(0, NUMBERS.clear(&mut store, None));
+        let count = NUMBERS.range(&store, None, None, Order::Ascending).count();
+        assert_eq!(0, count);
+
+        // everything else is untouched
+        assert_eq!(data, NUMBERS2.load(&store, b"john").unwrap());
+        assert_eq!(data, PEOPLE.load(&store, b"john").unwrap());
+        assert_eq!(7, COUNTER.load(&store).unwrap());
+
+        // clear without a limit removes more than one batch
+        for i in 0..250u32 {
+            NUMBERS.save(&mut store, U32Key::new(i), &data).unwrap();
+        }
+        assert_eq!(250, NUMBERS.clear(&mut store, None));
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn migrate_with_raw_full_keys() {
+        const NEW_PEOPLE: Map<&[u8], Data> = Map::new("people_v2");
+        let mut store = MockStorage::new();
+        for (name, age) in [(b"john".as_ref(), 32), (b"jim", 44), (b"maria", 42)].iter() {
+            let data = Data {
+                name: String::from_utf8(name.to_vec()).unwrap(),
+                age: *age,
+            };
+            PEOPLE.save(&mut store, name, &data).unwrap();
+        }
+        // another map, sharing the beginning of the namespace
+        const PEOPLE_EXTRA: Map<&[u8], u64> = Map::new("peoplex");
+        PEOPLE_EXTRA.save(&mut store, b"john", &1).unwrap();
+        let before: Vec<_> = PEOPLE
+            .range(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+
+        let entries: Vec<_> = PEOPLE
+            .prefix(())
+            .range_raw_full(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(3, entries.len());
+        let (full_key, key, value) = &entries[0];
+        assert_eq!(b"jim", key.as_slice());
+        assert_eq!(PEOPLE.key(b"jim").as_slice(), full_key.as_slice());
+        assert_eq!(store.get(full_key).unwrap(), *value);
+
+        for (full_key, key, value) in entries {
+            store.set(NEW_PEOPLE.key(&key).as_slice(), &value);
+            store.remove(&full_key);
+        }
+
+        let after: Vec<_> = NEW_PEOPLE
+            .range(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(before, after);
+        assert!(PEOPLE.is_empty(&store));
+        assert_eq!(1, PEOPLE_EXTRA.load(&store, b"john").unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn clear_prefix() {
+        let mut store = MockStorage::new();
+
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender"), &1000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender2"), &3000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner2", b"spender"), &5000)
+            .unwrap();
+        // another owner sharing the beginning of the name
+        ALLOWANCE
+            .save(&mut store, (b"own", b"spender"), &7000)
+            .unwrap();
+
+        assert_eq!(2, ALLOWANCE.prefix(b"owner").clear(&mut store, None));
+        assert_eq!(
+            None,
+            ALLOWANCE.may_load(&store, (b"owner", b"spender")).unwrap()
+        );
+        assert_eq!(
+            5000,
+            ALLOWANCE.load(&store, (b"owner2", b"spender")).unwrap()
+        );
+        assert_eq!(7000, ALLOWANCE.load(&store, (b"own", b"spender")).unwrap());
+
+        // clears all the triple keys under the owner, at most 1 at a time
+        TRIPLE
+            .save(&mut store, (b"owner", 9u8.into(), "recipient"), &1000)
+            .unwrap();
+        TRIPLE
+            .save(&mut store, (b"owner", 10u8.into(), "recipient"), &3000)
+            .unwrap();
+        assert_eq!(1, TRIPLE.sub_prefix(b"owner").clear(&mut store, Some(1)));
+        assert_eq!(1, TRIPLE.sub_prefix(b"owner").clear(&mut store, Some(1)));
+        assert_eq!(0, TRIPLE.sub_prefix(b"owner").clear(&mut store, Some(1)));
+
+        // clearing the whole map works for any key type
+        assert_eq!(2, ALLOWANCE.clear(&mut store, None));
+        let count = ALLOWANCE
+            .range(&store, None, None, Order::Ascending)
+            .count();
+        assert_eq!(0, count);
+    }
+
+    #[test]
+    fn remove_many_keys() {
+        let mut store = MockStorage::new();
+        for spender in [b"spender".as_ref(), b"spender2", b"spender3"].iter() {
+            ALLOWANCE.save(&mut store, (b"owner", spender), &5).unwrap();
+        }
+
+        // missing keys are skipped
+        ALLOWANCE.remove_many(
+            &mut store,
+            &[
+                (b"owner", b"spender"),
+                (b"owner", b"spender3"),
+                (b"owner", b"unknown"),
+            ],
+        );
+        assert!(!ALLOWANCE.has(&store, (b"owner", b"spender")));
+        assert!(ALLOWANCE.has(&store, (b"owner", b"spender2")));
+        assert!(!ALLOWANCE.has(&store, (b"owner", b"spender3")));
+        ALLOWANCE.remove_many(&mut store, &[]);
+        assert!(ALLOWANCE.has(&store, (b"owner", b"spender2")));
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn remove_up_to_in_batches() {
+        // (owner, expiration) -> amount
+        const EXPIRING: Map<(&[u8], U64Key), u64> = Map::new("expiring");
+        let mut store = MockStorage::new();
+        for expires in 1..=10u64 {
+            EXPIRING
+                .save(&mut store, (b"owner", expires.into()), &expires)
+                .unwrap();
+            EXPIRING
+                .save(&mut store, (b"owner2", expires.into()), &expires)
+                .unwrap();
+        }
+
+        // clean up everything expired at 7, 3 at a time
+        let mut rounds = 0;
+        loop {
+            rounds += 1;
+            let removed = EXPIRING.prefix(b"owner").remove_up_to(
+                &mut store,
+                Some(Bound::inclusive_int(7u64)),
+                3,
+            );
+            if removed < 3 {
+                break;
+            }
+        }
+        assert_eq!(3, rounds);
+
+        let left: Vec<_> = EXPIRING
+            .prefix(b"owner")
+            .range(&store, None, None, Order::Ascending)
+            .map(|r| r.unwrap().1)
+            .collect();
+        assert_eq!(left, vec![8, 9, 10]);
+        // nothing left to remove, and the sibling prefix is untouched
+        assert_eq!(
+            0,
+            EXPIRING
+                .prefix(b"owner")
+                .remove_up_to(&mut store, Some(Bound::inclusive_int(7u64)), 3)
+        );
+        assert_eq!(10, EXPIRING.prefix(b"owner2").count(&store, None));
+
+        // without a bound, it removes the first entries
+        assert_eq!(
+            2,
+            EXPIRING.prefix(b"owner").remove_up_to(&mut store, None, 2)
+        );
+        assert_eq!(
+            Some(10),
+            EXPIRING.may_load(&store, (b"owner", 10.into())).unwrap()
+        );
+        assert_eq!(
+            1,
+            EXPIRING.prefix(b"owner").remove_up_to(&mut store, None, 2)
+        );
+        assert!(EXPIRING.prefix(b"owner").is_empty(&store));
+        assert_eq!(10, EXPIRING.prefix(b"owner2").count(&store, None));
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn is_empty_and_count() {
+        const PEOPLE2: Map<&[u8], Data> = Map::new("people2");
+        let mut store = MockStorage::new();
+
+        assert!(PEOPLE.is_empty(&store));
+        assert_eq!(0, PEOPLE.count(&store, None));
+        assert_eq!(0, PEOPLE.count(&store, Some(10)));
+
+        // entries in other namespaces are not seen
+        let data = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        PEOPLE2.save(&mut store, b"john", &data).unwrap();
+        ALLOWANCE.save(&mut store, (b"people", b"x"), &1).unwrap();
+        assert!(PEOPLE.is_empty(&store));
+        assert_eq!(0, PEOPLE.count(&store, None));
+
+        PEOPLE.save(&mut store, b"john", &data).unwrap();
+        PEOPLE.save(&mut store, b"jim", &data).unwrap();
+        // values are never parsed
+        store.set(&PEOPLE.key(b"joe"), b"invalid-json");
+        assert!(!PEOPLE.is_empty(&store));
+        assert_eq!(3, PEOPLE.count(&store, None));
+        assert_eq!(2, PEOPLE.count(&store, Some(2)));
+        assert_eq!(3, PEOPLE.count(&store, Some(5)));
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn is_empty_and_count_composite_key() {
+        let mut store = MockStorage::new();
+
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender"), &1000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender2"), &3000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner2", b"spender"), &5000)
+            .unwrap();
+
+        assert_eq!(3, ALLOWANCE.count(&store, None));
+        assert_eq!(2, ALLOWANCE.prefix(b"owner").count(&store, None));
+        assert_eq!(1, ALLOWANCE.prefix(b"owner").count(&store, Some(1)));
+        assert_eq!(1, ALLOWANCE.prefix(b"owner2").count(&store, None));
+        assert!(!ALLOWANCE.prefix(b"owner2").is_empty(&store));
+        // prefix of an existing owner
+        assert!(ALLOWANCE.prefix(b"own").is_empty(&store));
+        assert_eq!(0, ALLOWANCE.prefix(b"own").count(&store, None));
+
+        TRIPLE
+            .save(&mut store, (b"owner", 9u8.into(), "recipient"), &1000)
+            .unwrap();
+        TRIPLE
+            .save(&mut store, (b"owner", 10u8.into(), "recipient"), &3000)
+            .unwrap();
+        assert_eq!(2, TRIPLE.sub_prefix(b"owner").count(&store, None));
+        assert_eq!(1, TRIPLE.prefix((b"owner", 9u8.into())).count(&store, None));
+        assert!(TRIPLE.prefix((b"owner", 8u8.into())).is_empty(&store));
+        assert!(TRIPLE.sub_prefix(b"owner2").is_empty(&store));
+        assert_eq!(2, TRIPLE.count(&store, None));
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn first_and_last() {
+        let mut store = MockStorage::new();
+
+        // empty map
+        assert_eq!(None, PEOPLE.first(&store).unwrap());
+        assert_eq!(None, PEOPLE.last(&store).unwrap());
+
+        let john = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        let jim = Data {
+            name: "Jim".to_string(),
+            age: 44,
+        };
+        PEOPLE.save(&mut store, b"john", &john).unwrap();
+        PEOPLE.save(&mut store, b"jim", &jim).unwrap();
+
+        assert_eq!(Some((b"jim".to_vec(), jim)), PEOPLE.first(&store).unwrap());
+        assert_eq!(
+            Some((b"john".to_vec(), john.clone())),
+            PEOPLE.last(&store).unwrap()
+        );
+
+        // a single entry is both first and last
+        const SINGLE: Map<U64Key, Data> = Map::new("single");
+        SINGLE.save(&mut store, U64Key::new(7), &john).unwrap();
+        assert_eq!(Some((7, john.clone())), SINGLE.first(&store).unwrap());
+        assert_eq!(Some((7, john)), SINGLE.last(&store).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn first_and_last_composite_key() {
+        let mut store = MockStorage::new();
+
+        assert_eq!(None, ALLOWANCE.first(&store).unwrap());
+        assert_eq!(None, ALLOWANCE.prefix(b"owner").last(&store).unwrap());
+
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender"), &1000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender2"), &3000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner2", b"spender"), &5000)
+            .unwrap();
+
+        // the full (decoded) key on the map
+        assert_eq!(
+            Some(((b"owner".to_vec(), b"spender".to_vec()), 1000)),
+            ALLOWANCE.first(&store).unwrap()
+        );
+        assert_eq!(
+            Some(((b"owner2".to_vec(), b"spender".to_vec()), 5000)),
+            ALLOWANCE.last(&store).unwrap()
+        );
+
+        // the raw remaining key on a prefix
+        let prefix = ALLOWANCE.prefix(b"owner");
+        assert_eq!(
+            Some((b"spender".to_vec(), 1000)),
+            prefix.first(&store).unwrap()
+        );
+        assert_eq!(
+            Some((b"spender2".to_vec(), 3000)),
+            prefix.last(&store).unwrap()
+        );
+        assert_eq!(None, ALLOWANCE.prefix(b"own").first(&store).unwrap());
+    }
+
+    #[test]
+    fn oversized_key_segments_return_errors() {
+        let mut store = MockStorage::new();
+        let longest = vec![b'a'; 0xFFFF];
+        let too_long = vec![b'a'; 0x10000];
+
+        // 0xFFFF bytes can still be length-prefixed
+        ALLOWANCE
+            .save(&mut store, (&longest, b"spender"), &100)
+            .unwrap();
+        assert_eq!(100, ALLOWANCE.load(&store, (&longest, b"spender")).unwrap());
+        ALLOWANCE.key_checked((&longest, b"spender")).unwrap();
+
+        // one more byte is an error instead of a panic
+        let assert_too_long = |err: StdError| match err {
+            StdError::GenericErr { msg, .. } => assert_eq!(
+                msg,
+                "Key segment of 65536 bytes exceeds the maximum length of 65535 bytes"
+            ),
+            e => panic!("Unexpected error: {:?}", e),
+        };
+        assert_too_long(ALLOWANCE.key_checked((&too_long, b"spender")).unwrap_err());
+        assert_too_long(
+            ALLOWANCE
+                .save(&mut store, (&too_long, b"spender"), &100)
+                .unwrap_err(),
+        );
+        assert_too_long(ALLOWANCE.load(&store, (&too_long, b"spender")).unwrap_err());
+        assert_too_long(
+            ALLOWANCE
+                .may_load(&store, (&too_long, b"spender"))
+                .unwrap_err(),
+        );
+        assert_too_long(
+            ALLOWANCE
+                .update(&mut store, (&too_long, b"spender"), |_| -> StdResult<_> {
+                    Ok(1)
+                })
+                .unwrap_err(),
+        );
+        assert_too_long(
+            ALLOWANCE
+                .load_many(&store, &[(&longest, b"spender"), (&too_long, b"spender")])
+                .unwrap_err(),
+        );
+        // such keys cannot hold data, so has and remove do not panic either
+        assert!(!ALLOWANCE.has(&store, (&too_long, b"spender")));
+        ALLOWANCE.remove(&mut store, (&too_long, b"spender"));
+        assert!(ALLOWANCE.has(&store, (&longest, b"spender")));
+        ALLOWANCE.remove(&mut store, (&longest, b"spender"));
+        assert!(!ALLOWANCE.has(&store, (&longest, b"spender")));
+
+        // the last segment is not length-prefixed, so it may be longer
+        PEOPLE
+            .save(
+                &mut store,
+                &too_long,
+                &Data {
+                    name: "John".to_string(),
+                    age: 32,
+                },
+            )
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner", &too_long), &100)
+            .unwrap();
+
+        // same for the Path
+        Path::<u64>::new_checked(&longest, &[b"foo", b"bar"]).unwrap();
+        assert_too_long(Path::<u64>::new_checked(&too_long, &[b"foo"]).unwrap_err());
+        assert_too_long(Path::<u64>::new_checked(b"ns", &[&too_long, b"bar"]).unwrap_err());
+        Path::<u64>::new_checked(b"ns", &[b"foo", &too_long]).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn owned_and_borrowed_keys_share_data() {
+        let mut store = MockStorage::new();
+        const BORROWED: Map<&Addr, u64> = Map::new("balance");
+        const OWNED: Map<Addr, u64> = Map::new("balance");
+        const NAMES: Map<&str, u64> = Map::new("names");
+        const OWNED_NAMES: Map<String, u64> = Map::new("names");
+        const BY_HEIGHT: Map<(&Addr, U64Key), u64> = Map::new("height");
+        const OWNED_BY_HEIGHT: Map<(Addr, U64Key), u64> = Map::new("height");
+
+        let john = Addr::unchecked("john");
+        BORROWED.save(&mut store, &john, &100).unwrap();
+        OWNED.save(&mut store, Addr::unchecked("jim"), &50).unwrap();
+        assert_eq!(100, OWNED.load(&store, john.clone()).unwrap());
+        assert_eq!(50, BORROWED.load(&store, &Addr::unchecked("jim")).unwrap());
+        assert_eq!(
+            BORROWED.key(&john).to_vec(),
+            OWNED.key(john.clone()).to_vec()
+        );
+
+        let all: Vec<_> = OWNED
+            .range_de(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(
+            all,
+            vec![(Addr::unchecked("jim"), 50), (Addr::unchecked("john"), 100)]
+        );
+
+        OWNED_NAMES
+            .save(&mut store, "maria".to_string(), &7)
+            .unwrap();
+        assert_eq!(7, NAMES.load(&store, "maria").unwrap());
+
+        OWNED_BY_HEIGHT
+            .save(&mut store, (john.clone(), 12345.into()), &1)
+            .unwrap();
+        assert_eq!(1, BY_HEIGHT.load(&store, (&john, 12345.into())).unwrap());
+        let heights: Vec<_> = OWNED_BY_HEIGHT
+            .prefix(john.clone())
+            .keys(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(heights, vec![12345u64.to_be_bytes().to_vec()]);
+        let entries: Vec<_> = OWNED_BY_HEIGHT
+            .range_de(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(entries, vec![((john, 12345), 1)]);
+    }
+
+    #[test]
+    fn raw_storage_keys() {
+        use crate::Item;
+
+        let mut store = MockStorage::new();
+        let data = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        PEOPLE.save(&mut store, b"john", &data).unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender"), &1234)
+            .unwrap();
+
+        let path = PEOPLE.key(b"john");
+        assert_eq!(b"\x00\x06peoplejohn", path.as_slice());
+        let raw = store.get(path.as_slice()).unwrap();
+        assert_eq!(data, cosmwasm_std::from_slice::<Data>(&raw).unwrap());
+
+        let path = ALLOWANCE.key((b"owner", b"spender"));
+        assert_eq!(b"\x00\x05allow\x00\x05ownerspender", path.as_slice());
+        assert_eq!(b"1234".to_vec(), store.get(path.as_slice()).unwrap());
+
+        const CONFIG: Item<u64> = Item::new("config");
+        CONFIG.save(&mut store, &7).unwrap();
+        assert_eq!(b"7".to_vec(), store.get(CONFIG.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn describe_matches_keys() {
+        assert_eq!(b"people", PEOPLE.namespace());
+        let description = PEOPLE.describe();
+        assert_eq!("Map", description.kind);
+        assert_eq!(b"people", description.namespace);
+        // the type names tell the key and value types apart
+        const OTHER_PEOPLE: Map<&[u8], Data> = Map::new("other");
+        let other = OTHER_PEOPLE.describe();
+        assert_eq!(description.key_type, other.key_type);
+        assert_eq!(description.value_type, other.value_type);
+        let allowance = ALLOWANCE.describe();
+        assert!(allowance.key_type.is_some());
+        assert_ne!(description.key_type, allowance.key_type);
+        assert_ne!(description.value_type, allowance.value_type);
+
+        // the keys start with the length-prefixed namespace
+        let path = PEOPLE.key(b"john");
+        let prefix = [b"\x00\x06".as_ref(), description.namespace].concat();
+        assert_eq!([prefix.as_slice(), b"john"].concat(), path.as_slice());
+        let path = TRIPLE.key((b"john", 8u8.into(), "pedro"));
+        assert!(path.as_slice().starts_with(b"\x00\x06triple"));
+        assert_eq!(b"triple", TRIPLE.describe().namespace);
+    }
+
+    #[test]
+    fn has_does_not_parse_values() {
+        let mut store = MockStorage::new();
+        // not a valid json encoding of Data
+        store.set(&PEOPLE.key(b"john"), b"{garbage");
+
+        assert!(PEOPLE.has(&store, b"john"));
+        assert!(PEOPLE.key(b"john").has(&store));
+        PEOPLE.may_load(&store, b"john").unwrap_err();
+        assert!(!PEOPLE.has(&store, b"jim"));
+        assert!(!PEOPLE.key(b"jim").has(&store));
+
+        // composite keys
+        store.set(&ALLOWANCE.key((b"owner", b"spender")), b"-");
+        assert!(ALLOWANCE.has(&store, (b"owner", b"spender")));
+        ALLOWANCE
+            .may_load(&store, (b"owner", b"spender"))
+            .unwrap_err();
+        assert!(!ALLOWANCE.has(&store, (b"owner", b"other")));
+    }
+
+    #[test]
+    fn query_remote_contract() {
+        use crate::Item;
+        use cosmwasm_std::{
+            from_slice, to_binary, ContractResult, Empty, Querier, QuerierResult, QueryRequest,
+            SystemResult, WasmQuery,
+        };
+
+        // answers raw queries from the storage of one contract
+        struct RawQuerier {
+            contract: String,
+            storage: MockStorage,
+        }
+
+        impl Querier for RawQuerier {
+            fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+                match from_slice(bin_request).unwrap() {
+                    QueryRequest::<Empty>::Wasm(WasmQuery::Raw { contract_addr, key }) => {
+                        assert_eq!(self.contract, contract_addr);
+                        let value = self.storage.get(&key).unwrap_or_default();
+                        SystemResult::Ok(ContractResult::Ok(value.into()))
+                    }
+                    _ => SystemResult::Ok(ContractResult::Ok(to_binary(&()).unwrap())),
+                }
+            }
+        }
+
+        const CONFIG: Item<Data> = Item::new("config");
+        let mut storage = MockStorage::new();
+        let data = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        PEOPLE.save(&mut storage, b"john", &data).unwrap();
+        ALLOWANCE
+            .save(&mut storage, (b"owner", b"spender"), &1000)
+            .unwrap();
+        CONFIG.save(&mut storage, &data).unwrap();
+
+        let remote = Addr::unchecked("remote");
+        let raw = RawQuerier {
+            contract: remote.to_string(),
+            storage,
+        };
+        let querier = QuerierWrapper::new(&raw);
+
+        let john = PEOPLE.query(&querier, remote.clone(), b"john").unwrap();
+        assert_eq!(Some(data.clone()), john);
+        let jim = PEOPLE.query(&querier, remote.clone(), b"jim").unwrap();
+        assert_eq!(None, jim);
+
+        let allowance = ALLOWANCE
+            .query(&querier, remote.clone(), (b"owner", b"spender"))
+            .unwrap();
+        assert_eq!(Some(1000), allowance);
+        let allowance = ALLOWANCE
+            .query(&querier, remote.clone(), (b"owner", b"other"))
+            .unwrap();
+        assert_eq!(None, allowance);
+
+        assert_eq!(Some(data), CONFIG.query(&querier, remote.clone()).unwrap());
+        let other: Item<Data> = Item::new("other");
+        assert_eq!(None, other.query(&querier, remote).unwrap());
+    }
+
+    #[test]
+    fn load_many_keeps_input_order() {
+        let mut store = MockStorage::new();
+
+        // empty input
+        assert_eq!(PEOPLE.load_many(&store, &[]).unwrap(), vec![]);
+
+        let john = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        let jim = Data {
+            name: "Jim".to_string(),
+            age: 44,
+        };
+        PEOPLE.save(&mut store, b"john", &john).unwrap();
+        PEOPLE.save(&mut store, b"jim", &jim).unwrap();
+
+        // missing keys give None, duplicates are returned every time
+        let loaded = PEOPLE
+            .load_many(&store, &[b"jim", b"joe", b"john", b"jim"])
+            .unwrap();
+        assert_eq!(loaded, vec![Some(jim.clone()), None, Some(john), Some(jim)]);
+
+        // a parse error fails the batch
+        store.set(&PEOPLE.key(b"joe"), b"invalid-json");
+        PEOPLE.load_many(&store, &[b"jim", b"joe"]).unwrap_err();
+
+        // composite keys
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender"), &1000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner2", b"spender"), &5000)
+            .unwrap();
+        let loaded = ALLOWANCE
+            .load_many(
+                &store,
+                &[
+                    (b"owner2", b"spender"),
+                    (b"owner", b"spender2"),
+                    (b"owner", b"spender"),
+                ],
+            )
+            .unwrap();
+        assert_eq!(loaded, vec![Some(5000), None, Some(1000)]);
+    }
+
+    #[test]
+    fn save_if_absent_and_take() {
+        let mut store = MockStorage::new();
+
+        let john = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        let jim = Data {
+            name: "Jim".to_string(),
+            age: 44,
+        };
+        PEOPLE.save_if_absent(&mut store, b"john", &john).unwrap();
+        let err = PEOPLE
+            .save_if_absent(&mut store, b"john", &jim)
+            .unwrap_err();
+        match err {
+            StdError::GenericErr { msg, .. } => assert!(msg.ends_with("already exists")),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        // the existing data is untouched
+        assert_eq!(john, PEOPLE.load(&store, b"john").unwrap());
+
+        assert_eq!(Some(john), PEOPLE.take(&mut store, b"john").unwrap());
+        assert_eq!(None, PEOPLE.may_load(&store, b"john").unwrap());
+        assert_eq!(None, PEOPLE.take(&mut store, b"john").unwrap());
+
+        // can be saved again once removed
+        PEOPLE.save_if_absent(&mut store, b"john", &jim).unwrap();
+        assert_eq!(jim, PEOPLE.load(&store, b"john").unwrap());
+    }
+
+    #[test]
+    fn save_if_absent_and_take_composite_key() {
+        let mut store = MockStorage::new();
+
+        ALLOWANCE
+            .save_if_absent(&mut store, (b"owner", b"spender"), &1000)
+            .unwrap();
+        ALLOWANCE
+            .save_if_absent(&mut store, (b"owner", b"spender2"), &2000)
+            .unwrap();
+        ALLOWANCE
+            .save_if_absent(&mut store, (b"owner", b"spender"), &3000)
+            .unwrap_err();
+        assert_eq!(
+            1000,
+            ALLOWANCE.load(&store, (b"owner", b"spender")).unwrap()
+        );
+
+        assert_eq!(
+            Some(1000),
+            ALLOWANCE.take(&mut store, (b"owner", b"spender")).unwrap()
+        );
+        assert_eq!(
+            None,
+            ALLOWANCE.take(&mut store, (b"owner", b"spender")).unwrap()
+        );
+        assert_eq!(
+            2000,
+            ALLOWANCE.load(&store, (b"owner", b"spender2")).unwrap()
+        );
+
+        TRIPLE
+            .save_if_absent(&mut store, (b"owner", 9u8.into(), "recipient"), &1000)
+            .unwrap();
+        TRIPLE
+            .save_if_absent(&mut store, (b"owner", 9u8.into(), "recipient"), &1000)
+            .unwrap_err();
+        assert_eq!(
+            Some(1000),
+            TRIPLE
+                .take(&mut store, (b"owner", 9u8.into(), "recipient"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn update_or_default_accumulates() {
+        use cosmwasm_std::{Addr, Uint128};
+
+        const BALANCES: Map<&Addr, Uint128> = Map::new("balances");
+        let mut store = MockStorage::new();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+
+        // the first call creates the entry
+        let balance = BALANCES
+            .update_or_default(&mut store, &alice, |b| -> StdResult<_> {
+                Ok(b + Uint128::new(100))
+            })
+            .unwrap();
+        assert_eq!(Uint128::new(100), balance);
+
+        // later calls accumulate
+        let balance = BALANCES
+            .update_or_default(&mut store, &alice, |b| -> StdResult<_> {
+                Ok(b + Uint128::new(23))
+            })
+            .unwrap();
+        assert_eq!(Uint128::new(123), balance);
+        assert_eq!(Uint128::new(123), BALANCES.load(&store, &alice).unwrap());
+
+        // errors are returned, nothing is saved
+        let err = BALANCES
+            .update_or_default(&mut store, &bob, |b| -> StdResult<_> {
+                Ok(b.checked_sub(Uint128::new(1))?)
+            })
+            .unwrap_err();
+        assert!(matches!(err, StdError::Overflow { .. }));
+        assert_eq!(None, BALANCES.may_load(&store, &bob).unwrap());
+    }
+
+    #[test]
+    fn update_old_returns_previous_value() {
+        let mut store = MockStorage::new();
+
+        // no prior value
+        let (old, new) = ALLOWANCE
+            .update_old(&mut store, (b"owner", b"spender"), |old| -> StdResult<_> {
+                assert_eq!(None, old);
+                Ok(100)
+            })
+            .unwrap();
+        assert_eq!((None, 100), (old, new));
+
+        let (old, new) = ALLOWANCE
+            .update_old(&mut store, (b"owner", b"spender"), |old| -> StdResult<_> {
+                Ok(old.unwrap_or_default() + 50)
+            })
+            .unwrap();
+        assert_eq!((Some(100), 150), (old, new));
+        assert_eq!(150, ALLOWANCE.load(&store, (b"owner", b"spender")).unwrap());
+
+        // nothing is written on error
+        let err = ALLOWANCE
+            .update_old(&mut store, (b"owner", b"spender"), |_| {
+                Err(StdError::generic_err("no way"))
+            })
+            .unwrap_err();
+        assert_eq!(StdError::generic_err("no way"), err);
+        assert_eq!(150, ALLOWANCE.load(&store, (b"owner", b"spender")).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn paginate_simple_key() {
+        const IDS: Map<U64Key, u64> = Map::new("ids");
+        let mut store = MockStorage::new();
+
+        // empty map
+        let (items, next) = IDS
+            .paginate(&store, None, None, 10, Order::Ascending)
+            .unwrap();
+        assert!(items.is_empty());
+        assert_eq!(None, next);
+
+        for id in 1..=5u64 {
+            IDS.save(&mut store, U64Key::new(id * 10), &id).unwrap();
+        }
+
+        let (items, next) = IDS
+            .paginate(&store, None, Some(2), 10, Order::Ascending)
+            .unwrap();
+        assert_eq!(items, vec![(10, 1), (20, 2)]);
+        assert_eq!(Some(20), next);
+
+        // insertions before the cursor don't change the next pages
+        IDS.save(&mut store, U64Key::new(5), &0).unwrap();
+        IDS.save(&mut store, U64Key::new(15), &0).unwrap();
+
+        let (items, next) = IDS
+            .paginate(&store, next.map(U64Key::new), Some(2), 10, Order::Ascending)
+            .unwrap();
+        assert_eq!(items, vec![(30, 3), (40, 4)]);
+        assert_eq!(Some(40), next);
+
+        // the last page has no cursor, even if it is full
+        let (items, next) = IDS
+            .paginate(&store, next.map(U64Key::new), Some(1), 10, Order::Ascending)
+            .unwrap();
+        assert_eq!(items, vec![(50, 5)]);
+        assert_eq!(None, next);
+
+        // limit is capped by max_limit, and defaults to it
+        let (items, next) = IDS
+            .paginate(&store, None, Some(100), 3, Order::Ascending)
+            .unwrap();
+        assert_eq!(3, items.len());
+        assert_eq!(Some(15), next);
+        let (items, _) = IDS
+            .paginate(&store, None, None, 4, Order::Ascending)
+            .unwrap();
+        assert_eq!(4, items.len());
+        // a limit of 0 still makes progress
+        let (items, next) = IDS
+            .paginate(&store, None, Some(0), 10, Order::Ascending)
+            .unwrap();
+        assert_eq!(items, vec![(5, 0)]);
+        assert_eq!(Some(5), next);
+
+        // descending
+        let (items, next) = IDS
+            .paginate(&store, None, Some(3), 10, Order::Descending)
+            .unwrap();
+        assert_eq!(items, vec![(50, 5), (40, 4), (30, 3)]);
+        assert_eq!(Some(30), next);
+        let (items, next) = IDS
+            .paginate(
+                &store,
+                next.map(U64Key::new),
+                Some(3),
+                10,
+                Order::Descending,
+            )
+            .unwrap();
+        assert_eq!(items, vec![(20, 2), (15, 0), (10, 1)]);
+        assert_eq!(Some(10), next);
+        let (items, next) = IDS
+            .paginate(
+                &store,
+                next.map(U64Key::new),
+                Some(3),
+                10,
+                Order::Descending,
+            )
+            .unwrap();
+        assert_eq!(items, vec![(5, 0)]);
+        assert_eq!(None, next);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn paginate_composite_key() {
+        let mut store = MockStorage::new();
+
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender"), &1000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender2"), &3000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner2", b"spender"), &5000)
+            .unwrap();
+
+        let (items, next) = ALLOWANCE
+            .paginate(&store, None, Some(2), 30, Order::Ascending)
+            .unwrap();
+        assert_eq!(
+            items,
+            vec![
+                ((b"owner".to_vec(), b"spender".to_vec()), 1000),
+                ((b"owner".to_vec(), b"spender2".to_vec()), 3000),
+            ]
+        );
+        let (owner, spender) = next.unwrap();
+        assert_eq!(
+            (b"owner".to_vec(), b"spender2".to_vec()),
+            (owner.clone(), spender.clone())
+        );
+
+        let start_after = (owner.as_slice(), spender.as_slice());
+        let (items, next) = ALLOWANCE
+            .paginate(&store, Some(start_after), Some(2), 30, Order::Ascending)
+            .unwrap();
+        assert_eq!(
+            items,
+            vec![((b"owner2".to_vec(), b"spender".to_vec()), 5000)]
+        );
+        assert_eq!(None, next);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn keys_simple_key() {
+        let mut store = MockStorage::new();
+
+        let data = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        PEOPLE.save(&mut store, b"john", &data).unwrap();
+        PEOPLE.save(&mut store, b"jim", &data).unwrap();
+        // values are never parsed, so even invalid data doesn't break keys()
+        store.set(&PEOPLE.key(b"joe"), b"invalid-json");
+
+        let keys: Vec<_> = PEOPLE.keys(&store, None, None, Order::Ascending).collect();
+        assert_eq!(
+            keys,
+            vec![b"jim".to_vec(), b"joe".to_vec(), b"john".to_vec()]
+        );
+
+        // same bound semantics as range
+        let keys: Vec<_> = PEOPLE
+            .keys(
+                &store,
+                Some(Bound::Exclusive(b"jim".to_vec())),
+                Some(Bound::Inclusive(b"joe".to_vec())),
+                Order::Descending,
+            )
+            .collect();
+        assert_eq!(keys, vec![b"joe".to_vec()]);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn keys_composite_key() {
+        let mut store = MockStorage::new();
+
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender"), &1000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner", b"spender2"), &3000)
+            .unwrap();
+        ALLOWANCE
+            .save(&mut store, (b"owner2", b"spender"), &5000)
+            .unwrap();
+
+        // only the last element of the key is returned under a prefix
+        let keys: Vec<_> = ALLOWANCE
+            .prefix(b"owner")
+            .keys(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(keys, vec![b"spender".to_vec(), b"spender2".to_vec()]);
+
+        let keys: Vec<_> = ALLOWANCE
+            .prefix(b"owner")
+            .keys(
+                &store,
+                Some(Bound::Exclusive(b"spender".to_vec())),
+                None,
+                Order::Ascending,
+            )
+            .collect();
+        assert_eq!(keys, vec![b"spender2".to_vec()]);
+
+        // same for triple keys with a two element prefix
+        TRIPLE
+            .save(&mut store, (b"owner", 9u8.into(), "recipient"), &1000)
+            .unwrap();
+        TRIPLE
+            .save(&mut store, (b"owner", 9u8.into(), "recipient2"), &3000)
+            .unwrap();
+        TRIPLE
+            .save(&mut store, (b"owner", 10u8.into(), "recipient3"), &3000)
+            .unwrap();
+        let keys: Vec<_> = TRIPLE
+            .prefix((b"owner", 9u8.into()))
+            .keys(&store, None, None, Order::Descending)
+            .collect();
+        assert_eq!(keys, vec![b"recipient2".to_vec(), b"recipient".to_vec()]);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_de_simple_key() {
+        let mut store = MockStorage::new();
+
+        let data = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        PEOPLE.save(&mut store, b"john", &data).unwrap();
+        let data2 = Data {
+            name: "Jim".to_string(),
+            age: 44,
+        };
+        PEOPLE.save(&mut store, b"jim", &data2).unwrap();
+
+        // keys come back as owned Vec<u8>
+        let all: StdResult<Vec<_>> = PEOPLE
+            .range_de(&store, None, None, Order::Descending)
+            .collect();
+        assert_eq!(
+            all.unwrap(),
+            vec![(b"john".to_vec(), data), (b"jim".to_vec(), data2)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_de_composite_key() {
+        let mut store = MockStorage::new();
+
+        const EXPIRES: Map<(&[u8], U64Key), u64> = Map::new("expires");
+        EXPIRES
+            .save(&mut store, (b"owner", 500u64.into()), &1000)
+            .unwrap();
+        EXPIRES
+            .save(&mut store, (b"owner", 20u64.into()), &3000)
+            .unwrap();
+        EXPIRES
+            .save(&mut store, (b"owner2", 100u64.into()), &5000)
+            .unwrap();
+
+        // the full composite key is decoded, including the integer
+        let all: StdResult<Vec<_>> = EXPIRES
+            .range_de(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(
+            all.unwrap(),
+            vec![
+                ((b"owner".to_vec(), 20), 3000),
+                ((b"owner".to_vec(), 500), 1000),
+                ((b"owner2".to_vec(), 100), 5000),
+            ]
+        );
+
+        // malformed keys surface as errors, not panics
+        store.set(
+            &[EXPIRES.key((b"owner3", 1u64.into())).to_vec(), vec![1]].concat(),
+            b"7",
+        );
+        let all: StdResult<Vec<_>> = EXPIRES
+            .range_de(&store, None, None, Order::Ascending)
+            .collect();
+        assert!(matches!(
+            all.unwrap_err(),
+            cosmwasm_std::StdError::InvalidDataSize { .. }
+        ));
+    }
+
     #[test]
     fn basic_update() {
         let mut store = MockStorage::new();
@@ -2,12 +2,17 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::marker::PhantomData;
 
-use crate::helpers::{may_deserialize, must_deserialize, nested_namespaces_with_key};
-use cosmwasm_std::{to_vec, StdError, StdResult, Storage};
+use crate::helpers::{
+    check_namespaces, may_deserialize_with, must_deserialize_with, nested_namespaces_with_key,
+};
+use crate::serialization::{Json, Serde};
+use cosmwasm_std::{StdError, StdResult, Storage};
 use std::ops::Deref;
 
+/// Path stores one typed item at the given (full) key.
+/// The values are encoded with the Serde strategy `S`, which is Json by default.
 #[derive(Debug, Clone)]
-pub struct Path<T>
+pub struct Path<T, S = Json>
 where
     T: Serialize + DeserializeOwned,
 {
@@ -15,9 +20,10 @@ where
     storage_key: Vec<u8>,
     // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
     data: PhantomData<T>,
+    serde: PhantomData<S>,
 }
 
-impl<T> Deref for Path<T>
+impl<T, S> Deref for Path<T, S>
 where
     T: Serialize + DeserializeOwned,
 {
@@ -28,9 +34,10 @@ where
     }
 }
 
-impl<T> Path<T>
+impl<T, S> Path<T, S>
 where
     T: Serialize + DeserializeOwned,
+    S: Serde,
 {
     pub fn new(namespace: &[u8], keys: &[&[u8]]) -> Self {
         let l = keys.len();
@@ -39,12 +46,28 @@ where
         Path {
             storage_key,
             data: PhantomData,
+            serde: PhantomData,
         }
     }
 
+    /// new_checked works like new, but returns an error rather than panicking if the namespace
+    /// or any key but the last one is longer than 0xFFFF bytes, and so cannot be length-prefixed.
+    /// Use this when the keys come from user input.
+    pub fn new_checked(namespace: &[u8], keys: &[&[u8]]) -> StdResult<Self> {
+        check_namespaces(&[namespace])?;
+        check_namespaces(&keys[0..keys.len().saturating_sub(1)])?;
+        Ok(Path::new(namespace, keys))
+    }
+
+    /// as_slice returns the full raw key the value is stored at, with all the namespaces.
+    /// eg. to build a WasmQuery::Raw against another contract using the same layout
+    pub fn as_slice(&self) -> &[u8] {
+        &self.storage_key
+    }
+
     /// save will serialize the model and store, returns an error on serialization issues
     pub fn save(&self, store: &mut dyn Storage, data: &T) -> StdResult<()> {
-        store.set(&self.storage_key, &to_vec(data)?);
+        store.set(&self.storage_key, &S::to_vec(data)?);
         Ok(())
     }
 
@@ -55,14 +78,14 @@ where
     /// load will return an error if no data is set at the given key, or on parse error
     pub fn load(&self, store: &dyn Storage) -> StdResult<T> {
         let value = store.get(&self.storage_key);
-        must_deserialize(&value)
+        must_deserialize_with::<S, T>(&value)
     }
 
     /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
     /// returns an error on issues parsing
     pub fn may_load(&self, store: &dyn Storage) -> StdResult<Option<T>> {
         let value = store.get(&self.storage_key);
-        may_deserialize(&value)
+        may_deserialize_with::<S, T>(&value)
     }
 
     /// has returns true or false if any data is at this key, without parsing or interpreting the
@@ -85,4 +108,29 @@ where
         self.save(store, &output)?;
         Ok(output)
     }
+
+    /// update_or_default works like update, but calls `action` with the default value
+    /// if there is no data yet.
+    pub fn update_or_default<A, E>(&self, store: &mut dyn Storage, action: A) -> Result<T, E>
+    where
+        A: FnOnce(T) -> Result<T, E>,
+        E: From<StdError>,
+        T: Default,
+    {
+        self.update(store, |current| action(current.unwrap_or_default()))
+    }
+
+    /// update_old works like update, but returns the previous value (if any) along with the new one.
+    /// Useful to report both values, eg. in events.
+    pub fn update_old<A, E>(&self, store: &mut dyn Storage, action: A) -> Result<(Option<T>, T), E>
+    where
+        A: FnOnce(Option<T>) -> Result<T, E>,
+        E: From<StdError>,
+        T: Clone,
+    {
+        let old = self.may_load(store)?;
+        let output = action(old.clone())?;
+        self.save(store, &output)?;
+        Ok((old, output))
+    }
 }
@@ -3,11 +3,14 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::marker::PhantomData;
 
-use cosmwasm_std::{Order, Pair, StdResult, Storage};
+use cosmwasm_std::{Order, Pair, StdResult, Storage, Timestamp};
 use std::ops::Deref;
 
+use crate::de::KeyDeserialize;
 use crate::helpers::nested_namespaces_with_key;
 use crate::iter_helpers::{concat, deserialize_kv, trim};
+use crate::keys::{PrimaryKey, TimestampKey};
+use crate::serialization::{Json, Serde};
 use crate::Endian;
 
 /// Bound is used to defines the two ends of a range, more explicit than Option<u8>
@@ -21,27 +24,47 @@ pub enum Bound {
 }
 
 impl Bound {
-    /// Turns optional binary, like Option<CanonicalAddr> into an inclusive bound
-    pub fn inclusive<T: Into<Vec<u8>>>(limit: T) -> Self {
-        Bound::Inclusive(limit.into())
+    /// Turns a typed key, like &Addr, U64Key or (&Addr, U64Key) into an inclusive bound.
+    /// This uses the same (joined) encoding as the Map key, so the bound matches
+    /// when ranging over a Map with key type K.
+    /// Raw bytes (eg. CanonicalAddr) can still be used with Bound::Inclusive.
+    pub fn inclusive<'a, K: PrimaryKey<'a>>(limit: K) -> Self {
+        Bound::Inclusive(limit.joined_key())
     }
 
-    /// Turns optional binary, like Option<CanonicalAddr> into an exclusive bound
-    pub fn exclusive<T: Into<Vec<u8>>>(limit: T) -> Self {
-        Bound::Exclusive(limit.into())
+    /// Turns a typed key, like &Addr, U64Key or (&Addr, U64Key) into an exclusive bound.
+    /// This uses the same (joined) encoding as the Map key, so the bound matches
+    /// when ranging over a Map with key type K.
+    /// Raw bytes (eg. CanonicalAddr) can still be used with Bound::Exclusive.
+    pub fn exclusive<'a, K: PrimaryKey<'a>>(limit: K) -> Self {
+        Bound::Exclusive(limit.joined_key())
     }
 
     /// Turns an int, like Option<u32> into an inclusive bound
     pub fn inclusive_int<T: Endian>(limit: T) -> Self {
-        Bound::Inclusive(limit.to_be_bytes().into())
+        Bound::Inclusive(limit.to_key_bytes().into())
     }
 
     /// Turns an int, like Option<u64> into an exclusive bound
     pub fn exclusive_int<T: Endian>(limit: T) -> Self {
-        Bound::Exclusive(limit.to_be_bytes().into())
+        Bound::Exclusive(limit.to_key_bytes().into())
+    }
+
+    /// Turns a Timestamp into an inclusive bound, matching the TimestampKey encoding
+    pub fn inclusive_timestamp(limit: Timestamp) -> Self {
+        Bound::Inclusive(TimestampKey::new(limit).into())
+    }
+
+    /// Turns a Timestamp into an exclusive bound, matching the TimestampKey encoding
+    pub fn exclusive_timestamp(limit: Timestamp) -> Self {
+        Bound::Exclusive(TimestampKey::new(limit).into())
     }
 }
 
+/// A raw entry returned by Prefix::range_raw_full: the full storage key,
+/// the key relative to the prefix and the raw value
+pub type RawEntry = (Vec<u8>, Vec<u8>, Vec<u8>);
+
 type DeserializeFn<T> = fn(&dyn Storage, &[u8], Pair) -> StdResult<Pair<T>>;
 
 #[derive(Clone)]
@@ -73,8 +96,13 @@ where
     T: Serialize + DeserializeOwned,
 {
     pub fn new(top_name: &[u8], sub_names: &[&[u8]]) -> Self {
+        Prefix::with_serde::<Json>(top_name, sub_names)
+    }
+
+    /// with_serde works like new, but parses the values with the given Serde strategy
+    pub fn with_serde<S: Serde>(top_name: &[u8], sub_names: &[&[u8]]) -> Self {
         Prefix::with_deserialization_function(top_name, sub_names, &[], |_, _, kv| {
-            deserialize_kv(kv)
+            deserialize_kv::<S, T>(kv)
         })
     }
 
@@ -111,6 +139,57 @@ where
         Box::new(mapped)
     }
 
+    /// range_de works like range, but deserializes the keys into the owned form of K, which
+    /// must be the type of the remaining components of the key, after this prefix.
+    /// eg. for a `Map<(&[u8], U8Key, &str), T>`, `map.sub_prefix(owner).range_de::<(U8Key, &str)>(...)`
+    /// yields `((u8, String), T)` items, and `map.prefix((owner, id)).range_de::<&str>(...)`
+    /// yields `(String, T)`.
+    pub fn range_de<'a, K>(
+        &self,
+        store: &'a dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'a>
+    where
+        T: 'a,
+        K: KeyDeserialize,
+        K::Output: 'a,
+    {
+        let mapped = self.range(store, min, max, order).map(|res| {
+            let (k, v) = res?;
+            Ok((K::from_vec(k)?, v))
+        });
+        Box::new(mapped)
+    }
+
+    /// range_paginated returns at most limit entries after the cursor in the given order.
+    /// The cursor is the (raw, relative) key of the last entry of the previous page, and is
+    /// never included. With Order::Ascending the page holds the keys greater than the cursor,
+    /// and with Order::Descending the keys lower than the cursor, from highest to lowest.
+    /// A None cursor starts from the first (or last) entry.
+    ///
+    /// This flips the cursor bound to max for descending order, which is easy to get wrong
+    /// when passing min and max to range directly.
+    pub fn range_paginated<'a>(
+        &self,
+        store: &'a dyn Storage,
+        cursor: Option<&[u8]>,
+        limit: usize,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<Pair<T>>> + 'a>
+    where
+        T: 'a,
+    {
+        let bound = cursor.map(|c| Bound::Exclusive(c.to_vec()));
+        let (min, max) = match order {
+            Order::Ascending => (bound, None),
+            Order::Descending => (None, bound),
+        };
+        Box::new(self.range(store, min, max, order).take(limit))
+    }
+
+    /// keys works like range, but only returns the raw keys, without deserializing the values
     pub fn keys<'a>(
         &self,
         store: &'a dyn Storage,
@@ -125,8 +204,107 @@ where
             range_with_prefix(store, &self.storage_prefix, min, max, order).map(|(k, _)| k);
         Box::new(mapped)
     }
+
+    /// range_raw_full works like range, but doesn't parse the values and returns, for every
+    /// entry, the full raw storage key (with all the namespaces), the key relative to the
+    /// prefix and the raw value. Useful to copy entries byte-for-byte, eg. in a migration
+    /// to a new namespace, and remove them at their full key afterwards.
+    pub fn range_raw_full<'a>(
+        &self,
+        store: &'a dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = RawEntry> + 'a> {
+        let storage_prefix = self.storage_prefix.clone();
+        let mapped = range_with_prefix(store, &self.storage_prefix, min, max, order)
+            .map(move |(k, v)| (concat(&storage_prefix, &k), k, v));
+        Box::new(mapped)
+    }
+
+    /// first returns the entry with the lowest key under this prefix (if any).
+    /// The key is returned as raw bytes, relative to the prefix.
+    pub fn first(&self, store: &dyn Storage) -> StdResult<Option<Pair<T>>> {
+        self.range(store, None, None, Order::Ascending)
+            .next()
+            .transpose()
+    }
+
+    /// last returns the entry with the highest key under this prefix (if any).
+    /// The key is returned as raw bytes, relative to the prefix.
+    pub fn last(&self, store: &dyn Storage) -> StdResult<Option<Pair<T>>> {
+        self.range(store, None, None, Order::Descending)
+            .next()
+            .transpose()
+    }
+
+    /// is_empty returns true if there are no entries under this prefix.
+    /// Only the first key is read, values are never parsed.
+    pub fn is_empty(&self, store: &dyn Storage) -> bool {
+        range_with_prefix(store, &self.storage_prefix, None, None, Order::Ascending)
+            .next()
+            .is_none()
+    }
+
+    /// count returns the number of entries under this prefix, counting at most limit entries
+    /// if given, to keep gas usage bounded. Values are never parsed.
+    pub fn count(&self, store: &dyn Storage, limit: Option<usize>) -> usize {
+        let iter = range_with_prefix(store, &self.storage_prefix, None, None, Order::Ascending);
+        match limit {
+            Some(limit) => iter.take(limit).count(),
+            None => iter.count(),
+        }
+    }
+
+    /// clear removes all the entries under this prefix, or at most limit entries if given.
+    /// Returns the number of entries removed. If this is equal to limit, there may be more left,
+    /// so it can be called again (eg. in a later migration step) until it returns less.
+    pub fn clear(&self, store: &mut dyn Storage, limit: Option<usize>) -> usize {
+        let mut removed = 0;
+        loop {
+            let batch = match limit {
+                Some(limit) => (limit - removed).min(CLEAR_BATCH_SIZE),
+                None => CLEAR_BATCH_SIZE,
+            };
+            if batch == 0 {
+                break;
+            }
+            // we cannot remove while iterating, so collect the keys of one batch first
+            let keys: Vec<Vec<u8>> = self
+                .keys(store, None, None, Order::Ascending)
+                .take(batch)
+                .collect();
+            for k in keys.iter() {
+                store.remove(&concat(&self.storage_prefix, k));
+            }
+            removed += keys.len();
+            if keys.len() < batch {
+                break;
+            }
+        }
+        removed
+    }
+
+    /// remove_up_to removes the first limit entries under this prefix (in ascending order),
+    /// stopping at max if given, eg. `Bound::inclusive_int(now)` for entries keyed by expiration.
+    /// Returns the number of entries removed. If this is less than limit, there are no entries
+    /// left below max, so it can be called in a loop (or in later calls) until it returns less.
+    pub fn remove_up_to(&self, store: &mut dyn Storage, max: Option<Bound>, limit: usize) -> usize {
+        // we cannot remove while iterating, so collect the keys first
+        let keys: Vec<Vec<u8>> = self
+            .keys(store, None, max, Order::Ascending)
+            .take(limit)
+            .collect();
+        for k in keys.iter() {
+            store.remove(&concat(&self.storage_prefix, k));
+        }
+        keys.len()
+    }
 }
 
+/// Number of keys held in memory at once by Prefix::clear
+const CLEAR_BATCH_SIZE: usize = 100;
+
 pub fn range_with_prefix<'a>(
     storage: &'a dyn Storage,
     namespace: &[u8],
@@ -138,7 +316,7 @@ pub fn range_with_prefix<'a>(
     let end = calc_end_bound(namespace, end);
 
     // get iterator from storage
-    let base_iterator = storage.range(Some(&start), Some(&end), order);
+    let base_iterator = storage.range(Some(&start), end.as_deref(), order);
 
     // make a copy for the closure to handle lifetimes safely
     let prefix = namespace.to_vec();
@@ -155,15 +333,28 @@ fn calc_start_bound(namespace: &[u8], bound: Option<Bound>) -> Vec<u8> {
     }
 }
 
-fn calc_end_bound(namespace: &[u8], bound: Option<Bound>) -> Vec<u8> {
+/// Returns None if there is no end, that is no key is greater than all the keys in the namespace
+fn calc_end_bound(namespace: &[u8], bound: Option<Bound>) -> Option<Vec<u8>> {
     match bound {
-        None => namespace_upper_bound(namespace),
+        None => prefix_upper_bound(namespace),
         // this is the natural limits of the underlying Storage
-        Some(Bound::Exclusive(limit)) => concat(namespace, &limit),
-        Some(Bound::Inclusive(limit)) => concat(namespace, &one_byte_higher(&limit)),
+        Some(Bound::Exclusive(limit)) => Some(concat(namespace, &limit)),
+        Some(Bound::Inclusive(limit)) => Some(concat(namespace, &one_byte_higher(&limit))),
     }
 }
 
+/// Returns the lowest key greater than all the keys starting with prefix, to use as an
+/// exclusive end. Like namespace_upper_bound, it increments the last byte that is not 255,
+/// but drops the trailing 255 bytes rather than zeroing them, so keys like
+/// `prefix[..n-1] + (last + 1)` (a sibling of the prefix) are not included.
+/// Returns None if the prefix is empty or only holds 255 bytes, as there is no such key.
+pub(crate) fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let last = prefix.iter().rposition(|&b| b != 255)?;
+    let mut end = prefix[..=last].to_vec();
+    end[last] += 1;
+    Some(end)
+}
+
 fn one_byte_higher(limit: &[u8]) -> Vec<u8> {
     let mut v = limit.to_vec();
     v.push(0);
@@ -173,7 +364,7 @@ fn one_byte_higher(limit: &[u8]) -> Vec<u8> {
 /// Returns a new vec of same length and last byte incremented by one
 /// If last bytes are 255, we handle overflow up the chain.
 /// If all bytes are 255, this returns wrong data - but that is never possible as a namespace
-fn namespace_upper_bound(input: &[u8]) -> Vec<u8> {
+pub(crate) fn namespace_upper_bound(input: &[u8]) -> Vec<u8> {
     let mut copy = input.to_vec();
     // zero out all trailing 255, increment first that is not such
     for i in (0..input.len()).rev() {
@@ -200,7 +391,7 @@ mod test {
             storage_prefix: b"foo".to_vec(),
             data: PhantomData::<u64>,
             pk_name: vec![],
-            de_fn: |_, _, kv| deserialize_kv(kv),
+            de_fn: |_, _, kv| deserialize_kv::<Json, _>(kv),
         };
 
         // set some data, we care about "foo" prefix
@@ -329,4 +520,146 @@ mod test {
             .collect();
         assert_eq!(res.unwrap().as_slice(), &[]);
     }
+
+    fn raw_prefix(storage_prefix: &[u8]) -> Prefix<u64> {
+        Prefix {
+            storage_prefix: storage_prefix.to_vec(),
+            data: PhantomData::<u64>,
+            pk_name: vec![],
+            de_fn: |_, _, kv| deserialize_kv::<Json, _>(kv),
+        }
+    }
+
+    #[test]
+    fn range_prefix_ending_in_ff() {
+        let mut store = MockStorage::new();
+        let prefix = raw_prefix(b"f\xff\xff");
+
+        store.set(b"f\xff\xff", b"1");
+        store.set(b"f\xff\xffa", b"2");
+        store.set(b"f\xff\xff\xff", b"3");
+        // siblings, on both sides
+        store.set(b"f\xff\xfe\xff", b"100");
+        store.set(b"g", b"200");
+        store.set(b"g\x00", b"300");
+        store.set(b"g\x00\x00", b"400");
+
+        let expected = vec![
+            (b"".to_vec(), 1u64),
+            (b"a".to_vec(), 2),
+            (b"\xff".to_vec(), 3),
+        ];
+        let res: StdResult<Vec<_>> = prefix.range(&store, None, None, Order::Ascending).collect();
+        assert_eq!(expected, res.unwrap());
+        let res: StdResult<Vec<_>> = prefix
+            .range(&store, None, None, Order::Descending)
+            .collect();
+        let expected_reversed: Vec<_> = expected.iter().rev().cloned().collect();
+        assert_eq!(expected_reversed, res.unwrap());
+
+        // with a start bound only
+        let res: Vec<_> = prefix
+            .keys(
+                &store,
+                Some(Bound::Exclusive(b"a".to_vec())),
+                None,
+                Order::Descending,
+            )
+            .collect();
+        assert_eq!(res, vec![b"\xff".to_vec()]);
+        assert_eq!(3, prefix.count(&store, None));
+    }
+
+    #[test]
+    fn range_prefix_only_ff() {
+        let mut store = MockStorage::new();
+        // there are no keys after this prefix, so the end is unbounded
+        let prefix = raw_prefix(b"\xff\xff");
+
+        store.set(b"\xff\xff", b"1");
+        store.set(b"\xff\xff\xff\xff", b"2");
+        store.set(b"\xff\xfe", b"100");
+        store.set(b"\xff", b"200");
+
+        let res: StdResult<Vec<_>> = prefix.range(&store, None, None, Order::Ascending).collect();
+        assert_eq!(
+            vec![(b"".to_vec(), 1u64), (b"\xff\xff".to_vec(), 2)],
+            res.unwrap()
+        );
+        let res: StdResult<Vec<_>> = prefix
+            .range(&store, None, None, Order::Descending)
+            .collect();
+        assert_eq!(
+            vec![(b"\xff\xff".to_vec(), 2u64), (b"".to_vec(), 1)],
+            res.unwrap()
+        );
+    }
+
+    #[test]
+    fn prefix_upper_bound_works() {
+        assert_eq!(prefix_upper_bound(b"bob"), Some(b"boc".to_vec()));
+        assert_eq!(prefix_upper_bound(b"fo\xfe"), Some(b"fo\xff".to_vec()));
+        assert_eq!(prefix_upper_bound(b"fo\xff"), Some(b"fp".to_vec()));
+        assert_eq!(prefix_upper_bound(b"f\xff\xff"), Some(b"g".to_vec()));
+        assert_eq!(prefix_upper_bound(b"\xffabc"), Some(b"\xffabd".to_vec()));
+        assert_eq!(prefix_upper_bound(b"\xff\xff"), None);
+        assert_eq!(prefix_upper_bound(b""), None);
+    }
+
+    #[test]
+    fn range_paginated_never_repeats_or_skips() {
+        let mut store = MockStorage::new();
+        let prefix = Prefix::<u64>::new(b"foo", &[]);
+        for i in 1..=7u64 {
+            store.set(
+                &concat(&prefix.storage_prefix, &i.to_be_bytes()),
+                i.to_string().as_bytes(),
+            );
+        }
+        // outside of the prefix, on both sides
+        store.set(b"\x00\x03fo", b"100");
+        store.set(b"\x00\x03fop", b"200");
+
+        let paginate = |order: Order| {
+            let mut pages = vec![];
+            let mut cursor: Option<Vec<u8>> = None;
+            loop {
+                let page: Vec<u64> = prefix
+                    .range_paginated(&store, cursor.as_deref(), 3, order)
+                    .map(|r| r.unwrap().1)
+                    .collect();
+                if page.is_empty() {
+                    break;
+                }
+                cursor = Some(page.last().unwrap().to_be_bytes().to_vec());
+                pages.push(page);
+            }
+            pages
+        };
+        assert_eq!(
+            paginate(Order::Ascending),
+            vec![vec![1, 2, 3], vec![4, 5, 6], vec![7]]
+        );
+        assert_eq!(
+            paginate(Order::Descending),
+            vec![vec![7, 6, 5], vec![4, 3, 2], vec![1]]
+        );
+
+        // the cursor is exclusive in both directions, and doesn't need to exist
+        let after = |cursor: u64, order| -> Vec<u64> {
+            prefix
+                .range_paginated(&store, Some(&cursor.to_be_bytes()), 10, order)
+                .map(|r| r.unwrap().1)
+                .collect()
+        };
+        assert_eq!(after(5, Order::Ascending), vec![6, 7]);
+        assert_eq!(after(5, Order::Descending), vec![4, 3, 2, 1]);
+        assert_eq!(after(100, Order::Descending), vec![7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(after(1, Order::Descending), Vec::<u64>::new());
+        assert_eq!(after(0, Order::Ascending).len(), 7);
+
+        // a zero limit gives an empty page
+        let empty = prefix.range_paginated(&store, None, 0, Order::Ascending);
+        assert_eq!(0, empty.count());
+    }
 }
@@ -0,0 +1,188 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{from_slice, to_vec, StdResult};
+
+/// Serde is the strategy used by Item, Map and Path to encode the values they store.
+/// Only the values are affected, the keys and namespaces are always encoded the same way.
+///
+/// Changing the strategy of existing storage is not possible without migrating all its values,
+/// as they are not readable with another encoding.
+pub trait Serde {
+    fn to_vec<T: Serialize + ?Sized>(data: &T) -> StdResult<Vec<u8>>;
+    fn from_slice<T: DeserializeOwned>(value: &[u8]) -> StdResult<T>;
+}
+
+/// Json is the default strategy, using the same encoding as cosmwasm_std::to_vec
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Json;
+
+impl Serde for Json {
+    fn to_vec<T: Serialize + ?Sized>(data: &T) -> StdResult<Vec<u8>> {
+        to_vec(data)
+    }
+
+    fn from_slice<T: DeserializeOwned>(value: &[u8]) -> StdResult<T> {
+        from_slice(value)
+    }
+}
+
+/// Bincode is a compact binary encoding, which is smaller and faster to parse than Json.
+/// Note that it doesn't support self-describing formats, like `#[serde(untagged)]` enums
+/// or `#[serde(flatten)]` fields.
+#[cfg(feature = "bincode")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl Serde for Bincode {
+    fn to_vec<T: Serialize + ?Sized>(data: &T) -> StdResult<Vec<u8>> {
+        bincode::serialize(data).map_err(|e| {
+            cosmwasm_std::StdError::serialize_err(std::any::type_name::<T>(), e.to_string())
+        })
+    }
+
+    fn from_slice<T: DeserializeOwned>(value: &[u8]) -> StdResult<T> {
+        bincode::deserialize(value).map_err(|e| {
+            cosmwasm_std::StdError::parse_err(std::any::type_name::<T>(), e.to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{StdError, Storage};
+    use serde::Deserialize;
+
+    use crate::{Item, Map};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Data {
+        pub name: String,
+        pub age: i32,
+    }
+
+    /// Stores the json reversed, so it is not readable with the default strategy
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct ReversedJson;
+
+    impl Serde for ReversedJson {
+        fn to_vec<T: Serialize + ?Sized>(data: &T) -> StdResult<Vec<u8>> {
+            let mut v = to_vec(data)?;
+            v.reverse();
+            Ok(v)
+        }
+
+        fn from_slice<T: DeserializeOwned>(value: &[u8]) -> StdResult<T> {
+            let mut v = value.to_vec();
+            v.reverse();
+            from_slice(&v)
+        }
+    }
+
+    const DATA: Map<&[u8], Data> = Map::new("data");
+    const REVERSED: Map<&[u8], Data, ReversedJson> = Map::with_serde("data");
+    const CONFIG: Item<Data> = Item::new("config");
+    const REVERSED_CONFIG: Item<Data, ReversedJson> = Item::with_serde("config");
+
+    #[test]
+    fn map_only_encodes_values() {
+        let mut store = MockStorage::new();
+        let data = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+
+        DATA.save(&mut store, b"john", &data).unwrap();
+        let key = DATA.key(b"john").to_vec();
+        let json = store.get(&key).unwrap();
+
+        // same key as with the default strategy, but another value
+        REVERSED.save(&mut store, b"john", &data).unwrap();
+        assert_eq!(key, REVERSED.key(b"john").to_vec());
+        let reversed = store.get(&key).unwrap();
+        assert_ne!(json, reversed);
+        assert_eq!(json, reversed.into_iter().rev().collect::<Vec<_>>());
+
+        assert_eq!(data, REVERSED.load(&store, b"john").unwrap());
+        assert_eq!(Some(data), REVERSED.may_load(&store, b"john").unwrap());
+        match DATA.load(&store, b"john").unwrap_err() {
+            StdError::ParseErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn map_ranges_use_strategy() {
+        use cosmwasm_std::Order;
+
+        let mut store = MockStorage::new();
+        let john = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        let jim = Data {
+            name: "Jim".to_string(),
+            age: 44,
+        };
+        REVERSED.save(&mut store, b"john", &john).unwrap();
+        REVERSED.save(&mut store, b"jim", &jim).unwrap();
+
+        let all: StdResult<Vec<_>> = REVERSED
+            .range(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(
+            all.unwrap(),
+            vec![(b"jim".to_vec(), jim.clone()), (b"john".to_vec(), john)]
+        );
+        // the keys are the same as with the default strategy
+        let keys: Vec<_> = DATA.keys(&store, None, None, Order::Ascending).collect();
+        assert_eq!(keys, vec![b"jim".to_vec(), b"john".to_vec()]);
+
+        let first = REVERSED.first(&store).unwrap();
+        assert_eq!(Some((b"jim".to_vec(), jim)), first);
+    }
+
+    #[test]
+    fn item_only_encodes_values() {
+        let mut store = MockStorage::new();
+        let data = Data {
+            name: "Maria".to_string(),
+            age: 42,
+        };
+
+        REVERSED_CONFIG.save(&mut store, &data).unwrap();
+        assert_eq!(CONFIG.as_slice(), REVERSED_CONFIG.as_slice());
+        let mut raw = store.get(b"config").unwrap();
+        raw.reverse();
+        assert_eq!(to_vec(&data).unwrap(), raw);
+
+        assert_eq!(data, REVERSED_CONFIG.load(&store).unwrap());
+        CONFIG.load(&store).unwrap_err();
+    }
+
+    #[test]
+    #[cfg(feature = "bincode")]
+    fn bincode_round_trip() {
+        let mut store = MockStorage::new();
+        const COMPACT: Map<&[u8], Data, Bincode> = Map::with_serde("data");
+        let data = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+
+        COMPACT.save(&mut store, b"john", &data).unwrap();
+        let raw = store.get(&DATA.key(b"john")).unwrap();
+        assert!(raw.len() < to_vec(&data).unwrap().len());
+        assert_eq!(data, COMPACT.load(&store, b"john").unwrap());
+
+        store.set(&DATA.key(b"john"), b"\x01");
+        match COMPACT.load(&store, b"john").unwrap_err() {
+            StdError::ParseErr { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,786 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+use crate::keys::{EmptyPrefix, PrimaryKey};
+use crate::map::Map;
+use crate::path::Path;
+use crate::prefix::Prefix;
+use crate::snapshot::Snapshot;
+use crate::{Bound, Prefixer, SnapshotItem, Strategy};
+
+/// Map that maintains a snapshots of one or more checkpoints.
+/// We can query historical data as well as current state.
+/// What data is snapshotted depends on the Strategy.
+pub struct SnapshotMap<'a, K, T> {
+    primary: Map<'a, K, T>,
+    snapshots: Snapshot<'a, K, T>,
+    // number of entries, only maintained if created with_total
+    total: Option<SnapshotItem<'a, u64>>,
+}
+
+impl<'a, K, T> SnapshotMap<'a, K, T> {
+    /// Usage: SnapshotMap::new(snapshot_names!("foobar"), Strategy::EveryBlock)
+    pub const fn new(
+        pk: &'a str,
+        checkpoints: &'a str,
+        changelog: &'a str,
+        strategy: Strategy,
+    ) -> Self {
+        SnapshotMap {
+            primary: Map::new(pk),
+            snapshots: Snapshot::new(checkpoints, changelog, strategy),
+            total: None,
+        }
+    }
+
+    /// with_total works like new, but also maintains the number of entries in the map,
+    /// stored at total_key with its changelog in total_changelog. The total shares the
+    /// checkpoints of the map, so it can be read at the same heights with total_at_height.
+    ///
+    /// The total is only correct if the map was created with_total from the start,
+    /// as entries saved before are not counted.
+    ///
+    /// Usage: SnapshotMap::with_total("foo", "foo__check", "foo__change", "foo__total",
+    /// "foo__total_change", Strategy::EveryBlock)
+    pub const fn with_total(
+        pk: &'a str,
+        checkpoints: &'a str,
+        changelog: &'a str,
+        total_key: &'a str,
+        total_changelog: &'a str,
+        strategy: Strategy,
+    ) -> Self {
+        SnapshotMap {
+            primary: Map::new(pk),
+            snapshots: Snapshot::new(checkpoints, changelog, strategy),
+            total: Some(SnapshotItem::new(
+                total_key,
+                checkpoints,
+                total_changelog,
+                strategy,
+            )),
+        }
+    }
+
+    pub fn add_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
+        self.snapshots.add_checkpoint(store, height)
+    }
+
+    pub fn remove_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
+        self.snapshots.remove_checkpoint(store, height)
+    }
+
+    /// list_checkpoints returns the heights with an open checkpoint, from lowest to highest.
+    /// A height that was checkpointed several times is only listed once, and stays open
+    /// until remove_checkpoint was called as many times.
+    pub fn list_checkpoints<'c>(
+        &self,
+        store: &'c dyn Storage,
+    ) -> Box<dyn Iterator<Item = StdResult<u64>> + 'c> {
+        self.snapshots.list_checkpoints(store)
+    }
+}
+
+impl<'a, K, T> SnapshotMap<'a, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a> + Prefixer<'a>,
+{
+    pub fn key(&self, k: K) -> Path<T> {
+        self.primary.key(k)
+    }
+
+    pub fn prefix(&self, p: K::Prefix) -> Prefix<T> {
+        self.primary.prefix(p)
+    }
+
+    pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<T> {
+        self.primary.sub_prefix(p)
+    }
+
+    /// load old value and store changelog
+    fn write_change(&self, store: &mut dyn Storage, k: K, height: u64) -> StdResult<()> {
+        // if there is already data in the changelog for this key and block, do not write more
+        if self.snapshots.has_changelog(store, k.clone(), height) {
+            return Ok(());
+        }
+        // otherwise, store the previous value
+        let old = self.primary.may_load(store, k.clone())?;
+        self.snapshots.write_changelog(store, k, height, old)
+    }
+
+    pub fn save(&self, store: &mut dyn Storage, k: K, data: &T, height: u64) -> StdResult<()> {
+        if self.snapshots.should_checkpoint(store, &k)? {
+            self.write_change(store, k.clone(), height)?;
+        }
+        if let Some(total) = &self.total {
+            if !self.primary.has(store, k.clone()) {
+                total.update(store, height, |n| -> StdResult<_> {
+                    Ok(n.unwrap_or_default() + 1)
+                })?;
+            }
+        }
+        self.primary.save(store, k, data)
+    }
+
+    pub fn remove(&self, store: &mut dyn Storage, k: K, height: u64) -> StdResult<()> {
+        if self.snapshots.should_checkpoint(store, &k)? {
+            self.write_change(store, k.clone(), height)?;
+        }
+        if let Some(total) = &self.total {
+            if self.primary.has(store, k.clone()) {
+                total.update(store, height, |n| -> StdResult<_> {
+                    Ok(n.unwrap_or_default().saturating_sub(1))
+                })?;
+            }
+        }
+        self.primary.remove(store, k);
+        Ok(())
+    }
+
+    /// total returns the current number of entries in the map.
+    /// Returns an error if the map was not created with_total.
+    pub fn total(&self, store: &dyn Storage) -> StdResult<u64> {
+        Ok(self.total_item()?.may_load(store)?.unwrap_or_default())
+    }
+
+    /// total_at_height returns the number of entries in the map at the start of that height,
+    /// with the same checkpoint rules as may_load_at_height (NotFound if not checkpointed).
+    /// Removing and re-adding a key in the same block doesn't change the count.
+    /// Returns an error if the map was not created with_total.
+    pub fn total_at_height(&self, store: &dyn Storage, height: u64) -> StdResult<u64> {
+        Ok(self
+            .total_item()?
+            .may_load_at_height(store, height)?
+            .unwrap_or_default())
+    }
+
+    fn total_item(&self) -> StdResult<&SnapshotItem<'a, u64>> {
+        self.total
+            .as_ref()
+            .ok_or_else(|| StdError::generic_err("SnapshotMap was not created with_total"))
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&self, store: &dyn Storage, k: K) -> StdResult<T> {
+        self.primary.load(store, k)
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, store: &dyn Storage, k: K) -> StdResult<Option<T>> {
+        self.primary.may_load(store, k)
+    }
+
+    /// has returns true or false if any data is at this key, without parsing or interpreting the
+    /// contents.
+    pub fn has(&self, store: &dyn Storage, k: K) -> bool {
+        self.primary.has(store, k)
+    }
+
+    // may_load_at_height reads historical data from given checkpoints.
+    // Only returns `Ok` if we have the data to be able to give the correct answer
+    // (Strategy::EveryBlock or Strategy::Selected and h is registered as checkpoint)
+    // In that case, `Ok(None)` means there was no data for the key at the start of that height.
+    //
+    // If there is no checkpoint for that height, then we return StdError::NotFound,
+    // rather than falling back to the current value (which may have changed since)
+    pub fn may_load_at_height(
+        &self,
+        store: &dyn Storage,
+        k: K,
+        height: u64,
+    ) -> StdResult<Option<T>> {
+        let snapshot = self
+            .snapshots
+            .may_load_at_height(store, k.clone(), height)?;
+
+        if let Some(r) = snapshot {
+            Ok(r)
+        } else {
+            // otherwise, return current value
+            self.may_load(store, k)
+        }
+    }
+
+    // If there is no checkpoint for that height, then we return StdError::NotFound
+    pub fn assert_checkpointed(&self, store: &dyn Storage, height: u64) -> StdResult<()> {
+        self.snapshots.assert_checkpointed(store, height)
+    }
+
+    /// prune deletes changelog entries of the given key that are older than up_to_height.
+    /// The newest entry at or before up_to_height is kept, so that may_load_at_height
+    /// still answers correctly for any height >= up_to_height.
+    /// Returns the number of entries removed.
+    pub fn prune(&self, store: &mut dyn Storage, k: K, up_to_height: u64) -> StdResult<usize> {
+        self.snapshots.prune(store, k, up_to_height)
+    }
+
+    /// prune_all is prune over all keys. To bound the gas usage, it looks at up to limit
+    /// changelog entries if given, starting after start_after. Returns the number of entries
+    /// removed, and the start_after of the next call, or None once the whole changelog is done.
+    /// If created with_total, the changelog of the total is pruned by the call that gets to the
+    /// end of the changelog, and its removed entries are counted as well.
+    pub fn prune_all(
+        &self,
+        store: &mut dyn Storage,
+        up_to_height: u64,
+        start_after: Option<Vec<u8>>,
+        limit: Option<usize>,
+    ) -> StdResult<(usize, Option<Vec<u8>>)> {
+        let (mut removed, next) =
+            self.snapshots
+                .prune_all(store, up_to_height, start_after, limit)?;
+        // the total has at most one entry per height, it is done once per pass
+        if let (Some(total), None) = (&self.total, &next) {
+            removed += total.prune(store, up_to_height)?;
+        }
+        Ok((removed, next))
+    }
+
+    /// Loads the data, perform the specified action, and store the result
+    /// in the database. This is shorthand for some common sequences, which may be useful.
+    ///
+    /// If the data exists, `action(Some(value))` is called. Otherwise `action(None)` is called.
+    ///
+    /// This is a bit more customized than needed to only read "old" value 1 time, not 2 per naive approach
+    pub fn update<A, E>(
+        &self,
+        store: &mut dyn Storage,
+        k: K,
+        height: u64,
+        action: A,
+    ) -> Result<T, E>
+    where
+        A: FnOnce(Option<T>) -> Result<T, E>,
+        E: From<StdError>,
+    {
+        let input = self.may_load(store, k.clone())?;
+        let output = action(input)?;
+        self.save(store, k, &output, height)?;
+        Ok(output)
+    }
+}
+
+// short-cut for simple keys, rather than .prefix(()).range(...)
+impl<'a, K, T> SnapshotMap<'a, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a> + Prefixer<'a>,
+    K::SubPrefix: EmptyPrefix,
+{
+    // I would prefer not to copy code from Prefix, but no other way
+    // with lifetimes (create Prefix inside function and return ref = no no)
+    pub fn range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<cosmwasm_std::Pair<T>>> + 'c>
+    where
+        T: 'c,
+    {
+        self.sub_prefix(K::SubPrefix::new())
+            .range(store, min, max, order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    type TestMap = SnapshotMap<'static, &'static [u8], u64>;
+    const NEVER: TestMap =
+        SnapshotMap::new("never", "never__check", "never__change", Strategy::Never);
+    const EVERY: TestMap = SnapshotMap::new(
+        "every",
+        "every__check",
+        "every__change",
+        Strategy::EveryBlock,
+    );
+    const SELECT: TestMap = SnapshotMap::new(
+        "select",
+        "select__check",
+        "select__change",
+        Strategy::Selected,
+    );
+
+    // Fills a map &[u8] -> u64 with the following writes:
+    // 1: A = 5
+    // 2: B = 7
+    // 3: C = 1, A = 8
+    // 4: B = None, C = 13
+    // 5: A = None, D = 22
+    // Final values -> C = 13, D = 22
+    // Values at beginning of 3 -> A = 5, B = 7
+    // Values at beginning of 5 -> A = 8, C = 13
+    fn init_data(map: &TestMap, storage: &mut dyn Storage) {
+        map.save(storage, b"A", &5, 1).unwrap();
+        map.save(storage, b"B", &7, 2).unwrap();
+
+        // checkpoint 3
+        map.add_checkpoint(storage, 3).unwrap();
+
+        // also use update to set - to ensure this works
+        map.save(storage, b"C", &1, 3).unwrap();
+        map.update(storage, b"A", 3, |_| -> StdResult<u64> { Ok(8) })
+            .unwrap();
+
+        map.remove(storage, b"B", 4).unwrap();
+        map.save(storage, b"C", &13, 4).unwrap();
+
+        // checkpoint 5
+        map.add_checkpoint(storage, 5).unwrap();
+        map.remove(storage, b"A", 5).unwrap();
+        map.update(storage, b"D", 5, |_| -> StdResult<u64> { Ok(22) })
+            .unwrap();
+        // and delete it later (unknown if all data present)
+        map.remove_checkpoint(storage, 5).unwrap();
+    }
+
+    const FINAL_VALUES: &[(&[u8], Option<u64>)] = &[
+        (b"A", None),
+        (b"B", None),
+        (b"C", Some(13)),
+        (b"D", Some(22)),
+    ];
+
+    const VALUES_START_3: &[(&[u8], Option<u64>)] =
+        &[(b"A", Some(5)), (b"B", Some(7)), (b"C", None), (b"D", None)];
+
+    const VALUES_START_5: &[(&[u8], Option<u64>)] = &[
+        (b"A", Some(8)),
+        (b"B", None),
+        (b"C", Some(13)),
+        (b"D", None),
+    ];
+
+    fn assert_final_values(map: &TestMap, storage: &dyn Storage) {
+        for (k, v) in FINAL_VALUES.iter().cloned() {
+            assert_eq!(v, map.may_load(storage, k).unwrap());
+        }
+    }
+
+    fn assert_values_at_height(
+        map: &TestMap,
+        storage: &dyn Storage,
+        height: u64,
+        values: &[(&[u8], Option<u64>)],
+    ) {
+        for (k, v) in values.iter().cloned() {
+            assert_eq!(v, map.may_load_at_height(storage, k, height).unwrap());
+        }
+    }
+
+    fn assert_missing_checkpoint(map: &TestMap, storage: &dyn Storage, height: u64) {
+        for k in &[b"A", b"B", b"C", b"D"] {
+            match map.may_load_at_height(storage, *k, height).unwrap_err() {
+                StdError::NotFound { kind, .. } => assert_eq!("checkpoint", kind),
+                e => panic!("Unexpected error: {:?}", e),
+            }
+        }
+        assert!(map.assert_checkpointed(storage, height).is_err());
+    }
+
+    #[test]
+    fn never_works_like_normal_map() {
+        let mut storage = MockStorage::new();
+        init_data(&NEVER, &mut storage);
+        assert_final_values(&NEVER, &storage);
+
+        // historical queries return error
+        assert_missing_checkpoint(&NEVER, &storage, 3);
+        assert_missing_checkpoint(&NEVER, &storage, 5);
+    }
+
+    #[test]
+    fn every_blocks_stores_present_and_past() {
+        let mut storage = MockStorage::new();
+        init_data(&EVERY, &mut storage);
+        assert_final_values(&EVERY, &storage);
+
+        // historical queries return historical values
+        assert_values_at_height(&EVERY, &storage, 3, VALUES_START_3);
+        assert_values_at_height(&EVERY, &storage, 5, VALUES_START_5);
+    }
+
+    #[test]
+    fn selected_shows_3_not_5() {
+        let mut storage = MockStorage::new();
+        init_data(&SELECT, &mut storage);
+        assert_final_values(&SELECT, &storage);
+
+        // historical queries return historical values
+        assert_values_at_height(&SELECT, &storage, 3, VALUES_START_3);
+        // never checkpointed
+        assert_missing_checkpoint(&SELECT, &storage, 1);
+        // deleted checkpoint
+        assert_missing_checkpoint(&SELECT, &storage, 5);
+    }
+
+    #[test]
+    fn checkpoint_per_proposal() {
+        let mut storage = MockStorage::new();
+        let list = |storage: &MockStorage| -> Vec<u64> {
+            SELECT
+                .list_checkpoints(storage)
+                .collect::<StdResult<_>>()
+                .unwrap()
+        };
+        assert_eq!(list(&storage), Vec::<u64>::new());
+
+        SELECT.save(&mut storage, b"A", &5, 1).unwrap();
+        // two proposals created at height 2, another one at 4
+        SELECT.add_checkpoint(&mut storage, 2).unwrap();
+        SELECT.add_checkpoint(&mut storage, 2).unwrap();
+        SELECT.add_checkpoint(&mut storage, 4).unwrap();
+        assert_eq!(list(&storage), vec![2, 4]);
+
+        SELECT.save(&mut storage, b"A", &6, 3).unwrap();
+        SELECT.save(&mut storage, b"A", &7, 5).unwrap();
+        assert_eq!(
+            Some(5),
+            SELECT.may_load_at_height(&storage, b"A", 2).unwrap()
+        );
+        assert_eq!(
+            Some(6),
+            SELECT.may_load_at_height(&storage, b"A", 4).unwrap()
+        );
+
+        // closing the proposal at 4 removes its checkpoint
+        SELECT.remove_checkpoint(&mut storage, 4).unwrap();
+        assert_eq!(list(&storage), vec![2]);
+        assert_missing_checkpoint(&SELECT, &storage, 4);
+
+        // height 2 stays covered until both proposals are closed
+        SELECT.remove_checkpoint(&mut storage, 2).unwrap();
+        assert_eq!(list(&storage), vec![2]);
+        assert_eq!(
+            Some(5),
+            SELECT.may_load_at_height(&storage, b"A", 2).unwrap()
+        );
+        SELECT.remove_checkpoint(&mut storage, 2).unwrap();
+        assert_eq!(list(&storage), Vec::<u64>::new());
+        assert_missing_checkpoint(&SELECT, &storage, 2);
+
+        // the current value is unchanged
+        assert_eq!(Some(7), SELECT.may_load(&storage, b"A").unwrap());
+    }
+
+    #[test]
+    fn no_data_yet_vs_no_checkpoint() {
+        // the key is written at height 4, and changed at 6
+        fn write(map: &TestMap, storage: &mut dyn Storage) {
+            map.add_checkpoint(storage, 2).unwrap();
+            map.save(storage, b"A", &1, 4).unwrap();
+            map.save(storage, b"A", &2, 6).unwrap();
+        }
+
+        // EveryBlock knows about every height
+        let mut storage = MockStorage::new();
+        write(&EVERY, &mut storage);
+        EVERY.assert_checkpointed(&storage, 3).unwrap();
+        assert_eq!(None, EVERY.may_load_at_height(&storage, b"A", 2).unwrap());
+        assert_eq!(None, EVERY.may_load_at_height(&storage, b"A", 4).unwrap());
+        assert_eq!(
+            Some(1),
+            EVERY.may_load_at_height(&storage, b"A", 5).unwrap()
+        );
+        assert_eq!(
+            Some(2),
+            EVERY.may_load_at_height(&storage, b"A", 7).unwrap()
+        );
+        // a key never written has no data at any height
+        assert_eq!(None, EVERY.may_load_at_height(&storage, b"B", 5).unwrap());
+
+        // Selected only knows about the checkpointed height
+        let mut storage = MockStorage::new();
+        write(&SELECT, &mut storage);
+        SELECT.assert_checkpointed(&storage, 2).unwrap();
+        assert_eq!(None, SELECT.may_load_at_height(&storage, b"A", 2).unwrap());
+        assert_eq!(None, SELECT.may_load_at_height(&storage, b"B", 2).unwrap());
+        // the key existed at 5, but it is not covered, so don't return the current value
+        assert_missing_checkpoint(&SELECT, &storage, 5);
+        assert_missing_checkpoint(&SELECT, &storage, 7);
+
+        // Never doesn't know about any height, not even the ones without data
+        let mut storage = MockStorage::new();
+        write(&NEVER, &mut storage);
+        assert_missing_checkpoint(&NEVER, &storage, 2);
+        assert_missing_checkpoint(&NEVER, &storage, 5);
+        assert_eq!(Some(2), NEVER.may_load(&storage, b"A").unwrap());
+    }
+
+    #[test]
+    fn handle_multiple_writes_in_one_block() {
+        let mut storage = MockStorage::new();
+
+        println!("SETUP");
+        EVERY.save(&mut storage, b"A", &5, 1).unwrap();
+        EVERY.save(&mut storage, b"B", &7, 2).unwrap();
+        EVERY.save(&mut storage, b"C", &2, 2).unwrap();
+
+        // update and save - A query at 3 => 5, at 4 => 12
+        EVERY
+            .update(&mut storage, b"A", 3, |_| -> StdResult<u64> { Ok(9) })
+            .unwrap();
+        EVERY.save(&mut storage, b"A", &12, 3).unwrap();
+        assert_eq!(
+            Some(5),
+            EVERY.may_load_at_height(&storage, b"A", 2).unwrap()
+        );
+        assert_eq!(
+            Some(5),
+            EVERY.may_load_at_height(&storage, b"A", 3).unwrap()
+        );
+        assert_eq!(
+            Some(12),
+            EVERY.may_load_at_height(&storage, b"A", 4).unwrap()
+        );
+
+        // save and remove - B query at 4 => 7, at 5 => None
+        EVERY.save(&mut storage, b"B", &17, 4).unwrap();
+        EVERY.remove(&mut storage, b"B", 4).unwrap();
+        assert_eq!(
+            Some(7),
+            EVERY.may_load_at_height(&storage, b"B", 3).unwrap()
+        );
+        assert_eq!(
+            Some(7),
+            EVERY.may_load_at_height(&storage, b"B", 4).unwrap()
+        );
+        assert_eq!(None, EVERY.may_load_at_height(&storage, b"B", 5).unwrap());
+
+        // remove and update - C query at 5 => 2, at 6 => 16
+        EVERY.remove(&mut storage, b"C", 5).unwrap();
+        EVERY
+            .update(&mut storage, b"C", 5, |_| -> StdResult<u64> { Ok(16) })
+            .unwrap();
+        assert_eq!(
+            Some(2),
+            EVERY.may_load_at_height(&storage, b"C", 4).unwrap()
+        );
+        assert_eq!(
+            Some(2),
+            EVERY.may_load_at_height(&storage, b"C", 5).unwrap()
+        );
+        assert_eq!(
+            Some(16),
+            EVERY.may_load_at_height(&storage, b"C", 6).unwrap()
+        );
+    }
+
+    #[test]
+    fn total_at_height_with_multiple_writes_in_one_block() {
+        let mut storage = MockStorage::new();
+        const COUNTED: TestMap = SnapshotMap::with_total(
+            "counted",
+            "counted__check",
+            "counted__change",
+            "counted__total",
+            "counted__total_change",
+            Strategy::EveryBlock,
+        );
+        assert_eq!(0, COUNTED.total(&storage).unwrap());
+
+        // 1: A, B added, A overwritten
+        COUNTED.save(&mut storage, b"A", &1, 1).unwrap();
+        COUNTED.save(&mut storage, b"B", &2, 1).unwrap();
+        COUNTED.save(&mut storage, b"A", &3, 1).unwrap();
+        assert_eq!(2, COUNTED.total(&storage).unwrap());
+        // 2: A removed and re-added, C added and removed, removing a missing key
+        COUNTED.remove(&mut storage, b"A", 2).unwrap();
+        COUNTED.save(&mut storage, b"A", &4, 2).unwrap();
+        COUNTED.save(&mut storage, b"C", &5, 2).unwrap();
+        COUNTED.remove(&mut storage, b"C", 2).unwrap();
+        COUNTED.remove(&mut storage, b"D", 2).unwrap();
+        assert_eq!(2, COUNTED.total(&storage).unwrap());
+        // 3: B removed, C added with update
+        COUNTED.remove(&mut storage, b"B", 3).unwrap();
+        COUNTED
+            .update(&mut storage, b"C", 3, |_| -> StdResult<u64> { Ok(6) })
+            .unwrap();
+        COUNTED.remove(&mut storage, b"A", 3).unwrap();
+        assert_eq!(1, COUNTED.total(&storage).unwrap());
+
+        assert_eq!(0, COUNTED.total_at_height(&storage, 1).unwrap());
+        assert_eq!(2, COUNTED.total_at_height(&storage, 2).unwrap());
+        assert_eq!(2, COUNTED.total_at_height(&storage, 3).unwrap());
+        assert_eq!(1, COUNTED.total_at_height(&storage, 4).unwrap());
+
+        // not enabled if created with new
+        let err = EVERY.total_at_height(&storage, 1).unwrap_err();
+        assert_eq!(
+            StdError::generic_err("SnapshotMap was not created with_total"),
+            err
+        );
+    }
+
+    #[test]
+    fn prune_all_prunes_the_total() {
+        let mut storage = MockStorage::new();
+        const COUNTED: TestMap = SnapshotMap::with_total(
+            "counted",
+            "counted__check",
+            "counted__change",
+            "counted__total",
+            "counted__total_change",
+            Strategy::EveryBlock,
+        );
+        // one change per key, so only the total has obsolete entries (at 1 and 2)
+        COUNTED.save(&mut storage, b"A", &1, 1).unwrap();
+        COUNTED.save(&mut storage, b"B", &2, 2).unwrap();
+        COUNTED.save(&mut storage, b"C", &3, 3).unwrap();
+        COUNTED.remove(&mut storage, b"A", 4).unwrap();
+
+        // in batches, only the last call prunes the total
+        let mut removed = vec![];
+        let mut start_after = None;
+        loop {
+            let (n, next) = COUNTED
+                .prune_all(&mut storage, 3, start_after, Some(1))
+                .unwrap();
+            removed.push(n);
+            start_after = next;
+            if start_after.is_none() {
+                break;
+            }
+        }
+        assert_eq!(Some(&2), removed.last());
+        assert_eq!(2, removed.iter().sum::<usize>());
+        assert_eq!(
+            (0, None),
+            COUNTED.prune_all(&mut storage, 3, None, None).unwrap()
+        );
+
+        // the retained range still answers properly
+        assert_eq!(2, COUNTED.total_at_height(&storage, 3).unwrap());
+        assert_eq!(3, COUNTED.total_at_height(&storage, 4).unwrap());
+        assert_eq!(2, COUNTED.total_at_height(&storage, 5).unwrap());
+        assert_eq!(2, COUNTED.total(&storage).unwrap());
+    }
+
+    #[test]
+    fn total_at_selected_checkpoints() {
+        let mut storage = MockStorage::new();
+        const COUNTED: TestMap = SnapshotMap::with_total(
+            "counted",
+            "counted__check",
+            "counted__change",
+            "counted__total",
+            "counted__total_change",
+            Strategy::Selected,
+        );
+        COUNTED.save(&mut storage, b"A", &1, 1).unwrap();
+        // the map checkpoints are shared with the total
+        COUNTED.add_checkpoint(&mut storage, 2).unwrap();
+        COUNTED.save(&mut storage, b"B", &2, 2).unwrap();
+        COUNTED.remove(&mut storage, b"A", 2).unwrap();
+        COUNTED.save(&mut storage, b"A", &3, 2).unwrap();
+        COUNTED.save(&mut storage, b"C", &4, 3).unwrap();
+
+        assert_eq!(1, COUNTED.total_at_height(&storage, 2).unwrap());
+        assert_eq!(3, COUNTED.total(&storage).unwrap());
+        // no checkpoint there
+        COUNTED.total_at_height(&storage, 3).unwrap_err();
+    }
+
+    const VALUES_START_4: &[(&[u8], Option<u64>)] = &[
+        (b"A", Some(8)),
+        (b"B", Some(7)),
+        (b"C", Some(1)),
+        (b"D", None),
+    ];
+
+    #[test]
+    fn prune_single_key() {
+        let mut storage = MockStorage::new();
+        init_data(&EVERY, &mut storage);
+
+        // A has changes at 1, 3 and 5. Only the one at 1 is obsolete for heights >= 4
+        assert_eq!(1, EVERY.prune(&mut storage, b"A", 4).unwrap());
+        // nothing left to prune
+        assert_eq!(0, EVERY.prune(&mut storage, b"A", 4).unwrap());
+        // D has just one change, which is newer
+        assert_eq!(0, EVERY.prune(&mut storage, b"D", 4).unwrap());
+
+        // the retained range still answers properly
+        assert_values_at_height(&EVERY, &storage, 4, VALUES_START_4);
+        assert_values_at_height(&EVERY, &storage, 5, VALUES_START_5);
+        assert_final_values(&EVERY, &storage);
+    }
+
+    #[test]
+    fn prune_all_with_limit() {
+        let mut storage = MockStorage::new();
+        init_data(&EVERY, &mut storage);
+
+        // A@1, B@2, and C@3 are obsolete for heights >= 4. There are 8 changelog entries
+        // (A@1, A@3, A@5, B@2, B@4, C@3, C@4, D@5), each call looks at 2 of them
+        let (removed, next) = EVERY.prune_all(&mut storage, 4, None, Some(2)).unwrap();
+        assert_eq!(1, removed);
+        let mut total = removed;
+        let mut calls = 1;
+        let mut start_after = next;
+        while start_after.is_some() {
+            let (removed, next) = EVERY
+                .prune_all(&mut storage, 4, start_after, Some(2))
+                .unwrap();
+            total += removed;
+            calls += 1;
+            start_after = next;
+        }
+        assert_eq!(3, total);
+        assert_eq!(4, calls);
+        // nothing left to prune
+        assert_eq!(
+            (0, None),
+            EVERY.prune_all(&mut storage, 4, None, None).unwrap()
+        );
+
+        // the retained range still answers properly
+        assert_values_at_height(&EVERY, &storage, 4, VALUES_START_4);
+        assert_values_at_height(&EVERY, &storage, 5, VALUES_START_5);
+        assert_final_values(&EVERY, &storage);
+
+        // prune everything up to the latest writes, keeps one entry per key (only A@3 goes)
+        assert_eq!(
+            (1, None),
+            EVERY.prune_all(&mut storage, 10, None, Some(100)).unwrap()
+        );
+        assert_final_values(&EVERY, &storage);
+        assert_values_at_height(&EVERY, &storage, 6, FINAL_VALUES);
+    }
+
+    #[test]
+    fn prune_all_across_batches() {
+        let mut storage = MockStorage::new();
+        init_data(&EVERY, &mut storage);
+
+        // with a limit of 1, the obsolete entry is the last one of every batch, and only the
+        // next entry (read ahead) shows it is obsolete
+        let mut total = 0;
+        let mut start_after = None;
+        loop {
+            let (removed, next) = EVERY
+                .prune_all(&mut storage, 4, start_after, Some(1))
+                .unwrap();
+            total += removed;
+            start_after = next;
+            if start_after.is_none() {
+                break;
+            }
+        }
+        assert_eq!(3, total);
+        assert_values_at_height(&EVERY, &storage, 4, VALUES_START_4);
+        assert_values_at_height(&EVERY, &storage, 5, VALUES_START_5);
+        assert_final_values(&EVERY, &storage);
+
+        let err = EVERY.prune_all(&mut storage, 4, None, Some(0)).unwrap_err();
+        assert_eq!(StdError::generic_err("Cannot prune with a limit of 0"), err);
+    }
+}
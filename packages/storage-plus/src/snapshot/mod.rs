@@ -0,0 +1,267 @@
+#![cfg(feature = "iterator")]
+mod item;
+mod map;
+
+pub use item::SnapshotItem;
+pub use map::SnapshotMap;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Order, StdError, StdResult, Storage};
+
+use crate::de::KeyDeserialize;
+use crate::keys::{PrimaryKey, U64Key};
+use crate::map::Map;
+use crate::{Bound, Prefixer};
+
+/// Structure holding a map of checkpoints composited from
+/// height (as U64Key) and counter of how many times it has
+/// been checkpointed (as u32).
+/// Stores all changes in changelog.
+#[derive(Debug, Clone)]
+pub(crate) struct Snapshot<'a, K, T> {
+    // maps height to number of checkpoints (only used for selected)
+    checkpoints: Map<'a, U64Key, u32>,
+
+    // this stores all changes (key, height). Must differentiate between no data written,
+    // and explicit None (just inserted)
+    pub changelog: Map<'a, (K, U64Key), ChangeSet<T>>,
+
+    // How aggressive we are about checkpointing all data
+    strategy: Strategy,
+}
+
+impl<'a, K, T> Snapshot<'a, K, T> {
+    pub const fn new(checkpoints: &'a str, changelog: &'a str, strategy: Strategy) -> Self {
+        Snapshot {
+            checkpoints: Map::new(checkpoints),
+            changelog: Map::new(changelog),
+            strategy,
+        }
+    }
+
+    pub fn add_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
+        self.checkpoints
+            .update::<_, StdError>(store, height.into(), |count| {
+                Ok(count.unwrap_or_default() + 1)
+            })?;
+        Ok(())
+    }
+
+    pub fn remove_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
+        let count = self
+            .checkpoints
+            .may_load(store, height.into())?
+            .unwrap_or_default();
+        if count <= 1 {
+            self.checkpoints.remove(store, height.into());
+            Ok(())
+        } else {
+            self.checkpoints.save(store, height.into(), &(count - 1))
+        }
+    }
+
+    pub fn list_checkpoints<'c>(
+        &self,
+        store: &'c dyn Storage,
+    ) -> Box<dyn Iterator<Item = StdResult<u64>> + 'c> {
+        let heights = self
+            .checkpoints
+            .keys(store, None, None, Order::Ascending)
+            .map(U64Key::from_vec);
+        Box::new(heights)
+    }
+}
+
+impl<'a, K, T> Snapshot<'a, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a> + Prefixer<'a>,
+{
+    /// should_checkpoint looks at the strategy and determines if we want to checkpoint
+    pub fn should_checkpoint(&self, store: &dyn Storage, k: &K) -> StdResult<bool> {
+        match self.strategy {
+            Strategy::EveryBlock => Ok(true),
+            Strategy::Never => Ok(false),
+            Strategy::Selected => self.should_checkpoint_selected(store, k),
+        }
+    }
+
+    /// this is just pulled out from above for the selected block
+    fn should_checkpoint_selected(&self, store: &dyn Storage, k: &K) -> StdResult<bool> {
+        // most recent checkpoint
+        let checkpoint = self
+            .checkpoints
+            .range(store, None, None, Order::Descending)
+            .next()
+            .transpose()?;
+        if let Some((height, _)) = checkpoint {
+            // any changelog for the given key since then?
+            let start = Bound::inclusive(U64Key::from(height));
+            let first = self
+                .changelog
+                .prefix(k.clone())
+                .range(store, Some(start), None, Order::Ascending)
+                .next()
+                .transpose()?;
+            if first.is_none() {
+                // there must be at least one open checkpoint and no changelog for the given address since then
+                return Ok(true);
+            }
+        }
+        // otherwise, we don't save this
+        Ok(false)
+    }
+
+    // If there is no checkpoint for that height, then we return StdError::NotFound
+    pub fn assert_checkpointed(&self, store: &dyn Storage, height: u64) -> StdResult<()> {
+        let has = match self.strategy {
+            Strategy::EveryBlock => true,
+            Strategy::Never => false,
+            Strategy::Selected => self.checkpoints.may_load(store, height.into())?.is_some(),
+        };
+        match has {
+            true => Ok(()),
+            false => Err(StdError::not_found("checkpoint")),
+        }
+    }
+
+    /// has_changelog returns true if there is already an entry for this key and height.
+    /// Only the first write in a block is recorded, as that holds the value at the start of it.
+    pub fn has_changelog(&self, store: &dyn Storage, k: K, height: u64) -> bool {
+        self.changelog.has(store, (k, U64Key::from(height)))
+    }
+
+    /// write_changelog stores the value prior to the first change at the given height
+    pub fn write_changelog(
+        &self,
+        store: &mut dyn Storage,
+        k: K,
+        height: u64,
+        old: Option<T>,
+    ) -> StdResult<()> {
+        self.changelog
+            .save(store, (k, U64Key::from(height)), &ChangeSet { old })
+    }
+
+    // may_load_at_height reads historical data from given checkpoints.
+    // Returns StdError::NotFound if we don't have checkpoint, and can give no answer.
+    // Returns Ok(None) if there is a checkpoint, but no cached data (no changes since the
+    // checkpoint. Caller should query current state).
+    // Return Ok(Some(x)) if there is a checkpoint and data written to changelog, returning the state at that time
+    pub fn may_load_at_height(
+        &self,
+        store: &dyn Storage,
+        k: K,
+        height: u64,
+    ) -> StdResult<Option<Option<T>>> {
+        self.assert_checkpointed(store, height)?;
+
+        // this will look for the first snapshot of the given address >= given height
+        // If None, there is no snapshot since that time.
+        let start = Bound::inclusive(U64Key::new(height));
+        let first = self
+            .changelog
+            .prefix(k)
+            .range(store, Some(start), None, Order::Ascending)
+            .next();
+
+        if let Some(r) = first {
+            // if we found a match, return this last one
+            r.map(|(_, v)| Some(v.old))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// prune removes all changelog entries for the given key older than up_to_height,
+    /// except the newest one at or before that height. This keeps may_load_at_height
+    /// answering correctly for all heights >= up_to_height.
+    /// Returns the number of entries removed.
+    pub fn prune(&self, store: &mut dyn Storage, k: K, up_to_height: u64) -> StdResult<usize> {
+        let end = Bound::inclusive(U64Key::new(up_to_height));
+        let heights = self
+            .changelog
+            .prefix(k.clone())
+            .keys(store, None, Some(end), Order::Descending)
+            // keep the newest entry at or before the height
+            .skip(1)
+            .map(U64Key::from_vec)
+            .collect::<StdResult<Vec<_>>>()?;
+        for height in heights.iter() {
+            self.changelog
+                .remove(store, (k.clone(), U64Key::new(*height)));
+        }
+        Ok(heights.len())
+    }
+
+    /// prune_all works like prune for every key in the changelog. It goes through the changelog
+    /// entries after start_after, looking at up to limit of them if given (kept or removed).
+    /// Returns the number of entries removed, and the start_after to pass to continue where it
+    /// stopped, or None once the end of the changelog is reached.
+    pub fn prune_all(
+        &self,
+        store: &mut dyn Storage,
+        up_to_height: u64,
+        start_after: Option<Vec<u8>>,
+        limit: Option<usize>,
+    ) -> StdResult<(usize, Option<Vec<u8>>)> {
+        if limit == Some(0) {
+            return Err(StdError::generic_err("Cannot prune with a limit of 0"));
+        }
+        // raw changelog keys are the (length-prefixed) key, followed by the 8 byte height
+        let mut to_remove: Vec<Vec<u8>> = vec![];
+        let mut pending: Option<Vec<u8>> = None;
+        let mut last: Option<Vec<u8>> = None;
+        let mut next: Option<Vec<u8>> = None;
+        let min = start_after.map(Bound::exclusive);
+        let entries = self.changelog.keys(store, min, None, Order::Ascending);
+        for (scanned, raw) in entries.enumerate() {
+            if raw.len() < 8 {
+                return Err(StdError::invalid_data_size(8, raw.len()));
+            }
+            let (key, height) = raw.split_at(raw.len() - 8);
+            let height = U64Key::from_slice(height)?;
+            // a newer entry at or before the height for the same key obsoletes the pending one
+            if let Some(prev) = pending.take() {
+                if height <= up_to_height && prev[..prev.len() - 8] == *key {
+                    to_remove.push(prev);
+                }
+            }
+            // the entry after the last one is only read to know if that one is obsolete,
+            // the next call starts with it
+            if limit == Some(scanned) {
+                next = last.take();
+                break;
+            }
+            if height <= up_to_height {
+                pending = Some(raw.clone());
+            }
+            last = Some(raw);
+        }
+
+        let prefix = self.changelog.sub_prefix(()).to_vec();
+        for raw in to_remove.iter() {
+            store.remove(&[prefix.as_slice(), raw.as_slice()].concat());
+        }
+        Ok((to_remove.len(), next))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Strategy {
+    EveryBlock,
+    Never,
+    /// Only writes for linked blocks - does a few more reads to save some writes.
+    /// Probably uses more gas, but less total disk usage.
+    ///
+    /// Note that you need a trusted source (eg. own contract) to set/remove checkpoints.
+    /// Useful when the checkpoint setting happens in the same contract as the snapshotting.
+    Selected,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub(crate) struct ChangeSet<T> {
+    pub old: Option<T>,
+}
@@ -0,0 +1,215 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{Order, StdResult, Storage};
+
+use crate::endian::Endian;
+use crate::keys::PrimaryKey;
+use crate::map::Map;
+use crate::path::Path;
+use crate::prefix::{prefix_upper_bound, Bound, Prefix};
+
+// the value stored for every entry of the ordering, so it is not empty
+const MARKER: &[u8] = b"\x01";
+
+/// SortedMap stores values under their primary key like a Map, and also keeps the entries
+/// ordered by value, so eg. the top N stakers can be listed without scanning all of them.
+///
+/// The ordering is stored under `order_namespace`, keyed by the big endian value followed by
+/// the primary key, so entries with the same value are ordered by primary key.
+/// It is updated on every save and remove, so always use these rather than writing to a Map
+/// with the same namespace directly.
+pub struct SortedMap<'a, K, V> {
+    map: Map<'a, K, V>,
+    order_namespace: &'a [u8],
+}
+
+impl<'a, K, V> SortedMap<'a, K, V> {
+    pub const fn new(namespace: &'a str, order_namespace: &'a str) -> Self {
+        SortedMap {
+            map: Map::new(namespace),
+            order_namespace: order_namespace.as_bytes(),
+        }
+    }
+}
+
+impl<'a, K, V> SortedMap<'a, K, V>
+where
+    K: PrimaryKey<'a>,
+    V: Endian + Serialize + DeserializeOwned,
+{
+    pub fn save(&self, store: &mut dyn Storage, k: K, value: &V) -> StdResult<()> {
+        if let Some(old) = self.map.may_load(store, k.clone())? {
+            store.remove(&self.order_path(&k, old));
+        }
+        self.map.save(store, k.clone(), value)?;
+        store.set(&self.order_path(&k, *value), MARKER);
+        Ok(())
+    }
+
+    pub fn remove(&self, store: &mut dyn Storage, k: K) -> StdResult<()> {
+        if let Some(old) = self.map.may_load(store, k.clone())? {
+            store.remove(&self.order_path(&k, old));
+            self.map.remove(store, k);
+        }
+        Ok(())
+    }
+
+    pub fn load(&self, store: &dyn Storage, k: K) -> StdResult<V> {
+        self.map.load(store, k)
+    }
+
+    pub fn may_load(&self, store: &dyn Storage, k: K) -> StdResult<Option<V>> {
+        self.map.may_load(store, k)
+    }
+
+    /// top returns (at most) the n entries with the highest values, highest first, as
+    /// (raw primary key, value) pairs. Entries with the same value are listed by descending
+    /// primary key.
+    pub fn top(&self, store: &dyn Storage, n: usize) -> Vec<(Vec<u8>, V)> {
+        self.rank_range(store, None, None, Order::Descending)
+            .take(n)
+            .collect()
+    }
+
+    /// rank_range returns all entries with min <= value <= max in the given order, as
+    /// (raw primary key, value) pairs. None means there is no bound on that side.
+    pub fn rank_range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<V>,
+        max: Option<V>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = (Vec<u8>, V)> + 'c>
+    where
+        V: 'c,
+    {
+        let min = min.map(|v| Bound::Inclusive(v.to_key_bytes().into()));
+        // every key starting with max sorts before the next prefix
+        let max =
+            max.and_then(|v| prefix_upper_bound(v.to_key_bytes().as_ref()).map(Bound::Exclusive));
+        let entries = Prefix::<()>::new(self.order_namespace, &[])
+            .keys(store, min, max, order)
+            .map(|key| {
+                let mut buf = V::Buf::default();
+                let len = buf.as_ref().len();
+                buf.as_mut().copy_from_slice(&key[..len]);
+                (key[len..].to_vec(), V::from_key_bytes(buf))
+            });
+        Box::new(entries)
+    }
+
+    fn order_path(&self, k: &K, value: V) -> Vec<u8> {
+        let mut key: Vec<u8> = value.to_key_bytes().into();
+        key.extend(k.joined_key());
+        Path::<()>::new(self.order_namespace, &[&key]).to_vec()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::Addr;
+
+    const STAKES: SortedMap<&Addr, u64> = SortedMap::new("stakes", "stakes__order");
+
+    fn names(entries: Vec<(Vec<u8>, u64)>) -> Vec<(String, u64)> {
+        entries
+            .into_iter()
+            .map(|(k, v)| (String::from_utf8(k).unwrap(), v))
+            .collect()
+    }
+
+    fn entry(name: &str, value: u64) -> (String, u64) {
+        (name.to_string(), value)
+    }
+
+    #[test]
+    fn top_with_ties() {
+        let mut store = MockStorage::new();
+        for (name, stake) in &[("alice", 50), ("bob", 100), ("carl", 50), ("dave", 7)] {
+            STAKES
+                .save(&mut store, &Addr::unchecked(*name), stake)
+                .unwrap();
+        }
+        assert_eq!(STAKES.load(&store, &Addr::unchecked("carl")).unwrap(), 50);
+
+        // same value, ordered by key
+        assert_eq!(
+            names(STAKES.top(&store, 3)),
+            vec![entry("bob", 100), entry("carl", 50), entry("alice", 50)]
+        );
+        assert_eq!(names(STAKES.top(&store, 10)).len(), 4);
+        assert_eq!(names(STAKES.top(&store, 0)), vec![]);
+    }
+
+    #[test]
+    fn updates_move_entries() {
+        let mut store = MockStorage::new();
+        let (alice, bob, carl) = (
+            Addr::unchecked("alice"),
+            Addr::unchecked("bob"),
+            Addr::unchecked("carl"),
+        );
+        STAKES.save(&mut store, &alice, &10).unwrap();
+        STAKES.save(&mut store, &bob, &20).unwrap();
+        STAKES.save(&mut store, &carl, &30).unwrap();
+
+        // alice moves from last to first, without leaving the old position behind
+        STAKES.save(&mut store, &alice, &40).unwrap();
+        assert_eq!(
+            names(STAKES.top(&store, 10)),
+            vec![entry("alice", 40), entry("carl", 30), entry("bob", 20)]
+        );
+
+        // carl drops to a tie with bob
+        STAKES.save(&mut store, &carl, &20).unwrap();
+        assert_eq!(
+            names(STAKES.top(&store, 10)),
+            vec![entry("alice", 40), entry("carl", 20), entry("bob", 20)]
+        );
+
+        // removed entries are gone from the ordering, removing twice is fine
+        STAKES.remove(&mut store, &alice).unwrap();
+        STAKES.remove(&mut store, &alice).unwrap();
+        assert_eq!(STAKES.may_load(&store, &alice).unwrap(), None);
+        assert_eq!(
+            names(STAKES.top(&store, 10)),
+            vec![entry("carl", 20), entry("bob", 20)]
+        );
+    }
+
+    #[test]
+    fn rank_range_bounds() {
+        let mut store = MockStorage::new();
+        for (name, stake) in &[("a", 0), ("b", 5), ("c", 10), ("d", 10), ("e", u64::MAX)] {
+            STAKES
+                .save(&mut store, &Addr::unchecked(*name), stake)
+                .unwrap();
+        }
+        let range = |min, max, order| names(STAKES.rank_range(&store, min, max, order).collect());
+
+        // both bounds are inclusive
+        assert_eq!(
+            range(Some(5), Some(10), Order::Ascending),
+            vec![entry("b", 5), entry("c", 10), entry("d", 10)]
+        );
+        assert_eq!(
+            range(Some(5), Some(10), Order::Descending),
+            vec![entry("d", 10), entry("c", 10), entry("b", 5)]
+        );
+        assert_eq!(range(None, Some(0), Order::Ascending), vec![entry("a", 0)]);
+        assert_eq!(
+            range(Some(11), None, Order::Ascending),
+            vec![entry("e", u64::MAX)]
+        );
+        // the max value has no next prefix, but is still included
+        assert_eq!(
+            range(Some(u64::MAX), Some(u64::MAX), Order::Descending),
+            vec![entry("e", u64::MAX)]
+        );
+        assert_eq!(range(Some(6), Some(9), Order::Ascending), vec![]);
+        assert_eq!(range(None, None, Order::Ascending).len(), 5);
+    }
+}
@@ -0,0 +1,350 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+
+#[cfg(feature = "iterator")]
+use crate::de::KeyDeserialize;
+use crate::keys::PrimaryKey;
+#[cfg(feature = "iterator")]
+use crate::keys::{EmptyPrefix, Prefixer};
+use crate::path::Path;
+#[cfg(feature = "iterator")]
+use crate::prefix::{Bound, Prefix};
+use crate::serialization::{Json, Serde};
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+/// SuffixedMap is a Map scoped by a runtime suffix, eg. one order book per pool id,
+/// created with `Map::add_suffix`. The suffix is stored as the first length-prefixed
+/// segment after the namespace, so the keys are the same as for
+/// `to_length_prefixed_nested(&[namespace, suffix])` followed by the (joined) key.
+/// Two SuffixedMaps with different suffixes never see each other's entries, but range and
+/// keys of the Map it was created from return the entries of all the suffixes.
+#[derive(Debug, Clone)]
+pub struct SuffixedMap<'a, K, T, S = Json> {
+    namespace: &'a [u8],
+    suffix: Vec<u8>,
+    // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
+    key_type: PhantomData<K>,
+    data_type: PhantomData<T>,
+    serde: PhantomData<S>,
+}
+
+impl<'a, K, T, S> SuffixedMap<'a, K, T, S> {
+    pub(crate) fn new(namespace: &'a [u8], suffix: &[u8]) -> Self {
+        SuffixedMap {
+            namespace,
+            suffix: suffix.to_vec(),
+            key_type: PhantomData,
+            data_type: PhantomData,
+            serde: PhantomData,
+        }
+    }
+
+    /// suffix returns the runtime suffix this map is scoped by
+    pub fn suffix(&self) -> &[u8] {
+        &self.suffix
+    }
+}
+
+impl<'a, K, T, S> SuffixedMap<'a, K, T, S>
+where
+    T: Serialize + DeserializeOwned,
+    K: PrimaryKey<'a>,
+    S: Serde,
+{
+    fn segments<'b>(&'b self, k: &'b K) -> Vec<&'b [u8]> {
+        let mut segments = vec![self.suffix.as_slice()];
+        segments.extend(k.key());
+        segments
+    }
+
+    pub fn key(&self, k: K) -> Path<T, S> {
+        Path::new(self.namespace, &self.segments(&k))
+    }
+
+    /// key_checked works like key, but returns an error rather than panicking if the suffix
+    /// or a part of the key (other than the last one) is too long to be length-prefixed
+    pub fn key_checked(&self, k: K) -> StdResult<Path<T, S>> {
+        Path::new_checked(self.namespace, &self.segments(&k))
+    }
+
+    #[cfg(feature = "iterator")]
+    pub fn prefix(&self, p: K::Prefix) -> Prefix<T> {
+        let mut sub_names = vec![self.suffix.as_slice()];
+        sub_names.extend(p.prefix());
+        Prefix::with_serde::<S>(self.namespace, &sub_names)
+    }
+
+    #[cfg(feature = "iterator")]
+    pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<T> {
+        let mut sub_names = vec![self.suffix.as_slice()];
+        sub_names.extend(p.prefix());
+        Prefix::with_serde::<S>(self.namespace, &sub_names)
+    }
+
+    /// is_empty returns true if there are no entries under this suffix
+    #[cfg(feature = "iterator")]
+    pub fn is_empty(&self, store: &dyn Storage) -> bool {
+        Prefix::<T>::new(self.namespace, &[&self.suffix]).is_empty(store)
+    }
+
+    /// clear removes all the entries under this suffix, or at most limit entries if given.
+    /// Returns the number of entries removed. If this is equal to limit, there may be more left.
+    #[cfg(feature = "iterator")]
+    pub fn clear(&self, store: &mut dyn Storage, limit: Option<usize>) -> usize {
+        Prefix::<T>::new(self.namespace, &[&self.suffix]).clear(store, limit)
+    }
+
+    pub fn save(&self, store: &mut dyn Storage, k: K, data: &T) -> StdResult<()> {
+        self.key_checked(k)?.save(store, data)
+    }
+
+    /// Keys that are too long to be saved cannot hold any data, so nothing is removed for them
+    pub fn remove(&self, store: &mut dyn Storage, k: K) {
+        if let Ok(path) = self.key_checked(k) {
+            path.remove(store)
+        }
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&self, store: &dyn Storage, k: K) -> StdResult<T> {
+        self.key_checked(k)?.load(store)
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, store: &dyn Storage, k: K) -> StdResult<Option<T>> {
+        self.key_checked(k)?.may_load(store)
+    }
+
+    /// has returns true or false if any data is at this key, without parsing or interpreting the
+    /// contents.
+    pub fn has(&self, store: &dyn Storage, k: K) -> bool {
+        match self.key_checked(k) {
+            Ok(path) => path.has(store),
+            Err(_) => false,
+        }
+    }
+
+    /// Loads the data, perform the specified action, and store the result
+    /// in the database. This is shorthand for some common sequences, which may be useful.
+    ///
+    /// If the data exists, `action(Some(value))` is called. Otherwise `action(None)` is called.
+    pub fn update<A, E>(&self, store: &mut dyn Storage, k: K, action: A) -> Result<T, E>
+    where
+        A: FnOnce(Option<T>) -> Result<T, E>,
+        E: From<StdError>,
+    {
+        self.key_checked(k)?.update(store, action)
+    }
+}
+
+// short-cut for simple keys, rather than .prefix(()).range(...)
+#[cfg(feature = "iterator")]
+impl<'a, K, T, S> SuffixedMap<'a, K, T, S>
+where
+    T: Serialize + DeserializeOwned,
+    K: PrimaryKey<'a>,
+    K::SubPrefix: EmptyPrefix,
+    S: Serde,
+{
+    pub fn range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<cosmwasm_std::Pair<T>>> + 'c>
+    where
+        T: 'c,
+    {
+        self.sub_prefix(K::SubPrefix::new())
+            .range(store, min, max, order)
+    }
+
+    /// range_de works like range, but deserializes the keys into their owned typed form
+    pub fn range_de<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K: KeyDeserialize,
+        K::Output: 'c,
+    {
+        let mapped = self.range(store, min, max, order).map(|res| {
+            let (k, v) = res?;
+            Ok((K::from_vec(k)?, v))
+        });
+        Box::new(mapped)
+    }
+
+    /// keys works like range, but only returns the raw keys, without deserializing the values
+    pub fn keys<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'c>
+    where
+        T: 'c,
+    {
+        self.sub_prefix(K::SubPrefix::new())
+            .keys(store, min, max, order)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    #[cfg(feature = "iterator")]
+    use cosmwasm_std::Order;
+
+    use crate::helpers::nested_namespaces_with_key;
+    use crate::{Map, U64Key};
+
+    // price -> amount, one book per pool
+    const ORDERS: Map<U64Key, u64> = Map::new("orders");
+
+    #[test]
+    fn suffixes_are_isolated() {
+        let mut store = MockStorage::new();
+        let pool1 = ORDERS.add_suffix(b"pool1");
+        let pool2 = ORDERS.add_suffix(b"pool2");
+        assert_eq!(b"pool1", pool1.suffix());
+
+        pool1.save(&mut store, 100.into(), &5).unwrap();
+        pool2.save(&mut store, 100.into(), &7).unwrap();
+        pool2.save(&mut store, 200.into(), &9).unwrap();
+
+        assert_eq!(5, pool1.load(&store, 100.into()).unwrap());
+        assert_eq!(7, pool2.load(&store, 100.into()).unwrap());
+        assert_eq!(None, pool1.may_load(&store, 200.into()).unwrap());
+        assert!(pool2.has(&store, 200.into()));
+        // the unscoped map doesn't see any of them
+        assert_eq!(None, ORDERS.may_load(&store, 100.into()).unwrap());
+
+        pool1
+            .update(&mut store, 100.into(), |old| -> StdResult<_> {
+                Ok(old.unwrap_or_default() + 1)
+            })
+            .unwrap();
+        assert_eq!(6, pool1.load(&store, 100.into()).unwrap());
+        assert_eq!(7, pool2.load(&store, 100.into()).unwrap());
+
+        pool2.remove(&mut store, 100.into());
+        assert!(!pool2.has(&store, 100.into()));
+        assert!(pool1.has(&store, 100.into()));
+
+        // the key is the nested namespace followed by the key
+        let expected = nested_namespaces_with_key(&[b"orders"], &[b"pool1"], &100u64.to_be_bytes());
+        assert_eq!(expected, pool1.key(100.into()).to_vec());
+    }
+
+    #[test]
+    fn oversized_suffix_returns_errors() {
+        let mut store = MockStorage::new();
+        let too_long = ORDERS.add_suffix(&[b'a'; 0x10000]);
+
+        too_long.key_checked(100.into()).unwrap_err();
+        too_long.save(&mut store, 100.into(), &5).unwrap_err();
+        too_long.load(&store, 100.into()).unwrap_err();
+        // such keys cannot hold data, so has and remove do not panic
+        assert!(!too_long.has(&store, 100.into()));
+        too_long.remove(&mut store, 100.into());
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn parent_range_returns_suffixed_entries() {
+        let mut store = MockStorage::new();
+        let pool1 = ORDERS.add_suffix(b"pool1");
+        pool1.save(&mut store, 100.into(), &5).unwrap();
+        ORDERS.save(&mut store, 200.into(), &7).unwrap();
+
+        // load on the parent map doesn't find the suffixed entry
+        assert_eq!(None, ORDERS.may_load(&store, 100.into()).unwrap());
+        assert_eq!(1, pool1.keys(&store, None, None, Order::Ascending).count());
+
+        // but range and keys on it return it, with the length-prefixed suffix in front of the key
+        let suffixed = [b"\x00\x05pool1".as_ref(), &100u64.to_be_bytes()].concat();
+        let all: Vec<_> = ORDERS
+            .range(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(
+            all,
+            vec![(200u64.to_be_bytes().to_vec(), 7), (suffixed.clone(), 5)]
+        );
+        let keys: Vec<_> = ORDERS.keys(&store, None, None, Order::Ascending).collect();
+        assert_eq!(keys, vec![200u64.to_be_bytes().to_vec(), suffixed]);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_within_one_suffix() {
+        let mut store = MockStorage::new();
+        let pool1 = ORDERS.add_suffix(b"pool");
+        // a suffix that is a prefix of the other must not leak into its range
+        let pool11 = ORDERS.add_suffix(b"pool1");
+
+        pool1.save(&mut store, 300.into(), &3).unwrap();
+        pool1.save(&mut store, 100.into(), &1).unwrap();
+        pool11.save(&mut store, 200.into(), &2).unwrap();
+
+        let all: Vec<_> = pool1
+            .range_de(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(all, vec![(100, 1), (300, 3)]);
+
+        let desc: Vec<_> = pool1
+            .keys(
+                &store,
+                None,
+                Some(Bound::exclusive_int(300u64)),
+                Order::Descending,
+            )
+            .collect();
+        assert_eq!(desc, vec![100u64.to_be_bytes().to_vec()]);
+
+        let other: Vec<_> = pool11
+            .range(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(other, vec![(200u64.to_be_bytes().to_vec(), 2)]);
+
+        assert_eq!(2, pool1.clear(&mut store, None));
+        assert!(pool1.is_empty(&store));
+        assert!(!pool11.is_empty(&store));
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn composite_keys_with_suffix() {
+        let mut store = MockStorage::new();
+        const ALLOWANCES: Map<(&[u8], &[u8]), u64> = Map::new("allow");
+        let pool = ALLOWANCES.add_suffix(b"pool");
+        pool.save(&mut store, (b"owner", b"spender"), &10).unwrap();
+        pool.save(&mut store, (b"owner", b"admin"), &20).unwrap();
+        ALLOWANCES
+            .add_suffix(b"other")
+            .save(&mut store, (b"owner", b"other"), &30)
+            .unwrap();
+
+        let spenders: Vec<_> = pool
+            .prefix(b"owner")
+            .range(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(
+            spenders,
+            vec![(b"admin".to_vec(), 20), (b"spender".to_vec(), 10)]
+        );
+    }
+}